@@ -1,9 +1,10 @@
-// Pure IPC-2581 parser modules
+// Pure IPC-2581 parser and writer modules
 mod checksum;
 mod intern;
 mod parse;
 pub mod types;
 pub mod units;
+mod write;
 
 pub use intern::{Interner, Symbol};
 pub use types::*;
@@ -13,6 +14,10 @@ use parse::Parser;
 use roxmltree::Document;
 use std::path::Path;
 use thiserror::Error;
+use write::Writer;
+
+/// XML namespace of IPC-2581 documents
+const NAMESPACE: &str = "http://webstds.ipc.org/2581";
 
 #[derive(Debug, Error)]
 pub enum Ipc2581Error {
@@ -70,7 +75,7 @@ impl Ipc2581 {
 
         // Validate namespace
         let root = doc.root_element();
-        if root.tag_name().namespace() != Some("http://webstds.ipc.org/2581") {
+        if root.tag_name().namespace() != Some(NAMESPACE) {
             return Err(Ipc2581Error::InvalidStructure(format!(
                 "Expected IPC-2581 namespace, got {:?}",
                 root.tag_name().namespace()
@@ -99,6 +104,20 @@ impl Ipc2581 {
         Self::parse(&xml)
     }
 
+    /// Serialize the document to IPC-2581 XML
+    ///
+    /// Dimensions are written in the units of their section, so the result parses back to the
+    /// same model. Elements the parser does not model are not written.
+    pub fn to_xml(&self) -> String {
+        Writer::new(&self.interner).write_document(self)
+    }
+
+    /// Serialize the document to an IPC-2581 XML file
+    pub fn write_file(&self, path: impl AsRef<Path>) -> Result<()> {
+        std::fs::write(path, self.to_xml())?;
+        Ok(())
+    }
+
     /// Get the revision string (e.g., "C")
     pub fn revision(&self) -> &str {
         self.interner.resolve(self.revision)
@@ -161,6 +180,41 @@ impl Ipc2581 {
     pub fn interner(&self) -> &Interner {
         &self.interner
     }
+
+    /// Intern a string for use in the document model
+    pub fn intern(&mut self, s: &str) -> Symbol {
+        self.interner.intern(s)
+    }
+
+    /// Get the content section for editing
+    pub fn content_mut(&mut self) -> &mut Content {
+        &mut self.content
+    }
+
+    /// Get the logistic header for editing; set it to `None` to remove the section
+    pub fn logistic_header_mut(&mut self) -> &mut Option<LogisticHeader> {
+        &mut self.logistic_header
+    }
+
+    /// Get the history record for editing; set it to `None` to remove the section
+    pub fn history_record_mut(&mut self) -> &mut Option<HistoryRecord> {
+        &mut self.history_record
+    }
+
+    /// Get the Ecad section for editing; set it to `None` to remove the section
+    pub fn ecad_mut(&mut self) -> &mut Option<Ecad> {
+        &mut self.ecad
+    }
+
+    /// Get the BOM section for editing; set it to `None` to remove the section
+    pub fn bom_mut(&mut self) -> &mut Option<Bom> {
+        &mut self.bom
+    }
+
+    /// Get the AVL section for editing; set it to `None` to remove the section
+    pub fn avl_mut(&mut self) -> &mut Option<Avl> {
+        &mut self.avl
+    }
 }
 
 #[cfg(test)]
@@ -242,6 +296,52 @@ mod tests {
         assert_eq!(doc.resolve(vmpn.mpns[0].name), "TEST123");
     }
 
+    #[test]
+    fn write_edited_document() {
+        let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
+<IPC-2581 revision="C" xmlns="http://webstds.ipc.org/2581">
+  <Content roleRef="Owner">
+    <FunctionMode mode="ASSEMBLY"/>
+    <DictionaryStandard units="INCH">
+      <EntryStandard id="PAD_1">
+        <RectRound width="0.05" height="0.025" radius="0.005" upperRight="true" upperLeft="true" lowerRight="true" lowerLeft="true"/>
+      </EntryStandard>
+    </DictionaryStandard>
+  </Content>
+  <LogisticHeader>
+    <Enterprise id="Acme &amp; Co" code="NONE" name="Acme"/>
+  </LogisticHeader>
+</IPC-2581>"#;
+
+        let mut doc = Ipc2581::parse(xml).unwrap();
+        let name = doc.intern("Test_AVL");
+        let part = doc.intern("PART_001");
+        *doc.avl_mut() = Some(Avl {
+            name,
+            header: None,
+            items: vec![AvlItem {
+                oem_design_number: part,
+                vmpn_list: vec![],
+                spec_refs: vec![],
+            }],
+        });
+
+        let written = doc.to_xml();
+        assert!(written.contains(r#"<RectRound width="0.05" height="0.025" radius="0.005""#));
+        assert!(written.contains(r#"<Enterprise id="Acme &amp; Co" code="NONE" name="Acme"/>"#));
+
+        let doc = Ipc2581::parse(&written).unwrap();
+        let avl = doc.avl().unwrap();
+        assert_eq!(doc.resolve(avl.name), "Test_AVL");
+        assert_eq!(doc.resolve(avl.items[0].oem_design_number), "PART_001");
+        let StandardPrimitive::RectRound(pad) =
+            &doc.content().dictionary_standard.entries[0].primitive
+        else {
+            panic!("Expected a RectRound");
+        };
+        assert!((pad.shape.size.width - 1.27).abs() < 1e-9);
+    }
+
     #[test]
     fn parse_bom_with_description() {
         let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
@@ -0,0 +1,1365 @@
+use crate::types::*;
+use crate::units::from_mm;
+use crate::{Interner, Ipc2581, Symbol, NAMESPACE};
+use quick_xml::escape::escape;
+
+type Attrs = Vec<(&'static str, String)>;
+
+/// Writer context: the inverse of `Parser`, emitting the element and attribute names it reads.
+///
+/// Dimensions are stored in mm and written back in the units of their section (the CadHeader
+/// units for Ecad, each dictionary's own units). Only what the model holds is written, so
+/// elements the parser skips are not carried over.
+pub struct Writer<'a> {
+    interner: &'a Interner,
+    xml: String,
+    depth: usize,
+}
+
+impl<'a> Writer<'a> {
+    pub fn new(interner: &'a Interner) -> Self {
+        Self {
+            interner,
+            xml: String::new(),
+            depth: 0,
+        }
+    }
+
+    pub fn write_document(mut self, doc: &Ipc2581) -> String {
+        self.xml
+            .push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+        self.start(
+            "IPC-2581",
+            vec![
+                ("revision", self.sym(doc.revision)),
+                ("xmlns", NAMESPACE.to_string()),
+            ],
+        );
+
+        self.write_content(&doc.content);
+        if let Some(logistic_header) = &doc.logistic_header {
+            self.write_logistic_header(logistic_header);
+        }
+        if let Some(history_record) = &doc.history_record {
+            self.write_history_record(history_record);
+        }
+        if let Some(bom) = &doc.bom {
+            self.write_bom(bom);
+        }
+        if let Some(ecad) = &doc.ecad {
+            self.write_ecad(ecad);
+        }
+        if let Some(avl) = &doc.avl {
+            // Avl serializes itself at the depth of a top-level section
+            self.xml.push_str(&avl.to_xml(self.interner));
+        }
+
+        self.end("IPC-2581");
+        self.xml
+    }
+
+    fn write_content(&mut self, content: &Content) {
+        self.start("Content", vec![("roleRef", self.sym(content.role_ref))]);
+
+        let mut attrs = vec![("mode", mode_str(content.function_mode.mode).to_string())];
+        if let Some(level) = content.function_mode.level {
+            attrs.push(("level", level_str(level).to_string()));
+        }
+        self.empty("FunctionMode", attrs);
+
+        for (tag, refs) in [
+            ("StepRef", &content.step_refs),
+            ("LayerRef", &content.layer_refs),
+            ("BomRef", &content.bom_refs),
+            ("AvlRef", &content.avl_refs),
+        ] {
+            for name in refs {
+                self.empty(tag, vec![("name", self.sym(*name))]);
+            }
+        }
+
+        self.list(
+            "DictionaryColor",
+            vec![],
+            &content.dictionary_color.entries,
+            |w, entry| {
+                w.start("EntryColor", vec![("id", w.sym(entry.id))]);
+                w.empty(
+                    "Color",
+                    vec![
+                        ("r", entry.color.r.to_string()),
+                        ("g", entry.color.g.to_string()),
+                        ("b", entry.color.b.to_string()),
+                    ],
+                );
+                w.end("EntryColor");
+            },
+        );
+
+        let dictionary = &content.dictionary_line_desc;
+        let units = dictionary.units.unwrap_or(Units::Millimeter);
+        self.list(
+            "DictionaryLineDesc",
+            units_attr(dictionary.units),
+            &dictionary.entries,
+            |w, entry| {
+                w.start("EntryLineDesc", vec![("id", w.sym(entry.id))]);
+                w.write_line_desc(&entry.line_desc, units);
+                w.end("EntryLineDesc");
+            },
+        );
+
+        let dictionary = &content.dictionary_fill_desc;
+        self.list(
+            "DictionaryFillDesc",
+            units_attr(dictionary.units),
+            &dictionary.entries,
+            |w, entry| {
+                w.start("EntryFillDesc", vec![("id", w.sym(entry.id))]);
+                w.write_fill_desc(&entry.fill_desc);
+                w.end("EntryFillDesc");
+            },
+        );
+
+        let dictionary = &content.dictionary_standard;
+        let units = dictionary.units.unwrap_or(Units::Millimeter);
+        self.list(
+            "DictionaryStandard",
+            units_attr(dictionary.units),
+            &dictionary.entries,
+            |w, entry| {
+                w.start("EntryStandard", vec![("id", w.sym(entry.id))]);
+                w.write_standard_primitive(&entry.primitive, units);
+                w.end("EntryStandard");
+            },
+        );
+
+        let dictionary = &content.dictionary_user;
+        let units = dictionary.units.unwrap_or(Units::Millimeter);
+        self.list(
+            "DictionaryUser",
+            units_attr(dictionary.units),
+            &dictionary.entries,
+            |w, entry| {
+                w.start("EntryUser", vec![("id", w.sym(entry.id))]);
+                match &entry.primitive {
+                    UserPrimitive::UserSpecial(special) => {
+                        w.list("UserSpecial", vec![], &special.shapes, |w, shape| {
+                            w.write_user_shape(shape, units)
+                        });
+                    }
+                }
+                w.end("EntryUser");
+            },
+        );
+
+        self.end("Content");
+    }
+
+    fn write_line_desc(&mut self, line_desc: &LineDesc, units: Units) {
+        let mut attrs = vec![
+            ("lineWidth", len(line_desc.line_width, units)),
+            ("lineEnd", line_end_str(line_desc.line_end).to_string()),
+        ];
+        if let Some(property) = line_desc.line_property {
+            attrs.push(("lineProperty", line_property_str(property).to_string()));
+        }
+        self.empty("LineDesc", attrs);
+    }
+
+    fn write_fill_desc(&mut self, fill_desc: &FillDesc) {
+        let mut attrs = vec![(
+            "fillProperty",
+            fill_property_str(fill_desc.fill_property).to_string(),
+        )];
+        push_opt(&mut attrs, "angle1", fill_desc.angle1.map(num));
+        push_opt(&mut attrs, "angle2", fill_desc.angle2.map(num));
+        self.empty("FillDesc", attrs);
+    }
+
+    fn write_standard_primitive(&mut self, primitive: &StandardPrimitive, units: Units) {
+        match primitive {
+            StandardPrimitive::Circle(s) => self.write_styled(
+                "Circle",
+                vec![("diameter", len(s.shape.diameter, units))],
+                s,
+            ),
+            StandardPrimitive::RectCenter(s) => {
+                self.write_styled("RectCenter", size_attrs(s.shape.size, units), s)
+            }
+            StandardPrimitive::RectRound(s) => {
+                let mut attrs = size_attrs(s.shape.size, units);
+                attrs.push(("radius", len(s.shape.radius, units)));
+                attrs.extend(corner_attrs([
+                    s.shape.upper_right,
+                    s.shape.upper_left,
+                    s.shape.lower_right,
+                    s.shape.lower_left,
+                ]));
+                self.write_styled("RectRound", attrs, s)
+            }
+            StandardPrimitive::RectCham(s) => {
+                let mut attrs = size_attrs(s.shape.size, units);
+                attrs.push(("chamfer", len(s.shape.chamfer, units)));
+                attrs.extend(corner_attrs([
+                    s.shape.upper_right,
+                    s.shape.upper_left,
+                    s.shape.lower_right,
+                    s.shape.lower_left,
+                ]));
+                self.write_styled("RectCham", attrs, s)
+            }
+            StandardPrimitive::RectCorner(s) => self.write_styled(
+                "RectCorner",
+                vec![
+                    ("lowerLeftX", len(s.shape.lower_left.x, units)),
+                    ("lowerLeftY", len(s.shape.lower_left.y, units)),
+                    ("upperRightX", len(s.shape.upper_right.x, units)),
+                    ("upperRightY", len(s.shape.upper_right.y, units)),
+                ],
+                s,
+            ),
+            StandardPrimitive::Oval(s) => {
+                self.write_styled("Oval", size_attrs(s.shape.size, units), s)
+            }
+            StandardPrimitive::Butterfly(s) => {
+                let (shape, size_attr) = match s.shape.shape {
+                    ButterflyShape::Round => ("ROUND", "diameter"),
+                    ButterflyShape::Square => ("SQUARE", "side"),
+                };
+                self.write_styled(
+                    "Butterfly",
+                    vec![
+                        ("shape", shape.to_string()),
+                        (size_attr, len(s.shape.size, units)),
+                    ],
+                    s,
+                )
+            }
+            StandardPrimitive::Diamond(s) => {
+                self.write_styled("Diamond", size_attrs(s.shape.size, units), s)
+            }
+            StandardPrimitive::Donut(s) => self.write_styled(
+                "Donut",
+                vec![
+                    ("shape", concentric_shape_str(s.shape.shape).to_string()),
+                    ("outerDiameter", len(s.shape.outer_diameter, units)),
+                    ("innerDiameter", len(s.shape.inner_diameter, units)),
+                ],
+                s,
+            ),
+            StandardPrimitive::Ellipse(s) => {
+                self.write_styled("Ellipse", size_attrs(s.shape.size, units), s)
+            }
+            StandardPrimitive::Hexagon(s) => self.write_styled(
+                "Hexagon",
+                vec![("length", len(s.shape.point_to_point, units))],
+                s,
+            ),
+            StandardPrimitive::Moire(moire) => {
+                let mut attrs = vec![
+                    ("diameter", len(moire.diameter, units)),
+                    ("ringWidth", len(moire.ring_width, units)),
+                    ("ringGap", len(moire.ring_gap, units)),
+                    ("ringNumber", moire.ring_number.to_string()),
+                ];
+                push_opt(
+                    &mut attrs,
+                    "lineWidth",
+                    moire.line_width.map(|v| len(v, units)),
+                );
+                push_opt(
+                    &mut attrs,
+                    "lineLength",
+                    moire.line_length.map(|v| len(v, units)),
+                );
+                push_opt(&mut attrs, "lineAngle", moire.line_angle.map(num));
+                self.empty("Moire", attrs);
+            }
+            StandardPrimitive::Octagon(s) => self.write_styled(
+                "Octagon",
+                vec![("length", len(s.shape.point_to_point, units))],
+                s,
+            ),
+            StandardPrimitive::Thermal(s) => {
+                let mut attrs = vec![
+                    ("shape", concentric_shape_str(s.shape.shape).to_string()),
+                    ("outerDiameter", len(s.shape.outer_diameter, units)),
+                    ("innerDiameter", len(s.shape.inner_diameter, units)),
+                    ("spokeCount", s.shape.spoke_count.to_string()),
+                ];
+                push_opt(
+                    &mut attrs,
+                    "spokeWidth",
+                    s.shape.spoke_width.map(|v| len(v, units)),
+                );
+                push_opt(
+                    &mut attrs,
+                    "spokeStartAngle",
+                    s.shape.spoke_start_angle.map(num),
+                );
+                self.write_styled("Thermal", attrs, s)
+            }
+            StandardPrimitive::Triangle(s) => self.write_styled(
+                "Triangle",
+                vec![
+                    ("base", len(s.shape.base, units)),
+                    ("height", len(s.shape.height, units)),
+                ],
+                s,
+            ),
+            StandardPrimitive::Contour(contour) => {
+                self.start("Contour", vec![]);
+                self.write_polygon("Polygon", &contour.polygon, units);
+                for cutout in &contour.cutouts {
+                    self.write_polygon("Cutout", cutout, units);
+                }
+                self.end("Contour");
+            }
+        }
+    }
+
+    /// Write a primitive with its optional FillDesc and LineDescRef children
+    fn write_styled<T>(&mut self, tag: &'static str, attrs: Attrs, styled: &Styled<T>) {
+        if styled.fill_property.is_none() && styled.line_desc_ref.is_none() {
+            self.empty(tag, attrs);
+            return;
+        }
+
+        self.start(tag, attrs);
+        if let Some(fill_property) = styled.fill_property {
+            self.empty(
+                "FillDesc",
+                vec![("fillProperty", fill_property_str(fill_property).to_string())],
+            );
+        }
+        if let Some(line_desc_ref) = styled.line_desc_ref {
+            self.empty("LineDescRef", vec![("id", self.sym(line_desc_ref))]);
+        }
+        self.end(tag);
+    }
+
+    fn write_polygon(&mut self, tag: &'static str, polygon: &Polygon, units: Units) {
+        self.start(tag, vec![]);
+        self.write_poly_steps(polygon, units);
+        self.end(tag);
+    }
+
+    fn write_poly_steps(&mut self, polygon: &Polygon, units: Units) {
+        self.empty("PolyBegin", point_attrs("x", "y", polygon.begin, units));
+        for step in &polygon.steps {
+            match step {
+                PolyStep::Segment(segment) => {
+                    self.empty(
+                        "PolyStepSegment",
+                        point_attrs("x", "y", segment.point, units),
+                    );
+                }
+                PolyStep::Curve(curve) => {
+                    let mut attrs = point_attrs("x", "y", curve.point, units);
+                    attrs.extend(point_attrs("centerX", "centerY", curve.center, units));
+                    attrs.push(("clockwise", curve.clockwise.to_string()));
+                    self.empty("PolyStepCurve", attrs);
+                }
+            }
+        }
+    }
+
+    fn write_user_shape(&mut self, shape: &UserShape, units: Units) {
+        let (tag, attrs) = match &shape.shape {
+            UserShapeType::Circle(circle) => {
+                ("Circle", vec![("diameter", len(circle.diameter, units))])
+            }
+            UserShapeType::RectCenter(rect) => ("RectCenter", size_attrs(rect.size, units)),
+            UserShapeType::Oval(oval) => ("Oval", size_attrs(oval.size, units)),
+            UserShapeType::Polygon(_) => ("Polygon", vec![]),
+        };
+
+        let is_polygon = matches!(shape.shape, UserShapeType::Polygon(_));
+        if !is_polygon && shape.line_desc.is_none() && shape.fill_desc.is_none() {
+            self.empty(tag, attrs);
+            return;
+        }
+
+        self.start(tag, attrs);
+        if let UserShapeType::Polygon(polygon) = &shape.shape {
+            self.write_poly_steps(polygon, units);
+        }
+        if let Some(line_desc) = &shape.line_desc {
+            self.write_line_desc(line_desc, units);
+        }
+        if let Some(fill_desc) = &shape.fill_desc {
+            self.write_fill_desc(fill_desc);
+        }
+        self.end(tag);
+    }
+
+    fn write_logistic_header(&mut self, header: &LogisticHeader) {
+        self.start("LogisticHeader", vec![]);
+        for role in &header.roles {
+            self.empty(
+                "Role",
+                vec![
+                    ("id", self.sym(role.id)),
+                    ("roleFunction", self.sym(role.role_function)),
+                ],
+            );
+        }
+        for enterprise in &header.enterprises {
+            let mut attrs = vec![
+                ("id", self.sym(enterprise.id)),
+                ("code", self.sym(enterprise.code)),
+            ];
+            push_opt(&mut attrs, "name", self.opt_sym(enterprise.name));
+            self.empty("Enterprise", attrs);
+        }
+        for person in &header.persons {
+            let mut attrs = vec![("name", self.sym(person.name))];
+            push_opt(&mut attrs, "email", self.opt_sym(person.email));
+            self.empty("Person", attrs);
+        }
+        self.end("LogisticHeader");
+    }
+
+    fn write_history_record(&mut self, record: &HistoryRecord) {
+        let mut attrs = vec![
+            ("number", record.number.to_string()),
+            ("origination", self.sym(record.origination)),
+        ];
+        push_opt(&mut attrs, "software", self.opt_sym(record.software));
+        attrs.push(("lastChange", self.sym(record.last_change)));
+
+        let Some(file_revision) = &record.file_revision else {
+            self.empty("HistoryRecord", attrs);
+            return;
+        };
+        self.start("HistoryRecord", attrs);
+
+        let mut attrs = vec![("fileRevisionId", self.sym(file_revision.file_revision))];
+        push_opt(&mut attrs, "comment", self.opt_sym(file_revision.comment));
+        match &file_revision.software_package {
+            Some(package) => {
+                self.start("FileRevision", attrs);
+                let mut attrs = vec![("name", self.sym(package.name))];
+                push_opt(&mut attrs, "revision", self.opt_sym(package.revision));
+                push_opt(&mut attrs, "vendor", self.opt_sym(package.vendor));
+                self.empty("SoftwarePackage", attrs);
+                self.end("FileRevision");
+            }
+            None => self.empty("FileRevision", attrs),
+        }
+
+        self.end("HistoryRecord");
+    }
+
+    fn write_bom(&mut self, bom: &Bom) {
+        self.list(
+            "Bom",
+            vec![("name", self.sym(bom.name))],
+            &bom.items,
+            |w, item| w.write_bom_item(item),
+        );
+    }
+
+    fn write_bom_item(&mut self, item: &BomItem) {
+        let mut attrs = vec![("OEMDesignNumberRef", self.sym(item.oem_design_number_ref))];
+        push_opt(&mut attrs, "quantity", item.quantity.map(|q| q.to_string()));
+        push_opt(
+            &mut attrs,
+            "pinCount",
+            item.pin_count.map(|p| p.to_string()),
+        );
+        push_opt(
+            &mut attrs,
+            "category",
+            item.category.map(|c| bom_category_str(c).to_string()),
+        );
+        push_opt(&mut attrs, "description", self.opt_sym(item.description));
+
+        if item.ref_des_list.is_empty() && item.characteristics.is_none() {
+            self.empty("BomItem", attrs);
+            return;
+        }
+
+        self.start("BomItem", attrs);
+        for ref_des in &item.ref_des_list {
+            self.empty(
+                "RefDes",
+                vec![
+                    ("name", self.sym(ref_des.name)),
+                    ("packageRef", self.sym(ref_des.package_ref)),
+                    ("populate", ref_des.populate.to_string()),
+                    ("layerRef", self.sym(ref_des.layer_ref)),
+                ],
+            );
+        }
+        if let Some(characteristics) = &item.characteristics {
+            let mut attrs = vec![];
+            push_opt(
+                &mut attrs,
+                "category",
+                characteristics
+                    .category
+                    .map(|c| bom_category_str(c).to_string()),
+            );
+            self.list(
+                "Characteristics",
+                attrs,
+                &characteristics.textuals,
+                |w, textual| {
+                    let mut attrs = vec![];
+                    push_opt(
+                        &mut attrs,
+                        "definitionSource",
+                        w.opt_sym(textual.definition_source),
+                    );
+                    push_opt(
+                        &mut attrs,
+                        "textualCharacteristicName",
+                        w.opt_sym(textual.name),
+                    );
+                    push_opt(
+                        &mut attrs,
+                        "textualCharacteristicValue",
+                        w.opt_sym(textual.value),
+                    );
+                    w.empty("Textual", attrs);
+                },
+            );
+        }
+        self.end("BomItem");
+    }
+
+    fn write_ecad(&mut self, ecad: &Ecad) {
+        let units = ecad.cad_header.units;
+        self.start("Ecad", vec![]);
+
+        // Specs are keyed by symbol; sort them by name so the output is stable
+        let mut specs: Vec<&ecad::Spec> = ecad.cad_header.specs.values().collect();
+        specs.sort_by(|a, b| self.resolve(a.name).cmp(self.resolve(b.name)));
+        self.list(
+            "CadHeader",
+            vec![("units", units_str(units).to_string())],
+            &specs,
+            |w, spec| w.write_spec(spec),
+        );
+
+        let cad_data = &ecad.cad_data;
+        self.start("CadData", vec![]);
+        for layer in &cad_data.layers {
+            self.write_layer(layer, units);
+        }
+        for stackup in &cad_data.stackups {
+            self.write_stackup(stackup, units);
+        }
+        for step in &cad_data.steps {
+            self.write_step(step, units);
+        }
+        self.end("CadData");
+
+        self.end("Ecad");
+    }
+
+    fn write_spec(&mut self, spec: &ecad::Spec) {
+        self.start("Spec", vec![("name", self.sym(spec.name))]);
+
+        // The parser takes the material from the first Property text
+        let properties = match (spec.properties.is_empty(), spec.material) {
+            (true, Some(material)) => vec![material],
+            _ => spec.properties.clone(),
+        };
+        if !properties.is_empty() || spec.color_term.is_some() || spec.color_rgb.is_some() {
+            self.start("General", vec![("type", "MATERIAL".to_string())]);
+            for text in properties {
+                self.empty("Property", vec![("text", self.sym(text))]);
+            }
+            if let Some(color_term) = spec.color_term {
+                self.empty("ColorTerm", vec![("name", self.sym(color_term))]);
+            }
+            if let Some((r, g, b)) = spec.color_rgb {
+                self.empty(
+                    "Color",
+                    vec![
+                        ("r", r.to_string()),
+                        ("g", g.to_string()),
+                        ("b", b.to_string()),
+                    ],
+                );
+            }
+            self.end("General");
+        }
+
+        for (kind, value) in [
+            ("DIELECTRIC_CONSTANT", spec.dielectric_constant),
+            ("LOSS_TANGENT", spec.loss_tangent),
+        ] {
+            if let Some(value) = value {
+                self.start("Dielectric", vec![("type", kind.to_string())]);
+                self.empty("Property", vec![("value", num(value))]);
+                self.end("Dielectric");
+            }
+        }
+
+        if let Some(weight) = spec.copper_weight_oz {
+            self.start("Conductor", vec![("type", "WEIGHT".to_string())]);
+            self.empty(
+                "Property",
+                vec![("value", num(weight)), ("unit", "OZ".to_string())],
+            );
+            self.end("Conductor");
+        }
+
+        if let Some(finish) = &spec.surface_finish {
+            self.start("SurfaceFinish", vec![]);
+            let mut attrs = vec![("type", finish_type_str(finish.finish_type).to_string())];
+            push_opt(&mut attrs, "comment", self.opt_sym(finish.comment));
+            self.list("Finish", attrs, &finish.products, |w, product| {
+                let mut attrs = vec![("name", w.sym(product.name))];
+                push_opt(
+                    &mut attrs,
+                    "criteria",
+                    product
+                        .criteria
+                        .map(|c| product_criteria_str(c).to_string()),
+                );
+                w.empty("Product", attrs);
+            });
+            self.end("SurfaceFinish");
+        }
+
+        self.end("Spec");
+    }
+
+    fn write_layer(&mut self, layer: &Layer, units: Units) {
+        let mut attrs = vec![
+            ("name", self.sym(layer.name)),
+            (
+                "layerFunction",
+                layer_function_str(layer.layer_function).to_string(),
+            ),
+        ];
+        push_opt(
+            &mut attrs,
+            "side",
+            layer.side.map(|s| side_str(s).to_string()),
+        );
+        push_opt(
+            &mut attrs,
+            "polarity",
+            layer.polarity.map(|p| polarity_str(p).to_string()),
+        );
+
+        match &layer.profile {
+            Some(profile) => {
+                self.start("Layer", attrs);
+                self.write_profile(profile, units);
+                self.end("Layer");
+            }
+            None => self.empty("Layer", attrs),
+        }
+    }
+
+    fn write_profile(&mut self, profile: &Profile, units: Units) {
+        self.start("Profile", vec![]);
+        self.write_polygon("Polygon", &profile.polygon, units);
+        for cutout in &profile.cutouts {
+            self.start("Cutout", vec![]);
+            self.write_polygon("Polygon", cutout, units);
+            self.end("Cutout");
+        }
+        self.end("Profile");
+    }
+
+    fn write_stackup(&mut self, stackup: &Stackup, units: Units) {
+        let mut attrs = vec![("name", self.sym(stackup.name))];
+        push_opt(
+            &mut attrs,
+            "overallThickness",
+            stackup.overall_thickness.map(|v| len(v, units)),
+        );
+        push_opt(
+            &mut attrs,
+            "whereMeasured",
+            stackup
+                .where_measured
+                .map(|w| where_measured_str(w).to_string()),
+        );
+        push_opt(
+            &mut attrs,
+            "tolPlus",
+            stackup.tol_plus.map(|v| len(v, units)),
+        );
+        push_opt(
+            &mut attrs,
+            "tolMinus",
+            stackup.tol_minus.map(|v| len(v, units)),
+        );
+
+        if stackup.layers.is_empty() {
+            self.empty("Stackup", attrs);
+            return;
+        }
+
+        self.start("Stackup", attrs);
+        self.start("StackupGroup", vec![("name", self.sym(stackup.name))]);
+        for layer in &stackup.layers {
+            let mut attrs = vec![("layerOrGroupRef", self.sym(layer.layer_ref))];
+            push_opt(
+                &mut attrs,
+                "thickness",
+                layer.thickness.map(|v| len(v, units)),
+            );
+            push_opt(&mut attrs, "tolPlus", layer.tol_plus.map(|v| len(v, units)));
+            push_opt(
+                &mut attrs,
+                "tolMinus",
+                layer.tol_minus.map(|v| len(v, units)),
+            );
+            push_opt(
+                &mut attrs,
+                "sequence",
+                layer.layer_number.map(|n| n.to_string()),
+            );
+            match layer.spec_ref {
+                Some(spec_ref) => {
+                    self.start("StackupLayer", attrs);
+                    self.empty("SpecRef", vec![("id", self.sym(spec_ref))]);
+                    self.end("StackupLayer");
+                }
+                None => self.empty("StackupLayer", attrs),
+            }
+        }
+        self.end("StackupGroup");
+        self.end("Stackup");
+    }
+
+    fn write_step(&mut self, step: &Step, units: Units) {
+        self.start("Step", vec![("name", self.sym(step.name))]);
+
+        if let Some(datum) = step.datum {
+            self.empty(
+                "Datum",
+                vec![("x", len(datum.x, units)), ("y", len(datum.y, units))],
+            );
+        }
+        if let Some(profile) = &step.profile {
+            self.write_profile(profile, units);
+        }
+
+        for padstack_def in &step.padstack_defs {
+            self.start("PadStackDef", vec![("name", self.sym(padstack_def.name))]);
+            if let Some(hole) = &padstack_def.hole_def {
+                self.empty(
+                    "PadstackHoleDef",
+                    vec![
+                        ("name", self.sym(hole.name)),
+                        ("diameter", len(hole.diameter, units)),
+                        (
+                            "platingStatus",
+                            plating_status_str(hole.plating_status).to_string(),
+                        ),
+                        ("plusTol", len(hole.plus_tol, units)),
+                        ("minusTol", len(hole.minus_tol, units)),
+                        ("x", len(hole.x, units)),
+                        ("y", len(hole.y, units)),
+                    ],
+                );
+            }
+            for pad_def in &padstack_def.pad_defs {
+                let attrs = vec![
+                    ("layerRef", self.sym(pad_def.layer_ref)),
+                    ("padUse", pad_use_str(pad_def.pad_use).to_string()),
+                ];
+                self.write_with_primitive_refs(
+                    "PadstackPadDef",
+                    attrs,
+                    pad_def.standard_primitive_ref,
+                    pad_def.user_primitive_ref,
+                );
+            }
+            self.end("PadStackDef");
+        }
+
+        for package in &step.packages {
+            let mut attrs = vec![
+                ("name", self.sym(package.name)),
+                ("type", self.sym(package.package_type)),
+            ];
+            push_opt(&mut attrs, "pinOne", self.opt_sym(package.pin_one));
+            push_opt(&mut attrs, "height", package.height.map(num));
+            self.empty("Package", attrs);
+        }
+
+        for component in &step.components {
+            let mut attrs = vec![
+                ("refDes", self.sym(component.ref_des)),
+                ("packageRef", self.sym(component.package_ref)),
+                ("layerRef", self.sym(component.layer_ref)),
+            ];
+            push_opt(
+                &mut attrs,
+                "mountType",
+                component.mount_type.map(|m| mount_type_str(m).to_string()),
+            );
+            push_opt(&mut attrs, "part", self.opt_sym(component.part));
+            self.empty("Component", attrs);
+        }
+
+        for net in &step.logical_nets {
+            self.list(
+                "LogicalNet",
+                vec![("name", self.sym(net.name))],
+                &net.pin_refs,
+                |w, pin_ref| {
+                    w.empty(
+                        "PinRef",
+                        vec![
+                            ("componentRef", w.sym(pin_ref.component_ref)),
+                            ("pin", w.sym(pin_ref.pin)),
+                        ],
+                    );
+                },
+            );
+        }
+
+        for group in &step.phy_net_groups {
+            self.empty("PhyNetGroup", vec![("name", self.sym(group.name))]);
+        }
+
+        for feature in &step.layer_features {
+            self.list(
+                "LayerFeature",
+                vec![("layerRef", self.sym(feature.layer_ref))],
+                &feature.sets,
+                |w, set| w.write_feature_set(set, units),
+            );
+        }
+
+        self.end("Step");
+    }
+
+    /// Write an element with optional StandardPrimitiveRef and UserPrimitiveRef children
+    fn write_with_primitive_refs(
+        &mut self,
+        tag: &'static str,
+        attrs: Attrs,
+        standard_primitive_ref: Option<Symbol>,
+        user_primitive_ref: Option<Symbol>,
+    ) {
+        if standard_primitive_ref.is_none() && user_primitive_ref.is_none() {
+            self.empty(tag, attrs);
+            return;
+        }
+
+        self.start(tag, attrs);
+        if let Some(id) = standard_primitive_ref {
+            self.empty("StandardPrimitiveRef", vec![("id", self.sym(id))]);
+        }
+        if let Some(id) = user_primitive_ref {
+            self.empty("UserPrimitiveRef", vec![("id", self.sym(id))]);
+        }
+        self.end(tag);
+    }
+
+    fn write_feature_set(&mut self, set: &FeatureSet, units: Units) {
+        let mut attrs = vec![];
+        push_opt(&mut attrs, "net", self.opt_sym(set.net));
+        push_opt(&mut attrs, "geometry", self.opt_sym(set.geometry));
+        push_opt(
+            &mut attrs,
+            "polarity",
+            set.polarity.map(|p| polarity_str(p).to_string()),
+        );
+        self.start("Set", attrs);
+
+        for attribute in &set.nonstandard_attributes {
+            let mut attrs = vec![("name", self.sym(attribute.name))];
+            push_opt(&mut attrs, "value", self.opt_sym(attribute.value));
+            push_opt(&mut attrs, "type", self.opt_sym(attribute.attr_type));
+            self.empty("NonstandardAttribute", attrs);
+        }
+
+        for pad in &set.pads {
+            self.write_pad(pad, units);
+        }
+
+        for hole in &set.holes {
+            let mut attrs = vec![];
+            push_opt(&mut attrs, "name", self.opt_sym(hole.name));
+            attrs.extend([
+                ("diameter", len(hole.diameter, units)),
+                (
+                    "platingStatus",
+                    plating_status_str(hole.plating_status).to_string(),
+                ),
+                ("x", len(hole.x, units)),
+                ("y", len(hole.y, units)),
+            ]);
+            self.empty("Hole", attrs);
+        }
+
+        for slot in &set.slots {
+            let mut attrs = vec![];
+            push_opt(&mut attrs, "name", self.opt_sym(slot.name));
+            attrs.push((
+                "platingStatus",
+                plating_status_str(slot.plating_status).to_string(),
+            ));
+            self.start("SlotCavity", attrs);
+            self.empty(
+                "Location",
+                vec![("x", len(slot.x, units)), ("y", len(slot.y, units))],
+            );
+            match &slot.shape {
+                SlotShape::Outline(polygon) => {
+                    self.start("Outline", vec![]);
+                    self.write_polygon("Polygon", polygon, units);
+                    self.end("Outline");
+                }
+                SlotShape::Primitive(primitive) => self.write_standard_primitive(primitive, units),
+            }
+            self.end("SlotCavity");
+        }
+
+        for trace in &set.traces {
+            self.start("Polyline", vec![]);
+            for (i, point) in trace.points.iter().enumerate() {
+                let tag = if i == 0 {
+                    "PolyBegin"
+                } else {
+                    "PolyStepSegment"
+                };
+                self.empty(
+                    tag,
+                    vec![("x", len(point.x, units)), ("y", len(point.y, units))],
+                );
+            }
+            if let Some(id) = trace.line_desc_ref {
+                self.empty("LineDescRef", vec![("id", self.sym(id))]);
+            }
+            self.end("Polyline");
+        }
+
+        if !set.polygons.is_empty() || !set.lines.is_empty() {
+            self.start("Features", vec![]);
+            self.empty("Location", vec![("x", num(0.0)), ("y", num(0.0))]);
+            for polygon in &set.polygons {
+                self.write_polygon("Polygon", polygon, units);
+            }
+            if !set.lines.is_empty() {
+                self.start("UserSpecial", vec![]);
+                for line in &set.lines {
+                    self.start(
+                        "Line",
+                        vec![
+                            ("startX", len(line.start_x, units)),
+                            ("startY", len(line.start_y, units)),
+                            ("endX", len(line.end_x, units)),
+                            ("endY", len(line.end_y, units)),
+                        ],
+                    );
+                    let mut attrs = vec![("lineWidth", len(line.line_width, units))];
+                    push_opt(
+                        &mut attrs,
+                        "lineEnd",
+                        line.line_end.map(|e| line_end_str(e).to_string()),
+                    );
+                    self.empty("LineDesc", attrs);
+                    self.end("Line");
+                }
+                self.end("UserSpecial");
+            }
+            self.end("Features");
+        }
+
+        self.end("Set");
+    }
+
+    fn write_pad(&mut self, pad: &Pad, units: Units) {
+        let mut attrs = vec![];
+        push_opt(
+            &mut attrs,
+            "padstackDefRef",
+            self.opt_sym(pad.padstack_def_ref),
+        );
+        self.start("Pad", attrs);
+
+        if let Some(xform) = pad.xform {
+            let default = Xform::default();
+            let mut attrs = vec![];
+            if xform.x_offset != default.x_offset {
+                attrs.push(("xOffset", num(xform.x_offset)));
+            }
+            if xform.y_offset != default.y_offset {
+                attrs.push(("yOffset", num(xform.y_offset)));
+            }
+            if xform.rotation != default.rotation {
+                attrs.push(("rotation", num(xform.rotation)));
+            }
+            if xform.mirror {
+                attrs.push(("mirror", "true".to_string()));
+            }
+            if xform.scale != default.scale {
+                attrs.push(("scale", num(xform.scale)));
+            }
+            self.empty("Xform", attrs);
+        }
+
+        if pad.x.is_some() || pad.y.is_some() {
+            let mut attrs = vec![];
+            push_opt(&mut attrs, "x", pad.x.map(|v| len(v, units)));
+            push_opt(&mut attrs, "y", pad.y.map(|v| len(v, units)));
+            self.empty("Location", attrs);
+        }
+
+        if let Some(id) = pad.standard_primitive_ref {
+            self.empty("StandardPrimitiveRef", vec![("id", self.sym(id))]);
+        }
+        if let Some(id) = pad.user_primitive_ref {
+            self.empty("UserPrimitiveRef", vec![("id", self.sym(id))]);
+        }
+
+        self.end("Pad");
+    }
+
+    // Helper methods
+    fn resolve(&self, sym: Symbol) -> &'a str {
+        self.interner.resolve(sym)
+    }
+
+    fn sym(&self, sym: Symbol) -> String {
+        self.resolve(sym).to_string()
+    }
+
+    fn opt_sym(&self, sym: Option<Symbol>) -> Option<String> {
+        sym.map(|s| self.sym(s))
+    }
+
+    fn open_tag(&mut self, tag: &str, attrs: &Attrs) {
+        for _ in 0..self.depth {
+            self.xml.push_str("  ");
+        }
+        self.xml.push('<');
+        self.xml.push_str(tag);
+        for (name, value) in attrs {
+            self.xml.push(' ');
+            self.xml.push_str(name);
+            self.xml.push_str("=\"");
+            // Whitespace other than spaces would be normalized away when parsed
+            let value = escape(value.as_str())
+                .replace('\n', "&#10;")
+                .replace('\r', "&#13;")
+                .replace('\t', "&#9;");
+            self.xml.push_str(&value);
+            self.xml.push('"');
+        }
+    }
+
+    fn start(&mut self, tag: &str, attrs: Attrs) {
+        self.open_tag(tag, &attrs);
+        self.xml.push_str(">\n");
+        self.depth += 1;
+    }
+
+    fn end(&mut self, tag: &str) {
+        self.depth -= 1;
+        for _ in 0..self.depth {
+            self.xml.push_str("  ");
+        }
+        self.xml.push_str("</");
+        self.xml.push_str(tag);
+        self.xml.push_str(">\n");
+    }
+
+    fn empty(&mut self, tag: &str, attrs: Attrs) {
+        self.open_tag(tag, &attrs);
+        self.xml.push_str("/>\n");
+    }
+
+    /// Write an element with one child per item, or an empty element if there are none
+    fn list<T>(
+        &mut self,
+        tag: &str,
+        attrs: Attrs,
+        items: &[T],
+        mut write_item: impl FnMut(&mut Self, &T),
+    ) {
+        if items.is_empty() {
+            self.empty(tag, attrs);
+            return;
+        }
+
+        self.start(tag, attrs);
+        for item in items {
+            write_item(self, item);
+        }
+        self.end(tag);
+    }
+}
+
+fn push_opt(attrs: &mut Attrs, name: &'static str, value: Option<String>) {
+    if let Some(value) = value {
+        attrs.push((name, value));
+    }
+}
+
+/// Format a number with at most 9 decimals, which covers every unit while dropping the float
+/// noise of converting to mm and back
+fn num(value: f64) -> String {
+    let s = format!("{:.9}", value);
+    let s = s.trim_end_matches('0').trim_end_matches('.');
+    if s == "-0" {
+        "0".to_string()
+    } else {
+        s.to_string()
+    }
+}
+
+/// Format a dimension stored in mm in the given units
+fn len(value: f64, units: Units) -> String {
+    num(from_mm(value, units))
+}
+
+fn size_attrs(size: Size, units: Units) -> Attrs {
+    vec![
+        ("width", len(size.width, units)),
+        ("height", len(size.height, units)),
+    ]
+}
+
+fn point_attrs(x: &'static str, y: &'static str, point: Point, units: Units) -> Attrs {
+    vec![(x, len(point.x, units)), (y, len(point.y, units))]
+}
+
+fn corner_attrs([upper_right, upper_left, lower_right, lower_left]: [bool; 4]) -> Attrs {
+    vec![
+        ("upperRight", upper_right.to_string()),
+        ("upperLeft", upper_left.to_string()),
+        ("lowerRight", lower_right.to_string()),
+        ("lowerLeft", lower_left.to_string()),
+    ]
+}
+
+fn units_attr(units: Option<Units>) -> Attrs {
+    units
+        .map(|u| vec![("units", units_str(u).to_string())])
+        .unwrap_or_default()
+}
+
+fn units_str(units: Units) -> &'static str {
+    match units {
+        Units::Millimeter => "MILLIMETER",
+        Units::Inch => "INCH",
+        Units::Micron => "MICRON",
+        Units::Mils => "MILS",
+    }
+}
+
+fn mode_str(mode: Mode) -> &'static str {
+    match mode {
+        Mode::UserDef => "USERDEF",
+        Mode::Bom => "BOM",
+        Mode::Stackup => "STACKUP",
+        Mode::Fabrication => "FABRICATION",
+        Mode::Assembly => "ASSEMBLY",
+        Mode::Test => "TEST",
+        Mode::Stencil => "STENCIL",
+        Mode::Dfx => "DFX",
+    }
+}
+
+fn level_str(level: Level) -> &'static str {
+    match level {
+        Level::Full => "FULL",
+        Level::Partial => "PARTIAL",
+    }
+}
+
+fn line_end_str(line_end: LineEnd) -> &'static str {
+    match line_end {
+        LineEnd::Round => "ROUND",
+        LineEnd::Square => "SQUARE",
+        LineEnd::Flat => "FLAT",
+    }
+}
+
+fn line_property_str(property: LineProperty) -> &'static str {
+    match property {
+        LineProperty::Solid => "SOLID",
+        LineProperty::Dashed => "DASHED",
+        LineProperty::Dotted => "DOTTED",
+    }
+}
+
+fn fill_property_str(property: FillProperty) -> &'static str {
+    match property {
+        FillProperty::Fill => "FILL",
+        FillProperty::Hollow => "HOLLOW",
+        FillProperty::Void => "VOID",
+        FillProperty::Hatch => "HATCH",
+        FillProperty::Mesh => "MESH",
+    }
+}
+
+fn concentric_shape_str(shape: ConcentricShape) -> &'static str {
+    match shape {
+        ConcentricShape::Round => "ROUND",
+        ConcentricShape::Square => "SQUARE",
+        ConcentricShape::Hexagon => "HEXAGON",
+        ConcentricShape::Octagon => "OCTAGON",
+    }
+}
+
+fn bom_category_str(category: BomCategory) -> &'static str {
+    match category {
+        BomCategory::Electrical => "ELECTRICAL",
+        BomCategory::Mechanical => "MECHANICAL",
+        BomCategory::Document => "DOCUMENT",
+    }
+}
+
+fn finish_type_str(finish_type: ecad::FinishType) -> &'static str {
+    use ecad::FinishType;
+    match finish_type {
+        FinishType::S => "S",
+        FinishType::T => "T",
+        FinishType::X => "X",
+        FinishType::TLU => "TLU",
+        FinishType::EnigN => "ENIG-N",
+        FinishType::EnigG => "ENIG-G",
+        FinishType::EnepigN => "ENEPIG-N",
+        FinishType::EnepigG => "ENEPIG-G",
+        FinishType::EnepigP => "ENEPIG-P",
+        FinishType::Dig => "DIG",
+        FinishType::IAg => "IAg",
+        FinishType::ISn => "ISn",
+        FinishType::Osp => "OSP",
+        FinishType::HtOsp => "HT_OSP",
+        FinishType::N => "N",
+        FinishType::NB => "NB",
+        FinishType::C => "C",
+        FinishType::G => "G",
+        FinishType::GS => "GS",
+        FinishType::GwbOneG => "GWB-1-G",
+        FinishType::GwbOneN => "GWB-1-N",
+        FinishType::GwbTwoG => "GWB-2-G",
+        FinishType::GwbTwoN => "GWB-2-N",
+        FinishType::Other => "OTHER",
+    }
+}
+
+fn product_criteria_str(criteria: ecad::ProductCriteria) -> &'static str {
+    use ecad::ProductCriteria;
+    match criteria {
+        ProductCriteria::Allowed => "ALLOWED",
+        ProductCriteria::Suggested => "SUGGESTED",
+        ProductCriteria::Preferred => "PREFERRED",
+        ProductCriteria::Required => "REQUIRED",
+        ProductCriteria::Chosen => "CHOSEN",
+    }
+}
+
+fn layer_function_str(function: LayerFunction) -> &'static str {
+    match function {
+        LayerFunction::Conductor => "CONDUCTOR",
+        LayerFunction::CondFilm => "CONDFILM",
+        LayerFunction::CondFoil => "CONDFOIL",
+        LayerFunction::Plane => "PLANE",
+        LayerFunction::Signal => "SIGNAL",
+        LayerFunction::Mixed => "MIXED",
+        LayerFunction::CoatingCond => "COATINGCOND",
+        LayerFunction::CoatingNonCond => "COATINGNONCOND",
+        LayerFunction::Soldermask => "SOLDERMASK",
+        LayerFunction::Solderpaste => "SOLDERPASTE",
+        LayerFunction::Pastemask => "PASTEMASK",
+        LayerFunction::Silkscreen => "SILKSCREEN",
+        LayerFunction::Legend => "LEGEND",
+        LayerFunction::Drill => "DRILL",
+        LayerFunction::Rout => "ROUT",
+        LayerFunction::VCut => "V_CUT",
+        LayerFunction::Score => "SCORE",
+        LayerFunction::EdgeChamfer => "EDGE_CHAMFER",
+        LayerFunction::EdgePlating => "EDGE_PLATING",
+        LayerFunction::DielBase => "DIELBASE",
+        LayerFunction::DielCore => "DIELCORE",
+        LayerFunction::DielPreg => "DIELPREG",
+        LayerFunction::DielAdhv => "DIELADHV",
+        LayerFunction::DielBondPly => "DIELBONDPLY",
+        LayerFunction::DielCoverlay => "DIELCOVERLAY",
+        LayerFunction::ComponentTop => "COMPONENT_TOP",
+        LayerFunction::ComponentBottom => "COMPONENT_BOTTOM",
+        LayerFunction::ComponentEmbedded => "COMPONENT_EMBEDDED",
+        LayerFunction::ComponentFormed => "COMPONENT_FORMED",
+        LayerFunction::Assembly => "ASSEMBLY",
+        LayerFunction::ConductiveAdhesive => "CONDUCTIVE_ADHESIVE",
+        LayerFunction::Glue => "GLUE",
+        LayerFunction::HoleFill => "HOLEFILL",
+        LayerFunction::SolderBump => "SOLDERBUMP",
+        LayerFunction::Stiffener => "STIFFENER",
+        LayerFunction::Capacitive => "CAPACITIVE",
+        LayerFunction::Resistive => "RESISTIVE",
+        LayerFunction::Document => "DOCUMENT",
+        LayerFunction::Graphic => "GRAPHIC",
+        LayerFunction::BoardOutline => "BOARD_OUTLINE",
+        LayerFunction::BoardFab => "BOARD_FAB",
+        LayerFunction::Rework => "REWORK",
+        LayerFunction::Fixture => "FIXTURE",
+        LayerFunction::Probe => "PROBE",
+        LayerFunction::Courtyard => "COURTYARD",
+        LayerFunction::LandPattern => "LANDPATTERN",
+        LayerFunction::ThievingKeepInout => "THIEVING_KEEP_INOUT",
+        LayerFunction::StackupComposite => "STACKUP_COMPOSITE",
+        LayerFunction::Other => "OTHER",
+    }
+}
+
+fn side_str(side: Side) -> &'static str {
+    match side {
+        Side::Top => "TOP",
+        Side::Bottom => "BOTTOM",
+        Side::Both => "BOTH",
+        Side::Internal => "INTERNAL",
+        Side::All => "ALL",
+        Side::None => "NONE",
+    }
+}
+
+fn polarity_str(polarity: Polarity) -> &'static str {
+    match polarity {
+        Polarity::Positive => "POSITIVE",
+        Polarity::Negative => "NEGATIVE",
+    }
+}
+
+fn where_measured_str(where_measured: WhereMeasured) -> &'static str {
+    match where_measured {
+        WhereMeasured::Metal => "METAL",
+        WhereMeasured::Mask => "MASK",
+        WhereMeasured::Laminate => "LAMINATE",
+        WhereMeasured::Other => "OTHER",
+    }
+}
+
+fn plating_status_str(status: PlatingStatus) -> &'static str {
+    match status {
+        PlatingStatus::Plated => "PLATED",
+        PlatingStatus::NonPlated => "NONPLATED",
+        PlatingStatus::Via => "VIA",
+    }
+}
+
+fn pad_use_str(pad_use: PadUse) -> &'static str {
+    match pad_use {
+        PadUse::Regular => "REGULAR",
+        PadUse::Antipad => "ANTIPAD",
+        PadUse::Thermal => "THERMAL",
+    }
+}
+
+fn mount_type_str(mount_type: MountType) -> &'static str {
+    match mount_type {
+        MountType::Smt => "SMT",
+        MountType::Tht => "THT",
+        MountType::Other => "OTHER",
+    }
+}
@@ -0,0 +1,111 @@
+mod test_helpers;
+
+use ipc2581::Ipc2581;
+use std::path::PathBuf;
+use walkdir::WalkDir;
+
+/// Sizes of the main collections of a document, to check nothing is lost when writing it
+fn summary(doc: &Ipc2581) -> Vec<(&'static str, usize)> {
+    let content = doc.content();
+    let mut summary = vec![
+        ("step refs", content.step_refs.len()),
+        ("layer refs", content.layer_refs.len()),
+        ("colors", content.dictionary_color.entries.len()),
+        ("line descs", content.dictionary_line_desc.entries.len()),
+        (
+            "standard primitives",
+            content.dictionary_standard.entries.len(),
+        ),
+        ("user primitives", content.dictionary_user.entries.len()),
+    ];
+
+    if let Some(header) = doc.logistic_header() {
+        summary.push(("enterprises", header.enterprises.len()));
+        summary.push(("persons", header.persons.len()));
+    }
+    if let Some(bom) = doc.bom() {
+        summary.push(("bom items", bom.items.len()));
+        summary.push((
+            "bom refdes",
+            bom.items.iter().map(|i| i.ref_des_list.len()).sum(),
+        ));
+    }
+    if let Some(avl) = doc.avl() {
+        summary.push(("avl items", avl.items.len()));
+    }
+    if let Some(ecad) = doc.ecad() {
+        let data = &ecad.cad_data;
+        summary.push(("specs", ecad.cad_header.specs.len()));
+        summary.push(("layers", data.layers.len()));
+        summary.push((
+            "stackup layers",
+            data.stackups.iter().map(|s| s.layers.len()).sum(),
+        ));
+        for step in &data.steps {
+            let sets = || step.layer_features.iter().flat_map(|f| &f.sets);
+            summary.extend([
+                ("padstack defs", step.padstack_defs.len()),
+                ("packages", step.packages.len()),
+                ("components", step.components.len()),
+                ("logical nets", step.logical_nets.len()),
+                (
+                    "pin refs",
+                    step.logical_nets.iter().map(|n| n.pin_refs.len()).sum(),
+                ),
+                ("pads", sets().map(|s| s.pads.len()).sum()),
+                ("holes", sets().map(|s| s.holes.len()).sum()),
+                ("slots", sets().map(|s| s.slots.len()).sum()),
+                ("traces", sets().map(|s| s.traces.len()).sum()),
+                ("polygons", sets().map(|s| s.polygons.len()).sum()),
+                ("lines", sets().map(|s| s.lines.len()).sum()),
+            ]);
+        }
+    }
+    summary
+}
+
+#[test]
+fn testcases_roundtrip() {
+    let mut paths: Vec<PathBuf> = WalkDir::new("tests/data")
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .map(|e| e.into_path())
+        .filter(|p| p.to_string_lossy().ends_with(".xml.zst"))
+        .collect();
+    paths.sort();
+    assert!(!paths.is_empty(), "No test data found");
+
+    for path in paths {
+        // The helpers expect the path without the .zst extension
+        let doc =
+            test_helpers::parse_compressed(path.with_extension("").to_str().unwrap()).unwrap();
+
+        let xml = doc.to_xml();
+        let reparsed = Ipc2581::parse(&xml)
+            .unwrap_or_else(|e| panic!("{}: written XML does not parse: {e}", path.display()));
+
+        assert_eq!(reparsed.revision(), doc.revision());
+        assert_eq!(
+            summary(&reparsed),
+            summary(&doc),
+            "{}: document changed when written",
+            path.display()
+        );
+        let profile = |doc: &Ipc2581| {
+            let step = doc.ecad()?.cad_data.steps.first()?;
+            Some(step.profile.as_ref()?.polygon.begin)
+        };
+        if let (Some(expected), Some(actual)) = (profile(&doc), profile(&reparsed)) {
+            assert!(
+                (expected.x - actual.x).abs() < 1e-6 && (expected.y - actual.y).abs() < 1e-6,
+                "{}: board outline moved from {expected:?} to {actual:?}",
+                path.display()
+            );
+        }
+        assert!(
+            reparsed.to_xml() == xml,
+            "{}: written XML is not stable",
+            path.display()
+        );
+    }
+}
@@ -0,0 +1,202 @@
+use anyhow::{bail, Result};
+use pcb_sexpr::{parse, Sexpr};
+use serde::Serialize;
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::Path;
+
+/// Kind of a footprint pad, as written after its number in a `.kicad_mod` file
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PadType {
+    ThruHole,
+    Smd,
+    Connect,
+    NpThruHole,
+}
+
+impl PadType {
+    fn from_keyword(keyword: &str) -> Option<Self> {
+        match keyword {
+            "thru_hole" => Some(PadType::ThruHole),
+            "smd" => Some(PadType::Smd),
+            "connect" => Some(PadType::Connect),
+            "np_thru_hole" => Some(PadType::NpThruHole),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FootprintPad {
+    pub number: String,
+    pub pad_type: PadType,
+}
+
+impl FootprintPad {
+    /// Mounting holes and other pads that never carry a net: NPTH pads and pads without a number
+    pub fn is_mechanical(&self) -> bool {
+        self.pad_type == PadType::NpThruHole || self.number.is_empty()
+    }
+}
+
+/// A footprint loaded from a `.kicad_mod` file
+#[derive(Debug, Clone, Serialize)]
+pub struct KicadFootprint {
+    pub name: String,
+    pub pads: Vec<FootprintPad>,
+}
+
+/// Pads that differ between a symbol and the footprint it is placed with
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct PadMismatch {
+    /// Symbol pads the footprint does not have
+    pub missing: Vec<String>,
+    /// Electrical footprint pads no symbol pin is mapped to
+    pub unmapped: Vec<String>,
+    /// Number of distinct electrical pads in the footprint
+    pub footprint_pads: usize,
+    /// Number of distinct pads in the symbol
+    pub symbol_pads: usize,
+}
+
+impl PadMismatch {
+    pub fn is_empty(&self) -> bool {
+        self.missing.is_empty() && self.unmapped.is_empty()
+    }
+}
+
+impl KicadFootprint {
+    /// Parse a footprint from the contents of a `.kicad_mod` file. Both the current `footprint`
+    /// and the pre-KiCad 6 `module` forms are accepted.
+    pub fn from_string(content: &str) -> Result<Self> {
+        let sexpr = parse(content)?;
+        let Some(items) = sexpr.as_list() else {
+            bail!("Not a KiCad footprint");
+        };
+        let (Some("footprint" | "module"), Some(name)) = (
+            items.first().and_then(Sexpr::as_sym),
+            items.get(1).and_then(Sexpr::as_atom),
+        ) else {
+            bail!("Not a KiCad footprint");
+        };
+
+        let pads = items
+            .iter()
+            .filter_map(|item| {
+                let pad = item.as_list()?;
+                if pad.first()?.as_sym() != Some("pad") {
+                    return None;
+                }
+                // Legacy files leave numeric pad numbers unquoted
+                let number = match pad.get(1)? {
+                    Sexpr::Int(number) => number.to_string(),
+                    other => other.as_atom()?.to_string(),
+                };
+                Some(FootprintPad {
+                    number,
+                    pad_type: PadType::from_keyword(pad.get(2)?.as_sym()?)?,
+                })
+            })
+            .collect();
+
+        Ok(KicadFootprint {
+            name: name.to_string(),
+            pads,
+        })
+    }
+
+    /// Parse a footprint from a `.kicad_mod` file
+    pub fn from_file(path: &Path) -> Result<Self> {
+        let content = fs::read_to_string(path)?;
+        Self::from_string(&content)
+    }
+
+    /// Distinct numbers of the pads that can carry a net. Several pads may share a number, e.g.
+    /// an exposed pad split into a paste grid.
+    pub fn pad_numbers(&self) -> BTreeSet<&str> {
+        self.pads
+            .iter()
+            .filter(|pad| !pad.is_mechanical())
+            .map(|pad| pad.number.as_str())
+            .collect()
+    }
+
+    /// Compare the footprint's electrical pads with the pad numbers of a symbol
+    pub fn check_pads<'a>(&self, symbol_pads: impl IntoIterator<Item = &'a str>) -> PadMismatch {
+        let footprint_pads = self.pad_numbers();
+        let symbol_pads: BTreeSet<&str> = symbol_pads.into_iter().collect();
+
+        PadMismatch {
+            missing: symbol_pads
+                .difference(&footprint_pads)
+                .map(|pad| pad.to_string())
+                .collect(),
+            unmapped: footprint_pads
+                .difference(&symbol_pads)
+                .map(|pad| pad.to_string())
+                .collect(),
+            footprint_pads: footprint_pads.len(),
+            symbol_pads: symbol_pads.len(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SOT23: &str = r#"(footprint "SOT-23"
+        (version 20240108)
+        (layer "F.Cu")
+        (attr smd)
+        (fp_line (start -0.7 -1.5) (end 0.7 -1.5) (layer "F.SilkS"))
+        (pad "1" smd roundrect (at -1 -0.95) (size 1.3 0.6) (layers "F.Cu" "F.Paste" "F.Mask"))
+        (pad "2" smd roundrect (at -1 0.95) (size 1.3 0.6) (layers "F.Cu" "F.Paste" "F.Mask"))
+        (pad "3" smd roundrect (at 1 0) (size 1.3 0.6) (layers "F.Cu" "F.Paste" "F.Mask"))
+        (pad "" np_thru_hole circle (at 0 0) (size 0.8 0.8) (drill 0.8) (layers "*.Cu" "*.Mask"))
+    )"#;
+
+    #[test]
+    fn parses_pads() {
+        let footprint = KicadFootprint::from_string(SOT23).unwrap();
+        assert_eq!(footprint.name, "SOT-23");
+        assert_eq!(footprint.pads.len(), 4);
+        assert_eq!(footprint.pads[0].pad_type, PadType::Smd);
+        assert!(footprint.pads[3].is_mechanical());
+        assert_eq!(
+            footprint.pad_numbers().into_iter().collect::<Vec<_>>(),
+            vec!["1", "2", "3"]
+        );
+    }
+
+    #[test]
+    fn parses_legacy_modules() {
+        let footprint = KicadFootprint::from_string(
+            r#"(module R_0402 (layer F.Cu)
+                (pad 1 smd rect (at -0.5 0) (size 0.6 0.5) (layers F.Cu F.Paste F.Mask))
+                (pad 2 smd rect (at 0.5 0) (size 0.6 0.5) (layers F.Cu F.Paste F.Mask))
+                (pad 5 np_thru_hole circle (at 0 2) (size 1 1) (drill 1) (layers *.Cu)))"#,
+        )
+        .unwrap();
+        assert_eq!(footprint.name, "R_0402");
+        assert_eq!(
+            footprint.pad_numbers().into_iter().collect::<Vec<_>>(),
+            vec!["1", "2"]
+        );
+
+        assert!(KicadFootprint::from_string("(kicad_symbol_lib)").is_err());
+    }
+
+    #[test]
+    fn checks_symbol_pads() {
+        let footprint = KicadFootprint::from_string(SOT23).unwrap();
+        assert!(footprint.check_pads(["1", "2", "3"]).is_empty());
+
+        let mismatch = footprint.check_pads(["1", "2", "4", "5"]);
+        assert_eq!(mismatch.missing, vec!["4", "5"]);
+        assert_eq!(mismatch.unmapped, vec!["3"]);
+        assert_eq!(mismatch.footprint_pads, 3);
+        assert_eq!(mismatch.symbol_pads, 4);
+    }
+}
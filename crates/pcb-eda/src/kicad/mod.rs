@@ -1,2 +1,3 @@
+pub mod footprint;
 pub mod symbol;
 pub mod symbol_library;
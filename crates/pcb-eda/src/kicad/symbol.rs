@@ -1,4 +1,4 @@
-use crate::{Part, Pin, Symbol};
+use crate::{Part, Pin, PinElectricalType, Symbol};
 use anyhow::Result;
 use pcb_sexpr::{parse, Sexpr};
 use serde::Serialize;
@@ -42,6 +42,7 @@ impl KicadSymbol {
 pub(super) struct KicadPin {
     pub(super) name: String,
     pub(super) number: String,
+    pub(super) electrical_type: PinElectricalType,
 }
 
 impl From<KicadSymbol> for Symbol {
@@ -63,6 +64,7 @@ impl From<KicadSymbol> for Symbol {
                 .map(|pin| Pin {
                     name: pin.name,
                     number: pin.number,
+                    electrical_type: pin.electrical_type,
                 })
                 .collect(),
             raw_sexp: symbol.raw_sexp,
@@ -169,7 +171,10 @@ fn parse_symbol_section(symbol: &mut KicadSymbol, section_data: &[Sexpr]) {
 // New function to parse pins from the nested symbol section
 fn parse_pin_from_section(pin_data: &[Sexpr]) -> Option<KicadPin> {
     // Format: (pin unspecified line (at X Y Z) (length L) (name "Name") (number "N"))
-    let mut pin = KicadPin::default();
+    let mut pin = KicadPin {
+        electrical_type: parse_electrical_type(pin_data),
+        ..Default::default()
+    };
 
     // Extract name and number from the pin data
     for item in pin_data {
@@ -198,6 +203,14 @@ fn parse_pin_from_section(pin_data: &[Sexpr]) -> Option<KicadPin> {
     }
 }
 
+/// Electrical type from the first atom after `pin`; unknown types are treated as unspecified
+fn parse_electrical_type(pin_data: &[Sexpr]) -> PinElectricalType {
+    match pin_data.get(1) {
+        Some(Sexpr::Symbol(kind)) => kind.parse().unwrap_or_default(),
+        _ => PinElectricalType::Unspecified,
+    }
+}
+
 fn parse_in_bom(symbol: &mut KicadSymbol, prop_list: &[Sexpr]) {
     symbol.in_bom = prop_list
         .get(1)
@@ -262,7 +275,10 @@ fn parse_property(symbol: &mut KicadSymbol, prop_list: &[Sexpr]) {
 }
 
 fn parse_pin(pin_list: &[Sexpr]) -> Option<KicadPin> {
-    let mut pin = KicadPin::default();
+    let mut pin = KicadPin {
+        electrical_type: parse_electrical_type(pin_list),
+        ..Default::default()
+    };
 
     for item in pin_list {
         if let Sexpr::List(prop_list) = item {
@@ -671,6 +671,10 @@ mod tests {
         assert_eq!(child.pins.len(), 1);
         assert_eq!(child.pins[0].name, "X");
         assert_eq!(child.pins[0].number, "3");
+        assert_eq!(
+            child.pins[0].electrical_type,
+            crate::PinElectricalType::Bidirectional
+        );
     }
 
     #[test]
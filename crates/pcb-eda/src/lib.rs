@@ -38,6 +38,75 @@ pub struct Part {
 pub struct Pin {
     pub name: String,
     pub number: String,
+    pub electrical_type: PinElectricalType,
+}
+
+/// Electrical type of a symbol pin, as used by KiCad's ERC
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PinElectricalType {
+    Input,
+    Output,
+    Bidirectional,
+    TriState,
+    Passive,
+    Free,
+    #[default]
+    Unspecified,
+    PowerIn,
+    PowerOut,
+    OpenCollector,
+    OpenEmitter,
+    NoConnect,
+}
+
+impl PinElectricalType {
+    /// The keyword KiCad uses for this type in `.kicad_sym` files
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            PinElectricalType::Input => "input",
+            PinElectricalType::Output => "output",
+            PinElectricalType::Bidirectional => "bidirectional",
+            PinElectricalType::TriState => "tri_state",
+            PinElectricalType::Passive => "passive",
+            PinElectricalType::Free => "free",
+            PinElectricalType::Unspecified => "unspecified",
+            PinElectricalType::PowerIn => "power_in",
+            PinElectricalType::PowerOut => "power_out",
+            PinElectricalType::OpenCollector => "open_collector",
+            PinElectricalType::OpenEmitter => "open_emitter",
+            PinElectricalType::NoConnect => "no_connect",
+        }
+    }
+}
+
+impl std::fmt::Display for PinElectricalType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for PinElectricalType {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "input" => Ok(PinElectricalType::Input),
+            "output" => Ok(PinElectricalType::Output),
+            "bidirectional" => Ok(PinElectricalType::Bidirectional),
+            "tri_state" => Ok(PinElectricalType::TriState),
+            "passive" => Ok(PinElectricalType::Passive),
+            "free" => Ok(PinElectricalType::Free),
+            "unspecified" => Ok(PinElectricalType::Unspecified),
+            "power_in" => Ok(PinElectricalType::PowerIn),
+            "power_out" => Ok(PinElectricalType::PowerOut),
+            "open_collector" => Ok(PinElectricalType::OpenCollector),
+            "open_emitter" => Ok(PinElectricalType::OpenEmitter),
+            // KiCad 5 and earlier spelled this `unconnected`
+            "no_connect" | "unconnected" => Ok(PinElectricalType::NoConnect),
+            _ => Err(format!("Unknown pin electrical type: '{s}'")),
+        }
+    }
 }
 
 impl Symbol {
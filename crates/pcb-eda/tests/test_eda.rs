@@ -2,7 +2,7 @@ mod test_utils;
 
 use test_utils::setup_symbol;
 
-use pcb_eda::{Part, Symbol};
+use pcb_eda::{Part, PinElectricalType, Symbol};
 use std::collections::HashMap;
 
 fn test_symbol_property(symbol_name: &str, property: impl Fn(&Symbol) -> String, expected: &str) {
@@ -106,6 +106,22 @@ fn test_pcm2903cdb_pin_names() {
     }
 }
 
+#[test]
+fn test_pcm2903cdb_pin_electrical_types() {
+    let symbol = setup_symbol("PCM2903CDB");
+    let type_of = |number: &str| {
+        symbol
+            .pins
+            .iter()
+            .find(|pin| pin.number == number)
+            .map(|pin| pin.electrical_type)
+    };
+
+    assert_eq!(type_of("1"), Some(PinElectricalType::Bidirectional));
+    assert_eq!(type_of("3"), Some(PinElectricalType::PowerIn));
+    assert_eq!(type_of("5"), Some(PinElectricalType::Input));
+}
+
 #[test]
 fn test_pcm2903cdb_manufacturer() {
     test_symbol_option_property(
@@ -0,0 +1,1147 @@
+//! Zener sources for an imported project: one module per sheet file, plus the symbol libraries
+//! the parts are drawn from
+
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use pcb_eda::kicad::symbol_library::KicadSymbolLibrary;
+use pcb_sexpr::Sexpr;
+
+use crate::mapping::{self, Generic, NetType};
+use crate::pcb::Footprint;
+use crate::schematic::{is_power_symbol, Hierarchy, PlacedSymbol};
+use crate::{Netlist, OutputMode};
+
+/// Identifiers a generated file must not bind: Starlark keywords, the builtins the generated code
+/// uses and the keyword arguments every module accepts
+const RESERVED: &[&str] = &[
+    "and",
+    "as",
+    "assert",
+    "break",
+    "class",
+    "continue",
+    "def",
+    "del",
+    "elif",
+    "else",
+    "except",
+    "finally",
+    "for",
+    "from",
+    "global",
+    "if",
+    "import",
+    "in",
+    "is",
+    "lambda",
+    "load",
+    "nonlocal",
+    "not",
+    "or",
+    "pass",
+    "raise",
+    "return",
+    "try",
+    "while",
+    "with",
+    "yield",
+    "None",
+    "True",
+    "False",
+    "Net",
+    "Power",
+    "Ground",
+    "Component",
+    "Symbol",
+    "Module",
+    "io",
+    "config",
+    "name",
+    "properties",
+    "dnp",
+    "schematic",
+];
+
+/// Field names that map onto dedicated `Component()` arguments
+const MPN_FIELDS: &[&str] = &["MPN", "Manufacturer_Part_Number", "Mfr_PN"];
+const MANUFACTURER_FIELDS: &[&str] = &["Manufacturer", "MFR", "Manufacturer_Name"];
+
+pub(crate) struct Output {
+    pub files: BTreeMap<PathBuf, String>,
+    pub netlist: Netlist,
+    pub warnings: Vec<String>,
+}
+
+/// A part placed in one sheet instance, joined with its footprint
+struct Part {
+    instance: usize,
+    /// Symbol of the part (its first unit) in the sheet file
+    symbol: usize,
+    reference: String,
+    footprint: usize,
+}
+
+/// A signal of a library symbol: the pin name, or the number of an unnamed pin
+struct Signal {
+    name: String,
+    pin_name: String,
+    numbers: Vec<String>,
+}
+
+/// Module input, identified across the instances of a sheet by its anchor pad
+struct Io {
+    name: String,
+    net_type: NetType,
+    instances: usize,
+}
+
+/// Names taken in one scope of a generated file
+#[derive(Default)]
+struct Names(HashSet<String>);
+
+impl Names {
+    fn identifiers() -> Self {
+        Names(RESERVED.iter().map(|s| s.to_string()).collect())
+    }
+
+    fn claim(&mut self, base: &str) -> String {
+        let mut name = base.to_string();
+        let mut n = 2;
+        while self.0.contains(&name) {
+            name = format!("{base}_{n}");
+            n += 1;
+        }
+        self.0.insert(name.clone());
+        name
+    }
+}
+
+/// Starlark identifier for a KiCad name, e.g. `+3V3` → `_3V3`
+fn identifier(name: &str, lowercase: bool) -> String {
+    let mut out = String::new();
+    for c in name.chars() {
+        if c.is_ascii_alphanumeric() {
+            out.push(if lowercase { c.to_ascii_lowercase() } else { c });
+        } else if !out.is_empty() && !out.ends_with('_') {
+            out.push('_');
+        }
+    }
+    let out = out.trim_end_matches('_');
+    if out.is_empty() {
+        "net".to_string()
+    } else if out.starts_with(|c: char| c.is_ascii_digit()) {
+        format!("_{out}")
+    } else {
+        out.to_string()
+    }
+}
+
+/// Zener component, module or net name for a KiCad name
+fn zen_name(name: &str) -> String {
+    let out: String = name
+        .chars()
+        .map(|c| {
+            if !c.is_ascii() || c.is_whitespace() || c == '.' || c == '@' {
+                '_'
+            } else {
+                c
+            }
+        })
+        .collect();
+    if out.is_empty() {
+        "_".to_string()
+    } else {
+        out
+    }
+}
+
+/// `power_supply` → `PowerSupply`
+fn pascal_case(name: &str) -> String {
+    let mut out = String::new();
+    for word in name.split(|c: char| !c.is_ascii_alphanumeric()) {
+        let mut chars = word.chars();
+        if let Some(first) = chars.next() {
+            out.push(first.to_ascii_uppercase());
+            out.extend(chars);
+        }
+    }
+    if out.is_empty() || out.starts_with(|c: char| c.is_ascii_digit()) {
+        out.insert_str(0, "Sheet");
+    }
+    out
+}
+
+fn quote(value: &str) -> String {
+    let mut out = String::from("\"");
+    for c in value.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\t' => out.push_str("\\t"),
+            c if c.is_control() => {}
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+/// KiCad generates `Net-(R1-Pad1)` and `unconnected-(R1-Pad1)` for nets the user did not name
+fn is_auto_named(net: &str) -> bool {
+    net.contains("Net-(") || net.contains("unconnected-(")
+}
+
+/// Name of a net without its sheet path
+fn local_name(net: &str) -> &str {
+    if let Some(start) = net.find("Net-(").or_else(|| net.find("unconnected-(")) {
+        return &net[start..];
+    }
+    net.rsplit('/').next().unwrap_or(net)
+}
+
+fn split_lib_id(lib_id: &str) -> (&str, &str) {
+    lib_id.split_once(':').unwrap_or(("Local", lib_id))
+}
+
+/// Signals of each library symbol, by `(library, name)`
+type SymbolSignals = HashMap<(String, String), Vec<Signal>>;
+
+/// Write the library symbols the parts use as `.kicad_sym` libraries, named after the KiCad
+/// library nickname, and read their pins back
+fn symbol_libraries(
+    hierarchy: &Hierarchy,
+    parts: &[Part],
+    files: &mut BTreeMap<PathBuf, String>,
+) -> Result<SymbolSignals> {
+    let mut libraries: BTreeMap<&str, BTreeMap<&str, Sexpr>> = BTreeMap::new();
+    for part in parts {
+        let file = &hierarchy.files[&hierarchy.instances[part.instance].file];
+        let lib_id = &file.symbols[part.symbol].lib_id;
+        let (library, name) = split_lib_id(lib_id);
+        libraries
+            .entry(library)
+            .or_default()
+            .entry(name)
+            .or_insert_with(|| {
+                let mut symbol = file.lib_symbols[lib_id].clone();
+                if let Sexpr::List(items) = &mut symbol {
+                    items[1] = Sexpr::string(name);
+                }
+                symbol
+            });
+    }
+
+    let mut signals = HashMap::new();
+    for (library, symbols) in libraries {
+        let mut items = vec![
+            Sexpr::symbol("kicad_symbol_lib"),
+            Sexpr::list(vec![Sexpr::symbol("version"), Sexpr::Int(20231120)]),
+            Sexpr::list(vec![Sexpr::symbol("generator"), Sexpr::string("pcb")]),
+        ];
+        items.extend(symbols.into_values());
+        let content = pcb_sexpr::format_sexpr(&Sexpr::list(items), 0) + "\n";
+
+        let parsed = KicadSymbolLibrary::from_string(&content)
+            .with_context(|| format!("Failed to read the symbols of library {library}"))?;
+        for symbol in parsed.into_symbols() {
+            let mut symbol_signals: Vec<Signal> = Vec::new();
+            for pin in symbol.pins {
+                let name = if pin.name.is_empty() || pin.name == "~" {
+                    pin.number.clone()
+                } else {
+                    pin.name.clone()
+                };
+                match symbol_signals.iter_mut().find(|s| s.name == name) {
+                    Some(signal) => signal.numbers.push(pin.number),
+                    None => symbol_signals.push(Signal {
+                        name,
+                        pin_name: pin.name,
+                        numbers: vec![pin.number],
+                    }),
+                }
+            }
+            signals.insert((library.to_string(), symbol.name), symbol_signals);
+        }
+        files.insert(
+            PathBuf::from("symbols").join(format!("{library}.kicad_sym")),
+            content,
+        );
+    }
+    Ok(signals)
+}
+
+struct Emitter<'a> {
+    hierarchy: &'a Hierarchy,
+    footprints: &'a [Footprint],
+    mode: OutputMode,
+    parts: Vec<Part>,
+    signals: SymbolSignals,
+    power_names: HashSet<String>,
+    /// Name of each sheet instance in its parent module (empty for the root)
+    instance_names: Vec<String>,
+    /// Name of each part in its module, by sheet file and symbol
+    part_names: HashMap<(&'a Path, usize), String>,
+    /// Module alias of each sheet file other than the root
+    aliases: HashMap<&'a Path, String>,
+    /// Pads of each net, as `(part, pad number)`
+    nets: BTreeMap<&'a str, BTreeSet<(usize, &'a str)>>,
+    /// Nets that cross the boundary of each sheet instance
+    io_nets: Vec<BTreeSet<&'a str>>,
+    ios: HashMap<&'a Path, Vec<Io>>,
+    io_index: HashMap<(&'a Path, (String, String)), usize>,
+    identifiers: HashMap<&'a Path, Names>,
+}
+
+impl<'a> Emitter<'a> {
+    fn symbol(&self, part: &Part) -> &'a PlacedSymbol {
+        let file = &self.hierarchy.files[&self.hierarchy.instances[part.instance].file];
+        &file.symbols[part.symbol]
+    }
+
+    fn part_signals(&self, part: &Part) -> &[Signal] {
+        let (library, name) = split_lib_id(&self.symbol(part).lib_id);
+        self.signals
+            .get(&(library.to_string(), name.to_string()))
+            .map_or(&[], Vec::as_slice)
+    }
+
+    fn part_name(&self, part: &Part) -> &str {
+        let file = self.hierarchy.instances[part.instance].file.as_path();
+        &self.part_names[&(file, part.symbol)]
+    }
+
+    fn is_within(&self, mut instance: usize, ancestor: usize) -> bool {
+        loop {
+            if instance == ancestor {
+                return true;
+            }
+            match self.hierarchy.instances[instance].parent {
+                Some(parent) => instance = parent,
+                None => return false,
+            }
+        }
+    }
+
+    /// Path of a part relative to the module of sheet instance `from`, e.g. `Power.R1`
+    fn part_path(&self, from: usize, part: &Part) -> String {
+        let mut segments = vec![self.part_name(part).to_string()];
+        let mut instance = part.instance;
+        while instance != from {
+            segments.push(self.instance_names[instance].clone());
+            instance = self.hierarchy.instances[instance].parent.unwrap();
+        }
+        segments.reverse();
+        segments.join(".")
+    }
+
+    /// Pad of `net` that identifies it within sheet instance `instance`: the first pad, by part
+    /// path and number, inside the instance
+    fn anchor(&self, instance: usize, net: &str) -> (String, String) {
+        self.nets[net]
+            .iter()
+            .filter(|(part, _)| self.is_within(self.parts[*part].instance, instance))
+            .map(|(part, pad)| {
+                (
+                    self.part_path(instance, &self.parts[*part]),
+                    pad.to_string(),
+                )
+            })
+            .min()
+            .unwrap()
+    }
+
+    fn lowest_common_ancestor(&self, mut a: usize, b: usize) -> usize {
+        while !self.is_within(b, a) {
+            a = self.hierarchy.instances[a].parent.unwrap();
+        }
+        a
+    }
+
+    fn net_type(&self, net: &str) -> NetType {
+        mapping::net_type(local_name(net), &self.power_names)
+    }
+
+    /// Net expression for a net that is not declared, such as an unconnected pin
+    fn inline_net(&self, net: Option<&str>) -> String {
+        match net {
+            Some(net) if !is_auto_named(net) => format!(
+                "{}({})",
+                self.net_type(net).name(),
+                quote(&zen_name(local_name(net)))
+            ),
+            _ => "Net()".to_string(),
+        }
+    }
+
+    fn compute_nets(&mut self) {
+        let hierarchy = self.hierarchy;
+        let footprints = self.footprints;
+        let mut nets: BTreeMap<&'a str, BTreeSet<(usize, &'a str)>> = BTreeMap::new();
+        for (index, part) in self.parts.iter().enumerate() {
+            let numbers: HashSet<&str> = self
+                .part_signals(part)
+                .iter()
+                .flat_map(|s| s.numbers.iter().map(String::as_str))
+                .collect();
+            for pad in &footprints[part.footprint].pads {
+                if let Some(net) = &pad.net {
+                    if numbers.contains(pad.number.as_str()) {
+                        nets.entry(net).or_default().insert((index, &pad.number));
+                    }
+                }
+            }
+        }
+        self.nets = nets;
+
+        // A net belongs to the lowest sheet instance containing all its pads, and is an input of
+        // every instance between that one and its pads
+        let mut io_nets = vec![BTreeSet::new(); hierarchy.instances.len()];
+        for (net, pads) in &self.nets {
+            if pads.len() < 2 {
+                continue;
+            }
+            let instances: BTreeSet<usize> =
+                pads.iter().map(|(p, _)| self.parts[*p].instance).collect();
+            let owner = instances
+                .iter()
+                .copied()
+                .reduce(|a, b| self.lowest_common_ancestor(a, b))
+                .unwrap();
+            for mut instance in instances {
+                while instance != owner {
+                    io_nets[instance].insert(*net);
+                    instance = hierarchy.instances[instance].parent.unwrap();
+                }
+            }
+        }
+        self.io_nets = io_nets;
+
+        // Inputs of each sheet file are the union over its instances, so a sheet used in several
+        // places gets the same signature everywhere
+        for index in 1..hierarchy.instances.len() {
+            let file = hierarchy.instances[index].file.as_path();
+            let mut anchored: Vec<_> = self.io_nets[index]
+                .iter()
+                .map(|net| (self.anchor(index, net), *net))
+                .collect();
+            anchored.sort();
+            for (anchor, net) in anchored {
+                let key = (file, anchor);
+                if let Some(&io) = self.io_index.get(&key) {
+                    self.ios.get_mut(file).unwrap()[io].instances += 1;
+                    continue;
+                }
+                let net_type = self.net_type(net);
+                let name = self
+                    .identifiers
+                    .get_mut(file)
+                    .unwrap()
+                    .claim(&identifier(local_name(net), false));
+                let ios = self.ios.entry(file).or_default();
+                self.io_index.insert(key, ios.len());
+                ios.push(Io {
+                    name,
+                    net_type,
+                    instances: 1,
+                });
+            }
+        }
+    }
+
+    fn io_name(&self, instance: usize, net: &str) -> Option<&str> {
+        let file = self.hierarchy.instances[instance].file.as_path();
+        let io = self.io_index.get(&(file, self.anchor(instance, net)))?;
+        Some(&self.ios[file][*io].name)
+    }
+
+    fn expected_netlist(&self) -> Netlist {
+        let mut netlist = Netlist::default();
+        for part in &self.parts {
+            let numbers: BTreeSet<&str> = self
+                .part_signals(part)
+                .iter()
+                .flat_map(|s| s.numbers.iter().map(String::as_str))
+                .collect();
+            let pads = netlist
+                .components
+                .entry(self.part_path(0, part))
+                .or_default();
+            for pad in &self.footprints[part.footprint].pads {
+                if numbers.contains(pad.number.as_str()) {
+                    let net = pad.net.clone();
+                    let entry = pads.entry(pad.number.clone()).or_insert(None);
+                    if entry.is_none() {
+                        *entry = net;
+                    }
+                }
+            }
+        }
+        netlist
+    }
+
+    /// Source of the module for a sheet file, written from its first instance
+    fn module(&mut self, instance: usize) -> String {
+        let hierarchy = self.hierarchy;
+        let sheet = &hierarchy.instances[instance];
+        let file = sheet.file.as_path();
+        let is_root = instance == 0;
+        let symbols_dir = if is_root { "symbols" } else { "../symbols" };
+        let mut names = self
+            .identifiers
+            .remove(file)
+            .unwrap_or_else(Names::identifiers);
+
+        // Variable of each net this module connects a part or a sub-sheet to
+        let mut relevant: BTreeSet<&str> = self
+            .nets
+            .iter()
+            .filter(|(_, pads)| {
+                pads.len() > 1
+                    && pads
+                        .iter()
+                        .any(|(p, _)| self.parts[*p].instance == instance)
+            })
+            .map(|(net, _)| *net)
+            .collect();
+        for &child in &sheet.children {
+            relevant.extend(self.io_nets[child].iter().copied());
+        }
+
+        let mut vars: HashMap<&str, String> = HashMap::new();
+        let mut declarations = Vec::new();
+        for net in relevant {
+            let declared = if is_root {
+                None
+            } else {
+                self.io_name(instance, net)
+            };
+            match declared {
+                Some(io) => {
+                    vars.insert(net, io.to_string());
+                }
+                None => {
+                    let var = names.claim(&identifier(local_name(net), true));
+                    let value = if is_auto_named(net) {
+                        format!("{}()", self.net_type(net).name())
+                    } else {
+                        self.inline_net(Some(net))
+                    };
+                    declarations.push(format!("{var} = {value}"));
+                    vars.insert(net, var);
+                }
+            }
+        }
+
+        let mut used_types: BTreeSet<&str> = BTreeSet::new();
+        let mut used_generics: BTreeSet<Generic> = BTreeSet::new();
+        let mut body = String::new();
+        let mut positions = BTreeMap::new();
+
+        let io_lines: Vec<String> = self
+            .ios
+            .get(file)
+            .map(|ios| {
+                let instances = self
+                    .hierarchy
+                    .instances
+                    .iter()
+                    .filter(|i| i.file == file)
+                    .count();
+                ios.iter()
+                    .map(|io| {
+                        let type_name = io.net_type.name();
+                        if io.net_type != NetType::Net {
+                            used_types.insert(type_name);
+                        }
+                        if io.instances < instances {
+                            format!(
+                                "{0} = io({1}, {2}, default={2}({1}))",
+                                io.name,
+                                quote(&io.name),
+                                type_name
+                            )
+                        } else {
+                            format!("{} = io({}, {})", io.name, quote(&io.name), type_name)
+                        }
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        for line in &declarations {
+            for type_name in ["Power", "Ground"] {
+                if line.contains(&format!(" = {type_name}(")) {
+                    used_types.insert(type_name);
+                }
+            }
+        }
+
+        for part in self.parts.iter().filter(|p| p.instance == instance) {
+            let symbol = self.symbol(part);
+            let name = self.part_name(part).to_string();
+            let footprint = &self.footprints[part.footprint];
+            let dnp = symbol.dnp || footprint.dnp;
+            let skip_bom = !symbol.in_bom || footprint.exclude_from_bom;
+
+            let mut pins: Vec<(&Signal, String)> = Vec::new();
+            for signal in self.part_signals(part) {
+                let net = signal.numbers.iter().find_map(|number| {
+                    footprint
+                        .pads
+                        .iter()
+                        .find(|pad| &pad.number == number)
+                        .and_then(|pad| pad.net.as_deref())
+                });
+                let value = match net.and_then(|net| vars.get(net)) {
+                    Some(var) => var.clone(),
+                    None => self.inline_net(net),
+                };
+                if value.starts_with("Power(") || value.starts_with("Ground(") {
+                    used_types.insert(if value.starts_with("Power(") {
+                        "Power"
+                    } else {
+                        "Ground"
+                    });
+                }
+                pins.push((signal, value));
+            }
+
+            let generic = (self.mode == OutputMode::Idiomatic && !skip_bom)
+                .then(|| idiomatic(symbol, footprint, &pins))
+                .flatten();
+            match generic {
+                Some((generic, args)) => {
+                    used_generics.insert(generic);
+                    let _ = writeln!(body, "\n{}(", generic.name());
+                    let _ = writeln!(body, "    name={},", quote(&name));
+                    for (key, value) in args {
+                        let _ = writeln!(body, "    {key}={value},");
+                    }
+                    if dnp {
+                        let _ = writeln!(body, "    dnp=True,");
+                    }
+                    if let Some(mpn) = field(symbol, MPN_FIELDS) {
+                        let _ = writeln!(body, "    properties={{\"mpn\": {}}},", quote(mpn));
+                    }
+                    let _ = writeln!(body, ")");
+                }
+                None => {
+                    let (library, symbol_name) = split_lib_id(&symbol.lib_id);
+                    let _ = writeln!(body, "\nComponent(");
+                    let _ = writeln!(body, "    name={},", quote(&name));
+                    let _ = writeln!(
+                        body,
+                        "    symbol=Symbol(library={}, name={}),",
+                        quote(&format!("{symbols_dir}/{library}.kicad_sym")),
+                        quote(symbol_name)
+                    );
+                    let _ = writeln!(body, "    footprint={},", quote(&footprint.footprint));
+                    let _ = writeln!(body, "    pins={{");
+                    for (signal, value) in &pins {
+                        let _ = writeln!(body, "        {}: {value},", quote(&signal.name));
+                    }
+                    let _ = writeln!(body, "    }},");
+                    if let Some(mpn) = field(symbol, MPN_FIELDS) {
+                        let _ = writeln!(body, "    mpn={},", quote(mpn));
+                    }
+                    if let Some(manufacturer) = field(symbol, MANUFACTURER_FIELDS) {
+                        let _ = writeln!(body, "    manufacturer={},", quote(manufacturer));
+                    }
+                    for (key, names) in [
+                        ("datasheet", &["Datasheet"][..]),
+                        ("description", &["Description"][..]),
+                    ] {
+                        if let Some(value) = field(symbol, names) {
+                            let _ = writeln!(body, "    {key}={},", quote(value));
+                        }
+                    }
+                    let properties: Vec<(String, &str)> = symbol
+                        .fields
+                        .iter()
+                        .filter(|(key, value)| {
+                            !value.is_empty()
+                                && value != "~"
+                                && !["Footprint", "Datasheet", "Description"]
+                                    .iter()
+                                    .chain(MPN_FIELDS)
+                                    .chain(MANUFACTURER_FIELDS)
+                                    .any(|k| k.eq_ignore_ascii_case(key))
+                        })
+                        .map(|(key, value)| {
+                            let key = if key == "Value" {
+                                "value".to_string()
+                            } else {
+                                key.clone()
+                            };
+                            (key, value.as_str())
+                        })
+                        .collect();
+                    if !properties.is_empty() {
+                        let _ = writeln!(body, "    properties={{");
+                        for (key, value) in properties {
+                            let _ = writeln!(body, "        {}: {},", quote(&key), quote(value));
+                        }
+                        let _ = writeln!(body, "    }},");
+                    }
+                    if dnp {
+                        let _ = writeln!(body, "    dnp=True,");
+                    }
+                    if skip_bom {
+                        let _ = writeln!(body, "    skip_bom=True,");
+                    }
+                    let _ = writeln!(body, ")");
+                }
+            }
+            positions.insert(name, symbol.at);
+        }
+
+        for &child in &sheet.children {
+            let child_file = self.hierarchy.instances[child].file.as_path();
+            let mut args: Vec<(String, &str)> = self.io_nets[child]
+                .iter()
+                .filter_map(|net| {
+                    Some((
+                        self.io_name(child, net)?.to_string(),
+                        vars.get(net)?.as_str(),
+                    ))
+                })
+                .collect();
+            args.sort();
+            let _ = writeln!(body, "\n{}(", self.aliases[child_file]);
+            let _ = writeln!(body, "    name={},", quote(&self.instance_names[child]));
+            for (key, value) in args {
+                let _ = writeln!(body, "    {key}={value},");
+            }
+            let _ = writeln!(body, ")");
+        }
+
+        let mut out = String::new();
+        let _ = writeln!(out, "\"\"\"\nImported from {}\n\"\"\"", file.display());
+        if !used_types.is_empty() {
+            let types: Vec<String> = used_types.iter().map(|t| quote(t)).collect();
+            let _ = writeln!(
+                out,
+                "\nload(\"@stdlib/interfaces.zen\", {})",
+                types.join(", ")
+            );
+        }
+        let mut modules = Vec::new();
+        for generic in &used_generics {
+            modules.push(format!(
+                "{} = Module({})",
+                generic.name(),
+                quote(&generic.module_path())
+            ));
+        }
+        let mut children: Vec<&Path> = sheet
+            .children
+            .iter()
+            .map(|&c| self.hierarchy.instances[c].file.as_path())
+            .collect();
+        children.sort();
+        children.dedup();
+        for child in children {
+            let alias = &self.aliases[child];
+            let path = if is_root {
+                format!("modules/{alias}.zen")
+            } else {
+                format!("{alias}.zen")
+            };
+            modules.push(format!("{alias} = Module({})", quote(&path)));
+        }
+        for section in [&modules, &io_lines, &declarations] {
+            if !section.is_empty() {
+                out.push('\n');
+                for line in section {
+                    let _ = writeln!(out, "{line}");
+                }
+            }
+        }
+        out.push_str(&body);
+
+        if !positions.is_empty() {
+            out.push('\n');
+            for (name, (x, y, rotation)) in positions {
+                let _ = writeln!(out, "# pcb:sch {name} x={x:.4} y={y:.4} rot={rotation:.0}");
+            }
+        }
+        out
+    }
+}
+
+fn field<'s>(symbol: &'s PlacedSymbol, names: &[&str]) -> Option<&'s str> {
+    names.iter().find_map(|name| symbol.field(name))
+}
+
+/// The stdlib generic and its arguments for a part, if it is one of the plain chip parts the
+/// generics cover
+fn idiomatic(
+    symbol: &PlacedSymbol,
+    footprint: &Footprint,
+    pins: &[(&Signal, String)],
+) -> Option<(Generic, Vec<(&'static str, String)>)> {
+    let generic = mapping::generic_for(&symbol.lib_id)?;
+    let package = mapping::package(&footprint.footprint, generic)?;
+    let value = symbol.field("Value")?;
+    if pins.len() != generic.pin_parameters().len() {
+        return None;
+    }
+
+    let mut args = Vec::new();
+    match generic {
+        Generic::Led => args.push(("color", quote(mapping::led_color(value)?))),
+        _ => args.push(("value", quote(&mapping::parse_value(value, generic)?))),
+    }
+    args.push(("package", quote(&package)));
+    for (pin, parameter) in generic.pin_parameters() {
+        let (_, net) = pins.iter().find(|(signal, _)| {
+            if generic.pins_by_name() {
+                signal.pin_name == *pin
+            } else {
+                signal.numbers.iter().any(|n| n == *pin)
+            }
+        })?;
+        args.push((*parameter, net.clone()));
+    }
+    Some((generic, args))
+}
+
+/// Zener sources for the sheet hierarchy of a project, with `footprints` giving connectivity
+pub(crate) fn emit(
+    project: &str,
+    hierarchy: &Hierarchy,
+    footprints: &[Footprint],
+    mode: OutputMode,
+) -> Result<Output> {
+    let mut warnings = Vec::new();
+    let by_path: HashMap<&str, usize> = footprints
+        .iter()
+        .enumerate()
+        .filter_map(|(i, f)| Some((f.path.as_deref()?, i)))
+        .collect();
+    let mut placed = vec![false; footprints.len()];
+    let mut power_names = HashSet::new();
+    let mut parts = Vec::new();
+
+    for (index, instance) in hierarchy.instances.iter().enumerate() {
+        let file = &hierarchy.files[&instance.file];
+
+        // Units of a multi-unit part share a reference
+        let mut units: Vec<(String, Vec<usize>)> = Vec::new();
+        for (i, symbol) in file.symbols.iter().enumerate() {
+            let reference = hierarchy.reference(symbol, &instance.path);
+            match units.iter_mut().find(|(r, _)| *r == reference) {
+                Some((_, symbols)) => symbols.push(i),
+                None => units.push((reference, vec![i])),
+            }
+        }
+
+        for (reference, symbols) in units {
+            let first = &file.symbols[symbols[0]];
+            let lib_symbol = file.lib_symbols.get(&first.lib_id);
+            if reference.starts_with('#') || lib_symbol.is_some_and(is_power_symbol) {
+                if let Some(value) = first.field("Value") {
+                    power_names.insert(value.to_string());
+                }
+                continue;
+            }
+            let footprint = symbols.iter().find_map(|&i| {
+                let path = format!("{}/{}", instance.path, file.symbols[i].uuid);
+                by_path.get(path.as_str()).copied()
+            });
+            let Some(footprint) = footprint else {
+                if first.on_board {
+                    warnings.push(format!(
+                        "{reference} in {} has no footprint on the board and was skipped",
+                        instance.file.display()
+                    ));
+                }
+                continue;
+            };
+            if lib_symbol.is_none() {
+                warnings.push(format!(
+                    "{reference} in {} uses {}, which is missing from the schematic's library \
+                     symbols, and was skipped",
+                    instance.file.display(),
+                    first.lib_id
+                ));
+                continue;
+            }
+            placed[footprint] = true;
+            parts.push(Part {
+                instance: index,
+                symbol: symbols[0],
+                reference,
+                footprint,
+            });
+        }
+    }
+    for (footprint, placed) in footprints.iter().zip(&placed) {
+        if !placed {
+            warnings.push(format!(
+                "Footprint {} is not in the schematic and was skipped",
+                footprint.reference
+            ));
+        }
+    }
+
+    let mut files = BTreeMap::new();
+    let signals = symbol_libraries(hierarchy, &parts, &mut files)?;
+
+    // Module aliases are global, so every file can refer to any generic or sheet
+    let mut globals = Names::identifiers();
+    for generic in [
+        Generic::Resistor,
+        Generic::Capacitor,
+        Generic::Inductor,
+        Generic::Led,
+    ] {
+        globals.claim(generic.name());
+    }
+    let mut aliases = HashMap::new();
+    for instance in &hierarchy.instances[1..] {
+        if !aliases.contains_key(instance.file.as_path()) {
+            let stem = instance
+                .file
+                .file_stem()
+                .map(|s| s.to_string_lossy().to_string())
+                .unwrap_or_default();
+            aliases.insert(instance.file.as_path(), globals.claim(&pascal_case(&stem)));
+        }
+    }
+
+    // Sub-sheets and parts share one namespace per sheet file
+    let mut sheet_names: HashMap<&Path, Vec<String>> = HashMap::new();
+    let mut part_names = HashMap::new();
+    let mut scopes: HashMap<&Path, Names> = HashMap::new();
+    for instance in &hierarchy.instances {
+        let file = instance.file.as_path();
+        if scopes.contains_key(file) {
+            continue;
+        }
+        let scope = scopes.entry(file).or_default();
+        let names = hierarchy.files[file]
+            .sheets
+            .iter()
+            .map(|sheet| scope.claim(&zen_name(&sheet.name)))
+            .collect();
+        sheet_names.insert(file, names);
+    }
+    for part in &parts {
+        let file = hierarchy.instances[part.instance].file.as_path();
+        let scope = scopes.get_mut(file).unwrap();
+        part_names
+            .entry((file, part.symbol))
+            .or_insert_with(|| scope.claim(&zen_name(&part.reference)));
+    }
+    let instance_names = (0..hierarchy.instances.len())
+        .map(|index| match hierarchy.instances[index].parent {
+            Some(parent) => {
+                let parent = &hierarchy.instances[parent];
+                let position = parent.children.iter().position(|&c| c == index).unwrap();
+                sheet_names[parent.file.as_path()][position].clone()
+            }
+            None => String::new(),
+        })
+        .collect();
+
+    let identifiers = aliases
+        .keys()
+        .map(|&file| (file, Names(globals.0.clone())))
+        .chain([(
+            hierarchy.instances[0].file.as_path(),
+            Names(globals.0.clone()),
+        )])
+        .collect();
+    let mut emitter = Emitter {
+        hierarchy,
+        footprints,
+        mode,
+        parts,
+        signals,
+        power_names,
+        instance_names,
+        part_names,
+        aliases,
+        nets: BTreeMap::new(),
+        io_nets: Vec::new(),
+        ios: HashMap::new(),
+        io_index: HashMap::new(),
+        identifiers,
+    };
+    emitter.compute_nets();
+    let netlist = emitter.expected_netlist();
+
+    let mut emitted = HashSet::new();
+    for (index, instance) in hierarchy.instances.iter().enumerate() {
+        if !emitted.insert(instance.file.as_path()) {
+            continue;
+        }
+        let source = emitter.module(index);
+        let path = if index == 0 {
+            PathBuf::from(format!("{project}.zen"))
+        } else {
+            PathBuf::from("modules")
+                .join(format!("{}.zen", emitter.aliases[instance.file.as_path()]))
+        };
+        files.insert(path, source);
+    }
+    files.insert(
+        PathBuf::from("pcb.toml"),
+        format!(
+            "[board]\nname = {}\npath = {}\n",
+            quote(project),
+            quote(&format!("{project}.zen"))
+        ),
+    );
+
+    Ok(Output {
+        files,
+        netlist,
+        warnings,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pcb::parse_pcb;
+
+    const ROOT: &str = r##"(kicad_sch (uuid "root")
+        (lib_symbols
+            (symbol "Device:R" (in_bom yes) (on_board yes)
+                (property "Reference" "R")
+                (symbol "R_1_1"
+                    (pin passive line (at 0 3.81 270) (length 1.27) (name "~") (number "1"))
+                    (pin passive line (at 0 -3.81 90) (length 1.27) (name "~") (number "2"))))
+            (symbol "power:GND" (power) (in_bom yes)
+                (property "Reference" "#PWR")
+                (symbol "GND_0_1" (pin power_in line (at 0 0 0) (length 0) (name "GND") (number "1")))))
+        (symbol (lib_id "Device:R") (at 50.8 76.2 90) (unit 1) (in_bom no) (dnp yes) (uuid "r1")
+            (property "Reference" "R1") (property "Value" "10k") (property "Footprint" "")
+            (instances (project "demo" (path "/root" (reference "R1")))))
+        (symbol (lib_id "power:GND") (at 0 0 0) (unit 1) (uuid "pwr1")
+            (property "Reference" "#PWR01") (property "Value" "GND"))
+        (sheet (at 0 0) (uuid "s1") (property "Sheetname" "Left") (property "Sheetfile" "divider.kicad_sch"))
+        (sheet (at 0 0) (uuid "s2") (property "Sheetname" "Right") (property "Sheetfile" "divider.kicad_sch")))"##;
+
+    const DIVIDER: &str = r#"(kicad_sch (uuid "div")
+        (lib_symbols
+            (symbol "Device:R" (in_bom yes) (on_board yes)
+                (property "Reference" "R")
+                (symbol "R_1_1"
+                    (pin passive line (at 0 3.81 270) (length 1.27) (name "~") (number "1"))
+                    (pin passive line (at 0 -3.81 90) (length 1.27) (name "~") (number "2")))))
+        (symbol (lib_id "Device:R") (at 10 20 0) (unit 1) (uuid "top")
+            (property "Reference" "R10") (property "Value" "4k7")
+            (instances (project "demo"
+                (path "/root/s1" (reference "R10"))
+                (path "/root/s2" (reference "R20")))))
+        (symbol (lib_id "Device:R") (at 10 30 0) (unit 1) (uuid "bottom")
+            (property "Reference" "R11") (property "Value" "1k")
+            (instances (project "demo"
+                (path "/root/s1" (reference "R11"))
+                (path "/root/s2" (reference "R21"))))))"#;
+
+    const PCB: &str = r#"(kicad_pcb
+        (footprint "Resistor_SMD:R_0402_1005Metric" (property "Reference" "R1") (path "/r1")
+            (attr smd)
+            (pad "1" smd roundrect (net "VIN")) (pad "2" smd roundrect (net "/MID")))
+        (footprint "Resistor_SMD:R_0402_1005Metric" (property "Reference" "R10") (path "/s1/top")
+            (pad "1" smd roundrect (net "/MID")) (pad "2" smd roundrect (net "/Left/OUT")))
+        (footprint "Resistor_SMD:R_0402_1005Metric" (property "Reference" "R11") (path "/s1/bottom")
+            (pad "1" smd roundrect (net "/Left/OUT")) (pad "2" smd roundrect (net "GND")))
+        (footprint "Resistor_SMD:R_0402_1005Metric" (property "Reference" "R20") (path "/s2/top")
+            (pad "1" smd roundrect (net "/MID")) (pad "2" smd roundrect (net "/Right/OUT")))
+        (footprint "Resistor_SMD:R_0402_1005Metric" (property "Reference" "R21") (path "/s2/bottom")
+            (pad "1" smd roundrect (net "/Right/OUT")) (pad "2" smd roundrect (net "GND")))
+        (footprint "TestPoint:TestPoint_Pad_D1.0mm" (property "Reference" "TP1") (path "/gone")
+            (pad "1" smd circle (net "GND"))))"#;
+
+    fn import(mode: OutputMode) -> Output {
+        let hierarchy = Hierarchy::load_with(Path::new("demo.kicad_sch"), |file| {
+            Ok(match file.to_str().unwrap() {
+                "demo.kicad_sch" => ROOT,
+                "divider.kicad_sch" => DIVIDER,
+                other => anyhow::bail!("unexpected {other}"),
+            }
+            .to_string())
+        })
+        .unwrap();
+        emit("demo", &hierarchy, &parse_pcb(PCB).unwrap(), mode).unwrap()
+    }
+
+    #[test]
+    fn emits_a_module_per_sheet_file() {
+        let output = import(OutputMode::Faithful);
+        assert_eq!(
+            output.files.keys().collect::<Vec<_>>(),
+            vec![
+                Path::new("demo.zen"),
+                Path::new("modules/Divider.zen"),
+                Path::new("pcb.toml"),
+                Path::new("symbols/Device.kicad_sym"),
+            ]
+        );
+        assert_eq!(
+            output.warnings,
+            vec!["Footprint TP1 is not in the schematic and was skipped"]
+        );
+
+        let root = &output.files[Path::new("demo.zen")];
+        assert!(root.contains("load(\"@stdlib/interfaces.zen\", \"Ground\")"));
+        assert!(root.contains("Divider = Module(\"modules/Divider.zen\")"));
+        assert!(root.contains("gnd = Ground(\"GND\")"));
+        assert!(root.contains("mid = Net(\"MID\")"));
+        assert!(root.contains("\"1\": Net(\"VIN\"),"));
+        assert!(root.contains("dnp=True,\n    skip_bom=True,"));
+        assert!(root.contains("name=\"Left\",\n    GND=gnd,\n    MID=mid,"));
+        assert!(root.contains("# pcb:sch R1 x=50.8000 y=76.2000 rot=90"));
+
+        let divider = &output.files[Path::new("modules/Divider.zen")];
+        assert!(divider.contains("GND = io(\"GND\", Ground)"));
+        assert!(divider.contains("MID = io(\"MID\", Net)"));
+        assert!(divider.contains("out = Net(\"OUT\")"));
+        assert!(divider.contains("library=\"../symbols/Device.kicad_sym\", name=\"R\""));
+        assert!(divider.contains("\"value\": \"4k7\""));
+        assert!(divider.contains("# pcb:sch R10 x=10.0000 y=20.0000 rot=0"));
+
+        let library = &output.files[Path::new("symbols/Device.kicad_sym")];
+        assert!(library.contains("\"R_1_1\""));
+        assert!(!library.contains("Device:R") && !library.contains("GND"));
+
+        let netlist = &output.netlist.components;
+        assert_eq!(
+            netlist.keys().collect::<Vec<_>>(),
+            vec!["Left.R10", "Left.R11", "R1", "Right.R10", "Right.R11"]
+        );
+        assert_eq!(netlist["Right.R11"]["1"].as_deref(), Some("/Right/OUT"));
+    }
+
+    #[test]
+    fn uses_generics_in_idiomatic_mode() {
+        let output = import(OutputMode::Idiomatic);
+        let divider = &output.files[Path::new("modules/Divider.zen")];
+        assert!(divider.contains("Resistor = Module(\"@stdlib/generics/Resistor.zen\")"));
+        assert!(divider.contains(
+            "Resistor(\n    name=\"R10\",\n    value=\"4.7kOhm\",\n    package=\"0402\",\n    P1=MID,\n    P2=out,\n)"
+        ));
+
+        // Parts left out of the BOM have no generic equivalent
+        let root = &output.files[Path::new("demo.zen")];
+        assert!(root.contains("Component(\n    name=\"R1\""));
+    }
+
+    #[test]
+    fn names() {
+        assert_eq!(identifier("+3V3", false), "_3V3");
+        assert_eq!(identifier("Net-(R1-Pad1)", true), "net_r1_pad1");
+        assert_eq!(zen_name("U 1.a"), "U_1_a");
+        assert_eq!(pascal_case("power_supply"), "PowerSupply");
+        assert_eq!(local_name("/Left/Net-(R1-Pad1/A)"), "Net-(R1-Pad1/A)");
+        assert_eq!(quote("a\"b"), "\"a\\\"b\"");
+    }
+}
@@ -0,0 +1,296 @@
+//! Import of KiCad projects as Zener modules.
+//!
+//! Connectivity comes from the `.kicad_pcb` (pad nets are authoritative), part metadata from the
+//! `.kicad_sch` hierarchy. Each sheet file becomes a module; nets crossing a sheet boundary become
+//! its `io()`s.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Context, Result};
+
+mod emit;
+pub mod mapping;
+mod pcb;
+mod schematic;
+
+use schematic::Hierarchy;
+
+/// How parts are written out
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OutputMode {
+    /// Every part as a `Component()` with the project's own symbol and footprint
+    #[default]
+    Faithful,
+    /// Plain chip resistors, capacitors, inductors and LEDs as stdlib generics
+    Idiomatic,
+}
+
+/// Pad connectivity by component path (`Sheet.R1`), with `None` for unconnected pads
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Netlist {
+    pub components: BTreeMap<String, BTreeMap<String, Option<String>>>,
+}
+
+/// Zener sources generated for a KiCad project
+#[derive(Debug)]
+pub struct ImportedProject {
+    /// Project name, from the `.kicad_pro` file name
+    pub name: String,
+    /// Generated files, relative to the output directory
+    pub files: BTreeMap<PathBuf, String>,
+    /// The root `.zen` file, relative to the output directory
+    pub root: PathBuf,
+    /// Connectivity of the original board, to compare the rebuilt design against
+    pub netlist: Netlist,
+    pub warnings: Vec<String>,
+}
+
+/// Directory and name of the project at `path`: a project directory or any of its
+/// `.kicad_pro`, `.kicad_sch` or `.kicad_pcb` files
+fn locate_project(path: &Path) -> Result<(PathBuf, String)> {
+    if path.is_dir() {
+        let mut projects: Vec<PathBuf> = fs::read_dir(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?
+            .filter_map(|entry| Some(entry.ok()?.path()))
+            .filter(|p| p.extension().is_some_and(|e| e == "kicad_pro"))
+            .collect();
+        projects.sort();
+        return match projects.as_slice() {
+            [project] => locate_project(project),
+            [] => bail!("No .kicad_pro file in {}", path.display()),
+            _ => bail!(
+                "Several .kicad_pro files in {}; pass the one to import",
+                path.display()
+            ),
+        };
+    }
+
+    let is_kicad = path
+        .extension()
+        .is_some_and(|e| e == "kicad_pro" || e == "kicad_sch" || e == "kicad_pcb");
+    let (Some(stem), true) = (path.file_stem(), is_kicad) else {
+        bail!(
+            "{} is not a KiCad project, schematic or board",
+            path.display()
+        );
+    };
+    let dir = path.parent().map(Path::to_path_buf).unwrap_or_default();
+    Ok((dir, stem.to_string_lossy().to_string()))
+}
+
+/// Convert the KiCad project at `path` into Zener sources
+pub fn import_project(path: &Path, mode: OutputMode) -> Result<ImportedProject> {
+    let (dir, name) = locate_project(path)?;
+
+    let root = PathBuf::from(format!("{name}.kicad_sch"));
+    if !dir.join(&root).exists() {
+        bail!("Schematic {} not found", dir.join(&root).display());
+    }
+    let hierarchy = Hierarchy::load(&dir, &root)?;
+
+    let board = dir.join(format!("{name}.kicad_pcb"));
+    let content = fs::read_to_string(&board).with_context(|| {
+        format!(
+            "Failed to read {}; the board is needed for connectivity",
+            board.display()
+        )
+    })?;
+    let footprints =
+        pcb::parse_pcb(&content).with_context(|| format!("Failed to parse {}", board.display()))?;
+
+    let output = emit::emit(&name, &hierarchy, &footprints, mode)?;
+    Ok(ImportedProject {
+        root: PathBuf::from(format!("{name}.zen")),
+        name,
+        files: output.files,
+        netlist: output.netlist,
+        warnings: output.warnings,
+    })
+}
+
+type Member<'a> = (&'a str, &'a str);
+
+/// Nets with at least two pads, as sets of `(component, pad)`
+fn connections<'a>(
+    components: impl Iterator<Item = (&'a str, &'a BTreeMap<String, Option<String>>)>,
+) -> BTreeMap<&'a str, BTreeSet<Member<'a>>> {
+    let mut nets: BTreeMap<&str, BTreeSet<Member>> = BTreeMap::new();
+    for (component, pads) in components {
+        for (pad, net) in pads {
+            if let Some(net) = net {
+                nets.entry(net).or_default().insert((component, pad));
+            }
+        }
+    }
+    nets.retain(|_, members| members.len() > 1);
+    nets
+}
+
+fn describe(members: &BTreeSet<Member>) -> String {
+    members
+        .iter()
+        .map(|(component, pad)| format!("{component}.{pad}"))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Differences between the connectivity of the original board and of the rebuilt design.
+///
+/// Components of `actual` are matched to `expected` by path; a stdlib generic matches the part it
+/// wraps (`R1.R` → `R1`). Pads of one component that were unconnected in the original may share a
+/// net, since Zener joins pins of the same name.
+pub fn compare_netlists(expected: &Netlist, actual: &Netlist) -> Vec<String> {
+    let mut differences = Vec::new();
+
+    let mut matched: BTreeMap<&str, BTreeMap<String, Option<String>>> = BTreeMap::new();
+    for (path, pads) in &actual.components {
+        let target = if expected.components.contains_key(path) {
+            Some(path.as_str())
+        } else {
+            expected
+                .components
+                .keys()
+                .filter(|e| path.starts_with(&format!("{e}.")))
+                .max_by_key(|e| e.len())
+                .map(String::as_str)
+        };
+        let Some(target) = target else {
+            differences.push(format!("Unexpected component {path}"));
+            continue;
+        };
+        // Pads the original footprint does not have cannot be compared
+        let known = &expected.components[target];
+        let pads = pads
+            .iter()
+            .filter(|(pad, _)| known.contains_key(*pad))
+            .map(|(pad, net)| (pad.clone(), net.clone()))
+            .collect();
+        if matched.insert(target, pads).is_some() {
+            differences.push(format!("Several components were built for {target}"));
+        }
+    }
+    for path in expected.components.keys() {
+        if !matched.contains_key(path.as_str()) {
+            differences.push(format!("Missing component {path}"));
+        }
+    }
+
+    let expected_nets = connections(
+        expected
+            .components
+            .iter()
+            .filter(|(path, _)| matched.contains_key(path.as_str()))
+            .map(|(path, pads)| (path.as_str(), pads)),
+    );
+    let actual_nets = connections(matched.iter().map(|(path, pads)| (*path, pads)));
+
+    let expected_groups: BTreeSet<&BTreeSet<Member>> = expected_nets.values().collect();
+    let actual_groups: BTreeSet<&BTreeSet<Member>> = actual_nets.values().collect();
+    let connected: BTreeSet<&Member> = expected_nets.values().flatten().collect();
+
+    for (net, members) in &expected_nets {
+        if !actual_groups.contains(members) {
+            differences.push(format!(
+                "Net {net} ({}) is not connected the same way",
+                describe(members)
+            ));
+        }
+    }
+    for (net, members) in &actual_nets {
+        if expected_groups.contains(members) {
+            continue;
+        }
+        let one_component = members
+            .iter()
+            .all(|(c, _)| *c == members.first().unwrap().0);
+        if one_component && members.iter().all(|m| !connected.contains(m)) {
+            continue;
+        }
+        differences.push(format!(
+            "Net {net} connects {}, which are not connected that way in the original",
+            describe(members)
+        ));
+    }
+    differences
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    type Pads<'a> = &'a [(&'a str, Option<&'a str>)];
+
+    fn netlist(components: &[(&str, Pads)]) -> Netlist {
+        Netlist {
+            components: components
+                .iter()
+                .map(|(path, pads)| {
+                    let pads = pads
+                        .iter()
+                        .map(|(pad, net)| (pad.to_string(), net.map(str::to_string)))
+                        .collect();
+                    (path.to_string(), pads)
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn compares_connectivity() {
+        let expected = netlist(&[
+            ("R1", &[("1", Some("VCC")), ("2", Some("/OUT"))]),
+            ("Sub.R2", &[("1", Some("/OUT")), ("2", Some("GND"))]),
+            ("U1", &[("1", Some("VCC")), ("2", None), ("3", None)]),
+        ]);
+
+        // Generics wrap the part, nets are renamed and unconnected pins of one part are joined
+        let actual = netlist(&[
+            ("R1.R", &[("1", Some("VCC")), ("2", Some("OUT"))]),
+            ("Sub.R2", &[("1", Some("OUT")), ("2", Some("GND"))]),
+            (
+                "U1",
+                &[("1", Some("VCC")), ("2", Some("NC")), ("3", Some("NC"))],
+            ),
+        ]);
+        assert!(compare_netlists(&expected, &actual).is_empty());
+
+        let actual = netlist(&[
+            ("R1", &[("1", Some("VCC")), ("2", Some("GND"))]),
+            ("Sub.R2", &[("1", Some("OUT")), ("2", Some("GND"))]),
+            ("U2", &[("1", Some("VCC"))]),
+        ]);
+        assert_eq!(
+            compare_netlists(&expected, &actual),
+            vec![
+                "Unexpected component U2",
+                "Missing component U1",
+                "Net /OUT (R1.2, Sub.R2.1) is not connected the same way",
+                "Net GND connects R1.2, Sub.R2.2, which are not connected that way in the original",
+            ]
+        );
+    }
+
+    #[test]
+    fn imports_the_bom_fixture() {
+        let project = Path::new(env!("CARGO_MANIFEST_DIR")).join("../pcb-sch/test/kicad-bom");
+        let imported = import_project(&project, OutputMode::Idiomatic).unwrap();
+
+        assert_eq!(imported.name, "layout");
+        assert_eq!(imported.root, Path::new("layout.zen"));
+        assert_eq!(
+            imported.netlist.components.keys().collect::<Vec<_>>(),
+            vec!["R1", "R2", "R3"]
+        );
+
+        let root = &imported.files[Path::new("layout.zen")];
+        // R1's value is an MPN, so it stays a Component
+        assert!(root.contains("Component(\n    name=\"R1\""));
+        assert!(root.contains("footprint=\"Resistor_SMD:R_0402_1005Metric\""));
+        assert!(root.contains("dnp=True"));
+        assert!(root.contains("skip_bom=True"));
+        assert!(root.contains("# pcb:sch R1 "));
+        assert!(imported.files[Path::new("pcb.toml")].contains("path = \"layout.zen\""));
+    }
+}
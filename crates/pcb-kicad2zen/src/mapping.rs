@@ -0,0 +1,304 @@
+//! Mapping of KiCad parts and nets onto Zener: stdlib generics, packages, values and net types
+
+use std::collections::HashSet;
+
+/// stdlib generic a KiCad part is imported as in idiomatic mode
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Generic {
+    Resistor,
+    Capacitor,
+    Inductor,
+    Led,
+}
+
+impl Generic {
+    pub fn name(self) -> &'static str {
+        match self {
+            Generic::Resistor => "Resistor",
+            Generic::Capacitor => "Capacitor",
+            Generic::Inductor => "Inductor",
+            Generic::Led => "Led",
+        }
+    }
+
+    pub fn module_path(self) -> String {
+        format!("@stdlib/generics/{}.zen", self.name())
+    }
+
+    /// Footprint name prefix of the KiCad standard library packages for this part
+    fn footprint_prefix(self) -> &'static str {
+        match self {
+            Generic::Resistor => "R",
+            Generic::Capacitor => "C",
+            Generic::Inductor => "L",
+            Generic::Led => "LED",
+        }
+    }
+
+    /// Unit the value of this part is written in
+    fn unit(self) -> Option<&'static str> {
+        match self {
+            Generic::Resistor => Some("Ohm"),
+            Generic::Capacitor => Some("F"),
+            Generic::Inductor => Some("H"),
+            Generic::Led => None,
+        }
+    }
+
+    /// Generic parameter of each pin, by KiCad pin number (passives) or name (LEDs)
+    pub fn pin_parameters(self) -> &'static [(&'static str, &'static str)] {
+        match self {
+            Generic::Led => &[("A", "A"), ("K", "K")],
+            _ => &[("1", "P1"), ("2", "P2")],
+        }
+    }
+
+    /// Whether pins are matched by name rather than number
+    pub fn pins_by_name(self) -> bool {
+        self == Generic::Led
+    }
+}
+
+/// Generic for a KiCad `lib_id`, e.g. `Device:R_Small` → `Resistor`
+pub fn generic_for(lib_id: &str) -> Option<Generic> {
+    let (library, name) = lib_id.split_once(':')?;
+    if library != "Device" {
+        return None;
+    }
+    match name {
+        "R" | "R_Small" | "R_US" | "R_Small_US" => Some(Generic::Resistor),
+        "C" | "C_Small" => Some(Generic::Capacitor),
+        "L" | "L_Small" => Some(Generic::Inductor),
+        "LED" | "LED_Small" => Some(Generic::Led),
+        _ => None,
+    }
+}
+
+/// Package of a KiCad standard library chip footprint, e.g. `Resistor_SMD:R_0402_1005Metric` →
+/// `0402`
+pub fn package(footprint: &str, generic: Generic) -> Option<String> {
+    let name = footprint
+        .rsplit_once(':')
+        .map_or(footprint, |(_, name)| name);
+    let mut parts = name.split('_');
+    if parts.next()? != generic.footprint_prefix() {
+        return None;
+    }
+    let package = parts.next()?;
+    let metric = parts.next()?;
+    (package.len() == 4
+        && package.chars().all(|c| c.is_ascii_digit())
+        && metric.ends_with("Metric"))
+    .then(|| package.to_string())
+}
+
+/// SI prefix of a value written in KiCad shorthand (`k` in `4k7`); `R` stands for the decimal
+/// point of resistances
+fn prefix(c: char, generic: Generic) -> Option<&'static str> {
+    Some(match c {
+        'p' => "p",
+        'n' => "n",
+        'u' | 'µ' | 'μ' => "u",
+        'm' => "m",
+        'k' | 'K' => "k",
+        'M' => "M",
+        'G' => "G",
+        'R' | 'r' if generic == Generic::Resistor => "",
+        _ => return None,
+    })
+}
+
+/// Zener value for a KiCad `Value` field, e.g. `4k7` → `4.7kOhm`, `100n` → `100nF`. A tolerance
+/// such as `1%` is kept. Returns `None` for values that are not a quantity, such as MPNs.
+pub fn parse_value(value: &str, generic: Generic) -> Option<String> {
+    let unit = generic.unit()?;
+    let mut tokens = value.split_whitespace();
+    let quantity = tokens.next()?.split('/').next()?;
+    let tolerance = tokens.find(|t| t.ends_with('%'));
+
+    let lower = quantity.to_lowercase();
+    let unit_suffixes: &[&str] = match generic {
+        Generic::Resistor => &["ohms", "ohm", "ω"],
+        Generic::Capacitor => &["f"],
+        _ => &["h"],
+    };
+    let stripped = unit_suffixes
+        .iter()
+        .find(|suffix| lower.ends_with(*suffix))
+        .map_or(quantity, |suffix| {
+            // Byte offset of the suffix in the original string
+            let chars = suffix.chars().count();
+            let cut = quantity
+                .char_indices()
+                .rev()
+                .nth(chars - 1)
+                .map_or(0, |(i, _)| i);
+            &quantity[..cut]
+        });
+
+    let split = stripped
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(stripped.len());
+    let (mantissa, rest) = stripped.split_at(split);
+    if mantissa.is_empty() || mantissa.parse::<f64>().is_err() {
+        return None;
+    }
+    let mut rest = rest.chars();
+    let (number, prefix) = match rest.next() {
+        None => (mantissa.to_string(), ""),
+        Some(c) => {
+            let prefix = prefix(c, generic)?;
+            let fraction: String = rest.collect();
+            if !fraction.chars().all(|c| c.is_ascii_digit()) {
+                return None;
+            }
+            if fraction.is_empty() {
+                (mantissa.to_string(), prefix)
+            } else if mantissa.contains('.') {
+                return None;
+            } else {
+                (format!("{mantissa}.{fraction}"), prefix)
+            }
+        }
+    };
+
+    let mut out = format!("{number}{prefix}{unit}");
+    if let Some(tolerance) = tolerance {
+        out.push(' ');
+        out.push_str(tolerance);
+    }
+    Some(out)
+}
+
+/// LED colour named in a `Value` field such as `LED_RED` or `Green`
+pub fn led_color(value: &str) -> Option<&'static str> {
+    let value = value.to_lowercase();
+    ["red", "green", "blue", "yellow", "orange", "white"]
+        .into_iter()
+        .find(|color| value.contains(color))
+}
+
+/// Zener type a net is declared with
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum NetType {
+    Net,
+    Power,
+    Ground,
+}
+
+impl NetType {
+    pub fn name(self) -> &'static str {
+        match self {
+            NetType::Net => "Net",
+            NetType::Power => "Power",
+            NetType::Ground => "Ground",
+        }
+    }
+}
+
+/// Net type inferred from a net name, or from the name of a power symbol on the net
+pub fn net_type(name: &str, power_symbols: &HashSet<String>) -> NetType {
+    let local = name.rsplit('/').next().unwrap_or(name);
+    let upper = local.to_uppercase();
+    let bare = upper.trim_start_matches(['+', '-']);
+
+    if bare.starts_with("GND") || bare.ends_with("GND") || bare.starts_with("VSS") {
+        return NetType::Ground;
+    }
+    // Rails such as 3V3, +5V, 12V or V1V8
+    let rail = bare.strip_prefix('V').unwrap_or(bare);
+    let is_rail = rail.starts_with(|c: char| c.is_ascii_digit())
+        && rail.contains('V')
+        && rail
+            .chars()
+            .all(|c| c.is_ascii_digit() || c == 'V' || c == '.');
+    if power_symbols.contains(local)
+        || is_rail
+        || ["VCC", "VDD", "VBUS", "VBAT"]
+            .iter()
+            .any(|p| bare.starts_with(p))
+        || upper.ends_with("_PWR")
+    {
+        return NetType::Power;
+    }
+    NetType::Net
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn maps_lib_ids_and_packages() {
+        assert_eq!(generic_for("Device:R_Small"), Some(Generic::Resistor));
+        assert_eq!(generic_for("Device:LED"), Some(Generic::Led));
+        assert_eq!(generic_for("Device:C_Polarized"), None);
+        assert_eq!(generic_for("MCU:STM32"), None);
+
+        assert_eq!(
+            package("Resistor_SMD:R_0402_1005Metric", Generic::Resistor),
+            Some("0402".into())
+        );
+        assert_eq!(
+            package("LED_SMD:LED_0805_2012Metric", Generic::Led),
+            Some("0805".into())
+        );
+        assert_eq!(
+            package("Capacitor_SMD:C_0603_1608Metric", Generic::Resistor),
+            None
+        );
+        assert_eq!(
+            package("Resistor_THT:R_Axial_DIN0207", Generic::Resistor),
+            None
+        );
+    }
+
+    #[test]
+    fn parses_values() {
+        let r = |v| parse_value(v, Generic::Resistor);
+        assert_eq!(r("10k").as_deref(), Some("10kOhm"));
+        assert_eq!(r("4K7").as_deref(), Some("4.7kOhm"));
+        assert_eq!(r("1M").as_deref(), Some("1MOhm"));
+        assert_eq!(r("4R7").as_deref(), Some("4.7Ohm"));
+        assert_eq!(r("0R").as_deref(), Some("0Ohm"));
+        assert_eq!(r("100").as_deref(), Some("100Ohm"));
+        assert_eq!(r("10kΩ 1%").as_deref(), Some("10kOhm 1%"));
+        assert_eq!(r("ERJ-2RKF1003X"), None);
+
+        let c = |v| parse_value(v, Generic::Capacitor);
+        assert_eq!(c("100n").as_deref(), Some("100nF"));
+        assert_eq!(c("10uF").as_deref(), Some("10uF"));
+        assert_eq!(c("4n7").as_deref(), Some("4.7nF"));
+        assert_eq!(c("100nF/50V").as_deref(), Some("100nF"));
+        assert_eq!(c("1.5.2"), None);
+
+        assert_eq!(
+            parse_value("10u", Generic::Inductor).as_deref(),
+            Some("10uH")
+        );
+        assert_eq!(led_color("LED_GREEN"), Some("green"));
+    }
+
+    #[test]
+    fn infers_net_types() {
+        let power: HashSet<String> = ["VMOT".to_string()].into();
+        for name in ["GND", "/power/AGND", "GNDA", "VSS"] {
+            assert_eq!(net_type(name, &power), NetType::Ground, "{name}");
+        }
+        for name in [
+            "+3V3",
+            "3V3",
+            "+5V",
+            "-12V",
+            "VCC",
+            "/mcu/VDDA",
+            "VMOT",
+            "USB_PWR",
+        ] {
+            assert_eq!(net_type(name, &power), NetType::Power, "{name}");
+        }
+        for name in ["SDA", "/mcu/VSENSE", "Net-(R1-Pad1)", "V"] {
+            assert_eq!(net_type(name, &power), NetType::Net, "{name}");
+        }
+    }
+}
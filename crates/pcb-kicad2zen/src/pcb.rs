@@ -0,0 +1,152 @@
+//! `.kicad_pcb` footprints: their schematic paths, flags and pad nets
+
+use std::collections::HashMap;
+
+use anyhow::{bail, Result};
+use pcb_sexpr::Sexpr;
+
+use crate::schematic::string;
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Pad {
+    pub number: String,
+    pub net: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct Footprint {
+    /// Library footprint, e.g. `Resistor_SMD:R_0402_1005Metric`
+    pub footprint: String,
+    pub reference: String,
+    /// Schematic symbol path, `/<sheet uuids>/<symbol uuid>`
+    pub path: Option<String>,
+    pub pads: Vec<Pad>,
+    pub dnp: bool,
+    pub exclude_from_bom: bool,
+}
+
+fn reference(items: &[Sexpr]) -> Option<String> {
+    items.iter().find_map(|item| {
+        let list = item.as_list()?;
+        match (list.first()?.as_sym()?, list.get(1)?.as_atom()?) {
+            // KiCad 8 and later
+            ("property", "Reference") => list.get(2)?.as_atom().map(str::to_string),
+            // KiCad 7 and earlier
+            ("fp_text", "reference") => list.get(2)?.as_atom().map(str::to_string),
+            _ => None,
+        }
+    })
+}
+
+/// The footprints of a board
+pub(crate) fn parse_pcb(content: &str) -> Result<Vec<Footprint>> {
+    let pcb = pcb_sexpr::parse(content)?;
+    let Some(items) = pcb.as_list() else {
+        bail!("Not a KiCad board");
+    };
+    if items.first().and_then(Sexpr::as_sym) != Some("kicad_pcb") {
+        bail!("Not a KiCad board");
+    }
+
+    // Boards before KiCad 10 refer to nets by number through a top-level net table
+    let net_table: HashMap<i64, &str> = pcb
+        .find_all_lists("net")
+        .into_iter()
+        .filter_map(|net| Some((net.get(1)?.as_int()?, net.get(2)?.as_atom()?)))
+        .collect();
+
+    let mut footprints = Vec::new();
+    for footprint in pcb.find_all_lists("footprint") {
+        let Some(name) = footprint.get(1).and_then(Sexpr::as_atom) else {
+            continue;
+        };
+        let attr = footprint.iter().find_map(|item| {
+            let list = item.as_list()?;
+            (list.first()?.as_sym() == Some("attr")).then_some(list)
+        });
+        let has_attr =
+            |name: &str| attr.is_some_and(|a| a.iter().any(|i| i.as_sym() == Some(name)));
+
+        let pads = footprint
+            .iter()
+            .filter_map(|item| {
+                let pad = item.as_list()?;
+                if pad.first()?.as_sym() != Some("pad") {
+                    return None;
+                }
+                let number = pad.get(1)?.as_atom()?.to_string();
+                let net = pad.iter().find_map(|item| {
+                    let list = item.as_list()?;
+                    if list.first()?.as_sym() != Some("net") {
+                        return None;
+                    }
+                    match list.get(1)? {
+                        Sexpr::Int(code) => net_table.get(code).map(|n| n.to_string()),
+                        other => other.as_atom().map(str::to_string),
+                    }
+                });
+                Some(Pad {
+                    number,
+                    net: net.filter(|n| !n.is_empty()),
+                })
+            })
+            .collect();
+
+        footprints.push(Footprint {
+            footprint: name.to_string(),
+            reference: reference(footprint).unwrap_or_default(),
+            path: string(footprint, "path"),
+            pads,
+            dnp: has_attr("dnp"),
+            exclude_from_bom: has_attr("exclude_from_bom"),
+        });
+    }
+    Ok(footprints)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_footprints_in_both_net_formats() {
+        let footprints = parse_pcb(
+            r#"(kicad_pcb
+                (net 0 "")
+                (net 1 "/VCC")
+                (footprint "Resistor_SMD:R_0402_1005Metric" (layer "F.Cu")
+                    (property "Reference" "R1")
+                    (path "/a/b")
+                    (attr smd dnp exclude_from_bom)
+                    (pad "1" smd roundrect (net 1 "/VCC"))
+                    (pad "2" smd roundrect (net 0 "")))
+                (footprint "Connector:TP" (layer "F.Cu")
+                    (fp_text reference "TP1")
+                    (pad "1" smd circle (net "GND"))))"#,
+        )
+        .unwrap();
+
+        let r1 = &footprints[0];
+        assert_eq!(r1.reference, "R1");
+        assert_eq!(r1.path.as_deref(), Some("/a/b"));
+        assert!(r1.dnp && r1.exclude_from_bom);
+        assert_eq!(
+            r1.pads,
+            vec![
+                Pad {
+                    number: "1".into(),
+                    net: Some("/VCC".into())
+                },
+                Pad {
+                    number: "2".into(),
+                    net: None
+                },
+            ]
+        );
+
+        let tp1 = &footprints[1];
+        assert_eq!(tp1.reference, "TP1");
+        assert_eq!(tp1.pads[0].net.as_deref(), Some("GND"));
+        assert!(!tp1.dnp && tp1.path.is_none());
+    }
+}
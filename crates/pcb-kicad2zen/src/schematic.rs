@@ -0,0 +1,367 @@
+//! `.kicad_sch` hierarchy: sheet instances, placed symbols and the embedded library symbols
+
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Context, Result};
+use pcb_sexpr::Sexpr;
+
+/// A symbol placed on a sheet
+#[derive(Debug, Clone)]
+pub(crate) struct PlacedSymbol {
+    pub lib_id: String,
+    pub uuid: String,
+    /// `Reference` field, used when the symbol has no entry for a sheet instance
+    pub reference: String,
+    /// Reference by full instance path (`/<root uuid>/<sheet uuid>...`)
+    pub instance_references: HashMap<String, String>,
+    /// Fields other than `Reference`, in file order
+    pub fields: Vec<(String, String)>,
+    pub at: (f64, f64, f64),
+    pub dnp: bool,
+    pub in_bom: bool,
+    pub on_board: bool,
+}
+
+impl PlacedSymbol {
+    pub fn field(&self, name: &str) -> Option<&str> {
+        self.fields
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+            .filter(|value| !value.is_empty() && *value != "~")
+    }
+}
+
+/// A sub-sheet placed on a sheet
+#[derive(Debug, Clone)]
+pub(crate) struct SheetRef {
+    pub uuid: String,
+    pub name: String,
+    /// Sheet file, relative to the directory of the parent sheet
+    pub file: String,
+}
+
+#[derive(Debug, Default)]
+pub(crate) struct SchematicFile {
+    pub uuid: String,
+    pub symbols: Vec<PlacedSymbol>,
+    pub sheets: Vec<SheetRef>,
+    /// Embedded library symbols by `lib_id`
+    pub lib_symbols: BTreeMap<String, Sexpr>,
+    /// KiCad 6 keeps symbol references in the root sheet, by `/<sheet uuids>/<symbol uuid>`
+    pub symbol_instances: HashMap<String, String>,
+}
+
+/// One use of a sheet file in the hierarchy
+#[derive(Debug, Clone)]
+pub(crate) struct SheetInstance {
+    /// Sheet UUIDs from the root, as `/<uuid>/<uuid>` (empty for the root sheet)
+    pub path: String,
+    /// Sheet file relative to the project directory
+    pub file: PathBuf,
+    pub parent: Option<usize>,
+    pub children: Vec<usize>,
+}
+
+#[derive(Debug)]
+pub(crate) struct Hierarchy {
+    pub files: BTreeMap<PathBuf, SchematicFile>,
+    /// Sheet instances, root first, each sheet before its sub-sheets
+    pub instances: Vec<SheetInstance>,
+}
+
+impl Hierarchy {
+    /// Load the sheet tree rooted at `root` (a `.kicad_sch` file in `project_dir`)
+    pub fn load(project_dir: &Path, root: &Path) -> Result<Self> {
+        Self::load_with(root, |file| {
+            let path = project_dir.join(file);
+            fs::read_to_string(&path).with_context(|| format!("Failed to read {}", path.display()))
+        })
+    }
+
+    /// Load the sheet tree rooted at `root`, reading sheet files (relative to the project
+    /// directory) through `read`
+    pub fn load_with(root: &Path, read: impl Fn(&Path) -> Result<String>) -> Result<Self> {
+        let mut hierarchy = Hierarchy {
+            files: BTreeMap::new(),
+            instances: Vec::new(),
+        };
+        hierarchy.visit(&read, root.to_path_buf(), String::new(), None)?;
+        Ok(hierarchy)
+    }
+
+    fn visit(
+        &mut self,
+        read: &impl Fn(&Path) -> Result<String>,
+        file: PathBuf,
+        path: String,
+        parent: Option<usize>,
+    ) -> Result<()> {
+        // A sheet that contains itself would recurse forever
+        let mut ancestor = parent;
+        while let Some(index) = ancestor {
+            if self.instances[index].file == file {
+                bail!("Sheet {} is recursive", file.display());
+            }
+            ancestor = self.instances[index].parent;
+        }
+
+        if !self.files.contains_key(&file) {
+            let parsed = parse_schematic(&read(&file)?)
+                .with_context(|| format!("Failed to parse {}", file.display()))?;
+            self.files.insert(file.clone(), parsed);
+        }
+
+        let index = self.instances.len();
+        self.instances.push(SheetInstance {
+            path: path.clone(),
+            file: file.clone(),
+            parent,
+            children: Vec::new(),
+        });
+        if let Some(parent) = parent {
+            self.instances[parent].children.push(index);
+        }
+
+        let dir = file.parent().map(Path::to_path_buf).unwrap_or_default();
+        for sheet in self.files[&file].sheets.clone() {
+            self.visit(
+                read,
+                normalize(&dir.join(&sheet.file)),
+                format!("{path}/{}", sheet.uuid),
+                Some(index),
+            )?;
+        }
+        Ok(())
+    }
+
+    pub fn root_file(&self) -> &SchematicFile {
+        &self.files[&self.instances[0].file]
+    }
+
+    /// Reference of `symbol` in the sheet instance at `instance_path`
+    pub fn reference(&self, symbol: &PlacedSymbol, instance_path: &str) -> String {
+        let root = self.root_file();
+        symbol
+            .instance_references
+            .get(&format!("/{}{instance_path}", root.uuid))
+            .or_else(|| {
+                root.symbol_instances
+                    .get(&format!("{instance_path}/{}", symbol.uuid))
+            })
+            .cloned()
+            .unwrap_or_else(|| symbol.reference.clone())
+    }
+}
+
+/// Remove `.` and `..` components so one sheet file always has the same key
+fn normalize(path: &Path) -> PathBuf {
+    let mut out = PathBuf::new();
+    for component in path.components() {
+        match component {
+            std::path::Component::CurDir => {}
+            std::path::Component::ParentDir => {
+                if !out.pop() {
+                    out.push("..");
+                }
+            }
+            other => out.push(other),
+        }
+    }
+    out
+}
+
+fn child<'a>(items: &'a [Sexpr], name: &str) -> Option<&'a [Sexpr]> {
+    items.iter().find_map(|item| {
+        let list = item.as_list()?;
+        (list.first()?.as_sym() == Some(name)).then_some(list)
+    })
+}
+
+fn children<'a>(items: &'a [Sexpr], name: &'a str) -> impl Iterator<Item = &'a [Sexpr]> + 'a {
+    items.iter().filter_map(move |item| {
+        let list = item.as_list()?;
+        (list.first()?.as_sym() == Some(name)).then_some(list)
+    })
+}
+
+/// Value of `(name value)`; a bare `(name)` counts as set
+pub(crate) fn flag(items: &[Sexpr], name: &str) -> Option<bool> {
+    let list = child(items, name)?;
+    Some(
+        list.get(1)
+            .and_then(Sexpr::as_atom)
+            .is_none_or(|v| v == "yes"),
+    )
+}
+
+pub(crate) fn string(items: &[Sexpr], name: &str) -> Option<String> {
+    child(items, name)?.get(1)?.as_atom().map(str::to_string)
+}
+
+fn number(item: &Sexpr) -> Option<f64> {
+    item.as_float().or_else(|| item.as_int().map(|n| n as f64))
+}
+
+pub(crate) fn at(items: &[Sexpr]) -> (f64, f64, f64) {
+    let Some(at) = child(items, "at") else {
+        return (0.0, 0.0, 0.0);
+    };
+    let value = |i: usize| at.get(i).and_then(number).unwrap_or(0.0);
+    (value(1), value(2), value(3))
+}
+
+fn properties(items: &[Sexpr]) -> Vec<(String, String)> {
+    children(items, "property")
+        .filter_map(|p| {
+            Some((
+                p.get(1)?.as_atom()?.to_string(),
+                p.get(2)?.as_atom()?.to_string(),
+            ))
+        })
+        .collect()
+}
+
+fn parse_symbol(items: &[Sexpr]) -> Option<PlacedSymbol> {
+    let mut fields = properties(items);
+    let reference = fields
+        .iter()
+        .position(|(key, _)| key == "Reference")
+        .map(|i| fields.remove(i).1)
+        .unwrap_or_default();
+
+    let mut instance_references = HashMap::new();
+    if let Some(instances) = child(items, "instances") {
+        for project in children(instances, "project") {
+            for path in children(project, "path") {
+                if let (Some(key), Some(reference)) = (
+                    path.get(1).and_then(Sexpr::as_atom),
+                    string(path, "reference"),
+                ) {
+                    instance_references.insert(key.to_string(), reference);
+                }
+            }
+        }
+    }
+
+    Some(PlacedSymbol {
+        lib_id: string(items, "lib_id")?,
+        uuid: string(items, "uuid").unwrap_or_default(),
+        reference,
+        instance_references,
+        fields,
+        at: at(items),
+        dnp: flag(items, "dnp").unwrap_or(false),
+        in_bom: flag(items, "in_bom").unwrap_or(true),
+        on_board: flag(items, "on_board").unwrap_or(true),
+    })
+}
+
+fn parse_sheet(items: &[Sexpr]) -> Option<SheetRef> {
+    let fields = properties(items);
+    let field = |names: &[&str]| {
+        fields
+            .iter()
+            .find(|(key, _)| names.contains(&key.as_str()))
+            .map(|(_, value)| value.clone())
+    };
+    Some(SheetRef {
+        uuid: string(items, "uuid")?,
+        name: field(&["Sheetname", "Sheet name"]).unwrap_or_default(),
+        file: field(&["Sheetfile", "Sheet file"])?,
+    })
+}
+
+pub(crate) fn parse_schematic(content: &str) -> Result<SchematicFile> {
+    let sexpr = pcb_sexpr::parse(content)?;
+    let Some(items) = sexpr.as_list() else {
+        bail!("Not a KiCad schematic");
+    };
+    if items.first().and_then(Sexpr::as_sym) != Some("kicad_sch") {
+        bail!("Not a KiCad schematic");
+    }
+
+    let mut file = SchematicFile {
+        uuid: string(items, "uuid").unwrap_or_default(),
+        ..Default::default()
+    };
+    if let Some(lib_symbols) = child(items, "lib_symbols") {
+        for symbol in children(lib_symbols, "symbol") {
+            if let Some(lib_id) = symbol.get(1).and_then(Sexpr::as_atom) {
+                file.lib_symbols
+                    .insert(lib_id.to_string(), Sexpr::List(symbol.to_vec()));
+            }
+        }
+    }
+    file.symbols = children(items, "symbol").filter_map(parse_symbol).collect();
+    file.sheets = children(items, "sheet").filter_map(parse_sheet).collect();
+    if let Some(instances) = child(items, "symbol_instances") {
+        for path in children(instances, "path") {
+            if let (Some(key), Some(reference)) = (
+                path.get(1).and_then(Sexpr::as_atom),
+                string(path, "reference"),
+            ) {
+                file.symbol_instances.insert(key.to_string(), reference);
+            }
+        }
+    }
+    Ok(file)
+}
+
+/// Whether the library symbol is a power symbol (a global net label, not a part)
+pub(crate) fn is_power_symbol(lib_symbol: &Sexpr) -> bool {
+    lib_symbol
+        .as_list()
+        .is_some_and(|items| child(items, "power").is_some())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SHEET: &str = r#"(kicad_sch
+        (uuid "root")
+        (lib_symbols
+            (symbol "Device:R" (in_bom yes)
+                (symbol "R_1_1" (pin passive line (at 0 3.81 270) (name "~") (number "1"))))
+            (symbol "power:GND" (power) (in_bom yes)))
+        (symbol (lib_id "Device:R") (at 101.6 107.95 90) (unit 1) (in_bom no) (dnp yes)
+            (uuid "r1")
+            (property "Reference" "R1") (property "Value" "10k") (property "Datasheet" "~")
+            (instances (project "demo" (path "/root/s1" (reference "R7")))))
+        (sheet (at 0 0) (uuid "s1")
+            (property "Sheetname" "Power") (property "Sheetfile" "power.kicad_sch")))"#;
+
+    #[test]
+    fn parses_symbols_sheets_and_library() {
+        let file = parse_schematic(SHEET).unwrap();
+        assert_eq!(file.uuid, "root");
+        assert_eq!(
+            file.lib_symbols.keys().collect::<Vec<_>>(),
+            vec!["Device:R", "power:GND"]
+        );
+        assert!(is_power_symbol(&file.lib_symbols["power:GND"]));
+        assert!(!is_power_symbol(&file.lib_symbols["Device:R"]));
+
+        let r1 = &file.symbols[0];
+        assert_eq!(r1.reference, "R1");
+        assert_eq!(r1.at, (101.6, 107.95, 90.0));
+        assert!(r1.dnp && !r1.in_bom && r1.on_board);
+        assert_eq!(r1.field("value"), Some("10k"));
+        assert_eq!(r1.field("Datasheet"), None);
+        assert_eq!(r1.instance_references["/root/s1"], "R7");
+
+        assert_eq!(file.sheets[0].name, "Power");
+        assert_eq!(file.sheets[0].file, "power.kicad_sch");
+    }
+
+    #[test]
+    fn normalizes_sheet_paths() {
+        assert_eq!(
+            normalize(Path::new("sub/./../power.kicad_sch")),
+            PathBuf::from("power.kicad_sch")
+        );
+    }
+}
@@ -0,0 +1,302 @@
+//! KiCad custom design rules (`.kicad_dru`) generated from typed design rules
+//!
+//! The generated rules live between two marker comments, so rules written by hand in the same
+//! file are kept across syncs.
+
+use std::fs;
+use std::path::Path;
+
+use anyhow::Context;
+use log::{debug, info, warn};
+use pcb_sch::design_rules::{DesignRule, RuleKind, RuleScope};
+use pcb_zen_core::lang::impedance::LayerGeometry;
+
+use crate::LayoutError;
+
+const BEGIN_MARKER: &str = "# BEGIN pcb design rules (generated, do not edit)";
+const END_MARKER: &str = "# END pcb design rules";
+
+/// Rule expression matching the nets of `scope` on side `item` (`A` or `B`) of a check. KiCad
+/// has no escape for `'` in these strings, so names must not contain quotes (design rules reject
+/// them when validated).
+fn condition(scope: &RuleScope, item: char) -> String {
+    match scope {
+        RuleScope::NetClass(class) => format!("{item}.NetClass == '{class}'"),
+        RuleScope::Nets(nets) => {
+            let terms: Vec<_> = nets
+                .iter()
+                .map(|net| format!("{item}.NetName == '{net}'"))
+                .collect();
+            if terms.len() == 1 {
+                terms.into_iter().next().unwrap()
+            } else {
+                format!("({})", terms.join(" || "))
+            }
+        }
+    }
+}
+
+fn rule_block(name: &str, layer: Option<&str>, constraints: &[String], condition: &str) -> String {
+    let mut block = format!("(rule \"{}\"\n", name.replace('"', "'"));
+    if let Some(layer) = layer {
+        block.push_str(&format!("\t(layer \"{layer}\")\n"));
+    }
+    for constraint in constraints {
+        block.push_str(&format!("\t(constraint {constraint})\n"));
+    }
+    block.push_str(&format!("\t(condition \"{condition}\"))\n"));
+    block
+}
+
+/// The rules as `.kicad_dru` S-expressions
+pub(crate) fn render_rules(rules: &[DesignRule]) -> String {
+    let mut out = String::new();
+    for rule in rules {
+        let a = condition(&rule.scope, 'A');
+        let block = match &rule.kind {
+            RuleKind::Clearance { min, against } => {
+                let condition = match against {
+                    Some(against) => format!("{a} && {}", condition(against, 'B')),
+                    None => a,
+                };
+                rule_block(
+                    &rule.name,
+                    None,
+                    &[format!("clearance (min {min}mm)")],
+                    &condition,
+                )
+            }
+            RuleKind::MaxLength { max } => {
+                rule_block(&rule.name, None, &[format!("length (max {max}mm)")], &a)
+            }
+            RuleKind::LengthMatch { tolerance: max } | RuleKind::Skew { max } => {
+                rule_block(&rule.name, None, &[format!("skew (max {max}mm)")], &a)
+            }
+            RuleKind::MaxVias { max } => {
+                rule_block(&rule.name, None, &[format!("via_count (max {max})")], &a)
+            }
+            // A rule has at most one layer clause, so emit one rule per layer
+            RuleKind::Keepout { layers } => layers
+                .iter()
+                .map(|layer| {
+                    rule_block(
+                        &format!("{} {layer}", rule.name),
+                        Some(layer),
+                        &["disallow track via zone".to_string()],
+                        &a,
+                    )
+                })
+                .collect(),
+        };
+        out.push_str(&block);
+        out.push('\n');
+    }
+    out
+}
+
+/// Per-layer netclass widths (and pair gaps) reaching the netclass impedance targets
+pub(crate) fn render_layer_geometry(geometry: &[LayerGeometry]) -> String {
+    let mut out = String::new();
+    for g in geometry {
+        if g.netclass.contains(['\'', '"']) {
+            warn!(
+                "Skipping the impedance widths of netclass '{}': KiCad rules cannot match a name containing a quote",
+                g.netclass
+            );
+            continue;
+        }
+        let mut constraints = vec![format!("track_width (opt {}mm)", g.track_width)];
+        if let Some(gap) = g.diff_pair_gap {
+            constraints.push(format!("diff_pair_gap (opt {gap}mm)"));
+        }
+        out.push_str(&rule_block(
+            &format!("{} {}", g.netclass, g.layer),
+            Some(&g.layer),
+            &constraints,
+            &condition(&RuleScope::NetClass(g.netclass.clone()), 'A'),
+        ));
+        out.push('\n');
+    }
+    out
+}
+
+/// Replace the generated section of `existing` (a `.kicad_dru` file, possibly empty) with
+/// `rules` and the per-layer impedance `geometry`, keeping everything else
+pub(crate) fn merge_rules(
+    existing: &str,
+    rules: &[DesignRule],
+    geometry: &[LayerGeometry],
+) -> String {
+    let mut kept = existing.to_string();
+    if let Some(start) = kept.find(BEGIN_MARKER) {
+        let end = kept[start..]
+            .find(END_MARKER)
+            .map(|i| start + i + END_MARKER.len())
+            .unwrap_or(kept.len());
+        let end = kept[end..]
+            .find(|c: char| c != '\n')
+            .map_or(kept.len(), |i| end + i);
+        kept.replace_range(start..end, "");
+    }
+
+    let mut out = kept.trim_end().to_string();
+    if out.is_empty() {
+        out.push_str("(version 1)");
+    }
+    out.push('\n');
+    if !rules.is_empty() || !geometry.is_empty() {
+        out.push('\n');
+        out.push_str(BEGIN_MARKER);
+        out.push('\n');
+        out.push_str(&render_rules(rules));
+        out.push_str(&render_layer_geometry(geometry));
+        out.push_str(END_MARKER);
+        out.push('\n');
+    }
+    out
+}
+
+/// Write `rules` and the per-layer impedance `geometry` into the `.kicad_dru` file next to
+/// `pcb_path`
+pub(crate) fn patch_custom_rules(
+    pcb_path: &Path,
+    rules: &[DesignRule],
+    geometry: &[LayerGeometry],
+) -> Result<(), LayoutError> {
+    let dru_path = pcb_path.with_extension("kicad_dru");
+    let existing = if dru_path.exists() {
+        fs::read_to_string(&dru_path)
+            .with_context(|| format!("Failed to read {}", dru_path.display()))?
+    } else if rules.is_empty() && geometry.is_empty() {
+        debug!("No design rules to write");
+        return Ok(());
+    } else {
+        String::new()
+    };
+
+    let updated = merge_rules(&existing, rules, geometry);
+    if updated == existing {
+        return Ok(());
+    }
+    info!(
+        "Writing {} design rules and {} impedance widths to {}",
+        rules.len(),
+        geometry.len(),
+        dru_path.display()
+    );
+    fs::write(&dru_path, updated)
+        .with_context(|| format!("Failed to write {}", dru_path.display()))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn nets(names: &[&str]) -> RuleScope {
+        RuleScope::Nets(names.iter().map(|n| n.to_string()).collect())
+    }
+
+    #[test]
+    fn renders_each_rule_kind() {
+        let rules = vec![
+            DesignRule::new(
+                "hv_gap",
+                RuleScope::NetClass("HV".into()),
+                RuleKind::Clearance {
+                    min: 2.0,
+                    against: Some(RuleScope::NetClass("Default".into())),
+                },
+            )
+            .unwrap(),
+            DesignRule::new(
+                "usb.match",
+                nets(&["USB_D_P", "USB_D_N"]),
+                RuleKind::LengthMatch { tolerance: 0.1 },
+            )
+            .unwrap(),
+            DesignRule::new("clk_vias", nets(&["CLK"]), RuleKind::MaxVias { max: 2 }).unwrap(),
+            DesignRule::new(
+                "rf",
+                nets(&["ANT"]),
+                RuleKind::Keepout {
+                    layers: vec!["In1.Cu".into(), "B.Cu".into()],
+                },
+            )
+            .unwrap(),
+        ];
+        assert_eq!(
+            render_rules(&rules),
+            "(rule \"hv_gap\"\n\
+             \t(constraint clearance (min 2mm))\n\
+             \t(condition \"A.NetClass == 'HV' && B.NetClass == 'Default'\"))\n\
+             \n\
+             (rule \"usb.match\"\n\
+             \t(constraint skew (max 0.1mm))\n\
+             \t(condition \"(A.NetName == 'USB_D_P' || A.NetName == 'USB_D_N')\"))\n\
+             \n\
+             (rule \"clk_vias\"\n\
+             \t(constraint via_count (max 2))\n\
+             \t(condition \"A.NetName == 'CLK'\"))\n\
+             \n\
+             (rule \"rf In1.Cu\"\n\
+             \t(layer \"In1.Cu\")\n\
+             \t(constraint disallow track via zone)\n\
+             \t(condition \"A.NetName == 'ANT'\"))\n\
+             (rule \"rf B.Cu\"\n\
+             \t(layer \"B.Cu\")\n\
+             \t(constraint disallow track via zone)\n\
+             \t(condition \"A.NetName == 'ANT'\"))\n\
+             \n"
+        );
+    }
+
+    #[test]
+    fn merge_keeps_hand_written_rules() {
+        let rule =
+            DesignRule::new("len", nets(&["CLK"]), RuleKind::MaxLength { max: 25.0 }).unwrap();
+        let manual = "(version 1)\n(rule \"manual\"\n\t(constraint clearance (min 1mm)))\n";
+
+        let merged = merge_rules(manual, std::slice::from_ref(&rule), &[]);
+        assert!(merged.starts_with(manual));
+        assert!(merged.contains("(constraint length (max 25mm))"));
+
+        // Re-merging is stable and replaces the generated section
+        assert_eq!(
+            merge_rules(&merged, std::slice::from_ref(&rule), &[]),
+            merged
+        );
+        assert_eq!(merge_rules(&merged, &[], &[]), manual);
+
+        // A new file gets a version header
+        assert!(merge_rules("", &[rule], &[]).starts_with("(version 1)\n\n# BEGIN"));
+    }
+
+    #[test]
+    fn renders_layer_geometry() {
+        let geometry = vec![LayerGeometry {
+            netclass: "90Ohm Diff".into(),
+            layer: "In2.Cu".into(),
+            track_width: 0.1649,
+            diff_pair_gap: Some(0.15),
+        }];
+        assert_eq!(
+            merge_rules("", &[], &geometry),
+            "(version 1)\n\n\
+             # BEGIN pcb design rules (generated, do not edit)\n\
+             (rule \"90Ohm Diff In2.Cu\"\n\
+             \t(layer \"In2.Cu\")\n\
+             \t(constraint track_width (opt 0.1649mm))\n\
+             \t(constraint diff_pair_gap (opt 0.15mm))\n\
+             \t(condition \"A.NetClass == '90Ohm Diff'\"))\n\
+             \n\
+             # END pcb design rules\n"
+        );
+
+        let quoted = LayerGeometry {
+            netclass: "Bob's".into(),
+            ..geometry[0].clone()
+        };
+        assert_eq!(render_layer_geometry(&[quoted]), "");
+    }
+}
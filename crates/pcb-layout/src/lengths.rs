@@ -0,0 +1,252 @@
+//! Post-layout length checks: routed track lengths read from the `.kicad_pcb`, compared with
+//! the length-matching and maximum-length design rules of the schematic
+
+use std::collections::HashMap;
+
+use pcb_sch::design_rules::{DesignRule, RuleKind, RuleScope};
+use pcb_sexpr::Sexpr;
+
+use crate::LayoutSyncDiagnostic;
+
+fn number(item: &Sexpr) -> Option<f64> {
+    item.as_float().or_else(|| item.as_int().map(|n| n as f64))
+}
+
+/// `(name x y)` child of a track item
+fn point(items: &[Sexpr], name: &str) -> Option<(f64, f64)> {
+    items.iter().find_map(|item| {
+        let list = item.as_list()?;
+        if list.first()?.as_sym() != Some(name) {
+            return None;
+        }
+        Some((number(list.get(1)?)?, number(list.get(2)?)?))
+    })
+}
+
+fn distance(a: (f64, f64), b: (f64, f64)) -> f64 {
+    (a.0 - b.0).hypot(a.1 - b.1)
+}
+
+/// Length of the circular arc from `start` through `mid` to `end`
+fn arc_length(start: (f64, f64), mid: (f64, f64), end: (f64, f64)) -> f64 {
+    let ((ax, ay), (bx, by), (cx, cy)) = (start, mid, end);
+    let d = 2.0 * (ax * (by - cy) + bx * (cy - ay) + cx * (ay - by));
+    if d.abs() < 1e-12 {
+        return distance(start, mid) + distance(mid, end);
+    }
+    // Centre of the circle through the three points
+    let (a2, b2, c2) = (ax * ax + ay * ay, bx * bx + by * by, cx * cx + cy * cy);
+    let centre = (
+        (a2 * (by - cy) + b2 * (cy - ay) + c2 * (ay - by)) / d,
+        (a2 * (cx - bx) + b2 * (ax - cx) + c2 * (bx - ax)) / d,
+    );
+    let angle = |p: (f64, f64)| (p.1 - centre.1).atan2(p.0 - centre.0);
+    let tau = std::f64::consts::TAU;
+    let ccw = |from: f64, to: f64| (to - from).rem_euclid(tau);
+    let (s, m, e) = (angle(start), angle(mid), angle(end));
+    // Sweep from start to end in whichever direction passes through mid
+    let sweep = if ccw(s, m) <= ccw(s, e) {
+        ccw(s, e)
+    } else {
+        tau - ccw(s, e)
+    };
+    distance(start, centre) * sweep
+}
+
+/// Routed length in mm of each net's tracks (segments and arcs), by net name
+pub(crate) fn routed_lengths(pcb: &Sexpr) -> HashMap<String, f64> {
+    // Boards before KiCad 10 refer to nets by number through a top-level net table
+    let net_table: HashMap<i64, &str> = pcb
+        .find_all_lists("net")
+        .into_iter()
+        .filter_map(|net| Some((net.get(1)?.as_int()?, net.get(2)?.as_atom()?)))
+        .collect();
+
+    let mut lengths = HashMap::new();
+    for (kind, track) in pcb
+        .find_all_lists("segment")
+        .into_iter()
+        .map(|t| ("segment", t))
+        .chain(pcb.find_all_lists("arc").into_iter().map(|t| ("arc", t)))
+    {
+        let Some(net) = track.iter().find_map(|item| {
+            let list = item.as_list()?;
+            (list.first()?.as_sym() == Some("net")).then(|| list.get(1))?
+        }) else {
+            continue;
+        };
+        let name = match net {
+            Sexpr::Int(number) => net_table.get(number).copied(),
+            other => other.as_atom(),
+        };
+        let (Some(name), Some(start), Some(end)) =
+            (name, point(track, "start"), point(track, "end"))
+        else {
+            continue;
+        };
+        let length = match (kind, point(track, "mid")) {
+            ("arc", Some(mid)) => arc_length(start, mid, end),
+            _ => distance(start, end),
+        };
+        *lengths.entry(name.to_string()).or_insert(0.0) += length;
+    }
+    lengths
+}
+
+/// Length-matching and maximum-length violations of `rules` given the routed `lengths`.
+/// Rules on netclasses and nets without tracks are skipped.
+pub(crate) fn check_lengths(
+    rules: &[DesignRule],
+    lengths: &HashMap<String, f64>,
+) -> Vec<LayoutSyncDiagnostic> {
+    let mut diagnostics = Vec::new();
+    for rule in rules {
+        let RuleScope::Nets(nets) = &rule.scope else {
+            continue;
+        };
+        let routed: Vec<(&str, f64)> = nets
+            .iter()
+            .filter_map(|net| Some((net.as_str(), *lengths.get(net)?)))
+            .collect();
+        match &rule.kind {
+            RuleKind::LengthMatch { tolerance: max } | RuleKind::Skew { max }
+                if routed.len() >= 2 =>
+            {
+                let (shortest, longest) =
+                    routed
+                        .iter()
+                        .fold((routed[0], routed[0]), |(short, long), &net| {
+                            (
+                                if net.1 < short.1 { net } else { short },
+                                if net.1 > long.1 { net } else { long },
+                            )
+                        });
+                let spread = longest.1 - shortest.1;
+                if spread > *max {
+                    let kind = match rule.kind {
+                        RuleKind::Skew { .. } => "layout.length.skew",
+                        _ => "layout.length.mismatch",
+                    };
+                    diagnostics.push(diagnostic(
+                        kind,
+                        format!(
+                            "Length group '{}' differs by {spread:.3} mm (tolerance {max} mm): \
+                             {} is {:.3} mm, {} is {:.3} mm",
+                            rule.name, longest.0, longest.1, shortest.0, shortest.1
+                        ),
+                    ));
+                }
+            }
+            RuleKind::MaxLength { max } => {
+                for (net, length) in &routed {
+                    if length > max {
+                        diagnostics.push(diagnostic(
+                            "layout.length.max",
+                            format!(
+                                "Net {net} is {length:.3} mm long, over the {max} mm limit of rule '{}'",
+                                rule.name
+                            ),
+                        ));
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    diagnostics
+}
+
+fn diagnostic(kind: &str, body: String) -> LayoutSyncDiagnostic {
+    LayoutSyncDiagnostic {
+        kind: kind.to_owned(),
+        severity: "error".to_owned(),
+        body,
+        path: String::new(),
+        reference: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BOARD: &str = r#"(kicad_pcb
+        (net 0 "")
+        (net 1 "D_P")
+        (net 2 "D_N")
+        (segment (start 0 0) (end 10 0) (width 0.2) (layer "F.Cu") (net 1))
+        (segment (start 10 0) (end 10 5) (width 0.2) (layer "F.Cu") (net 1))
+        (segment (start 0 1) (end 12 1) (width 0.2) (layer "F.Cu") (net 2))
+        (arc (start 0 0) (mid 1 1) (end 2 0) (width 0.2) (layer "B.Cu") (net "CLK"))
+    )"#;
+
+    fn pair_rule(name: &str, kind: RuleKind) -> DesignRule {
+        DesignRule::new(
+            name,
+            RuleScope::Nets(vec!["D_P".into(), "D_N".into()]),
+            kind,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn sums_segments_and_arcs_per_net() {
+        let lengths = routed_lengths(&pcb_sexpr::parse(BOARD).unwrap());
+        assert!((lengths["D_P"] - 15.0).abs() < 1e-9);
+        assert!((lengths["D_N"] - 12.0).abs() < 1e-9);
+        // Half circle of radius 1
+        assert!((lengths["CLK"] - std::f64::consts::PI).abs() < 1e-9);
+        assert!(!lengths.contains_key(""));
+    }
+
+    #[test]
+    fn arc_through_far_side_is_the_major_arc() {
+        // Three quarters of a circle of radius 1 centred on the origin
+        let length = arc_length(
+            (1.0, 0.0),
+            (
+                -std::f64::consts::FRAC_1_SQRT_2,
+                std::f64::consts::FRAC_1_SQRT_2,
+            ),
+            (0.0, -1.0),
+        );
+        assert!(
+            (length - 1.5 * std::f64::consts::PI).abs() < 1e-9,
+            "{length}"
+        );
+    }
+
+    #[test]
+    fn reports_mismatch_skew_and_max_length() {
+        let lengths = routed_lengths(&pcb_sexpr::parse(BOARD).unwrap());
+        let rules = vec![
+            pair_rule("usb.D", RuleKind::Skew { max: 0.5 }),
+            pair_rule("usb.length_match", RuleKind::LengthMatch { tolerance: 5.0 }),
+            // The kind comes from the rule, not from its name
+            pair_rule("user.skew", RuleKind::LengthMatch { tolerance: 1.0 }),
+            DesignRule::new(
+                "clk_len",
+                RuleScope::Nets(vec!["CLK".into(), "UNROUTED".into()]),
+                RuleKind::MaxLength { max: 3.0 },
+            )
+            .unwrap(),
+        ];
+        let diagnostics = check_lengths(&rules, &lengths);
+        let kinds: Vec<_> = diagnostics.iter().map(|d| d.kind.as_str()).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                "layout.length.skew",
+                "layout.length.mismatch",
+                "layout.length.max"
+            ]
+        );
+        assert!(
+            diagnostics[0]
+                .body
+                .contains("D_P is 15.000 mm, D_N is 12.000 mm"),
+            "{}",
+            diagnostics[0].body
+        );
+    }
+}
@@ -14,6 +14,14 @@ use thiserror::Error;
 use pcb_kicad::PythonScriptBuilder;
 use pcb_sch::kicad_netlist::{format_footprint, write_fp_lib_table};
 
+mod dru;
+mod lengths;
+mod reuse;
+mod sync;
+
+pub use reuse::LayoutReuse;
+pub use sync::{LayoutEngine, LAYOUT_ENGINE_ENV};
+
 /// Result of layout generation/update
 #[derive(Debug)]
 pub struct LayoutResult {
@@ -26,6 +34,8 @@ pub struct LayoutResult {
     pub diagnostics_file: PathBuf,
     pub created: bool, // true if new, false if updated
     pub sync_diagnostics: Vec<LayoutSyncDiagnostic>,
+    /// Module instances the layout of a [`LayoutReuse`] source was copied to
+    pub propagated_to: Vec<String>,
 }
 
 /// Error types for layout operations
@@ -54,6 +64,21 @@ pub enum LayoutError {
 
     #[error("JSON error: {0}")]
     Json(#[from] serde_json::Error),
+
+    #[error("Schematic export error: {0}")]
+    SchematicExport(#[from] pcb_sch::kicad_schematic::ConversionError),
+
+    #[error("Native layout sync does not support this design: {0}")]
+    NativeSyncUnsupported(String),
+
+    #[error("'{0}' is not a module instance")]
+    UnknownModuleInstance(String),
+
+    #[error("'{0}' is the only instance of its module; there is nothing to propagate to")]
+    NoOtherInstances(String),
+
+    #[error("The module of '{0}' has no layout_path to save its layout to")]
+    NoModuleLayoutPath(String),
 }
 
 /// Helper struct for layout file paths
@@ -61,6 +86,7 @@ pub enum LayoutError {
 pub struct LayoutPaths {
     pub netlist: PathBuf,
     pub pcb: PathBuf,
+    pub schematic: PathBuf,
     pub snapshot: PathBuf,
     pub log: PathBuf,
     pub json_netlist: PathBuf,
@@ -81,7 +107,7 @@ pub struct LayoutSyncDiagnostic {
     pub reference: Option<String>,
 }
 
-/// Container for layout sync diagnostics from Python script
+/// Container for layout sync diagnostics
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct LayoutSyncDiagnostics {
     pub diagnostics: Vec<LayoutSyncDiagnostic>,
@@ -104,17 +130,31 @@ impl LayoutSyncDiagnostics {
 /// 4. Write the footprint library table
 /// 5. Create or update the KiCad PCB file
 ///
+/// The board is synced by the native engine unless `PCB_LAYOUT_ENGINE=python` is set; designs
+/// the native engine cannot handle fall back to the KiCad Python script.
+///
 /// When `dry_run` is true (check mode):
 /// - Requires PCB file to already exist
 /// - Runs diagnostics without modifying the board
 /// - Skips directory creation, netlist writing, and post-processing
+///
+/// With `reuse`, the layout of one module instance is then copied to the other instances of its
+/// module (and optionally to the module's layout) by the KiCad Python script. It is ignored in
+/// dry-run mode.
 pub fn process_layout(
     schematic: &Schematic,
     source_path: &Path,
     sync_board_config: bool,
     use_temp_dir: bool,
     dry_run: bool,
+    reuse: Option<&LayoutReuse>,
 ) -> Result<LayoutResult, LayoutError> {
+    // Resolve the instances to copy between before touching any files
+    let reuse_plan = match reuse {
+        Some(reuse) if !dry_run => Some(reuse.plan(schematic)?),
+        _ => None,
+    };
+
     // Resolve layout directory
     let layout_dir = if use_temp_dir {
         // Create a temporary directory and keep it (prevent cleanup on drop)
@@ -197,7 +237,111 @@ pub fn process_layout(
         paths.pcb.display()
     );
 
-    // Build and run the Python script
+    // Propagating layouts needs KiCad's geometry, so it always runs through the Python script
+    let engine = LayoutEngine::from_env();
+    let synced_natively = engine == LayoutEngine::Native && reuse_plan.is_none() && {
+        let netlist = sync::Netlist::from_schematic(schematic);
+        let board_config_json = board_config
+            .as_ref()
+            .filter(|_| sync_board_config)
+            .map(serde_json::to_value)
+            .transpose()?;
+        let request = sync::SyncRequest {
+            netlist: &netlist,
+            pcb: &paths.pcb,
+            snapshot: &paths.snapshot,
+            diagnostics: &paths.diagnostics,
+            log: &paths.log,
+            board_config: board_config_json.as_ref(),
+            dry_run,
+        };
+        match sync::sync(&request) {
+            Ok(()) => true,
+            Err(LayoutError::NativeSyncUnsupported(reason)) => {
+                info!("Using the KiCad Python layout sync: {reason}");
+                false
+            }
+            Err(e) => return Err(e),
+        }
+    };
+
+    if !synced_natively {
+        run_python_sync(
+            &paths,
+            sync_board_config,
+            board_config_path.as_deref(),
+            reuse_plan.as_ref(),
+            dry_run,
+            pcb_exists,
+        )?;
+    }
+
+    // Apply board config (stackup + netclass patterns + custom rules) - only in normal mode
+    if !dry_run && sync_board_config {
+        let mut design_rules = schematic.design_rules.clone();
+        let mut layer_geometry: &[_] = &[];
+        if let Some(ref config) = board_config {
+            if let Some(ref stackup) = config.stackup {
+                patch_stackup_if_needed(&paths.pcb, stackup)?;
+            }
+
+            let assignments = build_netclass_assignments(schematic, config.netclasses());
+            if !assignments.is_empty() {
+                patch_netclass_patterns(&paths.pcb, &assignments)?;
+            }
+
+            design_rules.extend(config.design_rules().iter().cloned());
+            layer_geometry = config.layer_geometry();
+        }
+        dru::patch_custom_rules(&paths.pcb, &design_rules, layer_geometry)?;
+    }
+
+    // Parse sync diagnostics from JSON file (empty vec if file doesn't exist)
+    let mut sync_diagnostics = if paths.diagnostics.exists() {
+        LayoutSyncDiagnostics::from_file(&paths.diagnostics)?.diagnostics
+    } else {
+        Vec::new()
+    };
+
+    // Check routed lengths against the length-matching rules (check mode only)
+    if dry_run {
+        let mut design_rules = schematic.design_rules.clone();
+        if let Some(config) = utils::extract_board_config(schematic) {
+            design_rules.extend(config.design_rules().iter().cloned());
+        }
+        if !design_rules.is_empty() {
+            let content = fs::read_to_string(&paths.pcb)
+                .with_context(|| format!("Failed to read {}", paths.pcb.display()))?;
+            let board = pcb_sexpr::parse(&content)
+                .map_err(|e| anyhow::anyhow!("Failed to parse {}: {e}", paths.pcb.display()))?;
+            let lengths = lengths::routed_lengths(&board);
+            sync_diagnostics.extend(lengths::check_lengths(&design_rules, &lengths));
+        }
+    }
+
+    Ok(LayoutResult {
+        source_file: source_path.to_path_buf(),
+        layout_dir,
+        pcb_file: paths.pcb.clone(),
+        netlist_file: paths.netlist,
+        snapshot_file: paths.snapshot,
+        log_file: paths.log,
+        diagnostics_file: paths.diagnostics,
+        created: !pcb_exists && !dry_run,
+        sync_diagnostics,
+        propagated_to: reuse_plan.map(|plan| plan.targets).unwrap_or_default(),
+    })
+}
+
+/// Sync the board with `update_layout_file.py` through KiCad's Python interpreter
+fn run_python_sync(
+    paths: &LayoutPaths,
+    sync_board_config: bool,
+    board_config_path: Option<&str>,
+    reuse_plan: Option<&reuse::ReusePlan>,
+    dry_run: bool,
+    pcb_exists: bool,
+) -> Result<(), LayoutError> {
     let script = include_str!("scripts/update_layout_file.py");
     let mut script_builder = PythonScriptBuilder::new(script)
         .arg("-j")
@@ -216,9 +360,21 @@ pub fn process_layout(
             .arg("--sync-board-config")
             .arg(sync_board_config.to_string());
 
-        if let Some(ref board_config) = board_config_path {
+        if let Some(board_config) = board_config_path {
             script_builder = script_builder.arg("--board-config").arg(board_config);
         }
+
+        if let Some(plan) = reuse_plan {
+            script_builder = script_builder.arg("--propagate").arg(&plan.source);
+            for target in &plan.targets {
+                script_builder = script_builder.arg("--propagate-to").arg(target);
+            }
+            if let Some(module_layout) = &plan.module_layout {
+                script_builder = script_builder
+                    .arg("--save-module-layout")
+                    .arg(module_layout.to_str().unwrap());
+            }
+        }
     }
 
     script_builder
@@ -241,39 +397,32 @@ pub fn process_layout(
                 )
             }
         })?;
+    Ok(())
+}
 
-    // Apply board config (stackup + netclass patterns) - only in normal mode
-    if !dry_run && sync_board_config {
-        if let Some(ref config) = board_config {
-            if let Some(ref stackup) = config.stackup {
-                patch_stackup_if_needed(&paths.pcb, stackup)?;
-            }
-
-            let assignments = build_netclass_assignments(schematic, config.netclasses());
-            if !assignments.is_empty() {
-                patch_netclass_patterns(&paths.pcb, &assignments)?;
-            }
-        }
-    }
-
-    // Parse sync diagnostics from JSON file (empty vec if file doesn't exist)
-    let sync_diagnostics = if paths.diagnostics.exists() {
-        LayoutSyncDiagnostics::from_file(&paths.diagnostics)?.diagnostics
-    } else {
-        Vec::new()
-    };
+/// Write hierarchical KiCad schematic sheets for a schematic into its layout directory
+///
+/// The root sheet is named after the layout's KiCad project (`layout.kicad_sch`) so that
+/// opening the project shows the schematic next to the board. Returns all written files,
+/// root sheet first.
+pub fn write_schematic(
+    schematic: &Schematic,
+    source_path: &Path,
+) -> Result<Vec<PathBuf>, LayoutError> {
+    let layout_dir = utils::resolve_layout_dir(schematic, source_path)?;
+    fs::create_dir_all(&layout_dir).with_context(|| {
+        format!(
+            "Failed to create layout directory: {}",
+            layout_dir.display()
+        )
+    })?;
 
-    Ok(LayoutResult {
-        source_file: source_path.to_path_buf(),
-        layout_dir,
-        pcb_file: paths.pcb.clone(),
-        netlist_file: paths.netlist,
-        snapshot_file: paths.snapshot,
-        log_file: paths.log,
-        diagnostics_file: paths.diagnostics,
-        created: !pcb_exists && !dry_run,
-        sync_diagnostics,
-    })
+    let paths = utils::get_layout_paths(&layout_dir);
+    debug!("Writing schematic to {}", paths.schematic.display());
+    Ok(pcb_sch::kicad_schematic::write_kicad_schematic_sheets(
+        schematic,
+        &paths.schematic,
+    )?)
 }
 
 /// Utility functions
@@ -319,6 +468,7 @@ pub mod utils {
         LayoutPaths {
             netlist: layout_dir.join("default.net"),
             pcb: layout_dir.join("layout.kicad_pcb"),
+            schematic: layout_dir.join("layout.kicad_sch"),
             snapshot: layout_dir.join("snapshot.layout.json"),
             log: layout_dir.join("layout.log"),
             json_netlist,
@@ -328,7 +478,8 @@ pub mod utils {
         }
     }
 
-    /// Extract and parse board config from schematic's root instance attributes
+    /// Extract and parse board config from schematic's root instance attributes. Netclass
+    /// geometry left unspecified is derived from the stackup for the netclass impedance targets.
     pub fn extract_board_config(schematic: &Schematic) -> Option<BoardConfig> {
         let root = schematic.instances.get(schematic.root_ref.as_ref()?)?;
 
@@ -345,7 +496,10 @@ pub mod utils {
             })
             .and_then(|(_, v)| v.string())?;
 
-        BoardConfig::from_json_str(config_json).ok()
+        let mut config = BoardConfig::from_json_str(config_json).ok()?;
+        // Warnings were reported when the config was added
+        config.fill_impedance_geometry();
+        Some(config)
     }
 
     /// Write footprint library table for a layout
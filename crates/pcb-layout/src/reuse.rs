@@ -0,0 +1,162 @@
+//! Layout reuse between the instances of one module: `pcb layout --propagate` copies the layout
+//! of one instance to its siblings, and `--save-module-layout` writes it back to the module's own
+//! `layout_path`. The copying happens in the `PropagateLayout` step of `update_layout_file.py`;
+//! this resolves which instances and files it works on.
+
+use std::path::PathBuf;
+
+use pcb_sch::{InstanceKind, Schematic, ATTR_LAYOUT_PATH};
+
+use crate::LayoutError;
+
+/// Copy the layout of a module instance to the other instances of its module
+#[derive(Debug, Clone, Default)]
+pub struct LayoutReuse {
+    /// Path of the module instance whose layout is copied, e.g. `Phases.A`
+    pub instance: String,
+    /// Also write the instance's layout back to its module's `layout_path`
+    pub save_module_layout: bool,
+}
+
+/// Instances and files a [`LayoutReuse`] resolves to for one schematic
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct ReusePlan {
+    pub source: String,
+    /// Other instances of the same module, sorted by path
+    pub targets: Vec<String>,
+    /// Module layout board to write the source instance's layout to, created if missing
+    pub module_layout: Option<PathBuf>,
+}
+
+impl LayoutReuse {
+    pub(crate) fn plan(&self, schematic: &Schematic) -> Result<ReusePlan, LayoutError> {
+        let path_of = |instance_path: &[String]| instance_path.join(".");
+        let (_, source) = schematic
+            .instances
+            .iter()
+            .find(|(reference, instance)| {
+                instance.kind == InstanceKind::Module
+                    && !reference.instance_path.is_empty()
+                    && path_of(&reference.instance_path) == self.instance
+            })
+            .ok_or_else(|| LayoutError::UnknownModuleInstance(self.instance.clone()))?;
+
+        let mut targets: Vec<String> = schematic
+            .instances
+            .iter()
+            .filter(|(reference, instance)| {
+                instance.kind == InstanceKind::Module
+                    && instance.type_ref == source.type_ref
+                    && !reference.instance_path.is_empty()
+            })
+            .map(|(reference, _)| path_of(&reference.instance_path))
+            .filter(|path| *path != self.instance)
+            .collect();
+        targets.sort();
+
+        let module_layout = if self.save_module_layout {
+            let layout_dir = source
+                .attributes
+                .get(ATTR_LAYOUT_PATH)
+                .and_then(|v| v.string())
+                .ok_or_else(|| LayoutError::NoModuleLayoutPath(self.instance.clone()))?;
+            Some(PathBuf::from(layout_dir).join("layout.kicad_pcb"))
+        } else if targets.is_empty() {
+            return Err(LayoutError::NoOtherInstances(self.instance.clone()));
+        } else {
+            None
+        };
+
+        Ok(ReusePlan {
+            source: self.instance.clone(),
+            targets,
+            module_layout,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pcb_sch::{AttributeValue, Instance, InstanceRef, ModuleRef};
+
+    fn design(layout_dir: &str) -> Schematic {
+        let root = ModuleRef::new("/board/board.zen", "<root>");
+        let phase = ModuleRef::new("/board/modules/PhaseDriver.zen", "<root>");
+        let mut schematic = Schematic::new();
+        for path in ["Phases.C", "Phases.A", "Phases.B"] {
+            let mut instance = Instance::module(phase.clone());
+            instance.add_attribute(
+                ATTR_LAYOUT_PATH,
+                AttributeValue::String(layout_dir.to_owned()),
+            );
+            let instance_path = path.split('.').map(str::to_owned).collect();
+            schematic.add_instance(InstanceRef::new(root.clone(), instance_path), instance);
+        }
+        schematic.add_instance(
+            InstanceRef::new(root.clone(), vec!["Phases".to_owned()]),
+            Instance::module(ModuleRef::new("/board/modules/Phases.zen", "<root>")),
+        );
+        schematic
+    }
+
+    #[test]
+    fn targets_other_instances_of_the_module() {
+        let schematic = design("/board/modules/layout/PhaseDriver");
+        let reuse = LayoutReuse {
+            instance: "Phases.B".to_owned(),
+            save_module_layout: false,
+        };
+        assert_eq!(
+            reuse.plan(&schematic).unwrap(),
+            ReusePlan {
+                source: "Phases.B".to_owned(),
+                targets: vec!["Phases.A".to_owned(), "Phases.C".to_owned()],
+                module_layout: None,
+            }
+        );
+
+        let reuse = LayoutReuse {
+            instance: "Phases".to_owned(),
+            save_module_layout: false,
+        };
+        assert!(matches!(
+            reuse.plan(&schematic),
+            Err(LayoutError::NoOtherInstances(path)) if path == "Phases"
+        ));
+
+        let reuse = LayoutReuse {
+            instance: "Phases.D".to_owned(),
+            save_module_layout: false,
+        };
+        assert!(matches!(
+            reuse.plan(&schematic),
+            Err(LayoutError::UnknownModuleInstance(_))
+        ));
+    }
+
+    #[test]
+    fn saves_to_the_module_layout() {
+        let layout_dir = "/board/modules/layout/PhaseDriver";
+        let reuse = LayoutReuse {
+            instance: "Phases.A".to_owned(),
+            save_module_layout: true,
+        };
+
+        // The module layout board is created by the sync when it does not exist yet
+        let schematic = design(layout_dir);
+        assert_eq!(
+            reuse.plan(&schematic).unwrap().module_layout,
+            Some(PathBuf::from(layout_dir).join("layout.kicad_pcb"))
+        );
+
+        let mut schematic = design(layout_dir);
+        for instance in schematic.instances.values_mut() {
+            instance.attributes.remove(ATTR_LAYOUT_PATH);
+        }
+        assert!(matches!(
+            reuse.plan(&schematic),
+            Err(LayoutError::NoModuleLayoutPath(path)) if path == "Phases.A"
+        ));
+    }
+}
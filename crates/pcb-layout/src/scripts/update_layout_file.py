@@ -24,7 +24,11 @@ Pipeline Overview
    • Run the HierPlace heuristic (largest-first, TL/BR candidate points) with
      collision checks based on courtyard bounding boxes.
 
-4. FinalizeBoard
+4. PropagateLayout (only with --propagate)
+   • Copy placement, tracks, vias and zones of one module instance to the other
+     instances of its module, and optionally back to the module's layout file.
+
+5. FinalizeBoard
    • Fill all copper zones.
    • Emit a deterministic JSON snapshot (for regression tests).
    • Save the updated *.kicad_pcb*.
@@ -3560,7 +3564,248 @@ class PlaceComponents(Step):
 
 
 ####################################################################################################
-# Step 4. Finalize board
+# Step 4. Propagate layouts between module instances
+####################################################################################################
+
+
+class PropagateLayout(Step):
+    """Copy the layout of one module instance to the other instances of its module.
+
+    Footprints are matched by their path relative to the instance. Placement is copied as an
+    offset from the centre of the instance's footprints, so each target stays where it was on the
+    board. Tracks, vias, zones and graphics of an instance are the members of its KiCad group plus
+    the tracks, vias and zones on nets that only its own pads use; those of a target are replaced
+    by copies of the source's, with nets remapped through the matched pads.
+
+    With `module_layout`, the source's layout is written back to the module's own layout board in
+    the same way; the board is created from the source's footprints if it does not exist.
+    """
+
+    def __init__(
+        self,
+        state: SyncState,
+        board: pcbnew.BOARD,
+        source: str,
+        targets: List[str],
+        module_layout: Optional[Path] = None,
+    ):
+        self.state = state
+        self.board = board
+        self.source = source
+        self.targets = targets
+        self.module_layout = module_layout
+
+    @staticmethod
+    def _footprints_by_path(board: pcbnew.BOARD, prefix: str) -> Dict[str, Any]:
+        """Footprints of `board` under `prefix` by their path relative to it. An empty prefix
+        returns all footprints of a module layout board."""
+        footprints = {}
+        for fp in board.GetFootprints():
+            field = fp.GetFieldByName("Path")
+            path = field.GetText() if field else ""
+            if not prefix:
+                if path:
+                    footprints[path] = fp
+            elif path.startswith(prefix + "."):
+                footprints[path[len(prefix) + 1 :]] = fp
+        return footprints
+
+    def _instance_items(self, path: str, footprints: Dict[str, Any]) -> List[Any]:
+        """Tracks, vias, zones and graphics belonging to the instance at `path`."""
+        items = {}
+
+        def collect_group(group):
+            for item in get_group_items(group):
+                if isinstance(item, pcbnew.FOOTPRINT):
+                    continue
+                if isinstance(item, pcbnew.PCB_GROUP):
+                    collect_group(item)
+                    continue
+                items[item.m_Uuid.AsString()] = item
+
+        group = self.state.groups_registry.get(path)
+        if group:
+            collect_group(group)
+
+        own_fps = set(fp.m_Uuid.AsString() for fp in footprints.values())
+        inside = set()
+        outside = set()
+        for fp in self.board.GetFootprints():
+            codes = inside if fp.m_Uuid.AsString() in own_fps else outside
+            for pad in fp.Pads():
+                if pad.GetNetCode() > 0:
+                    codes.add(pad.GetNetCode())
+        private_nets = inside - outside
+
+        for item in list(self.board.GetTracks()) + list(self.board.Zones()):
+            if item.GetNetCode() in private_nets:
+                items[item.m_Uuid.AsString()] = item
+
+        return sorted(items.values(), key=lambda item: item.m_Uuid.AsString())
+
+    @staticmethod
+    def _center(footprints: List[Any]) -> Tuple[int, int]:
+        bbox = get_kicad_bbox(footprints[0])
+        for fp in footprints[1:]:
+            bbox = bbox.merge(get_kicad_bbox(fp))
+        return bbox.center_x, bbox.center_y
+
+    def _copy_layout(
+        self,
+        source_fps: Dict[str, Any],
+        source_items: List[Any],
+        target_board: pcbnew.BOARD,
+        target_fps: Dict[str, Any],
+        target_group: Optional[pcbnew.PCB_GROUP],
+        target_name: str,
+    ) -> None:
+        """Copy placement and items of the source instance onto the matching target footprints."""
+        common = sorted(set(source_fps) & set(target_fps))
+        unmatched = sorted(set(source_fps) ^ set(target_fps))
+        if unmatched:
+            logger.warning(
+                f"  {len(unmatched)} footprints of {self.source} and {target_name} have no counterpart: "
+                + ", ".join(unmatched)
+            )
+        if not common:
+            logger.warning(f"  No footprints of {target_name} match {self.source}; skipped")
+            return
+
+        source_x, source_y = self._center([source_fps[p] for p in common])
+        target_x, target_y = self._center([target_fps[p] for p in common])
+        offset = pcbnew.VECTOR2I(target_x - source_x, target_y - source_y)
+
+        pairs = [
+            (
+                VirtualFootprint(p, p, source_fps[p], None),
+                VirtualFootprint(p, p, target_fps[p], None),
+            )
+            for p in common
+        ]
+        net_code_map = build_net_code_mapping(self.board, target_board, pairs)
+
+        for source_vfp, target_vfp in pairs:
+            target_vfp.replace_with(source_vfp)
+            target_vfp.kicad_footprint.Move(offset)
+
+        for item in source_items:
+            new_item = item.Duplicate()
+            # Duplicate() puts the copy in the source's group
+            parent = new_item.GetParentGroup()
+            if parent:
+                parent.RemoveItem(new_item)
+            if hasattr(new_item, "GetNetCode") and new_item.GetNetCode() > 0:
+                target_net = target_board.FindNet(
+                    net_code_map.get(item.GetNetCode(), 0)
+                )
+                if target_net:
+                    new_item.SetNet(target_net)
+                else:
+                    new_item.SetNetCode(0)
+            target_board.Add(new_item)
+            new_item.Move(offset)
+            if target_group:
+                target_group.AddItem(new_item)
+
+        logger.info(
+            f"  Copied {len(common)} footprint placements and {len(source_items)} items to {target_name}"
+        )
+
+    @staticmethod
+    def _remove_items(board: pcbnew.BOARD, items: List[Any]) -> None:
+        for item in items:
+            parent = item.GetParentGroup()
+            if parent:
+                parent.RemoveItem(item)
+            board.Remove(item)
+
+    def _new_module_layout(self, source_fps: Dict[str, Any]) -> pcbnew.BOARD:
+        """Create the module layout board with a copy of each source footprint, keyed by its
+        relative path. Pad nets are carried over by name so the copied items can be remapped."""
+        assert self.module_layout is not None
+        logger.info(f"Creating module layout board at {self.module_layout}")
+        self.module_layout.parent.mkdir(parents=True, exist_ok=True)
+        module_board = pcbnew.NewBoard(str(self.module_layout))
+
+        for path, fp in sorted(source_fps.items()):
+            new_fp = pcbnew.FOOTPRINT(fp)
+            parent = new_fp.GetParentGroup()
+            if parent:
+                parent.RemoveItem(new_fp)
+            new_fp.GetFieldByName("Path").SetText(path)
+            for pad in new_fp.Pads():
+                if pad.GetNetCode() <= 0:
+                    continue
+                net = module_board.FindNet(pad.GetNetname())
+                if not net:
+                    net = pcbnew.NETINFO_ITEM(module_board, pad.GetNetname())
+                    module_board.Add(net)
+                pad.SetNet(net)
+            module_board.Add(new_fp)
+
+        return module_board
+
+    def _save_module_layout(
+        self, source_fps: Dict[str, Any], source_items: List[Any]
+    ) -> None:
+        """Replace the module layout board's placement and items with the source instance's,
+        creating the board if the module has no layout yet."""
+        assert self.module_layout is not None
+        logger.info(f"Saving the layout of {self.source} to {self.module_layout}")
+        if self.module_layout.exists():
+            module_board = pcbnew.LoadBoard(str(self.module_layout))
+        else:
+            module_board = self._new_module_layout(source_fps)
+
+        # The module layout board holds only this module, so everything but the outline goes
+        stale = list(module_board.GetTracks()) + list(module_board.Zones())
+        stale += [
+            drawing
+            for drawing in module_board.GetDrawings()
+            if drawing.GetClass() not in ["PCB_GROUP", "PCB_GENERATOR"]
+            and not isinstance(drawing.GetParent(), pcbnew.FOOTPRINT)
+            and drawing.GetLayer() != pcbnew.Edge_Cuts
+        ]
+        self._remove_items(module_board, stale)
+
+        self._copy_layout(
+            source_fps,
+            source_items,
+            module_board,
+            self._footprints_by_path(module_board, ""),
+            None,
+            str(self.module_layout),
+        )
+        pcbnew.SaveBoard(str(self.module_layout), module_board)
+
+        # Explicitly delete the layout board to release resources (important for Windows)
+        del module_board
+
+    def run(self):
+        source_fps = self._footprints_by_path(self.board, self.source)
+        if not source_fps:
+            raise ValueError(f"{self.source} has no footprints on the board")
+        source_items = self._instance_items(self.source, source_fps)
+
+        for target in self.targets:
+            logger.info(f"Propagating the layout of {self.source} to {target}")
+            target_fps = self._footprints_by_path(self.board, target)
+            self._remove_items(self.board, self._instance_items(target, target_fps))
+            self._copy_layout(
+                source_fps,
+                source_items,
+                self.board,
+                target_fps,
+                self.state.groups_registry.get(target),
+                target,
+            )
+
+        if self.module_layout:
+            self._save_module_layout(source_fps, source_items)
+
+
+####################################################################################################
+# Step 5. Finalize board
 ####################################################################################################
 
 
@@ -3981,6 +4226,26 @@ def main():
         action="store_true",
         help="""Detect issues without modifying the board (read-only mode).""",
     )
+    parser.add_argument(
+        "--propagate",
+        type=str,
+        metavar="path",
+        help="""Module instance whose layout is copied to the --propagate-to instances.""",
+    )
+    parser.add_argument(
+        "--propagate-to",
+        type=str,
+        action="append",
+        default=[],
+        metavar="path",
+        help="""Module instance to copy the --propagate layout to (can be repeated).""",
+    )
+    parser.add_argument(
+        "--save-module-layout",
+        type=str,
+        metavar="file",
+        help="""Module layout board to write the --propagate layout to.""",
+    )
     args = parser.parse_args()
 
     logger.setLevel(logging.DEBUG)
@@ -4035,8 +4300,17 @@ def main():
             ImportNetlist(state, board, args.output, netlist),
             SyncLayouts(state, board, netlist),
             PlaceComponents(state, board, netlist),
-            FinalizeBoard(state, board, snapshot_path, diagnostics_path),
         ]
+        if args.propagate:
+            module_layout = (
+                Path(args.save_module_layout) if args.save_module_layout else None
+            )
+            steps.append(
+                PropagateLayout(
+                    state, board, args.propagate, args.propagate_to, module_layout
+                )
+            )
+        steps.append(FinalizeBoard(state, board, snapshot_path, diagnostics_path))
         save_board = True
 
     for step in steps:
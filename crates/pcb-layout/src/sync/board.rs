@@ -0,0 +1,1064 @@
+//! Minimal `.kicad_pcb` document model built on `pcb-sexpr`.
+//!
+//! The board is kept as a raw S-expression tree so that anything the sync engine does not
+//! understand (zones, tracks, drawings, setup, ...) round-trips untouched. Only footprints,
+//! nets, groups and the title block are edited.
+
+use std::collections::BTreeMap;
+
+use pcb_sexpr::Sexpr;
+use uuid::Uuid;
+
+/// KiCad 9 board format version written for new boards
+const BOARD_VERSION: i64 = 20241229;
+
+/// Layer table of an empty two-layer KiCad 9 board (see `Stackup::generate_layers_sexpr`)
+const DEFAULT_LAYERS: &[(i64, &str, &str, Option<&str>)] = &[
+    (0, "F.Cu", "signal", None),
+    (2, "B.Cu", "signal", None),
+    (9, "F.Adhes", "user", Some("F.Adhesive")),
+    (11, "B.Adhes", "user", Some("B.Adhesive")),
+    (13, "F.Paste", "user", None),
+    (15, "B.Paste", "user", None),
+    (5, "F.SilkS", "user", Some("F.Silkscreen")),
+    (7, "B.SilkS", "user", Some("B.Silkscreen")),
+    (1, "F.Mask", "user", None),
+    (3, "B.Mask", "user", None),
+    (17, "Dwgs.User", "user", Some("User.Drawings")),
+    (19, "Cmts.User", "user", Some("User.Comments")),
+    (21, "Eco1.User", "user", Some("User.Eco1")),
+    (23, "Eco2.User", "user", Some("User.Eco2")),
+    (25, "Edge.Cuts", "user", None),
+    (27, "Margin", "user", None),
+    (31, "F.CrtYd", "user", Some("F.Courtyard")),
+    (29, "B.CrtYd", "user", Some("B.Courtyard")),
+    (35, "F.Fab", "user", None),
+    (33, "B.Fab", "user", None),
+];
+
+/// Axis-aligned bounding box in millimeters
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct BBox {
+    pub min_x: f64,
+    pub min_y: f64,
+    pub max_x: f64,
+    pub max_y: f64,
+}
+
+impl BBox {
+    pub fn from_point(x: f64, y: f64) -> Self {
+        Self {
+            min_x: x,
+            min_y: y,
+            max_x: x,
+            max_y: y,
+        }
+    }
+
+    pub fn include(&mut self, x: f64, y: f64) {
+        self.min_x = self.min_x.min(x);
+        self.min_y = self.min_y.min(y);
+        self.max_x = self.max_x.max(x);
+        self.max_y = self.max_y.max(y);
+    }
+
+    pub fn merge(&mut self, other: &BBox) {
+        self.include(other.min_x, other.min_y);
+        self.include(other.max_x, other.max_y);
+    }
+
+    pub fn width(&self) -> f64 {
+        self.max_x - self.min_x
+    }
+
+    pub fn height(&self) -> f64 {
+        self.max_y - self.min_y
+    }
+}
+
+/// Parsed `.kicad_pcb` file
+#[derive(Debug, Clone)]
+pub(crate) struct Board {
+    /// Top-level items, including the leading `kicad_pcb` symbol
+    items: Vec<Sexpr>,
+}
+
+impl Board {
+    /// Parse a board from the contents of a `.kicad_pcb` file
+    pub fn parse(content: &str) -> Result<Self, String> {
+        let root = pcb_sexpr::parse(content).map_err(|e| e.to_string())?;
+        match root {
+            Sexpr::List(items) if head(&items) == Some("kicad_pcb") => Ok(Self { items }),
+            _ => Err("not a kicad_pcb file".to_owned()),
+        }
+    }
+
+    /// An empty two-layer board, equivalent to what `pcbnew.NewBoard` saves
+    pub fn empty() -> Self {
+        let layers = DEFAULT_LAYERS.iter().map(|(id, name, kind, alias)| {
+            let mut layer = vec![Sexpr::Int(*id), Sexpr::string(*name), Sexpr::symbol(*kind)];
+            if let Some(alias) = alias {
+                layer.push(Sexpr::string(*alias));
+            }
+            Sexpr::List(layer)
+        });
+
+        Self {
+            items: vec![
+                Sexpr::symbol("kicad_pcb"),
+                pcb_sexpr::kv("version", BOARD_VERSION),
+                pcb_sexpr::kv("generator", Sexpr::string("pcbnew")),
+                pcb_sexpr::kv("generator_version", Sexpr::string("9.0")),
+                Sexpr::list(vec![
+                    Sexpr::symbol("general"),
+                    pcb_sexpr::kv("thickness", 1.6),
+                    pcb_sexpr::kv("legacy_teardrops", Sexpr::symbol("no")),
+                ]),
+                pcb_sexpr::kv("paper", Sexpr::string("A4")),
+                Sexpr::List(
+                    std::iter::once(Sexpr::symbol("layers"))
+                        .chain(layers)
+                        .collect(),
+                ),
+                Sexpr::list(vec![
+                    Sexpr::symbol("setup"),
+                    pcb_sexpr::kv("pad_to_mask_clearance", 0i64),
+                    pcb_sexpr::kv(
+                        "allow_soldermask_bridges_in_footprints",
+                        Sexpr::symbol("no"),
+                    ),
+                ]),
+                net_decl(0, ""),
+            ],
+        }
+    }
+
+    /// Serialize in KiCad's own layout so saved boards diff cleanly against pcbnew output
+    pub fn to_kicad_string(&self) -> String {
+        let mut out = String::new();
+        write_kicad(&Sexpr::List(self.items.clone()), 0, &mut out);
+        out.push('\n');
+        out
+    }
+
+    pub fn items(&self) -> &[Sexpr] {
+        &self.items[1..]
+    }
+
+    /// Item at `index` of [`Board::items_mut`] (as returned by the index lookups), if a list
+    pub fn item(&self, index: usize) -> Option<&[Sexpr]> {
+        self.items.get(index).and_then(|item| item.as_list())
+    }
+
+    pub fn items_mut(&mut self) -> &mut Vec<Sexpr> {
+        &mut self.items
+    }
+
+    /// All footprints on the board
+    pub fn footprints(&self) -> impl Iterator<Item = Footprint<'_>> {
+        self.items()
+            .iter()
+            .filter_map(|item| item.as_list())
+            .filter(|items| head(items) == Some("footprint"))
+            .map(Footprint)
+    }
+
+    /// Index of the footprint whose schematic path ends in `path_uuid`
+    pub fn footprint_index(&self, path_uuid: &str) -> Option<usize> {
+        self.items.iter().position(|item| {
+            item.as_list()
+                .filter(|items| head(items) == Some("footprint"))
+                .and_then(|items| Footprint(items).path_uuid())
+                == Some(path_uuid)
+        })
+    }
+
+    /// Net table as `code -> name`
+    pub fn nets(&self) -> BTreeMap<i64, String> {
+        self.items()
+            .iter()
+            .filter_map(|item| item.as_list())
+            .filter(|items| head(items) == Some("net"))
+            .filter_map(|items| {
+                Some((
+                    items.get(1)?.as_int()?,
+                    items
+                        .get(2)
+                        .and_then(|s| s.as_atom())
+                        .unwrap_or("")
+                        .to_owned(),
+                ))
+            })
+            .collect()
+    }
+
+    /// Replace the net table, keeping its position in the file
+    pub fn set_nets(&mut self, names: &[String]) {
+        let position = self
+            .items
+            .iter()
+            .position(|item| is_list_named(item, "net"))
+            .or_else(|| {
+                self.items
+                    .iter()
+                    .position(|item| is_list_named(item, "setup"))
+                    .map(|i| i + 1)
+            })
+            .unwrap_or(self.items.len());
+        self.items.retain(|item| !is_list_named(item, "net"));
+
+        let decls = std::iter::once(net_decl(0, ""))
+            .chain(
+                names
+                    .iter()
+                    .enumerate()
+                    .map(|(i, name)| net_decl(i as i64 + 1, name)),
+            )
+            .collect::<Vec<_>>();
+        let position = position.min(self.items.len());
+        self.items.splice(position..position, decls);
+    }
+
+    /// Named groups as `name -> index of the group item`
+    pub fn groups(&self) -> BTreeMap<String, usize> {
+        self.items
+            .iter()
+            .enumerate()
+            .filter_map(|(i, item)| {
+                let items = item
+                    .as_list()
+                    .filter(|items| head(items) == Some("group"))?;
+                let name = items
+                    .get(1)
+                    .and_then(|s| s.as_str())
+                    .filter(|n| !n.is_empty())?;
+                Some((name.to_owned(), i))
+            })
+            .collect()
+    }
+
+    /// Set (or add) the title block entries used by the Python sync
+    pub fn set_title_block(&mut self, entries: &[(&str, &str)]) {
+        if !self
+            .items
+            .iter()
+            .any(|item| is_list_named(item, "title_block"))
+        {
+            let position = self
+                .items
+                .iter()
+                .position(|item| is_list_named(item, "paper"))
+                .map(|i| i + 1)
+                .unwrap_or(self.items.len().min(2));
+            self.items
+                .insert(position, Sexpr::list(vec![Sexpr::symbol("title_block")]));
+        }
+        let title_block = self
+            .items
+            .iter_mut()
+            .find(|item| is_list_named(item, "title_block"))
+            .and_then(|item| item.as_list_mut())
+            .expect("title block was just inserted");
+        for (key, value) in entries {
+            set_child(title_block, key, vec![Sexpr::string(*value)]);
+        }
+    }
+}
+
+/// Read-only view of a `(footprint ...)` list
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Footprint<'a>(pub &'a [Sexpr]);
+
+impl<'a> Footprint<'a> {
+    /// `lib:name` footprint identifier
+    pub fn fpid(&self) -> &'a str {
+        self.0.get(1).and_then(|s| s.as_atom()).unwrap_or("")
+    }
+
+    /// KiCad item UUID (used for group membership)
+    pub fn uuid(&self) -> Option<&'a str> {
+        find(self.0, "uuid")
+            .or_else(|| find(self.0, "tstamp"))
+            .and_then(|items| items.get(1))
+            .and_then(|s| s.as_atom())
+    }
+
+    /// Full schematic path, e.g. `/<uuid>/<uuid>`
+    pub fn kiid_path(&self) -> &'a str {
+        find(self.0, "path")
+            .and_then(|items| items.get(1))
+            .and_then(|s| s.as_atom())
+            .unwrap_or("")
+    }
+
+    /// Last segment of the schematic path, which the sync uses as footprint identity
+    pub fn path_uuid(&self) -> Option<&'a str> {
+        self.kiid_path()
+            .rsplit('/')
+            .next()
+            .filter(|s| !s.is_empty())
+    }
+
+    pub fn reference(&self) -> &'a str {
+        self.property("Reference")
+            .or_else(|| self.fp_text("reference"))
+            .unwrap_or("")
+    }
+
+    pub fn value(&self) -> &'a str {
+        self.property("Value")
+            .or_else(|| self.fp_text("value"))
+            .unwrap_or("")
+    }
+
+    pub fn property(&self, name: &str) -> Option<&'a str> {
+        self.property_list(name)
+            .and_then(|items| items.get(2))
+            .and_then(|s| s.as_atom())
+    }
+
+    /// Whether a property is shown on the board
+    pub fn property_visible(&self, name: &str) -> Option<bool> {
+        let items = self.property_list(name)?;
+        Some(!is_hidden(items))
+    }
+
+    /// User fields (everything except the mandatory Reference/Value/Footprint)
+    pub fn fields(&self) -> Vec<(&'a str, &'a str)> {
+        find_all(self.0, "property")
+            .filter_map(|items| {
+                Some((
+                    items.get(1)?.as_atom()?,
+                    items.get(2).and_then(|s| s.as_atom()).unwrap_or(""),
+                ))
+            })
+            .filter(|(name, _)| !matches!(*name, "Reference" | "Value" | "Footprint"))
+            .collect()
+    }
+
+    /// Whether `flag` (e.g. `dnp`, `exclude_from_bom`) is set in the `attr` list
+    pub fn has_attr(&self, flag: &str) -> bool {
+        find(self.0, "attr").is_some_and(|items| items.iter().any(|s| s.as_sym() == Some(flag)))
+            || find(self.0, flag).is_some_and(|items| {
+                items.get(1).and_then(|s| s.as_sym()).unwrap_or("yes") == "yes"
+            })
+    }
+
+    pub fn layer(&self) -> &'a str {
+        find(self.0, "layer")
+            .and_then(|items| items.get(1))
+            .and_then(|s| s.as_atom())
+            .unwrap_or("F.Cu")
+    }
+
+    pub fn is_back(&self) -> bool {
+        self.layer() == "B.Cu"
+    }
+
+    pub fn locked(&self) -> bool {
+        self.0.iter().any(|s| s.as_sym() == Some("locked"))
+            || find(self.0, "locked")
+                .is_some_and(|items| items.get(1).and_then(|s| s.as_sym()) != Some("no"))
+    }
+
+    /// Position in mm and orientation in degrees
+    pub fn at(&self) -> (f64, f64, f64) {
+        find(self.0, "at").map(parse_at).unwrap_or((0.0, 0.0, 0.0))
+    }
+
+    pub fn pads(&self) -> impl Iterator<Item = &'a [Sexpr]> {
+        find_all(self.0, "pad")
+    }
+
+    /// Graphic items drawn as part of the footprint (lines, shapes and user text)
+    pub fn graphics(&self) -> impl Iterator<Item = &'a [Sexpr]> {
+        self.0
+            .iter()
+            .filter_map(|item| item.as_list())
+            .filter(|items| head(items).is_some_and(|h| h.starts_with("fp_")))
+    }
+
+    /// Bounding box in board coordinates, from the courtyard if present, else from the pads
+    pub fn bbox(&self) -> BBox {
+        let (x, y, rot) = self.at();
+        let local = self.local_bbox();
+        let mut bbox = BBox::from_point(x, y);
+        if let Some(local) = local {
+            let mut world: Option<BBox> = None;
+            for (lx, ly) in [
+                (local.min_x, local.min_y),
+                (local.max_x, local.min_y),
+                (local.min_x, local.max_y),
+                (local.max_x, local.max_y),
+            ] {
+                let (wx, wy) = to_board(x, y, rot, lx, ly);
+                match world.as_mut() {
+                    Some(world) => world.include(wx, wy),
+                    None => world = Some(BBox::from_point(wx, wy)),
+                }
+            }
+            bbox = world.unwrap_or(bbox);
+        }
+        bbox
+    }
+
+    /// Footprint-local bounding box
+    pub fn local_bbox(&self) -> Option<BBox> {
+        let mut courtyard: Option<BBox> = None;
+        for graphic in self.graphics() {
+            let on_courtyard = find(graphic, "layer")
+                .and_then(|items| items.get(1))
+                .and_then(|s| s.as_atom())
+                .is_some_and(|layer| layer.ends_with("CrtYd"));
+            if !on_courtyard {
+                continue;
+            }
+            for (px, py) in graphic_points(graphic) {
+                match courtyard.as_mut() {
+                    Some(bbox) => bbox.include(px, py),
+                    None => courtyard = Some(BBox::from_point(px, py)),
+                }
+            }
+        }
+        if courtyard.is_some() {
+            return courtyard;
+        }
+
+        let mut pads: Option<BBox> = None;
+        for pad in self.pads() {
+            let (px, py, _) = find(pad, "at").map(parse_at).unwrap_or_default();
+            let (w, h) = find(pad, "size")
+                .map(|items| (number_at(items, 1), number_at(items, 2)))
+                .unwrap_or((0.0, 0.0));
+            for (cx, cy) in [(px - w / 2.0, py - h / 2.0), (px + w / 2.0, py + h / 2.0)] {
+                match pads.as_mut() {
+                    Some(bbox) => bbox.include(cx, cy),
+                    None => pads = Some(BBox::from_point(cx, cy)),
+                }
+            }
+        }
+        pads
+    }
+
+    fn property_list(&self, name: &str) -> Option<&'a [Sexpr]> {
+        find_all(self.0, "property")
+            .find(|items| items.get(1).and_then(|s| s.as_atom()) == Some(name))
+    }
+
+    fn fp_text(&self, kind: &str) -> Option<&'a str> {
+        find_all(self.0, "fp_text")
+            .find(|items| items.get(1).and_then(|s| s.as_sym()) == Some(kind))
+            .and_then(|items| items.get(2))
+            .and_then(|s| s.as_atom())
+    }
+}
+
+/// Mutable operations on a `(footprint ...)` list
+pub(crate) struct FootprintMut<'a>(pub &'a mut Vec<Sexpr>);
+
+impl FootprintMut<'_> {
+    pub fn view(&self) -> Footprint<'_> {
+        Footprint(self.0)
+    }
+
+    pub fn set_fpid(&mut self, fpid: &str) {
+        if self.0.len() > 1 {
+            self.0[1] = Sexpr::string(fpid);
+        }
+    }
+
+    pub fn set_kiid_path(&mut self, path_uuid: &str) {
+        let path = format!("/{path_uuid}/{path_uuid}");
+        if let Some(items) = find_mut(self.0, "path") {
+            items.truncate(1);
+            items.push(Sexpr::string(path));
+            return;
+        }
+        let position = self
+            .0
+            .iter()
+            .position(|item| {
+                head_of(item).is_some_and(|h| {
+                    matches!(h, "sheetname" | "sheetfile" | "attr" | "pad" | "model")
+                        || h.starts_with("fp_")
+                })
+            })
+            .unwrap_or(self.0.len());
+        self.0
+            .insert(position, pcb_sexpr::kv("path", Sexpr::string(path)));
+    }
+
+    pub fn set_reference(&mut self, reference: &str) {
+        if !self.set_fp_text("reference", reference) {
+            self.set_property("Reference", reference, None);
+        }
+    }
+
+    pub fn set_value(&mut self, value: &str) {
+        if !self.set_fp_text("value", value) {
+            self.set_property("Value", value, None);
+        }
+    }
+
+    /// Set a property's text, creating it (hidden on the fab layer) if it does not exist.
+    /// `visible` forces the visibility when given.
+    pub fn set_property(&mut self, name: &str, value: &str, visible: Option<bool>) {
+        let existing = self.0.iter_mut().find_map(|item| {
+            item.as_list_mut()
+                .filter(|items| head(items) == Some("property"))
+                .filter(|items| items.get(1).and_then(|s| s.as_atom()) == Some(name))
+        });
+        match existing {
+            Some(items) => {
+                if items.len() > 2 {
+                    items[2] = Sexpr::string(value);
+                } else {
+                    items.push(Sexpr::string(value));
+                }
+                if let Some(visible) = visible {
+                    set_hidden(items, !visible);
+                }
+            }
+            None => {
+                let mut property = vec![
+                    Sexpr::symbol("property"),
+                    Sexpr::string(name),
+                    Sexpr::string(value),
+                    Sexpr::list(vec![
+                        Sexpr::symbol("at"),
+                        Sexpr::Int(0),
+                        Sexpr::Int(0),
+                        Sexpr::Int(0),
+                    ]),
+                    pcb_sexpr::kv("layer", Sexpr::string("F.Fab")),
+                    pcb_sexpr::kv("uuid", Sexpr::string(Uuid::new_v4().to_string())),
+                    default_effects(),
+                ];
+                set_hidden(&mut property, !visible.unwrap_or(false));
+                let position = self
+                    .0
+                    .iter()
+                    .rposition(|item| is_list_named(item, "property"))
+                    .map(|i| i + 1)
+                    .or_else(|| {
+                        self.0.iter().position(|item| {
+                            is_list_named(item, "path") || is_list_named(item, "attr")
+                        })
+                    })
+                    .unwrap_or(self.0.len());
+                self.0.insert(position, Sexpr::List(property));
+            }
+        }
+    }
+
+    pub fn remove_property(&mut self, name: &str) {
+        self.0.retain(|item| {
+            !(is_list_named(item, "property")
+                && item
+                    .as_list()
+                    .and_then(|items| items.get(1))
+                    .and_then(|s| s.as_atom())
+                    == Some(name))
+        });
+    }
+
+    pub fn set_property_visible(&mut self, name: &str, visible: bool) {
+        if let Some(items) = self.0.iter_mut().find_map(|item| {
+            item.as_list_mut()
+                .filter(|items| head(items) == Some("property"))
+                .filter(|items| items.get(1).and_then(|s| s.as_atom()) == Some(name))
+        }) {
+            set_hidden(items, !visible);
+        }
+    }
+
+    /// Set or clear a flag in the footprint's `attr` list
+    pub fn set_attr(&mut self, flag: &str, enabled: bool) {
+        if find(self.0, "attr").is_none() {
+            let position = self
+                .0
+                .iter()
+                .position(|item| head_of(item).is_some_and(|h| h == "pad" || h.starts_with("fp_")))
+                .unwrap_or(self.0.len());
+            self.0
+                .insert(position, Sexpr::list(vec![Sexpr::symbol("attr")]));
+        }
+        let attr = find_mut(self.0, "attr").expect("attr was just inserted");
+        attr.retain(|s| s.as_sym() != Some(flag));
+        if enabled {
+            attr.push(Sexpr::symbol(flag));
+        }
+        // KiCad 9 also accepts the flags as `(dnp yes)` style children; keep them consistent
+        self.0.retain(|item| !is_list_named(item, flag));
+    }
+
+    pub fn set_at(&mut self, x: f64, y: f64, rotation: f64) {
+        let mut at = vec![Sexpr::F64(x), Sexpr::F64(y)];
+        if rotation != 0.0 {
+            at.push(Sexpr::F64(rotation));
+        }
+        set_child(self.0, "at", at);
+    }
+
+    /// Assign the net of every pad with `number` (clearing it when `net` is `None`)
+    pub fn set_pad_net(&mut self, number: &str, net: Option<(i64, &str)>) {
+        for pad in self.0.iter_mut().filter_map(|item| {
+            item.as_list_mut()
+                .filter(|items| head(items) == Some("pad"))
+                .filter(|items| items.get(1).and_then(|s| s.as_atom()) == Some(number))
+        }) {
+            pad.retain(|item| !is_list_named(item, "net"));
+            if let Some((code, name)) = net {
+                let decl = net_decl(code, name);
+                let position = pad
+                    .iter()
+                    .position(|item| is_list_named(item, "uuid") || is_list_named(item, "tstamp"))
+                    .unwrap_or(pad.len());
+                pad.insert(position, decl);
+            }
+        }
+    }
+
+    /// Pad numbers with their current net names
+    pub fn pad_nets(&self) -> Vec<(String, Option<String>)> {
+        self.view()
+            .pads()
+            .filter_map(|pad| {
+                let number = pad.get(1)?.as_atom()?.to_owned();
+                let net = find(pad, "net")
+                    .and_then(|net| net.get(2))
+                    .and_then(|s| s.as_atom())
+                    .map(str::to_owned);
+                Some((number, net))
+            })
+            .collect()
+    }
+
+    fn set_fp_text(&mut self, kind: &str, text: &str) -> bool {
+        let Some(items) = self.0.iter_mut().find_map(|item| {
+            item.as_list_mut()
+                .filter(|items| head(items) == Some("fp_text"))
+                .filter(|items| items.get(1).and_then(|s| s.as_sym()) == Some(kind))
+        }) else {
+            return false;
+        };
+        if items.len() > 2 {
+            items[2] = Sexpr::string(text);
+        }
+        true
+    }
+}
+
+/// Turn a footprint loaded from a `.kicad_mod` file into a board footprint at the origin
+pub(crate) fn instantiate_footprint(mut module: Vec<Sexpr>, fpid: &str) -> Vec<Sexpr> {
+    module[0] = Sexpr::symbol("footprint");
+    if module.len() > 1 {
+        module[1] = Sexpr::string(fpid);
+    }
+    module.retain(|item| {
+        !head_of(item)
+            .is_some_and(|h| matches!(h, "version" | "generator" | "generator_version" | "tedit"))
+    });
+    // Library footprints may carry a bare `locked`/`placed` flag after the name
+    module.retain(|item| !matches!(item.as_sym(), Some("placed")));
+
+    regenerate_uuids(&mut module);
+    if find(&module, "layer").is_none() {
+        module.insert(
+            2.min(module.len()),
+            pcb_sexpr::kv("layer", Sexpr::string("F.Cu")),
+        );
+    }
+    let after_layer = module
+        .iter()
+        .position(|item| is_list_named(item, "layer"))
+        .map(|i| i + 1)
+        .unwrap_or(module.len());
+    module.retain(|item| !is_list_named(item, "uuid") && !is_list_named(item, "tstamp"));
+    let after_layer = after_layer.min(module.len());
+    module.insert(
+        after_layer,
+        pcb_sexpr::kv("uuid", Sexpr::string(Uuid::new_v4().to_string())),
+    );
+    module.insert(
+        after_layer + 1,
+        Sexpr::list(vec![Sexpr::symbol("at"), Sexpr::Int(0), Sexpr::Int(0)]),
+    );
+    module
+}
+
+/// Copy placement (position, orientation and side) from `from` onto a freshly loaded footprint
+pub(crate) fn copy_placement(target: &mut Vec<Sexpr>, from: Footprint<'_>) {
+    let (x, y, rotation) = from.at();
+    if from.is_back() {
+        flip_to_back(target);
+    }
+    if rotation != 0.0 {
+        rotate_children(target, rotation);
+    }
+    FootprintMut(target).set_at(x, y, rotation);
+    if from.locked() {
+        set_child(target, "locked", vec![Sexpr::symbol("yes")]);
+    }
+}
+
+/// Mirror a front-side footprint to the back side (KiCad's top/bottom flip)
+fn flip_to_back(items: &mut [Sexpr]) {
+    for item in items.iter_mut() {
+        let Some(list) = item.as_list_mut() else {
+            continue;
+        };
+        match head(list) {
+            Some("layer") | Some("layers") => {
+                for layer in list.iter_mut().skip(1) {
+                    if let Some(name) = layer.as_atom() {
+                        *layer = Sexpr::string(flip_layer(name));
+                    }
+                }
+            }
+            Some("at") | Some("start") | Some("end") | Some("mid") | Some("center")
+            | Some("xy") => {
+                if let Some(y) = list.get(2).and_then(number) {
+                    list[2] = Sexpr::F64(-y);
+                }
+                if head(list) == Some("at") {
+                    if let Some(angle) = list.get(3).and_then(number) {
+                        list[3] = Sexpr::F64(-angle);
+                    }
+                }
+            }
+            _ => flip_to_back(&mut list[1..]),
+        }
+    }
+}
+
+fn flip_layer(name: &str) -> String {
+    if let Some(rest) = name.strip_prefix("F.") {
+        format!("B.{rest}")
+    } else if let Some(rest) = name.strip_prefix("B.") {
+        format!("F.{rest}")
+    } else {
+        name.to_owned()
+    }
+}
+
+/// KiCad stores pad and text orientations in board space, so they follow the footprint rotation
+fn rotate_children(items: &mut [Sexpr], rotation: f64) {
+    for item in items.iter_mut() {
+        let Some(list) = item.as_list_mut() else {
+            continue;
+        };
+        if !matches!(head(list), Some("pad") | Some("property") | Some("fp_text")) {
+            continue;
+        }
+        if let Some(at) = find_mut(list, "at") {
+            let angle = at.get(3).and_then(number).unwrap_or(0.0);
+            let rotated = (angle + rotation).rem_euclid(360.0);
+            at.truncate(3);
+            at.push(Sexpr::F64(rotated));
+        }
+    }
+}
+
+fn regenerate_uuids(items: &mut [Sexpr]) {
+    for item in items.iter_mut() {
+        if let Some(list) = item.as_list_mut() {
+            if matches!(head(list), Some("uuid") | Some("tstamp")) && list.len() == 2 {
+                list[1] = Sexpr::string(Uuid::new_v4().to_string());
+            } else {
+                regenerate_uuids(&mut list[1..]);
+            }
+        }
+    }
+}
+
+fn default_effects() -> Sexpr {
+    Sexpr::list(vec![
+        Sexpr::symbol("effects"),
+        Sexpr::list(vec![
+            Sexpr::symbol("font"),
+            Sexpr::list(vec![Sexpr::symbol("size"), Sexpr::Int(1), Sexpr::Int(1)]),
+            pcb_sexpr::kv("thickness", 0.15),
+        ]),
+    ])
+}
+
+fn is_hidden(items: &[Sexpr]) -> bool {
+    find(items, "hide").is_some_and(|hide| hide.get(1).and_then(|s| s.as_sym()) != Some("no"))
+        || find(items, "effects").is_some_and(|effects| {
+            effects.iter().any(|s| s.as_sym() == Some("hide"))
+                || find(effects, "hide")
+                    .is_some_and(|hide| hide.get(1).and_then(|s| s.as_sym()) != Some("no"))
+        })
+}
+
+fn set_hidden(items: &mut Vec<Sexpr>, hidden: bool) {
+    if let Some(effects) = find_mut(items, "effects") {
+        effects.retain(|s| s.as_sym() != Some("hide") && !is_list_named(s, "hide"));
+    }
+    items.retain(|item| !is_list_named(item, "hide"));
+    if hidden {
+        let position = items
+            .iter()
+            .position(|item| is_list_named(item, "uuid") || is_list_named(item, "effects"))
+            .unwrap_or(items.len());
+        items.insert(position, pcb_sexpr::kv("hide", Sexpr::symbol("yes")));
+    }
+}
+
+/// `(net <code> "<name>")`
+pub(crate) fn net_decl(code: i64, name: &str) -> Sexpr {
+    Sexpr::list(vec![
+        Sexpr::symbol("net"),
+        Sexpr::Int(code),
+        Sexpr::string(name),
+    ])
+}
+
+/// Transform a footprint-local point into board coordinates
+pub(crate) fn to_board(x: f64, y: f64, rotation: f64, lx: f64, ly: f64) -> (f64, f64) {
+    let (sin, cos) = rotation.to_radians().sin_cos();
+    (x + lx * cos + ly * sin, y - lx * sin + ly * cos)
+}
+
+/// Points that describe a graphic item (endpoints, corners, circle extents)
+pub(crate) fn graphic_points(items: &[Sexpr]) -> Vec<(f64, f64)> {
+    let point = |name: &str| find(items, name).map(|p| (number_at(p, 1), number_at(p, 2)));
+    let mut points = Vec::new();
+    match head(items) {
+        Some("fp_circle") | Some("gr_circle") => {
+            if let (Some(center), Some(end)) = (point("center"), point("end")) {
+                let r = ((end.0 - center.0).powi(2) + (end.1 - center.1).powi(2)).sqrt();
+                points.push((center.0 - r, center.1 - r));
+                points.push((center.0 + r, center.1 + r));
+            }
+        }
+        _ => {
+            points.extend(["start", "mid", "end"].into_iter().filter_map(point));
+            if let Some(pts) = find(items, "pts") {
+                points.extend(find_all(pts, "xy").map(|xy| (number_at(xy, 1), number_at(xy, 2))));
+            }
+        }
+    }
+    points
+}
+
+/// Parse `(at x y [angle])`
+pub(crate) fn parse_at(items: &[Sexpr]) -> (f64, f64, f64) {
+    (
+        number_at(items, 1),
+        number_at(items, 2),
+        number_at(items, 3),
+    )
+}
+
+pub(crate) fn number(sexpr: &Sexpr) -> Option<f64> {
+    match sexpr {
+        Sexpr::Int(n) => Some(*n as f64),
+        Sexpr::F64(f) => Some(*f),
+        Sexpr::Symbol(s) | Sexpr::String(s) => s.parse().ok(),
+        Sexpr::List(_) => None,
+    }
+}
+
+pub(crate) fn number_at(items: &[Sexpr], index: usize) -> f64 {
+    items.get(index).and_then(number).unwrap_or(0.0)
+}
+
+/// Name of a list (its leading symbol)
+pub(crate) fn head(items: &[Sexpr]) -> Option<&str> {
+    items.first().and_then(|s| s.as_sym())
+}
+
+pub(crate) fn head_of(sexpr: &Sexpr) -> Option<&str> {
+    sexpr.as_list().and_then(head)
+}
+
+pub(crate) fn is_list_named(sexpr: &Sexpr, name: &str) -> bool {
+    head_of(sexpr) == Some(name)
+}
+
+/// First child list called `name`
+pub(crate) fn find<'a>(items: &'a [Sexpr], name: &str) -> Option<&'a [Sexpr]> {
+    find_all(items, name).next()
+}
+
+/// All child lists called `name`
+pub(crate) fn find_all<'a, 'n>(
+    items: &'a [Sexpr],
+    name: &'n str,
+) -> impl Iterator<Item = &'a [Sexpr]> + use<'a, 'n> {
+    items
+        .iter()
+        .filter_map(|item| item.as_list())
+        .filter(move |list| head(list) == Some(name))
+}
+
+pub(crate) fn find_mut<'a>(items: &'a mut [Sexpr], name: &str) -> Option<&'a mut Vec<Sexpr>> {
+    items
+        .iter_mut()
+        .filter_map(|item| item.as_list_mut())
+        .find(|list| head(list) == Some(name))
+}
+
+/// Replace the arguments of child `name`, adding the child if it is missing
+pub(crate) fn set_child(items: &mut Vec<Sexpr>, name: &str, args: Vec<Sexpr>) {
+    let list: Vec<Sexpr> = std::iter::once(Sexpr::symbol(name)).chain(args).collect();
+    match items.iter_mut().find(|item| is_list_named(item, name)) {
+        Some(existing) => *existing = Sexpr::List(list),
+        None => items.push(Sexpr::List(list)),
+    }
+}
+
+/// Write an S-expression the way KiCad's prettifier does: lists of atoms stay on one line,
+/// any list containing lists puts each child list on its own tab-indented line.
+fn write_kicad(sexpr: &Sexpr, depth: usize, out: &mut String) {
+    let Sexpr::List(items) = sexpr else {
+        write_atom(sexpr, out);
+        return;
+    };
+    out.push('(');
+    let mut multiline = false;
+    let mut previous_xy = false;
+    for (i, item) in items.iter().enumerate() {
+        if item.is_list() {
+            // Point lists are packed several to a line, as KiCad does for `pts`
+            let is_xy = is_list_named(item, "xy");
+            if !(is_xy && previous_xy) {
+                out.push('\n');
+                out.push_str(&"\t".repeat(depth + 1));
+            } else {
+                out.push(' ');
+            }
+            previous_xy = is_xy;
+            multiline = true;
+            write_kicad(item, depth + 1, out);
+        } else {
+            if i > 0 {
+                out.push(' ');
+            }
+            write_atom(item, out);
+        }
+    }
+    if multiline {
+        out.push('\n');
+        out.push_str(&"\t".repeat(depth));
+    }
+    out.push(')');
+}
+
+fn write_atom(sexpr: &Sexpr, out: &mut String) {
+    match sexpr {
+        Sexpr::Symbol(s) => out.push_str(s),
+        Sexpr::String(s) => {
+            out.push('"');
+            for ch in s.chars() {
+                match ch {
+                    '"' => out.push_str("\\\""),
+                    '\\' => out.push_str("\\\\"),
+                    '\n' => out.push_str("\\n"),
+                    _ => out.push(ch),
+                }
+            }
+            out.push('"');
+        }
+        Sexpr::Int(n) => out.push_str(&n.to_string()),
+        Sexpr::F64(f) => out.push_str(&format_mm(*f)),
+        Sexpr::List(_) => unreachable!("lists are handled by write_kicad"),
+    }
+}
+
+/// Format a coordinate with KiCad's precision (nanometers), trimming trailing zeros
+fn format_mm(value: f64) -> String {
+    let mut s = format!("{value:.6}");
+    while s.ends_with('0') {
+        s.pop();
+    }
+    if s.ends_with('.') {
+        s.pop();
+    }
+    if s == "-0" {
+        s = "0".to_owned();
+    }
+    s
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_kicad_formatting() {
+        let board = Board::parse(
+            r#"(kicad_pcb (version 20241229) (general (thickness 1.6))
+                (gr_poly (pts (xy 0 0) (xy 1.5 0) (xy 1.5 1)) (layer "F.Cu")))"#,
+        )
+        .unwrap();
+        assert_eq!(
+            board.to_kicad_string(),
+            "(kicad_pcb\n\t(version 20241229)\n\t(general\n\t\t(thickness 1.6)\n\t)\n\t(gr_poly\n\t\t(pts\n\t\t\t(xy 0 0) (xy 1.5 0) (xy 1.5 1)\n\t\t)\n\t\t(layer \"F.Cu\")\n\t)\n)\n"
+        );
+    }
+
+    #[test]
+    fn test_set_nets_replaces_table_in_place() {
+        let mut board = Board::parse(
+            r#"(kicad_pcb (version 20241229) (setup) (net 0 "") (net 1 "OLD") (footprint "a:b"))"#,
+        )
+        .unwrap();
+        board.set_nets(&["GND".to_owned(), "VCC".to_owned()]);
+        let nets = board.nets();
+        assert_eq!(nets.get(&1).map(String::as_str), Some("GND"));
+        assert_eq!(nets.get(&2).map(String::as_str), Some("VCC"));
+        // Net declarations stay ahead of the footprints
+        let last_net = board
+            .items()
+            .iter()
+            .rposition(|item| is_list_named(item, "net"))
+            .unwrap();
+        let first_fp = board
+            .items()
+            .iter()
+            .position(|item| is_list_named(item, "footprint"))
+            .unwrap();
+        assert!(last_net < first_fp);
+    }
+
+    #[test]
+    fn test_footprint_fields_and_flags() {
+        let mut items = match pcb_sexpr::parse(
+            r#"(footprint "lib:R" (layer "F.Cu") (at 10 20 90)
+                (property "Reference" "R1" (at 0 0 0))
+                (property "Value" "10k" (at 0 0 0) (hide yes))
+                (property "Path" "R1.R" (at 0 0 0))
+                (attr smd)
+                (pad "1" smd rect (at -1 0) (size 1 1) (layers "F.Cu"))
+                (pad "2" smd rect (at 1 0) (size 1 1) (layers "F.Cu")))"#,
+        )
+        .unwrap()
+        {
+            Sexpr::List(items) => items,
+            _ => unreachable!(),
+        };
+
+        let fp = Footprint(&items);
+        assert_eq!(fp.reference(), "R1");
+        assert_eq!(fp.property_visible("Value"), Some(false));
+        assert_eq!(fp.fields(), vec![("Path", "R1.R")]);
+        assert!(!fp.has_attr("dnp"));
+
+        let mut fp = FootprintMut(&mut items);
+        fp.set_attr("dnp", true);
+        fp.set_property_visible("Path", false);
+        fp.set_pad_net("1", Some((3, "GND")));
+        fp.set_kiid_path("abc");
+        let fp = fp.view();
+        assert!(fp.has_attr("dnp"));
+        assert_eq!(fp.property_visible("Path"), Some(false));
+        assert_eq!(fp.path_uuid(), Some("abc"));
+
+        // Rotated 90° CCW: pads end up above/below the origin
+        let bbox = fp.bbox();
+        assert!((bbox.min_y - 18.5).abs() < 1e-9 && (bbox.max_y - 21.5).abs() < 1e-9);
+    }
+}
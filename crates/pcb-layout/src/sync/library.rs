@@ -0,0 +1,196 @@
+//! Footprint library resolution for the native layout sync.
+//!
+//! Follows the same lookup order as `ImportNetlist._load_footprint_lib_map` in the Python
+//! script: global `fp-lib-table` files first, then the layout's local table, and finally the
+//! stock KiCad footprint directory.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use pcb_sexpr::Sexpr;
+
+use super::board::{find, find_all, head};
+
+/// Footprint libraries available to a layout
+#[derive(Debug, Default)]
+pub(crate) struct FootprintLibraries {
+    /// Library nickname -> directory containing `<name>.kicad_mod` files
+    libs: HashMap<String, PathBuf>,
+    /// Stock KiCad footprint directory (`<lib>.pretty` folders)
+    stock_dir: Option<PathBuf>,
+}
+
+impl FootprintLibraries {
+    /// Discover the libraries visible from a layout directory
+    pub fn discover(layout_dir: &Path) -> Self {
+        let stock_dir = std::env::var("KICAD9_FOOTPRINT_DIR")
+            .ok()
+            .map(PathBuf::from)
+            .or_else(default_footprint_dir);
+
+        let mut vars: HashMap<&str, String> = HashMap::new();
+        vars.insert("KIPRJMOD", layout_dir.display().to_string());
+        if let Some(dir) = &stock_dir {
+            vars.insert("KICAD9_FOOTPRINT_DIR", dir.display().to_string());
+        }
+
+        let mut libraries = Self {
+            libs: HashMap::new(),
+            stock_dir,
+        };
+        for table in global_lib_tables() {
+            libraries.load_table(&table, &vars);
+        }
+        libraries.load_table(&layout_dir.join("fp-lib-table"), &vars);
+        libraries
+    }
+
+    /// Path of the `.kicad_mod` file for a `lib:name` footprint identifier
+    pub fn resolve(&self, fpid: &str) -> Option<PathBuf> {
+        let (lib, name) = fpid.split_once(':')?;
+        let file = format!("{name}.kicad_mod");
+        self.libs
+            .get(lib)
+            .map(|dir| dir.join(&file))
+            .filter(|path| path.exists())
+            .or_else(|| {
+                let path = self
+                    .stock_dir
+                    .as_ref()?
+                    .join(format!("{lib}.pretty"))
+                    .join(&file);
+                path.exists().then_some(path)
+            })
+    }
+
+    fn load_table(&mut self, path: &Path, vars: &HashMap<&str, String>) {
+        let Ok(content) = std::fs::read_to_string(path) else {
+            return;
+        };
+        let Ok(Sexpr::List(table)) = pcb_sexpr::parse(&content) else {
+            log::warn!(
+                "Ignoring unreadable footprint library table {}",
+                path.display()
+            );
+            return;
+        };
+        for lib in find_all(&table, "lib") {
+            if find(lib, "disabled").is_some() {
+                continue;
+            }
+            let field = |name: &str| {
+                find(lib, name)
+                    .and_then(|items| items.get(1))
+                    .and_then(|s| s.as_atom())
+            };
+            if !field("type").is_some_and(|t| t.to_lowercase().contains("kicad")) {
+                continue;
+            }
+            let (Some(name), Some(uri)) = (field("name"), field("uri")) else {
+                continue;
+            };
+            self.libs
+                .insert(name.to_owned(), PathBuf::from(expand_vars(uri, vars)));
+        }
+    }
+}
+
+/// Load a footprint definition, returning the items of its top-level list
+pub(crate) fn load_footprint(path: &Path) -> Result<Vec<Sexpr>, String> {
+    let content = std::fs::read_to_string(path)
+        .map_err(|e| format!("failed to read {}: {e}", path.display()))?;
+    match pcb_sexpr::parse(&content) {
+        Ok(Sexpr::List(items)) if matches!(head(&items), Some("footprint") | Some("module")) => {
+            Ok(items)
+        }
+        Ok(_) => Err(format!("{} is not a footprint", path.display())),
+        Err(e) => Err(format!("failed to parse {}: {e}", path.display())),
+    }
+}
+
+/// Expand `${VAR}` references using `vars`, then the process environment
+fn expand_vars(uri: &str, vars: &HashMap<&str, String>) -> String {
+    let mut out = String::with_capacity(uri.len());
+    let mut rest = uri;
+    while let Some(start) = rest.find("${") {
+        out.push_str(&rest[..start]);
+        let Some(len) = rest[start + 2..].find('}') else {
+            out.push_str(&rest[start..]);
+            return out;
+        };
+        let name = &rest[start + 2..start + 2 + len];
+        match vars.get(name).cloned().or_else(|| std::env::var(name).ok()) {
+            Some(value) => out.push_str(&value),
+            None => out.push_str(&rest[start..start + 3 + len]),
+        }
+        rest = &rest[start + 3 + len..];
+    }
+    out.push_str(rest);
+    out
+}
+
+fn global_lib_tables() -> Vec<PathBuf> {
+    let mut dirs = Vec::new();
+    if let Some(config) = dirs::config_dir() {
+        dirs.push(config.join("kicad"));
+        dirs.push(config.join("kicad").join("9.0"));
+    }
+    if let Some(home) = dirs::home_dir() {
+        dirs.push(home.join("Library/Preferences/kicad"));
+        dirs.push(home.join(".config/kicad"));
+    }
+    dirs.push(PathBuf::from("/usr/share/kicad/template"));
+    dirs.push(PathBuf::from(
+        "/Applications/KiCad/KiCad.app/Contents/SharedSupport/template",
+    ));
+    dirs.dedup();
+    dirs.into_iter()
+        .map(|dir| dir.join("fp-lib-table"))
+        .collect()
+}
+
+fn default_footprint_dir() -> Option<PathBuf> {
+    let dir = if cfg!(target_os = "windows") {
+        PathBuf::from("C:/Program Files/KiCad/9.0/share/kicad/footprints")
+    } else if cfg!(target_os = "macos") {
+        PathBuf::from("/Applications/KiCad/KiCad.app/Contents/SharedSupport/footprints")
+    } else {
+        PathBuf::from("/usr/share/kicad/footprints")
+    };
+    dir.exists().then_some(dir)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_expand_vars() {
+        let vars = HashMap::from([("KIPRJMOD", "/board".to_owned())]);
+        assert_eq!(expand_vars("${KIPRJMOD}/fp", &vars), "/board/fp");
+        assert_eq!(
+            expand_vars("${PCB_UNDEFINED_VAR}/fp", &vars),
+            "${PCB_UNDEFINED_VAR}/fp"
+        );
+    }
+
+    #[test]
+    fn test_local_table_resolution() {
+        let dir = tempfile::tempdir().unwrap();
+        let lib_dir = dir.path().join("parts");
+        std::fs::create_dir_all(&lib_dir).unwrap();
+        std::fs::write(lib_dir.join("R_0603.kicad_mod"), "(footprint \"R_0603\")").unwrap();
+        std::fs::write(
+            dir.path().join("fp-lib-table"),
+            r#"(fp_lib_table (version 7)
+                (lib (name "parts") (type "KiCad") (uri "${KIPRJMOD}/parts") (options "") (descr "")))"#,
+        )
+        .unwrap();
+
+        let libs = FootprintLibraries::discover(dir.path());
+        let path = libs.resolve("parts:R_0603").unwrap();
+        assert_eq!(path, lib_dir.join("R_0603.kicad_mod"));
+        assert!(load_footprint(&path).is_ok());
+        assert!(libs.resolve("parts:missing").is_none());
+    }
+}
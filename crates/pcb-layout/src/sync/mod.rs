@@ -0,0 +1,1267 @@
+//! Native layout sync: updates a `.kicad_pcb` from a schematic without KiCad's Python API.
+//!
+//! This ports the `SetupBoard`, `ApplyMovedPaths`, `ImportNetlist`, `PlaceComponents` and
+//! `FinalizeBoard` steps of `scripts/update_layout_file.py` onto a plain S-expression model
+//! of the board, producing the same diagnostics and layout snapshot. Designs that need
+//! something only the Python script implements (instantiating module layout fragments,
+//! footprints only pcbnew can locate) are reported as
+//! [`LayoutError::NativeSyncUnsupported`] so the caller can fall back to the script.
+
+mod board;
+mod library;
+mod netlist;
+mod placement;
+mod project;
+mod snapshot;
+
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use pcb_sexpr::Sexpr;
+use uuid::Uuid;
+
+use crate::{LayoutError, LayoutSyncDiagnostic, LayoutSyncDiagnostics};
+use board::{
+    copy_placement, find, find_mut, head, head_of, instantiate_footprint, is_list_named, Board,
+    Footprint, FootprintMut,
+};
+use library::{load_footprint, FootprintLibraries};
+use placement::PlacementItem;
+
+/// Environment variable selecting the layout sync engine (`native` or `python`)
+pub const LAYOUT_ENGINE_ENV: &str = "PCB_LAYOUT_ENGINE";
+
+/// Properties that never become footprint fields
+const SKIP_PROPERTIES: &[&str] = &["value", "reference", "symbol_name", "symbol_path"];
+
+/// Which implementation performs the board sync
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LayoutEngine {
+    /// Pure Rust sync, falling back to Python for unsupported designs
+    #[default]
+    Native,
+    /// The `update_layout_file.py` script run through KiCad's Python
+    Python,
+}
+
+impl LayoutEngine {
+    /// Engine selected by `PCB_LAYOUT_ENGINE`, defaulting to [`LayoutEngine::Native`]
+    pub fn from_env() -> Self {
+        match std::env::var(LAYOUT_ENGINE_ENV) {
+            Ok(value) if value.eq_ignore_ascii_case("python") => Self::Python,
+            Ok(value) if !value.is_empty() && !value.eq_ignore_ascii_case("native") => {
+                log::warn!("Unknown {LAYOUT_ENGINE_ENV} value '{value}', using the native engine");
+                Self::Native
+            }
+            _ => Self::Native,
+        }
+    }
+}
+
+/// Flattened schematic data the sync operates on
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Netlist {
+    /// Components, sorted by hierarchical path
+    pub parts: Vec<Part>,
+    /// Nets with at least one pad, sorted by name
+    pub nets: Vec<PartNet>,
+    /// Module path -> `layout_path` attribute
+    pub modules: BTreeMap<String, Option<String>>,
+    /// Old path -> new path, from `moved()` directives
+    pub moved_paths: BTreeMap<String, String>,
+}
+
+/// A component that should have a footprint on the board
+#[derive(Debug, Clone)]
+pub(crate) struct Part {
+    /// Hierarchical path, e.g. `Power.Regulator.C1`
+    pub path: String,
+    /// UUIDv5 of `path`; the footprint's identity on the board
+    pub uuid: String,
+    pub reference: String,
+    pub value: String,
+    /// `lib:name` footprint identifier
+    pub fpid: String,
+    /// `.kicad_mod` file when the footprint attribute points at a file
+    pub footprint_file: Option<PathBuf>,
+    /// Remaining attributes, stringified
+    pub properties: Vec<(String, String)>,
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct PartNet {
+    pub name: String,
+    /// `(reference, pad number)` pairs
+    pub nodes: Vec<(String, String)>,
+}
+
+/// Files and options for one sync run
+pub(crate) struct SyncRequest<'a> {
+    pub netlist: &'a Netlist,
+    pub pcb: &'a Path,
+    pub snapshot: &'a Path,
+    pub diagnostics: &'a Path,
+    pub log: &'a Path,
+    /// Serialized board config to apply, if board config sync is enabled
+    pub board_config: Option<&'a serde_json::Value>,
+    pub dry_run: bool,
+}
+
+/// Sync the board at `request.pcb` with the netlist.
+///
+/// In dry-run mode the board is only inspected and the diagnostics file describes what
+/// `pcb layout` would change; otherwise the board, its project file and the snapshot are
+/// written and the diagnostics file is empty.
+pub(crate) fn sync(request: &SyncRequest) -> Result<(), LayoutError> {
+    if !request.dry_run {
+        if let Some((path, layout)) = request
+            .netlist
+            .modules
+            .iter()
+            .find_map(|(path, layout)| Some((path, layout.as_ref()?)))
+        {
+            return Err(LayoutError::NativeSyncUnsupported(format!(
+                "module '{path}' has a layout ({layout})"
+            )));
+        }
+    }
+
+    let mut board = if request.pcb.exists() {
+        let content = std::fs::read_to_string(request.pcb)?;
+        Board::parse(&content).map_err(|e| {
+            LayoutError::NativeSyncUnsupported(format!(
+                "cannot read {}: {e}",
+                request.pcb.display()
+            ))
+        })?
+    } else {
+        Board::empty()
+    };
+    let layout_dir = request.pcb.parent().unwrap_or(Path::new("."));
+    let mut sync = Sync {
+        netlist: request.netlist,
+        libraries: FootprintLibraries::discover(layout_dir),
+        log: SyncLog::default(),
+        diagnostics: Vec::new(),
+        added: Vec::new(),
+    };
+
+    let result = if request.dry_run {
+        sync.check_footprints(&board);
+        Ok(())
+    } else {
+        sync.run(&mut board, request)
+    };
+    std::fs::write(request.log, sync.log.contents())?;
+    result?;
+
+    let diagnostics = LayoutSyncDiagnostics {
+        diagnostics: sync.diagnostics,
+    };
+    std::fs::write(
+        request.diagnostics,
+        serde_json::to_string_pretty(&diagnostics)?,
+    )?;
+    Ok(())
+}
+
+struct Sync<'a> {
+    netlist: &'a Netlist,
+    libraries: FootprintLibraries,
+    log: SyncLog,
+    diagnostics: Vec<LayoutSyncDiagnostic>,
+    /// Path UUIDs of footprints added in this run
+    added: Vec<String>,
+}
+
+impl Sync<'_> {
+    fn run(&mut self, board: &mut Board, request: &SyncRequest) -> Result<(), LayoutError> {
+        self.setup_board(board, request)?;
+        self.apply_moved_paths(board);
+        let old_pad_nets = pad_nets_by_footprint(board);
+        self.sync_footprints(board)?;
+        self.sync_nets(board, &old_pad_nets);
+        self.sync_groups(board);
+        self.place_new_footprints(board);
+
+        let snapshot = snapshot::snapshot(board);
+        std::fs::write(request.snapshot, serde_json::to_string_pretty(&snapshot)?)?;
+        std::fs::write(request.pcb, board.to_kicad_string())?;
+        self.log
+            .info(format!("Saved board to {}", request.pcb.display()));
+        Ok(())
+    }
+
+    /// Title block placeholders and board config (design rules live in the `.kicad_pro`)
+    fn setup_board(&mut self, board: &mut Board, request: &SyncRequest) -> Result<(), LayoutError> {
+        board.set_title_block(&[
+            ("title", "${PCB_NAME}"),
+            ("date", "${CURRENT_DATE}"),
+            ("rev", "${PCB_VERSION}"),
+        ]);
+
+        let pro_path = request.pcb.with_extension("kicad_pro");
+        let existed = pro_path.exists();
+        let mut pro = project::load(&pro_path).map_err(|e| anyhow::anyhow!(e))?;
+        let changes = request
+            .board_config
+            .map(|config| project::apply_board_config(&mut pro, config))
+            .unwrap_or_default();
+        for change in &changes {
+            self.log.info(format!("Board config: {change}"));
+        }
+        if !existed || !changes.is_empty() {
+            project::save(&pro_path, &pro).map_err(|e| anyhow::anyhow!(e))?;
+        }
+        Ok(())
+    }
+
+    /// Re-key footprints and groups renamed by `moved()` so they are not recreated
+    fn apply_moved_paths(&mut self, board: &mut Board) {
+        if self.netlist.moved_paths.is_empty() {
+            return;
+        }
+        let remap = |path: &str| remap_path(&self.netlist.moved_paths, path);
+
+        let mut renamed = Vec::new();
+        for item in board.items_mut().iter_mut().skip(1) {
+            let Some(items) = item
+                .as_list_mut()
+                .filter(|items| head(items) == Some("footprint"))
+            else {
+                continue;
+            };
+            let Some(old_path) = Footprint(items).property("Path").map(str::to_owned) else {
+                continue;
+            };
+            let Some(new_path) = remap(&old_path).filter(|new| *new != old_path) else {
+                continue;
+            };
+            let uuid = Uuid::new_v5(&Uuid::NAMESPACE_URL, new_path.as_bytes()).to_string();
+            let mut fp = FootprintMut(items);
+            fp.set_property("Path", &new_path, None);
+            fp.set_kiid_path(&uuid);
+            renamed.push(format!("footprint {old_path} -> {new_path}"));
+        }
+
+        for (old_name, index) in board.groups() {
+            if let Some(new_name) = remap(&old_name).filter(|new| *new != old_name) {
+                if let Some(group) = board.items_mut()[index].as_list_mut() {
+                    group[1] = Sexpr::string(&new_name);
+                }
+                renamed.push(format!("group {old_name} -> {new_name}"));
+            }
+        }
+
+        for line in renamed {
+            self.log.debug(format!("Remapping {line}"));
+        }
+    }
+
+    /// Dry-run footprint check: report what `sync_footprints` would do
+    fn check_footprints(&mut self, board: &Board) {
+        let netlist = self.netlist;
+        let parts = netlist::parts_by_uuid(&netlist.parts);
+        let mut on_board = BTreeSet::new();
+
+        for fp in board.footprints() {
+            let uuid = fp.path_uuid().unwrap_or_default();
+            on_board.insert(uuid);
+            let Some(part) = parts.get(uuid) else {
+                let path_info = fp
+                    .property("Path")
+                    .filter(|p| !p.is_empty())
+                    .map(|p| format!(" at {p}"))
+                    .unwrap_or_default();
+                self.diagnostics.push(LayoutSyncDiagnostic {
+                    kind: "layout.sync.extra_footprint".to_owned(),
+                    severity: "warning".to_owned(),
+                    body: format!(
+                        "Footprint '{}' ({}){path_info} exists on board but not in netlist. \
+                         Run 'pcb layout' to remove it.",
+                        fp.reference(),
+                        fp.fpid()
+                    ),
+                    path: String::new(),
+                    reference: Some(fp.reference().to_owned()),
+                });
+                continue;
+            };
+
+            match compute_changes(fp, part, true) {
+                FootprintChanges::Replace => self.diagnostics.push(part_diagnostic(
+                    part,
+                    "layout.sync.fpid_mismatch",
+                    "error",
+                    format!(
+                        "Footprint '{}'{} should be '{}'. Run 'pcb layout' to replace it.",
+                        fp.fpid(),
+                        at_path(part),
+                        part.fpid
+                    ),
+                )),
+                FootprintChanges::Update(changes) if !changes.is_empty() => {
+                    let description: Vec<String> = changes.iter().map(Change::describe).collect();
+                    self.diagnostics.push(part_diagnostic(
+                        part,
+                        "layout.sync.metadata_mismatch",
+                        "warning",
+                        format!(
+                            "Metadata out of sync: {}. Run 'pcb layout' to update.",
+                            description.join(", ")
+                        ),
+                    ));
+                }
+                FootprintChanges::Update(_) => {}
+            }
+        }
+
+        for part in &netlist.parts {
+            if !on_board.contains(part.uuid.as_str()) {
+                self.diagnostics.push(part_diagnostic(
+                    part,
+                    "layout.sync.missing_footprint",
+                    "error",
+                    format!(
+                        "Footprint '{}' ({}){} is missing from board. Run 'pcb layout' to add it.",
+                        part.reference,
+                        part.fpid,
+                        at_path(part)
+                    ),
+                ));
+            }
+        }
+    }
+
+    /// Remove footprints not in the netlist, add missing ones and update the rest
+    fn sync_footprints(&mut self, board: &mut Board) -> Result<(), LayoutError> {
+        let netlist = self.netlist;
+        let parts = netlist::parts_by_uuid(&netlist.parts);
+
+        // Removals
+        let mut removed_kiids = HashSet::new();
+        board.items_mut().retain(|item| {
+            let Some(fp) = item
+                .as_list()
+                .filter(|items| head(items) == Some("footprint"))
+                .map(Footprint)
+            else {
+                return true;
+            };
+            if parts.contains_key(fp.path_uuid().unwrap_or_default()) {
+                return true;
+            }
+            self.log.info(format!(
+                "{} ({}): Removing from board",
+                fp.kiid_path(),
+                fp.reference()
+            ));
+            removed_kiids.extend(fp.uuid().map(str::to_owned));
+            false
+        });
+        remove_group_members(board, &removed_kiids);
+
+        for part in &netlist.parts {
+            match board.footprint_index(&part.uuid) {
+                None => {
+                    self.log.info(format!(
+                        "{} ({}): Adding to board",
+                        part.uuid, part.reference
+                    ));
+                    let mut items = instantiate_footprint(self.load_footprint(part)?, &part.fpid);
+                    if let FootprintChanges::Update(changes) =
+                        compute_changes(Footprint(&items), part, false)
+                    {
+                        apply_changes(&mut FootprintMut(&mut items), &changes, part);
+                    }
+                    board.items_mut().push(Sexpr::List(items));
+                    self.added.push(part.uuid.clone());
+                }
+                Some(index) => {
+                    let current = board.item(index).expect("footprint index points at a list");
+                    match compute_changes(Footprint(current), part, true) {
+                        FootprintChanges::Replace => {
+                            self.log.info(format!(
+                                "{} ({}): Replacing footprint '{}' -> '{}'",
+                                part.uuid,
+                                part.reference,
+                                Footprint(current).fpid(),
+                                part.fpid
+                            ));
+                            let replacement = self.replace_footprint(current, part)?;
+                            board.items_mut()[index] = Sexpr::List(replacement);
+                        }
+                        FootprintChanges::Update(changes) if !changes.is_empty() => {
+                            self.log.info(format!(
+                                "{} ({}): Updated {} field(s)",
+                                part.uuid,
+                                part.reference,
+                                changes.len()
+                            ));
+                            for change in &changes {
+                                self.log.debug(format!(
+                                    "  - {}: {} -> {}",
+                                    change.field, change.old, change.new
+                                ));
+                            }
+                            let items = board.items_mut()[index]
+                                .as_list_mut()
+                                .expect("footprint index points at a list");
+                            apply_changes(&mut FootprintMut(items), &changes, part);
+                        }
+                        FootprintChanges::Update(_) => {
+                            self.log
+                                .debug(format!("{}: No metadata changes detected", part.reference));
+                        }
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Swap in a different footprint, keeping placement, pad nets and the item UUID
+    fn replace_footprint(&self, old: &[Sexpr], part: &Part) -> Result<Vec<Sexpr>, LayoutError> {
+        let old = Footprint(old);
+        let mut items = instantiate_footprint(self.load_footprint(part)?, &part.fpid);
+        copy_placement(&mut items, old);
+
+        let old_nets: HashMap<String, (i64, String)> = old
+            .pads()
+            .filter_map(|pad| {
+                let number = pad.get(1)?.as_atom()?;
+                let net = find(pad, "net")?;
+                let code = net.get(1)?.as_int().filter(|code| *code > 0)?;
+                let name = net.get(2).and_then(|s| s.as_atom()).unwrap_or("");
+                Some((number.to_owned(), (code, name.to_owned())))
+            })
+            .collect();
+        let mut fp = FootprintMut(&mut items);
+        for (number, (code, name)) in &old_nets {
+            fp.set_pad_net(number, Some((*code, name)));
+        }
+        if let Some(uuid) = old.uuid() {
+            if let Some(item_uuid) = find_mut(fp.0, "uuid") {
+                item_uuid[1] = Sexpr::string(uuid);
+            }
+        }
+        fp.set_kiid_path(&part.uuid);
+
+        if let FootprintChanges::Update(changes) = compute_changes(fp.view(), part, false) {
+            apply_changes(&mut fp, &changes, part);
+        }
+        Ok(items)
+    }
+
+    fn load_footprint(&self, part: &Part) -> Result<Vec<Sexpr>, LayoutError> {
+        let path = part
+            .footprint_file
+            .clone()
+            .filter(|path| path.exists())
+            .or_else(|| self.libraries.resolve(&part.fpid))
+            .ok_or_else(|| {
+                LayoutError::NativeSyncUnsupported(format!(
+                    "footprint '{}' for {} not found in any footprint library",
+                    part.fpid, part.reference
+                ))
+            })?;
+        load_footprint(&path).map_err(LayoutError::NativeSyncUnsupported)
+    }
+
+    /// Rebuild the net table and assign pad nets from the netlist
+    fn sync_nets(
+        &mut self,
+        board: &mut Board,
+        old_pad_nets: &HashMap<String, Vec<(String, String)>>,
+    ) {
+        let old_codes = board.nets();
+
+        // Keep nets still used by copper so tracks and zones are not orphaned
+        let mut names: BTreeSet<String> =
+            self.netlist.nets.iter().map(|n| n.name.clone()).collect();
+        for item in board.items() {
+            if matches!(head_of(item), Some("segment" | "arc" | "via" | "zone")) {
+                let code = item.as_list().and_then(net_code);
+                if let Some(name) = code.and_then(|code| old_codes.get(&code)) {
+                    if !name.is_empty() {
+                        names.insert(name.clone());
+                    }
+                }
+            }
+        }
+        let names: Vec<String> = names.into_iter().collect();
+        let new_codes: HashMap<&str, i64> = names
+            .iter()
+            .enumerate()
+            .map(|(i, name)| (name.as_str(), i as i64 + 1))
+            .collect();
+
+        // Pad assignments: every pad of a managed footprint is cleared, then connected
+        let mut pad_nets: HashMap<&str, Vec<(&str, &str)>> = HashMap::new();
+        for net in &self.netlist.nets {
+            for (reference, pad) in &net.nodes {
+                pad_nets
+                    .entry(reference.as_str())
+                    .or_default()
+                    .push((pad.as_str(), net.name.as_str()));
+            }
+        }
+
+        let renames = net_renames(board, old_pad_nets, &pad_nets);
+        for (old, new) in &renames {
+            self.log.info(format!("RENAME: '{old}' -> '{new}'"));
+        }
+
+        board.set_nets(&names);
+        for item in board.items_mut().iter_mut().skip(1) {
+            let Some(items) = item.as_list_mut() else {
+                continue;
+            };
+            if head(items) == Some("footprint") {
+                let reference = Footprint(items).reference().to_owned();
+                let mut fp = FootprintMut(items);
+                let numbers: Vec<String> = fp.pad_nets().into_iter().map(|(n, _)| n).collect();
+                for number in &numbers {
+                    fp.set_pad_net(number, None);
+                }
+                for (pad, net) in pad_nets.get(reference.as_str()).into_iter().flatten() {
+                    fp.set_pad_net(pad, new_codes.get(net).map(|code| (*code, *net)));
+                }
+                continue;
+            }
+            let Some(old_name) = net_code(items).and_then(|code| old_codes.get(&code)) else {
+                continue;
+            };
+            // Zones and vias follow renamed nets, like `_fix_remaining_orphaned_items`
+            let name = match head(items) {
+                Some("zone" | "via") => renames.get(old_name).unwrap_or(old_name),
+                _ => old_name,
+            };
+            let code = new_codes.get(name.as_str()).copied().unwrap_or(0);
+            set_item_net(items, code, name);
+        }
+        self.log.info(format!("Synced {} nets", names.len()));
+    }
+
+    /// Create groups for modules and move footprints into them (`ImportNetlist._sync_groups`)
+    fn sync_groups(&mut self, board: &mut Board) {
+        let mut all_paths = BTreeSet::new();
+        let mut parts_at: HashMap<&str, usize> = HashMap::new();
+        for part in &self.netlist.parts {
+            let segments: Vec<&str> = part.path.split('.').collect();
+            *parts_at.entry(part.path.as_str()).or_default() += 1;
+            for len in 1..=segments.len() {
+                all_paths.insert(segments[..len].join("."));
+            }
+        }
+
+        let wanted: Vec<&String> = all_paths
+            .iter()
+            .filter(|path| {
+                let child_groups = all_paths
+                    .iter()
+                    .filter(|other| {
+                        other
+                            .strip_prefix(path.as_str())
+                            .and_then(|rest| rest.strip_prefix('.'))
+                            .is_some_and(|rest| !rest.contains('.'))
+                    })
+                    .count();
+                let has_layout = self
+                    .netlist
+                    .modules
+                    .get(path.as_str())
+                    .is_some_and(Option::is_some);
+                parts_at.get(path.as_str()).copied().unwrap_or(0) + child_groups > 1 || has_layout
+            })
+            .collect();
+
+        let existing: BTreeMap<String, String> = board
+            .groups()
+            .into_iter()
+            .filter_map(|(name, index)| Some((name, item_uuid(board.item(index)?)?.to_owned())))
+            .collect();
+
+        // Parents sort before their children, so they always exist first
+        let mut created: BTreeMap<String, String> = BTreeMap::new();
+        for path in wanted {
+            if let Some(uuid) = existing.get(path) {
+                created.insert(path.clone(), uuid.clone());
+                continue;
+            }
+            let uuid = Uuid::new_v4().to_string();
+            board.items_mut().push(Sexpr::list(vec![
+                Sexpr::symbol("group"),
+                Sexpr::string(path),
+                pcb_sexpr::kv("uuid", Sexpr::string(&uuid)),
+                Sexpr::list(vec![Sexpr::symbol("members")]),
+            ]));
+            self.log.info(format!("Created new group: {path}"));
+            if let Some(parent) = parent_paths(path).find_map(|parent| created.get(parent)) {
+                add_group_member(board, parent, &uuid);
+            }
+            created.insert(path.clone(), uuid);
+        }
+
+        for part in &self.netlist.parts {
+            let Some(group) = std::iter::once(part.path.as_str())
+                .chain(parent_paths(&part.path))
+                .find_map(|path| created.get(path))
+            else {
+                continue;
+            };
+            let Some(fp_uuid) = board
+                .footprint_index(&part.uuid)
+                .and_then(|index| board.item(index))
+                .and_then(|items| Footprint(items).uuid())
+                .map(str::to_owned)
+            else {
+                continue;
+            };
+            remove_group_members(board, &HashSet::from([fp_uuid.clone()]));
+            add_group_member(board, group, &fp_uuid);
+        }
+
+        // Drop groups that ended up empty
+        let empty: HashSet<String> = board
+            .groups()
+            .into_iter()
+            .filter(|(name, _)| existing.contains_key(name))
+            .filter_map(|(name, index)| {
+                let group = board.item(index)?;
+                let members = find(group, "members").map(|m| m.len() - 1).unwrap_or(0);
+                (members == 0).then(|| {
+                    self.log.info(format!("Removing empty group: {name}"));
+                    item_uuid(group).unwrap_or_default().to_owned()
+                })
+            })
+            .collect();
+        if !empty.is_empty() {
+            board.items_mut().retain(|item| {
+                !(is_list_named(item, "group")
+                    && item
+                        .as_list()
+                        .and_then(item_uuid)
+                        .is_some_and(|u| empty.contains(u)))
+            });
+            remove_group_members(board, &empty);
+        }
+    }
+
+    /// Pack footprints added in this run next to the existing content
+    fn place_new_footprints(&mut self, board: &mut Board) {
+        if self.added.is_empty() {
+            return;
+        }
+        let added: HashSet<&str> = self.added.iter().map(String::as_str).collect();
+        let groups = group_names_by_member(board);
+
+        let mut occupied: Option<board::BBox> = None;
+        let mut items = Vec::new();
+        let mut indices = Vec::new();
+        // `items()` skips the leading `kicad_pcb` symbol; placement indexes the full list
+        for (index, item) in board
+            .items()
+            .iter()
+            .enumerate()
+            .map(|(i, item)| (i + 1, item))
+        {
+            let Some(fp) = item
+                .as_list()
+                .filter(|items| head(items) == Some("footprint"))
+                .map(Footprint)
+            else {
+                continue;
+            };
+            if !added.contains(fp.path_uuid().unwrap_or_default()) {
+                let bbox = fp.bbox();
+                match occupied.as_mut() {
+                    Some(occupied) => occupied.merge(&bbox),
+                    None => occupied = Some(bbox),
+                }
+                continue;
+            }
+            items.push(PlacementItem {
+                id: indices.len(),
+                group: fp
+                    .uuid()
+                    .and_then(|uuid| groups.get(uuid))
+                    .cloned()
+                    .unwrap_or_default(),
+                key: fp.reference().to_owned(),
+                local_bbox: fp.local_bbox(),
+            });
+            indices.push(index);
+        }
+
+        for (id, x, y) in placement::place(&items, occupied) {
+            if let Some(items) = board.items_mut()[indices[id]].as_list_mut() {
+                let rotation = Footprint(items).at().2;
+                FootprintMut(items).set_at(x, y, rotation);
+            }
+        }
+        self.log
+            .info(format!("Placed {} new footprint(s)", items.len()));
+    }
+}
+
+/// A field value as the Python sync compares it
+#[derive(Debug, Clone, PartialEq)]
+enum FieldValue {
+    Text(String),
+    Flag(bool),
+    Missing,
+}
+
+/// Python `str()` formatting, so diagnostics read the same for both engines
+impl fmt::Display for FieldValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FieldValue::Text(text) => f.write_str(text),
+            FieldValue::Flag(true) => f.write_str("True"),
+            FieldValue::Flag(false) => f.write_str("False"),
+            FieldValue::Missing => f.write_str("None"),
+        }
+    }
+}
+
+impl From<&str> for FieldValue {
+    fn from(text: &str) -> Self {
+        FieldValue::Text(text.to_owned())
+    }
+}
+
+/// An auto-fixable mutation of a footprint
+#[derive(Debug, Clone)]
+struct Change {
+    /// e.g. `reference`, `field:Description`, `field:Path:visible`
+    field: String,
+    old: FieldValue,
+    new: FieldValue,
+}
+
+impl Change {
+    fn describe(&self) -> String {
+        let name = match self.field.strip_prefix("field:") {
+            Some(rest) => rest.split(':').next().unwrap_or(rest),
+            None => &self.field,
+        };
+        format!("{name}: '{}' -> '{}'", self.old, self.new)
+    }
+}
+
+enum FootprintChanges {
+    /// The footprint identifier changed; the footprint must be reloaded from its library
+    Replace,
+    Update(Vec<Change>),
+}
+
+/// Detect metadata differences between a footprint and its part (`compute_footprint_changes`)
+fn compute_changes(fp: Footprint<'_>, part: &Part, is_existing: bool) -> FootprintChanges {
+    let old_fpid = fp.fpid();
+    if is_existing && !old_fpid.is_empty() && old_fpid != part.fpid {
+        return FootprintChanges::Replace;
+    }
+
+    let mut changes = Vec::new();
+    let mut add = |field: String, old: FieldValue, new: FieldValue| {
+        if old != new {
+            changes.push(Change { field, old, new });
+        }
+    };
+    add("fpid".into(), old_fpid.into(), part.fpid.as_str().into());
+    add(
+        "reference".into(),
+        fp.reference().into(),
+        part.reference.as_str().into(),
+    );
+    add(
+        "value".into(),
+        fp.value().into(),
+        part.value.as_str().into(),
+    );
+    add(
+        "kiid_path".into(),
+        fp.kiid_path().trim_start_matches('/').into(),
+        FieldValue::Text(format!("{}/{}", part.uuid, part.uuid)),
+    );
+
+    let mut desired: Vec<(String, String)> = vec![
+        ("Datasheet".into(), String::new()),
+        ("Description".into(), String::new()),
+        ("Path".into(), part.path.clone()),
+    ];
+    let mut set_desired =
+        |name: String, value: &str| match desired.iter_mut().find(|(n, _)| *n == name) {
+            Some(entry) => entry.1 = value.to_owned(),
+            None => desired.push((name, value.to_owned())),
+        };
+    let (mut dnp, mut skip_bom, mut skip_pos) = (false, false, false);
+    for (name, value) in &part.properties {
+        match name.to_lowercase().as_str() {
+            "dnp" => dnp = value.eq_ignore_ascii_case("true"),
+            "skip_bom" => skip_bom = value.eq_ignore_ascii_case("true"),
+            "skip_pos" => skip_pos = value.eq_ignore_ascii_case("true"),
+            "datasheet" => set_desired("Datasheet".into(), value),
+            "description" => set_desired("Description".into(), value),
+            lower if !SKIP_PROPERTIES.contains(&lower) && !name.starts_with('_') => {
+                set_desired(python_title(&name.replace('_', " ")), value)
+            }
+            _ => {}
+        }
+    }
+
+    add(
+        "dnp".into(),
+        FieldValue::Flag(fp.has_attr("dnp")),
+        FieldValue::Flag(dnp),
+    );
+    add(
+        "exclude_from_bom".into(),
+        FieldValue::Flag(fp.has_attr("exclude_from_bom")),
+        FieldValue::Flag(skip_bom),
+    );
+    add(
+        "exclude_from_pos".into(),
+        FieldValue::Flag(fp.has_attr("exclude_from_pos_files")),
+        FieldValue::Flag(skip_pos),
+    );
+
+    let existing = fp.fields();
+    for (name, value) in &existing {
+        if !desired.iter().any(|(n, _)| n == name) {
+            add(
+                format!("field:{name}"),
+                (*value).into(),
+                FieldValue::Missing,
+            );
+        }
+    }
+    for (name, value) in &desired {
+        let old = existing
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, v)| FieldValue::from(*v))
+            .unwrap_or(FieldValue::Missing);
+        add(format!("field:{name}"), old, value.as_str().into());
+    }
+
+    for name in ["Path", "Value"] {
+        if fp.property_visible(name) == Some(true) {
+            add(
+                format!("field:{name}:visible"),
+                FieldValue::Flag(true),
+                FieldValue::Flag(false),
+            );
+        }
+    }
+    FootprintChanges::Update(changes)
+}
+
+fn apply_changes(fp: &mut FootprintMut<'_>, changes: &[Change], part: &Part) {
+    for change in changes {
+        let text = match &change.new {
+            FieldValue::Text(text) => Some(text.as_str()),
+            _ => None,
+        };
+        let flag = matches!(change.new, FieldValue::Flag(true));
+        match change.field.as_str() {
+            "fpid" => fp.set_fpid(text.unwrap_or_default()),
+            "reference" => fp.set_reference(text.unwrap_or_default()),
+            "value" => fp.set_value(text.unwrap_or_default()),
+            "kiid_path" => fp.set_kiid_path(&part.uuid),
+            "dnp" => fp.set_attr("dnp", flag),
+            "exclude_from_bom" => fp.set_attr("exclude_from_bom", flag),
+            "exclude_from_pos" => fp.set_attr("exclude_from_pos_files", flag),
+            field => {
+                let Some(rest) = field.strip_prefix("field:") else {
+                    continue;
+                };
+                match (rest.strip_suffix(":visible"), text) {
+                    (Some(name), _) => fp.set_property_visible(name, flag),
+                    (None, Some(text)) => fp.set_property(rest, text, Some(false)),
+                    (None, None) => fp.remove_property(rest),
+                }
+            }
+        }
+    }
+}
+
+fn part_diagnostic(part: &Part, kind: &str, severity: &str, body: String) -> LayoutSyncDiagnostic {
+    LayoutSyncDiagnostic {
+        kind: kind.to_owned(),
+        severity: severity.to_owned(),
+        body,
+        path: part.path.clone(),
+        reference: Some(part.reference.clone()),
+    }
+}
+
+fn at_path(part: &Part) -> String {
+    if part.path.is_empty() {
+        String::new()
+    } else {
+        format!(" at {}", part.path)
+    }
+}
+
+/// Python's `str.title()`: capitalize the first letter of every alphabetic run
+fn python_title(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut previous_alpha = false;
+    for ch in text.chars() {
+        if previous_alpha {
+            out.extend(ch.to_lowercase());
+        } else {
+            out.extend(ch.to_uppercase());
+        }
+        previous_alpha = ch.is_alphabetic();
+    }
+    out
+}
+
+/// Longest-prefix remap of a dotted path (the Python `Remapper`)
+fn remap_path(moved: &BTreeMap<String, String>, path: &str) -> Option<String> {
+    std::iter::once(path)
+        .chain(parent_paths(path))
+        .find_map(|prefix| {
+            moved
+                .get(prefix)
+                .map(|new_prefix| format!("{new_prefix}{}", &path[prefix.len()..]))
+        })
+}
+
+/// Proper ancestors of a dotted path, nearest first
+fn parent_paths(path: &str) -> impl Iterator<Item = &str> {
+    path.char_indices()
+        .rev()
+        .filter(|(_, ch)| *ch == '.')
+        .map(move |(i, _)| &path[..i])
+}
+
+/// Pad nets of every footprint before the sync, keyed by path UUID
+fn pad_nets_by_footprint(board: &Board) -> HashMap<String, Vec<(String, String)>> {
+    board
+        .footprints()
+        .filter_map(|fp| {
+            let nets = fp
+                .pads()
+                .filter_map(|pad| {
+                    let number = pad.get(1)?.as_atom()?;
+                    let name = find(pad, "net")?.get(2)?.as_atom()?;
+                    Some((number.to_owned(), name.to_owned()))
+                })
+                .collect();
+            Some((fp.path_uuid()?.to_owned(), nets))
+        })
+        .collect()
+}
+
+/// Old net -> new net for nets whose pads (on footprints that survived the sync) moved to a
+/// single new net with at least 80% agreement, as in the Python `NetRenameMapper`
+fn net_renames(
+    board: &Board,
+    old_pad_nets: &HashMap<String, Vec<(String, String)>>,
+    new_pad_nets: &HashMap<&str, Vec<(&str, &str)>>,
+) -> HashMap<String, String> {
+    let mut votes: BTreeMap<&str, BTreeMap<&str, usize>> = BTreeMap::new();
+    for fp in board.footprints() {
+        let Some(old) = fp.path_uuid().and_then(|uuid| old_pad_nets.get(uuid)) else {
+            continue;
+        };
+        let Some(new) = new_pad_nets.get(fp.reference()) else {
+            continue;
+        };
+        for (number, old_net) in old {
+            if let Some((_, new_net)) = new.iter().find(|(pad, _)| pad == number) {
+                *votes
+                    .entry(old_net)
+                    .or_default()
+                    .entry(new_net)
+                    .or_default() += 1;
+            }
+        }
+    }
+    votes
+        .into_iter()
+        .filter_map(|(old, counts)| {
+            let total: usize = counts.values().sum();
+            let (new, count) = counts.into_iter().max_by_key(|(_, count)| *count)?;
+            (old != new && count as f64 / total as f64 >= 0.8)
+                .then(|| (old.to_owned(), new.to_owned()))
+        })
+        .collect()
+}
+
+/// Net code referenced by a track, via, zone or drawing
+fn net_code(items: &[Sexpr]) -> Option<i64> {
+    find(items, "net")?.get(1)?.as_int()
+}
+
+fn set_item_net(items: &mut [Sexpr], code: i64, name: &str) {
+    if let Some(net) = find_mut(items, "net") {
+        net.truncate(1);
+        net.push(Sexpr::Int(code));
+    }
+    if let Some(net_name) = find_mut(items, "net_name") {
+        net_name.truncate(1);
+        net_name.push(Sexpr::string(name));
+    }
+}
+
+fn item_uuid(items: &[Sexpr]) -> Option<&str> {
+    find(items, "uuid")
+        .or_else(|| find(items, "id"))
+        .and_then(|uuid| uuid.get(1))
+        .and_then(|s| s.as_atom())
+}
+
+fn add_group_member(board: &mut Board, group_uuid: &str, member: &str) {
+    for item in board.items_mut().iter_mut() {
+        let Some(group) = item
+            .as_list_mut()
+            .filter(|items| head(items) == Some("group"))
+        else {
+            continue;
+        };
+        if item_uuid(group) != Some(group_uuid) {
+            continue;
+        }
+        if find(group, "members").is_none() {
+            group.push(Sexpr::list(vec![Sexpr::symbol("members")]));
+        }
+        let members = find_mut(group, "members").expect("members list was just added");
+        if !members.iter().any(|m| m.as_atom() == Some(member)) {
+            members.push(Sexpr::string(member));
+        }
+        return;
+    }
+}
+
+fn remove_group_members(board: &mut Board, members: &HashSet<String>) {
+    if members.is_empty() {
+        return;
+    }
+    for item in board.items_mut().iter_mut() {
+        if let Some(group) = item
+            .as_list_mut()
+            .filter(|items| head(items) == Some("group"))
+        {
+            if let Some(list) = find_mut(group, "members") {
+                list.retain(|m| !m.as_atom().is_some_and(|m| members.contains(m)));
+            }
+        }
+    }
+}
+
+/// Member UUID -> name of the (named) group containing it
+fn group_names_by_member(board: &Board) -> HashMap<String, String> {
+    let mut names = HashMap::new();
+    for (name, index) in board.groups() {
+        let Some(members) = board.item(index).and_then(|group| find(group, "members")) else {
+            continue;
+        };
+        for member in members[1..].iter().filter_map(|m| m.as_atom()) {
+            names.insert(member.to_owned(), name.clone());
+        }
+    }
+    names
+}
+
+/// Log lines written to `layout.log`, in the Python script's `LEVEL: message` format
+#[derive(Default)]
+struct SyncLog {
+    lines: Vec<String>,
+}
+
+impl SyncLog {
+    fn info(&mut self, message: String) {
+        log::debug!("{message}");
+        self.lines.push(format!("INFO: {message}"));
+    }
+
+    fn debug(&mut self, message: String) {
+        log::trace!("{message}");
+        self.lines.push(format!("DEBUG: {message}"));
+    }
+
+    fn contents(&self) -> String {
+        let mut out = self.lines.join("\n");
+        out.push('\n');
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn part(path: &str, reference: &str) -> Part {
+        Part {
+            path: path.to_owned(),
+            uuid: Uuid::new_v5(&Uuid::NAMESPACE_URL, path.as_bytes()).to_string(),
+            reference: reference.to_owned(),
+            value: "10k".to_owned(),
+            fpid: "lib:R_0603".to_owned(),
+            footprint_file: None,
+            properties: vec![
+                ("part_number".to_owned(), "RC0603".to_owned()),
+                ("dnp".to_owned(), "true".to_owned()),
+                ("_internal".to_owned(), "x".to_owned()),
+            ],
+        }
+    }
+
+    fn footprint(source: &str) -> Vec<Sexpr> {
+        match pcb_sexpr::parse(source).unwrap() {
+            Sexpr::List(items) => items,
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn test_remap_path_longest_prefix() {
+        let moved = BTreeMap::from([
+            ("Power".to_owned(), "Supply".to_owned()),
+            ("Power.LDO".to_owned(), "Regulator".to_owned()),
+        ]);
+        assert_eq!(
+            remap_path(&moved, "Power.LDO.C1").as_deref(),
+            Some("Regulator.C1")
+        );
+        assert_eq!(remap_path(&moved, "Power.R1").as_deref(), Some("Supply.R1"));
+        assert_eq!(remap_path(&moved, "PowerX.R1"), None);
+    }
+
+    #[test]
+    fn test_python_title() {
+        assert_eq!(python_title("part number"), "Part Number");
+        assert_eq!(python_title("IPC name2x"), "Ipc Name2X");
+    }
+
+    #[test]
+    fn test_metadata_changes_match_python_sync() {
+        let part = part("Power.R1", "R1");
+        let fp = footprint(&format!(
+            r#"(footprint "lib:R_0603" (layer "F.Cu")
+                (property "Reference" "R5") (property "Value" "10k" (hide yes))
+                (property "Datasheet" "") (property "Description" "")
+                (property "Path" "Power.R1" (hide yes)) (property "Part Number" "RC0603")
+                (path "/{0}/{0}"))"#,
+            part.uuid
+        ));
+        let FootprintChanges::Update(changes) = compute_changes(Footprint(&fp), &part, true) else {
+            panic!("same fpid should not need replacement");
+        };
+        let described: Vec<String> = changes.iter().map(Change::describe).collect();
+        assert_eq!(
+            described,
+            vec!["reference: 'R5' -> 'R1'", "dnp: 'False' -> 'True'"]
+        );
+
+        let mut fp = fp;
+        apply_changes(&mut FootprintMut(&mut fp), &changes, &part);
+        assert!(matches!(
+            compute_changes(Footprint(&fp), &part, true),
+            FootprintChanges::Update(changes) if changes.is_empty()
+        ));
+
+        let other = Part {
+            fpid: "lib:R_0805".to_owned(),
+            ..part
+        };
+        assert!(matches!(
+            compute_changes(Footprint(&fp), &other, true),
+            FootprintChanges::Replace
+        ));
+    }
+
+    #[test]
+    fn test_sync_then_check_is_clean() {
+        let dir = tempfile::tempdir().unwrap();
+        let fp_file = dir.path().join("R_0603.kicad_mod");
+        std::fs::write(
+            &fp_file,
+            r#"(footprint "R_0603" (layer "F.Cu")
+                (property "Reference" "REF**" (at 0 -1.5 0) (layer "F.SilkS"))
+                (property "Value" "R_0603" (at 0 1.5 0) (layer "F.Fab"))
+                (fp_line (start -1.5 -0.8) (end 1.5 -0.8) (stroke (width 0.05) (type solid)) (layer "F.CrtYd"))
+                (fp_line (start -1.5 0.8) (end 1.5 0.8) (stroke (width 0.05) (type solid)) (layer "F.CrtYd"))
+                (pad "1" smd roundrect (at -0.8 0) (size 0.8 0.9) (layers "F.Cu" "F.Paste" "F.Mask"))
+                (pad "2" smd roundrect (at 0.8 0) (size 0.8 0.9) (layers "F.Cu" "F.Paste" "F.Mask")))"#,
+        )
+        .unwrap();
+
+        let mut parts = vec![part("Power.R1", "R1"), part("Power.R2", "R2")];
+        for part in &mut parts {
+            part.footprint_file = Some(fp_file.clone());
+        }
+        let netlist = Netlist {
+            nets: vec![PartNet {
+                name: "VCC".to_owned(),
+                nodes: vec![
+                    ("R1".to_owned(), "1".to_owned()),
+                    ("R2".to_owned(), "1".to_owned()),
+                ],
+            }],
+            parts,
+            ..Default::default()
+        };
+
+        let pcb = dir.path().join("layout.kicad_pcb");
+        let snapshot_path = dir.path().join("snapshot.json");
+        let diagnostics_path = dir.path().join("diagnostics.json");
+        let log_path = dir.path().join("layout.log");
+        let request = |dry_run| SyncRequest {
+            netlist: &netlist,
+            pcb: &pcb,
+            snapshot: &snapshot_path,
+            diagnostics: &diagnostics_path,
+            log: &log_path,
+            board_config: None,
+            dry_run,
+        };
+        let diagnostics = || {
+            LayoutSyncDiagnostics::from_file(&diagnostics_path)
+                .unwrap()
+                .diagnostics
+        };
+
+        sync(&request(false)).unwrap();
+        assert!(dir.path().join("layout.kicad_pro").exists());
+
+        let board = Board::parse(&std::fs::read_to_string(&pcb).unwrap()).unwrap();
+        let references: Vec<&str> = board.footprints().map(|fp| fp.reference()).collect();
+        assert_eq!(references, vec!["R1", "R2"]);
+        assert_eq!(board.groups().keys().collect::<Vec<_>>(), vec!["Power"]);
+        let r1 = board.footprints().next().unwrap();
+        assert!(r1.has_attr("dnp"));
+        assert_eq!(r1.property("Part Number"), Some("RC0603"));
+        let snapshot: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(&snapshot_path).unwrap()).unwrap();
+        assert_eq!(snapshot["footprints"][0]["group"], "Power");
+        assert_eq!(
+            snapshot["groups"][0]["footprints"]
+                .as_array()
+                .unwrap()
+                .len(),
+            2
+        );
+
+        // A second pass has nothing left to report
+        sync(&request(true)).unwrap();
+        assert!(diagnostics().is_empty(), "{:?}", diagnostics());
+
+        // Removing a part from the schematic is reported, not applied, in dry-run mode
+        let mut smaller = netlist.clone();
+        smaller.parts.pop();
+        sync(&SyncRequest {
+            netlist: &smaller,
+            ..request(true)
+        })
+        .unwrap();
+        let kinds: Vec<String> = diagnostics().into_iter().map(|d| d.kind).collect();
+        assert_eq!(kinds, vec!["layout.sync.extra_footprint"]);
+    }
+}
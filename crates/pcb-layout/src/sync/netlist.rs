@@ -0,0 +1,183 @@
+//! Flattened view of a [`Schematic`] that the native layout sync operates on.
+//!
+//! This mirrors `JsonNetlistParser` in `update_layout_file.py` so that both engines agree on
+//! footprint identity (UUIDv5 of the hierarchical path), reference designators, values and the
+//! properties that end up as KiCad footprint fields.
+
+use std::collections::{BTreeMap, HashMap};
+use std::path::PathBuf;
+
+use pcb_sch::kicad_netlist::format_footprint;
+use pcb_sch::{AttributeValue, InstanceKind, InstanceRef, Schematic};
+use uuid::Uuid;
+
+use super::{Netlist, Part, PartNet};
+
+impl Netlist {
+    /// Build the netlist view for a schematic
+    pub fn from_schematic(schematic: &Schematic) -> Self {
+        let mut modules = BTreeMap::new();
+        let mut parts = Vec::new();
+
+        for (inst_ref, instance) in &schematic.instances {
+            let path = inst_ref.instance_path.join(".");
+            match instance.kind {
+                InstanceKind::Module if !path.is_empty() => {
+                    let layout_path = instance
+                        .attributes
+                        .get(pcb_sch::ATTR_LAYOUT_PATH)
+                        .and_then(|v| v.string())
+                        .map(str::to_owned);
+                    modules.insert(path, layout_path);
+                }
+                InstanceKind::Component => parts.push(part_from_instance(path, instance)),
+                _ => {}
+            }
+        }
+        parts.sort_by(|a, b| a.path.cmp(&b.path));
+
+        let mut nets: Vec<PartNet> = schematic
+            .nets
+            .iter()
+            .filter_map(|(name, net)| {
+                let nodes: Vec<(String, String)> = net
+                    .ports
+                    .iter()
+                    .flat_map(|port_ref| port_nodes(schematic, port_ref))
+                    .collect();
+                (!nodes.is_empty()).then(|| PartNet {
+                    name: name.clone(),
+                    nodes,
+                })
+            })
+            .collect();
+        nets.sort_by(|a, b| a.name.cmp(&b.name));
+
+        Netlist {
+            parts,
+            nets,
+            modules,
+            moved_paths: schematic
+                .moved_paths
+                .iter()
+                .map(|(old, new)| (old.clone(), new.clone()))
+                .collect(),
+        }
+    }
+}
+
+fn part_from_instance(path: String, instance: &pcb_sch::Instance) -> Part {
+    let attr_string = |key: &str| instance.attributes.get(key).and_then(|v| v.string());
+
+    // Same precedence as the Python sync: mpn > value > Value
+    let value = ["mpn", "value", "Value"]
+        .into_iter()
+        .find_map(attr_string)
+        .unwrap_or("?")
+        .to_owned();
+
+    let (fpid, footprint_file) = match attr_string("footprint") {
+        Some(fp) => {
+            let (fpid, lib) = format_footprint(fp);
+            (fpid, lib.map(|_| PathBuf::from(fp)))
+        }
+        None => ("unknown:unknown".to_owned(), None),
+    };
+
+    let mut properties: Vec<(String, String)> = instance
+        .attributes
+        .iter()
+        .filter(|(key, _)| !matches!(key.as_str(), "footprint" | "value" | "Value"))
+        .filter_map(|(key, value)| property_value(value).map(|v| (key.clone(), v)))
+        .collect();
+    properties.sort();
+
+    Part {
+        uuid: Uuid::new_v5(&Uuid::NAMESPACE_URL, path.as_bytes()).to_string(),
+        path,
+        reference: instance
+            .reference_designator
+            .clone()
+            .unwrap_or_else(|| "U?".to_owned()),
+        value,
+        fpid,
+        footprint_file,
+        properties,
+    }
+}
+
+/// Render an attribute the way the Python sync stringifies properties
+fn property_value(value: &AttributeValue) -> Option<String> {
+    match value {
+        AttributeValue::String(s) => Some(s.clone()),
+        AttributeValue::Boolean(b) => Some(b.to_string()),
+        AttributeValue::Number(n) => Some(python_number(*n)),
+        AttributeValue::Array(items) => Some(
+            items
+                .iter()
+                .map(|item| match item {
+                    AttributeValue::String(s) => s.clone(),
+                    AttributeValue::Number(n) => python_number(*n),
+                    AttributeValue::Boolean(b) => b.to_string(),
+                    other => format!("{other:?}"),
+                })
+                .collect::<Vec<_>>()
+                .join(","),
+        ),
+        _ => None,
+    }
+}
+
+/// Format a float like Python's `str()` so field values match between engines
+fn python_number(n: f64) -> String {
+    if n.is_finite() && n.fract() == 0.0 && n.abs() < 1e16 {
+        format!("{n:.1}")
+    } else {
+        n.to_string()
+    }
+}
+
+/// Resolve the `(reference, pad)` pairs a port connects to
+fn port_nodes(schematic: &Schematic, port_ref: &InstanceRef) -> Vec<(String, String)> {
+    // Walk up the hierarchy to find the owning component
+    let owner = (1..port_ref.instance_path.len()).rev().find_map(|len| {
+        let candidate = InstanceRef::new(
+            port_ref.module.clone(),
+            port_ref.instance_path[..len].to_vec(),
+        );
+        schematic
+            .instances
+            .get(&candidate)
+            .filter(|inst| inst.kind == InstanceKind::Component)
+    });
+    let Some(owner) = owner else {
+        return Vec::new();
+    };
+    let reference = owner
+        .reference_designator
+        .clone()
+        .unwrap_or_else(|| "U?".to_owned());
+
+    let pads = schematic
+        .instances
+        .get(port_ref)
+        .and_then(|port| port.attributes.get("pads"))
+        .and_then(|pads| match pads {
+            AttributeValue::Array(items) => Some(items.as_slice()),
+            _ => None,
+        })
+        .unwrap_or_default();
+
+    pads.iter()
+        .filter_map(|pad| pad.string())
+        .map(|pad| (reference.clone(), pad.to_owned()))
+        .collect()
+}
+
+/// Index parts by their footprint UUID
+pub(super) fn parts_by_uuid(parts: &[Part]) -> HashMap<&str, &Part> {
+    parts
+        .iter()
+        .map(|part| (part.uuid.as_str(), part))
+        .collect()
+}
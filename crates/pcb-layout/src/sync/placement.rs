@@ -0,0 +1,182 @@
+//! First-pass placement for footprints added by the native sync.
+//!
+//! This is a simpler take on the HierPlace packing in the Python script: new footprints are
+//! packed into one block per group (so modules stay together), and the blocks are stacked in
+//! a column to the right of everything already on the board. Existing footprints never move.
+
+use std::collections::BTreeMap;
+
+use super::board::BBox;
+
+/// Gap between footprints inside a block, in mm (same as the Python `MODULE_SPACING`)
+const ITEM_SPACING: f64 = 0.35;
+/// Gap between blocks, in mm (same as the Python `GROUP_SPACING`)
+const BLOCK_SPACING: f64 = 1.75;
+/// Gap between new and existing content, in mm
+const EXISTING_MARGIN: f64 = 10.0;
+/// Size assumed for footprints without courtyard or pads, in mm
+const DEFAULT_SIZE: f64 = 2.0;
+/// Center of an A4 sheet, where new content goes on an otherwise empty board
+const SHEET_CENTER: (f64, f64) = (148.5, 105.0);
+
+/// A footprint waiting to be placed
+#[derive(Debug, Clone)]
+pub(crate) struct PlacementItem {
+    /// Caller's identifier for the footprint
+    pub id: usize,
+    /// Group the footprint belongs to (empty for top level)
+    pub group: String,
+    /// Sort key within the group (the reference designator)
+    pub key: String,
+    /// Footprint-local bounding box, if known
+    pub local_bbox: Option<BBox>,
+}
+
+/// Compute board positions for `items`, avoiding the `occupied` area.
+///
+/// Returns `(id, x, y)` for the footprint origin of every item.
+pub(crate) fn place(items: &[PlacementItem], occupied: Option<BBox>) -> Vec<(usize, f64, f64)> {
+    let mut groups: BTreeMap<&str, Vec<&PlacementItem>> = BTreeMap::new();
+    for item in items {
+        groups.entry(item.group.as_str()).or_default().push(item);
+    }
+
+    // Lay the blocks out in a column starting at the origin, then move everything next to
+    // the existing content in one go
+    let mut positions = Vec::with_capacity(items.len());
+    let mut added: Option<BBox> = None;
+    let mut cursor_y = 0.0;
+    for (_, mut members) in groups {
+        members.sort_by(|a, b| natord::compare(&a.key, &b.key));
+
+        let sizes: Vec<(f64, f64)> = members
+            .iter()
+            .map(|item| {
+                item.local_bbox
+                    .map(|b| {
+                        (
+                            b.width().max(DEFAULT_SIZE / 4.0),
+                            b.height().max(DEFAULT_SIZE / 4.0),
+                        )
+                    })
+                    .unwrap_or((DEFAULT_SIZE, DEFAULT_SIZE))
+            })
+            .collect();
+
+        // Roughly square blocks: wrap rows at the square root of the total area
+        let area: f64 = sizes
+            .iter()
+            .map(|(w, h)| (w + ITEM_SPACING) * (h + ITEM_SPACING))
+            .sum();
+        let widest = sizes.iter().map(|(w, _)| *w).fold(0.0, f64::max);
+        let row_limit = area.sqrt().max(widest);
+
+        let (mut x, mut y, mut row_height) = (0.0, 0.0, 0.0f64);
+        for (item, (w, h)) in members.iter().zip(&sizes) {
+            if x > 0.0 && x + w > row_limit {
+                x = 0.0;
+                y += row_height + ITEM_SPACING;
+                row_height = 0.0;
+            }
+            // Align the footprint's bounding box (not its origin) to the slot
+            let (offset_x, offset_y) = item
+                .local_bbox
+                .map(|b| (-b.min_x, -b.min_y))
+                .unwrap_or((DEFAULT_SIZE / 2.0, DEFAULT_SIZE / 2.0));
+            positions.push((item.id, x + offset_x, cursor_y + y + offset_y));
+
+            let slot = BBox {
+                min_x: x,
+                min_y: cursor_y + y,
+                max_x: x + w,
+                max_y: cursor_y + y + h,
+            };
+            match added.as_mut() {
+                Some(added) => added.merge(&slot),
+                None => added = Some(slot),
+            }
+            x += w + ITEM_SPACING;
+            row_height = row_height.max(*h);
+        }
+        cursor_y += y + row_height + BLOCK_SPACING;
+    }
+
+    let Some(added) = added else {
+        return positions;
+    };
+    // Center-to-center alignment, like `_position_relative_to_existing` in the Python sync
+    let (target_x, target_y) = match occupied {
+        Some(bbox) => (
+            bbox.max_x + EXISTING_MARGIN + added.width() / 2.0,
+            (bbox.min_y + bbox.max_y) / 2.0,
+        ),
+        None => SHEET_CENTER,
+    };
+    let dx = target_x - (added.min_x + added.max_x) / 2.0;
+    let dy = target_y - (added.min_y + added.max_y) / 2.0;
+    positions
+        .into_iter()
+        .map(|(id, x, y)| (id, round_to_grid(x + dx), round_to_grid(y + dy)))
+        .collect()
+}
+
+/// Snap to a 0.01mm grid so positions stay readable in the saved board
+fn round_to_grid(value: f64) -> f64 {
+    (value * 100.0).round() / 100.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(id: usize, group: &str, key: &str) -> PlacementItem {
+        PlacementItem {
+            id,
+            group: group.to_owned(),
+            key: key.to_owned(),
+            local_bbox: Some(BBox {
+                min_x: -1.0,
+                min_y: -0.5,
+                max_x: 1.0,
+                max_y: 0.5,
+            }),
+        }
+    }
+
+    #[test]
+    fn test_places_right_of_existing_content_without_overlap() {
+        let existing = BBox {
+            min_x: 0.0,
+            min_y: 0.0,
+            max_x: 50.0,
+            max_y: 20.0,
+        };
+        let items = vec![item(0, "A", "R2"), item(1, "A", "R10"), item(2, "", "C1")];
+        let positions = place(&items, Some(existing));
+        assert_eq!(positions.len(), 3);
+
+        let boxes: Vec<BBox> = positions
+            .iter()
+            .map(|(_, x, y)| BBox {
+                min_x: x - 1.0,
+                min_y: y - 0.5,
+                max_x: x + 1.0,
+                max_y: y + 0.5,
+            })
+            .collect();
+        for (i, a) in boxes.iter().enumerate() {
+            assert!(a.min_x >= existing.max_x);
+            for b in &boxes[i + 1..] {
+                let overlaps = a.min_x < b.max_x
+                    && b.min_x < a.max_x
+                    && a.min_y < b.max_y
+                    && b.min_y < a.max_y;
+                assert!(!overlaps, "{a:?} overlaps {b:?}");
+            }
+        }
+
+        // Natural ordering within a group: R2 is placed before R10
+        let order: Vec<usize> = positions.iter().map(|(id, _, _)| *id).collect();
+        assert_eq!(order, vec![2, 0, 1]);
+    }
+}
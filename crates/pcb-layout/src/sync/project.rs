@@ -0,0 +1,324 @@
+//! Board setup stored in the KiCad project file (`.kicad_pro`).
+//!
+//! pcbnew keeps design rules, pre-defined track/via sizes and netclasses in the project file
+//! rather than the board, so the native sync writes them there directly. This is the
+//! equivalent of `SetupBoard._apply_board_config` in the Python script.
+
+use std::path::Path;
+
+use serde_json::{json, Map, Value};
+
+/// `(board config path, .kicad_pro key under board.design_settings.rules)`
+const RULE_MAPPINGS: &[(&[&str], &str)] = &[
+    (&["copper", "minimum_clearance"], "min_clearance"),
+    (&["copper", "minimum_track_width"], "min_track_width"),
+    (&["copper", "minimum_connection_width"], "min_connection"),
+    (
+        &["copper", "minimum_annular_width"],
+        "min_via_annular_width",
+    ),
+    (&["copper", "minimum_via_diameter"], "min_via_diameter"),
+    (
+        &["copper", "copper_to_hole_clearance"],
+        "min_hole_clearance",
+    ),
+    (
+        &["copper", "copper_to_edge_clearance"],
+        "min_copper_edge_clearance",
+    ),
+    (
+        &["holes", "minimum_through_hole"],
+        "min_through_hole_diameter",
+    ),
+    (&["holes", "hole_to_hole_clearance"], "min_hole_to_hole"),
+    (&["uvias", "minimum_uvia_diameter"], "min_microvia_diameter"),
+    (&["uvias", "minimum_uvia_hole"], "min_microvia_drill"),
+    (
+        &["silkscreen", "minimum_item_clearance"],
+        "min_silk_clearance",
+    ),
+    (&["silkscreen", "minimum_text_height"], "min_text_height"),
+];
+
+/// Netclass keys copied verbatim (values in mm, except `priority`)
+const NETCLASS_KEYS: &[&str] = &[
+    "clearance",
+    "track_width",
+    "via_diameter",
+    "via_drill",
+    "microvia_diameter",
+    "microvia_drill",
+    "diff_pair_width",
+    "diff_pair_gap",
+    "diff_pair_via_gap",
+    "priority",
+];
+
+/// Load the project next to a board, or a minimal one if it does not exist yet
+pub(crate) fn load(pro_path: &Path) -> Result<Value, String> {
+    match std::fs::read_to_string(pro_path) {
+        Ok(content) => serde_json::from_str(&content)
+            .map_err(|e| format!("failed to parse {}: {e}", pro_path.display())),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(new_project(pro_path)),
+        Err(e) => Err(format!("failed to read {}: {e}", pro_path.display())),
+    }
+}
+
+/// Write a project file the way KiCad formats it (two-space indented JSON)
+pub(crate) fn save(pro_path: &Path, project: &Value) -> Result<(), String> {
+    let content = serde_json::to_string_pretty(project).map_err(|e| e.to_string())?;
+    std::fs::write(pro_path, content + "\n")
+        .map_err(|e| format!("failed to write {}: {e}", pro_path.display()))
+}
+
+/// Apply the `design_rules` section of a serialized board config.
+///
+/// Returns a human-readable line for every setting that changed.
+pub(crate) fn apply_board_config(project: &mut Value, config: &Value) -> Vec<String> {
+    let mut changes = Vec::new();
+    let Some(design_rules) = config.get("design_rules") else {
+        return changes;
+    };
+    let design_settings = object_at(project, &["board", "design_settings"]);
+
+    if let Some(constraints) = design_rules.get("constraints") {
+        let rules = entry_object(design_settings, "rules");
+        for (path, key) in RULE_MAPPINGS {
+            let Some(value) = nested(constraints, path).filter(|v| v.is_number()) else {
+                continue;
+            };
+            if rules.get(*key) != Some(value) {
+                changes.push(format!(
+                    "{key}: {} -> {value}",
+                    rules.get(*key).unwrap_or(&Value::Null)
+                ));
+                rules.insert((*key).to_owned(), value.clone());
+            }
+        }
+    }
+
+    if let Some(sizes) = design_rules.get("predefined_sizes") {
+        // Index 0 is KiCad's "use netclass value" placeholder
+        if let Some(widths) = sizes.get("track_widths").and_then(Value::as_array) {
+            let list: Vec<Value> = std::iter::once(json!(0.0))
+                .chain(widths.iter().filter(|w| w.is_number()).cloned())
+                .collect();
+            set_if_changed(
+                design_settings,
+                "track_widths",
+                Value::Array(list),
+                &mut changes,
+            );
+        }
+        if let Some(vias) = sizes.get("via_dimensions").and_then(Value::as_array) {
+            let list: Vec<Value> = std::iter::once(json!({"diameter": 0.0, "drill": 0.0}))
+                .chain(vias.iter().filter_map(|via| {
+                    Some(json!({
+                        "diameter": via.get("diameter").filter(|v| v.is_number())?,
+                        "drill": via.get("drill").filter(|v| v.is_number())?,
+                    }))
+                }))
+                .collect();
+            set_if_changed(
+                design_settings,
+                "via_dimensions",
+                Value::Array(list),
+                &mut changes,
+            );
+        }
+    }
+
+    if let Some(netclasses) = design_rules.get("netclasses").and_then(Value::as_array) {
+        let net_settings = object_at(project, &["net_settings"]);
+        let default = net_settings
+            .get("classes")
+            .and_then(Value::as_array)
+            .and_then(|classes| {
+                classes
+                    .iter()
+                    .find(|c| c.get("name").and_then(Value::as_str) == Some("Default"))
+            })
+            .cloned()
+            .unwrap_or_else(default_netclass);
+
+        let mut classes = vec![default];
+        for netclass in netclasses {
+            let Some(name) = netclass
+                .get("name")
+                .and_then(Value::as_str)
+                .filter(|n| !n.is_empty())
+            else {
+                log::warn!("Netclass definition must have a non-empty 'name': {netclass}");
+                continue;
+            };
+            // "Default" updates KiCad's built-in class in place
+            let index = if name == "Default" {
+                0
+            } else {
+                classes.push(json!({ "name": name }));
+                classes.len() - 1
+            };
+            let class = classes[index]
+                .as_object_mut()
+                .expect("netclasses are objects");
+            for key in NETCLASS_KEYS {
+                if let Some(value) = netclass.get(*key).filter(|v| !v.is_null()) {
+                    class.insert((*key).to_owned(), value.clone());
+                }
+            }
+            if let Some(color) = netclass.get("color").and_then(Value::as_str) {
+                class.insert("pcb_color".to_owned(), json!(kicad_color(color)));
+            }
+        }
+        set_if_changed(net_settings, "classes", Value::Array(classes), &mut changes);
+    }
+
+    changes
+}
+
+fn set_if_changed(
+    object: &mut Map<String, Value>,
+    key: &str,
+    value: Value,
+    changes: &mut Vec<String>,
+) {
+    if object.get(key) != Some(&value) {
+        changes.push(format!("{key} updated"));
+        object.insert(key.to_owned(), value);
+    }
+}
+
+fn new_project(pro_path: &Path) -> Value {
+    let filename = pro_path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    json!({
+        "board": { "design_settings": {} },
+        "meta": { "filename": filename, "version": 3 },
+        "net_settings": {
+            "classes": [default_netclass()],
+            "meta": { "version": 4 },
+        },
+    })
+}
+
+/// KiCad 9's built-in Default netclass
+fn default_netclass() -> Value {
+    json!({
+        "bus_width": 12,
+        "clearance": 0.2,
+        "diff_pair_gap": 0.25,
+        "diff_pair_via_gap": 0.25,
+        "diff_pair_width": 0.2,
+        "line_style": 0,
+        "microvia_diameter": 0.3,
+        "microvia_drill": 0.1,
+        "name": "Default",
+        "pcb_color": "rgba(0, 0, 0, 0.000)",
+        "priority": 2147483647,
+        "schematic_color": "rgba(0, 0, 0, 0.000)",
+        "track_width": 0.2,
+        "via_diameter": 0.6,
+        "via_drill": 0.3,
+        "wire_width": 6
+    })
+}
+
+/// Convert `#rrggbb[aa]` to the `rgba(r, g, b, a)` form KiCad stores
+fn kicad_color(color: &str) -> String {
+    let Some(hex) = color
+        .strip_prefix('#')
+        .filter(|h| h.len() == 6 || h.len() == 8)
+    else {
+        return color.to_owned();
+    };
+    let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).ok();
+    match (channel(0), channel(2), channel(4)) {
+        (Some(r), Some(g), Some(b)) => {
+            let alpha = if hex.len() == 8 {
+                channel(6).map(|a| a as f64 / 255.0).unwrap_or(1.0)
+            } else {
+                1.0
+            };
+            format!("rgba({r}, {g}, {b}, {alpha:.3})")
+        }
+        _ => color.to_owned(),
+    }
+}
+
+fn nested<'a>(value: &'a Value, path: &[&str]) -> Option<&'a Value> {
+    path.iter().try_fold(value, |value, key| value.get(key))
+}
+
+/// Object at `path`, creating (or replacing non-object values with) empty objects on the way
+fn object_at<'a>(value: &'a mut Value, path: &[&str]) -> &'a mut Map<String, Value> {
+    let mut current = value;
+    for key in path {
+        if !current.is_object() {
+            *current = json!({});
+        }
+        current = current
+            .as_object_mut()
+            .expect("just ensured an object")
+            .entry(*key)
+            .or_insert_with(|| json!({}));
+    }
+    if !current.is_object() {
+        *current = json!({});
+    }
+    current.as_object_mut().expect("just ensured an object")
+}
+
+fn entry_object<'a>(object: &'a mut Map<String, Value>, key: &str) -> &'a mut Map<String, Value> {
+    let entry = object.entry(key).or_insert_with(|| json!({}));
+    if !entry.is_object() {
+        *entry = json!({});
+    }
+    entry.as_object_mut().expect("just ensured an object")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_apply_board_config() {
+        let mut project = new_project(Path::new("layout.kicad_pro"));
+        let config = json!({
+            "design_rules": {
+                "constraints": { "copper": { "minimum_clearance": 0.15 } },
+                "predefined_sizes": {
+                    "track_widths": [0.2, 0.4],
+                    "via_dimensions": [{ "diameter": 0.6, "drill": 0.3 }],
+                },
+                "netclasses": [
+                    { "name": "Default", "clearance": 0.18 },
+                    { "name": "Power", "track_width": 0.5, "color": "#ff0000" },
+                ],
+            }
+        });
+
+        let changes = apply_board_config(&mut project, &config);
+        assert_eq!(changes.len(), 4);
+
+        let ds = &project["board"]["design_settings"];
+        assert_eq!(ds["rules"]["min_clearance"], json!(0.15));
+        assert_eq!(ds["track_widths"], json!([0.0, 0.2, 0.4]));
+        assert_eq!(
+            ds["via_dimensions"][1],
+            json!({"diameter": 0.6, "drill": 0.3})
+        );
+
+        let classes = project["net_settings"]["classes"].as_array().unwrap();
+        assert_eq!(classes[0]["clearance"], json!(0.18));
+        assert_eq!(classes[0]["via_drill"], json!(0.3));
+        assert_eq!(
+            classes[1],
+            json!({"name": "Power", "track_width": 0.5, "pcb_color": "rgba(255, 0, 0, 1.000)"})
+        );
+
+        // Re-applying is a no-op
+        assert!(apply_board_config(&mut project, &config).is_empty());
+    }
+}
@@ -0,0 +1,430 @@
+//! Deterministic JSON snapshot of a board, matching `FinalizeBoard._export_layout_snapshot`.
+//!
+//! Values that pcbnew derives from geometry (positions of footprint children, group bounding
+//! boxes) are computed from the file contents; coordinates are in KiCad internal units (nm).
+
+use std::collections::{BTreeMap, HashMap};
+
+use pcb_sexpr::Sexpr;
+use serde_json::{json, Value};
+
+use super::board::{
+    find, find_all, graphic_points, head, head_of, number_at, parse_at, to_board, BBox, Board,
+    Footprint,
+};
+
+/// Build the layout snapshot for a board
+pub(crate) fn snapshot(board: &Board) -> Value {
+    let layer_names = user_layer_names(board);
+    let layer = |name: &str| -> String {
+        layer_names
+            .get(name)
+            .cloned()
+            .unwrap_or_else(|| name.to_owned())
+    };
+    let nets = board.nets();
+    let net_name = |items: &[Sexpr]| -> String {
+        find(items, "net")
+            .and_then(|net| net.get(1))
+            .and_then(|code| code.as_int())
+            .and_then(|code| nets.get(&code).cloned())
+            .unwrap_or_default()
+    };
+
+    // Group membership is recorded by item UUID
+    let mut footprint_group: HashMap<&str, String> = HashMap::new();
+    let mut groups = Vec::new();
+    for (name, index) in board.groups() {
+        let Some(group) = board.item(index) else {
+            continue;
+        };
+        let members: Vec<&str> = find(group, "members")
+            .map(|m| m[1..].iter().filter_map(|s| s.as_atom()).collect())
+            .unwrap_or_default();
+
+        let mut bbox: Option<BBox> = None;
+        let mut footprints = Vec::new();
+        let mut drawings = Vec::new();
+        for item in board.items().iter().filter_map(|item| item.as_list()) {
+            let Some(uuid) = find(item, "uuid")
+                .or_else(|| find(item, "tstamp"))
+                .and_then(|u| u.get(1))
+                .and_then(|s| s.as_atom())
+            else {
+                continue;
+            };
+            if !members.contains(&uuid) {
+                continue;
+            }
+            match head(item) {
+                Some("footprint") => {
+                    let fp = Footprint(item);
+                    footprint_group.insert(uuid, name.clone());
+                    footprints.push(fp.path_uuid().unwrap_or_default().to_owned());
+                    merge(&mut bbox, extent(fp));
+                }
+                Some(kind) if drawing_type(kind).is_some() => {
+                    let drawing = graphic_json(item, &layer, None);
+                    for (x, y) in graphic_points(item)
+                        .into_iter()
+                        .chain(find(item, "at").map(|at| (number_at(at, 1), number_at(at, 2))))
+                    {
+                        merge(&mut bbox, BBox::from_point(x, y));
+                    }
+                    drawings.push(drawing);
+                }
+                _ => {}
+            }
+        }
+        footprints.sort();
+
+        let bbox = bbox.unwrap_or(BBox::from_point(0.0, 0.0));
+        groups.push(json!({
+            "bounding_box": {
+                "bottom": nm(bbox.max_y),
+                "left": nm(bbox.min_x),
+                "right": nm(bbox.max_x),
+                "top": nm(bbox.min_y),
+            },
+            "drawings": drawings,
+            "footprints": footprints,
+            "locked": is_locked(group),
+            "name": name,
+        }));
+    }
+
+    let mut footprints: Vec<(String, Value)> = board
+        .footprints()
+        .map(|fp| {
+            let uuid = fp.path_uuid().unwrap_or_default().to_owned();
+            let (x, y, rotation) = fp.at();
+            let value = json!({
+                "footprint": fp.fpid(),
+                "group": fp.uuid().and_then(|u| footprint_group.get(u)),
+                "layer": layer(fp.layer()),
+                "locked": fp.locked(),
+                "orientation": rotation,
+                "position": point(x, y),
+                "reference": fp.reference(),
+                "uuid": uuid,
+                "value": fp.value().chars().filter(char::is_ascii).collect::<String>(),
+                "dnp": fp.has_attr("dnp"),
+                "exclude_from_bom": fp.has_attr("exclude_from_bom"),
+                "exclude_from_pos_files": fp.has_attr("exclude_from_pos_files"),
+                "pads": fp.pads().map(|pad| {
+                    let (px, py, _) = find(pad, "at").map(parse_at).unwrap_or_default();
+                    let (wx, wy) = to_board(x, y, rotation, px, py);
+                    json!({
+                        "name": pad.get(1).and_then(|s| s.as_atom()).unwrap_or(""),
+                        "position": point(wx, wy),
+                        "layer": layer(&pad_layer(pad, fp.is_back())),
+                    })
+                }).collect::<Vec<_>>(),
+                "graphical_items": fp
+                    .graphics()
+                    .filter(|item| !matches!(
+                        item.get(1).and_then(|s| s.as_sym()),
+                        Some("reference") | Some("value")
+                    ) || head(item) != Some("fp_text"))
+                    .map(|item| graphic_json(item, &layer, Some((x, y, rotation))))
+                    .collect::<Vec<_>>(),
+            });
+            (uuid, value)
+        })
+        .collect();
+    footprints.sort_by(|a, b| a.0.cmp(&b.0));
+
+    let mut tracks = Vec::new();
+    let mut vias = Vec::new();
+    let mut zones = Vec::new();
+    for item in board.items().iter().filter_map(|item| item.as_list()) {
+        match head(item) {
+            Some("segment") | Some("arc") => tracks.push(json!({
+                "net_name": net_name(item),
+                "layer": layer(&first_layer(item)),
+                "width": nm(find(item, "width").map(|w| number_at(w, 1)).unwrap_or(0.0)),
+                "locked": is_locked(item),
+                "start": find(item, "start").map(|p| point(number_at(p, 1), number_at(p, 2))),
+                "end": find(item, "end").map(|p| point(number_at(p, 1), number_at(p, 2))),
+            })),
+            Some("via") => {
+                let (x, y, _) = find(item, "at").map(parse_at).unwrap_or_default();
+                let via_type = match item.iter().find_map(|s| s.as_sym()) {
+                    Some("micro") => 1,
+                    Some("blind") => 2,
+                    _ => 3,
+                };
+                vias.push(json!({
+                    "net_name": net_name(item),
+                    "position": point(x, y),
+                    "drill": nm(find(item, "drill").map(|d| number_at(d, 1)).unwrap_or(0.0)),
+                    "diameter": nm(find(item, "size").map(|s| number_at(s, 1)).unwrap_or(0.0)),
+                    "locked": is_locked(item),
+                    "via_type": via_type,
+                }));
+            }
+            Some("zone") => {
+                let points: Vec<Value> = find(item, "polygon")
+                    .and_then(|polygon| find(polygon, "pts"))
+                    .map(|pts| {
+                        find_all(pts, "xy")
+                            .map(|xy| point(number_at(xy, 1), number_at(xy, 2)))
+                            .collect()
+                    })
+                    .unwrap_or_default();
+                let hatch_style = match find(item, "hatch")
+                    .and_then(|h| h.get(1))
+                    .and_then(|s| s.as_sym())
+                {
+                    Some("full") => 1,
+                    Some("edge") => 2,
+                    _ => 0,
+                };
+                zones.push(json!({
+                    "name": find(item, "name").and_then(|n| n.get(1)).and_then(|s| s.as_atom()).unwrap_or(""),
+                    "net_name": find(item, "net_name")
+                        .and_then(|n| n.get(1))
+                        .and_then(|s| s.as_atom())
+                        .map(str::to_owned)
+                        .unwrap_or_else(|| net_name(item)),
+                    "layer": layer(&first_layer(item)),
+                    "locked": is_locked(item),
+                    "filled": find(item, "filled_polygon").is_some(),
+                    "hatch_style": hatch_style,
+                    "min_thickness": nm(find(item, "min_thickness").map(|t| number_at(t, 1)).unwrap_or(0.0)),
+                    "points": points,
+                }));
+            }
+            _ => {}
+        }
+    }
+
+    canonicalize(json!({
+        "footprints": footprints.into_iter().map(|(_, v)| v).collect::<Vec<_>>(),
+        "groups": groups,
+        "zones": zones,
+        "tracks": tracks,
+        "vias": vias,
+    }))
+}
+
+/// Class name pcbnew reports for a board-level drawing
+fn drawing_type(kind: &str) -> Option<&'static str> {
+    match kind {
+        "gr_line" | "gr_rect" | "gr_circle" | "gr_arc" | "gr_poly" | "gr_curve" | "fp_line"
+        | "fp_rect" | "fp_circle" | "fp_arc" | "fp_poly" | "fp_curve" => Some("PCB_SHAPE"),
+        "gr_text" | "fp_text" => Some("PCB_TEXT"),
+        "gr_text_box" | "fp_text_box" => Some("PCB_TEXTBOX"),
+        _ => None,
+    }
+}
+
+/// pcbnew's `SHAPE_T` value for a drawing
+fn shape_code(kind: &str) -> Option<i64> {
+    match kind.trim_start_matches("gr_").trim_start_matches("fp_") {
+        "line" => Some(0),
+        "rect" => Some(1),
+        "arc" => Some(2),
+        "circle" => Some(3),
+        "poly" => Some(4),
+        "curve" => Some(5),
+        "text_box" => Some(1),
+        _ => None,
+    }
+}
+
+/// Snapshot entry for a drawing; footprint children are transformed by `parent` placement
+fn graphic_json(
+    item: &[Sexpr],
+    layer: &impl Fn(&str) -> String,
+    parent: Option<(f64, f64, f64)>,
+) -> Value {
+    let kind = head(item).unwrap_or_default();
+    let transform = |p: &[Sexpr]| {
+        let (lx, ly) = (number_at(p, 1), number_at(p, 2));
+        match parent {
+            Some((x, y, rotation)) => to_board(x, y, rotation, lx, ly),
+            None => (lx, ly),
+        }
+    };
+    let is_text = matches!(drawing_type(kind), Some("PCB_TEXT"));
+
+    let start = find(item, "start")
+        .or_else(|| find(item, "center"))
+        .or_else(|| find(item, "pts").and_then(|pts| find(pts, "xy")))
+        .map(transform);
+    let end = find(item, "end")
+        .or_else(|| find(item, "pts").and_then(|pts| find_all(pts, "xy").last()))
+        .map(transform);
+    let position = if is_text {
+        find(item, "at").map(transform)
+    } else if kind.ends_with("arc") {
+        arc_center(item).map(|(cx, cy)| match parent {
+            Some((x, y, rotation)) => to_board(x, y, rotation, cx, cy),
+            None => (cx, cy),
+        })
+    } else {
+        start
+    };
+    let width = find(item, "stroke")
+        .and_then(|stroke| find(stroke, "width"))
+        .or_else(|| find(item, "width"))
+        .map(|w| nm(number_at(w, 1)));
+
+    json!({
+        "type": drawing_type(kind).unwrap_or("PCB_SHAPE"),
+        "layer": layer(&first_layer(item)),
+        "position": position.map(|(x, y)| point(x, y)),
+        "start": (!is_text).then_some(start).flatten().map(|(x, y)| point(x, y)),
+        "end": (!is_text).then_some(end).flatten().map(|(x, y)| point(x, y)),
+        "angle": Value::Null,
+        "text": if is_text { item.get(2).and_then(|s| s.as_atom()) } else { None },
+        "shape": if is_text { None } else { shape_code(kind) },
+        "width": if is_text { None } else { width },
+    })
+}
+
+/// Center of a three-point arc
+fn arc_center(item: &[Sexpr]) -> Option<(f64, f64)> {
+    let p = |name: &str| find(item, name).map(|p| (number_at(p, 1), number_at(p, 2)));
+    let ((ax, ay), (bx, by), (cx, cy)) = (p("start")?, p("mid")?, p("end")?);
+    let d = 2.0 * (ax * (by - cy) + bx * (cy - ay) + cx * (ay - by));
+    if d.abs() < f64::EPSILON {
+        return None;
+    }
+    let a2 = ax * ax + ay * ay;
+    let b2 = bx * bx + by * by;
+    let c2 = cx * cx + cy * cy;
+    Some((
+        (a2 * (by - cy) + b2 * (cy - ay) + c2 * (ay - by)) / d,
+        (a2 * (cx - bx) + b2 * (ax - cx) + c2 * (bx - ax)) / d,
+    ))
+}
+
+/// Board-space extent of a footprint's pads and graphics
+fn extent(fp: Footprint<'_>) -> BBox {
+    let (x, y, rotation) = fp.at();
+    let mut bbox = fp.bbox();
+    for graphic in fp.graphics() {
+        for (lx, ly) in graphic_points(graphic)
+            .into_iter()
+            .chain(find(graphic, "at").map(|at| (number_at(at, 1), number_at(at, 2))))
+        {
+            let (wx, wy) = to_board(x, y, rotation, lx, ly);
+            bbox.include(wx, wy);
+        }
+    }
+    bbox
+}
+
+fn merge(bbox: &mut Option<BBox>, other: BBox) {
+    match bbox {
+        Some(bbox) => bbox.merge(&other),
+        None => *bbox = Some(other),
+    }
+}
+
+/// Principal layer of a pad (`*.Cu` pads report the front copper layer)
+fn pad_layer(pad: &[Sexpr], back: bool) -> String {
+    let layers: Vec<&str> = find(pad, "layers")
+        .map(|l| l[1..].iter().filter_map(|s| s.as_atom()).collect())
+        .unwrap_or_default();
+    if layers.iter().any(|l| l.starts_with('*')) {
+        return if back { "B.Cu" } else { "F.Cu" }.to_owned();
+    }
+    layers
+        .iter()
+        .find(|l| l.ends_with(".Cu"))
+        .or(layers.first())
+        .map(|l| l.to_string())
+        .unwrap_or_else(|| "F.Cu".to_owned())
+}
+
+fn first_layer(item: &[Sexpr]) -> String {
+    find(item, "layer")
+        .or_else(|| find(item, "layers"))
+        .and_then(|l| l.get(1))
+        .and_then(|s| s.as_atom())
+        .unwrap_or("")
+        .to_owned()
+}
+
+fn is_locked(item: &[Sexpr]) -> bool {
+    item.iter().any(|s| s.as_sym() == Some("locked"))
+        || find(item, "locked").is_some_and(|l| l.get(1).and_then(|s| s.as_sym()) != Some("no"))
+}
+
+/// Canonical layer name -> user-visible name from the board's layer table
+fn user_layer_names(board: &Board) -> HashMap<String, String> {
+    board
+        .items()
+        .iter()
+        .find(|item| head_of(item) == Some("layers"))
+        .and_then(|item| item.as_list())
+        .map(|layers| {
+            layers[1..]
+                .iter()
+                .filter_map(|layer| {
+                    let layer = layer.as_list()?;
+                    let name = layer.get(1)?.as_atom()?;
+                    let alias = layer.get(3).and_then(|s| s.as_atom()).unwrap_or(name);
+                    Some((name.to_owned(), alias.to_owned()))
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn point(x: f64, y: f64) -> Value {
+    json!({ "x": nm(x), "y": nm(y) })
+}
+
+/// Millimeters to KiCad internal units
+fn nm(mm: f64) -> i64 {
+    (mm * 1_000_000.0).round() as i64
+}
+
+/// Sort object keys and list entries, like `canonicalize_json` in the Python script
+fn canonicalize(value: Value) -> Value {
+    match value {
+        Value::Object(map) => Value::Object(
+            map.into_iter()
+                .map(|(k, v)| (k, canonicalize(v)))
+                .collect::<BTreeMap<_, _>>()
+                .into_iter()
+                .collect(),
+        ),
+        Value::Array(items) => {
+            let mut items: Vec<(String, Value)> = items
+                .into_iter()
+                .map(canonicalize)
+                .map(|v| (python_dumps(&v), v))
+                .collect();
+            items.sort_by(|a, b| a.0.cmp(&b.0));
+            Value::Array(items.into_iter().map(|(_, v)| v).collect())
+        }
+        other => other,
+    }
+}
+
+/// `json.dumps(value, sort_keys=True)` with Python's default separators, used as a sort key
+fn python_dumps(value: &Value) -> String {
+    match value {
+        Value::Object(map) => {
+            let mut entries: Vec<_> = map.iter().collect();
+            entries.sort_by(|a, b| a.0.cmp(b.0));
+            let body: Vec<String> = entries
+                .into_iter()
+                .map(|(k, v)| format!("{}: {}", Value::String(k.clone()), python_dumps(v)))
+                .collect();
+            format!("{{{}}}", body.join(", "))
+        }
+        Value::Array(items) => {
+            let body: Vec<String> = items.iter().map(python_dumps).collect();
+            format!("[{}]", body.join(", "))
+        }
+        Value::Null => "null".to_owned(),
+        Value::Bool(b) => b.to_string(),
+        other => other.to_string(),
+    }
+}
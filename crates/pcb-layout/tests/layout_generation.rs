@@ -1,78 +1,112 @@
 use anyhow::Result;
 use assert_fs::prelude::*;
 use assert_fs::TempDir;
-use pcb_layout::process_layout;
+use pcb_layout::{process_layout, LayoutResult, LayoutReuse};
 use serial_test::serial;
+use std::path::{Path, PathBuf};
 
 mod helpers;
 use helpers::*;
 
+/// Evaluate `<board_name>.zen` in `dir` and sync its layout
+fn sync_board(
+    dir: &Path,
+    board_name: &str,
+    sync_board_config: bool,
+    reuse: Option<&LayoutReuse>,
+) -> Result<LayoutResult> {
+    // Find and evaluate the board zen file
+    let zen_file = dir.join(format!("{board_name}.zen"));
+    assert!(zen_file.exists(), "{board_name}.zen should exist");
+
+    // Evaluate the Zen file to generate a schematic
+    let (output, diagnostics) = pcb_zen::run(&zen_file, pcb_zen::EvalConfig::default()).unpack();
+
+    // Check for errors in evaluation
+    if !diagnostics.is_empty() {
+        eprintln!("Zen evaluation diagnostics:");
+        for diag in diagnostics {
+            eprintln!("  {:?}", diag);
+        }
+    }
+
+    let schematic = output.expect("Zen evaluation should produce a schematic");
+
+    // Process the layout (enable sync_board_config for tests that need netclass assignment)
+    let result = process_layout(
+        &schematic,
+        &zen_file,
+        sync_board_config,
+        false,
+        false,
+        reuse,
+    )?;
+
+    // Verify the layout was created
+    assert!(result.pcb_file.exists(), "PCB file should exist");
+    assert!(result.netlist_file.exists(), "Netlist file should exist");
+    assert!(result.snapshot_file.exists(), "Snapshot file should exist");
+    assert!(result.log_file.exists(), "Log file should exist");
+
+    // Print the log file contents
+    let log_contents = std::fs::read_to_string(&result.log_file)?;
+    println!("Layout log file contents:");
+    println!("========================");
+    println!("{}", log_contents);
+    println!("========================");
+
+    Ok(result)
+}
+
+/// Copy the test resources to a temp directory and sync the board's layout there
+fn sync_resource(
+    name: &str,
+    board_name: &str,
+    sync_board_config: bool,
+    reuse: Option<&LayoutReuse>,
+) -> Result<(PathBuf, LayoutResult)> {
+    let temp = TempDir::new()?.into_persistent();
+    temp.copy_from(get_resource_path(name), &["**/*", "!.pcb/cache/**/*"])?;
+    let result = sync_board(temp.path(), board_name, sync_board_config, reuse)?;
+    Ok((temp.path().to_path_buf(), result))
+}
+
 macro_rules! layout_test {
+    (@engine $test_name:ident, $engine:expr, $name:expr, $board_name:expr, $snapshot_kicad_pro:expr) => {
+        #[cfg(not(target_os = "windows"))]
+        #[test]
+        #[serial]
+        fn $test_name() -> Result<()> {
+            std::env::set_var(pcb_layout::LAYOUT_ENGINE_ENV, $engine);
+            let (_, result) = sync_resource($name, $board_name, $snapshot_kicad_pro, None)?;
+
+            // Check the snapshot matches
+            assert_file_snapshot!(
+                format!("{}.layout.json", $name),
+                result.snapshot_file
+            );
+
+            // Snapshot netclass_patterns from .kicad_pro if requested
+            if $snapshot_kicad_pro {
+                let kicad_pro_path = result.pcb_file.with_extension("kicad_pro");
+                assert!(kicad_pro_path.exists(), "kicad_pro file should exist");
+                assert_netclass_patterns_snapshot!(
+                    format!("{}.netclass_patterns.json", $name),
+                    kicad_pro_path
+                );
+            }
+
+            Ok(())
+        }
+    };
     ($name:expr, $board_name:expr) => {
         layout_test!($name, $board_name, false);
     };
     ($name:expr, $board_name:expr, $snapshot_kicad_pro:expr) => {
+        // Both sync engines must produce the same board, so they share one snapshot
         paste::paste! {
-            #[cfg(not(target_os = "windows"))]
-            #[test]
-            #[serial]
-            fn [<test_layout_generation_with_ $name:snake>]() -> Result<()> {
-                // Create a temp directory and copy the test resources
-                let temp = TempDir::new()?.into_persistent();
-                let resource_path = get_resource_path($name);
-                temp.copy_from(&resource_path, &["**/*", "!.pcb/cache/**/*"])?;
-
-                // Find and evaluate the board zen file
-                let zen_file = temp.path().join(format!("{}.zen", $board_name));
-                assert!(zen_file.exists(), "{}.zen should exist", $board_name);
-
-                // Evaluate the Zen file to generate a schematic
-                let (output, diagnostics) = pcb_zen::run(&zen_file, pcb_zen::EvalConfig::default()).unpack();
-
-                // Check for errors in evaluation
-                if !diagnostics.is_empty() {
-                    eprintln!("Zen evaluation diagnostics:");
-                    for diag in diagnostics {
-                        eprintln!("  {:?}", diag);
-                    }
-                }
-
-                let schematic = output.expect("Zen evaluation should produce a schematic");
-
-                // Process the layout (enable sync_board_config for tests that need netclass assignment)
-                let result = process_layout(&schematic, &zen_file, $snapshot_kicad_pro, false, false)?;
-
-                // Verify the layout was created
-                assert!(result.pcb_file.exists(), "PCB file should exist");
-                assert!(result.netlist_file.exists(), "Netlist file should exist");
-                assert!(result.snapshot_file.exists(), "Snapshot file should exist");
-                assert!(result.log_file.exists(), "Log file should exist");
-
-                // Print the log file contents
-                let log_contents = std::fs::read_to_string(&result.log_file)?;
-                println!("Layout log file contents:");
-                println!("========================");
-                println!("{}", log_contents);
-                println!("========================");
-
-                // Check the snapshot matches
-                assert_file_snapshot!(
-                    format!("{}.layout.json", $name),
-                    result.snapshot_file
-                );
-
-                // Snapshot netclass_patterns from .kicad_pro if requested
-                if $snapshot_kicad_pro {
-                    let kicad_pro_path = result.pcb_file.with_extension("kicad_pro");
-                    assert!(kicad_pro_path.exists(), "kicad_pro file should exist");
-                    assert_netclass_patterns_snapshot!(
-                        format!("{}.netclass_patterns.json", $name),
-                        kicad_pro_path
-                    );
-                }
-
-                Ok(())
-            }
+            layout_test!(@engine [<test_layout_generation_with_ $name:snake>], "python", $name, $board_name, $snapshot_kicad_pro);
+            layout_test!(@engine [<test_native_layout_generation_with_ $name:snake>], "native", $name, $board_name, $snapshot_kicad_pro);
         }
     };
 }
@@ -97,3 +131,89 @@ layout_test!("graphics", "Board");
 layout_test!("complex", "Board");
 
 layout_test!("netclass_assignment", "netclass", true);
+
+/// Copy PhaseA's layout to PhaseB. Layout reuse only runs in the Python sync.
+fn reuse_phase_a(save_module_layout: bool) -> LayoutReuse {
+    std::env::set_var(pcb_layout::LAYOUT_ENGINE_ENV, "python");
+    LayoutReuse {
+        instance: "PhaseA".to_owned(),
+        save_module_layout,
+    }
+}
+
+// Two instances of one module on different nets; PhaseA's layout is copied to PhaseB.
+#[cfg(not(target_os = "windows"))]
+#[test]
+#[serial]
+fn test_layout_propagation_with_phase_drivers() -> Result<()> {
+    let (_, result) = sync_resource("phase_drivers", "Board", false, Some(&reuse_phase_a(false)))?;
+    assert_eq!(result.propagated_to, ["PhaseB"]);
+
+    // The copied tracks and vias must be on PhaseB's nets, not PhaseA's
+    let snapshot: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(&result.snapshot_file)?)?;
+    let phase_b = snapshot["groups"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|group| group["name"] == "PhaseB")
+        .expect("PhaseB should have a group");
+    let bbox = &phase_b["bounding_box"];
+    let inside = |point: &serde_json::Value| {
+        let (x, y) = (point["x"].as_i64().unwrap(), point["y"].as_i64().unwrap());
+        (bbox["left"].as_i64().unwrap()..=bbox["right"].as_i64().unwrap()).contains(&x)
+            && (bbox["top"].as_i64().unwrap()..=bbox["bottom"].as_i64().unwrap()).contains(&y)
+    };
+    let tracks = snapshot["tracks"].as_array().unwrap().iter();
+    let vias = snapshot["vias"].as_array().unwrap().iter();
+    let phase_b_nets: Vec<&str> = tracks
+        .filter(|track| inside(&track["start"]) && inside(&track["end"]))
+        .chain(vias.filter(|via| inside(&via["position"])))
+        .map(|item| item["net_name"].as_str().unwrap())
+        .collect();
+    assert!(!phase_b_nets.is_empty(), "PhaseB should have copied tracks");
+    for net in phase_b_nets {
+        assert!(net.starts_with("PHASE_B_"), "PhaseB item on net {net}");
+    }
+
+    assert_file_snapshot!("phase_drivers.layout.json", result.snapshot_file);
+    Ok(())
+}
+
+// Saving PhaseA's layout creates the module's layout board when it has none, then updates it.
+#[cfg(not(target_os = "windows"))]
+#[test]
+#[serial]
+fn test_layout_propagation_saves_module_layout() -> Result<()> {
+    let temp = TempDir::new()?.into_persistent();
+    temp.copy_from(
+        get_resource_path("phase_drivers"),
+        &["**/*", "!.pcb/cache/**/*", "!module/layout.kicad_pcb"],
+    )?;
+    let module_layout = temp.path().join("module/layout.kicad_pcb");
+    assert!(!module_layout.exists());
+
+    let reuse = reuse_phase_a(true);
+    for _ in 0..2 {
+        let result = sync_board(temp.path(), "Board", false, Some(&reuse))?;
+        assert_eq!(result.propagated_to, ["PhaseB"]);
+
+        // Footprints are keyed by their path within the module, on nets recreated by name
+        let content = std::fs::read_to_string(&module_layout)?;
+        for path in ["R1.R", "R2.R"] {
+            let field = format!("\"Path\" \"{path}\"");
+            assert_eq!(
+                content.matches(&field).count(),
+                1,
+                "{field} in the module layout"
+            );
+        }
+        for net in ["PHASE_A_IN", "PHASE_A_OUT"] {
+            assert!(
+                content.contains(&format!("\"{net}\"")),
+                "{net} in the module layout"
+            );
+        }
+    }
+    Ok(())
+}
@@ -807,7 +807,8 @@ fn capacitor_meets_or_exceeds(a: &Capacitor, b: &Capacitor) -> bool {
         && meets_or_exceeds(&a.esr, &b.esr, |ea, eb| ea.value <= eb.value)
 }
 
-fn detect_generic_component(instance: &crate::Instance) -> Option<GenericComponent> {
+/// Recognize a generic resistor or capacitor from its `Type` and value properties
+pub fn detect_generic_component(instance: &crate::Instance) -> Option<GenericComponent> {
     match instance.component_type()?.as_str() {
         "resistor" => {
             if let Some(resistance) = instance.physical_attr(&["Resistance", "resistance"]) {
@@ -0,0 +1,490 @@
+//! Typed design-rule constraints.
+//!
+//! Rules are declared in Zener with `design_rule()` on nets and interfaces, or per netclass in
+//! the `rules` list of a board config's design rules. `pcb layout` emits them into the board's
+//! KiCad custom rules file (`.kicad_dru`).
+//!
+//! Lengths are in millimetres. In JSON a rule is a flat object that sets exactly one of
+//! `clearance`, `max_length`, `length_match`, `max_vias` or `keepout`, and targets either
+//! `nets` or a `netclass`:
+//!
+//! ```json
+//! {"name": "hv", "netclass": "HV", "clearance": 2.0, "against_netclass": "Default"}
+//! ```
+
+use rust_decimal::prelude::ToPrimitive;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::physical::{PhysicalUnitDims, PhysicalValue};
+
+/// The nets a rule applies to
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum RuleScope {
+    /// Nets by name
+    Nets(Vec<String>),
+    /// Every net of a netclass
+    NetClass(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum RuleKind {
+    /// Minimum copper clearance from the scope's nets to `against`, or to anything else
+    Clearance {
+        min: f64,
+        against: Option<RuleScope>,
+    },
+    /// Maximum routed length of each net
+    MaxLength { max: f64 },
+    /// Routed lengths of the scope's nets within `tolerance` of each other
+    LengthMatch { tolerance: f64 },
+    /// Routed lengths of the scope's nets, the two sides of a differential pair, within `max`
+    /// of each other
+    Skew { max: f64 },
+    /// Maximum number of vias on each net
+    MaxVias { max: u32 },
+    /// Keep the scope's tracks, vias and zones off these copper layers
+    Keepout { layers: Vec<String> },
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "RawDesignRule", into = "RawDesignRule")]
+pub struct DesignRule {
+    pub name: String,
+    pub scope: RuleScope,
+    pub kind: RuleKind,
+}
+
+#[derive(Debug, Error, PartialEq)]
+pub enum DesignRuleError {
+    #[error("Design rule name must not be empty")]
+    EmptyName,
+
+    #[error("Design rule '{0}' must target either nets or a netclass")]
+    Scope(String),
+
+    #[error(
+        "Design rule '{0}' must set exactly one of clearance, max_length, length_match, max_vias or keepout"
+    )]
+    Kind(String),
+
+    #[error("Design rule '{rule}': `{field}` must be positive, got {value}")]
+    NotPositive {
+        rule: String,
+        field: &'static str,
+        value: f64,
+    },
+
+    #[error("Design rule '{0}': `against` only applies to clearance rules")]
+    AgainstWithoutClearance(String),
+
+    #[error("Design rule '{0}' matches lengths and needs at least two nets")]
+    TooFewNets(String),
+
+    #[error(
+        "Design rule '{rule}': KiCad rules cannot match '{name}', whose name contains a quote"
+    )]
+    QuotedName { rule: String, name: String },
+
+    #[error("Design rule '{0}': keepout needs at least one layer")]
+    NoLayers(String),
+
+    #[error("Design rule '{rule}' references unknown netclass '{netclass}'")]
+    UnknownNetClass { rule: String, netclass: String },
+}
+
+impl DesignRule {
+    /// A validated rule
+    pub fn new(
+        name: impl Into<String>,
+        scope: RuleScope,
+        kind: RuleKind,
+    ) -> Result<Self, DesignRuleError> {
+        let rule = Self {
+            name: name.into(),
+            scope,
+            kind,
+        };
+        rule.validate()?;
+        Ok(rule)
+    }
+
+    pub fn validate(&self) -> Result<(), DesignRuleError> {
+        let name = || self.name.clone();
+        if self.name.trim().is_empty() {
+            return Err(DesignRuleError::EmptyName);
+        }
+        let scopes = std::iter::once(&self.scope).chain(match &self.kind {
+            RuleKind::Clearance { against, .. } => against.as_ref(),
+            _ => None,
+        });
+        for scope in scopes {
+            let (names, empty) = match scope {
+                RuleScope::Nets(nets) => (nets.as_slice(), nets.is_empty()),
+                RuleScope::NetClass(class) => (std::slice::from_ref(class), class.is_empty()),
+            };
+            if empty {
+                return Err(DesignRuleError::Scope(name()));
+            }
+            // KiCad rule conditions quote names in '...' with no way to escape a quote
+            if let Some(quoted) = names.iter().find(|n| n.contains(['\'', '"'])) {
+                return Err(DesignRuleError::QuotedName {
+                    rule: name(),
+                    name: quoted.clone(),
+                });
+            }
+        }
+
+        let positive = |field: &'static str, value: f64| {
+            if value > 0.0 {
+                Ok(())
+            } else {
+                Err(DesignRuleError::NotPositive {
+                    rule: name(),
+                    field,
+                    value,
+                })
+            }
+        };
+        let two_nets = || match &self.scope {
+            RuleScope::Nets(nets) if nets.len() < 2 => Err(DesignRuleError::TooFewNets(name())),
+            _ => Ok(()),
+        };
+        match &self.kind {
+            RuleKind::Clearance { min, .. } => positive("clearance", *min),
+            RuleKind::MaxLength { max } => positive("max_length", *max),
+            RuleKind::LengthMatch { tolerance } => {
+                positive("length_match", *tolerance)?;
+                two_nets()
+            }
+            RuleKind::Skew { max } => {
+                positive("skew", *max)?;
+                two_nets()
+            }
+            RuleKind::MaxVias { .. } => Ok(()),
+            RuleKind::Keepout { layers } if layers.is_empty() => {
+                Err(DesignRuleError::NoLayers(name()))
+            }
+            RuleKind::Keepout { .. } => Ok(()),
+        }
+    }
+
+    /// Netclasses the rule refers to
+    pub fn netclasses(&self) -> Vec<&str> {
+        let against = match &self.kind {
+            RuleKind::Clearance { against, .. } => against.as_ref(),
+            _ => None,
+        };
+        std::iter::once(&self.scope)
+            .chain(against)
+            .filter_map(|scope| match scope {
+                RuleScope::NetClass(class) => Some(class.as_str()),
+                RuleScope::Nets(_) => None,
+            })
+            .collect()
+    }
+
+    /// Rename the nets the rule refers to. Nets `rename` returns None for are dropped.
+    pub fn map_nets(mut self, mut rename: impl FnMut(&str) -> Option<String>) -> Self {
+        let mut map = |scope: &mut RuleScope| {
+            if let RuleScope::Nets(nets) = scope {
+                *nets = nets.iter().filter_map(|net| rename(net)).collect();
+            }
+        };
+        map(&mut self.scope);
+        if let RuleKind::Clearance {
+            against: Some(against),
+            ..
+        } = &mut self.kind
+        {
+            map(against);
+        }
+        self
+    }
+}
+
+/// Flat JSON form of [`DesignRule`], friendly to Zener records (unset fields are `null`)
+#[derive(Default, Serialize, Deserialize)]
+struct RawDesignRule {
+    name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    nets: Option<Vec<String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    netclass: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    against_nets: Option<Vec<String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    against_netclass: Option<String>,
+    #[serde(
+        default,
+        deserialize_with = "deserialize_opt_mm",
+        skip_serializing_if = "Option::is_none"
+    )]
+    clearance: Option<f64>,
+    #[serde(
+        default,
+        deserialize_with = "deserialize_opt_mm",
+        skip_serializing_if = "Option::is_none"
+    )]
+    max_length: Option<f64>,
+    #[serde(
+        default,
+        deserialize_with = "deserialize_opt_mm",
+        skip_serializing_if = "Option::is_none"
+    )]
+    length_match: Option<f64>,
+    /// Intra-pair skew of interfaces with P and N nets; `design_rule()` has no such argument
+    #[serde(
+        default,
+        deserialize_with = "deserialize_opt_mm",
+        skip_serializing_if = "Option::is_none"
+    )]
+    skew: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    max_vias: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    keepout: Option<Vec<String>>,
+}
+
+fn raw_scope(nets: Option<Vec<String>>, netclass: Option<String>) -> Result<Option<RuleScope>, ()> {
+    match (nets, netclass) {
+        (None, None) => Ok(None),
+        (Some(nets), None) => Ok(Some(RuleScope::Nets(nets))),
+        (None, Some(class)) => Ok(Some(RuleScope::NetClass(class))),
+        (Some(_), Some(_)) => Err(()),
+    }
+}
+
+impl TryFrom<RawDesignRule> for DesignRule {
+    type Error = DesignRuleError;
+
+    fn try_from(raw: RawDesignRule) -> Result<Self, Self::Error> {
+        let name = raw.name;
+        let scope = raw_scope(raw.nets, raw.netclass)
+            .ok()
+            .flatten()
+            .ok_or_else(|| DesignRuleError::Scope(name.clone()))?;
+        let against = raw_scope(raw.against_nets, raw.against_netclass)
+            .map_err(|_| DesignRuleError::Scope(name.clone()))?;
+
+        let mut kinds = Vec::new();
+        if let Some(min) = raw.clearance {
+            kinds.push(RuleKind::Clearance {
+                min,
+                against: against.clone(),
+            });
+        }
+        if let Some(max) = raw.max_length {
+            kinds.push(RuleKind::MaxLength { max });
+        }
+        if let Some(tolerance) = raw.length_match {
+            kinds.push(RuleKind::LengthMatch { tolerance });
+        }
+        if let Some(max) = raw.skew {
+            kinds.push(RuleKind::Skew { max });
+        }
+        if let Some(max) = raw.max_vias {
+            kinds.push(RuleKind::MaxVias { max });
+        }
+        if let Some(layers) = raw.keepout {
+            kinds.push(RuleKind::Keepout { layers });
+        }
+        if kinds.len() != 1 {
+            return Err(DesignRuleError::Kind(name));
+        }
+        let kind = kinds.remove(0);
+        if against.is_some() && !matches!(kind, RuleKind::Clearance { .. }) {
+            return Err(DesignRuleError::AgainstWithoutClearance(name));
+        }
+        DesignRule::new(name, scope, kind)
+    }
+}
+
+impl From<DesignRule> for RawDesignRule {
+    fn from(rule: DesignRule) -> Self {
+        let mut raw = RawDesignRule {
+            name: rule.name,
+            ..Default::default()
+        };
+        let split = |scope: RuleScope| match scope {
+            RuleScope::Nets(nets) => (Some(nets), None),
+            RuleScope::NetClass(class) => (None, Some(class)),
+        };
+        (raw.nets, raw.netclass) = split(rule.scope);
+        match rule.kind {
+            RuleKind::Clearance { min, against } => {
+                raw.clearance = Some(min);
+                if let Some(against) = against {
+                    (raw.against_nets, raw.against_netclass) = split(against);
+                }
+            }
+            RuleKind::MaxLength { max } => raw.max_length = Some(max),
+            RuleKind::LengthMatch { tolerance } => raw.length_match = Some(tolerance),
+            RuleKind::Skew { max } => raw.skew = Some(max),
+            RuleKind::MaxVias { max } => raw.max_vias = Some(max),
+            RuleKind::Keepout { layers } => raw.keepout = Some(layers),
+        }
+        raw
+    }
+}
+
+/// A length in millimetres, given either as a bare number or as a `Length` value
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum MmOrLength {
+    Mm(f64),
+    Length(PhysicalValue),
+}
+
+fn deserialize_opt_mm<'de, D: serde::Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<f64>, D::Error> {
+    use serde::de::Error;
+    match Option::<MmOrLength>::deserialize(deserializer)? {
+        None => Ok(None),
+        Some(MmOrLength::Mm(mm)) => Ok(Some(mm)),
+        Some(MmOrLength::Length(pv)) if pv.unit == PhysicalUnitDims::LENGTH => (pv.value
+            * rust_decimal::Decimal::ONE_THOUSAND)
+            .to_f64()
+            .map(Some)
+            .ok_or_else(|| D::Error::custom(format!("length {pv} is out of range"))),
+        Some(MmOrLength::Length(pv)) => Err(D::Error::custom(format!(
+            "expected a Length, got {} ({pv})",
+            pv.unit.quantity()
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn parses_flat_json() {
+        let rule: DesignRule = serde_json::from_value(json!({
+            "name": "hv",
+            "nets": null,
+            "netclass": "HV",
+            "against_nets": null,
+            "against_netclass": "Default",
+            "clearance": 2.0,
+            "max_length": null,
+            "length_match": null,
+            "max_vias": null,
+            "keepout": null,
+        }))
+        .unwrap();
+        assert_eq!(
+            rule.kind,
+            RuleKind::Clearance {
+                min: 2.0,
+                against: Some(RuleScope::NetClass("Default".into())),
+            }
+        );
+        assert_eq!(rule.netclasses(), vec!["HV", "Default"]);
+
+        // Round-trips without the nulls
+        let value = serde_json::to_value(&rule).unwrap();
+        assert_eq!(
+            value,
+            json!({"name": "hv", "netclass": "HV", "against_netclass": "Default", "clearance": 2.0})
+        );
+        assert_eq!(serde_json::from_value::<DesignRule>(value).unwrap(), rule);
+    }
+
+    #[test]
+    fn skew_round_trips_as_its_own_kind() {
+        let rule = DesignRule::new(
+            "usb.skew",
+            RuleScope::Nets(vec!["D_P".into(), "D_N".into()]),
+            RuleKind::Skew { max: 0.1 },
+        )
+        .unwrap();
+        let value = serde_json::to_value(&rule).unwrap();
+        assert_eq!(
+            value,
+            json!({"name": "usb.skew", "nets": ["D_P", "D_N"], "skew": 0.1})
+        );
+        assert_eq!(serde_json::from_value::<DesignRule>(value).unwrap(), rule);
+    }
+
+    #[test]
+    fn rejects_invalid_rules() {
+        let parse = |value: serde_json::Value| serde_json::from_value::<DesignRule>(value);
+
+        assert!(
+            parse(json!({"name": "a", "nets": ["X"], "max_length": 5.0, "max_vias": 2})).is_err()
+        );
+        assert!(parse(json!({"name": "a", "max_length": 5.0})).is_err());
+        assert!(
+            parse(json!({"name": "a", "nets": ["X"], "netclass": "C", "max_vias": 1})).is_err()
+        );
+        assert!(parse(json!({"name": "a", "nets": ["X"], "max_length": -1.0})).is_err());
+        assert!(parse(json!({"name": "a", "nets": ["X"], "length_match": 0.1})).is_err());
+        assert!(parse(json!({"name": "a", "nets": ["X", "Y"], "skew": 0.0})).is_err());
+        assert!(parse(json!({"name": "a", "nets": ["X"], "keepout": []})).is_err());
+        assert!(
+            parse(json!({"name": "a", "nets": ["X"], "against_nets": ["Y"], "max_vias": 1}))
+                .is_err()
+        );
+
+        let err = DesignRule::new(
+            "match",
+            RuleScope::Nets(vec!["D_P".into()]),
+            RuleKind::LengthMatch { tolerance: 0.1 },
+        )
+        .unwrap_err();
+        assert_eq!(err, DesignRuleError::TooFewNets("match".into()));
+
+        let err = DesignRule::new(
+            "gap",
+            RuleScope::NetClass("HV".into()),
+            RuleKind::Clearance {
+                min: 1.0,
+                against: Some(RuleScope::Nets(vec!["BOB'S_NET".into()])),
+            },
+        )
+        .unwrap_err();
+        assert_eq!(
+            err,
+            DesignRuleError::QuotedName {
+                rule: "gap".into(),
+                name: "BOB'S_NET".into(),
+            }
+        );
+    }
+
+    #[test]
+    fn lengths_accept_length_values() {
+        let rule: DesignRule = serde_json::from_value(json!({
+            "name": "usb",
+            "nets": ["D_P", "D_N"],
+            "max_length": {"value": "0.05", "tolerance": "0", "unit": "Meters"},
+        }))
+        .unwrap_or_else(|e| panic!("{e}"));
+        assert_eq!(rule.kind, RuleKind::MaxLength { max: 50.0 });
+    }
+
+    #[test]
+    fn map_nets_renames_and_drops() {
+        let rule = DesignRule::new(
+            "gap",
+            RuleScope::Nets(vec!["1".into(), "2".into()]),
+            RuleKind::Clearance {
+                min: 0.5,
+                against: Some(RuleScope::Nets(vec!["3".into()])),
+            },
+        )
+        .unwrap()
+        .map_nets(|id| (id != "2").then(|| format!("N{id}")));
+        assert_eq!(rule.scope, RuleScope::Nets(vec!["N1".into()]));
+        assert_eq!(
+            rule.kind,
+            RuleKind::Clearance {
+                min: 0.5,
+                against: Some(RuleScope::Nets(vec!["N3".into()])),
+            }
+        );
+    }
+}
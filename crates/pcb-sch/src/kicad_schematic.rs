@@ -36,6 +36,9 @@ pub enum ConversionError {
         "KiCad symbol directory not found. Please set KICAD_SYMBOL_DIR environment variable or install KiCad"
     )]
     KiCadSymbolDirNotFound,
+
+    #[error("Failed to write schematic file {0}: {1}")]
+    WriteError(PathBuf, std::io::Error),
 }
 
 /// Minimal symbol info needed for creating instances
@@ -70,6 +73,236 @@ pub fn to_kicad_schematic(sch: &Schematic, output_path: &Path) -> Result<String,
     converter.convert(sch, output_path)
 }
 
+/// Textual form of a numeric atom.
+///
+/// The S-expression parser yields numbers as `Int`/`F64` rather than symbols, so coordinates
+/// must not be read with `as_atom()` alone.
+fn numeric_atom(sexpr: &Sexpr) -> Option<String> {
+    match sexpr {
+        Sexpr::Int(n) => Some(n.to_string()),
+        Sexpr::F64(f) => Some(f.to_string()),
+        other => other.as_atom().map(str::to_string),
+    }
+}
+
+/// Size of the sheet symbol drawn in the parent page for each hierarchical sheet
+const SHEET_SIZE: (f64, f64) = (30.48, 15.24);
+
+/// Vertical space reserved above and below a sheet symbol for its name and file fields
+const SHEET_FIELD_MARGIN: f64 = 5.08;
+
+/// A single `.kicad_sch` file produced by [`to_kicad_schematic_sheets`]
+#[derive(Debug, Clone)]
+pub struct SchematicSheet {
+    /// Where the sheet should be written. The first sheet is always the root sheet.
+    pub path: PathBuf,
+    /// Formatted `.kicad_sch` content
+    pub content: String,
+}
+
+/// One page of a hierarchical schematic: a module instance and what is drawn on it
+#[derive(Debug, Clone)]
+struct SheetPlan {
+    /// Module instance this page represents
+    module_ref: InstanceRef,
+    /// File name of the page, relative to the root sheet
+    file_name: String,
+    /// UUID written into the page's own header
+    file_uuid: String,
+    /// KiCad hierarchical path of this page (e.g. `/<root uuid>/<sheet uuid>`)
+    sheet_path: String,
+    /// Components drawn on this page (including those of collapsed single-component modules)
+    components: Vec<InstanceRef>,
+    /// Sub-sheets referenced from this page
+    children: Vec<ChildSheet>,
+}
+
+/// A sheet symbol placed on a parent page
+#[derive(Debug, Clone)]
+struct ChildSheet {
+    name: String,
+    file_name: String,
+    uuid: String,
+    page: usize,
+    position: (f64, f64),
+}
+
+/// Convert a pcb_sch::Schematic into a hierarchical set of KiCad schematic sheets.
+///
+/// The root module is written to `root_path`. Every module instance that contains more than
+/// one component gets its own page next to it and a sheet symbol on its parent page; modules
+/// wrapping a single component (such as stdlib generics) are drawn inline. Nets are connected
+/// across pages with global labels, and component placements from `# pcb:sch` comments are
+/// honored on the page of the module that declared them.
+pub fn to_kicad_schematic_sheets(
+    sch: &Schematic,
+    root_path: &Path,
+) -> Result<Vec<SchematicSheet>, ConversionError> {
+    let Some(root_ref) = sch.root_ref.as_ref() else {
+        // Without a root module there is no hierarchy to follow - emit a single flat page
+        return Ok(vec![SchematicSheet {
+            path: root_path.to_path_buf(),
+            content: to_kicad_schematic(sch, root_path)?,
+        }]);
+    };
+
+    let root_stem = root_path
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or("schematic");
+    let root_uuid = Uuid::new_v4().to_string();
+    let root_file_name = root_path
+        .file_name()
+        .and_then(|s| s.to_str())
+        .unwrap_or("schematic.kicad_sch")
+        .to_string();
+
+    let mut plans = Vec::new();
+    let mut next_page = 2;
+    plan_sheet(
+        sch,
+        root_ref,
+        root_file_name,
+        root_uuid.clone(),
+        format!("/{root_uuid}"),
+        root_stem,
+        &mut next_page,
+        &mut plans,
+    );
+
+    let output_dir = root_path.parent().unwrap_or(Path::new(""));
+    plans
+        .into_iter()
+        .map(|plan| {
+            let path = output_dir.join(&plan.file_name);
+            let mut converter = SchematicConverter::for_sheet(plan);
+            let content = converter.convert(sch, root_path)?;
+            Ok(SchematicSheet { path, content })
+        })
+        .collect()
+}
+
+/// Convert a schematic into hierarchical sheets and write them next to `root_path`.
+///
+/// Returns the paths of all written files, root sheet first.
+pub fn write_kicad_schematic_sheets(
+    sch: &Schematic,
+    root_path: &Path,
+) -> Result<Vec<PathBuf>, ConversionError> {
+    let sheets = to_kicad_schematic_sheets(sch, root_path)?;
+    let mut written = Vec::with_capacity(sheets.len());
+    for sheet in sheets {
+        write_schematic_file(&sheet.content, &sheet.path)
+            .map_err(|e| ConversionError::WriteError(sheet.path.clone(), e))?;
+        written.push(sheet.path);
+    }
+    Ok(written)
+}
+
+/// Recursively build the page plan for `module_ref` and all sub-sheets below it
+#[allow(clippy::too_many_arguments)]
+fn plan_sheet(
+    sch: &Schematic,
+    module_ref: &InstanceRef,
+    file_name: String,
+    file_uuid: String,
+    sheet_path: String,
+    root_stem: &str,
+    next_page: &mut usize,
+    plans: &mut Vec<SheetPlan>,
+) {
+    let mut components = Vec::new();
+    let mut sub_modules = Vec::new();
+    collect_sheet_contents(sch, module_ref, &mut components, &mut sub_modules);
+    components.sort_by(|a, b| a.instance_path.cmp(&b.instance_path));
+    sub_modules.sort_by(|a, b| a.instance_path.cmp(&b.instance_path));
+
+    let children: Vec<ChildSheet> = sub_modules
+        .iter()
+        .map(|child_ref| {
+            let page = *next_page;
+            *next_page += 1;
+            ChildSheet {
+                name: child_ref
+                    .instance_path
+                    .last()
+                    .cloned()
+                    .unwrap_or_else(|| child_ref.module.module_name.clone()),
+                file_name: format!(
+                    "{root_stem}-{}.kicad_sch",
+                    child_ref.instance_path.join(".")
+                ),
+                uuid: Uuid::new_v4().to_string(),
+                page,
+                position: (0.0, 0.0),
+            }
+        })
+        .collect();
+
+    plans.push(SheetPlan {
+        module_ref: module_ref.clone(),
+        file_name,
+        file_uuid,
+        sheet_path: sheet_path.clone(),
+        components,
+        children: children.clone(),
+    });
+
+    for (child_ref, child) in sub_modules.iter().zip(children) {
+        plan_sheet(
+            sch,
+            child_ref,
+            child.file_name,
+            Uuid::new_v4().to_string(),
+            format!("{sheet_path}/{}", child.uuid),
+            root_stem,
+            next_page,
+            plans,
+        );
+    }
+}
+
+/// Collect the components drawn on a module's page and the sub-modules that get their own page
+fn collect_sheet_contents(
+    sch: &Schematic,
+    module_ref: &InstanceRef,
+    components: &mut Vec<InstanceRef>,
+    sub_modules: &mut Vec<InstanceRef>,
+) {
+    let Some(module) = sch.instances.get(module_ref) else {
+        return;
+    };
+    for child_ref in module.children.values() {
+        let Some(child) = sch.instances.get(child_ref) else {
+            continue;
+        };
+        match child.kind {
+            InstanceKind::Component => components.push(child_ref.clone()),
+            InstanceKind::Module if count_components(sch, child_ref) > 1 => {
+                sub_modules.push(child_ref.clone())
+            }
+            InstanceKind::Module => collect_sheet_contents(sch, child_ref, components, sub_modules),
+            _ => {}
+        }
+    }
+}
+
+/// Count all components below a module instance
+fn count_components(sch: &Schematic, inst_ref: &InstanceRef) -> usize {
+    let Some(instance) = sch.instances.get(inst_ref) else {
+        return 0;
+    };
+    match instance.kind {
+        InstanceKind::Component => 1,
+        InstanceKind::Module => instance
+            .children
+            .values()
+            .map(|child| count_components(sch, child))
+            .sum(),
+        _ => 0,
+    }
+}
+
 struct SchematicConverter {
     /// Map from component instance ref to its KiCad symbol
     symbols: Vec<SchematicSymbol>,
@@ -95,12 +328,15 @@ struct SchematicConverter {
     component_label_positions: HashMap<InstanceRef, Vec<LabelInfo>>,
     /// Debug mode flag - when true, renders component bounding boxes
     debug_mode: bool,
+    /// Page being generated when converting one sheet of a hierarchical schematic
+    sheet: Option<SheetPlan>,
 }
 
 #[derive(Debug)]
 struct SchematicSymbol {
     lib_id: String,
     position: (f64, f64),
+    rotation: f64,
     unit: i32,
     in_bom: bool,
     on_board: bool,
@@ -163,6 +399,21 @@ impl SchematicConverter {
             texts: Vec::new(),
             component_label_positions: HashMap::new(),
             debug_mode,
+            sheet: None,
+        }
+    }
+
+    fn for_sheet(sheet: SheetPlan) -> Self {
+        let mut converter = Self::with_debug(DEBUG_MODE);
+        converter.sheet = Some(sheet);
+        converter
+    }
+
+    /// Whether a component is drawn by this converter (always true for flat conversion)
+    fn is_on_sheet(&self, comp_ref: &InstanceRef) -> bool {
+        match &self.sheet {
+            Some(sheet) => sheet.components.contains(comp_ref),
+            None => true,
         }
     }
 
@@ -226,12 +477,22 @@ impl SchematicConverter {
         // Second pass: collect all components and their symbols
         log::debug!("Processing {} instances", sch.instances.len());
         for (inst_ref, instance) in &sch.instances {
-            if instance.kind == InstanceKind::Component {
+            if instance.kind == InstanceKind::Component && self.is_on_sheet(inst_ref) {
                 log::debug!("Processing component: {inst_ref}");
                 self.process_component(inst_ref, instance, output_path)?;
             }
         }
 
+        // Sub-sheets are placed like components, reserving room for their name/file fields
+        if let Some(sheet) = &self.sheet {
+            for child in &sheet.children {
+                self.layout_engine.set_component_size(
+                    child.uuid.clone(),
+                    Size::new(SHEET_SIZE.0, SHEET_SIZE.1 + 2.0 * SHEET_FIELD_MARGIN),
+                );
+            }
+        }
+
         // Build the module hierarchy for the new layout engine
         self.build_module_hierarchy(sch);
 
@@ -276,8 +537,18 @@ impl SchematicConverter {
             }
         }
 
-        // Create rectangles and labels for modules
-        for (id, bbox) in &bounding_boxes {
+        // Position sub-sheet symbols inside their reserved space
+        if let Some(sheet) = &mut self.sheet {
+            for child in &mut sheet.children {
+                if let Some(bbox) = bounding_boxes.get(&child.uuid) {
+                    child.position = (bbox.position.x, bbox.position.y + SHEET_FIELD_MARGIN);
+                }
+            }
+        }
+
+        // Create rectangles and labels for modules (sub-modules are separate pages in sheet mode)
+        let draw_module_boxes = self.sheet.is_none();
+        for (id, bbox) in bounding_boxes.iter().filter(|_| draw_module_boxes) {
             // Check if this is a module (not a component UUID)
             if !self.uuid_map.values().any(|uuid| uuid == id) {
                 // Check if this module has more than one child
@@ -453,6 +724,11 @@ impl SchematicConverter {
             }
         }
 
+        // Placements saved from the schematic viewer take precedence over the packed layout
+        if self.sheet.is_some() {
+            self.apply_saved_positions(sch);
+        }
+
         // Third pass: create net connections
         log::debug!("Processing {} nets", sch.nets.len());
         for (net_name, net) in &sch.nets {
@@ -543,6 +819,7 @@ impl SchematicConverter {
         let symbol = SchematicSymbol {
             lib_id: lib_id.clone(),
             position: (0.0, 0.0), // Will be updated after layout calculation
+            rotation: 0.0,
             unit: 1,
             in_bom: true,
             on_board: true,
@@ -886,8 +1163,8 @@ impl SchematicConverter {
                     match tag {
                         "start" | "end" | "at" | "center" => {
                             if let (Some(x_str), Some(y_str)) = (
-                                sub_items.get(1).and_then(|s| s.as_atom()),
-                                sub_items.get(2).and_then(|s| s.as_atom()),
+                                sub_items.get(1).and_then(numeric_atom),
+                                sub_items.get(2).and_then(numeric_atom),
                             ) {
                                 if let (Ok(x), Ok(y)) = (x_str.parse::<f64>(), y_str.parse::<f64>())
                                 {
@@ -904,8 +1181,8 @@ impl SchematicConverter {
                                 if let Sexpr::List(pt_data) = pt {
                                     if let Some("xy") = pt_data.first().and_then(|s| s.as_atom()) {
                                         if let (Some(x_str), Some(y_str)) = (
-                                            pt_data.get(1).and_then(|s| s.as_atom()),
-                                            pt_data.get(2).and_then(|s| s.as_atom()),
+                                            pt_data.get(1).and_then(numeric_atom),
+                                            pt_data.get(2).and_then(numeric_atom),
                                         ) {
                                             if let (Ok(x), Ok(y)) =
                                                 (x_str.parse::<f64>(), y_str.parse::<f64>())
@@ -941,8 +1218,8 @@ impl SchematicConverter {
                 if let Some(tag) = sub_items.first().and_then(|s| s.as_atom()) {
                     if tag == "at" {
                         if let (Some(x_str), Some(y_str)) = (
-                            sub_items.get(1).and_then(|s| s.as_atom()),
-                            sub_items.get(2).and_then(|s| s.as_atom()),
+                            sub_items.get(1).and_then(numeric_atom),
+                            sub_items.get(2).and_then(numeric_atom),
                         ) {
                             if let (Ok(x), Ok(y)) = (x_str.parse::<f64>(), y_str.parse::<f64>()) {
                                 log::trace!("Pin at ({x}, {y})");
@@ -976,6 +1253,11 @@ impl SchematicConverter {
                 }
             };
 
+            // Ports of components drawn on other pages get their labels there
+            if !self.is_on_sheet(&comp_ref) {
+                continue;
+            }
+
             // Try to obtain the pin *number* first (from the port's "pad" attribute).
             let pin_identifier_owned: Option<String> = sch
                 .instances
@@ -1003,6 +1285,7 @@ impl SchematicConverter {
                             &symbol_info.raw_sexpr,
                             pin_identifier,
                             symbol.position,
+                            symbol.rotation,
                         ) {
                             // Justification based on pin orientation:
                             // 0° (pin points right): label on left side, right-justified
@@ -1134,7 +1417,12 @@ impl SchematicConverter {
             Sexpr::list(vec![Sexpr::symbol("generator"), Sexpr::string("diode_sch")]),
             Sexpr::list(vec![
                 Sexpr::symbol("uuid"),
-                Sexpr::symbol(Uuid::new_v4().to_string()),
+                Sexpr::symbol(
+                    self.sheet
+                        .as_ref()
+                        .map(|sheet| sheet.file_uuid.clone())
+                        .unwrap_or_else(|| Uuid::new_v4().to_string()),
+                ),
             ]),
             Sexpr::list(vec![Sexpr::symbol("paper"), Sexpr::string("A4")]),
             // Title block
@@ -1190,15 +1478,28 @@ impl SchematicConverter {
             schematic_items.push(self.symbol_to_sexpr(symbol, output_path));
         }
 
-        // Sheet instances
-        schematic_items.push(Sexpr::list(vec![
-            Sexpr::symbol("sheet_instances"),
-            Sexpr::list(vec![
-                Sexpr::symbol("path"),
-                Sexpr::string("/"),
-                Sexpr::list(vec![Sexpr::symbol("page"), Sexpr::string("1")]),
-            ]),
-        ]));
+        // Sub-sheets
+        if let Some(sheet) = &self.sheet {
+            for child in &sheet.children {
+                schematic_items.push(self.sheet_to_sexpr(child, &sheet.sheet_path, output_path));
+            }
+        }
+
+        // Sheet instances (only the root page carries them)
+        let is_root = self
+            .sheet
+            .as_ref()
+            .is_none_or(|sheet| sheet.module_ref.instance_path.is_empty());
+        if is_root {
+            schematic_items.push(Sexpr::list(vec![
+                Sexpr::symbol("sheet_instances"),
+                Sexpr::list(vec![
+                    Sexpr::symbol("path"),
+                    Sexpr::string("/"),
+                    Sexpr::list(vec![Sexpr::symbol("page"), Sexpr::string("1")]),
+                ]),
+            ]));
+        }
 
         // Build the complete schematic S-expression
         let schematic_sexpr = Sexpr::list({
@@ -1315,7 +1616,7 @@ impl SchematicConverter {
                 Sexpr::symbol("at"),
                 Sexpr::symbol(symbol.position.0.to_string()),
                 Sexpr::symbol(symbol.position.1.to_string()),
-                Sexpr::symbol("0"),
+                Sexpr::symbol(symbol.rotation.to_string()),
             ]),
             Sexpr::list(vec![
                 Sexpr::symbol("unit"),
@@ -1390,7 +1691,10 @@ impl SchematicConverter {
                 ),
                 Sexpr::list(vec![
                     Sexpr::symbol("path"),
-                    Sexpr::string(format!("/{}", symbol.uuid)),
+                    Sexpr::string(match &self.sheet {
+                        Some(sheet) => sheet.sheet_path.clone(),
+                        None => format!("/{}", symbol.uuid),
+                    }),
                     Sexpr::list(vec![
                         Sexpr::symbol("reference"),
                         Sexpr::string(symbol.reference.clone()),
@@ -1406,6 +1710,100 @@ impl SchematicConverter {
         Sexpr::list(symbol_items)
     }
 
+    fn sheet_to_sexpr(&self, child: &ChildSheet, parent_path: &str, output_path: &Path) -> Sexpr {
+        let (x, y) = child.position;
+        let field = |key: &str, value: &str, field_y: f64, vertical: &str| {
+            Sexpr::list(vec![
+                Sexpr::symbol("property"),
+                Sexpr::string(key),
+                Sexpr::string(value),
+                Sexpr::list(vec![
+                    Sexpr::symbol("at"),
+                    Sexpr::symbol(x.to_string()),
+                    Sexpr::symbol(field_y.to_string()),
+                    Sexpr::symbol("0"),
+                ]),
+                Sexpr::list(vec![
+                    Sexpr::symbol("effects"),
+                    Sexpr::list(vec![
+                        Sexpr::symbol("font"),
+                        Sexpr::list(vec![
+                            Sexpr::symbol("size"),
+                            Sexpr::symbol("1.27"),
+                            Sexpr::symbol("1.27"),
+                        ]),
+                    ]),
+                    Sexpr::list(vec![
+                        Sexpr::symbol("justify"),
+                        Sexpr::symbol("left"),
+                        Sexpr::symbol(vertical),
+                    ]),
+                ]),
+            ])
+        };
+
+        Sexpr::list(vec![
+            Sexpr::symbol("sheet"),
+            Sexpr::list(vec![
+                Sexpr::symbol("at"),
+                Sexpr::symbol(x.to_string()),
+                Sexpr::symbol(y.to_string()),
+            ]),
+            Sexpr::list(vec![
+                Sexpr::symbol("size"),
+                Sexpr::symbol(SHEET_SIZE.0.to_string()),
+                Sexpr::symbol(SHEET_SIZE.1.to_string()),
+            ]),
+            Sexpr::list(vec![Sexpr::symbol("fields_autoplaced")]),
+            Sexpr::list(vec![
+                Sexpr::symbol("stroke"),
+                Sexpr::list(vec![Sexpr::symbol("width"), Sexpr::symbol("0.1524")]),
+                Sexpr::list(vec![Sexpr::symbol("type"), Sexpr::symbol("solid")]),
+            ]),
+            Sexpr::list(vec![
+                Sexpr::symbol("fill"),
+                Sexpr::list(vec![
+                    Sexpr::symbol("color"),
+                    Sexpr::symbol("0"),
+                    Sexpr::symbol("0"),
+                    Sexpr::symbol("0"),
+                    Sexpr::symbol("0.0000"),
+                ]),
+            ]),
+            Sexpr::list(vec![
+                Sexpr::symbol("uuid"),
+                Sexpr::symbol(child.uuid.clone()),
+            ]),
+            field("Sheetname", &child.name, y - 0.7116, "bottom"),
+            field(
+                "Sheetfile",
+                &child.file_name,
+                y + SHEET_SIZE.1 + 0.5846,
+                "top",
+            ),
+            Sexpr::list(vec![
+                Sexpr::symbol("instances"),
+                Sexpr::list(vec![
+                    Sexpr::symbol("project"),
+                    Sexpr::string(
+                        output_path
+                            .file_stem()
+                            .and_then(|s| s.to_str())
+                            .unwrap_or("project"),
+                    ),
+                    Sexpr::list(vec![
+                        Sexpr::symbol("path"),
+                        Sexpr::string(parent_path),
+                        Sexpr::list(vec![
+                            Sexpr::symbol("page"),
+                            Sexpr::string(child.page.to_string()),
+                        ]),
+                    ]),
+                ]),
+            ]),
+        ])
+    }
+
     fn create_property_sexpr(&self, key: &str, value: &str, x: f64, y: f64, hide: bool) -> Sexpr {
         let mut property_items = vec![
             Sexpr::symbol("property"),
@@ -1445,10 +1843,30 @@ impl SchematicConverter {
         symbol_data: &Sexpr,
         pin_name: &str,
         symbol_position: (f64, f64),
+        symbol_rotation: f64,
     ) -> Option<((f64, f64), f64)> {
         // Delegate to recursive helper that understands nested sub-symbols.
         // For now we ignore rotation inside sub-symbols as most library parts keep rotation at 0°.
-        self.find_pin_with_transform(symbol_data, pin_name, symbol_position, (0.0, 0.0))
+        if symbol_rotation == 0.0 {
+            return self.find_pin_with_transform(
+                symbol_data,
+                pin_name,
+                symbol_position,
+                (0.0, 0.0),
+            );
+        }
+
+        // Resolve the pin relative to the symbol origin, then rotate it (counter-clockwise in
+        // symbol space, where +Y points up) before translating into schematic space.
+        let ((x, y), angle) =
+            self.find_pin_with_transform(symbol_data, pin_name, (0.0, 0.0), (0.0, 0.0))?;
+        let (local_x, local_y) = (x, -y);
+        let (sin, cos) = symbol_rotation.to_radians().sin_cos();
+        let rotated = (local_x * cos - local_y * sin, local_x * sin + local_y * cos);
+        Some((
+            (symbol_position.0 + rotated.0, symbol_position.1 - rotated.1),
+            (angle + symbol_rotation).rem_euclid(360.0),
+        ))
     }
 
     /// Recursively search for the pin while accumulating local offsets from any nested sub-symbols.
@@ -1489,8 +1907,8 @@ impl SchematicConverter {
                                 if let Sexpr::List(at_data) = sub_item {
                                     if let Some("at") = at_data.first().and_then(|s| s.as_atom()) {
                                         if let (Some(x_str), Some(y_str)) = (
-                                            at_data.get(1).and_then(|s| s.as_atom()),
-                                            at_data.get(2).and_then(|s| s.as_atom()),
+                                            at_data.get(1).and_then(numeric_atom),
+                                            at_data.get(2).and_then(numeric_atom),
                                         ) {
                                             if let (Ok(x), Ok(y)) =
                                                 (x_str.parse::<f64>(), y_str.parse::<f64>())
@@ -1549,8 +1967,8 @@ impl SchematicConverter {
                         }
                         "at" => {
                             if let (Some(x_str), Some(y_str)) = (
-                                sub_items.get(1).and_then(|s| s.as_atom()),
-                                sub_items.get(2).and_then(|s| s.as_atom()),
+                                sub_items.get(1).and_then(numeric_atom),
+                                sub_items.get(2).and_then(numeric_atom),
                             ) {
                                 if let (Ok(x), Ok(y)) = (x_str.parse::<f64>(), y_str.parse::<f64>())
                                 {
@@ -1558,8 +1976,7 @@ impl SchematicConverter {
                                     pin_y = y;
                                 }
                                 // Angle is optional
-                                if let Some(angle_str) = sub_items.get(3).and_then(|s| s.as_atom())
-                                {
+                                if let Some(angle_str) = sub_items.get(3).and_then(numeric_atom) {
                                     if let Ok(angle) = angle_str.parse::<f64>() {
                                         pin_angle = angle;
                                     }
@@ -1567,7 +1984,7 @@ impl SchematicConverter {
                             }
                         }
                         "length" => {
-                            if let Some(length_str) = sub_items.get(1).and_then(|s| s.as_atom()) {
+                            if let Some(length_str) = sub_items.get(1).and_then(numeric_atom) {
                                 if let Ok(length) = length_str.parse::<f64>() {
                                     _pin_length = length;
                                 }
@@ -1680,7 +2097,66 @@ impl SchematicConverter {
         ])
     }
 
+    /// Override packed positions with placements saved in `# pcb:sch` comments.
+    ///
+    /// Positions are stored on the module whose page the component is drawn on, keyed by the
+    /// component path relative to that module (e.g. `comp:R1` for the stdlib resistor `R1.R`).
+    fn apply_saved_positions(&mut self, sch: &Schematic) {
+        let Some(sheet) = &self.sheet else {
+            return;
+        };
+        let Some(module) = sch.instances.get(&sheet.module_ref) else {
+            return;
+        };
+        let prefix_len = sheet.module_ref.instance_path.len();
+
+        for symbol in &mut self.symbols {
+            let Some(inst_ref) = self
+                .uuid_map
+                .iter()
+                .find(|(_, uuid)| **uuid == symbol.uuid)
+                .map(|(inst_ref, _)| inst_ref)
+            else {
+                continue;
+            };
+            let relative = &inst_ref.instance_path[prefix_len.min(inst_ref.instance_path.len())..];
+
+            // Prefer the most specific key, falling back to enclosing single-component modules
+            let saved = (1..=relative.len()).rev().find_map(|len| {
+                module
+                    .symbol_positions
+                    .get(&format!("comp:{}", relative[..len].join(".")))
+            });
+            if let Some(position) = saved {
+                log::debug!(
+                    "Component {} placed from saved position ({}, {}, {})",
+                    inst_ref,
+                    position.x,
+                    position.y,
+                    position.rotation
+                );
+                symbol.position = (position.x, position.y);
+                symbol.rotation = position.rotation;
+            }
+        }
+    }
+
     fn build_module_hierarchy(&mut self, sch: &Schematic) {
+        // A hierarchical page packs its own components and sub-sheet symbols as one flat module
+        if let Some(sheet) = &self.sheet {
+            let mut children_ids: Vec<String> = sheet
+                .components
+                .iter()
+                .filter_map(|comp_ref| self.uuid_map.get(comp_ref).cloned())
+                .collect();
+            children_ids.extend(sheet.children.iter().map(|child| child.uuid.clone()));
+            if !children_ids.is_empty() {
+                self.layout_engine
+                    .add_module(sheet.module_ref.to_string(), children_ids);
+            }
+            return;
+        }
+
         // Start from the root instance if it exists
         if let Some(root_ref) = &sch.root_ref {
             if let Some(root_instance) = sch.instances.get(root_ref) {
@@ -1824,3 +2300,131 @@ impl SchematicConverter {
 pub fn write_schematic_file(schematic_content: &str, path: &Path) -> Result<(), std::io::Error> {
     fs::write(path, schematic_content)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::position::Position;
+    use crate::{AttributeValue, ModuleRef};
+
+    const RESISTOR_SYMBOL: &str = r#"(kicad_symbol_lib
+  (symbol "R"
+    (property "Reference" "R" (at 0 0 0))
+    (property "Value" "R" (at 0 0 0))
+    (symbol "R_0_1"
+      (rectangle (start -1.016 -2.54) (end 1.016 2.54))
+    )
+    (symbol "R_1_1"
+      (pin passive line (at 0 3.81 270) (length 1.27) (name "~" (effects (font (size 1.27 1.27)))) (number "1" (effects (font (size 1.27 1.27)))))
+      (pin passive line (at 0 -3.81 90) (length 1.27) (name "~" (effects (font (size 1.27 1.27)))) (number "2" (effects (font (size 1.27 1.27)))))
+    )
+  )
+)"#;
+
+    /// Board with a top-level resistor `R1` and a two-resistor module `DIV`
+    fn divider_schematic(symbol_path: &Path) -> Schematic {
+        let module = ModuleRef::from_path(Path::new("/board.zen"), "Board");
+        let root_ref = InstanceRef::new(module.clone(), vec![]);
+        let mut sch = Schematic::new();
+
+        let add_resistor = |sch: &mut Schematic, path: &[&str]| {
+            let wrapper_ref =
+                InstanceRef::new(module.clone(), path.iter().map(|s| s.to_string()).collect());
+            let comp_ref = wrapper_ref.append("R".into());
+            let mut comp = Instance::component(module.clone());
+            comp.add_attribute(
+                "symbol_path",
+                AttributeValue::String(symbol_path.display().to_string()),
+            );
+            sch.add_instance(comp_ref.clone(), comp);
+            sch.add_instance(
+                wrapper_ref.clone(),
+                Instance::module(module.clone()).with_child("R", comp_ref),
+            );
+            wrapper_ref
+        };
+
+        let r1 = add_resistor(&mut sch, &["R1"]);
+        let div_top = add_resistor(&mut sch, &["DIV", "R_TOP"]);
+        let div_bot = add_resistor(&mut sch, &["DIV", "R_BOT"]);
+        let div_ref = InstanceRef::new(module.clone(), vec!["DIV".into()]);
+        sch.add_instance(
+            div_ref.clone(),
+            Instance::module(module.clone())
+                .with_child("R_TOP", div_top)
+                .with_child("R_BOT", div_bot),
+        );
+
+        let mut root = Instance::module(module)
+            .with_child("R1", r1)
+            .with_child("DIV", div_ref);
+        root.symbol_positions.insert(
+            "comp:R1".to_string(),
+            Position {
+                x: 50.8,
+                y: 76.2,
+                rotation: 90.0,
+            },
+        );
+        sch.add_instance(root_ref.clone(), root);
+        sch.set_root_ref(root_ref);
+        sch
+    }
+
+    #[test]
+    fn test_plan_collapses_single_component_modules() {
+        let sch = divider_schematic(Path::new("/unused.kicad_sym"));
+        let mut plans = Vec::new();
+        let mut next_page = 2;
+        plan_sheet(
+            &sch,
+            sch.root_ref.as_ref().unwrap(),
+            "layout.kicad_sch".to_string(),
+            "root".to_string(),
+            "/root".to_string(),
+            "layout",
+            &mut next_page,
+            &mut plans,
+        );
+
+        assert_eq!(plans.len(), 2);
+        let root = &plans[0];
+        assert_eq!(root.components.len(), 1);
+        assert_eq!(root.components[0].instance_path, vec!["R1", "R"]);
+        assert_eq!(root.children.len(), 1);
+        assert_eq!(root.children[0].name, "DIV");
+        assert_eq!(root.children[0].file_name, "layout-DIV.kicad_sch");
+        assert_eq!(root.children[0].page, 2);
+
+        let div = &plans[1];
+        assert_eq!(div.file_name, "layout-DIV.kicad_sch");
+        assert_eq!(div.sheet_path, format!("/root/{}", root.children[0].uuid));
+        assert_eq!(div.components.len(), 2);
+        assert!(div.children.is_empty());
+    }
+
+    #[test]
+    fn test_sheets_reference_children_and_honor_saved_positions() {
+        let dir = tempfile::tempdir().unwrap();
+        let symbol_path = dir.path().join("R.kicad_sym");
+        fs::write(&symbol_path, RESISTOR_SYMBOL).unwrap();
+        let sch = divider_schematic(&symbol_path);
+
+        let root_path = dir.path().join("layout.kicad_sch");
+        let sheets = to_kicad_schematic_sheets(&sch, &root_path).unwrap();
+        assert_eq!(sheets.len(), 2);
+        assert_eq!(sheets[0].path, root_path);
+        assert_eq!(sheets[1].path, dir.path().join("layout-DIV.kicad_sch"));
+
+        let root = &sheets[0].content;
+        assert!(root.contains(r#""Sheetfile""#));
+        assert!(root.contains(r#""layout-DIV.kicad_sch""#));
+
+        // The root resistor is drawn at the position saved in `# pcb:sch R1 ...`
+        assert!(root.contains("(at 50.8 76.2 90)"));
+
+        // Only the root page carries sheet instances
+        assert!(sheets[0].content.contains("sheet_instances"));
+        assert!(!sheets[1].content.contains("sheet_instances"));
+    }
+}
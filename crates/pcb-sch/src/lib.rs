@@ -14,18 +14,22 @@
 pub mod bom;
 #[cfg(feature = "table")]
 mod bom_table;
+pub mod design_rules;
 pub mod hierarchical_layout;
 pub mod kicad_netlist;
 pub mod kicad_schematic;
 pub mod natural_string;
 pub mod physical;
 pub mod position;
+pub mod tolerance;
+pub mod variants;
 
 // Re-export BOM functionality
 pub use bom::{
-    parse_kicad_csv_bom, Alternative, AvailabilityData, Bom, BomEntry, BomMatchingKey,
-    BomMatchingRule, Capacitor, Dielectric, GenericComponent, GenericMatchingKey, GroupedBomEntry,
-    KiCadBomError, MatchedOffer, Offer, RegionAvailability, Resistor, UngroupedBomEntry,
+    detect_generic_component, parse_kicad_csv_bom, Alternative, AvailabilityData, Bom, BomEntry,
+    BomMatchingKey, BomMatchingRule, Capacitor, Dielectric, GenericComponent, GenericMatchingKey,
+    GroupedBomEntry, KiCadBomError, MatchedOffer, Offer, RegionAvailability, Resistor,
+    UngroupedBomEntry,
 };
 use std::collections::HashMap;
 use std::hash::{Hash, Hasher};
@@ -198,6 +202,8 @@ pub enum PhysicalUnit {
     Joules,
     Siemens,
     Webers,
+    Meters,
+    SquareMeters,
 }
 
 impl PhysicalUnit {
@@ -216,6 +222,8 @@ impl PhysicalUnit {
             PhysicalUnit::Joules => "J",
             PhysicalUnit::Siemens => "S",
             PhysicalUnit::Webers => "Wb",
+            PhysicalUnit::Meters => "m",
+            PhysicalUnit::SquareMeters => "m²",
         }
     }
 
@@ -234,6 +242,8 @@ impl PhysicalUnit {
             PhysicalUnit::Joules => "Energy",
             PhysicalUnit::Siemens => "Conductance",
             PhysicalUnit::Webers => "Flux",
+            PhysicalUnit::Meters => "Length",
+            PhysicalUnit::SquareMeters => "Area",
         }
     }
 }
@@ -254,6 +264,8 @@ impl std::fmt::Display for PhysicalUnit {
             PhysicalUnit::Joules => write!(f, "Joule"),
             PhysicalUnit::Siemens => write!(f, "Siemens"),
             PhysicalUnit::Webers => write!(f, "Weber"),
+            PhysicalUnit::Meters => write!(f, "Meter"),
+            PhysicalUnit::SquareMeters => write!(f, "Square meter"),
         }
     }
 }
@@ -275,6 +287,8 @@ impl std::str::FromStr for PhysicalUnit {
             "J" | "joule" | "Joule" | "joules" | "Joules" => Ok(PhysicalUnit::Joules),
             "S" | "siemens" | "Siemens" => Ok(PhysicalUnit::Siemens),
             "Wb" | "weber" | "Weber" | "webers" | "Webers" => Ok(PhysicalUnit::Webers),
+            "m" | "meter" | "Meter" | "meters" | "Meters" => Ok(PhysicalUnit::Meters),
+            "m²" | "m^2" | "SquareMeters" => Ok(PhysicalUnit::SquareMeters),
             _ => Err(format!("Unknown unit: '{}'", s)),
         }
     }
@@ -564,6 +578,14 @@ pub struct Schematic {
 
     /// Path remapping rules for moved() directives (old_path -> new_path)
     pub moved_paths: HashMap<String, String>,
+
+    /// Design rules declared with design_rule(), by final net name
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub design_rules: Vec<design_rules::DesignRule>,
+
+    /// Assembly variants declared with variant(), with paths relative to the root module
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub variants: Vec<variants::Variant>,
 }
 
 impl Schematic {
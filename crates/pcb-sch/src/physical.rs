@@ -50,6 +50,8 @@ const KELVIN_OFFSET: Decimal = dec!(273.15);
 const MINUTE: Decimal = dec!(60);
 const HOUR: Decimal = dec!(3600);
 const ONE_HUNDRED: Decimal = dec!(100);
+const INCH: Decimal = dec!(0.0254);
+const MIL: Decimal = dec!(0.0000254);
 
 /// Parse percentage or decimal string to tolerance fraction
 fn parse_percentish_decimal(s: &str) -> Result<Decimal, ParseError> {
@@ -486,6 +488,7 @@ pub struct PhysicalUnitDims {
     pub time: i8,
     pub voltage: i8,
     pub temp: i8,
+    pub length: i8,
 }
 
 impl Freeze for PhysicalUnitDims {
@@ -503,6 +506,7 @@ impl std::ops::Mul for PhysicalUnitDims {
             time: self.time + rhs.time,
             voltage: self.voltage + rhs.voltage,
             temp: self.temp + rhs.temp,
+            length: self.length + rhs.length,
         }
     }
 }
@@ -515,6 +519,7 @@ impl std::ops::Div for PhysicalUnitDims {
             time: self.time - rhs.time,
             voltage: self.voltage - rhs.voltage,
             temp: self.temp - rhs.temp,
+            length: self.length - rhs.length,
         }
     }
 }
@@ -542,6 +547,8 @@ impl From<PhysicalUnit> for PhysicalUnitDims {
             Joules => Self::VOLTAGE * Self::CURRENT * Self::TIME,
             Webers => Self::VOLTAGE * Self::TIME,
             Henries => Self::VOLTAGE * Self::TIME / Self::CURRENT,
+            Meters => Self::LENGTH,
+            SquareMeters => Self::LENGTH * Self::LENGTH,
         }
     }
 }
@@ -646,18 +653,21 @@ fn gather_units(list: &str) -> Result<PhysicalUnitDims, ParseError> {
 }
 
 impl PhysicalUnitDims {
-    pub const DIMENSIONLESS: Self = Self::new(0, 0, 0, 0);
-    pub const CURRENT: Self = Self::new(1, 0, 0, 0);
-    pub const TIME: Self = Self::new(0, 1, 0, 0);
-    pub const VOLTAGE: Self = Self::new(0, 0, 1, 0);
-    pub const TEMP: Self = Self::new(0, 0, 0, 1);
-
-    const fn new(current: i8, time: i8, voltage: i8, temp: i8) -> Self {
+    pub const DIMENSIONLESS: Self = Self::new(0, 0, 0, 0, 0);
+    pub const CURRENT: Self = Self::new(1, 0, 0, 0, 0);
+    pub const TIME: Self = Self::new(0, 1, 0, 0, 0);
+    pub const VOLTAGE: Self = Self::new(0, 0, 1, 0, 0);
+    pub const TEMP: Self = Self::new(0, 0, 0, 1, 0);
+    pub const LENGTH: Self = Self::new(0, 0, 0, 0, 1);
+    pub const AREA: Self = Self::new(0, 0, 0, 0, 2);
+
+    const fn new(current: i8, time: i8, voltage: i8, temp: i8, length: i8) -> Self {
         Self {
             current,
             time,
             voltage,
             temp,
+            length,
         }
     }
 
@@ -668,23 +678,26 @@ impl PhysicalUnitDims {
             time,
             voltage,
             temp,
+            length,
         } = self;
-        let alias = match (current, time, voltage, temp) {
+        let alias = match (current, time, voltage, temp, length) {
             // bases
-            (1, 0, 0, 0) => Amperes, // A
-            (0, 1, 0, 0) => Seconds, // s
-            (0, 0, 1, 0) => Volts,   // V
-            (0, 0, 0, 1) => Kelvin,  // K
+            (1, 0, 0, 0, 0) => Amperes, // A
+            (0, 1, 0, 0, 0) => Seconds, // s
+            (0, 0, 1, 0, 0) => Volts,   // V
+            (0, 0, 0, 1, 0) => Kelvin,  // K
+            (0, 0, 0, 0, 1) => Meters,  // m
             // derived
-            (0, -1, 0, 0) => Hertz,   // Hz = 1/s
-            (1, 1, 0, 0) => Coulombs, // C = A*s
-            (-1, 0, 1, 0) => Ohms,    // Ohm = V/A
-            (1, 0, -1, 0) => Siemens, // S = A/V
-            (1, 1, -1, 0) => Farads,  // F = A*s/V
-            (-1, 1, 1, 0) => Henries, // H = V*s/A
-            (1, 0, 1, 0) => Watts,    // W = V*A
-            (1, 1, 1, 0) => Joules,   // J = V*A*s
-            (0, 1, 1, 0) => Webers,   // Wb = V*s
+            (0, -1, 0, 0, 0) => Hertz,       // Hz = 1/s
+            (1, 1, 0, 0, 0) => Coulombs,     // C = A*s
+            (-1, 0, 1, 0, 0) => Ohms,        // Ohm = V/A
+            (1, 0, -1, 0, 0) => Siemens,     // S = A/V
+            (1, 1, -1, 0, 0) => Farads,      // F = A*s/V
+            (-1, 1, 1, 0, 0) => Henries,     // H = V*s/A
+            (1, 0, 1, 0, 0) => Watts,        // W = V*A
+            (1, 1, 1, 0, 0) => Joules,       // J = V*A*s
+            (0, 1, 1, 0, 0) => Webers,       // Wb = V*s
+            (0, 0, 0, 0, 2) => SquareMeters, // m² = m*m
             _ => return None,
         };
         Some(alias)
@@ -709,12 +722,14 @@ impl PhysicalUnitDims {
             time,
             voltage,
             temp,
+            length,
         } = *self;
         let mut num = Vec::new();
         let mut den = Vec::new();
         push(voltage, "V", &mut num, &mut den);
         push(current, "A", &mut num, &mut den);
         push(temp, "K", &mut num, &mut den);
+        push(length, "m", &mut num, &mut den);
         push(time, "s", &mut num, &mut den);
         let format_units = |units: &[String]| {
             let joined = units.join("·");
@@ -966,6 +981,22 @@ fn convert_temperature_to_kelvin(value: Decimal, unit: &str) -> Decimal {
     }
 }
 
+/// Metres per unit for length units: SI-prefixed metres (`mm`, `um`, ...) plus the imperial
+/// units common in PCB work (`mil`, `in`).
+///
+/// A bare `m` is not a length: after a number it is the milli prefix (`10m` is 10mΩ).
+fn length_scale(unit_str: &str) -> Option<Decimal> {
+    match unit_str {
+        "cm" => Some(dec!(0.01)),
+        "µm" => Some(dec!(0.000001)),
+        "mil" | "thou" => Some(MIL),
+        "in" | "inch" => Some(INCH),
+        _ => SI_PREFIXES.iter().find_map(|&(exp, prefix)| {
+            (!prefix.is_empty() && unit_str.strip_prefix(prefix) == Some("m")).then(|| pow10(exp))
+        }),
+    }
+}
+
 fn parse_unit_with_prefix(
     unit_str: &str,
     base_value: Decimal,
@@ -987,6 +1018,20 @@ fn parse_unit_with_prefix(
         _ => {}
     }
 
+    if let Some(scale) = length_scale(unit_str) {
+        return Ok((base_value * scale, PhysicalUnitDims::LENGTH));
+    }
+    if let Some(scale) = unit_str
+        .strip_suffix('²')
+        .or_else(|| unit_str.strip_suffix("^2"))
+        .and_then(|unit| match unit {
+            "m" => Some(Decimal::ONE),
+            _ => length_scale(unit),
+        })
+    {
+        return Ok((base_value * scale * scale, PhysicalUnitDims::AREA));
+    }
+
     // Try SI prefixes
     for &(exp, prefix) in &SI_PREFIXES {
         if !prefix.is_empty() {
@@ -1028,6 +1073,16 @@ impl std::fmt::Display for PhysicalValue {
                 };
                 write!(f, "{}{}{}", value_str, unit_suffix, tol_str)
             }
+            // A bare `m` parses as the milli prefix, so whole metres display in mm
+            Some(PhysicalUnit::Meters) if scale_to_si(self.value).1.is_empty() => {
+                let mm = self.value * pow10(3);
+                write!(f, "{}mm{}", fmt_significant(mm), tol_str)
+            }
+            // SI prefixes scale area by their square, so areas always display in mm²
+            Some(PhysicalUnit::SquareMeters) => {
+                let square_mm = self.value * pow10(6);
+                write!(f, "{}mm²{}", fmt_significant(square_mm), tol_str)
+            }
             _ => {
                 let (scaled, prefix) = scale_to_si(self.value);
                 write!(
@@ -1559,6 +1614,50 @@ impl fmt::Display for PhysicalRange {
 impl PhysicalRange {
     pub const TYPE: &'static str = "PhysicalRange";
 
+    /// Unit of the range bounds
+    pub fn unit(&self) -> PhysicalUnitDims {
+        self.r#type.unit
+    }
+
+    /// `(min, max)` bounds of the range
+    pub fn bounds(&self) -> (Decimal, Decimal) {
+        (self.min, self.max)
+    }
+
+    /// Nominal value of the range, or the midpoint of its bounds when none is given
+    pub fn nominal(&self) -> Decimal {
+        self.nominal
+            .unwrap_or_else(|| (self.min + self.max) / Decimal::TWO)
+    }
+
+    /// Range between two bounds given in either order, with an optional nominal value
+    pub fn from_bounds(
+        a: Decimal,
+        b: Decimal,
+        nominal: Option<Decimal>,
+        unit: PhysicalUnitDims,
+    ) -> Self {
+        Self {
+            min: a.min(b),
+            max: a.max(b),
+            nominal,
+            r#type: PhysicalRangeType::new(unit),
+        }
+    }
+
+    /// How far this range extends beyond `limit` on either side.
+    ///
+    /// Returns `None` when the range fits within `limit` or the units differ. Used to report
+    /// the margin by which a net's voltage exceeds a pin rating.
+    pub fn excess_over(&self, limit: &PhysicalRange) -> Option<PhysicalValue> {
+        if self.r#type.unit != limit.r#type.unit {
+            return None;
+        }
+        let excess = (self.max - limit.max).max(limit.min - self.min);
+        (excess > Decimal::ZERO)
+            .then(|| PhysicalValue::from_decimal(excess, Decimal::ZERO, self.r#type.unit))
+    }
+
     /// Calculate the maximum possible absolute difference between two ranges
     /// This is useful for determining component voltage ratings (e.g., capacitor max voltage)
     /// Returns PhysicalValue with the worst-case difference
@@ -2559,6 +2658,54 @@ mod tests {
         ]);
     }
 
+    #[test]
+    fn test_parsing_lengths() {
+        // Lengths are stored in metres, so compare exactly rather than with `check_many`
+        for (input, unit, metres) in [
+            ("1.6mm", PhysicalUnit::Meters, "0.0016"),
+            ("35um", PhysicalUnit::Meters, "0.000035"),
+            ("35µm", PhysicalUnit::Meters, "0.000035"),
+            ("5mil", PhysicalUnit::Meters, "0.000127"),
+            ("0.5in", PhysicalUnit::Meters, "0.0127"),
+            ("2km", PhysicalUnit::Meters, "2000"),
+            ("1m²", PhysicalUnit::SquareMeters, "1"),
+            ("4mm²", PhysicalUnit::SquareMeters, "0.000004"),
+            ("100mil^2", PhysicalUnit::SquareMeters, "0.000000064516"),
+        ] {
+            let parsed: PhysicalValue = input.parse().unwrap();
+            assert_eq!(parsed.unit, unit.into(), "{input}");
+            assert_eq!(parsed.value, Decimal::from_str(metres).unwrap(), "{input}");
+        }
+        test_cycle("0.2mm", PhysicalUnit::Meters, 0.0002, "200um");
+        test_cycle("1.5mm²", PhysicalUnit::SquareMeters, 1.5e-6, "1.5mm²");
+        test_cycle("1500mm", PhysicalUnit::Meters, 1.5, "1500mm");
+
+        // A bare `m` after a number stays the milli prefix of the default unit
+        let milliohms: PhysicalValue = "10m".parse().unwrap();
+        assert_eq!(milliohms.unit, PhysicalUnit::Ohms.into());
+        assert_eq!(milliohms.value, Decimal::from_str("0.01").unwrap());
+    }
+
+    #[test]
+    fn test_length_arithmetic() {
+        let width: PhysicalValue = "5mil".parse().unwrap();
+        let sum = (width + "0.1mm".parse().unwrap()).unwrap();
+        assert_eq!(sum.unit, PhysicalUnitDims::LENGTH);
+        assert_eq!(sum.value, Decimal::from_str("0.000227").unwrap());
+
+        let area = width * "2mm".parse().unwrap();
+        assert_eq!(area.unit, PhysicalUnitDims::AREA);
+        assert_eq!(format!("{area}"), "0.254mm²");
+
+        let voltage: PhysicalValue = "1V".parse().unwrap();
+        assert!((width + voltage).is_err());
+
+        // Length ranges for clearances and widths
+        let range: PhysicalRange = "0.1mm to 5mil".parse().unwrap();
+        assert_eq!(range.unit(), PhysicalUnitDims::LENGTH);
+        assert_eq!(range.max, Decimal::from_str("0.000127").unwrap());
+    }
+
     #[test]
     fn test_parsing_decimal_numbers() {
         check_many(&[
@@ -3045,7 +3192,7 @@ mod tests {
     #[test]
     fn test_physical_unit_dims_roundtrip() {
         // Test that fmt_unit output can be parsed back
-        let test_cases: [PhysicalUnitDims; 13] = [
+        let test_cases: [PhysicalUnitDims; 15] = [
             PhysicalUnit::Volts.into(),
             PhysicalUnit::Amperes.into(),
             PhysicalUnit::Ohms.into(),
@@ -3059,6 +3206,8 @@ mod tests {
             PhysicalUnit::Joules.into(),
             PhysicalUnit::Siemens.into(),
             PhysicalUnit::Webers.into(),
+            PhysicalUnit::Meters.into(),
+            PhysicalUnit::SquareMeters.into(),
         ];
 
         for original in test_cases {
@@ -3335,6 +3484,28 @@ mod tests {
         // In the actual implementation, this would be caught by the unit checking
     }
 
+    #[test]
+    fn test_range_excess_over() {
+        let rating: PhysicalRange = "-0.3V to 4V".parse().unwrap();
+
+        let within: PhysicalRange = "3V to 3.6V".parse().unwrap();
+        assert!(within.excess_over(&rating).is_none());
+
+        let above: PhysicalRange = "4.5V to 5.5V".parse().unwrap();
+        let excess = above.excess_over(&rating).unwrap();
+        assert_eq!(excess.value, Decimal::from_str("1.5").unwrap());
+        assert_eq!(excess.unit, PhysicalUnitDims::VOLTAGE);
+
+        let below: PhysicalRange = "-1V to 3V".parse().unwrap();
+        assert_eq!(
+            below.excess_over(&rating).unwrap().value,
+            Decimal::from_str("0.7").unwrap()
+        );
+
+        let current: PhysicalRange = "0A to 1A".parse().unwrap();
+        assert!(current.excess_over(&rating).is_none());
+    }
+
     #[test]
     fn test_range_parsing_endash() {
         let r = PhysicalRange::from_str("11–26V").unwrap();
@@ -0,0 +1,153 @@
+//! Sampling toleranced values for worst-case corner and Monte Carlo analysis.
+//!
+//! Both modes turn a list of [`PhysicalValue`]s into concrete value sets, one per run.
+//! Values without a tolerance keep their nominal value in every run.
+
+use rust_decimal::prelude::FromPrimitive;
+use rust_decimal::Decimal;
+use thiserror::Error;
+
+use crate::physical::PhysicalValue;
+
+/// Corner analysis runs every combination of extremes (`2^n` runs), so it is limited to this
+/// many toleranced values
+pub const MAX_CORNER_VALUES: usize = 16;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Variation {
+    /// Every combination of each toleranced value at its minimum and maximum
+    Corners,
+    /// `runs` values drawn uniformly within each tolerance, reproducible for a given `seed`
+    MonteCarlo { runs: usize, seed: u64 },
+}
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum VariationError {
+    #[error(
+        "{0} toleranced values is too many for corner analysis (at most {max}); use Monte Carlo instead",
+        max = MAX_CORNER_VALUES
+    )]
+    TooManyCorners(usize),
+}
+
+/// `(min, max)` of a value over its tolerance
+pub fn extremes(value: &PhysicalValue) -> (Decimal, Decimal) {
+    let (a, b) = (
+        value.min_value(value.tolerance),
+        value.max_value(value.tolerance),
+    );
+    (a.min(b), a.max(b))
+}
+
+/// Concrete values for each run, with one entry per input value in input order.
+///
+/// Corner runs are ordered like a binary counter over the toleranced values, starting with
+/// all of them at their minimum.
+pub fn sample(
+    values: &[PhysicalValue],
+    variation: Variation,
+) -> Result<Vec<Vec<Decimal>>, VariationError> {
+    let nominal: Vec<Decimal> = values.iter().map(|v| v.value).collect();
+    let varying: Vec<usize> = (0..values.len())
+        .filter(|&i| !values[i].tolerance.is_zero())
+        .collect();
+
+    match variation {
+        Variation::Corners => {
+            if varying.len() > MAX_CORNER_VALUES {
+                return Err(VariationError::TooManyCorners(varying.len()));
+            }
+            Ok((0..1usize << varying.len())
+                .map(|corner| {
+                    let mut run = nominal.clone();
+                    for (bit, &i) in varying.iter().enumerate() {
+                        let (min, max) = extremes(&values[i]);
+                        run[i] = if corner & (1 << bit) == 0 { min } else { max };
+                    }
+                    run
+                })
+                .collect())
+        }
+        Variation::MonteCarlo { runs, seed } => {
+            let mut rng = SplitMix64(seed);
+            Ok((0..runs)
+                .map(|_| {
+                    let mut run = nominal.clone();
+                    for &i in &varying {
+                        let (min, max) = extremes(&values[i]);
+                        let u = Decimal::from_f64(rng.next_unit()).unwrap_or_default();
+                        run[i] = min + (max - min) * u;
+                    }
+                    run
+                })
+                .collect())
+        }
+    }
+}
+
+/// Small deterministic generator, so runs are reproducible on every platform (including wasm)
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Uniform in `[0, 1)`
+    fn next_unit(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    fn pv(s: &str) -> PhysicalValue {
+        PhysicalValue::from_str(s).unwrap()
+    }
+
+    #[test]
+    fn test_corners() {
+        let values = [pv("10k 1%"), pv("3.3V"), pv("20k 5%")];
+        let runs = sample(&values, Variation::Corners).unwrap();
+        assert_eq!(runs.len(), 4);
+        let d = |s: &str| Decimal::from_str(s).unwrap();
+        assert_eq!(runs[0], vec![d("9900"), d("3.3"), d("19000")]);
+        assert_eq!(runs[1], vec![d("10100"), d("3.3"), d("19000")]);
+        assert_eq!(runs[3], vec![d("10100"), d("3.3"), d("21000")]);
+
+        // No toleranced values: a single nominal run
+        assert_eq!(
+            sample(&[pv("1k")], Variation::Corners).unwrap(),
+            vec![vec![d("1000")]]
+        );
+
+        let many = vec![pv("1k 1%"); MAX_CORNER_VALUES + 1];
+        assert_eq!(
+            sample(&many, Variation::Corners),
+            Err(VariationError::TooManyCorners(MAX_CORNER_VALUES + 1))
+        );
+    }
+
+    #[test]
+    fn test_monte_carlo() {
+        let values = [pv("10k 1%"), pv("3.3V")];
+        let variation = Variation::MonteCarlo { runs: 200, seed: 7 };
+        let runs = sample(&values, variation).unwrap();
+        assert_eq!(runs.len(), 200);
+        let (min, max) = extremes(&values[0]);
+        assert!(runs.iter().all(|r| r[0] >= min && r[0] <= max));
+        assert!(runs.iter().all(|r| r[1] == values[1].value));
+        // Spread across the band rather than stuck at the nominal value
+        assert!(runs.iter().any(|r| r[0] < Decimal::from(9950)));
+        assert!(runs.iter().any(|r| r[0] > Decimal::from(10050)));
+        // Reproducible for a seed
+        assert_eq!(runs, sample(&values, variation).unwrap());
+    }
+}
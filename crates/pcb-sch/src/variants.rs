@@ -0,0 +1,392 @@
+//! Assembly variants: named population options of one board.
+//!
+//! A variant is declared in Zener with `variant()` and overrides the `dnp` flag, MPN,
+//! manufacturer or value of components by instance path. A path names a component or a module,
+//! in which case the override applies to every component under it; a more specific path wins.
+//! Variants only change what is assembled: the layout is shared, and each component a variant
+//! touches carries a `variant_<name>` field describing the change.
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::physical::PhysicalValue;
+use crate::{AttributeValue, Instance, InstanceKind, Schematic};
+
+/// Prefix of the component attributes that describe each variant's change to the component
+pub const VARIANT_FIELD_PREFIX: &str = "variant_";
+
+/// Changes a variant makes to a component
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VariantOverride {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dnp: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mpn: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub manufacturer: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub value: Option<String>,
+}
+
+impl VariantOverride {
+    /// Fields set in `other` replace those of `self`
+    fn merge(&mut self, other: &VariantOverride) {
+        if other.dnp.is_some() {
+            self.dnp = other.dnp;
+        }
+        if other.mpn.is_some() {
+            self.mpn = other.mpn.clone();
+        }
+        if other.manufacturer.is_some() {
+            self.manufacturer = other.manufacturer.clone();
+        }
+        if other.value.is_some() {
+            self.value = other.value.clone();
+        }
+    }
+
+    /// Short description for the KiCad variant field, e.g. `DNP` or `MPN RC0402; Value 4.7kOhm`
+    pub fn describe(&self) -> String {
+        let mut parts = Vec::new();
+        match self.dnp {
+            Some(true) => parts.push("DNP".to_string()),
+            Some(false) => parts.push("Fitted".to_string()),
+            None => {}
+        }
+        if let Some(mpn) = &self.mpn {
+            parts.push(format!("MPN {mpn}"));
+        }
+        if let Some(manufacturer) = &self.manufacturer {
+            parts.push(format!("Manufacturer {manufacturer}"));
+        }
+        if let Some(value) = &self.value {
+            parts.push(format!("Value {value}"));
+        }
+        parts.join("; ")
+    }
+
+    /// Apply the override to a component instance
+    fn apply(&self, instance: &mut Instance) {
+        if let Some(dnp) = self.dnp {
+            instance
+                .attributes
+                .insert("dnp".to_string(), AttributeValue::Boolean(dnp));
+        }
+        if let Some(mpn) = &self.mpn {
+            replace_string_attr(instance, &["MPN", "Mpn"], "mpn", mpn);
+        }
+        if let Some(manufacturer) = &self.manufacturer {
+            replace_string_attr(instance, &["Manufacturer"], "manufacturer", manufacturer);
+        }
+        if let Some(value) = &self.value {
+            replace_string_attr(instance, &["Value"], "value", value);
+            // Keep the typed value generic parts are matched by in line with the new value
+            if let Ok(physical) = value.parse::<PhysicalValue>() {
+                for key in [
+                    "resistance",
+                    "Resistance",
+                    "capacitance",
+                    "Capacitance",
+                    "inductance",
+                    "Inductance",
+                ] {
+                    let same_unit = instance
+                        .attributes
+                        .get(key)
+                        .and_then(AttributeValue::physical)
+                        .is_some_and(|current| current.unit == physical.unit);
+                    if same_unit {
+                        instance
+                            .attributes
+                            .insert(key.to_string(), AttributeValue::String(value.clone()));
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Set `key` to `value`, dropping the alternative spellings of the key that would shadow it
+fn replace_string_attr(instance: &mut Instance, shadowing: &[&str], key: &str, value: &str) {
+    for key in shadowing {
+        instance.attributes.remove(*key);
+    }
+    instance
+        .attributes
+        .insert(key.to_string(), AttributeValue::String(value.to_string()));
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Variant {
+    pub name: String,
+    /// Overrides by instance path (`Power.U1`), relative to the root module
+    pub overrides: BTreeMap<String, VariantOverride>,
+}
+
+impl Variant {
+    pub fn new(name: impl Into<String>) -> Self {
+        Variant {
+            name: name.into(),
+            overrides: BTreeMap::new(),
+        }
+    }
+
+    /// The combined override for the component at `path`, from the overrides of the component and
+    /// of the modules it is in; more specific paths take precedence
+    pub fn override_for(&self, path: &str) -> Option<VariantOverride> {
+        let mut matching: Vec<(&String, &VariantOverride)> = self
+            .overrides
+            .iter()
+            .filter(|(prefix, _)| path_contains(prefix, path))
+            .collect();
+        if matching.is_empty() {
+            return None;
+        }
+        matching.sort_by_key(|(prefix, _)| prefix.len());
+        let mut combined = VariantOverride::default();
+        for (_, over) in matching {
+            combined.merge(over);
+        }
+        Some(combined)
+    }
+
+    /// Add the overrides of another declaration of this variant
+    pub fn extend(&mut self, other: Variant) {
+        for (path, over) in other.overrides {
+            self.overrides.entry(path).or_default().merge(&over);
+        }
+    }
+}
+
+/// Whether `path` is `prefix` or lies under it
+fn path_contains(prefix: &str, path: &str) -> bool {
+    path.strip_prefix(prefix)
+        .is_some_and(|rest| rest.is_empty() || rest.starts_with('.'))
+}
+
+#[derive(Debug, Error, PartialEq)]
+pub enum VariantError {
+    #[error("Unknown variant '{name}'; the design declares: {available}")]
+    Unknown { name: String, available: String },
+
+    #[error("Unknown variant '{0}'; the design declares no variants")]
+    NoVariants(String),
+}
+
+impl Schematic {
+    /// The variant named `name`
+    pub fn variant(&self, name: &str) -> Result<&Variant, VariantError> {
+        if self.variants.is_empty() {
+            return Err(VariantError::NoVariants(name.to_string()));
+        }
+        self.variants
+            .iter()
+            .find(|variant| variant.name == name)
+            .ok_or_else(|| VariantError::Unknown {
+                name: name.to_string(),
+                available: self
+                    .variants
+                    .iter()
+                    .map(|variant| variant.name.as_str())
+                    .collect::<Vec<_>>()
+                    .join(", "),
+            })
+    }
+
+    /// Paths of the components each override of `variant` applies to; an override that matches
+    /// nothing has an empty list
+    pub fn variant_matches<'a>(&self, variant: &'a Variant) -> BTreeMap<&'a str, Vec<String>> {
+        let components: Vec<String> = self.component_paths().collect();
+        variant
+            .overrides
+            .keys()
+            .map(|prefix| {
+                let matched = components
+                    .iter()
+                    .filter(|path| path_contains(prefix, path))
+                    .cloned()
+                    .collect();
+                (prefix.as_str(), matched)
+            })
+            .collect()
+    }
+
+    /// Apply the overrides of the variant named `name` to the components
+    pub fn apply_variant(&mut self, name: &str) -> Result<(), VariantError> {
+        let variant = self.variant(name)?.clone();
+        for (reference, instance) in self.instances.iter_mut() {
+            if instance.kind != InstanceKind::Component {
+                continue;
+            }
+            if let Some(over) = variant.override_for(&reference.instance_path.join(".")) {
+                over.apply(instance);
+            }
+        }
+        Ok(())
+    }
+
+    /// Record on each component what every variant changes about it, as `variant_<name>`
+    /// attributes that end up as KiCad fields
+    pub fn add_variant_fields(&mut self) {
+        let variants = self.variants.clone();
+        for (reference, instance) in self.instances.iter_mut() {
+            if instance.kind != InstanceKind::Component {
+                continue;
+            }
+            let path = reference.instance_path.join(".");
+            for variant in &variants {
+                if let Some(over) = variant.override_for(&path) {
+                    instance.attributes.insert(
+                        format!("{VARIANT_FIELD_PREFIX}{}", variant.name),
+                        AttributeValue::String(over.describe()),
+                    );
+                }
+            }
+        }
+    }
+
+    fn component_paths(&self) -> impl Iterator<Item = String> + '_ {
+        self.instances
+            .iter()
+            .filter(|(_, instance)| instance.kind == InstanceKind::Component)
+            .map(|(reference, _)| reference.instance_path.join("."))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{InstanceRef, ModuleRef};
+    use std::path::PathBuf;
+
+    fn component(schematic: &mut Schematic, path: &str, attributes: &[(&str, AttributeValue)]) {
+        let module = ModuleRef::new(PathBuf::from("/board.zen"), "<root>");
+        let mut instance = Instance::component(module.clone());
+        for (key, value) in attributes {
+            instance.add_attribute(*key, value.clone());
+        }
+        let reference = InstanceRef::new(module, path.split('.').map(str::to_string).collect());
+        schematic.add_instance(reference, instance);
+    }
+
+    fn get<'a>(schematic: &'a Schematic, path: &str) -> &'a Instance {
+        schematic
+            .instances
+            .iter()
+            .find(|(reference, _)| reference.instance_path.join(".") == path)
+            .map(|(_, instance)| instance)
+            .unwrap()
+    }
+
+    fn lite() -> Variant {
+        let mut variant = Variant::new("lite");
+        variant.overrides.insert(
+            "Radio".into(),
+            VariantOverride {
+                dnp: Some(true),
+                ..Default::default()
+            },
+        );
+        variant.overrides.insert(
+            "Radio.ANT".into(),
+            VariantOverride {
+                mpn: Some("ANT-2".into()),
+                ..Default::default()
+            },
+        );
+        variant.overrides.insert(
+            "R5".into(),
+            VariantOverride {
+                dnp: Some(false),
+                value: Some("4.7kOhm".into()),
+                ..Default::default()
+            },
+        );
+        variant
+    }
+
+    fn design() -> Schematic {
+        let mut schematic = Schematic::new();
+        component(
+            &mut schematic,
+            "Radio.U1",
+            &[("mpn", AttributeValue::String("NRF52".into()))],
+        );
+        component(
+            &mut schematic,
+            "Radio.ANT",
+            &[("MPN", AttributeValue::String("ANT-1".into()))],
+        );
+        component(
+            &mut schematic,
+            "R5",
+            &[
+                ("dnp", AttributeValue::Boolean(true)),
+                ("value", AttributeValue::String("10kOhm".into())),
+                ("resistance", AttributeValue::String("10kOhm".into())),
+            ],
+        );
+        component(&mut schematic, "Radio2.U1", &[]);
+        schematic.variants.push(lite());
+        schematic
+    }
+
+    #[test]
+    fn overrides_by_path() {
+        let variant = lite();
+        assert_eq!(variant.override_for("Radio2.U1"), None);
+        let ant = variant.override_for("Radio.ANT").unwrap();
+        assert_eq!(ant.dnp, Some(true));
+        assert_eq!(ant.mpn.as_deref(), Some("ANT-2"));
+        assert_eq!(ant.describe(), "DNP; MPN ANT-2");
+    }
+
+    #[test]
+    fn applies_a_variant() {
+        let mut schematic = design();
+        schematic.apply_variant("lite").unwrap();
+
+        assert!(get(&schematic, "Radio.U1").dnp());
+        assert!(!get(&schematic, "Radio2.U1").dnp());
+        let ant = get(&schematic, "Radio.ANT");
+        assert!(ant.dnp());
+        assert_eq!(ant.mpn().as_deref(), Some("ANT-2"));
+        let r5 = get(&schematic, "R5");
+        assert!(!r5.dnp());
+        assert_eq!(r5.value().as_deref(), Some("4.7kOhm"));
+        assert_eq!(r5.string_attr(&["resistance"]).as_deref(), Some("4.7kOhm"));
+
+        assert_eq!(
+            schematic.apply_variant("full"),
+            Err(VariantError::Unknown {
+                name: "full".into(),
+                available: "lite".into()
+            })
+        );
+    }
+
+    #[test]
+    fn reports_matches_and_adds_fields() {
+        let mut schematic = design();
+        let mut variant = lite();
+        variant
+            .overrides
+            .insert("Missing".into(), VariantOverride::default());
+        let matches = schematic.variant_matches(&variant);
+        assert_eq!(matches["Radio"].len(), 2);
+        assert!(matches["Missing"].is_empty());
+
+        schematic.add_variant_fields();
+        assert_eq!(
+            get(&schematic, "R5")
+                .string_attr(&["variant_lite"])
+                .as_deref(),
+            Some("Fitted; Value 4.7kOhm")
+        );
+        assert!(!get(&schematic, "Radio2.U1")
+            .attributes
+            .contains_key("variant_lite"));
+    }
+}
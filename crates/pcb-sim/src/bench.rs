@@ -0,0 +1,307 @@
+//! Simulation results as Starlark values.
+//!
+//! `pcb test` hands a `Simulation` to the checks of a `TestBench` that sets `simulation`, so
+//! checks can assert on simulated waveforms:
+//!
+//! ```python
+//! def vout_settles(module, inputs, sim):
+//!     vout = sim.voltage("VOUT")
+//!     check(vout.settles_to("3.3V 2%", within="1ms"), "VOUT settled at " + str(vout.final_value()))
+//! ```
+
+use std::fmt;
+use std::str::FromStr;
+use std::sync::Arc;
+
+use allocative::Allocative;
+use pcb_sch::physical::{PhysicalRange, PhysicalUnitDims, PhysicalValue};
+use pcb_sch::PhysicalUnit;
+use rust_decimal::prelude::{FromPrimitive, ToPrimitive};
+use rust_decimal::Decimal;
+use starlark::{
+    any::ProvidesStaticType,
+    environment::{Methods, MethodsBuilder, MethodsStatic},
+    starlark_module, starlark_simple_value,
+    values::{none::NoneOr, starlark_value, Heap, NoSerialize, StarlarkValue, Value},
+};
+
+use crate::raw::{Analysis, Plot};
+use crate::waveform::Waveform;
+
+/// All plots of one simulation run
+#[derive(Debug, Clone, ProvidesStaticType, NoSerialize, Allocative)]
+pub struct SimulationValue {
+    #[allocative(skip)]
+    plots: Arc<Vec<Plot>>,
+}
+
+impl SimulationValue {
+    pub fn new(plots: Vec<Plot>) -> Self {
+        Self {
+            plots: Arc::new(plots),
+        }
+    }
+
+    /// Plots to search for a vector: the requested analysis, or transient results first
+    fn candidate_plots(&self, analysis: Option<&str>) -> starlark::Result<Vec<&Plot>> {
+        let wanted = match analysis {
+            None => None,
+            Some("tran") => Some(Analysis::Transient),
+            Some("ac") => Some(Analysis::Ac),
+            Some("dc") => Some(Analysis::Dc),
+            Some("op") => Some(Analysis::OperatingPoint),
+            Some(other) => {
+                return Err(sim_error(format!(
+                    "unknown analysis '{other}', expected one of tran, ac, dc, op"
+                )))
+            }
+        };
+        let mut plots: Vec<&Plot> = self
+            .plots
+            .iter()
+            .filter(|p| wanted.as_ref().is_none_or(|a| &p.analysis == a))
+            .collect();
+        plots.sort_by_key(|p| p.analysis != Analysis::Transient);
+        Ok(plots)
+    }
+
+    fn lookup(
+        &self,
+        analysis: Option<&str>,
+        what: &str,
+        find: impl Fn(&Plot) -> Option<Waveform>,
+    ) -> starlark::Result<WaveformValue> {
+        let plots = self.candidate_plots(analysis)?;
+        plots
+            .iter()
+            .find_map(|plot| find(plot))
+            .map(WaveformValue)
+            .ok_or_else(|| {
+                let available: Vec<&str> = plots
+                    .iter()
+                    .flat_map(|p| p.vectors.iter().skip(1).map(|v| v.name.as_str()))
+                    .collect();
+                sim_error(format!(
+                    "no simulation results for {what} (available: {})",
+                    available.join(", ")
+                ))
+            })
+    }
+}
+
+impl fmt::Display for SimulationValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let names: Vec<&str> = self.plots.iter().map(|p| p.name.as_str()).collect();
+        write!(f, "Simulation({})", names.join(", "))
+    }
+}
+
+starlark_simple_value!(SimulationValue);
+
+#[starlark_value(type = "Simulation")]
+impl<'v> StarlarkValue<'v> for SimulationValue {
+    fn get_methods() -> Option<&'static Methods> {
+        static RES: MethodsStatic = MethodsStatic::new();
+        RES.methods(simulation_methods)
+    }
+}
+
+#[starlark_module]
+fn simulation_methods(methods: &mut MethodsBuilder) {
+    /// Names of the analyses that were run
+    #[starlark(attribute)]
+    fn analyses<'v>(this: &SimulationValue) -> starlark::Result<Vec<String>> {
+        Ok(this.plots.iter().map(|p| p.name.clone()).collect())
+    }
+
+    /// Voltage of a net (a `Net` or its name) against ground
+    fn voltage<'v>(
+        this: &SimulationValue,
+        #[starlark(require = pos)] net: Value<'v>,
+        #[starlark(require = named)] analysis: Option<String>,
+        heap: &'v Heap,
+    ) -> starlark::Result<WaveformValue> {
+        let name = match net.unpack_str() {
+            Some(name) => name.to_string(),
+            None => net
+                .get_attr("name", heap)?
+                .and_then(|n| n.unpack_str().map(str::to_string))
+                .ok_or_else(|| sim_error(format!("expected a net or net name, got {net}")))?,
+        };
+        this.lookup(analysis.as_deref(), &format!("net '{name}'"), |plot| {
+            plot.node_voltage(&name)
+        })
+    }
+
+    /// Current through a voltage source, e.g. `sim.current("V1")`
+    fn current<'v>(
+        this: &SimulationValue,
+        #[starlark(require = pos)] source: String,
+        #[starlark(require = named)] analysis: Option<String>,
+    ) -> starlark::Result<WaveformValue> {
+        let name = format!("i({source})");
+        this.lookup(analysis.as_deref(), &format!("'{source}'"), |plot| {
+            plot.waveform(plot.vector(&name)?)
+        })
+    }
+}
+
+/// A simulated waveform with physical units
+#[derive(Debug, Clone, ProvidesStaticType, NoSerialize, Allocative)]
+pub struct WaveformValue(#[allocative(skip)] Waveform);
+
+impl WaveformValue {
+    fn unit(&self) -> PhysicalUnitDims {
+        match self.0.kind.as_str() {
+            "voltage" => PhysicalUnit::Volts.into(),
+            "current" => PhysicalUnit::Amperes.into(),
+            _ => PhysicalUnitDims::DIMENSIONLESS,
+        }
+    }
+
+    fn scale_unit(&self) -> PhysicalUnitDims {
+        match self.0.scale_kind.as_str() {
+            "time" => PhysicalUnit::Seconds.into(),
+            "frequency" => PhysicalUnit::Hertz.into(),
+            _ => PhysicalUnitDims::DIMENSIONLESS,
+        }
+    }
+
+    fn value(&self, y: f64) -> starlark::Result<PhysicalValue> {
+        physical(y, self.unit())
+    }
+
+    /// `(low, high)` band described by a value with tolerance, a range or a string
+    fn band(&self, target: Value) -> starlark::Result<(f64, f64)> {
+        let (unit, low, high) = if let Some(s) = target.unpack_str() {
+            let range = PhysicalRange::from_str(s)
+                .map_err(|e| sim_error(format!("invalid target '{s}': {e}")))?;
+            let (low, high) = range.bounds();
+            (range.unit(), low, high)
+        } else if let Some(range) = target.downcast_ref::<PhysicalRange>() {
+            let (low, high) = range.bounds();
+            (range.unit(), low, high)
+        } else {
+            let pv = PhysicalValue::try_from(target)?;
+            let (a, b) = (pv.min_value(pv.tolerance), pv.max_value(pv.tolerance));
+            (pv.unit, a.min(b), a.max(b))
+        };
+        if unit != self.unit() && self.unit() != PhysicalUnitDims::DIMENSIONLESS {
+            return Err(sim_error(format!(
+                "cannot compare {} with a {} target",
+                self.0.name,
+                unit.quantity()
+            )));
+        }
+        Ok((to_f64(low)?, to_f64(high)?))
+    }
+
+    /// A scale position (time or frequency) given as a physical value, string or number
+    fn scale_position(&self, value: Value) -> starlark::Result<f64> {
+        let pv = PhysicalValue::try_from(value)?;
+        if pv.unit != self.scale_unit() && pv.unit != PhysicalUnitDims::DIMENSIONLESS {
+            return Err(sim_error(format!(
+                "expected a {}, got {pv}",
+                self.scale_unit().quantity()
+            )));
+        }
+        to_f64(pv.value)
+    }
+}
+
+impl fmt::Display for WaveformValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Waveform({})", self.0.name)
+    }
+}
+
+starlark_simple_value!(WaveformValue);
+
+#[starlark_value(type = "Waveform")]
+impl<'v> StarlarkValue<'v> for WaveformValue {
+    fn get_methods() -> Option<&'static Methods> {
+        static RES: MethodsStatic = MethodsStatic::new();
+        RES.methods(waveform_methods)
+    }
+}
+
+#[starlark_module]
+fn waveform_methods(methods: &mut MethodsBuilder) {
+    /// ngspice vector name, e.g. `v(vout)`
+    #[starlark(attribute)]
+    fn name<'v>(this: &WaveformValue) -> starlark::Result<String> {
+        Ok(this.0.name.clone())
+    }
+
+    /// Value at the end of the analysis
+    fn final_value<'v>(this: &WaveformValue) -> starlark::Result<PhysicalValue> {
+        this.value(this.0.final_value().ok_or_else(|| empty(this))?)
+    }
+
+    fn min<'v>(this: &WaveformValue) -> starlark::Result<PhysicalValue> {
+        this.value(this.0.min().ok_or_else(|| empty(this))?)
+    }
+
+    fn max<'v>(this: &WaveformValue) -> starlark::Result<PhysicalValue> {
+        this.value(this.0.max().ok_or_else(|| empty(this))?)
+    }
+
+    /// Interpolated value at a time (or frequency); `None` outside the simulated range
+    fn at<'v>(
+        this: &WaveformValue,
+        #[starlark(require = pos)] x: Value<'v>,
+    ) -> starlark::Result<NoneOr<PhysicalValue>> {
+        let x = this.scale_position(x)?;
+        Ok(match this.0.value_at(x) {
+            Some(y) => NoneOr::Other(this.value(y)?),
+            None => NoneOr::None,
+        })
+    }
+
+    /// Time from which the waveform stays within `target` (e.g. `"3.3V 2%"`), or `None` if
+    /// it never settles
+    fn settling_time<'v>(
+        this: &WaveformValue,
+        #[starlark(require = pos)] target: Value<'v>,
+    ) -> starlark::Result<NoneOr<PhysicalValue>> {
+        let (low, high) = this.band(target)?;
+        Ok(match this.0.settling_time(low, high) {
+            Some(t) => NoneOr::Other(physical(t, this.scale_unit())?),
+            None => NoneOr::None,
+        })
+    }
+
+    /// Whether the waveform settles within `target` no later than `within`
+    fn settles_to<'v>(
+        this: &WaveformValue,
+        #[starlark(require = pos)] target: Value<'v>,
+        #[starlark(require = named)] within: Value<'v>,
+    ) -> starlark::Result<bool> {
+        let (low, high) = this.band(target)?;
+        let deadline = this.scale_position(within)?;
+        Ok(this
+            .0
+            .settling_time(low, high)
+            .is_some_and(|t| t <= deadline))
+    }
+}
+
+fn physical(value: f64, unit: PhysicalUnitDims) -> starlark::Result<PhysicalValue> {
+    let value = Decimal::from_f64(value)
+        .ok_or_else(|| sim_error(format!("{value} is not representable")))?;
+    Ok(PhysicalValue::from_decimal(value, Decimal::ZERO, unit))
+}
+
+fn to_f64(value: Decimal) -> starlark::Result<f64> {
+    value
+        .to_f64()
+        .ok_or_else(|| sim_error(format!("{value} is out of range")))
+}
+
+fn empty(waveform: &WaveformValue) -> starlark::Error {
+    sim_error(format!("{} has no samples", waveform.0.name))
+}
+
+fn sim_error(message: String) -> starlark::Error {
+    starlark::Error::new_other(anyhow::anyhow!(message))
+}
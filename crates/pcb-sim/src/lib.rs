@@ -1,25 +1,105 @@
+pub mod bench;
+pub mod ngspice;
+pub mod passive;
+pub mod raw;
+pub mod variation;
+pub mod waveform;
+
 use anyhow::Result;
 use itertools::Itertools;
-use pcb_sch::{AttributeValue, Schematic};
-use pcb_zen_core::attrs;
-use std::collections::HashSet;
+use pcb_sch::{AttributeValue, Instance, InstanceRef, Schematic};
+use pcb_zen_core::lang::error::CategorizedDiagnostic;
+use pcb_zen_core::{attrs, Diagnostic, Diagnostics};
+use rust_decimal::Decimal;
+use starlark::errors::EvalSeverity;
+use std::collections::{HashMap, HashSet};
 use std::io::Write;
+use std::path::Path;
+
+use crate::passive::Passive;
+
+/// Components that have no SPICE model and are not a recognized passive
+pub const KIND_UNMODELLED: &str = "sim.unmodelled";
+
+// Generate .cir from a zen file. Resistors, capacitors and inductors without a SpiceModel are
+// written as primitive elements; any other component without a model is left out and
+// reported in the returned warning diagnostics.
+pub fn gen_sim(schematic: &Schematic, out: &mut impl Write) -> Result<Diagnostics> {
+    gen_sim_with_values(schematic, out, &HashMap::new())
+}
+
+/// Like [`gen_sim`], with the synthesized passives named in `values` (by reference
+/// designator) set to the given value instead of their nominal one. Used for corner and
+/// Monte Carlo runs.
+pub fn gen_sim_with_values(
+    schematic: &Schematic,
+    out: &mut impl Write,
+    values: &HashMap<String, Decimal>,
+) -> Result<Diagnostics> {
+    write_components(schematic, out, values, |_| true, |net| net)
+}
+
+/// Generate .cir lines for the components under a single top-level module instance, such as
+/// one TestBench case. Net names are written relative to that instance, so a net `VOUT`
+/// introduced by the case module is simulated as node `VOUT`.
+pub fn gen_sim_instance(
+    schematic: &Schematic,
+    instance: &str,
+    out: &mut impl Write,
+) -> Result<Diagnostics> {
+    let prefix = format!("{instance}.");
+    write_components(
+        schematic,
+        out,
+        &HashMap::new(),
+        |inst_ref| inst_ref.instance_path.first().map(String::as_str) == Some(instance),
+        |net| net.strip_prefix(&prefix).unwrap_or(net),
+    )
+}
 
-// Generate .cir from a zen file
-pub fn gen_sim(schematic: &Schematic, out: &mut impl Write) -> Result<()> {
+fn write_components<'a>(
+    schematic: &'a Schematic,
+    out: &mut impl Write,
+    values: &HashMap<String, Decimal>,
+    include: impl Fn(&InstanceRef) -> bool,
+    net_name: impl Fn(&'a str) -> &'a str,
+) -> Result<Diagnostics> {
     // Start with an empty line
     writeln!(out).unwrap();
 
     let mut included_libs = HashSet::new();
+    let mut unmodelled = Vec::new();
+
+    let port_nets: HashMap<&InstanceRef, &str> = schematic
+        .nets
+        .values()
+        .flat_map(|net| net.ports.iter().map(|port| (port, net.name.as_str())))
+        .collect();
 
     // Generate the .cir file
     for comp_inst in schematic
         .instances
-        .values()
-        .filter(|i| i.kind == pcb_sch::InstanceKind::Component)
+        .iter()
+        .filter(|(inst_ref, i)| i.kind == pcb_sch::InstanceKind::Component && include(inst_ref))
+        .map(|(_, i)| i)
         .sorted_by_key(|i| i.reference_designator.as_ref().unwrap())
     {
+        let comp_name = comp_inst.reference_designator.as_ref().unwrap();
         if !comp_inst.attributes.contains_key(attrs::MODEL_DEF) {
+            let nets = pin_nets(comp_inst, &port_nets);
+            let passive = Passive::detect(comp_inst).map(|p| match values.get(comp_name) {
+                Some(value) => p.with_value(*value),
+                None => p,
+            });
+            match (passive, nets.as_slice()) {
+                (Some(passive), [a, b]) => write!(
+                    out,
+                    "{}",
+                    passive.cards(comp_name, net_name(*a), net_name(*b))
+                )
+                .unwrap(),
+                _ => unmodelled.push(comp_name.as_str()),
+            }
             continue;
         }
         let model_def = comp_inst
@@ -38,7 +118,6 @@ pub fn gen_sim(schematic: &Schematic, out: &mut impl Write) -> Result<()> {
             .unwrap()
             .string()
             .unwrap();
-        let comp_name = comp_inst.reference_designator.as_ref().unwrap();
         let arg_str = comp_inst
             .attributes
             .get(attrs::MODEL_ARGS)
@@ -47,11 +126,48 @@ pub fn gen_sim(schematic: &Schematic, out: &mut impl Write) -> Result<()> {
             .unwrap();
         if let AttributeValue::Array(net_arr) = comp_inst.attributes.get(attrs::MODEL_NETS).unwrap()
         {
-            let nets = net_arr.iter().map(|s| s.string().unwrap()).join(" ");
+            let nets = net_arr
+                .iter()
+                .map(|s| net_name(s.string().unwrap()))
+                .join(" ");
             writeln!(out, "X{comp_name} {nets} {model_name} {arg_str}").unwrap();
         } else {
             unreachable!("bad spice model");
         }
     }
-    Ok(())
+
+    let mut diagnostics = Diagnostics::default();
+    if !unmodelled.is_empty() {
+        let path = schematic
+            .root_ref
+            .as_ref()
+            .map(|r| r.module.source_path.as_path())
+            .unwrap_or(Path::new(""));
+        let message = format!(
+            "{} not simulated: no SPICE model and not a two-terminal resistor, capacitor or inductor: {}",
+            if unmodelled.len() == 1 {
+                "1 component is".to_string()
+            } else {
+                format!("{} components are", unmodelled.len())
+            },
+            unmodelled.join(", ")
+        );
+        let categorized = CategorizedDiagnostic::new(message.clone(), KIND_UNMODELLED.to_string())
+            .expect("simulation diagnostic kind should be valid");
+        diagnostics.push(
+            Diagnostic::new(message, EvalSeverity::Warning, path)
+                .with_source_error(Some(categorized)),
+        );
+    }
+    Ok(diagnostics)
+}
+
+/// Distinct nets connected to a component's pins, in pin name order
+fn pin_nets<'a>(comp: &Instance, port_nets: &HashMap<&InstanceRef, &'a str>) -> Vec<&'a str> {
+    comp.children
+        .iter()
+        .sorted_by_key(|(pin, _)| pin.as_str())
+        .filter_map(|(_, port_ref)| port_nets.get(port_ref).copied())
+        .unique()
+        .collect()
 }
@@ -0,0 +1,99 @@
+//! Running a netlist through a local ngspice in batch mode.
+
+use std::path::Path;
+use std::process::Command;
+
+use anyhow::{bail, Context, Result};
+
+use crate::raw::{self, Plot};
+
+/// Program used when no ngspice path is given
+pub const DEFAULT_NGSPICE: &str = "ngspice";
+
+/// Append the analysis `setup` (sources, `.tran`/`.ac` lines, ...) to a generated netlist and
+/// terminate it with `.end` unless the setup already does.
+pub fn with_setup(body: &str, setup: &str) -> String {
+    let mut netlist = format!("{body}{}\n", setup.trim_end());
+    let has_end = setup
+        .lines()
+        .any(|line| line.trim().eq_ignore_ascii_case(".end"));
+    if !has_end {
+        netlist.push_str(".end\n");
+    }
+    netlist
+}
+
+/// Simulate `netlist` (a complete `.cir`, including analyses and `.end`) with `ngspice` and
+/// return the resulting plots.
+pub fn run(netlist: &str, ngspice: &Path) -> Result<Vec<Plot>> {
+    let dir = tempfile::tempdir().context("failed to create simulation directory")?;
+    let cir_path = dir.path().join("sim.cir");
+    let raw_path = dir.path().join("sim.raw");
+    std::fs::write(&cir_path, netlist)
+        .with_context(|| format!("failed to write {}", cir_path.display()))?;
+
+    let output = Command::new(ngspice)
+        .arg("-b")
+        .arg("-r")
+        .arg(&raw_path)
+        .arg(&cir_path)
+        .current_dir(dir.path())
+        .output()
+        .map_err(|e| match e.kind() {
+            std::io::ErrorKind::NotFound => anyhow::anyhow!(
+                "{} not found. Install ngspice or pass its path with --ngspice",
+                ngspice.display()
+            ),
+            _ => anyhow::Error::new(e).context(format!("failed to run {}", ngspice.display())),
+        })?;
+
+    // ngspice reports netlist errors on stdout/stderr but may still exit successfully
+    if !output.status.success() || !raw_path.exists() {
+        let log = String::from_utf8_lossy(&output.stderr).into_owned()
+            + &String::from_utf8_lossy(&output.stdout);
+        let errors: Vec<&str> = log
+            .lines()
+            .filter(|line| line.to_ascii_lowercase().contains("error"))
+            .collect();
+        bail!(
+            "ngspice simulation failed ({}):\n{}",
+            output.status,
+            if errors.is_empty() {
+                log.trim().to_string()
+            } else {
+                errors.join("\n")
+            }
+        );
+    }
+
+    let data = std::fs::read(&raw_path)
+        .with_context(|| format!("failed to read {}", raw_path.display()))?;
+    let plots = raw::parse(&data).context("failed to parse ngspice output")?;
+    if plots.is_empty() {
+        bail!("ngspice produced no results; add an analysis such as `.tran` or `.ac`");
+    }
+    Ok(plots)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_with_setup() {
+        let body = "\nXR1 VIN VOUT R_model R=1000\n";
+        assert_eq!(
+            with_setup(body, "V1 VIN 0 3.3\n.tran 1u 1m\n"),
+            "\nXR1 VIN VOUT R_model R=1000\nV1 VIN 0 3.3\n.tran 1u 1m\n.end\n"
+        );
+        // An explicit `.end` in the setup is kept as the only one
+        assert_eq!(with_setup(body, ".op\n.END\n").matches(".END").count(), 1);
+        assert!(!with_setup(body, ".op\n.END\n").contains(".end"));
+    }
+
+    #[test]
+    fn test_missing_ngspice() {
+        let err = run(".op\n.end\n", Path::new("/nonexistent/ngspice")).unwrap_err();
+        assert!(err.to_string().contains("--ngspice"), "{err}");
+    }
+}
@@ -0,0 +1,151 @@
+//! Primitive SPICE elements for passives without an explicit `SpiceModel`.
+//!
+//! Resistors and capacitors are recognized with the same generic-component detection the BOM
+//! uses (`Type` plus a `resistance`/`capacitance` property); inductors by `Type` and an
+//! `inductance` property. A capacitor's `esr` becomes a series resistor.
+
+use pcb_sch::physical::PhysicalValue;
+use pcb_sch::{detect_generic_component, GenericComponent, Instance};
+use rust_decimal::Decimal;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PassiveKind {
+    Resistor,
+    Capacitor,
+    Inductor,
+}
+
+impl PassiveKind {
+    /// SPICE element letter
+    fn letter(self) -> char {
+        match self {
+            PassiveKind::Resistor => 'R',
+            PassiveKind::Capacitor => 'C',
+            PassiveKind::Inductor => 'L',
+        }
+    }
+}
+
+/// A two-terminal passive that can be simulated as a primitive element
+#[derive(Debug, Clone, PartialEq)]
+pub struct Passive {
+    pub kind: PassiveKind,
+    /// Value and tolerance in base units (ohms, farads or henries)
+    pub value: PhysicalValue,
+    /// Equivalent series resistance of a capacitor
+    pub esr: Option<PhysicalValue>,
+}
+
+impl Passive {
+    pub fn detect(instance: &Instance) -> Option<Self> {
+        match detect_generic_component(instance) {
+            Some(GenericComponent::Resistor(resistor)) => Some(Passive {
+                kind: PassiveKind::Resistor,
+                value: resistor.resistance,
+                esr: None,
+            }),
+            Some(GenericComponent::Capacitor(capacitor)) => Some(Passive {
+                kind: PassiveKind::Capacitor,
+                value: capacitor.capacitance,
+                esr: capacitor.esr,
+            }),
+            None if instance.component_type().as_deref() == Some("inductor") => {
+                let value = instance.physical_attr(&["Inductance", "inductance"])?;
+                Some(Passive {
+                    kind: PassiveKind::Inductor,
+                    value,
+                    esr: None,
+                })
+            }
+            None => None,
+        }
+    }
+
+    /// This passive with its value replaced by an exact `value`
+    pub fn with_value(&self, value: Decimal) -> Self {
+        Self {
+            value: PhysicalValue::from_decimal(value, Decimal::ZERO, self.value.unit),
+            ..self.clone()
+        }
+    }
+
+    /// SPICE cards for this passive, named after `refdes`, between nodes `a` and `b`
+    pub fn cards(&self, refdes: &str, a: &str, b: &str) -> String {
+        let name = element_name(self.kind.letter(), refdes);
+        let value = spice_number(self.value.value);
+        match self.esr.filter(|esr| !esr.value.is_zero()) {
+            Some(esr) => {
+                let mid = format!("{refdes}_esr");
+                format!(
+                    "{name} {a} {mid} {value}\n{} {mid} {b} {}\n",
+                    element_name('R', &mid),
+                    spice_number(esr.value)
+                )
+            }
+            None => format!("{name} {a} {b} {value}\n"),
+        }
+    }
+}
+
+/// SPICE element name: the type letter, followed by the reference designator unless it
+/// already starts with that letter
+fn element_name(letter: char, refdes: &str) -> String {
+    if refdes
+        .chars()
+        .next()
+        .is_some_and(|c| c.eq_ignore_ascii_case(&letter))
+    {
+        refdes.to_string()
+    } else {
+        format!("{letter}{refdes}")
+    }
+}
+
+/// Plain decimal notation, which every SPICE dialect reads the same way (unlike suffixes
+/// such as `M`, which SPICE reads as milli)
+fn spice_number(value: Decimal) -> String {
+    value.normalize().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pcb_sch::{AttributeValue, ModuleRef};
+
+    fn instance(attributes: &[(&str, &str)]) -> Instance {
+        let mut instance = Instance::component(ModuleRef::new("/board.zen", "<root>"));
+        for (key, value) in attributes {
+            instance.add_attribute(*key, AttributeValue::String(value.to_string()));
+        }
+        instance
+    }
+
+    #[test]
+    fn test_detect_and_emit() {
+        let r =
+            Passive::detect(&instance(&[("Type", "resistor"), ("resistance", "10k 1%")])).unwrap();
+        assert_eq!(r.cards("R1", "VIN", "VOUT"), "R1 VIN VOUT 10000\n");
+
+        let c = Passive::detect(&instance(&[
+            ("Type", "capacitor"),
+            ("capacitance", "100nF"),
+            ("esr", "10mOhm"),
+        ]))
+        .unwrap();
+        assert_eq!(
+            c.cards("C1", "VOUT", "GND"),
+            "C1 VOUT C1_esr 0.0000001\nRC1_esr C1_esr GND 0.01\n"
+        );
+
+        let l =
+            Passive::detect(&instance(&[("type", "Inductor"), ("inductance", "4.7uH")])).unwrap();
+        assert_eq!(l.cards("FB1", "SW", "VOUT"), "LFB1 SW VOUT 0.0000047\n");
+    }
+
+    #[test]
+    fn test_unrecognized_components() {
+        assert!(Passive::detect(&instance(&[("Type", "resistor")])).is_none());
+        assert!(Passive::detect(&instance(&[("Type", "diode")])).is_none());
+        assert!(Passive::detect(&instance(&[])).is_none());
+    }
+}
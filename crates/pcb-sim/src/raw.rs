@@ -0,0 +1,387 @@
+//! Parser for ngspice raw files.
+//!
+//! ngspice writes one plot per analysis (`.op`, `.tran`, `.ac`, ...). Each plot has a header
+//! listing its vectors followed by the data, either as text (`Values:`, written when
+//! `filetype=ascii`) or as native little-endian doubles (`Binary:`, the default). The first
+//! vector of a plot is its scale (time for transient, frequency for AC).
+
+use anyhow::{bail, Context, Result};
+
+use crate::waveform::Waveform;
+
+/// Kind of analysis a plot came from, derived from its `Plotname`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Analysis {
+    OperatingPoint,
+    Transient,
+    Ac,
+    Dc,
+    Other(String),
+}
+
+impl Analysis {
+    fn from_plotname(name: &str) -> Self {
+        let lower = name.to_ascii_lowercase();
+        if lower.starts_with("transient") {
+            Analysis::Transient
+        } else if lower.starts_with("ac") {
+            Analysis::Ac
+        } else if lower.starts_with("dc") {
+            Analysis::Dc
+        } else if lower.starts_with("operating point") {
+            Analysis::OperatingPoint
+        } else {
+            Analysis::Other(name.to_string())
+        }
+    }
+}
+
+/// A single simulated quantity
+#[derive(Debug, Clone, PartialEq)]
+pub struct Vector {
+    /// ngspice name, e.g. `time`, `v(vout)` or `i(v1)`
+    pub name: String,
+    /// ngspice type, e.g. `time`, `frequency`, `voltage` or `current`
+    pub kind: String,
+    /// Real part of every point
+    pub real: Vec<f64>,
+    /// Imaginary part of every point, for complex (AC) plots
+    pub imag: Option<Vec<f64>>,
+}
+
+impl Vector {
+    /// Magnitude of every point (the real value for real vectors)
+    pub fn magnitude(&self) -> Vec<f64> {
+        match &self.imag {
+            Some(imag) => self
+                .real
+                .iter()
+                .zip(imag)
+                .map(|(re, im)| re.hypot(*im))
+                .collect(),
+            None => self.real.clone(),
+        }
+    }
+}
+
+/// One analysis result
+#[derive(Debug, Clone, PartialEq)]
+pub struct Plot {
+    pub title: String,
+    pub name: String,
+    pub analysis: Analysis,
+    pub vectors: Vec<Vector>,
+}
+
+impl Plot {
+    /// The scale vector (time, frequency, sweep variable)
+    pub fn scale(&self) -> Option<&Vector> {
+        self.vectors.first()
+    }
+
+    /// Look up a vector by name (ngspice names are case-insensitive)
+    pub fn vector(&self, name: &str) -> Option<&Vector> {
+        self.vectors
+            .iter()
+            .find(|v| v.name.eq_ignore_ascii_case(name))
+    }
+
+    /// Voltage of `node` against ground over the plot's scale
+    pub fn node_voltage(&self, node: &str) -> Option<Waveform> {
+        let vector = self
+            .vector(&format!("v({node})"))
+            .or_else(|| self.vector(node))?;
+        self.waveform(vector)
+    }
+
+    /// `vector` over the plot's scale; complex vectors are reduced to their magnitude
+    pub fn waveform(&self, vector: &Vector) -> Option<Waveform> {
+        let scale = self.scale()?;
+        Some(Waveform {
+            name: vector.name.clone(),
+            kind: vector.kind.clone(),
+            scale_kind: scale.kind.clone(),
+            x: scale.real.clone(),
+            y: vector.magnitude(),
+        })
+    }
+}
+
+/// Parse every plot in a raw file
+pub fn parse(data: &[u8]) -> Result<Vec<Plot>> {
+    let mut plots = Vec::new();
+    let mut pos = 0;
+    while pos < data.len() {
+        if data[pos].is_ascii_whitespace() {
+            pos += 1;
+            continue;
+        }
+        let (plot, next) = parse_plot(data, pos)?;
+        plots.push(plot);
+        pos = next;
+    }
+    Ok(plots)
+}
+
+#[derive(Default)]
+struct Header {
+    title: String,
+    name: String,
+    complex: bool,
+    num_vars: usize,
+    num_points: usize,
+    vars: Vec<(String, String)>,
+}
+
+/// Parse one plot starting at `pos`, returning it and the position after its data
+fn parse_plot(data: &[u8], mut pos: usize) -> Result<(Plot, usize)> {
+    let mut header = Header::default();
+    let mut in_variables = false;
+
+    let binary = loop {
+        let Some((line, next)) = next_line(data, pos) else {
+            bail!("raw file ended before the plot data");
+        };
+        pos = next;
+
+        if in_variables && line.starts_with(char::is_whitespace) {
+            let mut fields = line.split_whitespace();
+            let (Some(_index), Some(name), Some(kind)) =
+                (fields.next(), fields.next(), fields.next())
+            else {
+                bail!("malformed variable line: {line:?}");
+            };
+            header.vars.push((name.to_string(), kind.to_string()));
+            continue;
+        }
+        in_variables = false;
+
+        let Some((key, value)) = line.split_once(':') else {
+            continue;
+        };
+        let value = value.trim();
+        match key.trim() {
+            "Title" => header.title = value.to_string(),
+            "Plotname" => header.name = value.to_string(),
+            "Flags" => header.complex = value.split_whitespace().any(|f| f == "complex"),
+            "No. Variables" => header.num_vars = value.parse().context("bad variable count")?,
+            "No. Points" => header.num_points = value.parse().context("bad point count")?,
+            "Variables" => in_variables = true,
+            "Values" => break false,
+            "Binary" => break true,
+            _ => {}
+        }
+    };
+
+    if header.vars.len() != header.num_vars {
+        bail!(
+            "plot '{}' declares {} variables but lists {}",
+            header.name,
+            header.num_vars,
+            header.vars.len()
+        );
+    }
+
+    let (columns, next) = if binary {
+        read_binary(data, pos, &header)?
+    } else {
+        read_ascii(data, pos, &header)?
+    };
+
+    let vectors = header
+        .vars
+        .into_iter()
+        .zip(columns)
+        .map(|((name, kind), (real, imag))| Vector {
+            name,
+            kind,
+            real,
+            imag,
+        })
+        .collect();
+
+    Ok((
+        Plot {
+            title: header.title,
+            analysis: Analysis::from_plotname(&header.name),
+            name: header.name,
+            vectors,
+        },
+        next,
+    ))
+}
+
+type Column = (Vec<f64>, Option<Vec<f64>>);
+
+fn empty_columns(header: &Header) -> Vec<Column> {
+    (0..header.num_vars)
+        .map(|_| {
+            (
+                Vec::with_capacity(header.num_points),
+                header
+                    .complex
+                    .then(|| Vec::with_capacity(header.num_points)),
+            )
+        })
+        .collect()
+}
+
+fn read_binary(data: &[u8], pos: usize, header: &Header) -> Result<(Vec<Column>, usize)> {
+    let value_size = if header.complex { 16 } else { 8 };
+    let len = header.num_points * header.num_vars * value_size;
+    let Some(bytes) = data.get(pos..pos + len) else {
+        bail!(
+            "plot '{}' is truncated: expected {len} bytes of data",
+            header.name
+        );
+    };
+
+    let read = |offset: usize| f64::from_le_bytes(bytes[offset..offset + 8].try_into().unwrap());
+    let mut columns = empty_columns(header);
+    for point in 0..header.num_points {
+        for (var, (real, imag)) in columns.iter_mut().enumerate() {
+            let offset = (point * header.num_vars + var) * value_size;
+            real.push(read(offset));
+            if let Some(imag) = imag {
+                imag.push(read(offset + 8));
+            }
+        }
+    }
+    Ok((columns, pos + len))
+}
+
+fn read_ascii(data: &[u8], mut pos: usize, header: &Header) -> Result<(Vec<Column>, usize)> {
+    let mut columns = empty_columns(header);
+    for point in 0..header.num_points {
+        // Every point starts with its index, followed by one value per variable
+        let (_, next) = next_token(data, pos)
+            .with_context(|| format!("plot '{}' is missing point {point}", header.name))?;
+        pos = next;
+        for (real, imag) in columns.iter_mut() {
+            let (token, next) = next_token(data, pos)
+                .with_context(|| format!("plot '{}' is truncated", header.name))?;
+            pos = next;
+            let (re, im) = match token.split_once(',') {
+                Some((re, im)) => (re, Some(im)),
+                None => (token, None),
+            };
+            real.push(parse_f64(re)?);
+            if let Some(imag) = imag {
+                imag.push(im.map(parse_f64).transpose()?.unwrap_or(0.0));
+            }
+        }
+    }
+    Ok((columns, pos))
+}
+
+fn parse_f64(token: &str) -> Result<f64> {
+    token
+        .parse()
+        .with_context(|| format!("invalid number {token:?} in raw file"))
+}
+
+/// The line starting at `pos` (without its terminator) and the position after it
+fn next_line(data: &[u8], pos: usize) -> Option<(&str, usize)> {
+    if pos >= data.len() {
+        return None;
+    }
+    let end = data[pos..]
+        .iter()
+        .position(|&b| b == b'\n')
+        .map_or(data.len(), |i| pos + i);
+    let line = std::str::from_utf8(&data[pos..end]).ok()?;
+    Some((line.trim_end_matches('\r'), end + 1))
+}
+
+/// The whitespace-separated token at or after `pos` and the position after it
+fn next_token(data: &[u8], mut pos: usize) -> Option<(&str, usize)> {
+    while pos < data.len() && data[pos].is_ascii_whitespace() {
+        pos += 1;
+    }
+    let start = pos;
+    while pos < data.len() && !data[pos].is_ascii_whitespace() {
+        pos += 1;
+    }
+    if start == pos {
+        return None;
+    }
+    Some((std::str::from_utf8(&data[start..pos]).ok()?, pos))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ASCII: &str = "Title: rc step
+Date: Thu Jan  1 00:00:00  2026
+Plotname: Transient Analysis
+Flags: real
+No. Variables: 2
+No. Points: 3
+Variables:
+\t0\ttime\ttime
+\t1\tv(vout)\tvoltage
+Values:
+ 0\t0.000000000000000e+00
+\t0.000000000000000e+00
+
+ 1\t1.000000000000000e-03
+\t2.000000000000000e+00
+
+ 2\t2.000000000000000e-03
+\t3.000000000000000e+00
+
+Title: rc step
+Date: Thu Jan  1 00:00:00  2026
+Plotname: AC Analysis
+Flags: complex
+No. Variables: 2
+No. Points: 1
+Variables:
+\t0\tfrequency\tfrequency grid=3
+\t1\tv(vout)\tvoltage
+Values:
+ 0\t1.000000000000000e+03,0.000000000000000e+00
+\t3.000000000000000e+00,4.000000000000000e+00
+";
+
+    #[test]
+    fn test_parse_ascii() {
+        let plots = parse(ASCII.as_bytes()).unwrap();
+        assert_eq!(plots.len(), 2);
+
+        let tran = &plots[0];
+        assert_eq!(tran.analysis, Analysis::Transient);
+        assert_eq!(tran.scale().unwrap().real, vec![0.0, 1e-3, 2e-3]);
+        let vout = tran.node_voltage("VOUT").unwrap();
+        assert_eq!(vout.y, vec![0.0, 2.0, 3.0]);
+        assert_eq!(vout.scale_kind, "time");
+
+        let ac = &plots[1];
+        assert_eq!(ac.analysis, Analysis::Ac);
+        assert_eq!(ac.vector("frequency").unwrap().real, vec![1e3]);
+        assert_eq!(ac.node_voltage("vout").unwrap().y, vec![5.0]);
+    }
+
+    #[test]
+    fn test_parse_binary() {
+        let mut data = b"Title: op\nPlotname: Transient Analysis\nFlags: real\n\
+No. Variables: 2\nNo. Points: 2\nVariables:\n\t0\ttime\ttime\n\t1\tv(out)\tvoltage\nBinary:\n"
+            .to_vec();
+        for value in [0.0f64, 1.5, 1e-6, 2.5] {
+            data.extend_from_slice(&value.to_le_bytes());
+        }
+
+        let plots = parse(&data).unwrap();
+        assert_eq!(plots.len(), 1);
+        let out = plots[0].node_voltage("out").unwrap();
+        assert_eq!(out.x, vec![0.0, 1e-6]);
+        assert_eq!(out.y, vec![1.5, 2.5]);
+    }
+
+    #[test]
+    fn test_truncated_data_is_an_error() {
+        let truncated = &ASCII[..ASCII.find(" 2\t").unwrap()];
+        assert!(parse(truncated.as_bytes()).is_err());
+    }
+}
@@ -0,0 +1,61 @@
+//! Corner and Monte Carlo runs over the tolerances of synthesized passives.
+//!
+//! Only resistors, capacitors and inductors written as primitive elements are varied;
+//! components with an explicit `SpiceModel` always use their model arguments.
+
+use std::collections::HashMap;
+
+use anyhow::Result;
+use itertools::Itertools;
+use pcb_sch::physical::PhysicalValue;
+use pcb_sch::tolerance::{self, Variation};
+use pcb_sch::{InstanceKind, Schematic};
+use pcb_zen_core::attrs;
+use rust_decimal::Decimal;
+
+use crate::passive::Passive;
+use crate::raw::{Analysis, Plot};
+use crate::waveform::Waveform;
+
+/// Synthesized passives with a non-zero tolerance, by reference designator
+pub fn toleranced_passives(schematic: &Schematic) -> Vec<(String, PhysicalValue)> {
+    schematic
+        .instances
+        .values()
+        .filter(|i| {
+            i.kind == InstanceKind::Component && !i.attributes.contains_key(attrs::MODEL_DEF)
+        })
+        .filter_map(|i| {
+            let passive = Passive::detect(i)?;
+            let refdes = i.reference_designator.clone()?;
+            (!passive.value.tolerance.is_zero()).then_some((refdes, passive.value))
+        })
+        .sorted_by(|a, b| a.0.cmp(&b.0))
+        .collect()
+}
+
+/// Passive values for every run of `variation`
+pub fn runs(
+    passives: &[(String, PhysicalValue)],
+    variation: Variation,
+) -> Result<Vec<HashMap<String, Decimal>>> {
+    let values: Vec<PhysicalValue> = passives.iter().map(|(_, v)| *v).collect();
+    Ok(tolerance::sample(&values, variation)?
+        .into_iter()
+        .map(|run| {
+            passives
+                .iter()
+                .map(|(refdes, _)| refdes.clone())
+                .zip(run)
+                .collect()
+        })
+        .collect())
+}
+
+/// Voltage of `node` in the transient analysis of a run, or in the first analysis that has it
+pub fn node_voltage(plots: &[Plot], node: &str) -> Option<Waveform> {
+    plots
+        .iter()
+        .sorted_by_key(|p| p.analysis != Analysis::Transient)
+        .find_map(|p| p.node_voltage(node))
+}
@@ -0,0 +1,98 @@
+//! Simulated waveforms and the measurements TestBench checks make on them.
+
+/// A simulated quantity sampled over a scale (time for transient, frequency for AC)
+#[derive(Debug, Clone, PartialEq)]
+pub struct Waveform {
+    /// ngspice vector name, e.g. `v(vout)`
+    pub name: String,
+    /// ngspice type of the samples, e.g. `voltage` or `current`
+    pub kind: String,
+    /// ngspice type of the scale, e.g. `time` or `frequency`
+    pub scale_kind: String,
+    pub x: Vec<f64>,
+    pub y: Vec<f64>,
+}
+
+impl Waveform {
+    /// The last sample
+    pub fn final_value(&self) -> Option<f64> {
+        self.y.last().copied()
+    }
+
+    pub fn min(&self) -> Option<f64> {
+        self.y.iter().copied().reduce(f64::min)
+    }
+
+    pub fn max(&self) -> Option<f64> {
+        self.y.iter().copied().reduce(f64::max)
+    }
+
+    /// Linearly interpolated value at `x`, or `None` outside the simulated range
+    pub fn value_at(&self, x: f64) -> Option<f64> {
+        let i = self.x.partition_point(|&xi| xi < x);
+        if i == self.x.len() {
+            return None;
+        }
+        if self.x[i] == x {
+            return Some(self.y[i]);
+        }
+        if i == 0 {
+            return None;
+        }
+        let (x0, x1) = (self.x[i - 1], self.x[i]);
+        let (y0, y1) = (self.y[i - 1], self.y[i]);
+        Some(y0 + (y1 - y0) * (x - x0) / (x1 - x0))
+    }
+
+    /// Scale value from which the waveform stays within `[low, high]` until the end.
+    ///
+    /// Returns `None` if the last sample is outside the band, i.e. the waveform never settles.
+    pub fn settling_time(&self, low: f64, high: f64) -> Option<f64> {
+        let inside = |y: f64| (low..=high).contains(&y);
+        let last_outside = self.y.iter().rposition(|&y| !inside(y));
+        match last_outside {
+            None => self.x.first().copied(),
+            Some(i) if i + 1 < self.x.len() => Some(self.x[i + 1]),
+            Some(_) => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn step() -> Waveform {
+        Waveform {
+            name: "v(vout)".into(),
+            kind: "voltage".into(),
+            scale_kind: "time".into(),
+            x: vec![0.0, 1.0, 2.0, 3.0, 4.0],
+            y: vec![0.0, 2.0, 3.5, 3.25, 3.3],
+        }
+    }
+
+    #[test]
+    fn test_measurements() {
+        let w = step();
+        assert_eq!(w.final_value(), Some(3.3));
+        assert_eq!(w.min(), Some(0.0));
+        assert_eq!(w.max(), Some(3.5));
+        assert_eq!(w.value_at(0.5), Some(1.0));
+        assert_eq!(w.value_at(2.0), Some(3.5));
+        assert_eq!(w.value_at(-1.0), None);
+        assert_eq!(w.value_at(5.0), None);
+    }
+
+    #[test]
+    fn test_settling_time() {
+        let w = step();
+        // 3.3V ±2%: the 3.5V overshoot at t=2 is the last excursion
+        assert_eq!(w.settling_time(3.234, 3.366), Some(3.0));
+        // A wide band is met from the first sample that enters it
+        assert_eq!(w.settling_time(1.5, 4.0), Some(1.0));
+        assert_eq!(w.settling_time(-1.0, 4.0), Some(0.0));
+        // Never settles if the final value is outside the band
+        assert_eq!(w.settling_time(5.0, 6.0), None);
+    }
+}
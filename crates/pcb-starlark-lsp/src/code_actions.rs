@@ -0,0 +1,303 @@
+/*
+ * Copyright 2019 The Starlark in Rust Authors.
+ * Copyright (c) Facebook, Inc. and its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Code actions: a quick fix that adds the `load()` of an undefined symbol from the
+//! workspace file that exports it, followed by the actions of [`LspContext::code_actions`].
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Arc;
+
+use itertools::Itertools;
+use lsp_types::CodeAction;
+use lsp_types::CodeActionKind;
+use lsp_types::CodeActionOrCommand;
+use lsp_types::CodeActionParams;
+use lsp_types::Diagnostic;
+use lsp_types::InitializeParams;
+use lsp_types::Position;
+use lsp_types::Range;
+use lsp_types::TextEdit;
+use lsp_types::Url;
+use lsp_types::WorkspaceEdit;
+use starlark::codemap::Pos;
+use starlark::codemap::Span;
+use starlark::syntax::AstModule;
+use starlark::syntax::Dialect;
+use starlark_syntax::syntax::ast::AstLiteral;
+use starlark_syntax::syntax::ast::ExprP;
+use starlark_syntax::syntax::ast::StmtP;
+use starlark_syntax::syntax::module::AstModuleFields;
+use starlark_syntax::syntax::top_level_stmts::top_level_stmts;
+
+use crate::definition::LspModule;
+use crate::server::Backend;
+use crate::server::LspContext;
+use crate::server::LspUrl;
+
+/// The name in a "Variable `x` not found" error.
+fn undefined_variable(message: &str) -> Option<&str> {
+    let start = message.find("Variable `")? + "Variable `".len();
+    let rest = &message[start..];
+    let name = &rest[..rest.find('`')?];
+    rest[name.len()..]
+        .starts_with("` not found")
+        .then_some(name)
+}
+
+impl LspModule {
+    /// The edit that loads `name` from `path`: added to an existing `load()` of `path`, or as
+    /// a new `load()` after the last one (or after the module docstring).
+    fn load_edit(&self, path: &str, name: &str) -> TextEdit {
+        let codemap = self.ast.codemap();
+        let position = |pos: Pos| Range::from(codemap.resolve_span(Span::new(pos, pos))).start;
+        let mut insert_line = 0;
+        for (i, stmt) in top_level_stmts(self.ast.statement())
+            .into_iter()
+            .enumerate()
+        {
+            match &stmt.node {
+                StmtP::Load(load) if load.module.node == path => {
+                    let source = codemap.source_span(stmt.span);
+                    if let Some(close) = source.rfind(')') {
+                        let separator = if source[..close].trim_end().ends_with(',') {
+                            " "
+                        } else {
+                            ", "
+                        };
+                        let at = position(Pos::new(stmt.span.begin().get() + close as u32));
+                        return TextEdit::new(Range::new(at, at), format!("{separator}\"{name}\""));
+                    }
+                }
+                StmtP::Load(_) => insert_line = codemap.resolve_span(stmt.span).end.line + 1,
+                StmtP::Expression(expr)
+                    if i == 0 && matches!(expr.node, ExprP::Literal(AstLiteral::String(_))) =>
+                {
+                    insert_line = codemap.resolve_span(stmt.span).end.line + 1
+                }
+                _ => {}
+            }
+        }
+        let at = Position::new(insert_line as u32, 0);
+        TextEdit::new(
+            Range::new(at, at),
+            format!("load(\"{path}\", \"{name}\")\n"),
+        )
+    }
+}
+
+impl<T: LspContext> Backend<T> {
+    pub(crate) fn code_actions(
+        &self,
+        params: CodeActionParams,
+        initialize_params: &InitializeParams,
+    ) -> anyhow::Result<Vec<CodeActionOrCommand>> {
+        let uri: LspUrl = params.text_document.uri.clone().try_into()?;
+        let workspace_root =
+            Self::get_workspace_root(initialize_params.workspace_folders.as_ref(), &uri);
+        let mut actions = Vec::new();
+        if let Some(module) = self.last_valid_parse.read().unwrap().get(&uri).cloned() {
+            for diagnostic in &params.context.diagnostics {
+                actions.extend(self.missing_load_actions(
+                    &params.text_document.uri,
+                    &uri,
+                    &module,
+                    diagnostic,
+                    workspace_root.as_deref(),
+                ));
+            }
+        }
+        actions.extend(
+            self.context
+                .code_actions(&uri, &params, workspace_root.as_deref())?,
+        );
+        Ok(actions)
+    }
+
+    /// Quick fixes loading the symbol of an undefined-variable `diagnostic`, one per workspace
+    /// file that exports it.
+    fn missing_load_actions(
+        &self,
+        url: &Url,
+        uri: &LspUrl,
+        module: &LspModule,
+        diagnostic: &Diagnostic,
+        workspace_root: Option<&Path>,
+    ) -> Vec<CodeActionOrCommand> {
+        let Some(name) = undefined_variable(&diagnostic.message) else {
+            return Vec::new();
+        };
+        self.exporting_files(name, uri, workspace_root)
+            .into_iter()
+            .filter_map(|target| {
+                let path = self
+                    .context
+                    .render_as_load(&target, uri, workspace_root)
+                    .ok()?;
+                let edit = module.load_edit(&path, name);
+                Some(CodeActionOrCommand::CodeAction(CodeAction {
+                    title: format!("Add `load(\"{path}\", \"{name}\")`"),
+                    kind: Some(CodeActionKind::QUICKFIX),
+                    diagnostics: Some(vec![diagnostic.clone()]),
+                    edit: Some(WorkspaceEdit::new(HashMap::from([(
+                        url.clone(),
+                        vec![edit],
+                    )]))),
+                    ..CodeAction::default()
+                }))
+            })
+            .collect()
+    }
+
+    /// Workspace files other than `uri` that export a top-level `name`, by path.
+    fn exporting_files(
+        &self,
+        name: &str,
+        uri: &LspUrl,
+        workspace_root: Option<&Path>,
+    ) -> Vec<LspUrl> {
+        let mut modules: HashMap<LspUrl, Arc<LspModule>> =
+            self.parsed_modules().into_iter().collect();
+        if !self.context.is_eager() {
+            // Only open files have been parsed, so read the rest of the workspace. Parsing
+            // without evaluating is enough to find the exported symbols.
+            let roots: Vec<_> = workspace_root.map(Path::to_path_buf).into_iter().collect();
+            for path in self.context.workspace_files(&roots).unwrap_or_default() {
+                let Some(file) = Url::from_file_path(&path)
+                    .ok()
+                    .and_then(|url| LspUrl::try_from(url).ok())
+                else {
+                    continue;
+                };
+                if modules.contains_key(&file) {
+                    continue;
+                }
+                let Ok(Some(contents)) = self.context.get_load_contents(&file) else {
+                    continue;
+                };
+                if let Ok(ast) = AstModule::parse(
+                    &path.to_string_lossy(),
+                    contents,
+                    &Dialect::AllOptionsInternal,
+                ) {
+                    modules.insert(file, Arc::new(LspModule::new(ast)));
+                }
+            }
+        }
+        modules
+            .into_iter()
+            .filter(|(file, module)| file != uri && module.find_exported_symbol(name).is_some())
+            .map(|(file, _)| file)
+            .sorted_by_key(|file| file.to_string())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use lsp_types::request::CodeActionRequest;
+    use lsp_types::CodeActionContext;
+    use lsp_types::TextDocumentIdentifier;
+    use starlark::wasm::is_wasm;
+
+    use super::*;
+    use crate::test::TestServer;
+
+    fn temp_file_uri(rel_path: &str) -> Url {
+        Url::from_file_path(std::env::temp_dir().join(rel_path)).unwrap()
+    }
+
+    fn quick_fixes(
+        server: &mut TestServer,
+        uri: &Url,
+        message: &str,
+    ) -> anyhow::Result<Vec<(String, TextEdit)>> {
+        let range = Range::new(Position::new(2, 0), Position::new(2, 6));
+        let req = server.new_request::<CodeActionRequest>(CodeActionParams {
+            text_document: TextDocumentIdentifier { uri: uri.clone() },
+            range,
+            context: CodeActionContext {
+                diagnostics: vec![Diagnostic::new_simple(range, message.to_owned())],
+                only: None,
+                trigger_kind: None,
+            },
+            work_done_progress_params: Default::default(),
+            partial_result_params: Default::default(),
+        });
+        let id = server.send_request(req)?;
+        let actions = server.get_response::<Vec<CodeActionOrCommand>>(id)?;
+        Ok(actions
+            .into_iter()
+            .filter_map(|action| match action {
+                CodeActionOrCommand::CodeAction(action) => {
+                    let mut changes = action.edit?.changes?;
+                    Some((action.title, changes.remove(uri)?.pop()?))
+                }
+                CodeActionOrCommand::Command(_) => None,
+            })
+            .collect())
+    }
+
+    #[test]
+    fn adds_missing_loads() -> anyhow::Result<()> {
+        if is_wasm() {
+            return Ok(());
+        }
+
+        let lib_uri = temp_file_uri("missing_lib.star");
+        let new_uri = temp_file_uri("missing_new.star");
+        let existing_uri = temp_file_uri("missing_existing.star");
+
+        let mut server = TestServer::new()?;
+        server.open_file(
+            lib_uri,
+            "def helper():\n    pass\n\ndef other():\n    pass\n".to_owned(),
+        )?;
+        server.open_file(
+            new_uri.clone(),
+            "\"\"\"Docs.\"\"\"\n\nhelper()\n".to_owned(),
+        )?;
+        server.open_file(
+            existing_uri.clone(),
+            "load(\":missing_lib.star\", \"other\")\n\nhelper()\n".to_owned(),
+        )?;
+
+        let message = "Variable `helper` not found";
+        assert_eq!(
+            quick_fixes(&mut server, &new_uri, message)?,
+            vec![(
+                "Add `load(\":missing_lib.star\", \"helper\")`".to_owned(),
+                TextEdit::new(
+                    Range::new(Position::new(1, 0), Position::new(1, 0)),
+                    "load(\":missing_lib.star\", \"helper\")\n".to_owned()
+                )
+            )]
+        );
+        assert_eq!(
+            quick_fixes(&mut server, &existing_uri, message)?,
+            vec![(
+                "Add `load(\":missing_lib.star\", \"helper\")`".to_owned(),
+                TextEdit::new(
+                    Range::new(Position::new(0, 33), Position::new(0, 33)),
+                    ", \"helper\"".to_owned()
+                )
+            )]
+        );
+        assert!(quick_fixes(&mut server, &new_uri, "Variable `missing` not found")?.is_empty());
+        Ok(())
+    }
+}
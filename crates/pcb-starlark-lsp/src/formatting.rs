@@ -0,0 +1,122 @@
+/*
+ * Copyright 2019 The Starlark in Rust Authors.
+ * Copyright (c) Facebook, Inc. and its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Document and range formatting, with the formatter of [`LspContext::format_file`].
+//!
+//! The whole file is always formatted; the result is sent as one edit per changed run of
+//! lines, so that a range request only touches the lines it asked for.
+
+use lsp_types::DocumentFormattingParams;
+use lsp_types::DocumentRangeFormattingParams;
+use lsp_types::Position;
+use lsp_types::Range;
+use lsp_types::TextEdit;
+use similar::DiffOp;
+use similar::TextDiff;
+
+use crate::server::Backend;
+use crate::server::LspContext;
+use crate::server::LspUrl;
+
+impl<T: LspContext> Backend<T> {
+    pub(crate) fn formatting_edits(
+        &self,
+        params: DocumentFormattingParams,
+    ) -> anyhow::Result<Option<Vec<TextEdit>>> {
+        self.format_edits(&params.text_document.uri.try_into()?, None)
+    }
+
+    pub(crate) fn range_formatting_edits(
+        &self,
+        params: DocumentRangeFormattingParams,
+    ) -> anyhow::Result<Option<Vec<TextEdit>>> {
+        self.format_edits(&params.text_document.uri.try_into()?, Some(params.range))
+    }
+
+    fn format_edits(
+        &self,
+        uri: &LspUrl,
+        range: Option<Range>,
+    ) -> anyhow::Result<Option<Vec<TextEdit>>> {
+        let Some(contents) = self.context.get_load_contents(uri)? else {
+            return Ok(None);
+        };
+        let Some(formatted) = self.context.format_file(uri, &contents)? else {
+            return Ok(None);
+        };
+        Ok(Some(line_edits(&contents, &formatted, range)))
+    }
+}
+
+/// Edits that turn `old` into `new`, one per changed run of lines. With a `range`, only the
+/// runs that touch its lines are kept.
+pub(crate) fn line_edits(old: &str, new: &str, range: Option<Range>) -> Vec<TextEdit> {
+    let diff = TextDiff::from_lines(old, new);
+    let new_lines = diff.new_slices();
+    diff.ops()
+        .iter()
+        .filter(|op| !matches!(op, DiffOp::Equal { .. }))
+        .filter(|op| {
+            let lines = op.old_range();
+            // An insertion touches the line it is inserted before.
+            let last = lines.end.max(lines.start + 1) - 1;
+            range.is_none_or(|range| {
+                lines.start as u32 <= range.end.line && last as u32 >= range.start.line
+            })
+        })
+        .map(|op| {
+            let lines = op.old_range();
+            TextEdit::new(
+                Range::new(
+                    Position::new(lines.start as u32, 0),
+                    Position::new(lines.end as u32, 0),
+                ),
+                new_lines[op.new_range()].concat(),
+            )
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn edits_changed_lines_only() {
+        let old = "x=1\ny = 2\nz=[1,2]\n";
+        let new = "x = 1\ny = 2\nz = [1, 2]\n";
+
+        let edits = line_edits(old, new, None);
+        assert_eq!(
+            edits,
+            vec![
+                TextEdit::new(
+                    Range::new(Position::new(0, 0), Position::new(1, 0)),
+                    "x = 1\n".to_owned()
+                ),
+                TextEdit::new(
+                    Range::new(Position::new(2, 0), Position::new(3, 0)),
+                    "z = [1, 2]\n".to_owned()
+                ),
+            ]
+        );
+
+        let range = Range::new(Position::new(2, 0), Position::new(2, 3));
+        assert_eq!(line_edits(old, new, Some(range)), edits[1..]);
+        assert!(line_edits(new, new, None).is_empty());
+    }
+}
@@ -22,13 +22,17 @@
 #[allow(clippy::needless_lifetimes)]
 #[allow(clippy::type_complexity)]
 mod bind;
+mod code_actions;
 pub mod completion;
 mod definition;
 pub(crate) mod docs;
 pub mod error;
 mod exported;
+mod formatting;
 pub(crate) mod inspect;
 pub(crate) mod loaded;
+mod outline;
+mod references;
 pub mod server;
 mod symbols;
 #[cfg(test)]
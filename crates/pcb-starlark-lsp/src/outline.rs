@@ -0,0 +1,214 @@
+/*
+ * Copyright 2019 The Starlark in Rust Authors.
+ * Copyright (c) Facebook, Inc. and its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Document outline: the `io()` and `config()` parameters, modules, component instances,
+//! functions and variables declared at the top level of a file.
+
+use lsp_types::DocumentSymbol;
+use lsp_types::SymbolKind;
+use starlark::codemap::Span;
+use starlark_syntax::syntax::ast::ArgumentP;
+use starlark_syntax::syntax::ast::AssignTargetP;
+use starlark_syntax::syntax::ast::AstAssignIdent;
+use starlark_syntax::syntax::ast::AstExpr;
+use starlark_syntax::syntax::ast::Expr;
+use starlark_syntax::syntax::ast::StmtP;
+use starlark_syntax::syntax::module::AstModuleFields;
+use starlark_syntax::syntax::top_level_stmts::top_level_stmts;
+
+use crate::definition::LspModule;
+use crate::references::callee_name;
+use crate::references::first_string_argument;
+use crate::references::named_string_argument;
+
+impl LspModule {
+    /// Top-level symbols of the module, in source order.
+    pub(crate) fn document_symbols(&self) -> Vec<DocumentSymbol> {
+        let mut symbols = Vec::new();
+        for stmt in top_level_stmts(self.ast.statement()) {
+            match &stmt.node {
+                StmtP::Def(def) => symbols.push(self.symbol(
+                    def.name.ident.clone(),
+                    None,
+                    SymbolKind::FUNCTION,
+                    stmt.span,
+                    def.name.span,
+                )),
+                StmtP::Assign(assign) => {
+                    let AssignTargetP::Identifier(lhs) = &assign.lhs.node else {
+                        continue;
+                    };
+                    let symbol = self
+                        .call_symbol(&assign.rhs, stmt.span, Some(lhs))
+                        .unwrap_or_else(|| {
+                            let kind = match &assign.rhs.node {
+                                Expr::Lambda(_) => SymbolKind::FUNCTION,
+                                _ => SymbolKind::VARIABLE,
+                            };
+                            self.symbol(lhs.ident.clone(), None, kind, stmt.span, lhs.span)
+                        });
+                    symbols.push(symbol);
+                }
+                StmtP::Expression(expr) => symbols.extend(self.call_symbol(expr, stmt.span, None)),
+                _ => {}
+            }
+        }
+        symbols
+    }
+
+    /// The symbol declared by a call to `io()`, `config()` or `Module()`, or the component
+    /// instance created by a call with a `name = "..."` argument. `assigned` is the variable
+    /// the result is assigned to, if any.
+    fn call_symbol(
+        &self,
+        expr: &AstExpr,
+        range: Span,
+        assigned: Option<&AstAssignIdent>,
+    ) -> Option<DocumentSymbol> {
+        let Expr::Call(callee, args) = &expr.node else {
+            return None;
+        };
+        let source = |span: Span| self.ast.codemap().source_span(span).to_owned();
+        match callee_name(callee) {
+            Some(function @ ("io" | "config")) => {
+                let name = first_string_argument(args)?;
+                let detail = args.args.get(1).and_then(|arg| match &arg.node {
+                    ArgumentP::Positional(typ) => Some(format!("{function}({})", source(typ.span))),
+                    _ => None,
+                });
+                let kind = match function {
+                    "io" => SymbolKind::INTERFACE,
+                    _ => SymbolKind::PROPERTY,
+                };
+                Some(self.symbol(name.node.clone(), detail, kind, range, name.span))
+            }
+            Some("Module") => {
+                let path = first_string_argument(args)?;
+                let assigned = assigned?;
+                Some(self.symbol(
+                    assigned.ident.clone(),
+                    Some(path.node.clone()),
+                    SymbolKind::MODULE,
+                    range,
+                    assigned.span,
+                ))
+            }
+            _ => {
+                let name = named_string_argument(args, "name")?;
+                Some(self.symbol(
+                    name.node.clone(),
+                    Some(source(callee.span)),
+                    SymbolKind::OBJECT,
+                    range,
+                    name.span,
+                ))
+            }
+        }
+    }
+
+    fn symbol(
+        &self,
+        name: String,
+        detail: Option<String>,
+        kind: SymbolKind,
+        range: Span,
+        selection: Span,
+    ) -> DocumentSymbol {
+        let codemap = self.ast.codemap();
+        #[allow(deprecated)]
+        DocumentSymbol {
+            name,
+            detail,
+            kind,
+            tags: None,
+            deprecated: None,
+            range: codemap.resolve_span(range).into(),
+            selection_range: codemap.resolve_span(selection).into(),
+            children: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use starlark::syntax::AstModule;
+    use starlark::syntax::Dialect;
+
+    use super::*;
+
+    #[test]
+    fn outlines_zen_module() -> anyhow::Result<()> {
+        let program = r#"
+load("@stdlib/interfaces.zen", "Power")
+
+vin = io("VIN", Power)
+r1 = config("r1", str, default = "10k")
+Resistor = Module("@stdlib/generics/Resistor.zen")
+
+def helper(x):
+    return x
+
+Resistor(name = "R1", value = r1, P1 = vin.NET, P2 = Net("OUT"))
+c = Capacitor(name = "C1", value = "100nF")
+count = 2
+"#;
+        let module = LspModule::new(AstModule::parse(
+            "board.zen",
+            program.to_owned(),
+            &Dialect::AllOptionsInternal,
+        )?);
+
+        let outline: Vec<(String, Option<String>, SymbolKind)> = module
+            .document_symbols()
+            .into_iter()
+            .map(|s| (s.name, s.detail, s.kind))
+            .collect();
+        assert_eq!(
+            outline,
+            vec![
+                (
+                    "VIN".to_owned(),
+                    Some("io(Power)".to_owned()),
+                    SymbolKind::INTERFACE
+                ),
+                (
+                    "r1".to_owned(),
+                    Some("config(str)".to_owned()),
+                    SymbolKind::PROPERTY
+                ),
+                (
+                    "Resistor".to_owned(),
+                    Some("@stdlib/generics/Resistor.zen".to_owned()),
+                    SymbolKind::MODULE
+                ),
+                ("helper".to_owned(), None, SymbolKind::FUNCTION),
+                (
+                    "R1".to_owned(),
+                    Some("Resistor".to_owned()),
+                    SymbolKind::OBJECT
+                ),
+                (
+                    "C1".to_owned(),
+                    Some("Capacitor".to_owned()),
+                    SymbolKind::OBJECT
+                ),
+                ("count".to_owned(), None, SymbolKind::VARIABLE),
+            ]
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,851 @@
+/*
+ * Copyright 2019 The Starlark in Rust Authors.
+ * Copyright (c) Facebook, Inc. and its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Find references and rename.
+//!
+//! Bindings are resolved with the same scope analysis as goto-definition. Top-level symbols
+//! are followed into every workspace file that `load()`s them, and the names of `io()` and
+//! `config()` parameters into the keyword arguments of the `Module()` instantiations of
+//! their file.
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Arc;
+
+use dupe::Dupe;
+use lsp_types::InitializeParams;
+use lsp_types::Location;
+use lsp_types::Range;
+use lsp_types::ReferenceParams;
+use lsp_types::RenameParams;
+use lsp_types::TextEdit;
+use lsp_types::Url;
+use lsp_types::WorkspaceEdit;
+use starlark::codemap::Pos;
+use starlark::codemap::Span;
+use starlark::codemap::Spanned;
+use starlark_syntax::syntax::ast::ArgumentP;
+use starlark_syntax::syntax::ast::AssignTargetP;
+use starlark_syntax::syntax::ast::AstExpr;
+use starlark_syntax::syntax::ast::AstLiteral;
+use starlark_syntax::syntax::ast::AstNoPayload;
+use starlark_syntax::syntax::ast::CallArgsP;
+use starlark_syntax::syntax::ast::Expr;
+use starlark_syntax::syntax::ast::ExprP;
+use starlark_syntax::syntax::ast::Stmt;
+use starlark_syntax::syntax::ast::StmtP;
+use starlark_syntax::syntax::module::AstModuleFields;
+use starlark_syntax::syntax::top_level_stmts::top_level_stmts;
+use starlark_syntax::syntax::uniplate::Visit;
+
+use crate::bind::scope;
+use crate::bind::Assigner;
+use crate::bind::Bind;
+use crate::bind::Scope;
+use crate::definition::LspModule;
+use crate::server::Backend;
+use crate::server::LspContext;
+use crate::server::LspUrl;
+
+/// Functions whose first argument names a parameter of the module they are called in.
+const PORT_FUNCTIONS: [&str; 2] = ["io", "config"];
+
+/// A variable in a single file, identified by the span of its first assignment.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Binding {
+    pub(crate) name: String,
+    pub(crate) span: Span,
+    /// Whether the variable is bound at the top level, so that other files can `load()` it.
+    pub(crate) top_level: bool,
+}
+
+/// What references are being looked for.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum ReferenceTarget {
+    /// A variable defined in this file.
+    Binding(Binding),
+    /// A symbol loaded from another file: the path as written in `load()`, and the name of
+    /// the symbol in that file.
+    Loaded { path: String, name: String },
+    /// The name of an `io()` or `config()` parameter. `module` is the `Module()` path of the
+    /// file that declares it, or `None` for the current file.
+    Port {
+        module: Option<String>,
+        name: String,
+    },
+}
+
+/// A span that refers to a target, and whether it is the target's declaration.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Reference {
+    pub(crate) span: Span,
+    pub(crate) declaration: bool,
+}
+
+impl Reference {
+    fn new(span: Span) -> Self {
+        Self {
+            span,
+            declaration: false,
+        }
+    }
+
+    fn declaration(span: Span) -> Self {
+        Self {
+            span,
+            declaration: true,
+        }
+    }
+}
+
+/// Every call expression in the module, in source order.
+fn calls(module: &LspModule) -> Vec<(&AstExpr, &CallArgsP<AstNoPayload>)> {
+    fn visit<'a>(
+        node: Visit<'a, AstNoPayload>,
+        out: &mut Vec<(&'a AstExpr, &'a CallArgsP<AstNoPayload>)>,
+    ) {
+        match node {
+            Visit::Expr(expr) => {
+                if let Expr::Call(callee, args) = &expr.node {
+                    out.push((&**callee, args));
+                }
+                Visit::Expr(expr).visit_children(|node| visit(node, out));
+            }
+            node => node.visit_children(|node| visit(node, out)),
+        }
+    }
+
+    let mut out = Vec::new();
+    visit(Visit::Stmt(module.ast.statement()), &mut out);
+    out
+}
+
+/// The name of a function called by identifier, e.g. `io` in `io("VCC", Net)`.
+pub(crate) fn callee_name(callee: &AstExpr) -> Option<&str> {
+    match &callee.node {
+        ExprP::Identifier(ident) => Some(ident.node.ident.as_str()),
+        _ => None,
+    }
+}
+
+/// The first positional argument of a call, if it is a string literal.
+pub(crate) fn first_string_argument(args: &CallArgsP<AstNoPayload>) -> Option<&Spanned<String>> {
+    match &args.args.first()?.node {
+        ArgumentP::Positional(Spanned {
+            node: Expr::Literal(AstLiteral::String(s)),
+            ..
+        }) => Some(s),
+        _ => None,
+    }
+}
+
+/// The value of a named string argument of a call, e.g. `name = "R1"`.
+pub(crate) fn named_string_argument<'a>(
+    args: &'a CallArgsP<AstNoPayload>,
+    name: &str,
+) -> Option<&'a Spanned<String>> {
+    args.args.iter().find_map(|arg| match &arg.node {
+        ArgumentP::Named(
+            arg_name,
+            Spanned {
+                node: Expr::Literal(AstLiteral::String(s)),
+                ..
+            },
+        ) if arg_name.node == name => Some(s),
+        _ => None,
+    })
+}
+
+/// Whether `name` can be written as an identifier, and so as a variable or keyword argument.
+fn is_identifier(name: &str) -> bool {
+    const KEYWORDS: [&str; 22] = [
+        "and", "break", "continue", "def", "elif", "else", "for", "if", "in", "lambda", "load",
+        "not", "or", "pass", "return", "while", "None", "True", "False", "class", "import", "try",
+    ];
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+        && !KEYWORDS.contains(&name)
+}
+
+impl LspModule {
+    /// Zero based `line` and `col` as a position in the module, if the line exists.
+    fn pos_at(&self, line: u32, col: u32) -> Option<Pos> {
+        let line_span = self.ast.codemap().line_span_opt(line as usize)?;
+        Some(std::cmp::min(line_span.begin() + col, line_span.end()))
+    }
+
+    /// The span of the name itself: string literals such as `load()` symbols and `io()`
+    /// names lose their quotes.
+    fn name_span(&self, span: Span) -> Span {
+        let source = self.ast.codemap().source_span(span);
+        if source.len() >= 2 && (source.starts_with('"') || source.starts_with('\'')) {
+            Span::new(
+                Pos::new(span.begin().get() + 1),
+                Pos::new(span.end().get() - 1),
+            )
+        } else {
+            span
+        }
+    }
+
+    /// Find what the symbol at `line` and `col` (zero based) refers to.
+    pub(crate) fn find_reference_target(&self, line: u32, col: u32) -> Option<ReferenceTarget> {
+        let pos = self.pos_at(line, col)?;
+        if let Some(target) = self.port_at(pos) {
+            return Some(target);
+        }
+        if let Some(target) = self.load_symbol_at(pos) {
+            return Some(target);
+        }
+
+        /// `None` if nothing is at `pos`, `Some(None)` if an unresolvable global is.
+        fn visit<'a>(
+            scope: &'a Scope,
+            pos: Pos,
+            scopes: &mut Vec<&'a Scope>,
+        ) -> Option<Option<ReferenceTarget>> {
+            scopes.push(scope);
+            for bind in &scope.inner {
+                let name = match bind {
+                    Bind::Set(_, x) if x.span.contains(pos) => x.ident.as_str(),
+                    Bind::Get(x) if x.span.contains(pos) => x.node.ident.as_str(),
+                    Bind::GetDotted(x) if x.variable.span.contains(pos) => {
+                        x.variable.node.ident.as_str()
+                    }
+                    Bind::Scope(inner) => match visit(inner, pos, scopes) {
+                        Some(found) => return Some(found),
+                        None => continue,
+                    },
+                    _ => continue,
+                };
+                let resolved = scopes.iter().enumerate().rev().find_map(|(depth, s)| {
+                    s.bound
+                        .get(name)
+                        .map(|(assigner, span)| (depth, assigner, *span))
+                });
+                return Some(resolved.map(|(depth, assigner, span)| match assigner {
+                    // An unaliased load stands for the symbol in the loaded file
+                    Assigner::Load { path, name: their } if their.node == name => {
+                        ReferenceTarget::Loaded {
+                            path: path.node.clone(),
+                            name: their.node.clone(),
+                        }
+                    }
+                    _ => ReferenceTarget::Binding(Binding {
+                        name: name.to_owned(),
+                        span,
+                        top_level: depth == 0,
+                    }),
+                }));
+            }
+            scopes.pop();
+            None
+        }
+
+        visit(&scope(&self.ast), pos, &mut Vec::new()).flatten()
+    }
+
+    /// The symbol name in an aliased `load("path", local = "symbol")` at `pos`.
+    fn load_symbol_at(&self, pos: Pos) -> Option<ReferenceTarget> {
+        top_level_stmts(self.ast.statement())
+            .into_iter()
+            .find_map(|stmt| match &stmt.node {
+                StmtP::Load(load) => load.args.iter().find_map(|arg| {
+                    arg.their
+                        .span
+                        .contains(pos)
+                        .then(|| ReferenceTarget::Loaded {
+                            path: load.module.node.clone(),
+                            name: arg.their.node.clone(),
+                        })
+                }),
+                _ => None,
+            })
+    }
+
+    /// The `io()`/`config()` name, or the keyword argument of a `Module()` call, at `pos`.
+    fn port_at(&self, pos: Pos) -> Option<ReferenceTarget> {
+        let modules = self.module_bindings();
+        calls(self).into_iter().find_map(|(callee, args)| {
+            let callee = callee_name(callee)?;
+            if PORT_FUNCTIONS.contains(&callee) {
+                let name = first_string_argument(args)?;
+                return name.span.contains(pos).then(|| ReferenceTarget::Port {
+                    module: None,
+                    name: name.node.clone(),
+                });
+            }
+            let path = modules.get(callee)?;
+            args.args.iter().find_map(|arg| match &arg.node {
+                ArgumentP::Named(name, _) if name.span.contains(pos) => {
+                    Some(ReferenceTarget::Port {
+                        module: Some(path.clone()),
+                        name: name.node.clone(),
+                    })
+                }
+                _ => None,
+            })
+        })
+    }
+
+    /// Top-level `Name = Module("path")` assignments, by name.
+    pub(crate) fn module_bindings(&self) -> HashMap<String, String> {
+        top_level_stmts(self.ast.statement())
+            .into_iter()
+            .filter_map(|stmt| match &stmt.node {
+                Stmt::Assign(assign) => match (&assign.lhs.node, &assign.rhs.node) {
+                    (AssignTargetP::Identifier(lhs), Expr::Call(callee, args))
+                        if callee_name(callee) == Some("Module") =>
+                    {
+                        let path = first_string_argument(args)?;
+                        Some((lhs.ident.clone(), path.node.clone()))
+                    }
+                    _ => None,
+                },
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// The top-level variable `name`, unless it is loaded from another file.
+    pub(crate) fn top_level_binding(&self, name: &str) -> Option<Binding> {
+        match scope(&self.ast).bound.get(name)? {
+            (Assigner::Load { .. }, _) => None,
+            (_, span) => Some(Binding {
+                name: name.to_owned(),
+                span: *span,
+                top_level: true,
+            }),
+        }
+    }
+
+    /// Every assignment and use of `binding` in this file.
+    pub(crate) fn binding_references(&self, binding: &Binding) -> Vec<Reference> {
+        fn visit(scope: &Scope, binding: &Binding, visible: Option<Span>, out: &mut Vec<Span>) {
+            let visible = scope
+                .bound
+                .get(&binding.name)
+                .map(|(_, span)| *span)
+                .or(visible);
+            let here = visible == Some(binding.span);
+            for bind in &scope.inner {
+                match bind {
+                    Bind::Set(_, x) if here && x.ident == binding.name => out.push(x.span),
+                    Bind::Get(x) if here && x.node.ident == binding.name => out.push(x.span),
+                    Bind::GetDotted(x) if here && x.variable.node.ident == binding.name => {
+                        out.push(x.variable.span)
+                    }
+                    Bind::Scope(inner) => visit(inner, binding, visible, out),
+                    _ => {}
+                }
+            }
+        }
+
+        let mut spans = Vec::new();
+        visit(&scope(&self.ast), binding, None, &mut spans);
+        spans
+            .into_iter()
+            .map(|span| {
+                if span == binding.span {
+                    Reference::declaration(self.name_span(span))
+                } else {
+                    Reference::new(self.name_span(span))
+                }
+            })
+            .collect()
+    }
+
+    /// Every `load()` of the symbol `name` from a path for which `loads_target` holds, and
+    /// the uses of the symbols loaded without an alias.
+    pub(crate) fn load_references(
+        &self,
+        name: &str,
+        loads_target: impl Fn(&str) -> bool,
+    ) -> Vec<Reference> {
+        let mut references = Vec::new();
+        for stmt in top_level_stmts(self.ast.statement()) {
+            let StmtP::Load(load) = &stmt.node else {
+                continue;
+            };
+            let args: Vec<_> = load
+                .args
+                .iter()
+                .filter(|arg| arg.their.node == name)
+                .collect();
+            if args.is_empty() || !loads_target(&load.module.node) {
+                continue;
+            }
+            for arg in args {
+                if arg.local.span == arg.their.span {
+                    references.extend(self.binding_references(&Binding {
+                        name: name.to_owned(),
+                        span: arg.local.span,
+                        top_level: true,
+                    }));
+                } else {
+                    references.push(Reference::new(self.name_span(arg.their.span)));
+                }
+            }
+        }
+        // The load itself is where the symbol enters this file, not where it is declared
+        for reference in &mut references {
+            reference.declaration = false;
+        }
+        references
+    }
+
+    /// The names of the `io()` and `config()` declarations named `name`.
+    pub(crate) fn port_declarations(&self, name: &str) -> Vec<Reference> {
+        calls(self)
+            .into_iter()
+            .filter(|(callee, _)| callee_name(callee).is_some_and(|c| PORT_FUNCTIONS.contains(&c)))
+            .filter_map(|(_, args)| first_string_argument(args))
+            .filter(|arg| arg.node == name)
+            .map(|arg| Reference::declaration(self.name_span(arg.span)))
+            .collect()
+    }
+
+    /// The `name = ...` keyword arguments of calls to the module bound to `module`.
+    pub(crate) fn port_references(&self, module: &str, name: &str) -> Vec<Reference> {
+        calls(self)
+            .into_iter()
+            .filter(|(callee, _)| callee_name(callee) == Some(module))
+            .flat_map(|(_, args)| &args.args)
+            .filter_map(|arg| match &arg.node {
+                ArgumentP::Named(arg_name, _) if arg_name.node == name => {
+                    Some(Reference::new(arg_name.span))
+                }
+                _ => None,
+            })
+            .collect()
+    }
+}
+
+impl<T: LspContext> Backend<T> {
+    /// All parsed files, so that references can be found across the workspace.
+    pub(crate) fn parsed_modules(&self) -> Vec<(LspUrl, Arc<LspModule>)> {
+        let last_valid_parse = self.last_valid_parse.read().unwrap();
+        last_valid_parse
+            .iter()
+            .map(|(uri, module)| (uri.clone(), module.dupe()))
+            .collect()
+    }
+
+    /// Whether `path`, as written in `uri`, resolves to `target`.
+    fn resolves_to(
+        &self,
+        path: &str,
+        uri: &LspUrl,
+        target: &LspUrl,
+        workspace_root: Option<&Path>,
+    ) -> bool {
+        self.resolve_load_path(path, uri, workspace_root)
+            .is_ok_and(|resolved| &resolved == target)
+    }
+
+    /// Find every reference to the symbol at `line` and `col` of `uri`, across the
+    /// workspace.
+    pub(crate) fn find_references(
+        &self,
+        uri: &LspUrl,
+        line: u32,
+        col: u32,
+        workspace_root: Option<&Path>,
+    ) -> anyhow::Result<Vec<(LspUrl, Range, bool)>> {
+        let Some(module) = self.get_ast_or_load_from_disk(uri)? else {
+            return Ok(Vec::new());
+        };
+        let Some(target) = module.find_reference_target(line, col) else {
+            return Ok(Vec::new());
+        };
+
+        let mut references: Vec<(LspUrl, Reference)> = Vec::new();
+        match target {
+            ReferenceTarget::Binding(binding) if !binding.top_level => {
+                references.extend(
+                    module
+                        .binding_references(&binding)
+                        .into_iter()
+                        .map(|r| (uri.clone(), r)),
+                );
+            }
+            ReferenceTarget::Binding(binding) => {
+                self.symbol_references(uri, &binding.name, workspace_root, &mut references)?;
+            }
+            ReferenceTarget::Loaded { path, name } => {
+                let definition = self.resolve_load_path(&path, uri, workspace_root)?;
+                self.symbol_references(&definition, &name, workspace_root, &mut references)?;
+            }
+            ReferenceTarget::Port { module: None, name } => {
+                self.port_references(uri, &name, workspace_root, &mut references)?;
+            }
+            ReferenceTarget::Port {
+                module: Some(path),
+                name,
+            } => {
+                let definition = self.resolve_load_path(&path, uri, workspace_root)?;
+                self.port_references(&definition, &name, workspace_root, &mut references)?;
+            }
+        }
+
+        let mut resolved = Vec::with_capacity(references.len());
+        for (uri, reference) in references {
+            if let Some(module) = self.get_ast_or_load_from_disk(&uri)? {
+                let range = module.ast.codemap().resolve_span(reference.span).into();
+                resolved.push((uri, range, reference.declaration));
+            }
+        }
+        resolved.sort_by(|a, b| {
+            (a.0.to_string(), a.1.start.line, a.1.start.character).cmp(&(
+                b.0.to_string(),
+                b.1.start.line,
+                b.1.start.character,
+            ))
+        });
+        resolved.dedup();
+        Ok(resolved)
+    }
+
+    /// References to the top-level symbol `name` of `definition`, and to its loads.
+    fn symbol_references(
+        &self,
+        definition: &LspUrl,
+        name: &str,
+        workspace_root: Option<&Path>,
+        out: &mut Vec<(LspUrl, Reference)>,
+    ) -> anyhow::Result<()> {
+        if let Some(module) = self.get_ast_or_load_from_disk(definition)? {
+            if let Some(binding) = module.top_level_binding(name) {
+                out.extend(
+                    module
+                        .binding_references(&binding)
+                        .into_iter()
+                        .map(|r| (definition.clone(), r)),
+                );
+            }
+        }
+        // Symbols starting with `_` are private to their file
+        if name.starts_with('_') {
+            return Ok(());
+        }
+        for (uri, module) in self.parsed_modules() {
+            if &uri == definition {
+                continue;
+            }
+            let references = module.load_references(name, |path| {
+                self.resolves_to(path, &uri, definition, workspace_root)
+            });
+            out.extend(references.into_iter().map(|r| (uri.clone(), r)));
+        }
+        Ok(())
+    }
+
+    /// References to the `io()`/`config()` parameter `name` of `definition`: its declaration
+    /// and the keyword arguments of every `Module()` instantiation of that file.
+    fn port_references(
+        &self,
+        definition: &LspUrl,
+        name: &str,
+        workspace_root: Option<&Path>,
+        out: &mut Vec<(LspUrl, Reference)>,
+    ) -> anyhow::Result<()> {
+        if let Some(module) = self.get_ast_or_load_from_disk(definition)? {
+            out.extend(
+                module
+                    .port_declarations(name)
+                    .into_iter()
+                    .map(|r| (definition.clone(), r)),
+            );
+        }
+        for (uri, module) in self.parsed_modules() {
+            for (binding, path) in module.module_bindings() {
+                if self.resolves_to(&path, &uri, definition, workspace_root) {
+                    out.extend(
+                        module
+                            .port_references(&binding, name)
+                            .into_iter()
+                            .map(|r| (uri.clone(), r)),
+                    );
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub(crate) fn references_locations(
+        &self,
+        params: ReferenceParams,
+        initialize_params: &InitializeParams,
+    ) -> anyhow::Result<Vec<Location>> {
+        let uri = params.text_document_position.text_document.uri.try_into()?;
+        let position = params.text_document_position.position;
+        let workspace_root =
+            Self::get_workspace_root(initialize_params.workspace_folders.as_ref(), &uri);
+        let include_declaration = params.context.include_declaration;
+        self.find_references(
+            &uri,
+            position.line,
+            position.character,
+            workspace_root.as_deref(),
+        )?
+        .into_iter()
+        .filter(|(_, _, declaration)| include_declaration || !declaration)
+        .map(|(uri, range, _)| Ok(Location::new(Url::try_from(&uri)?, range)))
+        .collect()
+    }
+
+    pub(crate) fn rename_edits(
+        &self,
+        params: RenameParams,
+        initialize_params: &InitializeParams,
+    ) -> anyhow::Result<WorkspaceEdit> {
+        if !is_identifier(&params.new_name) {
+            return Err(anyhow::anyhow!(
+                "`{}` is not a valid identifier",
+                params.new_name
+            ));
+        }
+        let uri = params.text_document_position.text_document.uri.try_into()?;
+        let position = params.text_document_position.position;
+        let workspace_root =
+            Self::get_workspace_root(initialize_params.workspace_folders.as_ref(), &uri);
+        let references = self.find_references(
+            &uri,
+            position.line,
+            position.character,
+            workspace_root.as_deref(),
+        )?;
+        if references.is_empty() {
+            return Err(anyhow::anyhow!("No symbol to rename at this position"));
+        }
+
+        let mut changes: HashMap<Url, Vec<TextEdit>> = HashMap::new();
+        for (uri, range, _) in references {
+            changes
+                .entry(Url::try_from(&uri)?)
+                .or_default()
+                .push(TextEdit::new(range, params.new_name.clone()));
+        }
+        Ok(WorkspaceEdit::new(changes))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use lsp_types::request::References;
+    use lsp_types::request::Rename;
+    use lsp_types::Position;
+    use lsp_types::ReferenceContext;
+    use lsp_types::TextDocumentIdentifier;
+    use lsp_types::TextDocumentPositionParams;
+    use starlark::wasm::is_wasm;
+    use textwrap::dedent;
+
+    use super::*;
+    use crate::definition::helpers::FixtureWithRanges;
+    use crate::test::TestServer;
+
+    fn temp_file_uri(rel_path: &str) -> Url {
+        Url::from_file_path(std::env::temp_dir().join(rel_path)).unwrap()
+    }
+
+    fn load_string(uri: &Url) -> String {
+        uri.to_file_path()
+            .unwrap()
+            .to_string_lossy()
+            .replace('\\', "/")
+    }
+
+    fn position(uri: Url, fixture: &FixtureWithRanges, id: &str) -> TextDocumentPositionParams {
+        TextDocumentPositionParams {
+            text_document: TextDocumentIdentifier { uri },
+            position: Position::new(fixture.begin_line(id), fixture.begin_column(id)),
+        }
+    }
+
+    fn references(
+        server: &mut TestServer,
+        position: TextDocumentPositionParams,
+        include_declaration: bool,
+    ) -> anyhow::Result<Vec<Location>> {
+        let req = server.new_request::<References>(ReferenceParams {
+            text_document_position: position,
+            work_done_progress_params: Default::default(),
+            partial_result_params: Default::default(),
+            context: ReferenceContext {
+                include_declaration,
+            },
+        });
+        let id = server.send_request(req)?;
+        server.get_response::<Vec<Location>>(id)
+    }
+
+    fn location(uri: &Url, fixture: &FixtureWithRanges, id: &str) -> Location {
+        Location::new(uri.clone(), fixture.resolved_span(id).into())
+    }
+
+    #[test]
+    fn finds_local_references() -> anyhow::Result<()> {
+        if is_wasm() {
+            return Ok(());
+        }
+
+        let uri = temp_file_uri("locals.star");
+        let fixture = FixtureWithRanges::from_fixture(
+            uri.path(),
+            &dedent(
+                r#"
+                <x1>x</x1> = 1
+                def f(x):
+                    return x
+                <x2>x</x2> = <x3>x</x3> + 1
+                print(<x4>x</x4>.real)
+                "#,
+            ),
+        )?;
+
+        let mut server = TestServer::new()?;
+        server.open_file(uri.clone(), fixture.program())?;
+
+        let found = references(&mut server, position(uri.clone(), &fixture, "x3"), true)?;
+        let expected: Vec<Location> = ["x1", "x2", "x3", "x4"]
+            .iter()
+            .map(|id| location(&uri, &fixture, id))
+            .collect();
+        assert_eq!(expected, found);
+
+        let found = references(&mut server, position(uri.clone(), &fixture, "x3"), false)?;
+        assert_eq!(expected[1..], found[..]);
+        Ok(())
+    }
+
+    #[test]
+    fn renames_loaded_symbols_across_files() -> anyhow::Result<()> {
+        if is_wasm() {
+            return Ok(());
+        }
+
+        let lib_uri = temp_file_uri("rename_lib.star");
+        let user_uri = temp_file_uri("rename_user.star");
+        let alias_uri = temp_file_uri("rename_alias.star");
+        let lib = FixtureWithRanges::from_fixture(
+            lib_uri.path(),
+            "def <def>helper</def>():\n    pass\n",
+        )?;
+        let user = FixtureWithRanges::from_fixture(
+            user_uri.path(),
+            &format!(
+                "load(\"{}\", \"<load>helper</load>\")\n<call>helper</call>()\n",
+                load_string(&lib_uri)
+            ),
+        )?;
+        let alias = FixtureWithRanges::from_fixture(
+            alias_uri.path(),
+            &format!(
+                "load(\"{}\", h = \"<load>helper</load>\")\nh()\n",
+                load_string(&lib_uri)
+            ),
+        )?;
+
+        let mut server = TestServer::new()?;
+        server.open_file(lib_uri.clone(), lib.program())?;
+        server.open_file(user_uri.clone(), user.program())?;
+        server.open_file(alias_uri.clone(), alias.program())?;
+
+        let req = server.new_request::<Rename>(RenameParams {
+            text_document_position: position(user_uri.clone(), &user, "call"),
+            new_name: "assist".to_owned(),
+            work_done_progress_params: Default::default(),
+        });
+        let id = server.send_request(req)?;
+        let edit = server.get_response::<WorkspaceEdit>(id)?;
+
+        let edits = |fixture: &FixtureWithRanges, ids: &[&str]| -> Vec<TextEdit> {
+            ids.iter()
+                .map(|id| TextEdit::new(fixture.resolved_span(id).into(), "assist".to_owned()))
+                .collect()
+        };
+        let expected = HashMap::from([
+            (lib_uri.clone(), edits(&lib, &["def"])),
+            (user_uri.clone(), edits(&user, &["load", "call"])),
+            // The alias `h` keeps its name
+            (alias_uri.clone(), edits(&alias, &["load"])),
+        ]);
+        assert_eq!(Some(expected), edit.changes);
+        Ok(())
+    }
+
+    #[test]
+    fn finds_module_port_references() -> anyhow::Result<()> {
+        if is_wasm() {
+            return Ok(());
+        }
+
+        let child_uri = temp_file_uri("ports_child.zen");
+        let parent_uri = temp_file_uri("ports_parent.zen");
+        let child = FixtureWithRanges::from_fixture(
+            child_uri.path(),
+            "vin = io(\"<decl>VIN</decl>\", Net)\nr = config(\"r\", str)\n",
+        )?;
+        let parent = FixtureWithRanges::from_fixture(
+            parent_uri.path(),
+            &format!(
+                "Child = Module(\"{}\")\nChild(name = \"C1\", <use1>VIN</use1> = Net(\"A\"), r = \"1k\")\nChild(name = \"C2\", <use2>VIN</use2> = Net(\"B\"))\n",
+                load_string(&child_uri)
+            ),
+        )?;
+
+        let mut server = TestServer::new()?;
+        server.open_file(child_uri.clone(), child.program())?;
+        server.open_file(parent_uri.clone(), parent.program())?;
+
+        let expected = vec![
+            location(&child_uri, &child, "decl"),
+            location(&parent_uri, &parent, "use1"),
+            location(&parent_uri, &parent, "use2"),
+        ];
+        let mut found = references(
+            &mut server,
+            position(child_uri.clone(), &child, "decl"),
+            true,
+        )?;
+        found.sort_by_key(|l| (l.uri.to_string(), l.range.start));
+        assert_eq!(expected, found);
+
+        let mut found = references(
+            &mut server,
+            position(parent_uri.clone(), &parent, "use2"),
+            true,
+        )?;
+        found.sort_by_key(|l| (l.uri.to_string(), l.range.start));
+        assert_eq!(expected, found);
+        Ok(())
+    }
+
+    #[test]
+    fn validates_new_names() {
+        assert!(is_identifier("vout_2"));
+        assert!(is_identifier("_private"));
+        assert!(!is_identifier("2v"));
+        assert!(!is_identifier("v-out"));
+        assert!(!is_identifier("def"));
+        assert!(!is_identifier(""));
+    }
+}
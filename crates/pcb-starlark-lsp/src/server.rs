@@ -39,9 +39,18 @@ use lsp_types::notification::DidCloseTextDocument;
 use lsp_types::notification::DidOpenTextDocument;
 use lsp_types::notification::LogMessage;
 use lsp_types::notification::PublishDiagnostics;
+use lsp_types::request::CodeActionRequest;
 use lsp_types::request::Completion;
+use lsp_types::request::DocumentSymbolRequest;
+use lsp_types::request::Formatting;
 use lsp_types::request::GotoDefinition;
 use lsp_types::request::HoverRequest;
+use lsp_types::request::RangeFormatting;
+use lsp_types::request::References;
+use lsp_types::request::Rename;
+use lsp_types::CodeActionOrCommand;
+use lsp_types::CodeActionParams;
+use lsp_types::CodeActionProviderCapability;
 use lsp_types::CompletionItem;
 use lsp_types::CompletionItemKind;
 use lsp_types::CompletionOptions;
@@ -52,6 +61,10 @@ use lsp_types::Diagnostic;
 use lsp_types::DidChangeTextDocumentParams;
 use lsp_types::DidCloseTextDocumentParams;
 use lsp_types::DidOpenTextDocumentParams;
+use lsp_types::DocumentFormattingParams;
+use lsp_types::DocumentRangeFormattingParams;
+use lsp_types::DocumentSymbolParams;
+use lsp_types::DocumentSymbolResponse;
 use lsp_types::Documentation;
 use lsp_types::GotoDefinitionParams;
 use lsp_types::GotoDefinitionResponse;
@@ -70,6 +83,8 @@ use lsp_types::MessageType;
 use lsp_types::OneOf;
 use lsp_types::PublishDiagnosticsParams;
 use lsp_types::Range;
+use lsp_types::ReferenceParams;
+use lsp_types::RenameParams;
 use lsp_types::ServerCapabilities;
 use lsp_types::TextDocumentSyncCapability;
 use lsp_types::TextDocumentSyncKind;
@@ -448,6 +463,25 @@ pub trait LspContext {
     ) -> anyhow::Result<Option<Hover>> {
         Ok(None)
     }
+
+    /// Format the contents of a file, returning the formatted contents. Formatting requests
+    /// are only sent if [`capabilities`] advertises them.
+    ///
+    /// The default implementation returns `None`, leaving the file as it is.
+    fn format_file(&self, _uri: &LspUrl, _contents: &str) -> anyhow::Result<Option<String>> {
+        Ok(None)
+    }
+
+    /// Provide code actions for the range and diagnostics in `params`, in addition to the
+    /// quick fixes of the server itself (such as adding a missing `load()`).
+    fn code_actions(
+        &self,
+        _uri: &LspUrl,
+        _params: &CodeActionParams,
+        _workspace_root: Option<&Path>,
+    ) -> anyhow::Result<Vec<CodeActionOrCommand>> {
+        Ok(Vec::new())
+    }
 }
 
 /// Errors when [`LspContext::resolve_load()`] cannot resolve a given path.
@@ -489,6 +523,10 @@ impl<T: LspContext> Backend<T> {
             definition_provider,
             completion_provider: Some(CompletionOptions::default()),
             hover_provider: Some(HoverProviderCapability::Simple(true)),
+            references_provider: Some(OneOf::Left(true)),
+            rename_provider: Some(OneOf::Left(true)),
+            document_symbol_provider: Some(OneOf::Left(true)),
+            code_action_provider: Some(CodeActionProviderCapability::Simple(true)),
             workspace_symbol_provider: if context.is_eager() {
                 Some(OneOf::Left(true))
             } else {
@@ -603,6 +641,65 @@ impl<T: LspContext> Backend<T> {
         self.send_response(new_response(id, self.hover_info(params, initialize_params)));
     }
 
+    /// Finds references to the symbol at the current cursor across the workspace.
+    fn references(
+        &self,
+        id: RequestId,
+        params: ReferenceParams,
+        initialize_params: &InitializeParams,
+    ) {
+        self.send_response(new_response(
+            id,
+            self.references_locations(params, initialize_params),
+        ));
+    }
+
+    /// Renames the symbol at the current cursor, and its references across the workspace.
+    fn rename(&self, id: RequestId, params: RenameParams, initialize_params: &InitializeParams) {
+        self.send_response(new_response(
+            id,
+            self.rename_edits(params, initialize_params),
+        ));
+    }
+
+    /// Offers an outline of the top-level symbols in the current file.
+    fn document_symbols(&self, id: RequestId, params: DocumentSymbolParams) {
+        let response = LspUrl::try_from(params.text_document.uri)
+            .map_err(anyhow::Error::from)
+            .and_then(|uri| self.get_ast_or_load_from_disk(&uri))
+            .map(|module| {
+                DocumentSymbolResponse::Nested(
+                    module
+                        .map(|module| module.document_symbols())
+                        .unwrap_or_default(),
+                )
+            });
+        self.send_response(new_response(id, response));
+    }
+
+    /// Offers quick fixes for the diagnostics in the current range.
+    fn code_action(
+        &self,
+        id: RequestId,
+        params: CodeActionParams,
+        initialize_params: &InitializeParams,
+    ) {
+        self.send_response(new_response(
+            id,
+            self.code_actions(params, initialize_params),
+        ));
+    }
+
+    /// Formats the whole file.
+    fn formatting(&self, id: RequestId, params: DocumentFormattingParams) {
+        self.send_response(new_response(id, self.formatting_edits(params)));
+    }
+
+    /// Formats the lines of the file in the requested range.
+    fn range_formatting(&self, id: RequestId, params: DocumentRangeFormattingParams) {
+        self.send_response(new_response(id, self.range_formatting_edits(params)));
+    }
+
     /// Get the file contents of a starlark: URI.
     fn get_starlark_file_contents(&self, id: RequestId, params: StarlarkFileContentsParams) {
         let response: anyhow::Result<_> = match params.uri {
@@ -1154,7 +1251,7 @@ impl<T: LspContext> Backend<T> {
         })
     }
 
-    fn get_workspace_root(
+    pub(crate) fn get_workspace_root(
         workspace_roots: Option<&Vec<WorkspaceFolder>>,
         target: &LspUrl,
     ) -> Option<PathBuf> {
@@ -1366,8 +1463,6 @@ impl<T: LspContext> Backend<T> {
         for msg in &self.connection.receiver {
             match msg {
                 Message::Request(req) => {
-                    // TODO(nmj): Also implement DocumentSymbols so that some logic can
-                    //            be handled client side.
                     if let Some(params) = as_request::<GotoDefinition>(&req) {
                         self.goto_definition(req.id, params, &initialize_params);
                     } else if let Some(params) = as_request::<StarlarkFileContentsRequest>(&req) {
@@ -1376,6 +1471,18 @@ impl<T: LspContext> Backend<T> {
                         self.completion(req.id, params, &initialize_params);
                     } else if let Some(params) = as_request::<HoverRequest>(&req) {
                         self.hover(req.id, params, &initialize_params);
+                    } else if let Some(params) = as_request::<References>(&req) {
+                        self.references(req.id, params, &initialize_params);
+                    } else if let Some(params) = as_request::<Rename>(&req) {
+                        self.rename(req.id, params, &initialize_params);
+                    } else if let Some(params) = as_request::<DocumentSymbolRequest>(&req) {
+                        self.document_symbols(req.id, params);
+                    } else if let Some(params) = as_request::<CodeActionRequest>(&req) {
+                        self.code_action(req.id, params, &initialize_params);
+                    } else if let Some(params) = as_request::<Formatting>(&req) {
+                        self.formatting(req.id, params);
+                    } else if let Some(params) = as_request::<RangeFormatting>(&req) {
+                        self.range_formatting(req.id, params);
                     } else if self.connection.handle_shutdown(&req)? {
                         return Ok(());
                     } else if let Some(resp) =
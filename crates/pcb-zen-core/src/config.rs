@@ -31,6 +31,11 @@ pub const KICAD_ASSETS: &[(&str, &str, &str)] = &[
 /// Both V1 and V2 fields coexist in the same struct.
 #[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
 pub struct PcbToml {
+    /// SPDX license expression of the package, e.g. "MIT OR Apache-2.0" (V2 only)
+    /// Kept first so that it serializes before any table
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub license: Option<String>,
+
     /// Workspace configuration section
     #[serde(skip_serializing_if = "Option::is_none")]
     pub workspace: Option<WorkspaceConfig>,
@@ -66,6 +71,10 @@ pub struct PcbToml {
     /// Access control configuration section
     #[serde(skip_serializing_if = "Option::is_none")]
     pub access: Option<AccessConfig>,
+
+    /// License policy for the dependency closure (V2 only, workspace root)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub licenses: Option<LicensePolicy>,
 }
 
 impl PcbToml {
@@ -287,6 +296,12 @@ pub struct WorkspaceConfig {
     /// Example: ["modules/deprecated/*", "boards/test-*"]
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub exclude: Vec<String>,
+
+    /// Package index to fetch dependencies from instead of git (V2 only)
+    /// An http(s):// or file:// URL, or a path relative to the workspace root
+    /// Example: "https://mirror.example.com/pcb/index.json"
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub index: Option<String>,
 }
 
 impl Default for WorkspaceConfig {
@@ -301,6 +316,7 @@ impl Default for WorkspaceConfig {
             members: default_members(),
             vendor: Vec::new(),
             exclude: Vec::new(),
+            index: None,
         }
     }
 }
@@ -313,6 +329,26 @@ pub struct AccessConfig {
     pub allow: Vec<String>,
 }
 
+/// License policy checked by `pcb sbom` and `pcb release`
+///
+/// Patterns are SPDX license identifiers and may use `*` globs, e.g. "GPL-*".
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LicensePolicy {
+    /// Licenses allowed in the closure. When set, any other license is rejected,
+    /// and so are packages and assets that don't declare one.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub allow: Vec<String>,
+
+    /// Licenses rejected in the closure
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub deny: Vec<String>,
+
+    /// Licenses of packages and assets that don't declare one, keyed by URL pattern
+    /// Example: { "gitlab.com/kicad/libraries/*" = "CC-BY-SA-4.0" }
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub overrides: BTreeMap<String, String>,
+}
+
 /// Module configuration (V1 only)
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct ModuleConfig {
@@ -912,6 +948,36 @@ description = "A test board"
         assert_eq!(board.description, "A test board");
     }
 
+    #[test]
+    fn test_parse_license_and_policy() {
+        let content = r#"
+license = "MIT OR Apache-2.0"
+
+[workspace]
+pcb-version = "0.3"
+
+[licenses]
+deny = ["GPL-*", "AGPL-*"]
+overrides = { "gitlab.com/kicad/libraries/*" = "CC-BY-SA-4.0" }
+"#;
+
+        let config = PcbToml::parse(content).unwrap();
+        assert_eq!(config.license.as_deref(), Some("MIT OR Apache-2.0"));
+
+        let policy = config.licenses.as_ref().unwrap();
+        assert!(policy.allow.is_empty());
+        assert_eq!(policy.deny, vec!["GPL-*", "AGPL-*"]);
+        assert_eq!(
+            policy.overrides["gitlab.com/kicad/libraries/*"],
+            "CC-BY-SA-4.0"
+        );
+
+        // The license is written before any table
+        let written = toml::to_string(&config).unwrap();
+        assert!(written.starts_with("license = "), "{written}");
+        assert_eq!(PcbToml::parse(&written).unwrap(), config);
+    }
+
     #[test]
     fn test_parse_v1_module() {
         // [module] section requires V1
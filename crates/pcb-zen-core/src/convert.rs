@@ -1,5 +1,5 @@
 use crate::lang::interface::FrozenInterfaceValue;
-use crate::lang::module::{find_moved_span, ModulePath};
+use crate::lang::module::{find_moved_span, rule_length_mm, rule_net_ids, ModulePath};
 use crate::lang::r#enum::EnumValue;
 use crate::lang::symbol::SymbolValue;
 use crate::lang::type_info::TypeInfo;
@@ -9,8 +9,10 @@ use crate::{
     FrozenComponentValue, FrozenModuleValue, FrozenNetValue, FrozenSpiceModelValue, NetId,
 };
 use itertools::Itertools;
+use pcb_sch::design_rules::{DesignRule, RuleKind, RuleScope};
 use pcb_sch::physical::PhysicalValue;
 use pcb_sch::position::Position;
+use pcb_sch::variants::Variant;
 use pcb_sch::{AttributeValue, Instance, InstanceRef, ModuleRef, Net, NetKind, Schematic};
 use serde::{Deserialize, Serialize};
 use serde_json::{Map as JsonMap, Number as JsonNumber, Value as JsonValue};
@@ -195,6 +197,14 @@ impl ModuleConverter {
         // Propagate impedance from DiffPair interfaces to P/N nets (before creating Net objects)
         propagate_diffpair_impedance(&mut self.net_to_properties, &module_tree);
 
+        // Length-matching groups and pair skew limits declared by interfaces
+        let mut length_diagnostics = Diagnostics::default();
+        let interface_rules = collect_length_groups(
+            &mut self.net_to_properties,
+            &module_tree,
+            &mut length_diagnostics,
+        );
+
         // Create Net objects directly using the names recorded per-module.
         // Ensure global uniqueness and stable creation order by sorting names.
         let mut ids_and_names: Vec<(NetId, String)> = Vec::new();
@@ -288,7 +298,18 @@ impl ModuleConverter {
         self.schematic.assign_reference_designators();
 
         // Validate moved directives, collect warnings, and filter out problematic ones
-        let (diagnostics, mut filtered_moved_paths) = self.validate_and_filter_moved_directives();
+        let (mut diagnostics, mut filtered_moved_paths) =
+            self.validate_and_filter_moved_directives();
+
+        let (rule_diagnostics, design_rules) = self.resolve_design_rules(interface_rules);
+        diagnostics.extend(length_diagnostics.diagnostics);
+        diagnostics.extend(rule_diagnostics.diagnostics);
+        self.schematic.design_rules = design_rules;
+
+        let (variant_diagnostics, variants) = self.resolve_variants();
+        diagnostics.extend(variant_diagnostics.diagnostics);
+        self.schematic.variants = variants;
+        self.schematic.add_variant_fields();
 
         // Merge net name aliases (from nets appearing in multiple modules' introduced_nets)
         // These map the child's scoped name to the parent's canonical name.
@@ -615,6 +636,32 @@ impl ModuleConverter {
                     ),
                 );
 
+                // Only record the electrical type when every pad of the signal agrees on it
+                let mut pad_types = pads.iter().map(|p| symbol_value.pin_types().get(p));
+                if let Some(Some(pin_type)) = pad_types.next() {
+                    if pad_types.all(|t| t == Some(pin_type)) {
+                        pin_inst.add_attribute(
+                            crate::attrs::PIN_TYPE,
+                            AttributeValue::String(pin_type.clone()),
+                        );
+                    }
+                }
+
+                if let Some(rating) = component.pin_ratings().get(signal_name) {
+                    if let Some(abs_max) = &rating.abs_max {
+                        pin_inst.add_attribute(
+                            crate::attrs::ABS_MAX_VOLTAGE,
+                            AttributeValue::String(abs_max.clone()),
+                        );
+                    }
+                    if let Some(recommended) = &rating.recommended {
+                        pin_inst.add_attribute(
+                            crate::attrs::RECOMMENDED_VOLTAGE,
+                            AttributeValue::String(recommended.clone()),
+                        );
+                    }
+                }
+
                 self.schematic.add_instance(pin_inst_ref.clone(), pin_inst);
                 comp_inst.add_child(signal_name.clone(), pin_inst_ref.clone());
 
@@ -798,6 +845,104 @@ impl ModuleConverter {
 
         (diagnostics, filtered)
     }
+
+    /// Name the nets of design_rule() calls and interface length groups, which record nets by
+    /// net id, and scope design_rule() names to their module.
+    fn resolve_design_rules(
+        &self,
+        interface_rules: Vec<(DesignRule, String)>,
+    ) -> (Diagnostics, Vec<DesignRule>) {
+        let mut diagnostics = Diagnostics::default();
+        let mut rules = Vec::new();
+        let net_names: HashMap<NetId, &str> = self
+            .schematic
+            .nets
+            .iter()
+            .map(|(name, net)| (net.id, name.as_str()))
+            .collect();
+
+        let mut pending = Vec::new();
+        for (instance_ref, module) in &self.module_instances {
+            let module_path = instance_ref.instance_path.join(".");
+            for rule in module.design_rules() {
+                let mut rule = rule.clone();
+                rule.name = scoped_path(&module_path, &rule.name);
+                pending.push((rule, module.source_path().to_string()));
+            }
+        }
+        pending.extend(interface_rules);
+
+        for (rule, source) in pending {
+            let source = Path::new(&source);
+            let mut unnamed = 0;
+            let rule = rule.map_nets(|id| {
+                let name = id
+                    .parse::<NetId>()
+                    .ok()
+                    .and_then(|id| net_names.get(&id))
+                    .map(|name| name.to_string());
+                unnamed += usize::from(name.is_none());
+                name
+            });
+            if unnamed > 0 {
+                let body = format!(
+                    "Design rule '{}' refers to {unnamed} net(s) that are not part of the design",
+                    rule.name
+                );
+                diagnostics.push(Diagnostic::new(body, EvalSeverity::Warning, source));
+            }
+            // Dropping unknown nets can leave the rule without enough nets to apply to
+            match rule.validate() {
+                Ok(()) => rules.push(rule),
+                Err(e) => {
+                    diagnostics.push(Diagnostic::new(
+                        e.to_string(),
+                        EvalSeverity::Warning,
+                        source,
+                    ));
+                }
+            }
+        }
+        (diagnostics, rules)
+    }
+
+    /// Scope the paths of variant() overrides to their module and merge the declarations of each
+    /// variant across modules. Overrides that match no component are reported.
+    fn resolve_variants(&self) -> (Diagnostics, Vec<Variant>) {
+        let mut diagnostics = Diagnostics::default();
+        let mut variants: Vec<Variant> = Vec::new();
+        for (instance_ref, module) in &self.module_instances {
+            let module_path = instance_ref.instance_path.join(".");
+            for declared in module.variants() {
+                let mut scoped = Variant::new(declared.name.clone());
+                for (path, over) in &declared.overrides {
+                    scoped
+                        .overrides
+                        .insert(scoped_path(&module_path, path), over.clone());
+                }
+
+                for (path, matched) in self.schematic.variant_matches(&scoped) {
+                    if matched.is_empty() {
+                        let body = format!(
+                            "Variant '{}' overrides '{path}', which is not a component or a module with components",
+                            scoped.name
+                        );
+                        diagnostics.push(Diagnostic::new(
+                            body,
+                            EvalSeverity::Warning,
+                            Path::new(module.source_path()),
+                        ));
+                    }
+                }
+
+                match variants.iter_mut().find(|v| v.name == scoped.name) {
+                    Some(existing) => existing.extend(scoped),
+                    None => variants.push(scoped),
+                }
+            }
+        }
+        (diagnostics, variants)
+    }
 }
 
 /// Propagate impedance from DiffPair interfaces to P/N nets
@@ -852,6 +997,131 @@ fn propagate_from_value(
     }
 }
 
+/// Collect the length-matching groups (a `length_match` tolerance) and intra-pair skew limits
+/// (a `skew` on interfaces with P and N nets) of interfaces passed to modules. Records them as
+/// net properties and returns them as rules whose nets are net ids, with their source file.
+fn collect_length_groups(
+    net_props: &mut HashMap<NetId, HashMap<String, AttributeValue>>,
+    tree: &BTreeMap<ModulePath, FrozenModuleValue>,
+    diagnostics: &mut Diagnostics,
+) -> Vec<(DesignRule, String)> {
+    let mut rules = Vec::new();
+    // The same interface is usually passed down several levels; keep the outermost name
+    let mut seen = HashSet::new();
+    for (path, module) in tree {
+        let module_path = path.segments.join(".");
+        for param in module.signature().iter().filter(|p| !p.is_config) {
+            let Some(val) = param.actual_value else {
+                continue;
+            };
+            let mut found = Vec::new();
+            length_groups_from_value(
+                val.to_value(),
+                &scoped_path(&module_path, &param.name),
+                &mut found,
+            );
+            for result in found {
+                let (rule, properties) = match result {
+                    Ok(found) => found,
+                    Err(body) => {
+                        diagnostics.push(Diagnostic::new(
+                            body,
+                            EvalSeverity::Warning,
+                            Path::new(module.source_path()),
+                        ));
+                        continue;
+                    }
+                };
+                let key = (std::mem::discriminant(&rule.kind), rule.scope.clone());
+                if !seen.insert(key) {
+                    continue;
+                }
+                for id in rule_scope_ids(&rule) {
+                    let props = net_props.entry(id).or_default();
+                    for (key, value) in &properties {
+                        props.insert(key.clone(), value.clone());
+                    }
+                }
+                rules.push((rule, module.source_path().to_string()));
+            }
+        }
+    }
+    rules
+}
+
+/// A length rule found on an interface, with the properties to set on its nets
+type LengthGroup = (DesignRule, Vec<(String, AttributeValue)>);
+
+fn length_groups_from_value(
+    value: Value,
+    name: &str,
+    found: &mut Vec<Result<LengthGroup, String>>,
+) {
+    let Some(interface) = value.downcast_ref::<FrozenInterfaceValue>() else {
+        return;
+    };
+    let fields = interface.fields();
+    let field = |field: &str| fields.get(field).filter(|v| !v.is_none()).copied();
+
+    if let Some(tolerance) = field("length_match") {
+        let group = rule_net_ids(name, value).and_then(|ids| {
+            let mm = rule_length_mm(name, "length_match", tolerance.to_value())?;
+            let rule = DesignRule::new(
+                format!("{name}.length_match"),
+                RuleScope::Nets(ids),
+                RuleKind::LengthMatch { tolerance: mm },
+            )?;
+            let properties = vec![
+                (
+                    "length_match_group".to_string(),
+                    AttributeValue::String(name.to_string()),
+                ),
+                (
+                    "length_match_tolerance".to_string(),
+                    to_attribute_value(tolerance)?,
+                ),
+            ];
+            Ok((rule, properties))
+        });
+        found.push(group.map_err(|e| e.to_string()));
+    }
+
+    if let (Some(skew), Some(p), Some(n)) = (
+        field("skew"),
+        fields
+            .get("P")
+            .and_then(|v| v.downcast_ref::<FrozenNetValue>()),
+        fields
+            .get("N")
+            .and_then(|v| v.downcast_ref::<FrozenNetValue>()),
+    ) {
+        let pair = rule_length_mm(name, "skew", skew.to_value()).and_then(|mm| {
+            let rule = DesignRule::new(
+                format!("{name}.skew"),
+                RuleScope::Nets(vec![p.id().to_string(), n.id().to_string()]),
+                RuleKind::Skew { max: mm },
+            )?;
+            Ok((
+                rule,
+                vec![("max_skew".to_string(), to_attribute_value(skew)?)],
+            ))
+        });
+        found.push(pair.map_err(|e| e.to_string()));
+    }
+
+    for (field_name, field) in fields.iter() {
+        length_groups_from_value(field.to_value(), &format!("{name}.{field_name}"), found);
+    }
+}
+
+/// Net ids of a rule whose nets are recorded by id
+fn rule_scope_ids(rule: &DesignRule) -> Vec<NetId> {
+    match &rule.scope {
+        RuleScope::Nets(ids) => ids.iter().filter_map(|id| id.parse().ok()).collect(),
+        RuleScope::NetClass(_) => Vec::new(),
+    }
+}
+
 /// Helper to add a boolean attribute only if the value is true
 fn add_bool_attribute_if_true(instance: &mut Instance, attr_name: &str, value: bool) {
     if value {
@@ -0,0 +1,277 @@
+//! Electrical rules check (ERC) over a converted schematic.
+//!
+//! Pins carry the KiCad electrical type of their symbol pin (see [`crate::attrs::PIN_TYPE`]),
+//! which lets us catch the classic schematic mistakes KiCad's ERC flags: outputs fighting each
+//! other, inputs nothing drives, power inputs without a supply and no-connect pins that are
+//! connected anyway. Pins without a known type (inline symbols, pads of one signal that
+//! disagree) are treated as potential drivers so they never cause a report on their own.
+
+use std::collections::HashSet;
+use std::path::Path;
+
+use pcb_eda::PinElectricalType;
+use pcb_sch::{InstanceKind, InstanceRef, Net, NetKind, Schematic};
+use starlark::errors::EvalSeverity;
+
+use crate::lang::error::CategorizedDiagnostic;
+use crate::{Diagnostic, Diagnostics};
+
+/// Two or more outputs drive the same net
+pub const KIND_OUTPUT_CONFLICT: &str = "erc.output_conflict";
+/// An input pin is on a net with nothing driving it
+pub const KIND_UNDRIVEN_INPUT: &str = "erc.undriven_input";
+/// A power input pin is on a net that is neither a power rail nor driven by a power output
+pub const KIND_UNPOWERED_POWER_IN: &str = "erc.unpowered_power_in";
+/// A pin marked no-connect is connected to other pins
+pub const KIND_CONNECTED_NO_CONNECT: &str = "erc.connected_no_connect";
+
+/// A component pin on a net
+struct NetPin {
+    /// `REF.PIN` label used in messages
+    label: String,
+    /// Electrical type, if the symbol declared one
+    electrical_type: Option<PinElectricalType>,
+}
+
+/// Run the ERC over `schematic`.
+///
+/// `public_nets` are the nets exposed through the root module's `io()`s. They may be driven
+/// from outside the design, so they are exempt from the undriven checks. Diagnostics are
+/// reported against `path`, the root source file.
+pub fn check(schematic: &Schematic, public_nets: &HashSet<String>, path: &Path) -> Diagnostics {
+    let mut diagnostics = Diagnostics::default();
+
+    let mut nets: Vec<&Net> = schematic.nets.values().collect();
+    nets.sort_by(|a, b| a.name.cmp(&b.name));
+
+    for net in nets {
+        let pins = net_pins(schematic, net);
+        if pins.is_empty() {
+            continue;
+        }
+        let is_public = public_nets.contains(&net.name);
+        let mut report = |kind: &str, severity: EvalSeverity, message: String| {
+            diagnostics.push(erc_diagnostic(message, kind, severity, path));
+        };
+
+        let of_type = |types: &[PinElectricalType]| -> Vec<&str> {
+            pins.iter()
+                .filter(|pin| pin.electrical_type.is_some_and(|t| types.contains(&t)))
+                .map(|pin| pin.label.as_str())
+                .collect()
+        };
+
+        let outputs = of_type(&[PinElectricalType::Output, PinElectricalType::PowerOut]);
+        if outputs.len() > 1 {
+            report(
+                KIND_OUTPUT_CONFLICT,
+                EvalSeverity::Error,
+                format!(
+                    "Net '{}' is driven by multiple outputs: {}",
+                    net.name,
+                    outputs.join(", ")
+                ),
+            );
+        }
+
+        if pins.len() > 1 {
+            for label in of_type(&[PinElectricalType::NoConnect]) {
+                report(
+                    KIND_CONNECTED_NO_CONNECT,
+                    EvalSeverity::Warning,
+                    format!("No-connect pin {label} is connected to net '{}'", net.name),
+                );
+            }
+        }
+
+        if is_public {
+            continue;
+        }
+
+        let has_driver = pins.iter().any(|pin| {
+            !matches!(
+                pin.electrical_type,
+                Some(
+                    PinElectricalType::Input
+                        | PinElectricalType::PowerIn
+                        | PinElectricalType::NoConnect
+                )
+            )
+        });
+        if !has_driver {
+            for label in of_type(&[PinElectricalType::Input]) {
+                report(
+                    KIND_UNDRIVEN_INPUT,
+                    EvalSeverity::Warning,
+                    format!("Input pin {label} on net '{}' is not driven", net.name),
+                );
+            }
+        }
+
+        let is_rail = matches!(net.kind, NetKind::Power | NetKind::Ground);
+        if !is_rail && of_type(&[PinElectricalType::PowerOut]).is_empty() {
+            for label in of_type(&[PinElectricalType::PowerIn]) {
+                report(
+                    KIND_UNPOWERED_POWER_IN,
+                    EvalSeverity::Warning,
+                    format!(
+                        "Power input pin {label} on net '{}' is not powered; \
+                         declare the net as Power/Ground or connect a power output",
+                        net.name
+                    ),
+                );
+            }
+        }
+    }
+
+    diagnostics
+}
+
+/// Component pins on `net`, skipping pins of DNP components (they drive nothing)
+fn net_pins(schematic: &Schematic, net: &Net) -> Vec<NetPin> {
+    net.ports
+        .iter()
+        .filter_map(|port_ref| {
+            let port = schematic.instances.get(port_ref)?;
+            let (pin_name, component_path) = port_ref.instance_path.split_last()?;
+            let component_ref = InstanceRef::new(port_ref.module.clone(), component_path.to_vec());
+            let component = schematic.instances.get(&component_ref)?;
+            if component.kind != InstanceKind::Component || component.dnp() {
+                return None;
+            }
+
+            let owner = component
+                .reference_designator
+                .clone()
+                .unwrap_or_else(|| component_path.join("."));
+            let electrical_type = port
+                .string_attr(&[crate::attrs::PIN_TYPE])
+                .and_then(|t| t.parse().ok());
+            Some(NetPin {
+                label: format!("{owner}.{pin_name}"),
+                electrical_type,
+            })
+        })
+        .collect()
+}
+
+fn erc_diagnostic(message: String, kind: &str, severity: EvalSeverity, path: &Path) -> Diagnostic {
+    let categorized = CategorizedDiagnostic::new(message.clone(), kind.to_string())
+        .expect("ERC diagnostic kind should be valid");
+
+    Diagnostic::new(message, severity, path).with_source_error(Some(categorized))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pcb_sch::{AttributeValue, Instance, ModuleRef};
+
+    struct Builder {
+        schematic: Schematic,
+        module: ModuleRef,
+    }
+
+    impl Builder {
+        fn new() -> Self {
+            Self {
+                schematic: Schematic::new(),
+                module: ModuleRef::new("/board.zen", "<root>"),
+            }
+        }
+
+        /// Add a component with `(pin, type, net)` connections
+        fn component(&mut self, name: &str, pins: &[(&str, Option<&str>, &str)]) -> &mut Self {
+            let comp_ref = InstanceRef::new(self.module.clone(), vec![name.to_string()]);
+            let mut comp = Instance::component(self.module.clone());
+            comp.set_reference_designator(name);
+            for (pin, pin_type, net_name) in pins {
+                let pin_ref = comp_ref.append(pin.to_string());
+                let mut port = Instance::port(self.module.clone());
+                if let Some(pin_type) = pin_type {
+                    port.add_attribute(
+                        crate::attrs::PIN_TYPE,
+                        AttributeValue::String(pin_type.to_string()),
+                    );
+                }
+                self.schematic.add_instance(pin_ref.clone(), port);
+                comp.add_child(pin.to_string(), pin_ref.clone());
+
+                let id = self.schematic.nets.len() as u64;
+                self.schematic
+                    .nets
+                    .entry(net_name.to_string())
+                    .or_insert_with(|| Net::new(NetKind::Normal, *net_name, id))
+                    .add_port(pin_ref);
+            }
+            self.schematic.add_instance(comp_ref, comp);
+            self
+        }
+
+        fn kinds(&self, public_nets: &[&str]) -> Vec<String> {
+            let public_nets: HashSet<String> = public_nets.iter().map(|n| n.to_string()).collect();
+            check(&self.schematic, &public_nets, Path::new("/board.zen"))
+                .diagnostics
+                .iter()
+                .map(|d| {
+                    d.downcast_error_ref::<CategorizedDiagnostic>()
+                        .unwrap()
+                        .kind
+                        .clone()
+                })
+                .collect()
+        }
+    }
+
+    #[test]
+    fn test_output_conflict() {
+        let mut b = Builder::new();
+        b.component("U1", &[("OUT", Some("output"), "SIG")])
+            .component("U2", &[("OUT", Some("output"), "SIG")]);
+        assert_eq!(b.kinds(&[]), vec![KIND_OUTPUT_CONFLICT]);
+    }
+
+    #[test]
+    fn test_undriven_input() {
+        let mut b = Builder::new();
+        b.component("U1", &[("EN", Some("input"), "EN")])
+            .component("U2", &[("EN", Some("input"), "EN")]);
+        assert_eq!(b.kinds(&[]), vec![KIND_UNDRIVEN_INPUT, KIND_UNDRIVEN_INPUT]);
+
+        // Public nets may be driven from outside, and a passive pin (e.g. a pull-up) drives
+        assert!(b.kinds(&["EN"]).is_empty());
+        b.component("R1", &[("P1", Some("passive"), "EN")]);
+        assert!(b.kinds(&[]).is_empty());
+    }
+
+    #[test]
+    fn test_unpowered_power_in() {
+        let mut b = Builder::new();
+        b.component("U1", &[("VDD", Some("power_in"), "VDD")])
+            .component("C1", &[("P1", Some("passive"), "VDD")]);
+        assert_eq!(b.kinds(&[]), vec![KIND_UNPOWERED_POWER_IN]);
+
+        // Power rails count as powered
+        b.schematic.nets.get_mut("VDD").unwrap().kind = NetKind::Power;
+        assert!(b.kinds(&[]).is_empty());
+    }
+
+    #[test]
+    fn test_connected_no_connect() {
+        let mut b = Builder::new();
+        b.component("U1", &[("NC", Some("no_connect"), "NC")]);
+        assert!(b.kinds(&[]).is_empty());
+
+        b.component("U2", &[("IO", Some("bidirectional"), "NC")]);
+        assert_eq!(b.kinds(&[]), vec![KIND_CONNECTED_NO_CONNECT]);
+    }
+
+    #[test]
+    fn test_untyped_pins_are_ignored() {
+        let mut b = Builder::new();
+        b.component("U1", &[("OUT", None, "SIG")])
+            .component("U2", &[("OUT", None, "SIG")])
+            .component("U3", &[("IN", Some("input"), "SIG")]);
+        assert!(b.kinds(&[]).is_empty());
+    }
+}
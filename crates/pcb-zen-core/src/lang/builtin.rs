@@ -1,4 +1,5 @@
 use std::fmt;
+use std::path::Path;
 
 use allocative::Allocative;
 use pcb_sch::physical::*;
@@ -7,6 +8,7 @@ use starlark::{
     any::ProvidesStaticType,
     collections::SmallMap,
     environment::{GlobalsBuilder, Methods, MethodsBuilder, MethodsStatic},
+    errors::EvalSeverity,
     eval::Evaluator,
     starlark_module, starlark_simple_value,
     values::{
@@ -18,7 +20,10 @@ use starlark::{
     Error,
 };
 
-use crate::lang::{evaluator_ext::EvaluatorExt, net::*, stackup::BoardConfig};
+use crate::lang::{
+    error::CategorizedDiagnostic, evaluator_ext::EvaluatorExt, net::*, stackup::BoardConfig,
+};
+use crate::Diagnostic;
 
 #[derive(Clone, Copy, Debug, ProvidesStaticType, Freeze, Allocative, Serialize)]
 pub struct Builtin;
@@ -124,6 +129,16 @@ fn builtin_methods(methods: &mut MethodsBuilder) {
     fn MagneticFlux(this: &Builtin) -> starlark::Result<PhysicalValueType> {
         Ok(PhysicalValueType::new(pcb_sch::PhysicalUnit::Webers.into()))
     }
+    #[starlark(attribute)]
+    fn Length(this: &Builtin) -> starlark::Result<PhysicalValueType> {
+        Ok(PhysicalValueType::new(pcb_sch::PhysicalUnit::Meters.into()))
+    }
+    #[starlark(attribute)]
+    fn Area(this: &Builtin) -> starlark::Result<PhysicalValueType> {
+        Ok(PhysicalValueType::new(
+            pcb_sch::PhysicalUnit::SquareMeters.into(),
+        ))
+    }
 
     fn add_board_config<'v>(
         #[allow(unused_variables)] this: &Builtin,
@@ -169,10 +184,27 @@ fn builtin_methods(methods: &mut MethodsBuilder) {
         })?;
 
         // Parse and validate the board configuration (including stackup validation)
-        let _board_config = BoardConfig::from_json_str(&config_json).map_err(|e| {
+        let mut board_config = BoardConfig::from_json_str(&config_json).map_err(|e| {
             Error::new_other(anyhow::anyhow!("Board config validation failed: {}", e))
         })?;
 
+        // Report netclass geometry that misses its impedance target on the stackup
+        let call_site = eval.call_stack_top_location();
+        for warning in board_config.fill_impedance_geometry() {
+            let path = call_site
+                .as_ref()
+                .map(|cs| cs.filename().to_string())
+                .unwrap_or_default();
+            let categorized =
+                CategorizedDiagnostic::new(warning.clone(), "stackup.impedance".to_string())
+                    .expect("impedance diagnostic kind should be valid");
+            eval.add_diagnostic(
+                Diagnostic::new(warning, EvalSeverity::Warning, Path::new(&path))
+                    .with_span(call_site.as_ref().map(|cs| cs.resolve_span()))
+                    .with_source_error(Some(categorized)),
+            );
+        }
+
         // Parse and pretty-print the JSON
         let pretty_config_json = serde_json::from_str::<serde_json::Value>(&config_json)
             .and_then(|v| serde_json::to_string_pretty(&v))
@@ -1,7 +1,10 @@
 #![allow(clippy::needless_lifetimes)]
 
 use allocative::Allocative;
-use pcb_sch::physical::PhysicalValue;
+use once_cell::sync::Lazy;
+use pcb_eda::kicad::footprint::KicadFootprint;
+use pcb_sch::physical::{PhysicalRange, PhysicalUnitDims, PhysicalValue};
+use pcb_sch::PhysicalUnit;
 use starlark::{
     any::ProvidesStaticType,
     collections::SmallMap,
@@ -15,6 +18,11 @@ use starlark::{
     },
 };
 use std::cell::RefCell;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
 
 use crate::{
     lang::{evaluator_ext::EvaluatorExt, spice_model::SpiceModelValue},
@@ -57,6 +65,35 @@ pub enum ComponentError {
     PinNotInPinDefs { pin_name: String },
     #[error("pin '{pin_name}' defined in `pin_defs` but not connected")]
     PinDefinedButNotConnected { pin_name: String },
+    #[error(
+        "`pin_ratings` must be a dict mapping pin names to dicts with `abs_max`/`recommended`"
+    )]
+    PinRatingsNotDict,
+    #[error("`pin_ratings` refers to unknown pin '{pin_name}'")]
+    PinRatingUnknownPin { pin_name: String },
+    #[error(
+        "unknown rating '{field}' for pin '{pin_name}' in `pin_ratings` (expected `abs_max` or `recommended`)"
+    )]
+    UnknownPinRatingField { pin_name: String, field: String },
+    #[error(
+        "invalid `{field}` rating for pin '{pin_name}': expected a voltage range, got {value}"
+    )]
+    InvalidPinRating {
+        pin_name: String,
+        field: String,
+        value: String,
+    },
+    #[error(
+        "footprint '{footprint}' has no pad(s) {pads} used by the symbol ({footprint_pads} footprint pads, {symbol_pads} symbol pads)"
+    )]
+    FootprintPadsMissing {
+        footprint: String,
+        pads: String,
+        footprint_pads: usize,
+        symbol_pads: usize,
+    },
+    #[error("failed to load footprint '{path}': {message}")]
+    FootprintLoadFailed { path: String, message: String },
 }
 
 impl From<ComponentError> for starlark::Error {
@@ -65,6 +102,18 @@ impl From<ComponentError> for starlark::Error {
     }
 }
 
+/// Voltage ratings declared for a component pin via `pin_ratings`.
+///
+/// Ranges are kept in their `PhysicalRange` string form, which is also how they are
+/// stored on the schematic.
+#[derive(Clone, Debug, Default, PartialEq, Trace, Allocative)]
+pub struct PinVoltageRating {
+    /// Absolute maximum rating; exceeding it can damage the part
+    pub abs_max: Option<String>,
+    /// Recommended operating range
+    pub recommended: Option<String>,
+}
+
 // Mutable data stored in ComponentValue (wrapped in RefCell)
 #[derive(Clone, Debug, Trace, ProvidesStaticType, Allocative)]
 pub struct ComponentData<'v> {
@@ -104,6 +153,7 @@ pub struct ComponentGen<V, T> {
     spice_model: Option<V>,
     datasheet: Option<String>,
     description: Option<String>,
+    pin_ratings: SmallMap<String, PinVoltageRating>,
 }
 
 // Type aliases for mutable and frozen versions
@@ -150,6 +200,7 @@ impl<'v> Freeze for ComponentValue<'v> {
             },
             datasheet: self.datasheet,
             description: self.description,
+            pin_ratings: self.pin_ratings,
         })
     }
 }
@@ -251,6 +302,171 @@ impl std::fmt::Debug for FrozenComponentValue {
     }
 }
 
+/// Parse `pin_ratings = {"VDD": {"abs_max": ..., "recommended": ...}}`.
+///
+/// Ratings may be `VoltageRange` values or strings such as `"-0.3V to 4V"`.
+fn parse_pin_ratings<'v>(
+    value: Value<'v>,
+    symbol: &SymbolValue,
+) -> Result<SmallMap<String, PinVoltageRating>, ComponentError> {
+    let dict = DictRef::from_value(value).ok_or(ComponentError::PinRatingsNotDict)?;
+    let mut ratings = SmallMap::new();
+    for (pin_val, rating_val) in dict.iter() {
+        let pin_name = pin_val
+            .unpack_str()
+            .ok_or(ComponentError::PinNamesNotStrings)?
+            .to_owned();
+        if !symbol.signal_names().any(|n| n == pin_name) {
+            return Err(ComponentError::PinRatingUnknownPin { pin_name });
+        }
+        let rating_dict =
+            DictRef::from_value(rating_val).ok_or(ComponentError::PinRatingsNotDict)?;
+
+        let mut rating = PinVoltageRating::default();
+        for (field_val, range_val) in rating_dict.iter() {
+            let field = field_val
+                .unpack_str()
+                .map(str::to_owned)
+                .unwrap_or_else(|| field_val.to_string());
+            let slot = match field.as_str() {
+                "abs_max" => &mut rating.abs_max,
+                "recommended" => &mut rating.recommended,
+                _ => {
+                    return Err(ComponentError::UnknownPinRatingField {
+                        pin_name,
+                        field: field.clone(),
+                    })
+                }
+            };
+            *slot = Some(voltage_range_string(range_val).ok_or_else(|| {
+                ComponentError::InvalidPinRating {
+                    pin_name: pin_name.clone(),
+                    field: field.clone(),
+                    value: range_val.to_string(),
+                }
+            })?);
+        }
+        ratings.insert(pin_name, rating);
+    }
+    Ok(ratings)
+}
+
+/// Normalize a voltage range value (or string) to its `PhysicalRange` string form
+fn voltage_range_string(value: Value<'_>) -> Option<String> {
+    let range = match value.downcast_ref::<PhysicalRange>() {
+        Some(range) => range.clone(),
+        None => value.unpack_str()?.parse::<PhysicalRange>().ok()?,
+    };
+    let volts: PhysicalUnitDims = PhysicalUnit::Volts.into();
+    (range.unit() == volts).then(|| range.to_string())
+}
+
+/// Global cache for parsed `.kicad_mod` footprints by path, with the hash of the contents each
+/// was parsed from so that edited files are parsed again
+static FOOTPRINT_CACHE: Lazy<Mutex<HashMap<String, (u64, Arc<KicadFootprint>)>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// Load a `.kicad_mod` footprint with caching, or `None` if the file does not exist
+fn load_footprint(
+    path: &Path,
+    file_provider: &dyn crate::FileProvider,
+) -> Result<Option<Arc<KicadFootprint>>, ComponentError> {
+    if !file_provider.exists(path) {
+        return Ok(None);
+    }
+    let load_failed = |message: String| ComponentError::FootprintLoadFailed {
+        path: path.display().to_string(),
+        message,
+    };
+    let contents = file_provider
+        .read_file(path)
+        .map_err(|e| load_failed(e.to_string()))?;
+    let hash = {
+        let mut hasher = DefaultHasher::new();
+        contents.hash(&mut hasher);
+        hasher.finish()
+    };
+
+    let cache_key = file_provider
+        .canonicalize(path)
+        .unwrap_or_else(|_| path.to_path_buf())
+        .to_string_lossy()
+        .into_owned();
+    if let Some((cached_hash, footprint)) = FOOTPRINT_CACHE.lock().unwrap().get(&cache_key) {
+        if *cached_hash == hash {
+            return Ok(Some(footprint.clone()));
+        }
+    }
+
+    let footprint =
+        Arc::new(KicadFootprint::from_string(&contents).map_err(|e| load_failed(e.to_string()))?);
+    FOOTPRINT_CACHE
+        .lock()
+        .unwrap()
+        .insert(cache_key, (hash, footprint.clone()));
+    Ok(Some(footprint))
+}
+
+/// Check the symbol's pads against the pads of its `.kicad_mod` footprint.
+///
+/// Symbol pads missing from the footprint are an error. Footprint pads no pin is mapped to are a
+/// warning, since parts may leave pads such as shield tabs unconnected; mechanical pads are ignored.
+fn check_footprint_pads(
+    eval_ctx: &Evaluator<'_, '_, '_>,
+    footprint_path: &str,
+    symbol: &SymbolValue,
+) -> Result<(), ComponentError> {
+    let Some(file_provider) = eval_ctx.eval_context().map(|ctx| ctx.file_provider()) else {
+        return Ok(());
+    };
+    let Some(footprint) = load_footprint(Path::new(footprint_path), file_provider)? else {
+        return Ok(());
+    };
+
+    let mismatch = footprint.check_pads(symbol.pad_to_signal.keys().map(String::as_str));
+    if !mismatch.missing.is_empty() {
+        return Err(ComponentError::FootprintPadsMissing {
+            footprint: footprint.name.clone(),
+            pads: mismatch.missing.join(", "),
+            footprint_pads: mismatch.footprint_pads,
+            symbol_pads: mismatch.symbol_pads,
+        });
+    }
+
+    if !mismatch.unmapped.is_empty() {
+        if let Some(call_site) = eval_ctx.call_stack_top_location() {
+            use crate::lang::error::CategorizedDiagnostic;
+            use crate::Diagnostic;
+            use starlark::errors::EvalSeverity;
+
+            let body = format!(
+                "Footprint '{}' pad(s) {} are not mapped to any symbol pin ({} footprint pads, {} symbol pads)",
+                footprint.name,
+                mismatch.unmapped.join(", "),
+                mismatch.footprint_pads,
+                mismatch.symbol_pads
+            );
+            let kind = "footprint.unmapped_pads";
+
+            let source_error = CategorizedDiagnostic::new(body.clone(), kind.to_string())
+                .ok()
+                .map(|c| Arc::new(anyhow::Error::new(c)));
+
+            eval_ctx.add_diagnostic(Diagnostic {
+                path: call_site.filename().to_string(),
+                span: Some(call_site.resolve_span()),
+                severity: EvalSeverity::Warning,
+                body,
+                call_stack: None,
+                child: None,
+                source_error,
+                suppressed: false,
+            });
+        }
+    }
+    Ok(())
+}
+
 fn capitalize_first(s: &str) -> String {
     let mut c = s.chars();
     match c.next() {
@@ -693,6 +909,10 @@ impl<'v> ComponentValue<'v> {
     pub fn spice_model(&self) -> Option<&Value<'v>> {
         self.spice_model.as_ref()
     }
+
+    pub fn pin_ratings(&self) -> &SmallMap<String, PinVoltageRating> {
+        &self.pin_ratings
+    }
 }
 
 // Accessor methods for FrozenComponentValue
@@ -763,6 +983,10 @@ impl FrozenComponentValue {
     pub fn spice_model(&self) -> Option<&FrozenValue> {
         self.spice_model.as_ref()
     }
+
+    pub fn pin_ratings(&self) -> &SmallMap<String, PinVoltageRating> {
+        &self.pin_ratings
+    }
 }
 
 /// ComponentFactory is a value that represents a factory for a component.
@@ -808,6 +1032,7 @@ where
                 ("skip_pos", ParametersSpecParam::<Value<'_>>::Optional),
                 ("datasheet", ParametersSpecParam::<Value<'_>>::Optional),
                 ("description", ParametersSpecParam::<Value<'_>>::Optional),
+                ("pin_ratings", ParametersSpecParam::<Value<'_>>::Optional),
             ],
         );
 
@@ -861,6 +1086,7 @@ where
             let skip_pos_val: Option<Value> = param_parser.next_opt()?;
             let datasheet_val: Option<Value> = param_parser.next_opt()?;
             let description_val: Option<Value> = param_parser.next_opt()?;
+            let pin_ratings_val: Option<Value> = param_parser.next_opt()?;
 
             // Get a SymbolValue from the pin_defs or symbol_val
             let final_symbol: SymbolValue = if let Some(pin_defs) = pin_defs_val {
@@ -902,6 +1128,7 @@ where
                             source_path: symbol_value.source_path.clone(),
                             raw_sexp: symbol_value.raw_sexp.clone(),
                             properties: symbol_value.properties.clone(),
+                            pin_types: symbol_value.pin_types.clone(),
                         }
                     } else {
                         // symbol is not a Symbol type, just use pin_defs
@@ -911,6 +1138,7 @@ where
                             source_path: None,
                             raw_sexp: None,
                             properties: SmallMap::new(),
+                            pin_types: SmallMap::new(),
                         }
                     }
                 } else {
@@ -921,6 +1149,7 @@ where
                         source_path: None,
                         raw_sexp: None,
                         properties: SmallMap::new(),
+                        pin_types: SmallMap::new(),
                     }
                 }
             } else if let Some(symbol) = &symbol_val {
@@ -987,6 +1216,16 @@ where
                 ))));
             }
 
+            // Catch pad mismatches with a local footprint before layout does
+            if footprint.ends_with(".kicad_mod") {
+                check_footprint_pads(eval_ctx, &footprint, &final_symbol)?;
+            }
+
+            let pin_ratings = match pin_ratings_val {
+                Some(v) if !v.is_none() => parse_pin_ratings(v, &final_symbol)?,
+                _ => SmallMap::new(),
+            };
+
             // Properties map
             let mut properties_map: SmallMap<String, Value<'v>> = SmallMap::new();
             if !properties_val.is_none() {
@@ -1232,6 +1471,7 @@ where
                 spice_model: spice_model_val,
                 datasheet: final_datasheet,
                 description: final_description,
+                pin_ratings,
             });
 
             Ok(component)
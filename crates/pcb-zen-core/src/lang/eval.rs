@@ -45,6 +45,7 @@ use super::{
     spice_model::model_globals,
     symbol::SymbolType,
     test_bench::test_bench_globals,
+    tolerance::tolerance_globals,
 };
 
 /// A PrintHandler that collects all print output into a vector
@@ -95,6 +96,26 @@ impl EvalOutput {
         converter.build(self.module_tree.clone())
     }
 
+    /// Run the built-in electrical checks (ERC and voltage-domain compatibility) over a
+    /// schematic converted from this output.
+    ///
+    /// Nets exposed through the root module's `io()`s are driven from outside, so they are
+    /// exempt from the undriven checks.
+    pub fn run_electrical_checks(&self, schematic: &pcb_sch::Schematic) -> crate::Diagnostics {
+        let mut public_nets = HashSet::new();
+        for param in self.sch_module.signature().iter() {
+            if let (false, Some(value)) = (param.is_config, &param.actual_value) {
+                public_nets.extend(FrozenModuleValue::extract_nets_from_value(value.to_value()));
+            }
+        }
+        let path = Path::new(self.sch_module.source_path());
+        let mut diagnostics = crate::erc::check(schematic, &public_nets, path);
+        diagnostics
+            .diagnostics
+            .extend(crate::voltage_domains::check(schematic, path).diagnostics);
+        diagnostics
+    }
+
     /// Convert to schematic (error if conversion fails)
     pub fn to_schematic(&self) -> anyhow::Result<pcb_sch::Schematic> {
         let result = self.to_schematic_with_diagnostics();
@@ -453,6 +474,7 @@ impl EvalContext {
         .with(file_globals)
         .with(model_globals)
         .with(test_bench_globals)
+        .with(tolerance_globals)
         .build()
     }
 
@@ -1236,6 +1258,18 @@ impl EvalContext {
                 heap.alloc_simple(PhysicalValueType::new(PhysicalUnit::Webers.into())),
             );
         }
+        if self.module.get("Length").is_some() {
+            self.module.set(
+                "Length",
+                heap.alloc_simple(PhysicalValueType::new(PhysicalUnit::Meters.into())),
+            );
+        }
+        if self.module.get("Area").is_some() {
+            self.module.set(
+                "Area",
+                heap.alloc_simple(PhysicalValueType::new(PhysicalUnit::SquareMeters.into())),
+            );
+        }
     }
 
     fn hijack_interfaces(&mut self) {
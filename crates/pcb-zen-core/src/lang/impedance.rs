@@ -0,0 +1,489 @@
+//! Controlled-impedance trace geometry derived from a stackup
+//!
+//! Uses the closed-form IPC-2141 microstrip and stripline approximations, with the usual
+//! edge-coupled corrections for differential pairs. They are good to within a few percent for
+//! typical FR-4 geometries; the fabricator's field solver remains the reference.
+
+use super::stackup::{copper_layer_mapping, BoardConfig, CopperRole, Layer, NetClass, Stackup};
+
+/// Relative deviation from the target impedance beyond which specified geometry is reported.
+/// Generous, as the approximations ignore solder mask and coplanar ground, which both lower
+/// the impedance of outer-layer tracks.
+pub const IMPEDANCE_TOLERANCE: f64 = 0.2;
+
+/// Derived widths and gaps are rounded to 0.1 µm
+const STEPS_PER_MM: f64 = 1e4;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TraceModel {
+    /// Outer layer over a single reference plane
+    Microstrip,
+    /// Inner layer between two reference planes
+    Stripline,
+}
+
+/// A signal layer of the stackup with the dimensions that set its impedance (mm)
+#[derive(Debug, Clone, PartialEq)]
+pub struct TraceLayer {
+    /// KiCad layer name (`F.Cu`, `In1.Cu`, ...)
+    pub name: String,
+    pub model: TraceModel,
+    /// Copper thickness
+    pub thickness: f64,
+    /// Dielectric height to the reference plane (the plane above, for stripline)
+    pub height: f64,
+    /// Dielectric height to the plane below (stripline only)
+    pub height_below: f64,
+    /// Relative permittivity of the surrounding dielectric
+    pub permittivity: f64,
+}
+
+impl TraceLayer {
+    /// Characteristic impedance (Ω) of a single track of `width`
+    pub fn single_ended(&self, width: f64) -> f64 {
+        let (t, er) = (self.thickness, self.permittivity);
+        match self.model {
+            TraceModel::Microstrip => {
+                87.0 / (er + 1.41).sqrt() * (5.98 * self.height / (0.8 * width + t)).ln()
+            }
+            TraceModel::Stripline => {
+                // Off-centre tracks: harmonic mean of the symmetric striplines to each plane
+                let symmetric =
+                    |h: f64| 60.0 / er.sqrt() * (1.9 * (2.0 * h + t) / (0.8 * width + t)).ln();
+                let (above, below) = (symmetric(self.height), symmetric(self.height_below));
+                if above.min(below) <= 0.0 {
+                    return 0.0;
+                }
+                2.0 * above * below / (above + below)
+            }
+        }
+    }
+
+    /// Widest track the approximations hold for: past it the impedance would go negative
+    fn max_width(&self) -> f64 {
+        let height = match self.model {
+            TraceModel::Microstrip => 5.98 * self.height,
+            TraceModel::Stripline => {
+                1.9 * (2.0 * self.height.min(self.height_below) + self.thickness)
+            }
+        };
+        (height - self.thickness) / 0.8
+    }
+
+    /// Differential impedance (Ω) of an edge-coupled pair of `width` tracks `gap` apart
+    pub fn differential(&self, width: f64, gap: f64) -> f64 {
+        let z0 = self.single_ended(width);
+        match self.model {
+            TraceModel::Microstrip => 2.0 * z0 * (1.0 - 0.48 * (-0.96 * gap / self.height).exp()),
+            TraceModel::Stripline => {
+                let spacing = self.height + self.height_below + self.thickness;
+                2.0 * z0 * (1.0 - 0.347 * (-2.9 * gap / spacing).exp())
+            }
+        }
+    }
+}
+
+/// Geometry of a netclass on one layer, as derived from its impedance targets (mm)
+#[derive(Debug, Clone, PartialEq)]
+pub struct LayerGeometry {
+    pub netclass: String,
+    pub layer: String,
+    pub track_width: f64,
+    pub diff_pair_gap: Option<f64>,
+}
+
+/// Solve `f(x) = target` for `x` in [1 µm, `max`], `f` being monotonic over that range
+fn solve(target: f64, max: f64, f: impl Fn(f64) -> f64) -> Option<f64> {
+    let (mut lo, mut hi) = (1e-3, max);
+    let increasing = f(hi) > f(lo);
+    let (f_lo, f_hi) = if increasing {
+        (f(lo), f(hi))
+    } else {
+        (f(hi), f(lo))
+    };
+    if !(f_lo..=f_hi).contains(&target) {
+        return None;
+    }
+    for _ in 0..100 {
+        let mid = (lo + hi) / 2.0;
+        if (f(mid) < target) == increasing {
+            lo = mid;
+        } else {
+            hi = mid;
+        }
+    }
+    Some(((lo + hi) / 2.0 * STEPS_PER_MM).round() / STEPS_PER_MM)
+}
+
+/// Widest gap considered between the tracks of a pair (mm)
+const MAX_GAP: f64 = 10.0;
+
+impl Stackup {
+    /// Signal and mixed copper layers with their reference geometry, top to bottom. Plane
+    /// layers are skipped, and the neighbouring copper layers are taken as reference planes.
+    /// Layers next to a dielectric without a known permittivity are skipped.
+    pub fn trace_layers(&self) -> Vec<TraceLayer> {
+        let Some(layers) = self.layers.as_deref() else {
+            return Vec::new();
+        };
+        let permittivity = |layer: Option<&Layer>| match layer? {
+            Layer::Dielectric {
+                thickness,
+                material,
+                ..
+            } => {
+                let er = self
+                    .materials
+                    .iter()
+                    .flatten()
+                    .find(|m| m.name.as_deref() == Some(material.as_str()))?
+                    .relative_permittivity?;
+                Some((*thickness, er))
+            }
+            Layer::Copper { .. } => None,
+        };
+
+        let total_copper = self.copper_layer_count();
+        let mut trace_layers = Vec::new();
+        let mut copper_index = 0;
+        for (i, layer) in layers.iter().enumerate() {
+            let Layer::Copper { thickness, role } = layer else {
+                continue;
+            };
+            let name = copper_layer_mapping(copper_index, total_copper).1;
+            copper_index += 1;
+            if matches!(role, CopperRole::Ground | CopperRole::Power) {
+                continue;
+            }
+
+            let above = i.checked_sub(1).and_then(|j| permittivity(layers.get(j)));
+            let below = permittivity(layers.get(i + 1));
+            let trace_layer = match (above, below) {
+                (Some((h1, er1)), Some((h2, er2))) => TraceLayer {
+                    name: name.into_owned(),
+                    model: TraceModel::Stripline,
+                    thickness: *thickness,
+                    height: h1,
+                    height_below: h2,
+                    permittivity: (h1 * er1 + h2 * er2) / (h1 + h2),
+                },
+                (Some((h, er)), None) | (None, Some((h, er)))
+                    if i == 0 || i == layers.len() - 1 =>
+                {
+                    TraceLayer {
+                        name: name.into_owned(),
+                        model: TraceModel::Microstrip,
+                        thickness: *thickness,
+                        height: h,
+                        height_below: 0.0,
+                        permittivity: er,
+                    }
+                }
+                _ => continue,
+            };
+            trace_layers.push(trace_layer);
+        }
+        trace_layers
+    }
+}
+
+/// Impedance targets of a netclass: (single-ended, differential) in Ω
+fn targets(netclass: &NetClass) -> (Option<f64>, Option<f64>) {
+    (
+        netclass.single_ended_impedance_ohms(),
+        netclass.differential_pair_impedance_ohms(),
+    )
+}
+
+fn check(
+    warnings: &mut Vec<String>,
+    netclass: &str,
+    what: String,
+    layer: &str,
+    z: f64,
+    target: f64,
+) {
+    if ((z - target) / target).abs() > IMPEDANCE_TOLERANCE {
+        warnings.push(format!(
+            "Netclass '{netclass}': {what} gives {z:.1} Ω on {layer}, target is {target} Ω"
+        ));
+    }
+}
+
+impl BoardConfig {
+    /// Fill in the track width and differential pair geometry of netclasses with impedance
+    /// targets, as derived for the top signal layer of the stackup, and derive the geometry
+    /// the other signal layers need (see [`BoardConfig::layer_geometry`]). Geometry the
+    /// netclass specifies is kept on every layer. Returns warnings for specified geometry more
+    /// than [`IMPEDANCE_TOLERANCE`] off its target on a layer, and for targets no geometry
+    /// reaches.
+    pub fn fill_impedance_geometry(&mut self) -> Vec<String> {
+        let layers = self
+            .stackup
+            .as_ref()
+            .map(Stackup::trace_layers)
+            .unwrap_or_default();
+        let Some(design_rules) = self.design_rules.as_mut() else {
+            return Vec::new();
+        };
+
+        let mut warnings = Vec::new();
+        let mut layer_geometry = Vec::new();
+        for netclass in &mut design_rules.netclasses {
+            let name = netclass.name.clone();
+            let (single_ended, differential) = targets(netclass);
+            // Only geometry the netclass leaves unspecified is derived, on any layer
+            let track_width = netclass.track_width;
+            let diff_pair_width = netclass.diff_pair_width;
+
+            for (i, layer) in layers.iter().enumerate() {
+                let is_top = i == 0;
+                let mut derived_width = None;
+                let mut derived_pair = None;
+
+                if let Some(target) = single_ended {
+                    match track_width {
+                        Some(width) => check(
+                            &mut warnings,
+                            &name,
+                            format!("track width {width} mm"),
+                            &layer.name,
+                            layer.single_ended(width),
+                            target,
+                        ),
+                        None => match solve(target, layer.max_width(), |w| layer.single_ended(w)) {
+                            Some(width) if is_top => netclass.track_width = Some(width),
+                            Some(width) => derived_width = Some(width),
+                            None => warnings.push(format!(
+                                "Netclass '{name}': no track width gives {target} Ω on {}",
+                                layer.name
+                            )),
+                        },
+                    }
+                }
+
+                if let Some(target) = differential {
+                    // The gap solved for the top layer is kept on the others
+                    let solved = match (diff_pair_width, netclass.diff_pair_gap) {
+                        (Some(width), Some(gap)) => {
+                            check(
+                                &mut warnings,
+                                &name,
+                                format!("differential pair {width} mm wide, {gap} mm apart"),
+                                &layer.name,
+                                layer.differential(width, gap),
+                                target,
+                            );
+                            Some((width, gap))
+                        }
+                        (None, Some(gap)) => {
+                            solve(target, layer.max_width(), |w| layer.differential(w, gap))
+                                .map(|w| (w, gap))
+                        }
+                        (Some(width), None) => {
+                            solve(target, MAX_GAP, |s| layer.differential(width, s))
+                                .map(|s| (width, s))
+                        }
+                        // Without a given gap, space the tracks one width apart
+                        (None, None) => {
+                            solve(target, layer.max_width(), |w| layer.differential(w, w))
+                                .map(|w| (w, w))
+                        }
+                    };
+                    match solved {
+                        Some((width, gap)) if is_top => {
+                            netclass.diff_pair_width = Some(width);
+                            netclass.diff_pair_gap = Some(gap);
+                            if single_ended.is_none() && netclass.track_width.is_none() {
+                                netclass.track_width = Some(width);
+                            }
+                        }
+                        Some((width, gap)) if diff_pair_width.is_none() => {
+                            derived_pair = Some((width, gap));
+                        }
+                        Some(_) => {}
+                        None => warnings.push(format!(
+                            "Netclass '{name}': no differential pair geometry gives {target} Ω on {}",
+                            layer.name
+                        )),
+                    }
+                }
+
+                // The per-layer rule sets the track width, so a netclass that gives its own
+                // keeps it everywhere
+                let entry = match (derived_pair, derived_width) {
+                    _ if track_width.is_some() => None,
+                    (Some((width, gap)), _) => Some((width, Some(gap))),
+                    (None, Some(width)) => Some((width, None)),
+                    (None, None) => None,
+                };
+                if let Some((track_width, diff_pair_gap)) = entry {
+                    layer_geometry.push(LayerGeometry {
+                        netclass: name.clone(),
+                        layer: layer.name.clone(),
+                        track_width,
+                        diff_pair_gap,
+                    });
+                }
+            }
+        }
+        self.layer_geometry = layer_geometry;
+        warnings
+    }
+
+    /// Track widths (and differential pair gaps) reaching each netclass's impedance targets on
+    /// the signal layers below the top one, whose geometry the netclass itself carries. Only
+    /// netclasses that leave their track width to be derived get them, with the differential
+    /// pair gap kept and the width solved for on each layer. Filled in by
+    /// [`BoardConfig::fill_impedance_geometry`].
+    pub fn layer_geometry(&self) -> &[LayerGeometry] {
+        &self.layer_geometry
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FOUR_LAYER: &str = r#"{
+        "design_rules": {
+            "netclasses": [
+                {"name": "Default", "track_width": 0.2},
+                {
+                    "name": "50Ohm SE",
+                    "single_ended_impedance": {"value": "50", "tolerance": "0", "unit": "Ohms"}
+                },
+                {
+                    "name": "90Ohm Diff",
+                    "diff_pair_gap": 0.15,
+                    "differential_pair_impedance": {"value": "90", "tolerance": "0", "unit": "Ohms"}
+                },
+                {
+                    "name": "Narrow",
+                    "track_width": 0.1,
+                    "single_ended_impedance": {"value": "50", "tolerance": "0", "unit": "Ohms"}
+                }
+            ]
+        },
+        "stackup": {
+            "materials": [
+                {"name": "Prepreg", "relative_permittivity": 4.4},
+                {"name": "Core", "relative_permittivity": 4.6}
+            ],
+            "layers": [
+                {"thickness": 0.035, "role": "signal"},
+                {"thickness": 0.2, "material": "Prepreg", "form": "prepreg"},
+                {"thickness": 0.035, "role": "ground"},
+                {"thickness": 1.0, "material": "Core", "form": "core"},
+                {"thickness": 0.035, "role": "signal"},
+                {"thickness": 0.2, "material": "Prepreg", "form": "prepreg"},
+                {"thickness": 0.035, "role": "signal"}
+            ]
+        }
+    }"#;
+
+    fn close(a: f64, b: f64, eps: f64) -> bool {
+        (a - b).abs() < eps
+    }
+
+    #[test]
+    fn impedance_formulas() {
+        let microstrip = TraceLayer {
+            name: "F.Cu".into(),
+            model: TraceModel::Microstrip,
+            thickness: 0.035,
+            height: 0.2,
+            height_below: 0.0,
+            permittivity: 4.4,
+        };
+        // 87 / sqrt(5.81) * ln(1.196 / 0.315)
+        assert!(close(microstrip.single_ended(0.35), 48.15, 0.01));
+        assert!(microstrip.differential(0.2, 0.2) < 2.0 * microstrip.single_ended(0.2));
+
+        let stripline = TraceLayer {
+            name: "In1.Cu".into(),
+            model: TraceModel::Stripline,
+            thickness: 0.035,
+            height: 0.2,
+            height_below: 0.2,
+            permittivity: 4.4,
+        };
+        // Centred: 60 / sqrt(4.4) * ln(1.9 * 0.435 / 0.135)
+        assert!(close(stripline.single_ended(0.125), 51.83, 0.01));
+        let offset = TraceLayer {
+            height_below: 1.0,
+            ..stripline.clone()
+        };
+        assert!(offset.single_ended(0.125) > stripline.single_ended(0.125));
+
+        let width = solve(50.0, stripline.max_width(), |w| stripline.single_ended(w)).unwrap();
+        assert!(close(stripline.single_ended(width), 50.0, 0.05));
+        assert_eq!(
+            solve(1000.0, stripline.max_width(), |w| stripline.single_ended(w)),
+            None
+        );
+    }
+
+    #[test]
+    fn trace_layers_skip_planes() {
+        let config = BoardConfig::from_json_str(FOUR_LAYER).unwrap();
+        let layers = config.stackup.unwrap().trace_layers();
+        let summary: Vec<_> = layers.iter().map(|l| (l.name.as_str(), l.model)).collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("F.Cu", TraceModel::Microstrip),
+                ("In2.Cu", TraceModel::Stripline),
+                ("B.Cu", TraceModel::Microstrip),
+            ]
+        );
+        assert!(close(layers[1].permittivity, (4.6 + 0.2 * 4.4) / 1.2, 1e-9));
+    }
+
+    #[test]
+    fn fills_unspecified_netclass_geometry() {
+        let mut config = BoardConfig::from_json_str(FOUR_LAYER).unwrap();
+        let warnings = config.fill_impedance_geometry();
+        let top = config.stackup.as_ref().unwrap().trace_layers()[0].clone();
+
+        let netclasses = config.netclasses();
+        assert_eq!(netclasses[0].track_width, Some(0.2));
+
+        let width = netclasses[1].track_width.unwrap();
+        assert!(close(top.single_ended(width), 50.0, 0.1), "{width}");
+
+        let diff = &netclasses[2];
+        assert_eq!(diff.diff_pair_gap, Some(0.15));
+        let width = diff.diff_pair_width.unwrap();
+        assert!(close(top.differential(width, 0.15), 90.0, 0.1), "{width}");
+        assert_eq!(diff.track_width, Some(width));
+
+        // The explicit width of 'Narrow' misses its target on every layer, and is kept
+        let narrow: Vec<_> = warnings
+            .iter()
+            .filter_map(|w| w.strip_prefix("Netclass 'Narrow': track width 0.1 mm gives"))
+            .map(|w| w.split(" on ").nth(1).unwrap().split(',').next().unwrap())
+            .collect();
+        assert_eq!(narrow, vec!["F.Cu", "In2.Cu", "B.Cu"], "{warnings:?}");
+        assert_eq!(warnings.len(), 3, "{warnings:?}");
+        assert_eq!(netclasses[3].track_width, Some(0.1));
+
+        // Inner and bottom layers get their own widths, keeping the pair gap
+        let geometry = config.layer_geometry();
+        let layers: Vec<_> = geometry
+            .iter()
+            .map(|g| (g.netclass.as_str(), g.layer.as_str()))
+            .collect();
+        assert_eq!(
+            layers,
+            vec![
+                ("50Ohm SE", "In2.Cu"),
+                ("50Ohm SE", "B.Cu"),
+                ("90Ohm Diff", "In2.Cu"),
+                ("90Ohm Diff", "B.Cu"),
+            ]
+        );
+        assert!(geometry[0].track_width < netclasses[1].track_width.unwrap());
+        assert_eq!(geometry[2].diff_pair_gap, Some(0.15));
+    }
+}
@@ -5,6 +5,7 @@ pub mod electrical_check;
 pub mod r#enum;
 pub mod eval;
 pub(crate) mod evaluator_ext;
+pub mod impedance;
 pub(crate) mod interface;
 pub mod module;
 pub mod net;
@@ -12,6 +13,7 @@ pub mod spice_model;
 pub mod stackup;
 pub mod symbol;
 pub mod test_bench;
+pub mod tolerance;
 pub mod type_info;
 
 // Misc helpers (error/check)
@@ -33,6 +33,9 @@ use crate::lang::interface::{
 };
 use crate::lang::naming;
 use crate::lang::validation::validate_identifier_name;
+use pcb_sch::design_rules::{DesignRule, DesignRuleError, RuleKind, RuleScope};
+use pcb_sch::physical::{PhysicalUnitDims, PhysicalValue};
+use pcb_sch::variants::{Variant, VariantOverride};
 use regex::Regex;
 use starlark::codemap::{CodeMap, Pos, Span};
 use starlark::values::dict::{AllocDict, DictRef};
@@ -313,6 +316,16 @@ pub struct ModuleValueGen<V: ValueLifetimeless> {
     positions: PositionMap,
     /// Path movement directives from moved() calls. Map of `old path → (new path, auto_generated)`.
     moved_directives: SmallMap<String, (String, bool)>,
+    /// Rules from design_rule() calls. Their nets are net ids until the schematic names them.
+    #[freeze(identity)]
+    #[allocative(skip)]
+    #[trace(unsafe_ignore)]
+    design_rules: Vec<DesignRule>,
+    /// Assembly variants from variant() calls, with paths relative to this module.
+    #[freeze(identity)]
+    #[allocative(skip)]
+    #[trace(unsafe_ignore)]
+    variants: Vec<Variant>,
     /// Local values (components, electrical checks, testbenches). Child modules are in module_tree.
     children: Vec<V>,
     /// Component modifier functions registered via builtin.add_component_modifier().
@@ -429,6 +442,8 @@ impl<'v, V: ValueLike<'v>> ModuleValueGen<V> {
             net_name_to_id: SmallMap::new(),
             positions,
             moved_directives: SmallMap::new(),
+            design_rules: Vec::new(),
+            variants: Vec::new(),
             children: Vec::new(),
             component_modifiers: Vec::new(),
             parent_component_modifiers: Vec::new(),
@@ -656,6 +671,29 @@ impl<'v, V: ValueLike<'v>> ModuleValueGen<V> {
         &self.moved_directives
     }
 
+    /// Add a design rule whose nets are given by net id.
+    pub fn add_design_rule(&mut self, rule: DesignRule) {
+        self.design_rules.push(rule);
+    }
+
+    /// Return the design rules of this module, with nets given by net id.
+    pub fn design_rules(&self) -> &[DesignRule] {
+        &self.design_rules
+    }
+
+    /// Add an assembly variant, merging it into an earlier declaration of the same name.
+    pub fn add_variant(&mut self, variant: Variant) {
+        match self.variants.iter_mut().find(|v| v.name == variant.name) {
+            Some(existing) => existing.extend(variant),
+            None => self.variants.push(variant),
+        }
+    }
+
+    /// Return the assembly variants declared in this module.
+    pub fn variants(&self) -> &[Variant] {
+        &self.variants
+    }
+
     /// Extract all net names from a value recursively.
     /// This handles Net types directly and recursively extracts nets from Interface types.
     pub fn extract_nets_from_value(value: starlark::values::Value<'_>) -> HashSet<String> {
@@ -1853,4 +1891,217 @@ pub fn module_globals(builder: &mut GlobalsBuilder) {
         }
         Ok(Value::new_none())
     }
+
+    /// Declare a routing constraint on nets or interfaces, emitted into the board's KiCad custom
+    /// rules. Exactly one of `clearance`, `max_length`, `length_match`, `max_vias` or `keepout`
+    /// must be given; lengths are numbers in mm or `Length` values.
+    fn design_rule<'v>(
+        #[starlark(require = pos)] name: String,
+        #[starlark(require = pos)] nets: Value<'v>,
+        #[starlark(require = named)] clearance: Option<Value<'v>>,
+        #[starlark(require = named)] against: Option<Value<'v>>,
+        #[starlark(require = named)] max_length: Option<Value<'v>>,
+        #[starlark(require = named)] length_match: Option<Value<'v>>,
+        #[starlark(require = named)] max_vias: Option<i32>,
+        #[starlark(require = named)] keepout: Option<Value<'v>>,
+        eval: &mut Evaluator<'v, '_, '_>,
+    ) -> anyhow::Result<Value<'v>> {
+        let scope = RuleScope::Nets(rule_net_ids(&name, nets)?);
+        let against = against
+            .map(|value| rule_net_ids(&name, value).map(RuleScope::Nets))
+            .transpose()?;
+
+        let mut kinds = Vec::new();
+        if let Some(value) = clearance {
+            let min = rule_length_mm(&name, "clearance", value)?;
+            kinds.push(RuleKind::Clearance {
+                min,
+                against: against.clone(),
+            });
+        }
+        if let Some(value) = max_length {
+            let max = rule_length_mm(&name, "max_length", value)?;
+            kinds.push(RuleKind::MaxLength { max });
+        }
+        if let Some(value) = length_match {
+            let tolerance = rule_length_mm(&name, "length_match", value)?;
+            kinds.push(RuleKind::LengthMatch { tolerance });
+        }
+        if let Some(max) = max_vias {
+            let max = u32::try_from(max).map_err(|_| {
+                anyhow::anyhow!("design_rule '{name}': max_vias must not be negative, got {max}")
+            })?;
+            kinds.push(RuleKind::MaxVias { max });
+        }
+        if let Some(value) = keepout {
+            let layers = ListRef::from_value(value)
+                .and_then(|list| {
+                    list.iter()
+                        .map(|layer| layer.unpack_str().map(str::to_string))
+                        .collect::<Option<Vec<_>>>()
+                })
+                .ok_or_else(|| {
+                    anyhow::anyhow!("design_rule '{name}': keepout must be a list of layer names")
+                })?;
+            kinds.push(RuleKind::Keepout { layers });
+        }
+        if kinds.len() != 1 {
+            return Err(DesignRuleError::Kind(name).into());
+        }
+        let kind = kinds.remove(0);
+        if against.is_some() && !matches!(kind, RuleKind::Clearance { .. }) {
+            return Err(DesignRuleError::AgainstWithoutClearance(name).into());
+        }
+
+        let rule = DesignRule::new(name, scope, kind)?;
+        if let Some(ctx) = eval.context_value() {
+            ctx.module_mut().add_design_rule(rule);
+        }
+        Ok(Value::new_none())
+    }
+
+    /// Declare an assembly variant of the board. `overrides` maps instance paths, relative to this
+    /// module, to the `dnp`, `mpn`, `manufacturer` or `value` the variant gives the components at
+    /// or under that path. Select a variant with `--variant` in `pcb build`, `bom` and `release`.
+    fn variant<'v>(
+        #[starlark(require = pos)] name: String,
+        #[starlark(require = pos)] overrides: Value<'v>,
+        eval: &mut Evaluator<'v, '_, '_>,
+    ) -> anyhow::Result<Value<'v>> {
+        validate_identifier_name(&name, "Variant name")?;
+        let dict = DictRef::from_value(overrides).ok_or_else(|| {
+            anyhow::anyhow!(
+                "variant '{name}': overrides must be a dict of instance path -> dict, got {}",
+                overrides.get_type()
+            )
+        })?;
+
+        let mut variant = Variant::new(name.clone());
+        for (path_val, fields_val) in dict.iter() {
+            let path = path_val
+                .unpack_str()
+                .filter(|p| !p.is_empty())
+                .ok_or_else(|| {
+                    anyhow::anyhow!("variant '{name}': instance paths must be non-empty strings")
+                })?;
+            let fields = DictRef::from_value(fields_val).ok_or_else(|| {
+                anyhow::anyhow!(
+                    "variant '{name}': override for '{path}' must be a dict, got {}",
+                    fields_val.get_type()
+                )
+            })?;
+            let mut over = VariantOverride::default();
+            for (field_val, value) in fields.iter() {
+                let field = field_val.unpack_str().unwrap_or_default();
+                let text = || -> anyhow::Result<String> {
+                    value
+                        .unpack_str()
+                        .map(str::to_string)
+                        .or_else(|| {
+                            value
+                                .downcast_ref::<PhysicalValue>()
+                                .map(|pv| pv.to_string())
+                        })
+                        .ok_or_else(|| {
+                            anyhow::anyhow!(
+                                "variant '{name}': `{field}` for '{path}' must be a string or physical value, got {}",
+                                value.get_type()
+                            )
+                        })
+                };
+                match field {
+                    "dnp" => {
+                        over.dnp = Some(value.unpack_bool().ok_or_else(|| {
+                            anyhow::anyhow!(
+                                "variant '{name}': `dnp` for '{path}' must be a bool, got {}",
+                                value.get_type()
+                            )
+                        })?)
+                    }
+                    "mpn" => over.mpn = Some(text()?),
+                    "manufacturer" => over.manufacturer = Some(text()?),
+                    "value" => over.value = Some(text()?),
+                    other => anyhow::bail!(
+                        "variant '{name}': unknown field `{other}` for '{path}' (expected dnp, mpn, manufacturer or value)"
+                    ),
+                }
+            }
+            variant.overrides.insert(path.to_string(), over);
+        }
+
+        if let Some(ctx) = eval.context_value() {
+            ctx.module_mut().add_variant(variant);
+        }
+        Ok(Value::new_none())
+    }
+}
+
+/// Ids of the nets in a Net, an interface or a list of them, as strings
+pub(crate) fn rule_net_ids(rule: &str, value: Value) -> anyhow::Result<Vec<String>> {
+    fn collect(value: Value, ids: &mut Vec<NetId>) -> bool {
+        use crate::lang::interface::{FrozenInterfaceValue, InterfaceValue};
+
+        if let Some(net) = value.downcast_ref::<NetValue>() {
+            ids.push(net.id());
+        } else if let Some(net) = value.downcast_ref::<FrozenNetValue>() {
+            ids.push(net.id());
+        } else if let Some(iface) = value.downcast_ref::<InterfaceValue>() {
+            for (_, field) in iface.fields().iter() {
+                collect(*field, ids);
+            }
+        } else if let Some(iface) = value.downcast_ref::<FrozenInterfaceValue>() {
+            for (_, field) in iface.fields().iter() {
+                collect(field.to_value(), ids);
+            }
+        } else if let Some(list) = ListRef::from_value(value) {
+            for item in list.iter() {
+                if !collect(item, ids) {
+                    return false;
+                }
+            }
+        } else {
+            return false;
+        }
+        true
+    }
+
+    let mut ids = Vec::new();
+    if !collect(value, &mut ids) {
+        anyhow::bail!(
+            "design_rule '{rule}': expected a Net, an interface or a list of them, got {}",
+            value.get_type()
+        );
+    }
+    let mut seen = HashSet::new();
+    ids.retain(|id| seen.insert(*id));
+    Ok(ids.into_iter().map(|id| id.to_string()).collect())
+}
+
+/// A rule length in mm, from a number (mm), a `Length` value or a string such as `"0.2mm"`
+pub(crate) fn rule_length_mm(rule: &str, field: &str, value: Value) -> anyhow::Result<f64> {
+    use rust_decimal::prelude::ToPrimitive;
+
+    if let Some(mm) = value.unpack_i32() {
+        return Ok(mm as f64);
+    }
+    if let Some(mm) = value.downcast_ref::<StarlarkFloat>() {
+        return Ok(mm.0);
+    }
+    let physical = if let Some(&physical) = value.downcast_ref::<PhysicalValue>() {
+        Some(physical)
+    } else {
+        value
+            .unpack_str()
+            .and_then(|s| s.parse::<PhysicalValue>().ok())
+    };
+    match physical {
+        Some(pv) if pv.unit == PhysicalUnitDims::LENGTH => (pv.value
+            * rust_decimal::Decimal::ONE_THOUSAND)
+            .to_f64()
+            .ok_or_else(|| anyhow::anyhow!("design_rule '{rule}': {field} is out of range")),
+        _ => anyhow::bail!(
+            "design_rule '{rule}': {field} must be a length, got {}",
+            value.to_repr()
+        ),
+    }
 }
@@ -1,10 +1,13 @@
-use pcb_sch::physical::PhysicalValue;
+use pcb_sch::design_rules::{DesignRule, DesignRuleError};
+use pcb_sch::physical::{PhysicalUnitDims, PhysicalValue};
 use pcb_sexpr::{kv, ListBuilder, Sexpr};
 use serde::{Deserialize, Serialize};
 use serde_json::Value as JsonValue;
 use std::{borrow::Cow, collections::HashMap, fmt, path::PathBuf, str::FromStr};
 use thiserror::Error;
 
+use super::impedance::LayerGeometry;
+
 pub const THICKNESS_EPS: f64 = f64::EPSILON * 1000.0; // Floating point precision tolerance
 
 /// Trait for approximate equality with tolerance
@@ -112,7 +115,7 @@ impl ApproxEq for Stackup {
 }
 
 // Helper functions for layer mapping
-fn copper_layer_mapping(index: usize, total_copper: usize) -> (u32, Cow<'static, str>) {
+pub(super) fn copper_layer_mapping(index: usize, total_copper: usize) -> (u32, Cow<'static, str>) {
     match (index, total_copper) {
         (0, _) => (0, Cow::Borrowed("F.Cu")),
         (i, total) if i == total - 1 => (2, Cow::Borrowed("B.Cu")),
@@ -281,15 +284,54 @@ pub enum Layer {
     },
 }
 
+/// A length in millimetres, given either as a bare number or as a `Length` value
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum MmOrLength {
+    Mm(f64),
+    Length(PhysicalValue),
+}
+
+impl MmOrLength {
+    fn into_mm<E: serde::de::Error>(self) -> Result<f64, E> {
+        use rust_decimal::prelude::ToPrimitive;
+        match self {
+            MmOrLength::Mm(mm) => Ok(mm),
+            MmOrLength::Length(pv) if pv.unit == PhysicalUnitDims::LENGTH => (pv.value
+                * rust_decimal::Decimal::ONE_THOUSAND)
+                .to_f64()
+                .ok_or_else(|| E::custom(format!("length {pv} is out of range"))),
+            MmOrLength::Length(pv) => Err(E::custom(format!(
+                "expected a Length, got {} ({pv})",
+                pv.unit.quantity()
+            ))),
+        }
+    }
+}
+
+fn deserialize_mm<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<f64, D::Error> {
+    MmOrLength::deserialize(deserializer)?.into_mm()
+}
+
+fn deserialize_opt_mm<'de, D: serde::Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<f64>, D::Error> {
+    Option::<MmOrLength>::deserialize(deserializer)?
+        .map(MmOrLength::into_mm)
+        .transpose()
+}
+
 // Helper structs for deserialization
 #[derive(Deserialize)]
 struct CopperLayerData {
+    #[serde(deserialize_with = "deserialize_mm")]
     thickness: f64,
     role: CopperRole,
 }
 
 #[derive(Deserialize)]
 struct DielectricLayerData {
+    #[serde(deserialize_with = "deserialize_mm")]
     thickness: f64,
     material: String,
     form: DielectricForm,
@@ -348,27 +390,66 @@ fn default_num_user_layers() -> usize {
     4
 }
 
-/// Netclass definition with impedance specifications
+/// Netclass definition with impedance specifications.
+///
+/// Widths, clearances and via sizes are in millimetres. The board config may give them as
+/// bare numbers (mm) or as `Length` values, which are converted on load.
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct NetClass {
     pub name: String,
-    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(
+        default,
+        deserialize_with = "deserialize_opt_mm",
+        skip_serializing_if = "Option::is_none"
+    )]
     pub clearance: Option<f64>,
-    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(
+        default,
+        deserialize_with = "deserialize_opt_mm",
+        skip_serializing_if = "Option::is_none"
+    )]
     pub track_width: Option<f64>,
-    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(
+        default,
+        deserialize_with = "deserialize_opt_mm",
+        skip_serializing_if = "Option::is_none"
+    )]
     pub via_diameter: Option<f64>,
-    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(
+        default,
+        deserialize_with = "deserialize_opt_mm",
+        skip_serializing_if = "Option::is_none"
+    )]
     pub via_drill: Option<f64>,
-    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(
+        default,
+        deserialize_with = "deserialize_opt_mm",
+        skip_serializing_if = "Option::is_none"
+    )]
     pub microvia_diameter: Option<f64>,
-    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(
+        default,
+        deserialize_with = "deserialize_opt_mm",
+        skip_serializing_if = "Option::is_none"
+    )]
     pub microvia_drill: Option<f64>,
-    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(
+        default,
+        deserialize_with = "deserialize_opt_mm",
+        skip_serializing_if = "Option::is_none"
+    )]
     pub diff_pair_width: Option<f64>,
-    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(
+        default,
+        deserialize_with = "deserialize_opt_mm",
+        skip_serializing_if = "Option::is_none"
+    )]
     pub diff_pair_gap: Option<f64>,
-    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(
+        default,
+        deserialize_with = "deserialize_opt_mm",
+        skip_serializing_if = "Option::is_none"
+    )]
     pub diff_pair_via_gap: Option<f64>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub priority: Option<i32>,
@@ -409,6 +490,19 @@ pub struct DesignRules {
     pub predefined_sizes: Option<JsonValue>,
     #[serde(default)]
     pub netclasses: Vec<NetClass>,
+    /// Typed rules, usually between netclasses, emitted into the board's `.kicad_dru`
+    #[serde(
+        default,
+        deserialize_with = "deserialize_null_as_empty",
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    pub rules: Vec<DesignRule>,
+}
+
+fn deserialize_null_as_empty<'de, D: serde::Deserializer<'de>, T: Deserialize<'de>>(
+    deserializer: D,
+) -> Result<Vec<T>, D::Error> {
+    Ok(Option::<Vec<T>>::deserialize(deserializer)?.unwrap_or_default())
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -419,6 +513,9 @@ pub struct BoardConfig {
     pub num_user_layers: usize,
     #[serde(default)]
     pub spec: Option<PathBuf>,
+    /// Per-layer netclass geometry derived by `fill_impedance_geometry`
+    #[serde(skip)]
+    pub(crate) layer_geometry: Vec<LayerGeometry>,
 }
 
 impl BoardConfig {
@@ -431,6 +528,23 @@ impl BoardConfig {
             stackup.validate()?;
         }
 
+        // Rules may only refer to netclasses the config defines
+        for rule in board_config.design_rules() {
+            for netclass in rule.netclasses() {
+                if !board_config
+                    .netclasses()
+                    .iter()
+                    .any(|nc| nc.name == netclass)
+                {
+                    return Err(DesignRuleError::UnknownNetClass {
+                        rule: rule.name.clone(),
+                        netclass: netclass.to_string(),
+                    }
+                    .into());
+                }
+            }
+        }
+
         Ok(board_config)
     }
 
@@ -441,6 +555,14 @@ impl BoardConfig {
             .map(|dr| dr.netclasses.as_slice())
             .unwrap_or(&[])
     }
+
+    /// Get all typed design rules defined in this board config
+    pub fn design_rules(&self) -> &[DesignRule] {
+        self.design_rules
+            .as_ref()
+            .map(|dr| dr.rules.as_slice())
+            .unwrap_or(&[])
+    }
 }
 
 #[derive(Debug, Error)]
@@ -450,6 +572,9 @@ pub enum BoardConfigError {
 
     #[error(transparent)]
     Stackup(#[from] StackupError),
+
+    #[error(transparent)]
+    DesignRule(#[from] DesignRuleError),
 }
 
 #[derive(Debug, Error)]
@@ -1576,4 +1701,78 @@ mod tests {
         assert!(layers[0].is_copper());
         assert!(!layers[1].is_copper());
     }
+
+    #[test]
+    fn test_length_values_convert_to_mm() {
+        let layer: Layer = serde_json::from_str(
+            r#"{"thickness":{"value":"0.000035","tolerance":"0","unit":"Meters"},"role":"signal"}"#,
+        )
+        .unwrap();
+        assert!(layer.thickness().approx_eq(&0.035, THICKNESS_EPS));
+
+        let netclass: NetClass = serde_json::from_str(
+            r#"{
+                "name": "Power",
+                "track_width": {"value":"0.000127","tolerance":"0","unit":"Meters"},
+                "clearance": 0.2
+            }"#,
+        )
+        .unwrap();
+        assert!(netclass.track_width.approx_eq(&Some(0.127), THICKNESS_EPS));
+        assert_eq!(netclass.clearance, Some(0.2));
+        assert_eq!(netclass.via_drill, None);
+
+        // Other quantities are rejected
+        let err = serde_json::from_str::<NetClass>(
+            r#"{"name": "X", "clearance": {"value":"3.3","tolerance":"0","unit":"Volts"}}"#,
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("expected a Length"), "{err}");
+    }
+
+    #[test]
+    fn test_board_config_design_rules() {
+        let config = BoardConfig::from_json_str(
+            r#"{
+                "design_rules": {
+                    "netclasses": [{"name": "Default"}, {"name": "HV"}],
+                    "rules": [{
+                        "name": "hv_gap",
+                        "nets": null,
+                        "netclass": "HV",
+                        "against_netclass": "Default",
+                        "clearance": 2.0,
+                        "max_length": null
+                    }]
+                },
+                "stackup": null
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(config.design_rules().len(), 1);
+        assert_eq!(config.design_rules()[0].netclasses(), vec!["HV", "Default"]);
+
+        // `rules: null` from a Zener record is an empty list
+        let config = BoardConfig::from_json_str(
+            r#"{"design_rules": {"netclasses": [], "rules": null}, "stackup": null}"#,
+        )
+        .unwrap();
+        assert!(config.design_rules().is_empty());
+
+        // Rules may not refer to undefined netclasses
+        let err = BoardConfig::from_json_str(
+            r#"{
+                "design_rules": {
+                    "netclasses": [{"name": "Default"}],
+                    "rules": [{"name": "hv_len", "netclass": "HV", "max_length": 20.0}]
+                },
+                "stackup": null
+            }"#,
+        )
+        .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Design rule 'hv_len' references unknown netclass 'HV'"
+        );
+    }
 }
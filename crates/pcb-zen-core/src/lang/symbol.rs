@@ -47,6 +47,7 @@ pub struct SymbolValue {
     pub source_path: Option<String>, // Absolute path to the symbol library (if loaded from file)
     pub raw_sexp: Option<String>, // Raw s-expression of the symbol (if loaded from file, otherwise None)
     pub properties: SmallMap<String, String>, // Properties from the symbol definition
+    pub pin_types: SmallMap<String, String>, // pad name -> KiCad pin electrical type
 }
 
 impl std::fmt::Debug for SymbolValue {
@@ -214,6 +215,7 @@ impl<'v> SymbolValue {
                 source_path: None,
                 raw_sexp: None,
                 properties: SmallMap::new(),
+                pin_types: SmallMap::new(),
             })
         }
         // Case 2: Load from library
@@ -278,6 +280,7 @@ impl<'v> SymbolValue {
             // Convert EdaSymbol pins to our Symbol format
             // Map pad number -> signal name (which is the pin name from the symbol)
             let mut pad_to_signal: SmallMap<String, String> = SmallMap::new();
+            let mut pin_types: SmallMap<String, String> = SmallMap::new();
             for pin in &selected_symbol.pins {
                 // If pin name is ~, use the pin number instead
                 let signal_name = if pin.name == "~" {
@@ -286,6 +289,7 @@ impl<'v> SymbolValue {
                     &pin.name
                 };
                 pad_to_signal.insert(pin.number.clone(), signal_name.to_owned());
+                pin_types.insert(pin.number.clone(), pin.electrical_type.to_string());
             }
 
             // Get the absolute path using file provider
@@ -312,6 +316,7 @@ impl<'v> SymbolValue {
                 source_path: Some(absolute_path),
                 raw_sexp: sexpr,
                 properties,
+                pin_types,
             })
         } else {
             Err(starlark::Error::new_other(anyhow!(
@@ -343,6 +348,11 @@ impl<'v> SymbolValue {
     pub fn properties(&self) -> &SmallMap<String, String> {
         &self.properties
     }
+
+    /// Electrical type of each pad, as declared by the KiCad symbol
+    pub fn pin_types(&self) -> &SmallMap<String, String> {
+        &self.pin_types
+    }
 }
 
 /// SymbolType is a factory for creating Symbol values
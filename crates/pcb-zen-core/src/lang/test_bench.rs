@@ -54,6 +54,9 @@ pub struct TestBenchValueGen<V: ValueLifetimeless> {
     module_loader: ModuleLoader,
     /// Deferred test cases (used when checks are deferred)
     pub(crate) deferred_cases: Vec<DeferredTestCaseGen<V>>,
+    /// SPICE analysis setup appended to each case's netlist; when set, `pcb test` simulates
+    /// every case with ngspice and passes the results to checks as a third argument
+    simulation: Option<String>,
     /// Source file path where TestBench was defined (for diagnostic context)
     source_path: String,
     /// Span of the TestBench() call for diagnostic context
@@ -103,6 +106,10 @@ impl<'v, V: ValueLike<'v>> TestBenchValueGen<V> {
         &self.deferred_cases
     }
 
+    pub fn simulation(&self) -> Option<&str> {
+        self.simulation.as_deref()
+    }
+
     pub fn case_count(&self) -> usize {
         self.deferred_cases.len()
     }
@@ -138,6 +145,7 @@ pub fn test_bench_globals(builder: &mut GlobalsBuilder) {
         #[starlark(require = named)] module: Value<'v>,
         #[starlark(require = named)] test_cases: Value<'v>,
         #[starlark(require = named)] checks: Option<Value<'v>>,
+        #[starlark(require = named)] simulation: Option<String>,
         eval: &mut Evaluator<'v, '_, '_>,
     ) -> anyhow::Result<Value<'v>> {
         // Extract ModuleLoader from the module parameter
@@ -270,6 +278,7 @@ pub fn test_bench_globals(builder: &mut GlobalsBuilder) {
             name,
             module_loader: loader.clone(),
             deferred_cases,
+            simulation,
             source_path,
             call_span,
             call_stack,
@@ -295,14 +304,43 @@ pub struct CheckContext<'a> {
     pub call_span: Option<&'a starlark::codemap::ResolvedSpan>,
 }
 
-/// Execute a single deferred check and create diagnostic
+/// Execute a single deferred check and create diagnostic.
+///
+/// Checks are called as `check(module, inputs)`, or `check(module, inputs, sim)` when the
+/// TestBench was simulated.
 pub fn execute_deferred_check<'v, V: ValueLike<'v>>(
     eval: &mut Evaluator<'v, '_, '_>,
     check: &DeferredCheckGen<V>,
     module_value: Value<'v>,
     inputs_dict: Value<'v>,
+    sim: Option<Value<'v>>,
     ctx: &CheckContext,
 ) -> (bool, Vec<Diagnostic>) {
+    // Execute the check function
+    let mut args = vec![module_value, inputs_dict];
+    args.extend(sim);
+    let result = eval.eval_function(check.check_func.to_value(), &args, &[]);
+    let passed = result.is_ok();
+
+    let diagnostic = check_diagnostic(check, ctx, result.err().map(Diagnostic::from));
+    (passed, vec![diagnostic])
+}
+
+/// Create a failing diagnostic for a check that could not run, e.g. because the simulation
+/// of its case failed
+pub fn failed_check_diagnostic<'v, V: ValueLike<'v>>(
+    check: &DeferredCheckGen<V>,
+    error: Diagnostic,
+    ctx: &CheckContext,
+) -> Diagnostic {
+    check_diagnostic(check, ctx, Some(error))
+}
+
+fn check_diagnostic<'v, V: ValueLike<'v>>(
+    check: &DeferredCheckGen<V>,
+    ctx: &CheckContext,
+    error: Option<Diagnostic>,
+) -> Diagnostic {
     // Extract check name
     let check_name = check.custom_name.clone().unwrap_or_else(|| {
         check
@@ -314,16 +352,9 @@ pub fn execute_deferred_check<'v, V: ValueLike<'v>>(
             .unwrap_or("check")
             .to_string()
     });
+    let passed = error.is_none();
 
-    // Execute the check function
-    let result = eval.eval_function(
-        check.check_func.to_value(),
-        &[module_value, inputs_dict],
-        &[],
-    );
-    let passed = result.is_ok();
-
-    let diagnostic = Diagnostic {
+    Diagnostic {
         path: ctx.source_path.to_string(),
         span: ctx.call_span.cloned(),
         severity: if passed {
@@ -338,7 +369,7 @@ pub fn execute_deferred_check<'v, V: ValueLike<'v>>(
             if passed { "passed" } else { "failed" }
         ),
         call_stack: None,
-        child: result.err().map(|e| Box::new(Diagnostic::from(e))),
+        child: error.map(Box::new),
         source_error: Some(Arc::new(
             crate::lang::error::BenchTestResult {
                 test_bench_name: ctx.test_bench_name.to_string(),
@@ -350,7 +381,5 @@ pub fn execute_deferred_check<'v, V: ValueLike<'v>>(
             .into(),
         )),
         suppressed: false,
-    };
-
-    (passed, vec![diagnostic])
+    }
 }
@@ -0,0 +1,147 @@
+//! `worst_case()` and `monte_carlo()`: evaluate an expression over component tolerances.
+//!
+//! ```python
+//! def divider(r1, r2):
+//!     return Voltage("5V") * r2 / (r1 + r2)
+//!
+//! vout = worst_case(divider, r1 = "10k 1%", r2 = "20k 1%")   # PhysicalRange
+//! samples = monte_carlo(divider, runs = 1000, r1 = "10k 1%", r2 = "20k 1%")
+//! ```
+
+use pcb_sch::physical::{PhysicalRange, PhysicalValue};
+use pcb_sch::tolerance::{self, Variation};
+use rust_decimal::Decimal;
+use starlark::collections::SmallMap;
+use starlark::environment::GlobalsBuilder;
+use starlark::eval::Evaluator;
+use starlark::starlark_module;
+use starlark::values::Value;
+
+/// Call `f` at the nominal values and then once per run, with every keyword argument set to
+/// that run's exact value. Returns the nominal result and the result of each run.
+fn evaluate<'v>(
+    eval: &mut Evaluator<'v, '_, '_>,
+    f: Value<'v>,
+    values: &SmallMap<String, Value<'v>>,
+    variation: Variation,
+) -> starlark::Result<(PhysicalValue, Vec<PhysicalValue>)> {
+    let names: Vec<&str> = values.keys().map(String::as_str).collect();
+    let inputs = values
+        .values()
+        .map(|v| PhysicalValue::try_from(*v))
+        .collect::<starlark::Result<Vec<_>>>()?;
+
+    let nominal: Vec<Decimal> = inputs.iter().map(|v| v.value).collect();
+    let runs = tolerance::sample(&inputs, variation)
+        .map_err(|e| starlark::Error::new_other(anyhow::Error::new(e)))?;
+
+    let mut results = Vec::with_capacity(runs.len() + 1);
+    for run in std::iter::once(&nominal).chain(&runs) {
+        let args: Vec<(&str, Value<'v>)> = names
+            .iter()
+            .zip(run.iter().zip(&inputs))
+            .map(|(name, (value, input))| {
+                let exact = PhysicalValue::from_decimal(*value, Decimal::ZERO, input.unit);
+                (*name, eval.heap().alloc(exact))
+            })
+            .collect();
+        let result = eval.eval_function(f, &[], &args)?;
+        results.push(PhysicalValue::try_from(result)?);
+    }
+
+    let nominal = results.remove(0);
+    if let Some(other) = results.iter().find(|r| r.unit != nominal.unit) {
+        return Err(starlark::Error::new_other(anyhow::anyhow!(
+            "expression returned both {} and {}",
+            nominal.unit.quantity(),
+            other.unit.quantity()
+        )));
+    }
+    Ok((nominal, results))
+}
+
+#[starlark_module]
+pub(crate) fn tolerance_globals(builder: &mut GlobalsBuilder) {
+    /// Evaluate `f` with its keyword arguments at every combination of their tolerance
+    /// extremes. Returns the range of results, with `f` at the nominal values as its nominal.
+    ///
+    /// The corners bound the result when `f` is monotonic in each argument, as dividers,
+    /// filters and most passive networks are.
+    fn worst_case<'v>(
+        #[starlark(require = pos)] f: Value<'v>,
+        #[starlark(kwargs)] values: SmallMap<String, Value<'v>>,
+        eval: &mut Evaluator<'v, '_, '_>,
+    ) -> starlark::Result<PhysicalRange> {
+        let (nominal, corners) = evaluate(eval, f, &values, Variation::Corners)?;
+        let min = corners
+            .iter()
+            .map(|r| r.value)
+            .fold(nominal.value, Decimal::min);
+        let max = corners
+            .iter()
+            .map(|r| r.value)
+            .fold(nominal.value, Decimal::max);
+        Ok(PhysicalRange::from_bounds(
+            min,
+            max,
+            Some(nominal.value),
+            nominal.unit,
+        ))
+    }
+
+    /// Evaluate `f` for `runs` random draws of its keyword arguments, each uniform within its
+    /// tolerance. Returns the list of results; draws are reproducible for a given `seed`.
+    fn monte_carlo<'v>(
+        #[starlark(require = pos)] f: Value<'v>,
+        #[starlark(require = named, default = 1000)] runs: u32,
+        #[starlark(require = named, default = 0)] seed: u32,
+        #[starlark(kwargs)] values: SmallMap<String, Value<'v>>,
+        eval: &mut Evaluator<'v, '_, '_>,
+    ) -> starlark::Result<Vec<PhysicalValue>> {
+        let variation = Variation::MonteCarlo {
+            runs: runs as usize,
+            seed: seed.into(),
+        };
+        let (_, samples) = evaluate(eval, f, &values, variation)?;
+        Ok(samples)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use starlark::assert::Assert;
+    use starlark::environment::GlobalsBuilder;
+
+    fn assert() -> Assert<'static> {
+        let mut a = Assert::new();
+        a.globals_add(|builder: &mut GlobalsBuilder| {
+            super::tolerance_globals(builder);
+            crate::lang::builtin::builtin_globals(builder);
+        });
+        a
+    }
+
+    #[test]
+    fn test_worst_case_divider() {
+        assert().is_true(
+            r#"
+def divider(r1, r2):
+    return builtin.Voltage("3V") * r2 / (r1 + r2)
+
+# 3V * 19.8k / 29.9k at the low corner, 3V * 20.2k / 30.1k at the high one
+vout = worst_case(divider, r1 = "10k 1%", r2 = "20k 1%")
+abs(vout.min - 1.986622) < 1e-6 and abs(vout.max - 2.013289) < 1e-6 and abs(vout.nominal - 2) < 1e-9
+"#,
+        );
+    }
+
+    #[test]
+    fn test_monte_carlo() {
+        assert().is_true(
+            r#"
+samples = monte_carlo(lambda r: r * 2, runs = 100, seed = 3, r = "10k 5%")
+len(samples) == 100 and all([s.value >= 19000 and s.value <= 21000 for s in samples])
+"#,
+        );
+    }
+}
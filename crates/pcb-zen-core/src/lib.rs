@@ -8,13 +8,17 @@ use std::{
 pub mod config;
 pub mod convert;
 pub mod diagnostics;
+pub mod erc;
 mod file_provider;
 pub mod graph;
 pub mod lang;
 pub mod load_spec;
 mod moved;
 pub mod passes;
+pub mod power;
 pub mod resolution;
+pub mod schematic_diff;
+pub mod voltage_domains;
 pub mod workspace;
 
 /// Pinned stdlib version bundled with this toolchain.
@@ -44,13 +48,28 @@ pub mod attrs {
     pub const SYMBOL_PATH: &str = "symbol_path";
     pub const SYMBOL_VALUE: &str = "__symbol_value";
     pub const PADS: &str = "pads";
+    pub const PIN_TYPE: &str = "pin_type";
+    pub const ABS_MAX_VOLTAGE: &str = "abs_max_voltage";
+    pub const RECOMMENDED_VOLTAGE: &str = "recommended_voltage";
     pub const DNP: &str = "dnp";
     pub const SKIP_BOM: &str = "skip_bom";
     pub const SKIP_POS: &str = "skip_pos";
     pub const DATASHEET: &str = "datasheet";
     pub const DESCRIPTION: &str = "description";
 
+    /// Component properties describing the power tree (see `power`)
+    pub mod power {
+        pub const REGULATOR_INPUT: &str = "regulator_input";
+        pub const REGULATOR_OUTPUT: &str = "regulator_output";
+        pub const MAX_CURRENT: &str = "max_current";
+        pub const EFFICIENCY: &str = "efficiency";
+        pub const LOAD_CURRENT: &str = "load_current";
+        pub const LOAD_PINS: &str = "load_pins";
+    }
+
     pub mod net {
+        pub const VOLTAGE: &str = "voltage";
+
         pub mod kind {
             pub const GROUND: &str = "ground";
             pub const POWER: &str = "power";
@@ -71,7 +90,7 @@ pub use lang::error::SuppressedDiagnostics;
 pub use lang::eval::{EvalContext, EvalOutput};
 pub use load_spec::LoadSpec;
 pub use passes::{
-    AggregatePass, CommentSuppressPass, FilterHiddenPass, JsonExportPass, LspFilterPass,
+    AggregatePass, CommentSuppressPass, DenyPass, FilterHiddenPass, JsonExportPass, LspFilterPass,
     PromotePass, SortPass, StylePromotePass, SuppressPass,
 };
 
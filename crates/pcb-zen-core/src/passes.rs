@@ -154,6 +154,15 @@ impl CommentSuppressPass {
             source_cache: std::cell::RefCell::new(SourceCache::new()),
         }
     }
+
+    /// Read `path` from `contents` instead of from disk, e.g. for a file with unsaved edits
+    pub fn with_source(self, path: impl Into<String>, contents: &str) -> Self {
+        self.source_cache
+            .borrow_mut()
+            .files
+            .insert(path.into(), contents.lines().map(String::from).collect());
+        self
+    }
 }
 
 impl Default for CommentSuppressPass {
@@ -358,6 +367,43 @@ impl DiagnosticsPass for PromotePass {
     }
 }
 
+/// A pass that escalates diagnostics matching specified patterns to Error severity.
+///
+/// This backs `-D <kind>` in the CLI (e.g. `-D erc` fails the build on any ERC finding).
+/// `-D warnings` is handled by the caller, so the "warnings" pattern is ignored here.
+/// Patterns work hierarchically: "erc" matches "erc.undriven_input", etc.
+pub struct DenyPass {
+    patterns: Vec<String>,
+}
+
+impl DenyPass {
+    pub fn new(patterns: Vec<String>) -> Self {
+        Self { patterns }
+    }
+}
+
+impl DiagnosticsPass for DenyPass {
+    fn apply(&self, diagnostics: &mut Diagnostics) {
+        for diag in &mut diagnostics.diagnostics {
+            if matches!(diag.severity, EvalSeverity::Error) {
+                continue;
+            }
+
+            let should_deny = self.patterns.iter().any(|p| {
+                p != "warnings"
+                    && diag
+                        .innermost()
+                        .downcast_error_ref::<CategorizedDiagnostic>()
+                        .is_some_and(|c| c.kind == *p || c.kind.starts_with(&format!("{p}.")))
+            });
+
+            if should_deny {
+                diag.severity = EvalSeverity::Error;
+            }
+        }
+    }
+}
+
 /// A pass that promotes all style-related diagnostics from Advice to Warning severity.
 ///
 /// This is specifically for LSP use where we want style hints to be more visible.
@@ -487,4 +533,33 @@ mod tests {
         // The diagnostic should be suppressed via hierarchical matching
         assert!(diagnostics.diagnostics[0].suppressed);
     }
+
+    #[test]
+    fn test_deny_pass_escalates_matching_kinds() {
+        let diag = |kind: &str| {
+            Diagnostic::new("ERC finding", EvalSeverity::Warning, Path::new("test.zen"))
+                .with_source_error(Some(
+                    crate::lang::error::CategorizedDiagnostic::new(
+                        "ERC finding".to_string(),
+                        kind.to_string(),
+                    )
+                    .unwrap(),
+                ))
+        };
+
+        let mut diagnostics = Diagnostics {
+            diagnostics: vec![diag("erc.undriven_input"), diag("bom.match_generic")],
+        };
+
+        DenyPass::new(vec!["erc".to_string(), "warnings".to_string()]).apply(&mut diagnostics);
+
+        assert!(matches!(
+            diagnostics.diagnostics[0].severity,
+            EvalSeverity::Error
+        ));
+        assert!(matches!(
+            diagnostics.diagnostics[1].severity,
+            EvalSeverity::Warning
+        ));
+    }
 }
@@ -0,0 +1,802 @@
+//! Power tree extraction and rail budget.
+//!
+//! A regulator is a component whose `regulator_input` and `regulator_output` properties name
+//! its input and output pins (a pin name or a list of them). It may declare `max_current`, the
+//! output capacity, and `efficiency` for switching regulators; without one it is treated as
+//! linear and draws its output current from its input. Loads declare `load_current`, drawn
+//! from the power net they are connected to, or from the nets of the pins listed in
+//! `load_pins` when they sit on several rails. A regulator's own `load_current` is its
+//! quiescent draw from the input.
+//!
+//! The rail tree is found by walking the circuit graph from each rail through the regulators
+//! it feeds. Rails drawing more than their regulators supply, power nets that are neither
+//! supplied by a regulator nor feed one, and regulated rails no input rail reaches are
+//! reported.
+
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::path::Path;
+
+use pcb_sch::physical::{PhysicalRange, PhysicalUnitDims, PhysicalValue};
+use pcb_sch::{AttributeValue, Instance, InstanceKind, InstanceRef, NetKind, Schematic};
+use rust_decimal::prelude::{FromPrimitive, ToPrimitive};
+use rust_decimal::Decimal;
+use serde::Serialize;
+use starlark::errors::EvalSeverity;
+
+use crate::attrs::power as attrs;
+use crate::graph::{CircuitGraph, FactorType, GraphError, PortPath};
+use crate::lang::error::CategorizedDiagnostic;
+use crate::lang::module::ModulePath;
+use crate::{Diagnostic, Diagnostics};
+
+/// A rail draws more current than its regulators supply
+pub const KIND_OVERLOAD: &str = "power.overload";
+/// A power net is neither supplied by a regulator nor feeds one
+pub const KIND_ORPHAN: &str = "power.orphan";
+/// A load sits on several rails without `load_pins` to pick one, or has invalid power properties
+pub const KIND_INVALID: &str = "power.invalid";
+
+/// Current drawn from a rail by one component (A)
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Load {
+    pub component: String,
+    pub current: f64,
+}
+
+/// A power rail and the rails derived from it
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Rail {
+    pub net: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub voltage: Option<String>,
+    /// Regulators supplying the rail; empty for input rails
+    pub regulators: Vec<String>,
+    /// Combined output capacity of the regulators (A), if they all declare one
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub capacity: Option<f64>,
+    /// Components drawing from the rail, other than the regulators it feeds
+    pub loads: Vec<Load>,
+    /// Total current drawn from the rail, including what its child rails draw (A)
+    pub current: f64,
+    /// Current the regulators draw from the parent rail to supply this one (A)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub input_current: Option<f64>,
+    pub children: Vec<Rail>,
+}
+
+/// A problem found in the power tree
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PowerIssue {
+    pub kind: &'static str,
+    pub severity: &'static str,
+    pub message: String,
+}
+
+/// Rail tree of a design: input rails at the root, regulated rails below the rail feeding them
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct PowerTree {
+    pub rails: Vec<Rail>,
+    /// Power nets outside the tree, with the loads declared on them
+    pub orphans: Vec<Rail>,
+    pub issues: Vec<PowerIssue>,
+}
+
+struct Regulator {
+    label: String,
+    path: ModulePath,
+    /// Rail the regulator draws from, if its input pins are connected
+    input: Option<String>,
+    outputs: Vec<String>,
+    max_current: Option<f64>,
+    efficiency: Option<f64>,
+    quiescent: f64,
+}
+
+/// Current in A as a display string to the microamp, e.g. `150mA`
+pub fn format_current(amps: f64) -> String {
+    let value = Decimal::from_f64(amps).unwrap_or_default().round_dp(6);
+    PhysicalValue::from_decimal(value, Decimal::ZERO, PhysicalUnitDims::CURRENT).to_string()
+}
+
+fn pin_list(value: Option<&AttributeValue>) -> Vec<String> {
+    match value {
+        Some(AttributeValue::String(pin)) => vec![pin.clone()],
+        Some(AttributeValue::Array(pins)) => pins
+            .iter()
+            .filter_map(|pin| pin.string().map(str::to_string))
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+fn current(component: &Instance, attr: &str) -> Option<f64> {
+    let value = component
+        .physical_attr(&[attr])
+        .filter(|value| value.unit == PhysicalUnitDims::CURRENT)?;
+    value.value.to_f64()
+}
+
+fn voltage(schematic: &Schematic, net: &str) -> Option<PhysicalRange> {
+    schematic
+        .nets
+        .get(net)?
+        .properties
+        .get(crate::attrs::net::VOLTAGE)?
+        .string()?
+        .parse()
+        .ok()
+}
+
+struct Analysis<'a> {
+    schematic: &'a Schematic,
+    graph: CircuitGraph,
+    regulators: Vec<Regulator>,
+    /// Direct loads by net
+    loads: HashMap<String, Vec<Load>>,
+    /// Regulators (indices) feeding from each net, and supplying each net
+    feeds: BTreeMap<String, Vec<usize>>,
+    supplied_by: BTreeMap<String, Vec<usize>>,
+    issues: Vec<PowerIssue>,
+}
+
+impl Analysis<'_> {
+    /// Net connected to `pin` of the component at `path`
+    fn net_of(&self, path: &ModulePath, pin: &str) -> Option<String> {
+        let port = self.graph.port_id(&PortPath::new(path.clone(), pin))?;
+        self.graph.port_factors(port).iter().find_map(|&factor| {
+            match self.graph.factor_type(factor) {
+                FactorType::Net(name) => Some(name.clone()),
+                FactorType::Component(_) => None,
+            }
+        })
+    }
+
+    fn nets_of(&self, path: &ModulePath, pins: &[String]) -> Vec<String> {
+        let nets: BTreeSet<String> = pins.iter().filter_map(|p| self.net_of(path, p)).collect();
+        nets.into_iter().collect()
+    }
+
+    fn issue(&mut self, kind: &'static str, severity: &'static str, message: String) {
+        self.issues.push(PowerIssue {
+            kind,
+            severity,
+            message,
+        });
+    }
+
+    /// Rail `net` and its subtree. `visiting` guards against regulator loops.
+    fn rail(&mut self, net: &str, visiting: &mut HashSet<String>) -> Rail {
+        visiting.insert(net.to_string());
+        let regulator_ids = self.supplied_by.get(net).cloned().unwrap_or_default();
+        let mut loads = self.loads.get(net).cloned().unwrap_or_default();
+        let mut children = Vec::new();
+        for id in self.feeds.get(net).cloned().unwrap_or_default() {
+            let regulator = &self.regulators[id];
+            if regulator.quiescent > 0.0 {
+                loads.push(Load {
+                    component: regulator.label.clone(),
+                    current: regulator.quiescent,
+                });
+            }
+            let outputs = self.nets_of(&regulator.path, &regulator.outputs);
+            for output in outputs {
+                // A rail fed by several regulators is listed under the first rail reached
+                if visiting.contains(&output) || children.iter().any(|c: &Rail| c.net == output) {
+                    continue;
+                }
+                children.push(self.rail(&output, visiting));
+            }
+        }
+        loads.sort_by(|a, b| a.component.cmp(&b.component));
+
+        let current = loads.iter().map(|l| l.current).sum::<f64>()
+            + children.iter().filter_map(|c| c.input_current).sum::<f64>();
+        let capacity = if regulator_ids.is_empty() {
+            None
+        } else {
+            regulator_ids
+                .iter()
+                .map(|&id| self.regulators[id].max_current)
+                .sum::<Option<f64>>()
+        };
+        let input_current = (!regulator_ids.is_empty()).then(|| self.input_current(net, current));
+        let regulators: Vec<String> = regulator_ids
+            .iter()
+            .map(|&id| self.regulators[id].label.clone())
+            .collect();
+
+        if let Some(capacity) = capacity.filter(|&capacity| current > capacity) {
+            self.issue(
+                KIND_OVERLOAD,
+                "error",
+                format!(
+                    "Rail '{net}' draws {}, over the {} supplied by {}",
+                    format_current(current),
+                    format_current(capacity),
+                    regulators.join(", ")
+                ),
+            );
+        }
+
+        Rail {
+            net: net.to_string(),
+            voltage: self.schematic.nets.get(net).and_then(|n| {
+                n.properties
+                    .get(crate::attrs::net::VOLTAGE)
+                    .and_then(|v| v.string().map(str::to_string))
+            }),
+            regulators,
+            capacity,
+            loads,
+            current,
+            input_current,
+            children,
+        }
+    }
+
+    /// Current drawn from the input rails to supply `current` on the regulated rail `net`.
+    /// Regulators in parallel share the current in proportion to their capacity when they all
+    /// declare one, and equally otherwise.
+    fn input_current(&self, net: &str, current: f64) -> f64 {
+        let ids = self
+            .supplied_by
+            .get(net)
+            .map(Vec::as_slice)
+            .unwrap_or_default();
+        let capacities: Option<Vec<f64>> = ids
+            .iter()
+            .map(|&id| self.regulators[id].max_current)
+            .collect();
+        let total_capacity = capacities
+            .as_ref()
+            .map(|c| c.iter().sum::<f64>())
+            .filter(|&total| total > 0.0);
+        ids.iter()
+            .enumerate()
+            .map(|(i, &id)| {
+                let share = match (&capacities, total_capacity) {
+                    (Some(capacities), Some(total)) => current * capacities[i] / total,
+                    _ => current / ids.len() as f64,
+                };
+                self.regulator_input_current(&self.regulators[id], net, share)
+            })
+            .sum()
+    }
+
+    /// Current `regulator` draws from its input to supply `current` on `net`
+    fn regulator_input_current(&self, regulator: &Regulator, net: &str, current: f64) -> f64 {
+        let Some(efficiency) = regulator.efficiency else {
+            return current;
+        };
+        let nominal =
+            |net: &str| voltage(self.schematic, net).and_then(|range| range.nominal().to_f64());
+        match (regulator.input.as_deref().and_then(nominal), nominal(net)) {
+            (Some(v_in), Some(v_out)) if v_in > 0.0 => v_out * current / (efficiency * v_in),
+            _ => current,
+        }
+    }
+}
+
+/// Extract the power tree of `schematic`
+pub fn analyze(schematic: &Schematic) -> Result<PowerTree, GraphError> {
+    // Component pins by net, for the circuit graph
+    let mut net_to_ports: HashMap<String, Vec<PortPath>> = HashMap::new();
+    let mut component_pins: HashMap<ModulePath, Vec<String>> = HashMap::new();
+    for net in schematic.nets.values() {
+        for port_ref in &net.ports {
+            let Some((pin, component_path)) = port_ref.instance_path.split_last() else {
+                continue;
+            };
+            let path = ModulePath {
+                segments: component_path.to_vec(),
+            };
+            net_to_ports
+                .entry(net.name.clone())
+                .or_default()
+                .push(PortPath::new(path.clone(), pin.clone()));
+            component_pins.entry(path).or_default().push(pin.clone());
+        }
+    }
+    let graph = CircuitGraph::new(net_to_ports, component_pins, HashSet::new())?;
+
+    let mut analysis = Analysis {
+        schematic,
+        graph,
+        regulators: Vec::new(),
+        loads: HashMap::new(),
+        feeds: BTreeMap::new(),
+        supplied_by: BTreeMap::new(),
+        issues: Vec::new(),
+    };
+
+    let mut components: Vec<(&InstanceRef, &Instance)> = schematic
+        .instances
+        .iter()
+        .filter(|(_, inst)| inst.kind == InstanceKind::Component && !inst.dnp())
+        .collect();
+    components.sort_by(|a, b| a.0.instance_path.cmp(&b.0.instance_path));
+
+    for (instance_ref, component) in components {
+        let path = ModulePath {
+            segments: instance_ref.instance_path.clone(),
+        };
+        let label = component
+            .reference_designator
+            .clone()
+            .unwrap_or_else(|| path.to_string());
+        let inputs = pin_list(component.attributes.get(attrs::REGULATOR_INPUT));
+        let outputs = pin_list(component.attributes.get(attrs::REGULATOR_OUTPUT));
+        let load_current = current(component, attrs::LOAD_CURRENT);
+
+        if !inputs.is_empty() && !outputs.is_empty() {
+            let efficiency = component
+                .attributes
+                .get(attrs::EFFICIENCY)
+                .and_then(|value| match value {
+                    AttributeValue::Number(n) => Some(*n),
+                    AttributeValue::String(s) => s.parse().ok(),
+                    _ => None,
+                });
+            if let Some(efficiency) = efficiency.filter(|e| !(*e > 0.0 && *e <= 1.0)) {
+                analysis.issue(
+                    KIND_INVALID,
+                    "warning",
+                    format!("{label}: efficiency {efficiency} is not between 0 and 1"),
+                );
+            }
+            let id = analysis.regulators.len();
+            // Regulators with several input rails are fed from the first
+            let input = analysis.nets_of(&path, &inputs).into_iter().next();
+            if let Some(input) = &input {
+                analysis.feeds.entry(input.clone()).or_default().push(id);
+            }
+            for output in analysis.nets_of(&path, &outputs) {
+                analysis.supplied_by.entry(output).or_default().push(id);
+            }
+            analysis.regulators.push(Regulator {
+                label,
+                path,
+                input,
+                outputs,
+                max_current: current(component, attrs::MAX_CURRENT),
+                efficiency: efficiency.filter(|e| *e > 0.0 && *e <= 1.0),
+                quiescent: load_current.unwrap_or(0.0),
+            });
+            continue;
+        }
+
+        let Some(load_current) = load_current else {
+            continue;
+        };
+        let load_pins = pin_list(component.attributes.get(attrs::LOAD_PINS));
+        let rails = if load_pins.is_empty() {
+            let pins: Vec<String> = component.children.keys().cloned().collect();
+            analysis
+                .nets_of(&path, &pins)
+                .into_iter()
+                .filter(|net| {
+                    schematic
+                        .nets
+                        .get(net)
+                        .is_some_and(|n| n.kind == NetKind::Power)
+                })
+                .collect()
+        } else {
+            analysis.nets_of(&path, &load_pins)
+        };
+        match rails.as_slice() {
+            [] => analysis.issue(
+                KIND_INVALID,
+                "warning",
+                format!("{label}: `load_current` is set but the component is on no power net"),
+            ),
+            [rail] => analysis.loads.entry(rail.clone()).or_default().push(Load {
+                component: label,
+                current: load_current,
+            }),
+            rails => analysis.issue(
+                KIND_INVALID,
+                "warning",
+                format!(
+                    "{label}: `load_current` is ambiguous on rails {}; set `load_pins` to the supply pins",
+                    rails.join(", ")
+                ),
+            ),
+        }
+    }
+
+    // Input rails: nets feeding a regulator without being supplied by one
+    let roots: Vec<String> = analysis
+        .feeds
+        .keys()
+        .filter(|net| !analysis.supplied_by.contains_key(*net))
+        .cloned()
+        .collect();
+    let mut visiting = HashSet::new();
+    let mut rails = Vec::new();
+    for root in roots {
+        rails.push(analysis.rail(&root, &mut visiting));
+    }
+
+    let mut power_nets: Vec<&String> = schematic
+        .nets
+        .values()
+        .filter(|net| net.kind == NetKind::Power)
+        .map(|net| &net.name)
+        .collect();
+    power_nets.sort();
+    let mut orphans = Vec::new();
+
+    // Regulated rails no input rail reaches: their regulators' inputs are unconnected, or
+    // the regulators feed each other in a loop. Rails whose regulators have no input go first,
+    // so the rails below them are listed in their subtree.
+    let mut unreached: Vec<String> = analysis
+        .supplied_by
+        .keys()
+        .filter(|net| !visiting.contains(*net))
+        .cloned()
+        .collect();
+    unreached.sort_by_key(|net| {
+        analysis.supplied_by[net]
+            .iter()
+            .any(|&id| analysis.regulators[id].input.is_some())
+    });
+    for net in unreached {
+        if visiting.contains(&net) {
+            continue;
+        }
+        let regulators: Vec<&str> = analysis.supplied_by[&net]
+            .iter()
+            .map(|&id| analysis.regulators[id].label.as_str())
+            .collect();
+        let message = format!(
+            "Rail '{net}' is supplied by {}, but no input rail feeds it",
+            regulators.join(", ")
+        );
+        analysis.issue(KIND_ORPHAN, "warning", message);
+        orphans.push(analysis.rail(&net, &mut visiting));
+    }
+
+    for net in power_nets {
+        if visiting.contains(net)
+            || analysis.feeds.contains_key(net)
+            || analysis.supplied_by.contains_key(net)
+        {
+            continue;
+        }
+        analysis.issue(
+            KIND_ORPHAN,
+            "warning",
+            format!("Power net '{net}' is not supplied by a regulator and feeds none"),
+        );
+        orphans.push(analysis.rail(net, &mut visiting));
+    }
+
+    Ok(PowerTree {
+        rails,
+        orphans,
+        issues: analysis.issues,
+    })
+}
+
+impl PowerTree {
+    /// The issues as diagnostics, reported against `path` (the root source file)
+    pub fn diagnostics(&self, path: &Path) -> Diagnostics {
+        let mut diagnostics = Diagnostics::default();
+        for issue in &self.issues {
+            let severity = match issue.severity {
+                "error" => EvalSeverity::Error,
+                _ => EvalSeverity::Warning,
+            };
+            let categorized =
+                CategorizedDiagnostic::new(issue.message.clone(), issue.kind.to_string())
+                    .expect("power diagnostic kind should be valid");
+            diagnostics.push(
+                Diagnostic::new(issue.message.clone(), severity, path)
+                    .with_source_error(Some(categorized)),
+            );
+        }
+        diagnostics
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pcb_sch::{ModuleRef, Net};
+
+    struct Builder {
+        module: ModuleRef,
+        schematic: Schematic,
+        nets: BTreeMap<String, Net>,
+    }
+
+    impl Builder {
+        fn new() -> Self {
+            Self {
+                module: ModuleRef::new("/board.zen", "<root>"),
+                schematic: Schematic::new(),
+                nets: BTreeMap::new(),
+            }
+        }
+
+        fn net(&mut self, name: &str, kind: NetKind, voltage: Option<&str>) -> &mut Self {
+            let mut net = Net::new(kind, name, self.nets.len() as u64);
+            if let Some(voltage) = voltage {
+                net.add_property(
+                    crate::attrs::net::VOLTAGE,
+                    AttributeValue::String(voltage.to_string()),
+                );
+            }
+            self.nets.insert(name.to_string(), net);
+            self
+        }
+
+        fn component(
+            &mut self,
+            name: &str,
+            pins: &[(&str, &str)],
+            attributes: &[(&str, AttributeValue)],
+        ) -> &mut Self {
+            let comp_ref = InstanceRef::new(self.module.clone(), vec![name.to_string()]);
+            let mut comp = Instance::component(self.module.clone());
+            comp.set_reference_designator(name);
+            for (key, value) in attributes {
+                comp.add_attribute(*key, value.clone());
+            }
+            for (pin, net) in pins {
+                let pin_ref = comp_ref.append(pin.to_string());
+                comp.add_child(*pin, pin_ref.clone());
+                self.schematic
+                    .add_instance(pin_ref.clone(), Instance::port(self.module.clone()));
+                self.nets.get_mut(*net).unwrap().add_port(pin_ref);
+            }
+            self.schematic.add_instance(comp_ref, comp);
+            self
+        }
+
+        fn build(&mut self) -> Schematic {
+            let mut schematic = std::mem::take(&mut self.schematic);
+            for net in std::mem::take(&mut self.nets).into_values() {
+                schematic.add_net(net);
+            }
+            schematic
+        }
+    }
+
+    fn s(value: &str) -> AttributeValue {
+        AttributeValue::String(value.to_string())
+    }
+
+    fn board(max_current: &str) -> Schematic {
+        let mut b = Builder::new();
+        b.net("VBUS", NetKind::Power, Some("5V"))
+            .net("3V3", NetKind::Power, Some("3.3V"))
+            .net("1V8", NetKind::Power, Some("1.8V"))
+            .net("GND", NetKind::Ground, None)
+            .net("VBAT", NetKind::Power, None)
+            .component(
+                "U1",
+                &[("VIN", "VBUS"), ("VOUT", "3V3"), ("GND", "GND")],
+                &[
+                    ("regulator_input", s("VIN")),
+                    ("regulator_output", s("VOUT")),
+                    ("max_current", s(max_current)),
+                    ("efficiency", s("0.9")),
+                ],
+            )
+            .component(
+                "U2",
+                &[("IN", "3V3"), ("OUT", "1V8"), ("GND", "GND")],
+                &[
+                    ("regulator_input", s("IN")),
+                    ("regulator_output", s("OUT")),
+                    ("max_current", s("300mA")),
+                    ("load_current", s("1mA")),
+                ],
+            )
+            .component(
+                "U3",
+                &[("VDD", "3V3"), ("VDDCORE", "1V8"), ("GND", "GND")],
+                &[
+                    ("load_current", s("200mA")),
+                    ("load_pins", AttributeValue::Array(vec![s("VDD")])),
+                ],
+            )
+            .component(
+                "U4",
+                &[("VDD", "1V8"), ("GND", "GND")],
+                &[("load_current", s("100mA"))],
+            )
+            .component(
+                "U5",
+                &[("VDD", "3V3"), ("VCORE", "1V8")],
+                &[("load_current", s("5mA"))],
+            );
+        b.build()
+    }
+
+    fn close(a: f64, b: f64) -> bool {
+        (a - b).abs() < 1e-9
+    }
+
+    #[test]
+    fn builds_rail_tree_and_budget() {
+        let tree = analyze(&board("1A")).unwrap();
+        assert_eq!(tree.rails.len(), 1);
+        let vbus = &tree.rails[0];
+        assert_eq!(vbus.net, "VBUS");
+        assert!(vbus.regulators.is_empty());
+
+        let v3 = &vbus.children[0];
+        assert_eq!(v3.regulators, vec!["U1"]);
+        assert_eq!(v3.capacity, Some(1.0));
+        // U3 200 mA, U2 quiescent 1 mA, and U2 passes 100 mA through from 1V8
+        assert!(close(v3.current, 0.301), "{}", v3.current);
+        // Switching: 3.3 V * 0.301 A / (0.9 * 5 V)
+        assert!(close(v3.input_current.unwrap(), 3.3 * 0.301 / 4.5));
+        assert!(close(vbus.current, v3.input_current.unwrap()));
+
+        let v18 = &v3.children[0];
+        assert_eq!(v18.net, "1V8");
+        assert!(close(v18.current, 0.1));
+        assert!(close(v18.input_current.unwrap(), 0.1));
+
+        assert_eq!(
+            tree.orphans
+                .iter()
+                .map(|r| r.net.as_str())
+                .collect::<Vec<_>>(),
+            vec!["VBAT"]
+        );
+        let kinds: Vec<_> = tree.issues.iter().map(|i| i.kind).collect();
+        assert_eq!(kinds, vec![KIND_INVALID, KIND_ORPHAN]);
+        assert!(tree.issues[0].message.starts_with("U5:"));
+    }
+
+    #[test]
+    fn reports_overloaded_rails() {
+        let tree = analyze(&board("250mA")).unwrap();
+        let overloads: Vec<_> = tree
+            .issues
+            .iter()
+            .filter(|i| i.kind == KIND_OVERLOAD)
+            .collect();
+        assert_eq!(overloads.len(), 1);
+        assert_eq!(overloads[0].severity, "error");
+        assert!(
+            overloads[0]
+                .message
+                .starts_with("Rail '3V3' draws 301mA, over the 250mA"),
+            "{}",
+            overloads[0].message
+        );
+        assert!(tree.diagnostics(Path::new("/board.zen")).has_errors());
+    }
+
+    fn regulator(
+        input: &str,
+        output: &str,
+        extra: &[(&'static str, &str)],
+    ) -> Vec<(&'static str, AttributeValue)> {
+        let mut attributes = vec![
+            ("regulator_input", s(input)),
+            ("regulator_output", s(output)),
+        ];
+        attributes.extend(extra.iter().map(|(key, value)| (*key, s(value))));
+        attributes
+    }
+
+    #[test]
+    fn chains_switching_regulators() {
+        let mut b = Builder::new();
+        b.net("12V", NetKind::Power, Some("12V"))
+            .net("5V", NetKind::Power, Some("5V"))
+            .net("3V3", NetKind::Power, Some("3.3V"))
+            .component(
+                "U1",
+                &[("VIN", "12V"), ("VOUT", "5V")],
+                &regulator("VIN", "VOUT", &[("efficiency", "0.9")]),
+            )
+            .component(
+                "U2",
+                &[("VIN", "5V"), ("VOUT", "3V3")],
+                &regulator("VIN", "VOUT", &[("efficiency", "0.8")]),
+            )
+            .component("U3", &[("VDD", "3V3")], &[("load_current", s("1A"))]);
+        let tree = analyze(&b.build()).unwrap();
+
+        let v12 = &tree.rails[0];
+        let v5 = &v12.children[0];
+        let v3 = &v5.children[0];
+        assert_eq!(
+            (v12.net.as_str(), v5.net.as_str(), v3.net.as_str()),
+            ("12V", "5V", "3V3")
+        );
+        let from_5v = 3.3 / (0.8 * 5.0);
+        assert!(close(v3.input_current.unwrap(), from_5v));
+        assert!(close(v5.current, from_5v));
+        assert!(close(v12.current, 5.0 * from_5v / (0.9 * 12.0)));
+        assert!(tree.issues.is_empty(), "{:?}", tree.issues);
+    }
+
+    #[test]
+    fn sums_regulators_in_parallel() {
+        let mut b = Builder::new();
+        b.net("5V", NetKind::Power, Some("5V"))
+            .net("3V3", NetKind::Power, Some("3.3V"))
+            .component(
+                "U1",
+                &[("VIN", "5V"), ("VOUT", "3V3")],
+                &regulator(
+                    "VIN",
+                    "VOUT",
+                    &[("max_current", "300mA"), ("efficiency", "0.9")],
+                ),
+            )
+            .component(
+                "U2",
+                &[("VIN", "5V"), ("VOUT", "3V3")],
+                &regulator("VIN", "VOUT", &[("max_current", "100mA")]),
+            )
+            .component("U3", &[("VDD", "3V3")], &[("load_current", s("600mA"))]);
+        let tree = analyze(&b.build()).unwrap();
+
+        let v3 = &tree.rails[0].children[0];
+        assert_eq!(v3.regulators, vec!["U1", "U2"]);
+        assert_eq!(v3.capacity, Some(0.4));
+        // U1 supplies three quarters of the load through its switcher, U2 the rest linearly
+        let expected = 3.3 * 0.45 / (0.9 * 5.0) + 0.15;
+        assert!(
+            close(v3.input_current.unwrap(), expected),
+            "{:?}",
+            v3.input_current
+        );
+        assert!(close(tree.rails[0].current, expected));
+
+        assert_eq!(tree.issues.len(), 1, "{:?}", tree.issues);
+        assert_eq!(tree.issues[0].kind, KIND_OVERLOAD);
+        assert!(
+            tree.issues[0]
+                .message
+                .starts_with("Rail '3V3' draws 600mA, over the 400mA supplied by U1, U2"),
+            "{}",
+            tree.issues[0].message
+        );
+    }
+
+    #[test]
+    fn reports_rails_with_unconnected_regulator_input() {
+        let mut b = Builder::new();
+        b.net("3V3", NetKind::Power, Some("3.3V"))
+            .net("1V8", NetKind::Power, Some("1.8V"))
+            // U1's input pin is left unconnected
+            .component(
+                "U1",
+                &[("VOUT", "3V3")],
+                &regulator("VIN", "VOUT", &[("max_current", "500mA")]),
+            )
+            .component(
+                "U2",
+                &[("VIN", "3V3"), ("VOUT", "1V8")],
+                &regulator("VIN", "VOUT", &[]),
+            )
+            .component("U3", &[("VDD", "1V8")], &[("load_current", s("50mA"))]);
+        let tree = analyze(&b.build()).unwrap();
+
+        assert!(tree.rails.is_empty());
+        assert_eq!(tree.orphans.len(), 1);
+        let v3 = &tree.orphans[0];
+        assert_eq!(v3.net, "3V3");
+        assert_eq!(v3.regulators, vec!["U1"]);
+        assert_eq!(v3.children[0].net, "1V8");
+        assert!(close(v3.current, 0.05));
+
+        let kinds: Vec<_> = tree.issues.iter().map(|i| i.kind).collect();
+        assert_eq!(kinds, vec![KIND_ORPHAN]);
+        assert_eq!(
+            tree.issues[0].message,
+            "Rail '3V3' is supplied by U1, but no input rail feeds it"
+        );
+    }
+}
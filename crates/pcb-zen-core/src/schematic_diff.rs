@@ -0,0 +1,475 @@
+//! Connectivity diff between two evaluations of a design.
+//!
+//! Components are matched by instance path and nets by name. Paths of the old design are
+//! first remapped through the new design's `moved()` directives, so a renamed component or
+//! net shows up as a rename rather than as a removal and an addition. A net renamed without
+//! a `moved()` is still matched if its pins are unchanged. When several old paths are moved
+//! onto the same new path, the collision is reported and only one of them is matched.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::Path;
+
+use pcb_sch::{AttributeValue, InstanceKind, InstanceRef, Schematic};
+use serde::Serialize;
+
+use crate::attrs;
+use crate::moved::Remapper;
+
+/// Attributes that describe how a component is built rather than what it is
+const IGNORED_ATTRIBUTES: &[&str] = &[attrs::PADS, attrs::PREFIX];
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct SchematicDiff {
+    pub added_components: Vec<ComponentSummary>,
+    pub removed_components: Vec<ComponentSummary>,
+    pub changed_components: Vec<ComponentChange>,
+    pub added_nets: Vec<NetSummary>,
+    pub removed_nets: Vec<NetSummary>,
+    pub changed_nets: Vec<NetChange>,
+    pub moved_collisions: Vec<MovedCollision>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ComponentSummary {
+    pub path: String,
+    pub reference: Option<String>,
+    pub mpn: Option<String>,
+    pub value: Option<String>,
+}
+
+/// A component present in both designs that was renamed or whose properties changed
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ComponentChange {
+    pub path: String,
+    /// Path in the old design, if `moved()` renamed it
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub old_path: Option<String>,
+    pub reference: Option<String>,
+    pub properties: Vec<PropertyChange>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PropertyChange {
+    pub name: String,
+    pub old: Option<String>,
+    pub new: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct NetSummary {
+    pub name: String,
+    pub ports: Vec<String>,
+}
+
+/// A net present in both designs that was renamed or whose pins changed
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct NetChange {
+    pub name: String,
+    /// Name in the old design, if it was renamed
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub old_name: Option<String>,
+    pub added_ports: Vec<String>,
+    pub removed_ports: Vec<String>,
+}
+
+/// Old paths that `moved()` maps onto the same new path. The one already at `path`, or else the
+/// first, is matched; the others are reported as removed.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct MovedCollision {
+    pub path: String,
+    pub old_paths: Vec<String>,
+}
+
+impl SchematicDiff {
+    pub fn is_empty(&self) -> bool {
+        self.added_components.is_empty()
+            && self.removed_components.is_empty()
+            && self.changed_components.is_empty()
+            && self.added_nets.is_empty()
+            && self.removed_nets.is_empty()
+            && self.changed_nets.is_empty()
+            && self.moved_collisions.is_empty()
+    }
+}
+
+/// A component as compared: its properties, with paths under the design root made relative
+struct Component {
+    reference: Option<String>,
+    mpn: Option<String>,
+    value: Option<String>,
+    properties: BTreeMap<String, String>,
+}
+
+fn dotted(instance_ref: &InstanceRef) -> String {
+    instance_ref.instance_path.join(".")
+}
+
+fn render_attribute(value: &AttributeValue, root: &Path) -> String {
+    match value {
+        AttributeValue::String(s) | AttributeValue::Port(s) => Path::new(s)
+            .strip_prefix(root)
+            .map(|rel| rel.display().to_string())
+            .unwrap_or_else(|_| s.clone()),
+        AttributeValue::Number(n) => n.to_string(),
+        AttributeValue::Boolean(b) => b.to_string(),
+        AttributeValue::Array(items) => {
+            let items: Vec<_> = items.iter().map(|v| render_attribute(v, root)).collect();
+            format!("[{}]", items.join(", "))
+        }
+        AttributeValue::Json(json) => json.to_string(),
+    }
+}
+
+fn components(schematic: &Schematic, root: &Path) -> BTreeMap<String, Component> {
+    schematic
+        .instances
+        .iter()
+        .filter(|(_, inst)| inst.kind == InstanceKind::Component)
+        .map(|(instance_ref, inst)| {
+            let properties = inst
+                .attributes
+                .iter()
+                .filter(|(key, _)| {
+                    !key.starts_with("__") && !IGNORED_ATTRIBUTES.contains(&key.as_str())
+                })
+                .map(|(key, value)| (key.clone(), render_attribute(value, root)))
+                .collect();
+            let component = Component {
+                reference: inst.reference_designator.clone(),
+                mpn: inst.mpn(),
+                value: inst.value(),
+                properties,
+            };
+            (dotted(instance_ref), component)
+        })
+        .collect()
+}
+
+fn nets(schematic: &Schematic) -> BTreeMap<String, BTreeSet<String>> {
+    schematic
+        .nets
+        .iter()
+        .map(|(name, net)| (name.clone(), net.ports.iter().map(dotted).collect()))
+        .collect()
+}
+
+fn summary(path: &str, component: &Component) -> ComponentSummary {
+    ComponentSummary {
+        path: path.to_string(),
+        reference: component.reference.clone(),
+        mpn: component.mpn.clone(),
+        value: component.value.clone(),
+    }
+}
+
+/// Old entries keyed by their path in the new design, each with its old path
+type Remapped<T> = BTreeMap<String, (String, T)>;
+
+/// Key the `old` entries by their remapped path, returning the entries that lost a collision
+fn remap_keys<T>(
+    old: BTreeMap<String, T>,
+    remap: impl Fn(&str) -> String,
+    collisions: &mut Vec<MovedCollision>,
+) -> (Remapped<T>, Vec<(String, T)>) {
+    let mut grouped: BTreeMap<String, Vec<(String, T)>> = BTreeMap::new();
+    for (path, value) in old {
+        grouped.entry(remap(&path)).or_default().push((path, value));
+    }
+    let mut keyed = BTreeMap::new();
+    let mut dropped = Vec::new();
+    for (path, mut entries) in grouped {
+        if entries.len() > 1 {
+            collisions.push(MovedCollision {
+                path: path.clone(),
+                old_paths: entries
+                    .iter()
+                    .map(|(old_path, _)| old_path.clone())
+                    .collect(),
+            });
+        }
+        let kept = entries
+            .iter()
+            .position(|(old_path, _)| *old_path == path)
+            .unwrap_or(0);
+        keyed.insert(path, entries.remove(kept));
+        dropped.extend(entries);
+    }
+    (keyed, dropped)
+}
+
+/// Diff `old` against `new`. String properties that are paths under `old_root` or
+/// `new_root`, such as footprints, are compared relative to their root.
+pub fn diff(old: &Schematic, old_root: &Path, new: &Schematic, new_root: &Path) -> SchematicDiff {
+    let remapper = Remapper::from_path_map(new.moved_paths.clone());
+    let remap = |path: &str| remapper.remap(path).unwrap_or_else(|| path.to_string());
+    let mut diff = SchematicDiff::default();
+
+    // Components, keyed by their path in the new design
+    let (old_components, dropped) =
+        remap_keys(components(old, old_root), remap, &mut diff.moved_collisions);
+    for (old_path, component) in &dropped {
+        diff.removed_components.push(summary(old_path, component));
+    }
+    let new_components = components(new, new_root);
+
+    for (path, component) in &new_components {
+        let Some((old_path, old_component)) = old_components.get(path) else {
+            diff.added_components.push(summary(path, component));
+            continue;
+        };
+        let names: BTreeSet<&String> = old_component
+            .properties
+            .keys()
+            .chain(component.properties.keys())
+            .collect();
+        let properties: Vec<PropertyChange> = names
+            .into_iter()
+            .filter_map(|name| {
+                let old = old_component.properties.get(name);
+                let new = component.properties.get(name);
+                (old != new).then(|| PropertyChange {
+                    name: name.clone(),
+                    old: old.cloned(),
+                    new: new.cloned(),
+                })
+            })
+            .collect();
+        let old_path = (old_path != path).then(|| old_path.clone());
+        if old_path.is_some() || !properties.is_empty() {
+            diff.changed_components.push(ComponentChange {
+                path: path.clone(),
+                old_path,
+                reference: component.reference.clone(),
+                properties,
+            });
+        }
+    }
+    for (path, (old_path, component)) in &old_components {
+        if !new_components.contains_key(path) {
+            diff.removed_components.push(summary(old_path, component));
+        }
+    }
+
+    // Nets, keyed by their name in the new design, with pins renamed to match
+    let old_net_ports: BTreeMap<String, BTreeSet<String>> = nets(old)
+        .into_iter()
+        .map(|(name, ports)| (name, ports.iter().map(|p| remap(p)).collect()))
+        .collect();
+    let (mut old_nets, dropped) = remap_keys(old_net_ports, remap, &mut diff.moved_collisions);
+    for (old_name, ports) in dropped {
+        diff.removed_nets.push(NetSummary {
+            name: old_name,
+            ports: ports.into_iter().collect(),
+        });
+    }
+    let mut new_nets = nets(new);
+
+    // Unmatched nets with identical pins were renamed without a `moved()`
+    let unmatched_old: Vec<String> = old_nets
+        .keys()
+        .filter(|name| !new_nets.contains_key(*name))
+        .cloned()
+        .collect();
+    for old_key in unmatched_old {
+        let (old_name, ports) = &old_nets[&old_key];
+        if ports.is_empty() {
+            continue;
+        }
+        let renamed = new_nets
+            .iter()
+            .find(|(name, new_ports)| !old_nets.contains_key(*name) && *new_ports == ports)
+            .map(|(name, _)| name.clone());
+        if let Some(new_name) = renamed {
+            diff.changed_nets.push(NetChange {
+                name: new_name.clone(),
+                old_name: Some(old_name.clone()),
+                added_ports: Vec::new(),
+                removed_ports: Vec::new(),
+            });
+            old_nets.remove(&old_key);
+            new_nets.remove(&new_name);
+        }
+    }
+
+    for (name, ports) in &new_nets {
+        let Some((old_name, old_ports)) = old_nets.get(name) else {
+            diff.added_nets.push(NetSummary {
+                name: name.clone(),
+                ports: ports.iter().cloned().collect(),
+            });
+            continue;
+        };
+        let added_ports: Vec<String> = ports.difference(old_ports).cloned().collect();
+        let removed_ports: Vec<String> = old_ports.difference(ports).cloned().collect();
+        let old_name = (old_name != name).then(|| old_name.clone());
+        if old_name.is_some() || !added_ports.is_empty() || !removed_ports.is_empty() {
+            diff.changed_nets.push(NetChange {
+                name: name.clone(),
+                old_name,
+                added_ports,
+                removed_ports,
+            });
+        }
+    }
+    for (name, (old_name, ports)) in &old_nets {
+        if !new_nets.contains_key(name) {
+            diff.removed_nets.push(NetSummary {
+                name: old_name.clone(),
+                ports: ports.iter().cloned().collect(),
+            });
+        }
+    }
+    diff.changed_nets.sort_by(|a, b| a.name.cmp(&b.name));
+    diff
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pcb_sch::{Instance, ModuleRef, Net, NetKind};
+    use std::collections::HashMap;
+
+    /// A design at `root` with components `(path, mpn)` and nets `(name, pins)`
+    fn design(root: &str, components: &[(&str, &str)], nets: &[(&str, &[&str])]) -> Schematic {
+        let module = ModuleRef::new(format!("{root}/board.zen"), "<root>");
+        let instance_ref = |path: &str| {
+            InstanceRef::new(
+                module.clone(),
+                path.split('.').map(str::to_string).collect(),
+            )
+        };
+        let mut schematic = Schematic::new();
+        for (path, mpn) in components {
+            let inst = Instance::component(module.clone())
+                .with_attribute("mpn", mpn.to_string())
+                .with_attribute("footprint", format!("{root}/parts/R0402.kicad_mod"));
+            schematic.add_instance(instance_ref(path), inst);
+        }
+        for (id, (name, pins)) in nets.iter().enumerate() {
+            schematic.add_net(Net {
+                kind: NetKind::Normal,
+                id: id as u64,
+                name: name.to_string(),
+                ports: pins.iter().map(|p| instance_ref(p)).collect(),
+                properties: HashMap::new(),
+            });
+        }
+        schematic
+    }
+
+    #[test]
+    fn test_components_and_nets() {
+        let old = design(
+            "/old",
+            &[("R1", "RC0402-10K"), ("R2", "RC0402-1K"), ("C1", "GRM155")],
+            &[
+                ("VCC", &["R1.P1", "R2.P1"]),
+                ("OUT", &["R1.P2", "C1.P1"]),
+                ("GND", &["C1.P2"]),
+            ],
+        );
+        let new = design(
+            "/ws",
+            &[("R1", "RC0402-10K"), ("R2", "RC0402-2K2"), ("U1", "LM358")],
+            &[
+                ("VCC", &["R1.P1", "R2.P1", "U1.V+"]),
+                ("OUT", &["R1.P2"]),
+                ("GND", &["U1.V-"]),
+            ],
+        );
+        let diff = diff(&old, Path::new("/old"), &new, Path::new("/ws"));
+
+        assert_eq!(diff.added_components[0].path, "U1");
+        assert_eq!(diff.removed_components[0].path, "C1");
+        assert_eq!(diff.changed_components.len(), 1);
+        assert_eq!(
+            diff.changed_components[0].properties,
+            vec![PropertyChange {
+                name: "mpn".to_string(),
+                old: Some("RC0402-1K".to_string()),
+                new: Some("RC0402-2K2".to_string()),
+            }]
+        );
+
+        let changed: Vec<_> = diff.changed_nets.iter().map(|n| n.name.as_str()).collect();
+        assert_eq!(changed, vec!["GND", "OUT", "VCC"]);
+        assert_eq!(diff.changed_nets[2].added_ports, vec!["U1.V+"]);
+        assert_eq!(diff.changed_nets[1].removed_ports, vec!["C1.P1"]);
+    }
+
+    #[test]
+    fn test_moved_renames() {
+        let old = design(
+            "/ws",
+            &[("POW.PS1", "TPS54331"), ("R1", "RC0402-10K")],
+            &[("POW.VIN", &["POW.PS1.VIN", "R1.P1"]), ("FB", &["R1.P2"])],
+        );
+        let mut new = design(
+            "/ws",
+            &[("PS1", "TPS54331"), ("R1", "RC0402-10K")],
+            &[("VIN", &["PS1.VIN", "R1.P1"]), ("FEEDBACK", &["R1.P2"])],
+        );
+        new.moved_paths = HashMap::from([
+            ("POW.PS1".to_string(), "PS1".to_string()),
+            ("POW.VIN".to_string(), "VIN".to_string()),
+        ]);
+        let diff = diff(&old, Path::new("/ws"), &new, Path::new("/ws"));
+
+        assert!(diff.added_components.is_empty() && diff.removed_components.is_empty());
+        assert_eq!(diff.changed_components.len(), 1);
+        assert_eq!(
+            diff.changed_components[0].old_path.as_deref(),
+            Some("POW.PS1")
+        );
+        assert!(diff.changed_components[0].properties.is_empty());
+
+        // One net renamed by moved(), one recognized by its pins
+        assert!(diff.added_nets.is_empty() && diff.removed_nets.is_empty());
+        let renames: Vec<_> = diff
+            .changed_nets
+            .iter()
+            .map(|n| (n.old_name.as_deref().unwrap(), n.name.as_str()))
+            .collect();
+        assert_eq!(renames, vec![("FB", "FEEDBACK"), ("POW.VIN", "VIN")]);
+        assert!(diff.changed_nets.iter().all(|n| n.added_ports.is_empty()));
+        assert!(diff.moved_collisions.is_empty());
+    }
+
+    #[test]
+    fn test_moved_collisions_are_reported() {
+        let old = design(
+            "/ws",
+            &[("A.R1", "RC0402-10K"), ("B.R1", "RC0402-1K")],
+            &[("A.VIN", &["A.R1.P1"]), ("B.VIN", &["B.R1.P1"])],
+        );
+        let mut new = design("/ws", &[("R1", "RC0402-10K")], &[("VIN", &["R1.P1"])]);
+        new.moved_paths = [
+            ("A.R1", "R1"),
+            ("B.R1", "R1"),
+            ("A.VIN", "VIN"),
+            ("B.VIN", "VIN"),
+        ]
+        .into_iter()
+        .map(|(old, new)| (old.to_string(), new.to_string()))
+        .collect();
+        let diff = diff(&old, Path::new("/ws"), &new, Path::new("/ws"));
+
+        assert_eq!(
+            diff.moved_collisions,
+            vec![
+                MovedCollision {
+                    path: "R1".to_string(),
+                    old_paths: vec!["A.R1".to_string(), "B.R1".to_string()],
+                },
+                MovedCollision {
+                    path: "VIN".to_string(),
+                    old_paths: vec!["A.VIN".to_string(), "B.VIN".to_string()],
+                },
+            ]
+        );
+        // The first old path is matched and the other is removed rather than silently dropped
+        assert_eq!(diff.changed_components[0].old_path.as_deref(), Some("A.R1"));
+        assert_eq!(diff.removed_components[0].path, "B.R1");
+        assert_eq!(diff.removed_nets[0].name, "B.VIN");
+    }
+}
@@ -0,0 +1,184 @@
+//! Voltage-domain compatibility check.
+//!
+//! Components can declare per-pin voltage ratings with `pin_ratings`, and nets can declare
+//! their operating range with `voltage`. This check reports every pin whose net's voltage
+//! range reaches outside the pin's absolute maximum (an error) or recommended operating range
+//! (a warning). Nets or pins without a range, and pins of DNP components, are not checked.
+
+use std::path::Path;
+
+use pcb_sch::physical::PhysicalRange;
+use pcb_sch::{Instance, InstanceKind, InstanceRef, Net, Schematic};
+use starlark::errors::EvalSeverity;
+
+use crate::lang::error::CategorizedDiagnostic;
+use crate::{Diagnostic, Diagnostics};
+
+/// A net's voltage exceeds a pin's absolute maximum rating
+pub const KIND_ABS_MAX: &str = "electrical.voltage.abs_max";
+/// A net's voltage is outside a pin's recommended operating range
+pub const KIND_RECOMMENDED: &str = "electrical.voltage.recommended";
+
+/// Check every rated pin against the voltage of the net it is connected to.
+///
+/// Diagnostics are reported against `path`, the root source file.
+pub fn check(schematic: &Schematic, path: &Path) -> Diagnostics {
+    let mut diagnostics = Diagnostics::default();
+
+    let mut nets: Vec<&Net> = schematic.nets.values().collect();
+    nets.sort_by(|a, b| a.name.cmp(&b.name));
+
+    for net in nets {
+        let Some(net_voltage) = net
+            .properties
+            .get(crate::attrs::net::VOLTAGE)
+            .and_then(|v| v.string())
+            .and_then(|s| s.parse::<PhysicalRange>().ok())
+        else {
+            continue;
+        };
+
+        for port_ref in &net.ports {
+            let Some(port) = schematic.instances.get(port_ref) else {
+                continue;
+            };
+            let Some(pin) = pin_label(schematic, port_ref) else {
+                continue;
+            };
+
+            // Only report the recommended range when the absolute maximum holds, so each pin
+            // gets at most one diagnostic
+            let checks = [
+                (
+                    crate::attrs::ABS_MAX_VOLTAGE,
+                    KIND_ABS_MAX,
+                    EvalSeverity::Error,
+                    "absolute maximum rating",
+                ),
+                (
+                    crate::attrs::RECOMMENDED_VOLTAGE,
+                    KIND_RECOMMENDED,
+                    EvalSeverity::Warning,
+                    "recommended operating range",
+                ),
+            ];
+            for (attr, kind, severity, what) in checks {
+                let Some(rating) = rating(port, attr) else {
+                    continue;
+                };
+                let Some(margin) = net_voltage.excess_over(&rating) else {
+                    continue;
+                };
+                let message = format!(
+                    "Net '{}' ({net_voltage}) exceeds the {what} of {pin} ({rating}) by {margin}",
+                    net.name
+                );
+                diagnostics.push(voltage_diagnostic(message, kind, severity, path));
+                break;
+            }
+        }
+    }
+
+    diagnostics
+}
+
+fn rating(port: &Instance, attr: &str) -> Option<PhysicalRange> {
+    port.string_attr(&[attr])?.parse().ok()
+}
+
+/// `REF.PIN` for a component pin, or `None` for pins of DNP components (they are not fitted)
+fn pin_label(schematic: &Schematic, port_ref: &InstanceRef) -> Option<String> {
+    let (pin_name, component_path) = port_ref.instance_path.split_last()?;
+    let component_ref = InstanceRef::new(port_ref.module.clone(), component_path.to_vec());
+    let component = schematic.instances.get(&component_ref)?;
+    if component.kind != InstanceKind::Component || component.dnp() {
+        return None;
+    }
+    let owner = component
+        .reference_designator
+        .clone()
+        .unwrap_or_else(|| component_path.join("."));
+    Some(format!("{owner}.{pin_name}"))
+}
+
+fn voltage_diagnostic(
+    message: String,
+    kind: &str,
+    severity: EvalSeverity,
+    path: &Path,
+) -> Diagnostic {
+    let categorized = CategorizedDiagnostic::new(message.clone(), kind.to_string())
+        .expect("voltage diagnostic kind should be valid");
+
+    Diagnostic::new(message, severity, path).with_source_error(Some(categorized))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pcb_sch::{AttributeValue, ModuleRef, NetKind};
+
+    fn schematic(net_voltage: &str, abs_max: &str, recommended: &str) -> Schematic {
+        let module = ModuleRef::new("/board.zen", "<root>");
+        let comp_ref = InstanceRef::new(module.clone(), vec!["U1".to_string()]);
+        let pin_ref = comp_ref.append("VDD".to_string());
+
+        let mut schematic = Schematic::new();
+        let mut port = Instance::port(module.clone());
+        port.add_attribute(crate::attrs::ABS_MAX_VOLTAGE, abs_max.to_string())
+            .add_attribute(crate::attrs::RECOMMENDED_VOLTAGE, recommended.to_string());
+        schematic.add_instance(pin_ref.clone(), port);
+        let mut comp = Instance::component(module);
+        comp.set_reference_designator("U1")
+            .add_child("VDD", pin_ref.clone());
+        schematic.add_instance(comp_ref, comp);
+
+        let mut net = Net::new(NetKind::Power, "VCC", 1);
+        net.add_port(pin_ref).add_property(
+            crate::attrs::net::VOLTAGE,
+            AttributeValue::String(net_voltage.to_string()),
+        );
+        schematic.add_net(net);
+        schematic
+    }
+
+    fn run(schematic: &Schematic) -> Vec<(String, String)> {
+        check(schematic, Path::new("/board.zen"))
+            .diagnostics
+            .iter()
+            .map(|d| {
+                let kind = d
+                    .downcast_error_ref::<CategorizedDiagnostic>()
+                    .unwrap()
+                    .kind
+                    .clone();
+                (kind, d.body.clone())
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_within_ratings() {
+        let s = schematic("3–3.6 V", "-0.3–4 V", "1.8–3.6 V");
+        assert!(run(&s).is_empty());
+    }
+
+    #[test]
+    fn test_exceeds_recommended() {
+        let s = schematic("3–3.9 V", "-0.3–4 V", "1.8–3.6 V");
+        let diags = run(&s);
+        assert_eq!(diags.len(), 1);
+        assert_eq!(diags[0].0, KIND_RECOMMENDED);
+        assert!(diags[0].1.contains("U1.VDD"), "{}", diags[0].1);
+    }
+
+    #[test]
+    fn test_exceeds_abs_max_reports_margin() {
+        let s = schematic("4.5–5.5 V", "-0.3–4 V", "1.8–3.6 V");
+        let diags = run(&s);
+        assert_eq!(diags.len(), 1);
+        assert_eq!(diags[0].0, KIND_ABS_MAX);
+        assert!(diags[0].1.contains("'VCC'"), "{}", diags[0].1);
+        assert!(diags[0].1.contains("1.5V"), "{}", diags[0].1);
+    }
+}
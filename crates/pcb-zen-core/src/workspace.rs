@@ -8,7 +8,7 @@ use serde::{Deserialize, Serialize};
 use std::collections::BTreeMap;
 use std::path::{Path, PathBuf};
 
-use crate::config::{find_workspace_root, Lockfile, PcbToml, WorkspaceConfig};
+use crate::config::{find_workspace_root, LicensePolicy, Lockfile, PcbToml, WorkspaceConfig};
 use crate::FileProvider;
 
 fn is_default<T: Default + PartialEq>(value: &T) -> bool {
@@ -121,6 +121,19 @@ impl WorkspaceInfo {
             .and_then(|w| w.path.as_deref())
     }
 
+    /// Get package index location from workspace config
+    pub fn package_index(&self) -> Option<&str> {
+        self.config
+            .as_ref()
+            .and_then(|c| c.workspace.as_ref())
+            .and_then(|w| w.index.as_deref())
+    }
+
+    /// Get the license policy from the root pcb.toml
+    pub fn license_policy(&self) -> Option<&LicensePolicy> {
+        self.config.as_ref().and_then(|c| c.licenses.as_ref())
+    }
+
     /// Get minimum pcb toolchain version
     pub fn pcb_version(&self) -> Option<&str> {
         self.config
@@ -279,6 +279,30 @@ macro_rules! snapshot_eval {
     };
 }
 
+/// Evaluate `main_file` of a set of in-memory files
+pub fn eval_files(
+    files: &[(&str, &str)],
+    main_file: &str,
+) -> pcb_zen_core::WithDiagnostics<pcb_zen_core::EvalOutput> {
+    use pcb_zen_core::{CoreLoadResolver, EvalContext, NoopRemoteFetcher};
+    use std::sync::Arc;
+
+    let files = files
+        .iter()
+        .map(|(path, content)| (path.to_string(), content.to_string()))
+        .collect();
+    let load_resolver = Arc::new(CoreLoadResolver::new(
+        Arc::new(InMemoryFileProvider::new(files)),
+        Arc::new(NoopRemoteFetcher::default()),
+        PathBuf::from("/"),
+        true,
+        None,
+    ));
+    EvalContext::new(load_resolver)
+        .set_source_path(PathBuf::from(main_file))
+        .eval()
+}
+
 /// Strips common leading indentation from a string.
 /// This allows test code to be indented nicely without affecting the actual content.
 fn dedent(s: &str) -> String {
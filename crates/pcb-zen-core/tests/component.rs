@@ -784,3 +784,92 @@ snapshot_eval!(module_schematic_invalid, {
         )
     "#
 });
+
+snapshot_eval!(component_footprint_missing_pads, {
+    "SOT-23.kicad_mod" => r#"(footprint "SOT-23"
+        (pad "1" smd rect (at -1 -0.95) (size 1.3 0.6) (layers "F.Cu"))
+        (pad "2" smd rect (at -1 0.95) (size 1.3 0.6) (layers "F.Cu"))
+        (pad "3" smd rect (at 1 0) (size 1.3 0.6) (layers "F.Cu"))
+        (pad "" np_thru_hole circle (at 0 0) (size 0.8 0.8) (drill 0.8) (layers "*.Cu"))
+    )"#,
+    "test.zen" => r#"
+        Component(
+            name = "U1",
+            footprint = "SOT-23.kicad_mod",
+            pin_defs = {"IN": "1", "GND": "2", "OUT": "4", "EN": "5"},
+            pins = {
+                "IN": Net("IN"),
+                "GND": Net("GND"),
+                "OUT": Net("OUT"),
+                "EN": Net("EN"),
+            },
+        )
+    "#
+});
+
+/// Unmapped-pad warnings of a two-pin component on the footprint `kicad_mod`
+fn unmapped_pad_warnings(kicad_mod: &str) -> Vec<String> {
+    let result = common::eval_files(
+        &[
+            ("SOT-23-3.kicad_mod", kicad_mod),
+            (
+                "test.zen",
+                r#"
+                Component(
+                    name = "U1",
+                    footprint = "SOT-23-3.kicad_mod",
+                    pin_defs = {"IN": "1", "GND": "2"},
+                    pins = {"IN": Net("IN"), "GND": Net("GND")},
+                )
+                "#,
+            ),
+        ],
+        "test.zen",
+    );
+    assert!(result.is_success(), "{:?}", result.diagnostics);
+    result
+        .diagnostics
+        .warnings()
+        .into_iter()
+        .map(|d| d.body)
+        .filter(|body| body.starts_with("Footprint"))
+        .collect()
+}
+
+#[test]
+#[cfg(not(target_os = "windows"))]
+fn component_footprint_unmapped_pads() {
+    let warnings = unmapped_pad_warnings(
+        r#"(footprint "SOT-23"
+            (pad "1" smd rect (at -1 -0.95) (size 1.3 0.6) (layers "F.Cu"))
+            (pad "2" smd rect (at -1 0.95) (size 1.3 0.6) (layers "F.Cu"))
+            (pad "3" smd rect (at 1 0) (size 1.3 0.6) (layers "F.Cu"))
+            (pad "" np_thru_hole circle (at 0 0) (size 0.8 0.8) (drill 0.8) (layers "*.Cu"))
+        )"#,
+    );
+    assert_eq!(
+        warnings,
+        ["Footprint 'SOT-23' pad(s) 3 are not mapped to any symbol pin (3 footprint pads, 2 symbol pads)"]
+    );
+
+    // An edited footprint at the same path is parsed again rather than served from the cache
+    let warnings = unmapped_pad_warnings(
+        r#"(footprint "SOT-23"
+            (pad "1" smd rect (at -1 -0.95) (size 1.3 0.6) (layers "F.Cu"))
+            (pad "2" smd rect (at -1 0.95) (size 1.3 0.6) (layers "F.Cu"))
+        )"#,
+    );
+    assert!(warnings.is_empty(), "{warnings:?}");
+}
+
+snapshot_eval!(component_pin_ratings_unknown_field, {
+    "test.zen" => r#"
+        Component(
+            name = "U1",
+            footprint = "SOT-23",
+            pin_defs = {"VDD": "1", "GND": "2"},
+            pins = {"VDD": Net("VDD"), "GND": Net("GND")},
+            pin_ratings = {"VDD": {"max": "0V to 4V"}},
+        )
+    "#
+});
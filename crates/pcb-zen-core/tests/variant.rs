@@ -0,0 +1,76 @@
+mod common;
+
+use pcb_sch::variants::VARIANT_FIELD_PREFIX;
+
+/// Evaluate `test.zen` and convert it, returning the schematic and the variant warnings
+fn convert(files: &[(&str, &str)]) -> (pcb_sch::Schematic, Vec<String>) {
+    let result = common::eval_files(files, "test.zen");
+    assert!(result.is_success(), "{:?}", result.diagnostics);
+
+    let converted = result.output.unwrap().to_schematic_with_diagnostics();
+    let warnings = converted
+        .diagnostics
+        .warnings()
+        .into_iter()
+        .map(|d| d.body)
+        .filter(|body| body.starts_with("Variant"))
+        .collect();
+    (converted.output.unwrap(), warnings)
+}
+
+const POWER_ZEN: &str = r#"
+    Component(
+        name = "R1",
+        footprint = "SMD:0402",
+        pin_defs = {"1": "1", "2": "2"},
+        pins = {"1": Net("VIN"), "2": Net("VOUT")},
+    )
+
+    variant("lite", {
+        "R1": {"dnp": True},
+        "R9": {"dnp": True},
+    })
+"#;
+
+#[test]
+#[cfg(not(target_os = "windows"))]
+fn variant_paths_are_scoped_to_their_module() {
+    let (schematic, warnings) = convert(&[
+        ("Power.zen", POWER_ZEN),
+        (
+            "test.zen",
+            r#"
+            Power = Module("Power.zen")
+            Power(name = "Power")
+
+            variant("lite", {"Power": {"mpn": "TPS62130"}})
+            "#,
+        ),
+    ]);
+
+    assert_eq!(schematic.variants.len(), 1);
+    let lite = &schematic.variants[0];
+    assert_eq!(lite.name, "lite");
+    assert_eq!(
+        lite.overrides.keys().collect::<Vec<_>>(),
+        ["Power", "Power.R1", "Power.R9"]
+    );
+
+    let r1 = schematic
+        .instances
+        .iter()
+        .find(|(reference, _)| reference.instance_path.join(".") == "Power.R1")
+        .map(|(_, instance)| instance)
+        .expect("Power.R1 should be in the schematic");
+    assert_eq!(
+        r1.attributes
+            .get(&format!("{VARIANT_FIELD_PREFIX}lite"))
+            .and_then(|v| v.string()),
+        Some("DNP; MPN TPS62130")
+    );
+
+    assert_eq!(
+        warnings,
+        ["Variant 'lite' overrides 'Power.R9', which is not a component or a module with components"]
+    );
+}
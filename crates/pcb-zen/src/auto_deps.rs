@@ -10,6 +10,7 @@ use std::path::{Path, PathBuf};
 use crate::ast_utils::{skip_vendor, visit_string_literals};
 use crate::cache_index::{cache_base, find_lockfile_entry, CacheIndex};
 use crate::git;
+use crate::package_index::PackageIndex;
 use pcb_zen_core::config::{AssetDependencySpec, DependencySpec, Lockfile, PcbToml, KICAD_ASSETS};
 use pcb_zen_core::DefaultFileProvider;
 
@@ -35,11 +36,13 @@ struct CollectedImports {
 /// Resolution order for URL imports:
 /// 1. Workspace members (local packages)
 /// 2. Lockfile entries (pcb.sum) - fast path, no git operations
-/// 3. Remote package discovery (git tags) - slow path, cached per repo (skipped when offline)
+/// 3. Remote package discovery (the package index, else git tags) - slow path, cached per
+///    repo (skipped when offline)
 pub fn auto_add_zen_deps(
     workspace_root: &Path,
     packages: &BTreeMap<String, crate::workspace::MemberPackage>,
     lockfile: Option<&Lockfile>,
+    package_index: Option<&PackageIndex>,
     offline: bool,
 ) -> Result<AutoDepsSummary> {
     let package_imports = collect_imports_by_package(workspace_root, packages)?;
@@ -113,7 +116,7 @@ pub fn auto_add_zen_deps(
             }
 
             if let Some(ref idx) = index {
-                match idx.find_or_discover_remote_package(url) {
+                match idx.find_or_discover_remote_package(url, package_index) {
                     Ok(Some((module_path, version))) => {
                         deps_to_add.push((module_path, version, false));
                         summary.discovered_remote += 1;
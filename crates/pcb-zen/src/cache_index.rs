@@ -3,11 +3,10 @@
 use anyhow::{Context, Result};
 use pcb_zen_core::config::Lockfile;
 use rusqlite::{params, Connection, OptionalExtension};
-use semver::Version;
-use std::collections::BTreeMap;
 use std::path::PathBuf;
 
 use crate::git;
+use crate::package_index::PackageIndex;
 use crate::tags;
 
 /// Bump this when changing table schemas to auto-reset the cache.
@@ -174,6 +173,7 @@ impl CacheIndex {
     pub fn find_or_discover_remote_package(
         &self,
         file_url: &str,
+        package_index: Option<&PackageIndex>,
     ) -> Result<Option<(String, String)>> {
         if let Some(result) = self.find_remote_package(file_url) {
             return Ok(Some(result));
@@ -184,36 +184,29 @@ impl CacheIndex {
             return Ok(None);
         }
 
-        self.discover_remote_packages(repo_url)?;
+        self.discover_remote_packages(repo_url, package_index)?;
         Ok(self.find_remote_package(file_url))
     }
 
-    fn discover_remote_packages(&self, repo_url: &str) -> Result<()> {
-        let bare_dir = ensure_bare_repo(repo_url)?;
-        let tags = git::list_all_tags(&bare_dir)?;
-
-        let mut packages: BTreeMap<String, Version> = BTreeMap::new();
-        for tag in tags {
-            if let Some((pkg_path, version)) = tags::parse_tag(&tag) {
-                packages
-                    .entry(pkg_path)
-                    .and_modify(|v| {
-                        if version > *v {
-                            *v = version.clone()
-                        }
-                    })
-                    .or_insert(version);
-            }
-        }
+    fn discover_remote_packages(
+        &self,
+        repo_url: &str,
+        package_index: Option<&PackageIndex>,
+    ) -> Result<()> {
+        let packages = tags::get_versions_for_repo(repo_url, package_index)?;
 
         self.conn.execute(
             "DELETE FROM remote_packages WHERE repo_url = ?1",
             params![repo_url],
         )?;
-        for (package_path, version) in packages {
+        for (package_path, versions) in packages {
+            // Versions are sorted newest first
+            let Some(latest) = versions.first() else {
+                continue;
+            };
             self.conn.execute(
                 "INSERT INTO remote_packages (repo_url, package_path, latest_version) VALUES (?1, ?2, ?3)",
-                params![repo_url, package_path, version.to_string()],
+                params![repo_url, package_path, latest.to_string()],
             )?;
         }
 
@@ -0,0 +1,389 @@
+//! Explains a V2 dependency resolution: the resolved graph, the requirements that selected
+//! each version, and the patches, forks, vendored packages and duplicate majors in effect.
+//!
+//! MVS selects the highest version requested within each semver family, so the requirements
+//! that asked for exactly the selected version are the ones that pinned it.
+
+use std::collections::BTreeMap;
+
+use pcb_zen_core::config::PatchSpec;
+use pcb_zen_core::resolution::ModuleLine;
+use semver::Version;
+use serde::Serialize;
+
+use crate::resolve_v2::{find_matching_patch, RequiredBy, Requirement, ResolutionResult};
+use crate::workspace::WorkspaceInfo;
+
+/// The resolved dependency graph of a workspace
+#[derive(Debug, Clone, Serialize)]
+pub struct DepGraph {
+    /// Packages in the build closure, sorted by URL and version
+    pub packages: Vec<ResolvedPackage>,
+    /// `[patch]` entries of the workspace
+    pub patches: Vec<Patch>,
+    /// Packages resolved at more than one major version
+    pub conflicts: Vec<MajorConflict>,
+}
+
+/// A package in the build closure
+#[derive(Debug, Clone, Serialize)]
+pub struct ResolvedPackage {
+    pub url: String,
+    #[serde(serialize_with = "serialize_version")]
+    pub version: Version,
+    pub source: PackageSource,
+    /// Whether a workspace member or the toolchain requires it directly
+    pub direct: bool,
+    /// Resolved packages this version depends on
+    pub dependencies: Vec<PackageRef>,
+    /// Requirements on this major version from packages in the graph
+    pub requirements: Vec<PackageRequirement>,
+}
+
+/// Where a resolved package is read from
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum PackageSource {
+    /// The shared package cache
+    Cache,
+    /// The workspace's `vendor/` directory
+    Vendor,
+    /// Replaced by the `[patch]` entry `pattern`
+    Patch { pattern: String },
+}
+
+/// A resolved package version
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+pub struct PackageRef {
+    pub url: String,
+    #[serde(serialize_with = "serialize_version")]
+    pub version: Version,
+}
+
+/// A requirement on a resolved package
+#[derive(Debug, Clone, Serialize)]
+pub struct PackageRequirement {
+    #[serde(flatten)]
+    pub requirement: Requirement,
+    /// Whether it asked for the selected version, and so pinned it
+    pub selected: bool,
+}
+
+/// A `[patch]` entry of the workspace
+#[derive(Debug, Clone, Serialize)]
+pub struct Patch {
+    /// URL or glob pattern the patch applies to
+    pub pattern: String,
+    /// What replaces the matching packages, e.g. `path = "fork/..."`
+    pub replacement: String,
+    /// Whether the replacement is a fork in the workspace, resolved as a member
+    pub fork: bool,
+    /// Resolved packages and workspace members it applies to
+    pub packages: Vec<String>,
+}
+
+/// A package resolved at several major versions, each built as a separate package
+#[derive(Debug, Clone, Serialize)]
+pub struct MajorConflict {
+    pub url: String,
+    #[serde(serialize_with = "serialize_versions")]
+    pub versions: Vec<Version>,
+}
+
+fn serialize_version<S: serde::Serializer>(version: &Version, s: S) -> Result<S::Ok, S::Error> {
+    s.collect_str(version)
+}
+
+fn serialize_versions<S: serde::Serializer>(versions: &[Version], s: S) -> Result<S::Ok, S::Error> {
+    s.collect_seq(versions.iter().map(|v| v.to_string()))
+}
+
+impl DepGraph {
+    pub fn new(workspace: &WorkspaceInfo, resolution: &ResolutionResult) -> Self {
+        let closure = &resolution.closure;
+        let patch_specs = workspace
+            .config
+            .as_ref()
+            .map(|c| c.patch.clone())
+            .unwrap_or_default();
+
+        // Requirements of versions that lost out to a higher one don't count
+        let live = |r: &&Requirement| match &r.required_by {
+            RequiredBy::Package { url, version } => {
+                closure.get(&ModuleLine::new(url.clone(), version)) == Some(version)
+            }
+            _ => true,
+        };
+
+        let mut packages: Vec<ResolvedPackage> = closure
+            .iter()
+            .map(|(line, version)| {
+                let requirements = resolution
+                    .requirements
+                    .iter()
+                    .filter(live)
+                    .filter(|r| {
+                        r.url == line.path && ModuleLine::new(r.url.clone(), &r.version) == *line
+                    })
+                    .map(|r| PackageRequirement {
+                        requirement: r.clone(),
+                        selected: r.version == *version,
+                    })
+                    .collect::<Vec<_>>();
+
+                let mut dependencies: Vec<PackageRef> = resolution
+                    .requirements
+                    .iter()
+                    .filter(|r| {
+                        matches!(&r.required_by, RequiredBy::Package { url, version: v }
+                            if *url == line.path && v == version)
+                    })
+                    .filter_map(|r| {
+                        let dep_line = ModuleLine::new(r.url.clone(), &r.version);
+                        closure.get(&dep_line).map(|v| PackageRef {
+                            url: r.url.clone(),
+                            version: v.clone(),
+                        })
+                    })
+                    .collect();
+                dependencies.sort();
+                dependencies.dedup();
+
+                ResolvedPackage {
+                    url: line.path.clone(),
+                    version: version.clone(),
+                    source: package_source(workspace, &patch_specs, &line.path, version),
+                    direct: requirements.iter().any(|r| {
+                        matches!(
+                            r.requirement.required_by,
+                            RequiredBy::Workspace { .. } | RequiredBy::Toolchain
+                        )
+                    }),
+                    dependencies,
+                    requirements,
+                }
+            })
+            .collect();
+        packages.sort_by(|a, b| (&a.url, &a.version).cmp(&(&b.url, &b.version)));
+
+        let patches = patch_specs
+            .iter()
+            .map(|(pattern, patch)| {
+                let fork = patch.path.is_some() && workspace.packages.contains_key(pattern);
+                let mut matched: Vec<String> = packages
+                    .iter()
+                    .map(|p| p.url.clone())
+                    .chain(workspace.packages.keys().cloned())
+                    .filter(|url| {
+                        find_matching_patch(url, &patch_specs).is_some_and(|(p, _)| p == pattern)
+                    })
+                    .collect();
+                matched.sort();
+                matched.dedup();
+                Patch {
+                    pattern: pattern.clone(),
+                    replacement: describe_patch(patch),
+                    fork,
+                    packages: matched,
+                }
+            })
+            .collect();
+
+        let mut by_url: BTreeMap<&str, Vec<Version>> = BTreeMap::new();
+        for package in &packages {
+            by_url
+                .entry(&package.url)
+                .or_default()
+                .push(package.version.clone());
+        }
+        let conflicts = by_url
+            .into_iter()
+            .filter(|(_, versions)| versions.len() > 1)
+            .map(|(url, versions)| MajorConflict {
+                url: url.to_string(),
+                versions,
+            })
+            .collect();
+
+        DepGraph {
+            packages,
+            patches,
+            conflicts,
+        }
+    }
+
+    /// Packages required directly by workspace members or the toolchain
+    pub fn roots(&self) -> impl Iterator<Item = &ResolvedPackage> {
+        self.packages.iter().filter(|p| p.direct)
+    }
+
+    /// The resolved version `version` of `url`
+    pub fn get(&self, url: &str, version: &Version) -> Option<&ResolvedPackage> {
+        self.packages
+            .iter()
+            .find(|p| p.url == url && p.version == *version)
+    }
+
+    /// The part of the graph that explains `url`: its resolved versions, the patches that
+    /// apply to it, and its major-version conflicts
+    pub fn why(&self, url: &str) -> DepGraph {
+        DepGraph {
+            packages: self
+                .packages
+                .iter()
+                .filter(|p| p.url == url)
+                .cloned()
+                .collect(),
+            patches: self
+                .patches
+                .iter()
+                .filter(|p| p.packages.iter().any(|u| u == url))
+                .cloned()
+                .collect(),
+            conflicts: self
+                .conflicts
+                .iter()
+                .filter(|c| c.url == url)
+                .cloned()
+                .collect(),
+        }
+    }
+}
+
+fn package_source(
+    workspace: &WorkspaceInfo,
+    patches: &BTreeMap<String, PatchSpec>,
+    url: &str,
+    version: &Version,
+) -> PackageSource {
+    if let Some((pattern, _)) = find_matching_patch(url, patches) {
+        return PackageSource::Patch {
+            pattern: pattern.to_string(),
+        };
+    }
+    let vendored = workspace
+        .root
+        .join("vendor")
+        .join(url)
+        .join(version.to_string());
+    if vendored.exists() {
+        PackageSource::Vendor
+    } else {
+        PackageSource::Cache
+    }
+}
+
+fn describe_patch(patch: &PatchSpec) -> String {
+    [
+        patch.path.as_ref().map(|p| format!("path = \"{p}\"")),
+        patch.branch.as_ref().map(|b| format!("branch = \"{b}\"")),
+        patch.rev.as_ref().map(|r| format!("rev = \"{r}\"")),
+    ]
+    .into_iter()
+    .flatten()
+    .collect::<Vec<_>>()
+    .join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn v(s: &str) -> Version {
+        Version::parse(s).unwrap()
+    }
+
+    fn req(url: &str, version: &str, required_by: RequiredBy) -> Requirement {
+        Requirement {
+            url: url.to_string(),
+            version: v(version),
+            spec: version.to_string(),
+            required_by,
+        }
+    }
+
+    fn workspace() -> WorkspaceInfo {
+        WorkspaceInfo {
+            root: std::env::temp_dir().join("dep-graph-test"),
+            config: None,
+            packages: BTreeMap::new(),
+            lockfile: None,
+            errors: Vec::new(),
+        }
+    }
+
+    const BOARD: &str = "github.com/acme/boards/main";
+    const REG: &str = "github.com/acme/registry/regulator";
+    const STD: &str = "github.com/diodeinc/stdlib";
+
+    #[test]
+    fn test_selected_requirements_and_edges() {
+        let board = || RequiredBy::Workspace {
+            package: BOARD.to_string(),
+        };
+        let reg = |version: &str| RequiredBy::Package {
+            url: REG.to_string(),
+            version: v(version),
+        };
+        let resolution = ResolutionResult {
+            closure: HashMap::from([
+                (ModuleLine::new(REG.to_string(), &v("0.2.1")), v("0.2.1")),
+                (ModuleLine::new(STD.to_string(), &v("0.3.4")), v("0.3.4")),
+            ]),
+            requirements: vec![
+                req(STD, "0.3.0", RequiredBy::Toolchain),
+                req(REG, "0.2.0", board()),
+                req(STD, "0.3.2", reg("0.2.0")),
+                req(REG, "0.2.1", RequiredBy::Lockfile),
+                req(STD, "0.3.4", reg("0.2.1")),
+            ],
+            ..ResolutionResult::default()
+        };
+        let graph = DepGraph::new(&workspace(), &resolution);
+
+        let regulator = graph.get(REG, &v("0.2.1")).unwrap();
+        assert!(regulator.direct);
+        assert_eq!(regulator.source, PackageSource::Cache);
+        assert_eq!(
+            regulator.dependencies,
+            vec![PackageRef {
+                url: STD.to_string(),
+                version: v("0.3.4")
+            }]
+        );
+        let pinned: Vec<_> = regulator
+            .requirements
+            .iter()
+            .filter(|r| r.selected)
+            .map(|r| &r.requirement.required_by)
+            .collect();
+        assert_eq!(pinned, vec![&RequiredBy::Lockfile]);
+
+        // The requirement of the superseded regulator v0.2.0 is dropped
+        let stdlib = graph.get(STD, &v("0.3.4")).unwrap();
+        assert_eq!(stdlib.requirements.len(), 2);
+        assert!(graph.conflicts.is_empty());
+        assert_eq!(graph.roots().count(), 2);
+    }
+
+    #[test]
+    fn test_major_conflicts() {
+        let resolution = ResolutionResult {
+            closure: HashMap::from([
+                (ModuleLine::new(STD.to_string(), &v("0.3.4")), v("0.3.4")),
+                (ModuleLine::new(STD.to_string(), &v("1.0.2")), v("1.0.2")),
+                (ModuleLine::new(REG.to_string(), &v("0.2.1")), v("0.2.1")),
+            ]),
+            ..ResolutionResult::default()
+        };
+        let graph = DepGraph::new(&workspace(), &resolution);
+        assert_eq!(graph.conflicts.len(), 1);
+        assert_eq!(graph.conflicts[0].versions, vec![v("0.3.4"), v("1.0.2")]);
+
+        let why = graph.why(STD);
+        assert_eq!(why.packages.len(), 2);
+        assert_eq!(why.conflicts.len(), 1);
+        assert!(graph.why(REG).conflicts.is_empty());
+    }
+}
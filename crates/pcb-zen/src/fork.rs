@@ -5,7 +5,8 @@
 
 use crate::cache_index::cache_base;
 use crate::git;
-use crate::tags::get_all_versions_for_repo;
+use crate::package_index::PackageIndex;
+use crate::tags::get_versions_for_repo;
 use crate::{copy_dir_all, ensure_sparse_checkout, get_workspace_info};
 use anyhow::{Context, Result};
 use path_slash::PathExt;
@@ -73,8 +74,9 @@ pub fn fork_package(options: ForkOptions) -> Result<ForkSuccess> {
     let input_url = options.url.trim().to_string();
     let (repo_url, pkg_path) = split_repo_and_subpath(&input_url);
 
-    // Discover versions
-    let all_versions = get_all_versions_for_repo(repo_url)
+    // Discover versions, from the workspace's package index if it lists the repo
+    let package_index = PackageIndex::for_workspace(&workspace_info, false)?;
+    let all_versions = get_versions_for_repo(repo_url, package_index.as_ref())
         .with_context(|| format!("Failed to fetch versions from {}", repo_url))?;
 
     // Find versioned package by walking up the path (supports .zen file paths)
@@ -321,6 +321,13 @@ pub fn create_worktree(bare_repo: &Path, worktree_dir: &Path, rev: &str) -> anyh
     run_silent(cmd)
 }
 
+pub fn remove_worktree(repo_root: &Path, worktree_dir: &Path) -> anyhow::Result<()> {
+    let mut cmd = git(repo_root);
+    cmd.args(["worktree", "remove", "--force"])
+        .arg(worktree_dir);
+    run_silent(cmd)
+}
+
 pub fn get_remote_url(repo_root: &Path) -> anyhow::Result<String> {
     run_output(repo_root, &["remote", "get-url", "origin"])
 }
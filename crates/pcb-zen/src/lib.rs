@@ -5,12 +5,17 @@ pub mod ast_utils;
 mod auto_deps;
 pub mod cache_index;
 pub mod canonical;
+pub mod dep_graph;
 pub mod diagnostics;
 pub mod fork;
 pub mod git;
 pub mod load;
 pub mod lsp;
+pub mod package_index;
+pub mod public_api;
+pub mod report;
 pub mod resolve_v2;
+pub mod sbom;
 pub mod suppression;
 pub mod tags;
 pub mod workspace;
@@ -140,3 +145,14 @@ pub fn lsp_with_eager(eager: bool) -> anyhow::Result<()> {
     let ctx = lsp::LspEvalContext::default().set_eager(eager);
     pcb_starlark_lsp::server::stdio_server(ctx)
 }
+
+/// Start the LSP server like [`lsp_with_eager`], offering `codemods` as source actions.
+pub fn lsp_with_codemods(
+    eager: bool,
+    codemods: Vec<Box<dyn lsp::FileCodemod>>,
+) -> anyhow::Result<()> {
+    let ctx = lsp::LspEvalContext::default()
+        .set_eager(eager)
+        .with_codemods(codemods);
+    pcb_starlark_lsp::server::stdio_server(ctx)
+}
@@ -0,0 +1,310 @@
+//! Quick fixes for Zen diagnostics, and whole-file codemods offered as source actions.
+//!
+//! - Suppress a diagnostic with a `# suppress:` comment that `CommentSuppressPass` honors.
+//! - For a `moved()` whose old path still exists, rename the instance or net to the new
+//!   path; for one whose new path doesn't exist, remove the directive.
+//! - Run the registered [`FileCodemod`]s, such as the `pcb migrate` codemods.
+
+use std::collections::HashMap;
+use std::path::Path;
+
+use log::warn;
+use lsp_types::{
+    CodeAction, CodeActionKind, CodeActionOrCommand, Diagnostic, DiagnosticSeverity,
+    NumberOrString, Position, Range, TextEdit, Url, WorkspaceEdit,
+};
+use once_cell::sync::Lazy;
+use regex::Regex;
+
+/// `moved("old", "new")` calls, as `find_moved_span` matches them
+static MOVED_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r#"(?s)moved\s*\(\s*"([^"]+)"\s*,\s*"([^"]+)"\s*\)"#).unwrap());
+
+/// A rewrite of a whole file that the language server offers as a source action.
+pub trait FileCodemod {
+    /// Title of the code action
+    fn title(&self) -> String;
+
+    /// The rewritten `content` of the file at `path`, or `None` if it needs no change
+    fn apply(&self, path: &Path, content: &str) -> anyhow::Result<Option<String>>;
+}
+
+/// Position of the byte `offset` of `contents`, with the column in UTF-16 code units
+fn position(contents: &str, offset: usize) -> Position {
+    let before = &contents[..offset];
+    let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+    Position::new(
+        before.matches('\n').count() as u32,
+        before[line_start..].encode_utf16().count() as u32,
+    )
+}
+
+fn action(
+    title: String,
+    kind: CodeActionKind,
+    diagnostic: Option<&Diagnostic>,
+    url: &Url,
+    edits: Vec<TextEdit>,
+) -> CodeActionOrCommand {
+    CodeActionOrCommand::CodeAction(CodeAction {
+        title,
+        kind: Some(kind),
+        diagnostics: diagnostic.map(|d| vec![d.clone()]),
+        edit: Some(WorkspaceEdit::new(HashMap::from([(url.clone(), edits)]))),
+        ..CodeAction::default()
+    })
+}
+
+/// The `# suppress:` pattern for `diagnostic`: its kind, or else its severity
+fn suppress_pattern(diagnostic: &Diagnostic) -> Option<String> {
+    match (&diagnostic.code, diagnostic.severity) {
+        (Some(NumberOrString::String(kind)), _) => Some(kind.clone()),
+        (_, Some(DiagnosticSeverity::WARNING)) => Some("warnings".to_string()),
+        (_, Some(DiagnosticSeverity::ERROR)) => Some("errors".to_string()),
+        _ => None,
+    }
+}
+
+/// Suppress `diagnostic` on its first line: add its pattern to the line's `# suppress:`
+/// comment, or append one.
+pub(super) fn suppress_action(
+    url: &Url,
+    contents: &str,
+    diagnostic: &Diagnostic,
+) -> Option<CodeActionOrCommand> {
+    let pattern = suppress_pattern(diagnostic)?;
+    let line_number = diagnostic.range.start.line;
+    let line = contents.lines().nth(line_number as usize)?;
+
+    let lower = line.to_lowercase();
+    let (end, text) = match lower
+        .find("# suppress:")
+        .or_else(|| lower.find("#suppress:"))
+    {
+        // Patterns run until the end of the line or the next comment
+        Some(marker) => {
+            let end = line[marker + 1..]
+                .find('#')
+                .map_or(line.len(), |i| marker + 1 + i);
+            (line[..end].trim_end().len(), format!(", {pattern}"))
+        }
+        None => (line.trim_end().len(), format!("  # suppress: {pattern}")),
+    };
+    let at = Position::new(line_number, line[..end].encode_utf16().count() as u32);
+    Some(action(
+        format!("Suppress `{pattern}` on this line"),
+        CodeActionKind::QUICKFIX,
+        Some(diagnostic),
+        url,
+        vec![TextEdit::new(Range::new(at, at), text)],
+    ))
+}
+
+/// Fixes for the warnings of `validate_and_filter_moved_directives`.
+pub(super) fn moved_actions(
+    url: &Url,
+    contents: &str,
+    diagnostic: &Diagnostic,
+) -> Vec<CodeActionOrCommand> {
+    let message = diagnostic.message.lines().next().unwrap_or_default();
+    let path_in = |suffix: &str| {
+        message
+            .strip_prefix("moved() references path '")?
+            .strip_suffix(suffix)
+            .map(str::to_string)
+    };
+
+    if let Some(old) = path_in("' that still exists.") {
+        let Some(new) = MOVED_RE
+            .captures_iter(contents)
+            .find(|c| c[1] == old)
+            .map(|c| c[2].to_string())
+        else {
+            return Vec::new();
+        };
+        let edits = rename_edits(contents, &old, &new);
+        if edits.is_empty() {
+            return Vec::new();
+        }
+        return vec![action(
+            format!("Rename \"{old}\" to \"{new}\""),
+            CodeActionKind::QUICKFIX,
+            Some(diagnostic),
+            url,
+            edits,
+        )];
+    }
+
+    if let Some(new) = path_in("' that doesn't exist.") {
+        return MOVED_RE
+            .captures_iter(contents)
+            .filter(|c| c[2] == new)
+            .map(|c| {
+                let call = c.get(0).unwrap();
+                let (start, end) = line_extent(contents, call.start(), call.end());
+                action(
+                    format!("Remove moved(\"{}\", \"{new}\")", &c[1]),
+                    CodeActionKind::QUICKFIX,
+                    Some(diagnostic),
+                    url,
+                    vec![TextEdit::new(
+                        Range::new(position(contents, start), position(contents, end)),
+                        String::new(),
+                    )],
+                )
+            })
+            .collect();
+    }
+
+    Vec::new()
+}
+
+/// Edits renaming the component or net named `old` in this module to `new`. Only names
+/// without a `.` are renamed; the others belong to a child module's file.
+fn rename_edits(contents: &str, old: &str, new: &str) -> Vec<TextEdit> {
+    if old.contains('.') || new.contains('.') {
+        return Vec::new();
+    }
+    let re = Regex::new(&format!(
+        r#"(\bname\s*=\s*|\bNet\s*\(\s*)"{}""#,
+        regex::escape(old)
+    ))
+    .unwrap();
+    re.captures_iter(contents)
+        .map(|c| {
+            let literal = c.get(0).unwrap().start() + c[1].len();
+            TextEdit::new(
+                Range::new(
+                    position(contents, literal),
+                    position(contents, c.get(0).unwrap().end()),
+                ),
+                format!("\"{new}\""),
+            )
+        })
+        .collect()
+}
+
+/// The byte range `start..end`, widened to whole lines when nothing else is on them
+fn line_extent(contents: &str, start: usize, end: usize) -> (usize, usize) {
+    let line_start = contents[..start].rfind('\n').map_or(0, |i| i + 1);
+    let line_end = contents[end..]
+        .find('\n')
+        .map_or(contents.len(), |i| end + i + 1);
+    if contents[line_start..start].trim().is_empty() && contents[end..line_end].trim().is_empty() {
+        (line_start, line_end)
+    } else {
+        (start, end)
+    }
+}
+
+/// One source action per codemod that changes the file. A codemod that fails is logged and
+/// left out, so that it doesn't hide the other actions.
+pub(super) fn codemod_actions(
+    url: &Url,
+    path: &Path,
+    contents: &str,
+    codemods: &[Box<dyn FileCodemod>],
+) -> Vec<CodeActionOrCommand> {
+    let whole_file = Range::new(Position::new(0, 0), position(contents, contents.len()));
+    codemods
+        .iter()
+        .filter_map(|codemod| match codemod.apply(path, contents) {
+            Ok(rewritten) => Some(action(
+                codemod.title(),
+                CodeActionKind::SOURCE,
+                None,
+                url,
+                vec![TextEdit::new(whole_file, rewritten?)],
+            )),
+            Err(e) => {
+                warn!("{}: {e:#}", codemod.title());
+                None
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn url() -> Url {
+        Url::parse("file:///board.zen").unwrap()
+    }
+
+    fn diagnostic(line: u32, message: &str, code: Option<&str>) -> Diagnostic {
+        Diagnostic {
+            range: Range::new(Position::new(line, 0), Position::new(line, 1)),
+            severity: Some(DiagnosticSeverity::WARNING),
+            code: code.map(|c| NumberOrString::String(c.to_string())),
+            message: message.to_string(),
+            ..Diagnostic::default()
+        }
+    }
+
+    fn edits(action: &CodeActionOrCommand) -> Vec<TextEdit> {
+        let CodeActionOrCommand::CodeAction(action) = action else {
+            panic!("expected a code action");
+        };
+        let changes = action.edit.as_ref().unwrap().changes.as_ref().unwrap();
+        changes[&url()].clone()
+    }
+
+    #[test]
+    fn test_suppress_comment() {
+        let contents = "Resistor(name = \"R1\")\nNet(\"VCC\")  # suppress: warnings # keep\n";
+
+        let action = suppress_action(
+            &url(),
+            contents,
+            &diagnostic(0, "unused", Some("bom.match_generic")),
+        )
+        .unwrap();
+        assert_eq!(
+            edits(&action),
+            vec![TextEdit::new(
+                Range::new(Position::new(0, 21), Position::new(0, 21)),
+                "  # suppress: bom.match_generic".to_string()
+            )]
+        );
+
+        let action = suppress_action(&url(), contents, &diagnostic(1, "unused", None)).unwrap();
+        assert_eq!(
+            edits(&action),
+            vec![TextEdit::new(
+                Range::new(Position::new(1, 32), Position::new(1, 32)),
+                ", warnings".to_string()
+            )]
+        );
+    }
+
+    #[test]
+    fn test_moved_fixes() {
+        let contents = "moved(\"R1\", \"R_PULLUP\")\nResistor(name = \"R1\", value = \"10k\")\n";
+
+        let still_exists = diagnostic(0, "moved() references path 'R1' that still exists.", None);
+        let actions = moved_actions(&url(), contents, &still_exists);
+        assert_eq!(actions.len(), 1);
+        assert_eq!(
+            edits(&actions[0]),
+            vec![TextEdit::new(
+                Range::new(Position::new(1, 16), Position::new(1, 20)),
+                "\"R_PULLUP\"".to_string()
+            )]
+        );
+
+        let missing = diagnostic(
+            0,
+            "moved() references path 'R_PULLUP' that doesn't exist.",
+            None,
+        );
+        let actions = moved_actions(&url(), contents, &missing);
+        assert_eq!(
+            edits(&actions[0]),
+            vec![TextEdit::new(
+                Range::new(Position::new(0, 0), Position::new(1, 0)),
+                String::new()
+            )]
+        );
+    }
+}
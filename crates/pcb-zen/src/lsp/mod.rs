@@ -1,10 +1,14 @@
+mod code_actions;
 pub mod signature;
 
+pub use code_actions::FileCodemod;
+
 use log::{debug, info};
 use lsp_server::ResponseError;
 use lsp_types::{
-    request::Request, Hover, HoverContents, MarkupContent, MarkupKind, ServerCapabilities,
-    SignatureHelpOptions, Url, WorkDoneProgressOptions,
+    request::Request, CodeActionKind, CodeActionOrCommand, CodeActionParams, Hover, HoverContents,
+    MarkupContent, MarkupKind, NumberOrString, OneOf, ServerCapabilities, SignatureHelpOptions,
+    Url, WorkDoneProgressOptions,
 };
 use pcb_sch::natural_string::NaturalString;
 use pcb_sch::position::{
@@ -14,6 +18,7 @@ use pcb_starlark_lsp::server::{
     self, CompletionMeta, LspContext, LspEvalResult, LspUrl, Response, StringLiteralResult,
 };
 use pcb_zen_core::config::find_workspace_root;
+use pcb_zen_core::lang::error::CategorizedDiagnostic;
 use pcb_zen_core::lang::type_info::ParameterInfo;
 use pcb_zen_core::{
     CoreLoadResolver, DefaultFileProvider, EvalContext, FileProvider, LoadResolver,
@@ -40,6 +45,8 @@ pub struct LspEvalContext {
     inner: EvalContext,
     builtin_docs: HashMap<LspUrl, String>,
     file_provider: Arc<dyn FileProvider>,
+    /// Whole-file rewrites offered as source actions
+    codemods: Vec<Box<dyn FileCodemod>>,
 }
 
 /// Helper function to create a standard load resolver with remote and workspace support.
@@ -107,6 +114,7 @@ impl Default for LspEvalContext {
             inner,
             builtin_docs,
             file_provider,
+            codemods: Vec::new(),
         }
     }
 }
@@ -117,10 +125,18 @@ impl LspEvalContext {
         self
     }
 
-    /// Create LSP-specific diagnostic passes
+    /// Offer `codemods` as source actions on open files
+    pub fn with_codemods(mut self, codemods: Vec<Box<dyn FileCodemod>>) -> Self {
+        self.codemods = codemods;
+        self
+    }
+
+    /// Create LSP-specific diagnostic passes. `contents` are the current, possibly unsaved,
+    /// contents of `current_file`.
     fn create_lsp_diagnostic_passes(
         &self,
         current_file: &std::path::Path,
+        contents: &str,
     ) -> Vec<Box<dyn pcb_zen_core::DiagnosticsPass>> {
         let file_provider = self.inner.file_provider();
         let workspace_root = find_workspace_root(file_provider, current_file)
@@ -130,6 +146,10 @@ impl LspEvalContext {
             Box::new(pcb_zen_core::LspFilterPass::new(workspace_root)),
             // Promote style diagnostics from Advice to Warning for LSP visibility
             Box::new(pcb_zen_core::StylePromotePass),
+            Box::new(
+                pcb_zen_core::CommentSuppressPass::new()
+                    .with_source(current_file.to_string_lossy(), contents),
+            ),
         ]
     }
 
@@ -224,10 +244,16 @@ impl LspEvalContext {
         }
         let full_message = full_chain_lines.join("\n");
 
+        // The kind, so that a suppress comment can name it
+        let code = diag
+            .innermost()
+            .downcast_error_ref::<CategorizedDiagnostic>()
+            .map(|c| NumberOrString::String(c.kind.clone()));
+
         lsp_types::Diagnostic {
             range,
             severity: Some(severity),
-            code: None,
+            code,
             code_description: None,
             source: Some("diode-star".to_string()),
             message: full_message,
@@ -252,6 +278,8 @@ impl LspContext for LspEvalContext {
                     work_done_progress: None,
                 },
             }),
+            document_formatting_provider: Some(OneOf::Left(true)),
+            document_range_formatting_provider: Some(OneOf::Left(true)),
             ..ServerCapabilities::default()
         }
     }
@@ -271,13 +299,14 @@ impl LspContext for LspEvalContext {
                     .parse_and_analyze_file(path.clone(), content.clone());
 
                 // Apply LSP-specific diagnostic passes
-                let passes = self.create_lsp_diagnostic_passes(path);
+                let passes = self.create_lsp_diagnostic_passes(path, &content);
                 result.diagnostics.apply_passes(&passes);
 
                 // Convert diagnostics to LSP format
                 let diagnostics = result
                     .diagnostics
                     .iter()
+                    .filter(|d| !d.suppressed)
                     .map(|d| self.diagnostic_to_lsp(d))
                     .collect();
 
@@ -497,6 +526,48 @@ impl LspContext for LspEvalContext {
         }
     }
 
+    fn format_file(&self, _uri: &LspUrl, contents: &str) -> anyhow::Result<Option<String>> {
+        pcb_fmt::RuffFormatter::default()
+            .format_source(contents)
+            .map(Some)
+    }
+
+    fn code_actions(
+        &self,
+        uri: &LspUrl,
+        params: &CodeActionParams,
+        _workspace_root: Option<&Path>,
+    ) -> anyhow::Result<Vec<CodeActionOrCommand>> {
+        let LspUrl::File(path) = uri else {
+            return Ok(Vec::new());
+        };
+        let Some(contents) = self.get_load_contents(uri)? else {
+            return Ok(Vec::new());
+        };
+        let url = &params.text_document.uri;
+
+        let mut actions = Vec::new();
+        for diagnostic in &params.context.diagnostics {
+            actions.extend(code_actions::moved_actions(url, &contents, diagnostic));
+            actions.extend(code_actions::suppress_action(url, &contents, diagnostic));
+        }
+
+        // Codemods can be slow, so only run them when source actions are asked for
+        let wants_source = params.context.only.as_ref().is_some_and(|only| {
+            only.iter()
+                .any(|kind| kind.as_str().starts_with(CodeActionKind::SOURCE.as_str()))
+        });
+        if wants_source {
+            actions.extend(code_actions::codemod_actions(
+                url,
+                path,
+                &contents,
+                &self.codemods,
+            ));
+        }
+        Ok(actions)
+    }
+
     fn handle_custom_request(
         &self,
         req: &server::Request,
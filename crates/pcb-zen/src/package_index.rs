@@ -0,0 +1,425 @@
+//! Static package index, an alternative to discovering packages through git tags.
+//!
+//! An index is a JSON file listing, for each package and version, the URL of a canonical
+//! tarball (see [`crate::canonical`]) and its content and manifest hashes:
+//!
+//! ```json
+//! {
+//!   "packages": {
+//!     "github.com/diodeinc/stdlib": {
+//!       "0.3.4": {
+//!         "url": "github.com/diodeinc/stdlib/0.3.4.tar",
+//!         "content_hash": "h1:...",
+//!         "manifest_hash": "h1:..."
+//!       }
+//!     }
+//!   }
+//! }
+//! ```
+//!
+//! Tarball URLs are resolved relative to the index, which can be served over HTTP or read
+//! from disk. A workspace opts in with `index = "..."` in `[workspace]`; packages the index
+//! doesn't list are still fetched from git. Version discovery (`pcb update`, `pcb fork` and
+//! auto-deps) also reads the index for the repos it lists. An index on disk is also used by
+//! offline builds, which makes it suitable for air-gapped mirrors.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Context, Result};
+use semver::Version;
+use serde::{Deserialize, Serialize};
+
+use crate::canonical::{
+    compute_content_hash_from_dir, compute_manifest_hash, create_canonical_tar,
+};
+use crate::workspace::WorkspaceInfo;
+
+/// A package version listed in an index
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IndexEntry {
+    /// Tarball URL, absolute or relative to the index. `.gz` tarballs are decompressed.
+    pub url: String,
+    /// Hash of the canonical tarball, as recorded in pcb.sum
+    pub content_hash: String,
+    /// Hash of the package's pcb.toml, as recorded in pcb.sum
+    pub manifest_hash: String,
+}
+
+/// Contents of an index file: package URL -> version -> entry
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IndexFile {
+    #[serde(default)]
+    pub packages: BTreeMap<String, BTreeMap<String, IndexEntry>>,
+}
+
+/// Where an index or tarball lives
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Location {
+    Http(String),
+    File(PathBuf),
+}
+
+impl Location {
+    /// Parse `location`: an `http(s)://` or `file://` URL, or a path relative to `base_dir`
+    fn parse(location: &str, base_dir: &Path) -> Self {
+        if location.starts_with("http://") || location.starts_with("https://") {
+            Location::Http(location.to_string())
+        } else if let Some(path) = location.strip_prefix("file://") {
+            Location::File(PathBuf::from(path))
+        } else {
+            Location::File(base_dir.join(location))
+        }
+    }
+
+    /// `reference` resolved against this location, like a relative link
+    fn join(&self, reference: &str) -> Self {
+        match self {
+            Location::Http(url) if !reference.contains("://") => {
+                let base = &url[..url.rfind('/').map_or(url.len(), |i| i + 1)];
+                Location::Http(format!("{base}{reference}"))
+            }
+            Location::File(path) if !reference.contains("://") => {
+                Location::File(path.parent().unwrap_or(Path::new("")).join(reference))
+            }
+            _ => Location::parse(reference, Path::new("")),
+        }
+    }
+
+    fn read(&self) -> Result<Vec<u8>> {
+        match self {
+            Location::Http(url) => {
+                log::debug!("Downloading {}", url);
+                let response = reqwest::blocking::get(url)?;
+                if !response.status().is_success() {
+                    bail!("HTTP {} from {}", response.status(), url);
+                }
+                Ok(response.bytes()?.to_vec())
+            }
+            Location::File(path) => {
+                fs::read(path).with_context(|| format!("Failed to read {}", path.display()))
+            }
+        }
+    }
+}
+
+impl std::fmt::Display for Location {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Location::Http(url) => write!(f, "{url}"),
+            Location::File(path) => write!(f, "{}", path.display()),
+        }
+    }
+}
+
+/// A loaded package index
+#[derive(Debug, Clone)]
+pub struct PackageIndex {
+    location: Location,
+    file: IndexFile,
+}
+
+impl PackageIndex {
+    /// Load the index at `location`, an `http(s)://` or `file://` URL or a path relative to
+    /// `base_dir`
+    pub fn load(location: &str, base_dir: &Path) -> Result<Self> {
+        let location = Location::parse(location, base_dir);
+        let bytes = location.read()?;
+        let file = serde_json::from_slice(&bytes)
+            .with_context(|| format!("Invalid package index {location}"))?;
+        Ok(Self { location, file })
+    }
+
+    /// The index configured in the workspace's `[workspace] index`, if any. Only an index on
+    /// disk is loaded when `offline`.
+    pub fn for_workspace(workspace_info: &WorkspaceInfo, offline: bool) -> Result<Option<Self>> {
+        match workspace_info.package_index() {
+            Some(location) if !offline || !location.starts_with("http") => Ok(Some(
+                Self::load(location, &workspace_info.root)
+                    .with_context(|| format!("Failed to load package index {location}"))?,
+            )),
+            _ => Ok(None),
+        }
+    }
+
+    /// Whether the index is read from disk, so that offline builds can use it
+    pub fn is_local(&self) -> bool {
+        matches!(self.location, Location::File(_))
+    }
+
+    pub fn get(&self, module_path: &str, version: &str) -> Option<&IndexEntry> {
+        self.file.packages.get(module_path)?.get(version)
+    }
+
+    /// Versions of `module_path` listed in the index, in ascending order
+    pub fn versions(&self, module_path: &str) -> Vec<Version> {
+        let mut versions: Vec<Version> = self
+            .file
+            .packages
+            .get(module_path)
+            .into_iter()
+            .flat_map(|versions| versions.keys())
+            .filter_map(|v| Version::parse(v).ok())
+            .collect();
+        versions.sort();
+        versions
+    }
+
+    /// Versions of the packages the index lists in `repo_url`, keyed by path within the repo
+    /// (empty for a package at the repo root) and sorted newest first, like
+    /// [`crate::tags::get_all_versions_for_repo`]
+    pub fn repo_versions(&self, repo_url: &str) -> BTreeMap<String, Vec<Version>> {
+        let mut packages = BTreeMap::new();
+        for module_path in self.file.packages.keys() {
+            let pkg_path = match module_path.strip_prefix(repo_url) {
+                Some("") => "",
+                Some(rest) => match rest.strip_prefix('/') {
+                    Some(pkg_path) => pkg_path,
+                    None => continue,
+                },
+                None => continue,
+            };
+            let mut versions = self.versions(module_path);
+            if versions.is_empty() {
+                continue;
+            }
+            versions.reverse();
+            packages.insert(pkg_path.to_string(), versions);
+        }
+        packages
+    }
+
+    /// Download `entry` and extract it into `target_dir`, after checking its hashes.
+    /// `target_dir` is replaced only once the package is verified.
+    pub fn fetch(&self, module_path: &str, entry: &IndexEntry, target_dir: &Path) -> Result<()> {
+        let tarball = self.location.join(&entry.url);
+        let bytes = tarball.read()?;
+
+        let parent = target_dir
+            .parent()
+            .context("Package directory has no parent")?;
+        fs::create_dir_all(parent)?;
+        let staging = tempfile::tempdir_in(parent)?;
+
+        let reader: Box<dyn Read> = if entry.url.ends_with(".gz") {
+            Box::new(flate2::read::GzDecoder::new(&bytes[..]))
+        } else {
+            Box::new(&bytes[..])
+        };
+        tar::Archive::new(reader)
+            .unpack(staging.path())
+            .with_context(|| format!("Failed to extract {tarball}"))?;
+
+        let content_hash = compute_content_hash_from_dir(staging.path())?;
+        if content_hash != entry.content_hash {
+            bail!(
+                "Content hash mismatch for {module_path} from {tarball}\n  \
+                Expected: {}\n  \
+                Actual:   {content_hash}",
+                entry.content_hash
+            );
+        }
+        let manifest = fs::read_to_string(staging.path().join("pcb.toml"))
+            .with_context(|| format!("{tarball} has no pcb.toml"))?;
+        let manifest_hash = compute_manifest_hash(&manifest);
+        if manifest_hash != entry.manifest_hash {
+            bail!(
+                "Manifest hash mismatch for {module_path} from {tarball}\n  \
+                Expected: {}\n  \
+                Actual:   {manifest_hash}",
+                entry.manifest_hash
+            );
+        }
+
+        if target_dir.exists() {
+            fs::remove_dir_all(target_dir)?;
+        }
+        fs::rename(staging.keep(), target_dir)?;
+        Ok(())
+    }
+}
+
+impl IndexFile {
+    /// Write the canonical tarball of the package in `package_dir` to `tarball`, and list it
+    /// as `module_path` at `version` with the URL `url`
+    pub fn add_package(
+        &mut self,
+        module_path: &str,
+        version: &Version,
+        package_dir: &Path,
+        tarball: &Path,
+        url: &str,
+    ) -> Result<IndexEntry> {
+        let manifest = fs::read_to_string(package_dir.join("pcb.toml"))
+            .with_context(|| format!("{} has no pcb.toml", package_dir.display()))?;
+        if let Some(parent) = tarball.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        create_canonical_tar(package_dir, fs::File::create(tarball)?)?;
+
+        let entry = IndexEntry {
+            url: url.to_string(),
+            content_hash: compute_content_hash_from_dir(package_dir)?,
+            manifest_hash: compute_manifest_hash(&manifest),
+        };
+        self.packages
+            .entry(module_path.to_string())
+            .or_default()
+            .insert(version.to_string(), entry.clone());
+        Ok(entry)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const STDLIB: &str = "github.com/diodeinc/stdlib";
+
+    /// A mirror with one package, returning its index path
+    fn mirror(root: &Path) -> Result<PathBuf> {
+        let package = root.join("src");
+        fs::create_dir_all(package.join("units"))?;
+        fs::write(package.join("pcb.toml"), "[dependencies]\n")?;
+        fs::write(package.join("units/units.zen"), "Voltage = 1\n")?;
+
+        let mirror = root.join("mirror");
+        let mut index = IndexFile::default();
+        index.add_package(
+            STDLIB,
+            &Version::new(0, 3, 4),
+            &package,
+            &mirror.join("stdlib-0.3.4.tar"),
+            "stdlib-0.3.4.tar",
+        )?;
+        let index_path = mirror.join("index.json");
+        fs::write(&index_path, serde_json::to_string_pretty(&index)?)?;
+        Ok(index_path)
+    }
+
+    #[test]
+    fn test_location_join() {
+        let http = Location::parse("https://mirror.example.com/pcb/index.json", Path::new(""));
+        assert_eq!(
+            http.join("stdlib/0.3.4.tar"),
+            Location::Http("https://mirror.example.com/pcb/stdlib/0.3.4.tar".to_string())
+        );
+        assert_eq!(
+            http.join("file:///srv/stdlib.tar"),
+            Location::File(PathBuf::from("/srv/stdlib.tar"))
+        );
+
+        let file = Location::parse("mirror/index.json", Path::new("/ws"));
+        assert_eq!(
+            file.join("stdlib.tar"),
+            Location::File(PathBuf::from("/ws/mirror/stdlib.tar"))
+        );
+    }
+
+    #[test]
+    fn test_fetch_from_file_index() -> Result<()> {
+        let tmp = tempfile::tempdir()?;
+        let index_path = mirror(tmp.path())?;
+
+        let index = PackageIndex::load(&format!("file://{}", index_path.display()), tmp.path())?;
+        assert!(index.is_local());
+        assert_eq!(index.versions(STDLIB), vec![Version::new(0, 3, 4)]);
+        assert_eq!(
+            index.repo_versions(STDLIB),
+            BTreeMap::from([(String::new(), vec![Version::new(0, 3, 4)])])
+        );
+        assert!(index.repo_versions("github.com/diodeinc/std").is_empty());
+
+        let entry = index.get(STDLIB, "0.3.4").unwrap().clone();
+        let target = tmp.path().join("cache").join(STDLIB).join("0.3.4");
+        index.fetch(STDLIB, &entry, &target)?;
+        assert_eq!(
+            fs::read_to_string(target.join("units/units.zen"))?,
+            "Voltage = 1\n"
+        );
+        assert_eq!(compute_content_hash_from_dir(&target)?, entry.content_hash);
+        Ok(())
+    }
+
+    /// Serve the files under `root` over HTTP on a loopback port, returning the base URL
+    fn serve(root: PathBuf) -> Result<String> {
+        use std::io::{BufRead, BufReader, Write};
+
+        let listener = std::net::TcpListener::bind("127.0.0.1:0")?;
+        let base = format!("http://{}", listener.local_addr()?);
+        std::thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                let mut reader = BufReader::new(&stream);
+                let mut request = String::new();
+                if reader.read_line(&mut request).is_err() {
+                    continue;
+                }
+                // Skip the headers
+                let mut line = String::new();
+                while reader.read_line(&mut line).is_ok_and(|n| n > 2) {
+                    line.clear();
+                }
+
+                let path = request.split_whitespace().nth(1).unwrap_or("/");
+                let (status, body) = match fs::read(root.join(path.trim_start_matches('/'))) {
+                    Ok(body) => ("200 OK", body),
+                    Err(_) => ("404 Not Found", Vec::new()),
+                };
+                let mut stream = &stream;
+                let _ = write!(
+                    stream,
+                    "HTTP/1.1 {status}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                    body.len()
+                );
+                let _ = stream.write_all(&body);
+            }
+        });
+        Ok(base)
+    }
+
+    #[test]
+    fn test_fetch_from_http_index() -> Result<()> {
+        let tmp = tempfile::tempdir()?;
+        let index_path = mirror(tmp.path())?;
+        let base = serve(index_path.parent().unwrap().to_path_buf())?;
+
+        let index = PackageIndex::load(&format!("{base}/index.json"), tmp.path())?;
+        assert!(!index.is_local());
+        assert_eq!(index.versions(STDLIB), vec![Version::new(0, 3, 4)]);
+        assert_eq!(
+            index.repo_versions("github.com/diodeinc"),
+            BTreeMap::from([("stdlib".to_string(), vec![Version::new(0, 3, 4)])])
+        );
+
+        // The tarball URL is relative to the index, so it is fetched from the same server
+        let entry = index.get(STDLIB, "0.3.4").unwrap().clone();
+        let target = tmp.path().join("cache").join(STDLIB).join("0.3.4");
+        index.fetch(STDLIB, &entry, &target)?;
+        assert_eq!(
+            fs::read_to_string(target.join("units/units.zen"))?,
+            "Voltage = 1\n"
+        );
+
+        let missing = PackageIndex::load(&format!("{base}/missing.json"), tmp.path());
+        assert!(missing.unwrap_err().to_string().contains("HTTP 404"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_fetch_rejects_hash_mismatch() -> Result<()> {
+        let tmp = tempfile::tempdir()?;
+        let index_path = mirror(tmp.path())?;
+        let index = PackageIndex::load("mirror/index.json", tmp.path())?;
+        assert_eq!(index.location, Location::File(index_path));
+
+        let mut entry = index.get(STDLIB, "0.3.4").unwrap().clone();
+        entry.content_hash = "h1:tampered".to_string();
+        let target = tmp.path().join("cache/stdlib");
+        let err = index.fetch(STDLIB, &entry, &target).unwrap_err();
+        assert!(err.to_string().contains("Content hash mismatch"), "{err}");
+        assert!(!target.exists());
+        Ok(())
+    }
+}
@@ -0,0 +1,417 @@
+//! Public API of a package's modules, and semver classification of changes to it.
+//!
+//! The API of a module is its `io()` and `config()` parameters and the symbols it exports,
+//! including the fields of exported interfaces. Comparing the APIs of two versions of a
+//! package tells which version bump the change needs.
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+use pcb_zen_core::lang::type_info::{ParameterInfo, TypeInfo};
+use pcb_zen_core::EvalOutput;
+use serde::Serialize;
+
+/// How much of a version bump a change to the API needs
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChangeLevel {
+    /// No change to the API
+    Patch,
+    /// Backwards compatible additions
+    Additive,
+    /// Changes that can break dependants
+    Breaking,
+}
+
+impl fmt::Display for ChangeLevel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ChangeLevel::Patch => write!(f, "patch"),
+            ChangeLevel::Additive => write!(f, "additive"),
+            ChangeLevel::Breaking => write!(f, "breaking"),
+        }
+    }
+}
+
+/// An `io()` or `config()` parameter of a module
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct Parameter {
+    pub io: bool,
+    pub type_name: String,
+    pub required: bool,
+    pub default: Option<serde_json::Value>,
+}
+
+/// A symbol exported by a module
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Export {
+    /// An interface, with the types of its fields
+    Interface { fields: BTreeMap<String, String> },
+    /// Any other value, such as a function, a `Module()` or a constant
+    Value { type_name: String },
+}
+
+/// The public API of one module
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+pub struct ModuleApi {
+    pub parameters: BTreeMap<String, Parameter>,
+    pub exports: BTreeMap<String, Export>,
+}
+
+impl ModuleApi {
+    /// API of an evaluated module. Names starting with `_` and loaded symbols are private.
+    pub fn from_output(output: &EvalOutput) -> Self {
+        let parameters = output
+            .signature
+            .iter()
+            .map(|p| (p.name.clone(), Parameter::from_info(p)))
+            .collect();
+
+        let exports = output
+            .star_module
+            .names()
+            .filter(|name| !name.as_str().starts_with('_'))
+            .filter_map(|name| {
+                let value = output.star_module.get_option(name.as_str()).ok()??;
+                let export = match TypeInfo::from_value(value.value()) {
+                    TypeInfo::Interface { pins, .. } => Export::Interface {
+                        fields: pins
+                            .iter()
+                            .map(|(field, typ)| (field.clone(), type_name(typ)))
+                            .collect(),
+                    },
+                    _ => Export::Value {
+                        type_name: value.value().get_type().to_string(),
+                    },
+                };
+                Some((name.as_str().to_string(), export))
+            })
+            .collect();
+
+        Self {
+            parameters,
+            exports,
+        }
+    }
+}
+
+impl Parameter {
+    fn from_info(info: &ParameterInfo) -> Self {
+        Self {
+            io: !info.is_config(),
+            type_name: type_name(&info.type_info),
+            required: info.required,
+            default: info.default_value.clone(),
+        }
+    }
+
+    fn kind(&self) -> &'static str {
+        if self.io {
+            "io"
+        } else {
+            "config"
+        }
+    }
+}
+
+/// Name of a type, as written in Zen
+pub fn type_name(typ: &TypeInfo) -> String {
+    match typ {
+        TypeInfo::String => "str".to_string(),
+        TypeInfo::Int => "int".to_string(),
+        TypeInfo::Float => "float".to_string(),
+        TypeInfo::Bool => "bool".to_string(),
+        TypeInfo::List { element } => format!("list[{}]", type_name(element)),
+        TypeInfo::Dict { key, value } => format!("dict[{}, {}]", type_name(key), type_name(value)),
+        TypeInfo::Net => "Net".to_string(),
+        TypeInfo::Enum { name, .. }
+        | TypeInfo::Record { name, .. }
+        | TypeInfo::Interface { name, .. } => name.clone(),
+        TypeInfo::Unknown { type_name } => type_name.clone(),
+    }
+}
+
+/// One difference between two versions of a package's API
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct ApiChange {
+    pub level: ChangeLevel,
+    /// Path of the module, relative to the package
+    pub module: String,
+    pub message: String,
+}
+
+/// Differences from the `old` to the `new` API of a package's modules, keyed by module path.
+pub fn diff(
+    old: &BTreeMap<String, ModuleApi>,
+    new: &BTreeMap<String, ModuleApi>,
+) -> Vec<ApiChange> {
+    let mut changes = Vec::new();
+    for (module, old_api) in old {
+        let mut push = |level, message: String| {
+            changes.push(ApiChange {
+                level,
+                module: module.clone(),
+                message,
+            })
+        };
+        let Some(new_api) = new.get(module) else {
+            push(ChangeLevel::Breaking, "module removed".to_string());
+            continue;
+        };
+        diff_parameters(old_api, new_api, &mut push);
+        diff_exports(old_api, new_api, &mut push);
+    }
+    for module in new.keys().filter(|m| !old.contains_key(*m)) {
+        changes.push(ApiChange {
+            level: ChangeLevel::Additive,
+            module: module.clone(),
+            message: "module added".to_string(),
+        });
+    }
+    changes
+}
+
+fn diff_parameters(old: &ModuleApi, new: &ModuleApi, push: &mut impl FnMut(ChangeLevel, String)) {
+    for (name, before) in &old.parameters {
+        let kind = before.kind();
+        let Some(after) = new.parameters.get(name) else {
+            push(ChangeLevel::Breaking, format!("{kind} `{name}` removed"));
+            continue;
+        };
+        if before.io != after.io || before.type_name != after.type_name {
+            push(
+                ChangeLevel::Breaking,
+                format!(
+                    "{kind} `{name}` changed from {}({}) to {}({})",
+                    kind,
+                    before.type_name,
+                    after.kind(),
+                    after.type_name
+                ),
+            );
+        }
+        match (before.required, after.required) {
+            (false, true) => push(
+                ChangeLevel::Breaking,
+                format!("{kind} `{name}` is now required"),
+            ),
+            (true, false) => push(
+                ChangeLevel::Additive,
+                format!("{kind} `{name}` is now optional"),
+            ),
+            _ => {}
+        }
+        if !after.required && before.default != after.default {
+            let show = |d: &Option<serde_json::Value>| {
+                d.as_ref().map_or("none".to_string(), |v| v.to_string())
+            };
+            push(
+                ChangeLevel::Patch,
+                format!(
+                    "{kind} `{name}` default changed from {} to {}",
+                    show(&before.default),
+                    show(&after.default)
+                ),
+            );
+        }
+    }
+    for (name, after) in &new.parameters {
+        if old.parameters.contains_key(name) {
+            continue;
+        }
+        let kind = after.kind();
+        if after.required {
+            push(
+                ChangeLevel::Breaking,
+                format!("required {kind} `{name}` added"),
+            );
+        } else {
+            push(
+                ChangeLevel::Additive,
+                format!("optional {kind} `{name}` added"),
+            );
+        }
+    }
+}
+
+fn diff_exports(old: &ModuleApi, new: &ModuleApi, push: &mut impl FnMut(ChangeLevel, String)) {
+    for (name, before) in &old.exports {
+        let Some(after) = new.exports.get(name) else {
+            push(ChangeLevel::Breaking, format!("export `{name}` removed"));
+            continue;
+        };
+        match (before, after) {
+            (
+                Export::Interface { fields: old_fields },
+                Export::Interface { fields: new_fields },
+            ) => {
+                for (field, typ) in old_fields {
+                    match new_fields.get(field) {
+                        None => push(
+                            ChangeLevel::Breaking,
+                            format!("interface `{name}` field `{field}` removed"),
+                        ),
+                        Some(new_typ) if new_typ != typ => push(
+                            ChangeLevel::Breaking,
+                            format!(
+                                "interface `{name}` field `{field}` changed from {typ} to {new_typ}"
+                            ),
+                        ),
+                        Some(_) => {}
+                    }
+                }
+                for field in new_fields.keys().filter(|f| !old_fields.contains_key(*f)) {
+                    push(
+                        ChangeLevel::Additive,
+                        format!("interface `{name}` field `{field}` added"),
+                    );
+                }
+            }
+            (before, after) if before != after => push(
+                ChangeLevel::Breaking,
+                format!(
+                    "export `{name}` changed from {} to {}",
+                    export_kind(before),
+                    export_kind(after)
+                ),
+            ),
+            _ => {}
+        }
+    }
+    for name in new.exports.keys().filter(|n| !old.exports.contains_key(*n)) {
+        push(ChangeLevel::Additive, format!("export `{name}` added"));
+    }
+}
+
+fn export_kind(export: &Export) -> &str {
+    match export {
+        Export::Interface { .. } => "interface",
+        Export::Value { type_name } => type_name,
+    }
+}
+
+/// The bump `changes` need: the highest level among them, or `Patch` when there are none
+pub fn required_level(changes: &[ApiChange]) -> ChangeLevel {
+    changes
+        .iter()
+        .map(|c| c.level)
+        .max()
+        .unwrap_or(ChangeLevel::Patch)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(type_name: &str, required: bool, default: Option<serde_json::Value>) -> Parameter {
+        Parameter {
+            io: false,
+            type_name: type_name.to_string(),
+            required,
+            default,
+        }
+    }
+
+    fn interface(fields: &[(&str, &str)]) -> Export {
+        Export::Interface {
+            fields: fields
+                .iter()
+                .map(|(f, t)| (f.to_string(), t.to_string()))
+                .collect(),
+        }
+    }
+
+    fn package(api: ModuleApi) -> BTreeMap<String, ModuleApi> {
+        BTreeMap::from([("Regulator.zen".to_string(), api)])
+    }
+
+    fn base() -> ModuleApi {
+        ModuleApi {
+            parameters: BTreeMap::from([
+                ("vout".to_string(), config("str", true, None)),
+                (
+                    "fsw".to_string(),
+                    config("str", false, Some(serde_json::json!("1MHz"))),
+                ),
+            ]),
+            exports: BTreeMap::from([(
+                "Rail".to_string(),
+                interface(&[("VCC", "Net"), ("GND", "Net")]),
+            )]),
+        }
+    }
+
+    #[test]
+    fn test_unchanged_api_is_patch() {
+        let changes = diff(&package(base()), &package(base()));
+        assert!(changes.is_empty());
+        assert_eq!(required_level(&changes), ChangeLevel::Patch);
+    }
+
+    #[test]
+    fn test_default_change_is_patch() {
+        let mut new = base();
+        new.parameters.get_mut("fsw").unwrap().default = Some(serde_json::json!("2MHz"));
+        let changes = diff(&package(base()), &package(new));
+        assert_eq!(changes.len(), 1);
+        assert_eq!(required_level(&changes), ChangeLevel::Patch);
+    }
+
+    #[test]
+    fn test_additions_are_additive() {
+        let mut new = base();
+        new.parameters
+            .insert("enable".to_string(), config("bool", false, None));
+        new.exports.insert(
+            "Rail".to_string(),
+            interface(&[("VCC", "Net"), ("GND", "Net"), ("PG", "Net")]),
+        );
+        new.parameters.get_mut("vout").unwrap().required = false;
+
+        let changes = diff(&package(base()), &package(new));
+        assert_eq!(changes.len(), 3);
+        assert_eq!(required_level(&changes), ChangeLevel::Additive);
+    }
+
+    #[test]
+    fn test_breaking_changes() {
+        let cases: Vec<fn(&mut ModuleApi)> = vec![
+            |api| {
+                api.parameters.remove("fsw");
+            },
+            |api| {
+                api.parameters
+                    .insert("iout".to_string(), config("str", true, None));
+            },
+            |api| api.parameters.get_mut("vout").unwrap().type_name = "float".to_string(),
+            |api| api.parameters.get_mut("fsw").unwrap().required = true,
+            |api| {
+                api.exports
+                    .insert("Rail".to_string(), interface(&[("VCC", "Net")]));
+            },
+            |api| {
+                api.exports.insert(
+                    "Rail".to_string(),
+                    Export::Value {
+                        type_name: "function".to_string(),
+                    },
+                );
+            },
+        ];
+        for change in cases {
+            let mut new = base();
+            change(&mut new);
+            let changes = diff(&package(base()), &package(new));
+            assert_eq!(
+                required_level(&changes),
+                ChangeLevel::Breaking,
+                "{changes:?}"
+            );
+        }
+
+        let changes = diff(&package(base()), &BTreeMap::new());
+        assert_eq!(changes[0].message, "module removed");
+        assert_eq!(required_level(&changes), ChangeLevel::Breaking);
+    }
+}
@@ -0,0 +1,542 @@
+//! SARIF and JUnit XML reports of diagnostics, for CI dashboards and code review bots.
+//!
+//! A diagnostic chain is reported the way `DiagnosticReport` flattens it: the innermost
+//! diagnostic is the result, and the diagnostics wrapping it are its context.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use path_slash::PathExt;
+use pcb_zen_core::lang::error::{BenchTestResult, CategorizedDiagnostic};
+use pcb_zen_core::{Diagnostic, Diagnostics, DiagnosticsPass};
+use serde_json::{json, Value};
+use starlark::codemap::ResolvedSpan;
+use starlark::errors::EvalSeverity;
+
+const SARIF_SCHEMA: &str = "https://json.schemastore.org/sarif-2.1.0.json";
+
+/// Diagnostics of a run, grouped by the file they were produced for
+#[derive(Clone, Default)]
+pub struct DiagnosticsCollector {
+    sources: Arc<Mutex<Vec<(PathBuf, Arc<Mutex<Vec<Diagnostic>>>)>>>,
+}
+
+impl DiagnosticsCollector {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A pass that records the diagnostics of `source`. The source is reported even if the
+    /// pass never sees a diagnostic.
+    pub fn pass(&self, source: &Path) -> CollectPass {
+        let diagnostics = Arc::new(Mutex::new(Vec::new()));
+        self.sources
+            .lock()
+            .unwrap()
+            .push((source.to_path_buf(), diagnostics.clone()));
+        CollectPass { diagnostics }
+    }
+
+    /// The collected diagnostics of each source, in the order the sources were added
+    pub fn sources(&self) -> Vec<(PathBuf, Vec<Diagnostic>)> {
+        self.sources
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(source, diagnostics)| (source.clone(), diagnostics.lock().unwrap().clone()))
+            .collect()
+    }
+}
+
+/// A pass that keeps a copy of the diagnostics for a [`DiagnosticsCollector`]
+pub struct CollectPass {
+    diagnostics: Arc<Mutex<Vec<Diagnostic>>>,
+}
+
+impl DiagnosticsPass for CollectPass {
+    fn apply(&self, diagnostics: &mut Diagnostics) {
+        self.diagnostics
+            .lock()
+            .unwrap()
+            .extend(diagnostics.iter().cloned());
+    }
+}
+
+/// The categorized kind of a diagnostic, from the innermost diagnostic of the chain that
+/// has one
+pub fn diagnostic_kind(diagnostic: &Diagnostic) -> Option<&str> {
+    let mut chain = vec![diagnostic];
+    while let Some(child) = chain.last().unwrap().child.as_deref() {
+        chain.push(child);
+    }
+    chain
+        .iter()
+        .rev()
+        .find_map(|d| d.downcast_error_ref::<CategorizedDiagnostic>())
+        .map(|c| c.kind.as_str())
+}
+
+/// Whether a diagnostic only records a passing TestBench check
+fn is_passed_check(diagnostic: &Diagnostic) -> bool {
+    diagnostic
+        .downcast_error_ref::<BenchTestResult>()
+        .is_some_and(|r| r.passed)
+}
+
+/// `path` relative to `root` with forward slashes, or unchanged if it is outside of `root`
+fn relative_path(path: &str, root: &Path) -> (String, bool) {
+    match Path::new(path).strip_prefix(root) {
+        Ok(rel) => (rel.to_slash_lossy().into_owned(), true),
+        Err(_) => (Path::new(path).to_slash_lossy().into_owned(), false),
+    }
+}
+
+fn sarif_region(span: ResolvedSpan) -> Value {
+    // SARIF lines and columns are 1-based
+    json!({
+        "startLine": span.begin.line + 1,
+        "startColumn": span.begin.column + 1,
+        "endLine": span.end.line + 1,
+        "endColumn": span.end.column + 1,
+    })
+}
+
+fn sarif_location(diagnostic: &Diagnostic, root: &Path) -> Value {
+    if diagnostic.path.is_empty() {
+        return json!({});
+    }
+    let (uri, relative) = relative_path(&diagnostic.path, root);
+    let mut artifact = json!({ "uri": uri });
+    if relative {
+        artifact["uriBaseId"] = json!("%SRCROOT%");
+    }
+    let mut physical = json!({ "artifactLocation": artifact });
+    if let Some(span) = diagnostic.span {
+        physical["region"] = sarif_region(span);
+    }
+    json!({ "physicalLocation": physical })
+}
+
+fn sarif_result(diagnostic: &Diagnostic, root: &Path) -> Value {
+    let innermost = diagnostic.innermost();
+    let level = match innermost.severity {
+        EvalSeverity::Error => "error",
+        EvalSeverity::Warning => "warning",
+        EvalSeverity::Advice | EvalSeverity::Disabled => "note",
+    };
+
+    let mut result = json!({
+        "level": level,
+        "message": { "text": innermost.body },
+    });
+    if let Some(kind) = diagnostic_kind(diagnostic) {
+        result["ruleId"] = json!(kind);
+    }
+    if !innermost.path.is_empty() {
+        result["locations"] = json!([sarif_location(innermost, root)]);
+    }
+
+    // The diagnostics wrapping the innermost one, outermost first
+    let mut context = Vec::new();
+    let mut current = diagnostic;
+    while let Some(child) = current.child.as_deref() {
+        let mut location = sarif_location(current, root);
+        location["id"] = json!(context.len());
+        location["message"] = json!({ "text": current.body });
+        context.push(location);
+        current = child;
+    }
+    if !context.is_empty() {
+        result["relatedLocations"] = json!(context);
+    }
+
+    if let Some(count) = diagnostic.suppressed_count() {
+        result["occurrenceCount"] = json!(count + 1);
+    }
+    if diagnostic.suppressed {
+        result["suppressions"] = json!([{ "kind": "external" }]);
+    }
+    result
+}
+
+/// A SARIF 2.1.0 log of the collected diagnostics, with paths relative to `root`
+pub fn sarif(sources: &[(PathBuf, Vec<Diagnostic>)], root: &Path, version: &str) -> Value {
+    let diagnostics: Vec<&Diagnostic> = sources
+        .iter()
+        .flat_map(|(_, diagnostics)| diagnostics)
+        .filter(|d| !is_passed_check(d))
+        .collect();
+
+    let rules: BTreeSet<&str> = diagnostics
+        .iter()
+        .filter_map(|d| diagnostic_kind(d))
+        .collect();
+
+    json!({
+        "$schema": SARIF_SCHEMA,
+        "version": "2.1.0",
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": "pcb",
+                    "version": version,
+                    "informationUri": "https://github.com/diodeinc/pcb",
+                    "rules": rules.iter().map(|id| json!({ "id": id })).collect::<Vec<_>>(),
+                }
+            },
+            "results": diagnostics
+                .iter()
+                .map(|d| sarif_result(d, root))
+                .collect::<Vec<_>>(),
+        }]
+    })
+}
+
+/// A JUnit test suite
+#[derive(Debug, Clone, PartialEq)]
+pub struct JunitSuite {
+    pub name: String,
+    pub cases: Vec<JunitCase>,
+}
+
+/// A JUnit test case
+#[derive(Debug, Clone, PartialEq)]
+pub struct JunitCase {
+    pub name: String,
+    pub classname: String,
+    pub outcome: JunitOutcome,
+    /// Text reported as the case's `<system-out>`
+    pub output: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum JunitOutcome {
+    Passed,
+    Failed {
+        kind: String,
+        message: String,
+        details: String,
+    },
+    Skipped {
+        message: String,
+    },
+}
+
+/// A suite for the diagnostics of one source: a failing case per error, and a passing case
+/// per warning with the warning as its output. Suppressed diagnostics are skipped cases. A
+/// source without errors or warnings gets a single passing case.
+pub fn diagnostics_suite(name: &str, diagnostics: &[Diagnostic], root: &Path) -> JunitSuite {
+    let mut cases: Vec<JunitCase> = diagnostics
+        .iter()
+        .filter(|d| matches!(d.severity, EvalSeverity::Error | EvalSeverity::Warning))
+        .filter(|d| !is_passed_check(d))
+        .map(|diagnostic| {
+            let innermost = diagnostic.innermost();
+            let kind = diagnostic_kind(diagnostic)
+                .map(str::to_string)
+                .unwrap_or_else(|| innermost.severity.to_string().to_lowercase());
+            let (path, _) = relative_path(&innermost.path, root);
+            let location = match innermost.span {
+                Some(span) => format!("{path}:{span}"),
+                None => path,
+            };
+            let message = innermost
+                .body
+                .lines()
+                .next()
+                .unwrap_or_default()
+                .to_string();
+            let outcome = if diagnostic.suppressed {
+                JunitOutcome::Skipped {
+                    message: "suppressed".to_string(),
+                }
+            } else if diagnostic.is_error() {
+                JunitOutcome::Failed {
+                    kind: kind.clone(),
+                    message,
+                    details: diagnostic.to_string(),
+                }
+            } else {
+                JunitOutcome::Passed
+            };
+            let output = matches!(outcome, JunitOutcome::Passed).then(|| diagnostic.to_string());
+            JunitCase {
+                name: format!("{kind} at {location}"),
+                classname: name.to_string(),
+                outcome,
+                output,
+            }
+        })
+        .collect();
+
+    if cases.is_empty() {
+        cases.push(JunitCase {
+            name: name.to_string(),
+            classname: name.to_string(),
+            outcome: JunitOutcome::Passed,
+            output: None,
+        });
+    }
+    JunitSuite {
+        name: name.to_string(),
+        cases,
+    }
+}
+
+/// Suites for the TestBench check results among `diagnostics`, one per TestBench
+pub fn test_bench_suites(diagnostics: &[Diagnostic], root: &Path) -> Vec<JunitSuite> {
+    let mut suites: BTreeMap<(String, String), Vec<JunitCase>> = BTreeMap::new();
+    for diagnostic in diagnostics {
+        let Some(result) = diagnostic.downcast_error_ref::<BenchTestResult>() else {
+            continue;
+        };
+        let (file_path, _) = relative_path(&result.file_path, root);
+        let name = match &result.case_name {
+            Some(case) => format!("{case}: {}", result.check_name),
+            None => result.check_name.clone(),
+        };
+        let outcome = if result.passed {
+            JunitOutcome::Passed
+        } else {
+            let cause = diagnostic.child.as_deref().unwrap_or(diagnostic);
+            JunitOutcome::Failed {
+                kind: diagnostic_kind(cause).unwrap_or("check").to_string(),
+                message: cause
+                    .innermost()
+                    .body
+                    .lines()
+                    .next()
+                    .unwrap_or_default()
+                    .to_string(),
+                details: cause.to_string(),
+            }
+        };
+        suites
+            .entry((file_path.clone(), result.test_bench_name.clone()))
+            .or_default()
+            .push(JunitCase {
+                name,
+                classname: format!("{file_path}.{}", result.test_bench_name),
+                outcome,
+                output: None,
+            });
+    }
+    suites
+        .into_iter()
+        .map(|((_, test_bench), cases)| JunitSuite {
+            name: test_bench,
+            cases,
+        })
+        .collect()
+}
+
+fn escape_xml(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            // Characters XML 1.0 doesn't allow, such as the escapes of colored output
+            c if (c as u32) < 0x20 && !matches!(c, '\t' | '\n' | '\r') => {}
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+impl JunitSuite {
+    fn failures(&self) -> usize {
+        self.cases
+            .iter()
+            .filter(|c| matches!(c.outcome, JunitOutcome::Failed { .. }))
+            .count()
+    }
+
+    fn skipped(&self) -> usize {
+        self.cases
+            .iter()
+            .filter(|c| matches!(c.outcome, JunitOutcome::Skipped { .. }))
+            .count()
+    }
+}
+
+/// A JUnit XML document of `suites`
+pub fn junit_xml(suites: &[JunitSuite]) -> String {
+    let total: usize = suites.iter().map(|s| s.cases.len()).sum();
+    let failures: usize = suites.iter().map(JunitSuite::failures).sum();
+
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    xml.push_str(&format!(
+        "<testsuites name=\"pcb\" tests=\"{total}\" failures=\"{failures}\" errors=\"0\">\n"
+    ));
+    for suite in suites {
+        xml.push_str(&format!(
+            "  <testsuite name=\"{}\" tests=\"{}\" failures=\"{}\" errors=\"0\" skipped=\"{}\">\n",
+            escape_xml(&suite.name),
+            suite.cases.len(),
+            suite.failures(),
+            suite.skipped(),
+        ));
+        for case in &suite.cases {
+            xml.push_str(&format!(
+                "    <testcase name=\"{}\" classname=\"{}\"",
+                escape_xml(&case.name),
+                escape_xml(&case.classname)
+            ));
+            if case.outcome == JunitOutcome::Passed && case.output.is_none() {
+                xml.push_str("/>\n");
+                continue;
+            }
+            xml.push_str(">\n");
+            match &case.outcome {
+                JunitOutcome::Passed => {}
+                JunitOutcome::Failed {
+                    kind,
+                    message,
+                    details,
+                } => xml.push_str(&format!(
+                    "      <failure type=\"{}\" message=\"{}\">{}</failure>\n",
+                    escape_xml(kind),
+                    escape_xml(message),
+                    escape_xml(details)
+                )),
+                JunitOutcome::Skipped { message } => xml.push_str(&format!(
+                    "      <skipped message=\"{}\"/>\n",
+                    escape_xml(message)
+                )),
+            }
+            if let Some(output) = &case.output {
+                xml.push_str(&format!(
+                    "      <system-out>{}</system-out>\n",
+                    escape_xml(output)
+                ));
+            }
+            xml.push_str("    </testcase>\n");
+        }
+        xml.push_str("  </testsuite>\n");
+    }
+    xml.push_str("</testsuites>\n");
+    xml
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use starlark::codemap::ResolvedPos;
+
+    fn span(line: usize, column: usize) -> ResolvedSpan {
+        ResolvedSpan {
+            begin: ResolvedPos { line, column },
+            end: ResolvedPos {
+                line,
+                column: column + 3,
+            },
+        }
+    }
+
+    /// An ERC error raised in a child module, reported against the instantiation in the board
+    fn nested_error() -> Diagnostic {
+        let inner = Diagnostic::new(
+            "Output pins U1.OUT and U2.OUT drive the same net",
+            EvalSeverity::Error,
+            Path::new("/ws/modules/Buffer.zen"),
+        )
+        .with_span(span(4, 0))
+        .with_source_error(Some(
+            CategorizedDiagnostic::new(
+                "Output pins U1.OUT and U2.OUT drive the same net".to_string(),
+                "erc.output_conflict".to_string(),
+            )
+            .unwrap(),
+        ));
+        Diagnostic::new(
+            "Error instantiating `Buffer`",
+            EvalSeverity::Error,
+            Path::new("/ws/boards/Main.zen"),
+        )
+        .with_span(span(10, 4))
+        .with_child(inner.boxed())
+    }
+
+    #[test]
+    fn test_sarif_result() {
+        let mut suppressed =
+            Diagnostic::new("Unused net", EvalSeverity::Warning, Path::new("/ws/a.zen"));
+        suppressed.suppressed = true;
+        let sources = vec![(
+            PathBuf::from("/ws/boards/Main.zen"),
+            vec![nested_error(), suppressed],
+        )];
+
+        let log = sarif(&sources, Path::new("/ws"), "1.0.0");
+        let run = &log["runs"][0];
+        assert_eq!(
+            run["tool"]["driver"]["rules"],
+            json!([{ "id": "erc.output_conflict" }])
+        );
+
+        let result = &run["results"][0];
+        assert_eq!(result["ruleId"], "erc.output_conflict");
+        assert_eq!(result["level"], "error");
+        assert_eq!(
+            result["locations"][0]["physicalLocation"],
+            json!({
+                "artifactLocation": { "uri": "modules/Buffer.zen", "uriBaseId": "%SRCROOT%" },
+                "region": { "startLine": 5, "startColumn": 1, "endLine": 5, "endColumn": 4 },
+            })
+        );
+        assert_eq!(
+            result["relatedLocations"][0]["message"]["text"],
+            "Error instantiating `Buffer`"
+        );
+        assert_eq!(
+            result["relatedLocations"][0]["physicalLocation"]["region"]["startLine"],
+            11
+        );
+
+        let result = &run["results"][1];
+        assert_eq!(result["level"], "warning");
+        assert_eq!(result["suppressions"], json!([{ "kind": "external" }]));
+    }
+
+    #[test]
+    fn test_junit_xml() {
+        let warning = Diagnostic::new(
+            "Net <VCC> has no voltage",
+            EvalSeverity::Warning,
+            Path::new("/ws/boards/Main.zen"),
+        );
+        let suites = vec![
+            diagnostics_suite(
+                "boards/Main.zen",
+                &[nested_error(), warning],
+                Path::new("/ws"),
+            ),
+            diagnostics_suite("boards/Clean.zen", &[], Path::new("/ws")),
+        ];
+
+        assert_eq!(
+            junit_xml(&suites),
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<testsuites name="pcb" tests="3" failures="1" errors="0">
+  <testsuite name="boards/Main.zen" tests="2" failures="1" errors="0" skipped="0">
+    <testcase name="erc.output_conflict at modules/Buffer.zen:5:1-4" classname="boards/Main.zen">
+      <failure type="erc.output_conflict" message="Output pins U1.OUT and U2.OUT drive the same net">Error: /ws/boards/Main.zen:11:5-8 Error instantiating `Buffer`
+Error: /ws/modules/Buffer.zen:5:1-4 Output pins U1.OUT and U2.OUT drive the same net</failure>
+    </testcase>
+    <testcase name="warning at boards/Main.zen" classname="boards/Main.zen">
+      <system-out>Warning: /ws/boards/Main.zen Net &lt;VCC&gt; has no voltage</system-out>
+    </testcase>
+  </testsuite>
+  <testsuite name="boards/Clean.zen" tests="1" failures="0" errors="0" skipped="0">
+    <testcase name="boards/Clean.zen" classname="boards/Clean.zen"/>
+  </testsuite>
+</testsuites>
+"#
+        );
+    }
+}
@@ -10,6 +10,7 @@ use pcb_zen_core::resolution::{
 use pcb_zen_core::DefaultFileProvider;
 use rayon::prelude::*;
 use semver::Version;
+use serde::Serialize;
 use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
 use std::fs;
 use std::path::{Path, PathBuf};
@@ -21,22 +22,24 @@ use crate::cache_index::{
 };
 use crate::canonical::{compute_content_hash_from_dir, compute_manifest_hash};
 use crate::git;
+use crate::package_index::PackageIndex;
 use crate::tags;
 use crate::workspace::{WorkspaceInfo, WorkspaceInfoExt};
 
 /// Find matching patch for a module path, supporting glob patterns.
 /// Exact matches take priority, then glob patterns in sorted order.
-fn find_matching_patch<'a>(
+/// Returns the matching pattern along with the patch.
+pub(crate) fn find_matching_patch<'a>(
     url: &str,
     patches: &'a BTreeMap<String, PatchSpec>,
-) -> Option<&'a PatchSpec> {
-    if let Some(patch) = patches.get(url) {
-        return Some(patch);
+) -> Option<(&'a str, &'a PatchSpec)> {
+    if let Some((pattern, patch)) = patches.get_key_value(url) {
+        return Some((pattern, patch));
     }
     for (pattern, patch) in patches {
         if let Ok(glob) = globset::Glob::new(pattern) {
             if glob.compile_matcher().is_match(url) {
-                return Some(patch);
+                return Some((pattern, patch));
             }
         }
     }
@@ -48,7 +51,7 @@ fn find_matching_patch<'a>(
 /// Patches act as a "dumb rewrite" of dependency specs before MVS runs.
 /// Path patches don't affect version resolution (they just change fetch location).
 fn get_patch_override(url: &str, patches: &BTreeMap<String, PatchSpec>) -> Option<DependencySpec> {
-    let patch = find_matching_patch(url, patches)?;
+    let (_, patch) = find_matching_patch(url, patches)?;
 
     if let Some(branch) = &patch.branch {
         return Some(DependencySpec::Detailed(DependencyDetail {
@@ -141,6 +144,69 @@ pub struct ResolutionResult {
     pub assets: HashMap<(String, String), PathBuf>,
     /// Whether the lockfile (pcb.sum) was updated during resolution
     pub lockfile_changed: bool,
+    /// Every version requirement seen during MVS, in the order it was added
+    pub requirements: Vec<Requirement>,
+}
+
+/// Where a version requirement came from
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum RequiredBy {
+    /// The `[dependencies]` of a workspace member, by package URL
+    Workspace { package: String },
+    /// The `[dependencies]` of a resolved package
+    Package {
+        url: String,
+        #[serde(serialize_with = "serialize_version")]
+        version: Version,
+    },
+    /// The stdlib version the toolchain requires
+    Toolchain,
+    /// A version recorded in pcb.sum
+    Lockfile,
+}
+
+impl std::fmt::Display for RequiredBy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RequiredBy::Workspace { package } => write!(f, "{package} (workspace)"),
+            RequiredBy::Package { url, version } => write!(f, "{url}@v{version}"),
+            RequiredBy::Toolchain => write!(f, "pcb toolchain"),
+            RequiredBy::Lockfile => write!(f, "pcb.sum"),
+        }
+    }
+}
+
+/// A version of a package requested during resolution
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Requirement {
+    pub url: String,
+    #[serde(serialize_with = "serialize_version")]
+    pub version: Version,
+    /// The dependency spec the version was resolved from, after patches
+    pub spec: String,
+    pub required_by: RequiredBy,
+}
+
+fn serialize_version<S: serde::Serializer>(version: &Version, s: S) -> Result<S::Ok, S::Error> {
+    s.collect_str(version)
+}
+
+/// Human-readable form of a dependency spec, e.g. `0.3`, `branch = "main"`
+fn describe_spec(spec: &DependencySpec) -> String {
+    match spec {
+        DependencySpec::Version(v) => v.clone(),
+        DependencySpec::Detailed(d) => [
+            d.version.as_ref().map(|v| v.to_string()),
+            d.branch.as_ref().map(|b| format!("branch = \"{b}\"")),
+            d.rev.as_ref().map(|r| format!("rev = \"{r}\"")),
+            d.path.as_ref().map(|p| format!("path = \"{p}\"")),
+        ]
+        .into_iter()
+        .flatten()
+        .collect::<Vec<_>>()
+        .join(", "),
+    }
 }
 
 impl ResolutionResult {
@@ -299,6 +365,7 @@ pub struct VendorResult {
 fn run_auto_deps(
     workspace_info: &mut WorkspaceInfo,
     workspace_root: &Path,
+    package_index: Option<&PackageIndex>,
     offline: bool,
 ) -> Result<()> {
     log::debug!("Phase -1: Auto-detecting dependencies from .zen files");
@@ -306,6 +373,7 @@ fn run_auto_deps(
         workspace_root,
         &workspace_info.packages,
         workspace_info.lockfile.as_ref(),
+        package_index,
         offline,
     )?;
 
@@ -393,11 +461,20 @@ pub fn resolve_dependencies(
     );
     log::debug!("Workspace root: {}", workspace_root.display());
 
+    // Package index from [workspace], used instead of git for the packages it lists.
+    // Only an index on disk can be used offline.
+    let package_index = PackageIndex::for_workspace(workspace_info, offline)?;
+
     // Phase -1: Auto-add missing dependencies from .zen files
     // Skip for standalone mode (no pcb.toml to modify)
     // Skip for locked/offline modes (trust the lockfile)
     if !is_standalone && !locked && !offline {
-        run_auto_deps(workspace_info, &workspace_root, offline)?;
+        run_auto_deps(
+            workspace_info,
+            &workspace_root,
+            package_index.as_ref(),
+            offline,
+        )?;
     }
 
     // Validate patches are only at workspace root
@@ -427,6 +504,7 @@ pub fn resolve_dependencies(
     let mut selected: HashMap<ModuleLine, Version> = HashMap::new();
     let mut work_queue: VecDeque<ModuleLine> = VecDeque::new();
     let mut manifest_cache: HashMap<(ModuleLine, Version), PackageManifest> = HashMap::new();
+    let mut requirements: Vec<Requirement> = Vec::new();
 
     // Inject implicit stdlib dependency (toolchain-pinned minimum version)
     // This ensures stdlib is always available without explicit declaration,
@@ -440,6 +518,12 @@ pub fn resolve_dependencies(
             &stdlib_version,
         );
         if find_matching_patch(pcb_zen_core::STDLIB_MODULE_PATH, &patches).is_none() {
+            requirements.push(Requirement {
+                url: pcb_zen_core::STDLIB_MODULE_PATH.to_string(),
+                version: stdlib_version.clone(),
+                spec: pcb_zen_core::STDLIB_VERSION.to_string(),
+                required_by: RequiredBy::Toolchain,
+            });
             selected.insert(stdlib_line.clone(), stdlib_version.clone());
             work_queue.push_back(stdlib_line);
             log::debug!(
@@ -481,6 +565,12 @@ pub fn resolve_dependencies(
             }
 
             let line = ModuleLine::new(entry.module_path.clone(), &version);
+            requirements.push(Requirement {
+                url: entry.module_path.clone(),
+                version: version.clone(),
+                spec: entry.version.clone(),
+                required_by: RequiredBy::Lockfile,
+            });
 
             // Insert if not already selected, or replace if this version is higher.
             // This ensures deterministic selection of the highest version within a family,
@@ -509,7 +599,7 @@ pub fn resolve_dependencies(
     let mut packages_with_deps = Vec::new();
     let mut packages_without_deps = 0;
 
-    for pkg in workspace_info.packages.values() {
+    for (pkg_url, pkg) in &workspace_info.packages {
         let pkg_dir = pkg.dir(&workspace_info.root);
         let package_name = pkg_dir
             .file_name()
@@ -539,7 +629,7 @@ pub fn resolve_dependencies(
             continue;
         }
 
-        packages_with_deps.push((package_name, package_deps));
+        packages_with_deps.push((pkg_url.clone(), package_name, package_deps));
     }
 
     // Print summary
@@ -548,7 +638,7 @@ pub fn resolve_dependencies(
     }
     if !packages_with_deps.is_empty() {
         log::debug!("  {} packages with dependencies:", packages_with_deps.len());
-        for (package_name, package_deps) in &packages_with_deps {
+        for (_, package_name, package_deps) in &packages_with_deps {
             log::debug!("    {} ({} deps)", package_name, package_deps.len());
         }
     }
@@ -557,7 +647,7 @@ pub fn resolve_dependencies(
     let mut pseudo_ctx = PseudoVersionContext::new()?;
 
     // Seed MVS state from direct dependencies
-    for (_package_name, package_deps) in &packages_with_deps {
+    for (pkg_url, _package_name, package_deps) in &packages_with_deps {
         for dep in package_deps {
             // Apply branch/rev patch overrides before resolution
             let patch_override = get_patch_override(&dep.url, &patches);
@@ -578,6 +668,14 @@ pub fn resolve_dependencies(
             )
             .with_context(|| format!("Failed to resolve {}", dep.url))?;
 
+            requirements.push(Requirement {
+                url: dep.url.clone(),
+                version: version.clone(),
+                spec: describe_spec(spec),
+                required_by: RequiredBy::Workspace {
+                    package: pkg_url.clone(),
+                },
+            });
             add_requirement(
                 dep.url.clone(),
                 version,
@@ -625,7 +723,13 @@ pub fn resolve_dependencies(
         let results: Vec<_> = wave
             .par_iter()
             .map(|(line, version)| {
-                let result = fetch_package(workspace_info, &line.path, version, offline);
+                let result = fetch_package(
+                    workspace_info,
+                    &line.path,
+                    version,
+                    package_index.as_ref(),
+                    offline,
+                );
                 (line.clone(), version.clone(), result)
             })
             .collect();
@@ -657,6 +761,15 @@ pub fn resolve_dependencies(
                 )
                 .with_context(|| format!("Failed to resolve {}", dep_path))?;
 
+                requirements.push(Requirement {
+                    url: dep_path.clone(),
+                    version: dep_version.clone(),
+                    spec: describe_spec(spec),
+                    required_by: RequiredBy::Package {
+                        url: line.path.clone(),
+                        version: version.clone(),
+                    },
+                });
                 let before = work_queue.len();
                 add_requirement(
                     dep_path.clone(),
@@ -782,6 +895,7 @@ pub fn resolve_dependencies(
         closure,
         assets: asset_paths,
         lockfile_changed,
+        requirements,
     })
 }
 
@@ -1373,7 +1487,7 @@ fn collect_and_fetch_assets(
     Ok(results.into_iter().collect())
 }
 
-/// Fetch a package from Git using sparse checkout
+/// Fetch a package from the package index or Git using sparse checkout
 ///
 /// Fetches all package files, computes content/manifest hashes, and caches locally.
 /// Returns the package manifest for dependency resolution.
@@ -1382,12 +1496,14 @@ fn collect_and_fetch_assets(
 /// 1. Workspace members (always)
 /// 2. Patches (always)
 /// 3. Vendor directory (always)
-/// 4. Cache (only if !offline)
-/// 5. Network fetch (only if !offline)
+/// 4. Cache (only if !offline, or if listed in an index on disk)
+/// 5. Package index (only if !offline, or if the index is on disk)
+/// 6. Network fetch (only if !offline)
 fn fetch_package(
     workspace_info: &WorkspaceInfo,
     module_path: &str,
     version: &Version,
+    package_index: Option<&PackageIndex>,
     offline: bool,
 ) -> Result<PackageManifest> {
     // 1. Workspace member override (highest priority)
@@ -1432,8 +1548,12 @@ fn fetch_package(
         return read_manifest_from_path(&vendor_toml);
     }
 
-    // 4. If offline, fail here - vendor is the only allowed source for offline builds
-    if offline {
+    // 4. If offline, fail here - vendor and package indexes on disk are the only allowed
+    // sources for offline builds
+    let index_entry = package_index
+        .and_then(|i| i.get(module_path, &version_str).map(|entry| (i, entry)))
+        .filter(|(i, _)| !offline || i.is_local());
+    if offline && index_entry.is_none() {
         anyhow::bail!(
             "Package not vendored (offline mode)\n  \
             Run `pcb vendor` to vendor dependencies for offline builds"
@@ -1454,6 +1574,20 @@ fn fetch_package(
         return read_manifest_from_path(&pcb_toml_path);
     }
 
+    // Package index: download the verified tarball instead of cloning
+    if let Some((package_index, entry)) = index_entry {
+        package_index
+            .fetch(module_path, entry, &checkout_dir)
+            .with_context(|| format!("Failed to fetch {module_path} from package index"))?;
+        index.set_package(
+            module_path,
+            &version_str,
+            &entry.content_hash,
+            &entry.manifest_hash,
+        )?;
+        return read_manifest_from_path(&checkout_dir.join("pcb.toml"));
+    }
+
     // Slow path: fetch via sparse checkout (network)
     let package_root = ensure_sparse_checkout(&checkout_dir, module_path, &version_str, true)?;
     let pcb_toml_path = package_root.join("pcb.toml");
@@ -2158,7 +2292,7 @@ fn update_lockfile(
 
     for (line, version) in closure {
         // Skip packages with path patches (local overrides are not locked)
-        if let Some(patch) = find_matching_patch(&line.path, patches) {
+        if let Some((_, patch)) = find_matching_patch(&line.path, patches) {
             if patch.path.is_some() {
                 continue;
             }
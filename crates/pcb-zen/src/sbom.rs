@@ -0,0 +1,580 @@
+//! Software bill of materials for a package closure.
+//!
+//! Lists every package and asset of a closure with its version, the hashes pinned in pcb.sum
+//! and the license declared by its `pcb.toml`, and writes it as a CycloneDX 1.5 or SPDX 2.3
+//! JSON document. A [`LicensePolicy`] from the workspace root rejects licenses that must not
+//! enter the closure.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::PathBuf;
+
+use anyhow::{bail, Result};
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use pcb_zen_core::config::{LicensePolicy, PcbToml};
+use serde::Serialize;
+use serde_json::{json, Value};
+
+use crate::cache_index::cache_base;
+use crate::workspace::{PackageClosure, WorkspaceInfo};
+
+/// Licenses of well-known asset repositories, which have no `pcb.toml` to declare one
+const KNOWN_ASSET_LICENSES: &[(&str, &str)] = &[("gitlab.com/kicad/libraries/", "CC-BY-SA-4.0")];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ComponentKind {
+    /// A workspace member
+    Local,
+    /// A package from the dependency closure
+    Package,
+    /// An asset repository, such as a KiCad library
+    Asset,
+}
+
+/// A package or asset of the closure
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Component {
+    pub kind: ComponentKind,
+    pub url: String,
+    /// Version, or ref for assets. Workspace members may be unversioned.
+    pub version: Option<String>,
+    /// Content hash from pcb.sum
+    pub content_hash: Option<String>,
+    /// Manifest hash from pcb.sum
+    pub manifest_hash: Option<String>,
+    /// SPDX license expression
+    pub license: Option<String>,
+    /// Components this one depends on, by [`Component::bom_ref`]
+    pub dependencies: Vec<String>,
+}
+
+impl Component {
+    /// Identifier of the component within the document
+    pub fn bom_ref(&self) -> String {
+        match &self.version {
+            Some(version) => format!("{}@{}", self.url, version),
+            None => self.url.clone(),
+        }
+    }
+}
+
+/// The components of a closure
+#[derive(Debug, Clone, Serialize)]
+pub struct Sbom {
+    /// Name of the described package or workspace
+    pub name: String,
+    pub version: Option<String>,
+    /// Components sorted by kind and URL
+    pub components: Vec<Component>,
+}
+
+impl Sbom {
+    /// Components of `closure`. Licenses come from each `pcb.toml`, then from the policy's
+    /// overrides, then from the known licenses of asset repositories.
+    pub fn from_closure(
+        name: &str,
+        version: Option<&str>,
+        workspace: &WorkspaceInfo,
+        closure: &PackageClosure,
+        policy: Option<&LicensePolicy>,
+    ) -> Self {
+        let lock = |url: &str, version: &str| {
+            workspace
+                .lockfile
+                .as_ref()
+                .and_then(|lf| lf.get(url, version))
+        };
+        let mut manifests: BTreeMap<(ComponentKind, String), (Option<String>, PcbToml)> =
+            BTreeMap::new();
+
+        for url in &closure.local_packages {
+            if let Some(pkg) = workspace.packages.get(url) {
+                manifests.insert(
+                    (ComponentKind::Local, url.clone()),
+                    (pkg.version.clone(), pkg.config.clone()),
+                );
+            }
+        }
+        for (url, version) in &closure.remote_packages {
+            let config = package_root(workspace, url, version)
+                .and_then(|root| std::fs::read_to_string(root.join("pcb.toml")).ok())
+                .and_then(|content| PcbToml::parse(&content).ok())
+                .unwrap_or_default();
+            manifests.insert(
+                (ComponentKind::Package, url.clone()),
+                (Some(version.clone()), config),
+            );
+        }
+
+        // bom-ref of each package and asset URL, for resolving dependencies
+        let mut refs: BTreeMap<&str, String> = BTreeMap::new();
+        for ((_, url), (version, _)) in &manifests {
+            let r = version
+                .as_ref()
+                .map_or(url.clone(), |v| format!("{url}@{v}"));
+            refs.insert(url, r);
+        }
+        for (url, ref_str) in &closure.assets {
+            refs.insert(url, format!("{url}@{ref_str}"));
+        }
+
+        let mut components: Vec<Component> = manifests
+            .iter()
+            .map(|((kind, url), (version, config))| {
+                let entry = version.as_deref().and_then(|v| lock(url, v));
+                let dependencies: BTreeSet<String> = config
+                    .dependencies
+                    .keys()
+                    .chain(config.assets.keys())
+                    .filter_map(|dep| refs.get(dep.as_str()).cloned())
+                    .collect();
+                Component {
+                    kind: *kind,
+                    url: url.clone(),
+                    version: version.clone(),
+                    content_hash: entry.map(|e| e.content_hash.clone()),
+                    manifest_hash: entry.and_then(|e| e.manifest_hash.clone()),
+                    license: config
+                        .license
+                        .clone()
+                        .or_else(|| override_license(url, policy)),
+                    dependencies: dependencies.into_iter().collect(),
+                }
+            })
+            .collect();
+
+        for (url, ref_str) in &closure.assets {
+            let entry = lock(url, ref_str);
+            components.push(Component {
+                kind: ComponentKind::Asset,
+                url: url.clone(),
+                version: Some(ref_str.clone()),
+                content_hash: entry.map(|e| e.content_hash.clone()),
+                manifest_hash: None,
+                license: override_license(url, policy).or_else(|| {
+                    KNOWN_ASSET_LICENSES
+                        .iter()
+                        .find(|(prefix, _)| url.starts_with(prefix))
+                        .map(|(_, license)| license.to_string())
+                }),
+                dependencies: Vec::new(),
+            });
+        }
+        components.sort_by(|a, b| (a.kind, &a.url, &a.version).cmp(&(b.kind, &b.url, &b.version)));
+
+        Self {
+            name: name.to_string(),
+            version: version.map(str::to_string),
+            components,
+        }
+    }
+}
+
+/// Directory of a resolved package: vendored, or else in the cache
+fn package_root(workspace: &WorkspaceInfo, url: &str, version: &str) -> Option<PathBuf> {
+    [workspace.root.join("vendor"), cache_base()]
+        .into_iter()
+        .map(|base| base.join(url).join(version))
+        .find(|dir| dir.exists())
+}
+
+fn override_license(url: &str, policy: Option<&LicensePolicy>) -> Option<String> {
+    policy?
+        .overrides
+        .iter()
+        .find(|(pattern, _)| pattern_matches(pattern, url))
+        .map(|(_, license)| license.clone())
+}
+
+/// Case-insensitive glob match, as used for URL patterns and license identifiers
+fn pattern_matches(pattern: &str, text: &str) -> bool {
+    globset::GlobBuilder::new(pattern)
+        .case_insensitive(true)
+        .literal_separator(false)
+        .build()
+        .is_ok_and(|glob| glob.compile_matcher().is_match(text))
+}
+
+/// A component whose license the policy rejects
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct LicenseViolation {
+    pub component: String,
+    pub license: Option<String>,
+    pub reason: String,
+}
+
+/// Components of `sbom` whose license `policy` rejects
+pub fn check_licenses(sbom: &Sbom, policy: &LicensePolicy) -> Vec<LicenseViolation> {
+    sbom.components
+        .iter()
+        .filter(|c| c.kind != ComponentKind::Local)
+        .filter_map(|c| {
+            let reason = match &c.license {
+                None if policy.allow.is_empty() => return None,
+                None => "no declared license".to_string(),
+                Some(expr) => match license_allowed(expr, policy) {
+                    Ok(true) => return None,
+                    Ok(false) => "license not allowed".to_string(),
+                    Err(e) => format!("{e:#}"),
+                },
+            };
+            Some(LicenseViolation {
+                component: c.bom_ref(),
+                license: c.license.clone(),
+                reason,
+            })
+        })
+        .collect()
+}
+
+/// Whether the SPDX expression `expr` is acceptable: one of its `OR` alternatives must have
+/// every `AND` term allowed
+pub fn license_allowed(expr: &str, policy: &LicensePolicy) -> Result<bool> {
+    let spaced = expr.replace('(', " ( ").replace(')', " ) ");
+    let tokens: Vec<&str> = spaced.split_whitespace().collect();
+    let mut pos = 0;
+    let allowed = eval_or(&tokens, &mut pos, policy)?;
+    if pos != tokens.len() {
+        bail!("invalid SPDX expression `{expr}`");
+    }
+    Ok(allowed)
+}
+
+fn eval_or(tokens: &[&str], pos: &mut usize, policy: &LicensePolicy) -> Result<bool> {
+    let mut allowed = eval_and(tokens, pos, policy)?;
+    while tokens
+        .get(*pos)
+        .is_some_and(|t| t.eq_ignore_ascii_case("OR"))
+    {
+        *pos += 1;
+        allowed |= eval_and(tokens, pos, policy)?;
+    }
+    Ok(allowed)
+}
+
+fn eval_and(tokens: &[&str], pos: &mut usize, policy: &LicensePolicy) -> Result<bool> {
+    let mut allowed = eval_term(tokens, pos, policy)?;
+    while tokens
+        .get(*pos)
+        .is_some_and(|t| t.eq_ignore_ascii_case("AND"))
+    {
+        *pos += 1;
+        allowed &= eval_term(tokens, pos, policy)?;
+    }
+    Ok(allowed)
+}
+
+fn eval_term(tokens: &[&str], pos: &mut usize, policy: &LicensePolicy) -> Result<bool> {
+    let token = tokens.get(*pos).copied().unwrap_or_default();
+    *pos += 1;
+    match token {
+        "(" => {
+            let allowed = eval_or(tokens, pos, policy)?;
+            if tokens.get(*pos) != Some(&")") {
+                bail!("unbalanced parentheses in SPDX expression");
+            }
+            *pos += 1;
+            Ok(allowed)
+        }
+        "" | ")" => bail!("invalid SPDX expression"),
+        id if ["AND", "OR", "WITH"].contains(&id.to_ascii_uppercase().as_str()) => {
+            bail!("invalid SPDX expression")
+        }
+        id => {
+            // `<license> WITH <exception>` is judged by the license, unless a pattern names
+            // the combination
+            let with = if tokens
+                .get(*pos)
+                .is_some_and(|t| t.eq_ignore_ascii_case("WITH"))
+            {
+                let exception = tokens
+                    .get(*pos + 1)
+                    .filter(|t| **t != "(" && **t != ")")
+                    .ok_or_else(|| anyhow::anyhow!("missing exception after WITH"))?;
+                *pos += 2;
+                Some(format!("{id} WITH {exception}"))
+            } else {
+                None
+            };
+            let matches = |patterns: &[String]| {
+                patterns.iter().any(|p| {
+                    pattern_matches(p, id) || with.as_ref().is_some_and(|w| pattern_matches(p, w))
+                })
+            };
+            Ok(!matches(&policy.deny) && (policy.allow.is_empty() || matches(&policy.allow)))
+        }
+    }
+}
+
+/// `h1:<base64>` hash as hex, as CycloneDX and SPDX expect
+fn hex_hash(hash: &str) -> Option<String> {
+    let bytes = STANDARD.decode(hash.strip_prefix("h1:")?).ok()?;
+    Some(bytes.iter().map(|b| format!("{b:02x}")).collect())
+}
+
+/// Download location of a component, as a URL
+fn vcs_url(component: &Component) -> String {
+    format!("https://{}", component.url)
+}
+
+/// CycloneDX 1.5 JSON document. `serial` is a UUID and `timestamp` an RFC 3339 time.
+pub fn cyclonedx(sbom: &Sbom, tool_version: &str, serial: &str, timestamp: &str) -> Value {
+    let components: Vec<Value> = sbom
+        .components
+        .iter()
+        .filter(|c| c.kind != ComponentKind::Local)
+        .map(|c| {
+            let mut component = json!({
+                "type": if c.kind == ComponentKind::Asset { "data" } else { "library" },
+                "bom-ref": c.bom_ref(),
+                "name": c.url,
+                "externalReferences": [{ "type": "vcs", "url": vcs_url(c) }],
+            });
+            if let Some(version) = &c.version {
+                component["version"] = json!(version);
+            }
+            if let Some(hash) = c.content_hash.as_deref().and_then(hex_hash) {
+                component["hashes"] = json!([{ "alg": "BLAKE3", "content": hash }]);
+            }
+            if let Some(license) = &c.license {
+                component["licenses"] = json!([{ "expression": license }]);
+            }
+            component
+        })
+        .collect();
+
+    // The described package, and the workspace members it includes
+    let mut root = json!({
+        "type": "application",
+        "bom-ref": sbom.name,
+        "name": sbom.name,
+    });
+    if let Some(version) = &sbom.version {
+        root["version"] = json!(version);
+    }
+
+    let local_refs: BTreeSet<String> = sbom
+        .components
+        .iter()
+        .filter(|c| c.kind == ComponentKind::Local)
+        .map(Component::bom_ref)
+        .collect();
+    let dependencies: Vec<Value> = sbom
+        .components
+        .iter()
+        .filter(|c| c.kind != ComponentKind::Local)
+        .map(|c| json!({ "ref": c.bom_ref(), "dependsOn": c.dependencies }))
+        .chain(std::iter::once({
+            // Workspace members are folded into the described package
+            let depends_on: BTreeSet<&String> = sbom
+                .components
+                .iter()
+                .filter(|c| c.kind == ComponentKind::Local)
+                .flat_map(|c| &c.dependencies)
+                .filter(|d| !local_refs.contains(*d))
+                .collect();
+            json!({ "ref": sbom.name, "dependsOn": depends_on })
+        }))
+        .collect();
+
+    json!({
+        "bomFormat": "CycloneDX",
+        "specVersion": "1.5",
+        "serialNumber": format!("urn:uuid:{serial}"),
+        "version": 1,
+        "metadata": {
+            "timestamp": timestamp,
+            "tools": { "components": [{ "type": "application", "name": "pcb", "version": tool_version }] },
+            "component": root,
+        },
+        "components": components,
+        "dependencies": dependencies,
+    })
+}
+
+/// SPDX 2.3 JSON document. `serial` is a UUID and `timestamp` an RFC 3339 time.
+pub fn spdx(sbom: &Sbom, tool_version: &str, serial: &str, timestamp: &str) -> Value {
+    let ids: BTreeMap<String, String> = sbom
+        .components
+        .iter()
+        .enumerate()
+        .map(|(i, c)| (c.bom_ref(), format!("SPDXRef-Package-{}", i + 1)))
+        .collect();
+
+    let packages: Vec<Value> = sbom
+        .components
+        .iter()
+        .map(|c| {
+            let mut package = json!({
+                "SPDXID": ids[&c.bom_ref()],
+                "name": c.url,
+                "downloadLocation": if c.kind == ComponentKind::Local {
+                    "NOASSERTION".to_string()
+                } else {
+                    format!("git+{}", vcs_url(c))
+                },
+                "filesAnalyzed": false,
+                "licenseConcluded": "NOASSERTION",
+                "licenseDeclared": c.license.as_deref().unwrap_or("NOASSERTION"),
+                "copyrightText": "NOASSERTION",
+            });
+            if let Some(version) = &c.version {
+                package["versionInfo"] = json!(version);
+            }
+            if let Some(hash) = c.content_hash.as_deref().and_then(hex_hash) {
+                package["checksums"] = json!([{ "algorithm": "BLAKE3", "checksumValue": hash }]);
+            }
+            package
+        })
+        .collect();
+
+    let mut relationships: Vec<Value> = sbom
+        .components
+        .iter()
+        .filter(|c| c.kind == ComponentKind::Local)
+        .map(|c| {
+            json!({
+                "spdxElementId": "SPDXRef-DOCUMENT",
+                "relationshipType": "DESCRIBES",
+                "relatedSpdxElement": ids[&c.bom_ref()],
+            })
+        })
+        .collect();
+    for c in &sbom.components {
+        for dep in c.dependencies.iter().filter_map(|d| ids.get(d)) {
+            relationships.push(json!({
+                "spdxElementId": ids[&c.bom_ref()],
+                "relationshipType": "DEPENDS_ON",
+                "relatedSpdxElement": dep,
+            }));
+        }
+    }
+
+    json!({
+        "spdxVersion": "SPDX-2.3",
+        "dataLicense": "CC0-1.0",
+        "SPDXID": "SPDXRef-DOCUMENT",
+        "name": sbom.name,
+        "documentNamespace": format!("https://spdx.org/spdxdocs/{}-{serial}", sbom.name.replace('/', "-")),
+        "creationInfo": {
+            "created": timestamp,
+            "creators": [format!("Tool: pcb-{tool_version}")],
+        },
+        "packages": packages,
+        "relationships": relationships,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy(allow: &[&str], deny: &[&str]) -> LicensePolicy {
+        LicensePolicy {
+            allow: allow.iter().map(|s| s.to_string()).collect(),
+            deny: deny.iter().map(|s| s.to_string()).collect(),
+            overrides: BTreeMap::new(),
+        }
+    }
+
+    fn component(kind: ComponentKind, url: &str, license: Option<&str>) -> Component {
+        Component {
+            kind,
+            url: url.to_string(),
+            version: Some("1.0.0".to_string()),
+            content_hash: Some("h1:AAEC".to_string()),
+            manifest_hash: None,
+            license: license.map(str::to_string),
+            dependencies: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_license_expressions() {
+        let deny_gpl = policy(&[], &["GPL-*", "AGPL-*"]);
+        assert!(license_allowed("MIT", &deny_gpl).unwrap());
+        assert!(!license_allowed("GPL-3.0-only", &deny_gpl).unwrap());
+        assert!(license_allowed("MIT OR GPL-3.0-only", &deny_gpl).unwrap());
+        assert!(!license_allowed("MIT AND gpl-2.0-or-later", &deny_gpl).unwrap());
+        assert!(license_allowed("(GPL-2.0 AND MIT) OR Apache-2.0", &deny_gpl).unwrap());
+        assert!(!license_allowed("GPL-2.0 WITH Classpath-exception-2.0", &deny_gpl).unwrap());
+        assert!(license_allowed("(MIT", &deny_gpl).is_err());
+        assert!(license_allowed("MIT OR", &deny_gpl).is_err());
+
+        let permissive = policy(&["MIT", "Apache-*", "CC-BY-SA-4.0"], &[]);
+        assert!(license_allowed("Apache-2.0", &permissive).unwrap());
+        assert!(!license_allowed("MPL-2.0", &permissive).unwrap());
+    }
+
+    #[test]
+    fn test_check_licenses() {
+        let sbom = Sbom {
+            name: "board".to_string(),
+            version: None,
+            components: vec![
+                component(
+                    ComponentKind::Local,
+                    "github.com/acme/board",
+                    Some("GPL-3.0"),
+                ),
+                component(ComponentKind::Package, "github.com/acme/mcu", Some("MIT")),
+                component(
+                    ComponentKind::Package,
+                    "github.com/acme/gpl",
+                    Some("GPL-3.0"),
+                ),
+                component(ComponentKind::Asset, "github.com/acme/lib", None),
+            ],
+        };
+
+        // Workspace members are not checked, and undeclared licenses pass a deny-only policy
+        let violations = check_licenses(&sbom, &policy(&[], &["GPL-*"]));
+        assert_eq!(violations.len(), 1);
+        assert_eq!(violations[0].component, "github.com/acme/gpl@1.0.0");
+
+        let violations = check_licenses(&sbom, &policy(&["MIT"], &[]));
+        assert_eq!(violations.len(), 2);
+        assert_eq!(violations[1].reason, "no declared license");
+    }
+
+    #[test]
+    fn test_documents() {
+        let mut mcu = component(ComponentKind::Package, "github.com/acme/mcu", Some("MIT"));
+        mcu.dependencies = vec!["gitlab.com/kicad/libraries/kicad-symbols@9.0.3".to_string()];
+        let mut board = component(ComponentKind::Local, "github.com/acme/board", None);
+        board.dependencies = vec![mcu.bom_ref()];
+        let mut symbols = component(
+            ComponentKind::Asset,
+            "gitlab.com/kicad/libraries/kicad-symbols",
+            Some("CC-BY-SA-4.0"),
+        );
+        symbols.version = Some("9.0.3".to_string());
+        let sbom = Sbom {
+            name: "board".to_string(),
+            version: Some("1.2.0".to_string()),
+            components: vec![board, mcu, symbols],
+        };
+
+        let cdx = cyclonedx(&sbom, "0.3.21", "uuid", "2026-01-01T00:00:00Z");
+        assert_eq!(cdx["components"].as_array().unwrap().len(), 2);
+        assert_eq!(cdx["components"][0]["hashes"][0]["content"], "000102");
+        assert_eq!(cdx["components"][1]["type"], "data");
+        let root = cdx["dependencies"]
+            .as_array()
+            .unwrap()
+            .last()
+            .unwrap()
+            .clone();
+        assert_eq!(root["ref"], "board");
+        assert_eq!(root["dependsOn"][0], "github.com/acme/mcu@1.0.0");
+
+        let doc = spdx(&sbom, "0.3.21", "uuid", "2026-01-01T00:00:00Z");
+        assert_eq!(doc["packages"].as_array().unwrap().len(), 3);
+        assert_eq!(doc["packages"][0]["licenseDeclared"], "NOASSERTION");
+        assert_eq!(doc["packages"][2]["licenseDeclared"], "CC-BY-SA-4.0");
+        let relationships = doc["relationships"].as_array().unwrap();
+        assert_eq!(relationships[0]["relationshipType"], "DESCRIBES");
+        assert_eq!(relationships.len(), 3);
+    }
+}
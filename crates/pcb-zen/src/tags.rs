@@ -16,6 +16,7 @@ use semver::Version;
 
 use crate::cache_index::ensure_bare_repo;
 use crate::git;
+use crate::package_index::PackageIndex;
 
 /// Parse a version string, with or without 'v' prefix.
 ///
@@ -145,6 +146,21 @@ pub fn get_all_versions_for_repo(repo_url: &str) -> Result<BTreeMap<String, Vec<
     Ok(packages)
 }
 
+/// Like [`get_all_versions_for_repo`], but from `package_index` when it lists packages in
+/// the repo, so that a workspace with an index doesn't need git to discover versions.
+pub fn get_versions_for_repo(
+    repo_url: &str,
+    package_index: Option<&PackageIndex>,
+) -> Result<BTreeMap<String, Vec<Version>>> {
+    if let Some(index) = package_index {
+        let packages = index.repo_versions(repo_url);
+        if !packages.is_empty() {
+            return Ok(packages);
+        }
+    }
+    get_all_versions_for_repo(repo_url)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
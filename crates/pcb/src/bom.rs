@@ -83,6 +83,10 @@ pub struct BomArgs {
     /// add dependencies or if the lockfile would be modified. Recommended for CI.
     #[arg(long)]
     pub locked: bool,
+
+    /// Generate the BOM of the named assembly variant declared with variant()
+    #[arg(long, value_name = "NAME")]
+    pub variant: Option<String>,
 }
 
 pub fn execute(args: BomArgs) -> Result<()> {
@@ -103,15 +107,18 @@ pub fn execute(args: BomArgs) -> Result<()> {
     let layout_path = extract_layout_path(&args.file, &eval_result).ok();
     let eval_output = eval_result.output_result().map_err(|mut diagnostics| {
         // Apply passes and render diagnostics if there are errors
-        diagnostics.apply_passes(&create_diagnostics_passes(&[], &[]));
+        diagnostics.apply_passes(&create_diagnostics_passes(&[], &[], &[]));
         anyhow::anyhow!("Failed to build {} - cannot generate BOM", file_name)
     })?;
 
     // Generate BOM entries with KiCad fallback
     spinner.set_message(format!("{file_name}: Generating BOM"));
-    let schematic = eval_output
+    let mut schematic = eval_output
         .to_schematic()
         .context("Failed to convert to schematic")?;
+    if let Some(variant) = &args.variant {
+        schematic.apply_variant(variant)?;
+    }
 
     let mut bom = generate_bom_with_fallback(schematic.bom(), layout_path.as_deref())?;
 
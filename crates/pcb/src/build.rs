@@ -6,6 +6,7 @@ use pcb_ui::prelude::*;
 use std::path::{Path, PathBuf};
 
 use crate::file_walker;
+use crate::report::{self, ReportFormat};
 
 fn execute_electrical_check(
     check: &pcb_zen_core::lang::electrical_check::FrozenElectricalCheck,
@@ -26,6 +27,7 @@ fn execute_electrical_check(
 pub fn create_diagnostics_passes(
     suppress: &[String],
     promote: &[String],
+    deny: &[String],
 ) -> Vec<Box<dyn pcb_zen_core::DiagnosticsPass>> {
     let mut passes: Vec<Box<dyn pcb_zen_core::DiagnosticsPass>> = vec![
         Box::new(pcb_zen_core::FilterHiddenPass),
@@ -38,6 +40,11 @@ pub fn create_diagnostics_passes(
         passes.push(Box::new(pcb_zen_core::PromotePass::new(promote.to_vec())));
     }
 
+    // Escalate specific kinds to errors (e.g., -D erc); -D warnings is handled by the caller
+    if deny.iter().any(|d| d != "warnings") {
+        passes.push(Box::new(pcb_zen_core::DenyPass::new(deny.to_vec())));
+    }
+
     passes.push(Box::new(pcb_zen_core::AggregatePass));
     passes.push(Box::new(pcb_zen_core::SortPass));
     passes.push(Box::new(pcb_zen::diagnostics::RenderPass));
@@ -54,19 +61,23 @@ pub struct BuildArgs {
     pub paths: Vec<PathBuf>,
 
     /// Print JSON netlist to stdout (undocumented)
-    #[arg(long = "netlist", hide = true)]
+    #[arg(long = "netlist", hide = true, conflicts_with = "format")]
     pub netlist: bool,
 
     /// Print board config JSON to stdout (undocumented)
-    #[arg(long = "board-config", hide = true)]
+    #[arg(long = "board-config", hide = true, conflicts_with = "format")]
     pub board_config: bool,
 
+    /// Write hierarchical KiCad schematic sheets (.kicad_sch) next to the layout
+    #[arg(long = "schematic")]
+    pub schematic: bool,
+
     /// Disable network access (offline mode) - only use vendored dependencies
     #[arg(long = "offline")]
     pub offline: bool,
 
     /// Set lint level to deny (treat as error). Use 'warnings' for all warnings,
-    /// or specific lint names like 'unstable-refs'
+    /// or specific kinds like 'erc' or 'erc.output_conflict'
     #[arg(short = 'D', long = "deny", value_name = "LINT")]
     pub deny: Vec<String>,
 
@@ -86,6 +97,15 @@ pub struct BuildArgs {
     /// add dependencies or if the lockfile would be modified. Recommended for CI.
     #[arg(long)]
     pub locked: bool,
+
+    /// Print the diagnostics as a SARIF or JUnit XML report on stdout.
+    /// Cannot be combined with --netlist or --board-config, which also print to stdout.
+    #[arg(long = "format", value_enum, value_name = "FORMAT")]
+    pub format: Option<ReportFormat>,
+
+    /// Build the named assembly variant declared with variant()
+    #[arg(long, value_name = "NAME")]
+    pub variant: Option<String>,
 }
 
 /// Print success message with component count for a built schematic
@@ -136,12 +156,17 @@ pub fn build(
         None
     };
 
-    // Convert to schematic and merge diagnostics
+    // Convert to schematic, run the ERC and merge diagnostics
     let schematic = output.and_then(|eval_output| {
         let schematic_result = eval_output.to_schematic_with_diagnostics();
         diagnostics
             .diagnostics
             .extend(schematic_result.diagnostics.diagnostics);
+        if let Some(schematic) = &schematic_result.output {
+            diagnostics
+                .diagnostics
+                .extend(eval_output.run_electrical_checks(schematic).diagnostics);
+        }
         schematic_result.output
     });
 
@@ -194,15 +219,21 @@ pub fn execute(args: BuildArgs) -> Result<()> {
     // Process .zen files using shared walker - always recursive for directories
     let zen_files = file_walker::collect_workspace_zen_files(&args.paths, &workspace_info)?;
 
+    let collector = args
+        .format
+        .map(|_| pcb_zen::report::DiagnosticsCollector::new());
+
     // Process each .zen file
     let deny_warnings = args.deny.contains(&"warnings".to_string());
     let mut has_warnings = false;
     for zen_path in &zen_files {
         let file_name = zen_path.file_name().unwrap().to_string_lossy();
-        let Some(schematic) = build(
+        let mut passes = create_diagnostics_passes(&args.suppress, &args.warn, &args.deny);
+        report::collect(&mut passes, collector.as_ref(), zen_path);
+        let Some(mut schematic) = build(
             zen_path,
             args.offline,
-            create_diagnostics_passes(&args.suppress, &args.warn),
+            passes,
             deny_warnings,
             &mut has_errors,
             &mut has_warnings,
@@ -211,6 +242,18 @@ pub fn execute(args: BuildArgs) -> Result<()> {
             continue;
         };
 
+        if let Some(variant) = &args.variant {
+            if let Err(e) = schematic.apply_variant(variant) {
+                eprintln!(
+                    "{} {}: {e}",
+                    pcb_ui::icons::error(),
+                    file_name.with_style(Style::Red).bold()
+                );
+                has_errors = true;
+                continue;
+            }
+        }
+
         if args.netlist {
             match schematic.to_json() {
                 Ok(json) => println!("{json}"),
@@ -234,6 +277,45 @@ pub fn execute(args: BuildArgs) -> Result<()> {
         } else {
             print_build_success(&file_name, &schematic);
         }
+
+        if args.schematic {
+            match pcb_layout::write_schematic(&schematic, zen_path) {
+                Ok(sheets) => {
+                    if let Some(root_sheet) = sheets.first() {
+                        let relative_path = zen_path
+                            .parent()
+                            .and_then(|parent| root_sheet.strip_prefix(parent).ok())
+                            .unwrap_or(root_sheet);
+                        eprintln!(
+                            "{} {} ({}, {} sheets)",
+                            pcb_ui::icons::success(),
+                            file_name.with_style(Style::Green).bold(),
+                            relative_path.display(),
+                            sheets.len()
+                        );
+                    }
+                }
+                Err(pcb_layout::LayoutError::NoLayoutPath) => {
+                    eprintln!(
+                        "{} {} (no layout path, skipping schematic)",
+                        pcb_ui::icons::warning(),
+                        file_name.with_style(Style::Yellow).bold(),
+                    );
+                }
+                Err(e) => {
+                    eprintln!(
+                        "{} {}: {e:#}",
+                        pcb_ui::icons::error(),
+                        file_name.with_style(Style::Red).bold()
+                    );
+                    has_errors = true;
+                }
+            }
+        }
+    }
+
+    if let (Some(format), Some(collector)) = (args.format, &collector) {
+        report::print_report(format, collector, &workspace_info.root)?;
     }
 
     if has_errors {
@@ -0,0 +1,227 @@
+//! `pcb deps tree` and `pcb deps why`: explain the V2 dependency resolution
+
+use anyhow::{bail, Result};
+use clap::{Args, Subcommand};
+use colored::Colorize;
+use pcb_zen::dep_graph::{DepGraph, PackageSource, ResolvedPackage};
+use pcb_zen::resolve_v2::{RequiredBy, ResolutionResult};
+use pcb_zen::workspace::{get_workspace_info, WorkspaceInfo};
+use pcb_zen_core::DefaultFileProvider;
+use std::collections::HashSet;
+use std::path::PathBuf;
+
+use crate::info::OutputFormat;
+
+#[derive(Args)]
+pub struct DepsArgs {
+    #[command(subcommand)]
+    pub command: DepsCommands,
+}
+
+#[derive(Subcommand)]
+pub enum DepsCommands {
+    /// Print the resolved dependency graph
+    Tree(TreeArgs),
+
+    /// Explain why a package is resolved at its version
+    Why(WhyArgs),
+}
+
+#[derive(Args)]
+pub struct TreeArgs {
+    /// Output format
+    #[arg(short = 'f', long, value_enum, default_value = "human")]
+    pub format: OutputFormat,
+
+    /// Resolve without network access, from pcb.sum and the cache
+    #[arg(long)]
+    pub offline: bool,
+
+    /// Optional path to start discovery from (defaults to current directory)
+    pub path: Option<PathBuf>,
+}
+
+#[derive(Args)]
+pub struct WhyArgs {
+    /// Fully-qualified package URL (e.g., github.com/diodeinc/stdlib)
+    #[arg(value_name = "URL")]
+    pub url: String,
+
+    /// Output format
+    #[arg(short = 'f', long, value_enum, default_value = "human")]
+    pub format: OutputFormat,
+
+    /// Resolve without network access, from pcb.sum and the cache
+    #[arg(long)]
+    pub offline: bool,
+
+    /// Optional path to start discovery from (defaults to current directory)
+    pub path: Option<PathBuf>,
+}
+
+pub fn execute(args: DepsArgs) -> Result<()> {
+    match args.command {
+        DepsCommands::Tree(args) => execute_tree(args),
+        DepsCommands::Why(args) => execute_why(args),
+    }
+}
+
+/// Resolve the workspace at `path` and build its dependency graph
+fn resolve(
+    path: Option<PathBuf>,
+    offline: bool,
+) -> Result<(WorkspaceInfo, ResolutionResult, DepGraph)> {
+    let start_path = match path {
+        Some(path) => path,
+        None => std::env::current_dir()?,
+    };
+    let mut workspace = get_workspace_info(&DefaultFileProvider::new(), &start_path)?;
+    if !workspace.is_v2() {
+        bail!("Dependency graphs are only available for V2 workspaces");
+    }
+    let resolution = pcb_zen::resolve_dependencies(&mut workspace, offline, false)?;
+    let graph = DepGraph::new(&workspace, &resolution);
+    Ok((workspace, resolution, graph))
+}
+
+fn execute_tree(args: TreeArgs) -> Result<()> {
+    let (workspace, resolution, graph) = resolve(args.path, args.offline)?;
+    if matches!(args.format, OutputFormat::Json) {
+        println!("{}", serde_json::to_string_pretty(&graph)?);
+        return Ok(());
+    }
+
+    resolution.print_tree(&workspace);
+
+    let vendored: Vec<_> = graph
+        .packages
+        .iter()
+        .filter(|p| matches!(p.source, PackageSource::Vendor))
+        .collect();
+    if !vendored.is_empty() {
+        println!();
+        println!("{}", "Vendored".bold());
+        for package in vendored {
+            println!("  {} v{}", package.url, package.version);
+        }
+    }
+    if !graph.patches.is_empty() {
+        println!();
+        println!("{}", "Patches".bold());
+        for patch in &graph.patches {
+            let fork = if patch.fork { " (fork)" } else { "" };
+            println!(
+                "  {} → {}{}",
+                patch.pattern.cyan(),
+                patch.replacement,
+                fork.dimmed()
+            );
+        }
+    }
+    print_conflicts(&graph);
+    Ok(())
+}
+
+fn source_suffix(source: &PackageSource) -> String {
+    match source {
+        PackageSource::Cache => String::new(),
+        PackageSource::Vendor => " [vendored]".to_string(),
+        PackageSource::Patch { pattern } => format!(" [patched: {pattern}]"),
+    }
+}
+
+fn print_conflicts(graph: &DepGraph) {
+    if graph.conflicts.is_empty() {
+        return;
+    }
+    println!();
+    println!("{}", "Duplicate major versions".yellow().bold());
+    for conflict in &graph.conflicts {
+        let versions: Vec<_> = conflict.versions.iter().map(|v| format!("v{v}")).collect();
+        println!("  {} at {}", conflict.url, versions.join(", "));
+    }
+    println!(
+        "  {}",
+        "Each major version is built as a separate package; values don't mix between them."
+            .dimmed()
+    );
+}
+
+fn execute_why(args: WhyArgs) -> Result<()> {
+    let (workspace, _, graph) = resolve(args.path, args.offline)?;
+    let why = graph.why(&args.url);
+    let fork = workspace.packages.get(&args.url);
+
+    if why.packages.is_empty() && fork.is_none() {
+        bail!("{} is not in the dependency graph", args.url);
+    }
+    if matches!(args.format, OutputFormat::Json) {
+        println!("{}", serde_json::to_string_pretty(&why)?);
+        return Ok(());
+    }
+
+    if let Some(member) = fork {
+        println!(
+            "{} is a workspace member at {}, so no version is resolved for it",
+            args.url.bold(),
+            member.rel_path.display()
+        );
+    }
+    for package in &why.packages {
+        println!(
+            "{} v{}{}",
+            package.url.bold(),
+            package.version,
+            source_suffix(&package.source).yellow()
+        );
+        let mut visited = HashSet::new();
+        print_dependents(&graph, package, "", &mut visited);
+        println!();
+    }
+    for patch in &why.patches {
+        let kind = if patch.fork { "Forked" } else { "Patched" };
+        println!(
+            "{} by [patch] {} → {}",
+            kind,
+            patch.pattern.cyan(),
+            patch.replacement
+        );
+    }
+    print_conflicts(&why);
+    Ok(())
+}
+
+/// Print the requirements on `package`, and recursively the requirements on the packages
+/// that made them, marking the ones that selected its version
+fn print_dependents(
+    graph: &DepGraph,
+    package: &ResolvedPackage,
+    prefix: &str,
+    visited: &mut HashSet<(String, String)>,
+) {
+    for (i, req) in package.requirements.iter().enumerate() {
+        let is_last = i == package.requirements.len() - 1;
+        let branch = if is_last { "└── " } else { "├── " };
+        let selected = if req.selected {
+            " ← selected".green().to_string()
+        } else {
+            String::new()
+        };
+        println!(
+            "{}{}{} requires {}{}",
+            prefix, branch, req.requirement.required_by, req.requirement.spec, selected
+        );
+
+        let RequiredBy::Package { url, version } = &req.requirement.required_by else {
+            continue;
+        };
+        let Some(requirer) = graph.get(url, version) else {
+            continue;
+        };
+        if !visited.insert((url.clone(), version.to_string())) {
+            continue;
+        }
+        let child_prefix = format!("{}{}", prefix, if is_last { "    " } else { "│   " });
+        print_dependents(graph, requirer, &child_prefix, visited);
+    }
+}
@@ -0,0 +1,286 @@
+//! `pcb diff`: connectivity diff of a design between two git revisions
+
+use anyhow::{bail, Context, Result};
+use clap::Args;
+use colored::Colorize;
+use pcb_sch::Schematic;
+use pcb_zen::workspace::get_workspace_info;
+use pcb_zen::{git, EvalConfig};
+use pcb_zen_core::schematic_diff::{self, SchematicDiff};
+use pcb_zen_core::{normalize_path, DefaultFileProvider};
+use std::path::{Path, PathBuf};
+
+use crate::info::OutputFormat;
+
+#[derive(Args, Debug)]
+#[command(about = "Compare the components and nets of a design between two git revisions")]
+pub struct DiffArgs {
+    /// Old revision (branch, tag or commit)
+    pub old: String,
+
+    /// New revision (branch, tag or commit)
+    pub new: String,
+
+    /// .zen file to compare (defaults to every board of the workspace)
+    #[arg(value_hint = clap::ValueHint::FilePath)]
+    pub file: Option<PathBuf>,
+
+    /// Output format
+    #[arg(short = 'f', long, value_enum, default_value = "human")]
+    pub format: OutputFormat,
+
+    /// Resolve dependencies without network access
+    #[arg(long)]
+    pub offline: bool,
+}
+
+/// A revision of the repository checked out in a temporary worktree
+struct Checkout {
+    repo_root: PathBuf,
+    root: PathBuf,
+    _dir: tempfile::TempDir,
+}
+
+impl Checkout {
+    fn new(repo_root: &Path, rev: &str) -> Result<Self> {
+        if git::rev_parse(repo_root, &format!("{rev}^{{commit}}")).is_none() {
+            bail!("Unknown revision '{rev}'");
+        }
+        let dir = tempfile::tempdir()?;
+        let root = dir.path().canonicalize()?.join("tree");
+        git::create_worktree(repo_root, &root, rev)?;
+        Ok(Self {
+            repo_root: repo_root.to_path_buf(),
+            root,
+            _dir: dir,
+        })
+    }
+
+    /// Schematic of the design at `rel_path`, or None if it doesn't exist in this revision
+    fn schematic(&self, rel_path: &Path, offline: bool) -> Result<Option<Schematic>> {
+        let zen_path = self.root.join(rel_path);
+        if !zen_path.exists() {
+            return Ok(None);
+        }
+        let mut workspace =
+            get_workspace_info(&DefaultFileProvider::new(), zen_path.parent().unwrap())?;
+        let resolution = if workspace.is_v2() {
+            Some(pcb_zen::resolve_dependencies(
+                &mut workspace,
+                offline,
+                false,
+            )?)
+        } else {
+            None
+        };
+
+        let eval_result =
+            pcb_zen::eval(&zen_path, EvalConfig::with_resolution(resolution, offline));
+        let output = eval_result.output_result().map_err(|mut diagnostics| {
+            diagnostics.apply_passes(&crate::build::create_diagnostics_passes(&[], &[], &[]));
+            anyhow::anyhow!("Failed to evaluate {}", rel_path.display())
+        })?;
+        output.to_schematic().map(Some)
+    }
+}
+
+impl Drop for Checkout {
+    fn drop(&mut self) {
+        let _ = git::remove_worktree(&self.repo_root, &self.root);
+        let _ = git::prune_worktrees(&self.repo_root);
+    }
+}
+
+pub fn execute(args: DiffArgs) -> Result<()> {
+    let cwd = std::env::current_dir()?;
+    let repo_root = git::get_repo_root(&cwd)?.canonicalize()?;
+
+    // Designs to compare, relative to the repository root
+    let files: Vec<PathBuf> = match &args.file {
+        // Resolved lexically: the design may only exist at one of the revisions
+        Some(file) => {
+            let path = normalize_path(&cwd.canonicalize()?.join(file));
+            let rel = path
+                .strip_prefix(&repo_root)
+                .with_context(|| format!("{} is not inside the repository", file.display()))?;
+            vec![rel.to_path_buf()]
+        }
+        None => {
+            let workspace = get_workspace_info(&DefaultFileProvider::new(), &cwd)?;
+            let workspace_root = workspace.root.canonicalize()?;
+            let rel_root = workspace_root.strip_prefix(&repo_root)?.to_path_buf();
+            workspace
+                .boards()
+                .values()
+                .map(|board| rel_root.join(&board.zen_path))
+                .collect()
+        }
+    };
+    if files.is_empty() {
+        bail!("No boards found; pass the .zen file to compare");
+    }
+
+    let old = Checkout::new(&repo_root, &args.old)?;
+    let new = Checkout::new(&repo_root, &args.new)?;
+
+    let mut diffs = Vec::new();
+    for file in &files {
+        let old_schematic = old
+            .schematic(file, args.offline)
+            .with_context(|| format!("At {}", args.old))?;
+        let new_schematic = new
+            .schematic(file, args.offline)
+            .with_context(|| format!("At {}", args.new))?;
+        let diff = match (&old_schematic, &new_schematic) {
+            (Some(a), Some(b)) => Some(schematic_diff::diff(a, &old.root, b, &new.root)),
+            _ => None,
+        };
+        diffs.push(FileDiff {
+            file: file.display().to_string(),
+            in_old: old_schematic.is_some(),
+            in_new: new_schematic.is_some(),
+            diff,
+        });
+    }
+
+    match args.format {
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&diffs)?),
+        OutputFormat::Human => {
+            for file_diff in &diffs {
+                print_file_diff(file_diff, &args.old, &args.new);
+            }
+        }
+    }
+    Ok(())
+}
+
+#[derive(serde::Serialize)]
+struct FileDiff {
+    file: String,
+    in_old: bool,
+    in_new: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    diff: Option<SchematicDiff>,
+}
+
+fn print_file_diff(file_diff: &FileDiff, old_rev: &str, new_rev: &str) {
+    println!("{} {}..{}", file_diff.file.bold(), old_rev, new_rev);
+    let Some(diff) = &file_diff.diff else {
+        let rev = if file_diff.in_old { new_rev } else { old_rev };
+        println!("  {}", format!("Not present at {rev}").dimmed());
+        println!();
+        return;
+    };
+    if diff.is_empty() {
+        println!("  {}", "No connectivity changes".dimmed());
+        println!();
+        return;
+    }
+
+    let has_components = !diff.added_components.is_empty()
+        || !diff.removed_components.is_empty()
+        || !diff.changed_components.is_empty();
+    if has_components {
+        println!("  {}", "Components".bold());
+    }
+    for c in &diff.added_components {
+        let part = [c.mpn.as_deref(), c.value.as_deref()]
+            .into_iter()
+            .flatten()
+            .collect::<Vec<_>>()
+            .join(" ");
+        println!(
+            "    {} {} {}",
+            "+".green(),
+            component_label(&c.path, c.reference.as_deref()).green(),
+            part.dimmed()
+        );
+    }
+    for c in &diff.removed_components {
+        println!(
+            "    {} {}",
+            "-".red(),
+            component_label(&c.path, c.reference.as_deref()).red()
+        );
+    }
+    for c in &diff.changed_components {
+        let renamed = c
+            .old_path
+            .as_ref()
+            .map(|old| format!(" (moved from {old})"))
+            .unwrap_or_default();
+        println!(
+            "    {} {}{}",
+            "~".yellow(),
+            component_label(&c.path, c.reference.as_deref()).yellow(),
+            renamed.dimmed()
+        );
+        for p in &c.properties {
+            println!(
+                "        {}: {} → {}",
+                p.name,
+                p.old.as_deref().unwrap_or("(none)"),
+                p.new.as_deref().unwrap_or("(none)")
+            );
+        }
+    }
+
+    let has_nets = !diff.added_nets.is_empty()
+        || !diff.removed_nets.is_empty()
+        || !diff.changed_nets.is_empty();
+    if has_nets {
+        println!("  {}", "Nets".bold());
+    }
+    for n in &diff.added_nets {
+        println!(
+            "    {} {} {}",
+            "+".green(),
+            n.name.green(),
+            n.ports.join(", ").dimmed()
+        );
+    }
+    for n in &diff.removed_nets {
+        println!("    {} {}", "-".red(), n.name.red());
+    }
+    for n in &diff.changed_nets {
+        let renamed = n
+            .old_name
+            .as_ref()
+            .map(|old| format!(" (was {old})"))
+            .unwrap_or_default();
+        println!(
+            "    {} {}{}",
+            "~".yellow(),
+            n.name.yellow(),
+            renamed.dimmed()
+        );
+        for port in &n.added_ports {
+            println!("        {} {}", "+".green(), port);
+        }
+        for port in &n.removed_ports {
+            println!("        {} {}", "-".red(), port);
+        }
+    }
+
+    // moved() directives that map several old paths onto one; only the first was compared
+    if !diff.moved_collisions.is_empty() {
+        println!("  {}", "moved() collisions".bold());
+    }
+    for c in &diff.moved_collisions {
+        println!(
+            "    {} {} {}",
+            "!".yellow(),
+            c.path.yellow(),
+            format!("(moved from {})", c.old_paths.join(", ")).dimmed()
+        );
+    }
+    println!();
+}
+
+/// `R5 (power.R5)`, or the path alone when the component has no reference designator
+fn component_label(path: &str, reference: Option<&str>) -> String {
+    match reference {
+        Some(reference) if reference != path => format!("{reference} ({path})"),
+        _ => path.to_string(),
+    }
+}
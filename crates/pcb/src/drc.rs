@@ -1,6 +1,7 @@
 use anyhow::{Context, Result};
 use pcb_layout::LayoutSyncDiagnostic;
 use pcb_ui::prelude::*;
+use pcb_zen::report::DiagnosticsCollector;
 use pcb_zen_core::diagnostics::{Diagnostic, DiagnosticsPass};
 use pcb_zen_core::lang::error::CategorizedDiagnostic;
 use pcb_zen_core::passes::{FilterHiddenPass, SortPass, SuppressPass};
@@ -9,13 +10,14 @@ use std::collections::HashMap;
 use std::path::Path;
 use std::sync::Arc;
 
-/// Run KiCad DRC checks and print results
+/// Run KiCad DRC checks and print results, recording the diagnostics in `collector` if given
 ///
 /// Returns (had_errors, warning_count)
 pub fn run_and_print_drc(
     kicad_pcb_path: &Path,
     suppress_kinds: &[String],
     sync_diagnostics: &[LayoutSyncDiagnostic],
+    collector: Option<&DiagnosticsCollector>,
 ) -> Result<(bool, usize)> {
     // Run DRC using the pcb-kicad crate
     let drc_report = pcb_kicad::run_drc(kicad_pcb_path).context("Failed to run KiCad DRC")?;
@@ -38,6 +40,9 @@ pub fn run_and_print_drc(
     ] {
         pass.apply(&mut diagnostics);
     }
+    if let Some(collector) = collector {
+        collector.pass(kicad_pcb_path).apply(&mut diagnostics);
+    }
 
     // Count errors and print diagnostics
     print_drc_diagnostics(&diagnostics)
@@ -0,0 +1,197 @@
+//! `pcb import kicad`: convert a KiCad project into Zener sources
+
+use anyhow::{bail, Context, Result};
+use clap::{Args, Subcommand};
+use colored::Colorize;
+use pcb_fmt::RuffFormatter;
+use pcb_kicad2zen::{compare_netlists, ImportedProject, Netlist, OutputMode};
+use pcb_sch::{AttributeValue, InstanceKind, InstanceRef, Schematic};
+use pcb_ui::prelude::*;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::build::create_diagnostics_passes;
+
+#[derive(Args)]
+pub struct ImportArgs {
+    #[command(subcommand)]
+    pub command: ImportCommands,
+}
+
+#[derive(Subcommand)]
+pub enum ImportCommands {
+    /// Convert a KiCad project (.kicad_sch hierarchy and .kicad_pcb) into .zen modules
+    Kicad(KicadArgs),
+}
+
+#[derive(Args)]
+pub struct KicadArgs {
+    /// KiCad project directory, or its .kicad_pro, .kicad_sch or .kicad_pcb file
+    #[arg(value_name = "PROJECT", value_hint = clap::ValueHint::AnyPath)]
+    pub project: PathBuf,
+
+    /// Use stdlib generics (Resistor, Capacitor, Inductor, Led) for plain chip parts
+    #[arg(long)]
+    pub idiomatic: bool,
+
+    /// Directory to write the Zener project to (defaults to <project name>-zen next to the project)
+    #[arg(short, long, value_hint = clap::ValueHint::DirPath)]
+    pub output: Option<PathBuf>,
+
+    /// Skip rebuilding the imported design and comparing its netlist with the board
+    #[arg(long)]
+    pub no_verify: bool,
+
+    /// Disable network access (offline mode) - only use vendored dependencies
+    #[arg(long = "offline")]
+    pub offline: bool,
+}
+
+pub fn execute(args: ImportArgs) -> Result<()> {
+    match args.command {
+        ImportCommands::Kicad(args) => execute_kicad(args),
+    }
+}
+
+fn execute_kicad(args: KicadArgs) -> Result<()> {
+    let mode = if args.idiomatic {
+        OutputMode::Idiomatic
+    } else {
+        OutputMode::Faithful
+    };
+    let imported = pcb_kicad2zen::import_project(&args.project, mode)
+        .with_context(|| format!("Failed to import {}", args.project.display()))?;
+    for warning in &imported.warnings {
+        eprintln!("{} {warning}", "Warning:".yellow());
+    }
+
+    let output = args.output.clone().unwrap_or_else(|| {
+        let parent = if args.project.is_dir() {
+            args.project.parent()
+        } else {
+            args.project.parent().and_then(Path::parent)
+        };
+        parent
+            .unwrap_or(Path::new("."))
+            .join(format!("{}-zen", imported.name))
+    });
+    if output.join(&imported.root).exists() {
+        bail!(
+            "{} already exists; pass another --output directory",
+            output.join(&imported.root).display()
+        );
+    }
+    write_files(&imported, &output)?;
+    println!(
+        "{} Imported {} into {}",
+        "✓".green(),
+        imported.name.bold(),
+        output.display()
+    );
+
+    if args.no_verify {
+        return Ok(());
+    }
+    verify(&imported, &output, args.offline)
+}
+
+fn write_files(imported: &ImportedProject, output: &Path) -> Result<()> {
+    let formatter = RuffFormatter::default();
+    for (path, content) in &imported.files {
+        let target = output.join(path);
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create {}", parent.display()))?;
+        }
+        let content = if path.extension().is_some_and(|e| e == "zen") {
+            formatter
+                .format_source(content)
+                .with_context(|| format!("Failed to format {}", path.display()))?
+        } else {
+            content.clone()
+        };
+        fs::write(&target, content)
+            .with_context(|| format!("Failed to write {}", target.display()))?;
+    }
+    Ok(())
+}
+
+/// Rebuild the imported design and compare its connectivity with the original board
+fn verify(imported: &ImportedProject, output: &Path, offline: bool) -> Result<()> {
+    let root = output.join(&imported.root);
+    let (_workspace_info, resolution_result) =
+        crate::resolve::resolve_v2_if_needed(Some(output), offline, false)?;
+
+    let file_name = imported.root.display().to_string();
+    let spinner = Spinner::builder(format!("{file_name}: Building")).start();
+    let eval_result = pcb_zen::eval(
+        &root,
+        pcb_zen::EvalConfig::with_resolution(resolution_result, offline),
+    );
+    let eval_output = match eval_result.output_result() {
+        Ok(eval_output) => eval_output,
+        Err(mut diagnostics) => {
+            spinner.error(format!("{file_name}: Build failed"));
+            diagnostics.apply_passes(&create_diagnostics_passes(&[], &[], &[]));
+            bail!("The imported design does not build");
+        }
+    };
+    let schematic = eval_output
+        .to_schematic()
+        .context("Failed to convert to schematic")?;
+    spinner.finish();
+
+    let differences = compare_netlists(&imported.netlist, &built_netlist(&schematic));
+    if differences.is_empty() {
+        println!(
+            "{} Netlist matches the board ({} components)",
+            "✓".green(),
+            imported.netlist.components.len()
+        );
+        return Ok(());
+    }
+    for difference in &differences {
+        eprintln!("  {} {difference}", "✗".red());
+    }
+    bail!(
+        "The imported design differs from the board in {} place(s)",
+        differences.len()
+    );
+}
+
+/// Pad connectivity of a built design, by component path
+fn built_netlist(schematic: &Schematic) -> Netlist {
+    let port_nets: HashMap<&InstanceRef, &str> = schematic
+        .nets
+        .values()
+        .flat_map(|net| net.ports.iter().map(|port| (port, net.name.as_str())))
+        .collect();
+
+    let mut netlist = Netlist::default();
+    for (reference, instance) in &schematic.instances {
+        if instance.kind != InstanceKind::Component {
+            continue;
+        }
+        let pads = netlist
+            .components
+            .entry(reference.instance_path.join("."))
+            .or_default();
+        for port_ref in instance.children.values() {
+            let Some(AttributeValue::Array(numbers)) = schematic
+                .instances
+                .get(port_ref)
+                .and_then(|port| port.attributes.get("pads"))
+            else {
+                continue;
+            };
+            let net = port_nets.get(port_ref).map(|net| net.to_string());
+            for number in numbers {
+                if let AttributeValue::String(number) = number {
+                    pads.insert(number.clone(), net.clone());
+                }
+            }
+        }
+    }
+    netlist
+}
@@ -1,13 +1,15 @@
 use anyhow::{Context, Result};
 use clap::Args;
 use inquire::Select;
-use pcb_layout::{process_layout, LayoutError};
+use pcb_layout::{process_layout, LayoutError, LayoutReuse};
 use pcb_ui::prelude::*;
+use std::io::Write;
 use std::path::PathBuf;
 
 use crate::build::{build, create_diagnostics_passes};
 use crate::drc;
 use crate::file_walker;
+use crate::report::{self, ReportFormat};
 
 #[derive(Args, Debug, Default, Clone)]
 #[command(about = "Generate PCB layout files from .zen files")]
@@ -60,6 +62,19 @@ pub struct LayoutArgs {
     /// add dependencies or if the lockfile would be modified. Recommended for CI.
     #[arg(long)]
     pub locked: bool,
+
+    /// Print the build and DRC diagnostics as a SARIF or JUnit XML report on stdout
+    #[arg(long = "format", value_enum, value_name = "FORMAT")]
+    pub format: Option<ReportFormat>,
+
+    /// Copy the placement, tracks, vias and zones of this module instance (e.g. `Phases.A`)
+    /// to the other instances of its module
+    #[arg(long, value_name = "INSTANCE", conflicts_with_all = ["check", "temp"])]
+    pub propagate: Option<String>,
+
+    /// With --propagate, also write the instance's layout back to its module's layout_path
+    #[arg(long, requires = "propagate")]
+    pub save_module_layout: bool,
 }
 
 pub fn execute(mut args: LayoutArgs) -> Result<()> {
@@ -84,14 +99,24 @@ pub fn execute(mut args: LayoutArgs) -> Result<()> {
     let mut has_errors = false;
     let mut has_warnings = false;
     let mut generated_layouts = Vec::new();
+    let collector = args
+        .format
+        .map(|_| pcb_zen::report::DiagnosticsCollector::new());
+    let mut status = report::status_output(args.format);
+    let reuse = args.propagate.as_ref().map(|instance| LayoutReuse {
+        instance: instance.clone(),
+        save_module_layout: args.save_module_layout,
+    });
 
     // Process each .zen file
     for zen_path in zen_paths {
         let file_name = zen_path.file_name().unwrap().to_string_lossy().to_string();
+        let mut passes = create_diagnostics_passes(&args.suppress, &[], &[]);
+        report::collect(&mut passes, collector.as_ref(), &zen_path);
         let Some(schematic) = build(
             &zen_path,
             args.offline,
-            create_diagnostics_passes(&args.suppress, &[]),
+            passes,
             false, // don't deny warnings for layout command
             &mut has_errors,
             &mut has_warnings,
@@ -115,36 +140,40 @@ pub fn execute(mut args: LayoutArgs) -> Result<()> {
             args.sync_board_config,
             args.temp,
             args.check, // dry_run
+            reuse.as_ref(),
         )
-        .map(|r| (r.pcb_file, r.sync_diagnostics));
+        .map(|r| (r.pcb_file, r.sync_diagnostics, r.propagated_to));
 
-        let (pcb_file, sync_diagnostics) = match result {
+        let (pcb_file, sync_diagnostics, propagated_to) = match result {
             Ok(r) => r,
             Err(LayoutError::NoLayoutPath) => {
                 spinner.finish();
-                println!(
+                writeln!(
+                    status,
                     "{} {} (no layout)",
                     pcb_ui::icons::warning(),
                     file_name.with_style(Style::Yellow).bold(),
-                );
+                )?;
                 continue;
             }
             Err(LayoutError::NoLayoutFile(_)) => {
                 spinner.finish();
-                println!(
+                writeln!(
+                    status,
                     "{} {} (no layout file)",
                     pcb_ui::icons::warning(),
                     file_name.with_style(Style::Yellow).bold(),
-                );
+                )?;
                 continue;
             }
             Err(e) => {
                 spinner.finish();
-                println!(
+                writeln!(
+                    status,
                     "{} {}: {e:#}",
                     pcb_ui::icons::error(),
                     file_name.with_style(Style::Red).bold()
-                );
+                )?;
                 has_errors = true;
                 continue;
             }
@@ -155,18 +184,42 @@ pub fn execute(mut args: LayoutArgs) -> Result<()> {
             .parent()
             .and_then(|parent| pcb_file.strip_prefix(parent).ok())
             .unwrap_or(&pcb_file);
-        println!(
+        writeln!(
+            status,
             "{} {} ({})",
             pcb_ui::icons::success(),
             file_name.clone().with_style(Style::Green).bold(),
             relative_path.display()
-        );
+        )?;
+        if let Some(reuse) = &reuse {
+            if !propagated_to.is_empty() {
+                writeln!(
+                    status,
+                    "  Propagated the layout of {} to {}",
+                    reuse.instance.as_str().bold(),
+                    propagated_to.join(", ")
+                )?;
+            }
+            if reuse.save_module_layout {
+                writeln!(
+                    status,
+                    "  Saved the layout of {} to its module layout",
+                    reuse.instance.as_str().bold()
+                )?;
+            }
+        }
 
         // Run DRC checks in --check mode
         if args.check {
             let drc_spinner = Spinner::builder(format!("{file_name}: Running DRC checks")).start();
-            let result = drc_spinner
-                .suspend(|| drc::run_and_print_drc(&pcb_file, &args.suppress, &sync_diagnostics));
+            let result = drc_spinner.suspend(|| {
+                drc::run_and_print_drc(
+                    &pcb_file,
+                    &args.suppress,
+                    &sync_diagnostics,
+                    collector.as_ref(),
+                )
+            });
 
             match result {
                 Ok((had_errors, _warnings)) => {
@@ -190,12 +243,16 @@ pub fn execute(mut args: LayoutArgs) -> Result<()> {
         generated_layouts.push((zen_path.clone(), pcb_file));
     }
 
+    if let (Some(format), Some(collector)) = (args.format, &collector) {
+        report::print_report(format, collector, &workspace_info.root)?;
+    }
+
     if has_errors {
         std::process::exit(1);
     }
 
     if generated_layouts.is_empty() {
-        println!("\nNo layouts found.");
+        writeln!(status, "\nNo layouts found.")?;
         return Ok(());
     }
 
@@ -1,9 +1,27 @@
+use std::path::Path;
+
 use clap::Args;
+use pcb_zen::lsp::FileCodemod;
+
+use crate::migrate;
 
 #[derive(Args)]
 pub struct LspArgs {}
 
+/// `pcb migrate` as a source action on the open file
+struct MigrateCodemod;
+
+impl FileCodemod for MigrateCodemod {
+    fn title(&self) -> String {
+        "Run `pcb migrate` codemods".to_string()
+    }
+
+    fn apply(&self, path: &Path, content: &str) -> anyhow::Result<Option<String>> {
+        migrate::migrate_source(path, content)
+    }
+}
+
 pub fn execute(_args: LspArgs) -> anyhow::Result<()> {
-    pcb_zen::lsp_with_eager(false)?;
+    pcb_zen::lsp_with_codemods(false, vec![Box::new(MigrateCodemod)])?;
     Ok(())
 }
@@ -8,10 +8,13 @@ use std::process::Command;
 mod api;
 mod bom;
 mod build;
+mod deps;
+mod diff;
 mod drc;
 mod file_walker;
 mod fmt;
 mod fork;
+mod import;
 mod info;
 mod ipc2581;
 mod layout;
@@ -20,11 +23,15 @@ mod mcp;
 mod migrate;
 mod open;
 mod package;
+mod power;
 mod publish;
 mod release;
+mod report;
 #[cfg(feature = "api")]
 mod route;
+mod sbom;
 mod self_update;
+mod semver_check;
 mod sim;
 mod tag;
 mod test;
@@ -77,6 +84,9 @@ enum Commands {
     /// Generate Bill of Materials (BOM)
     Bom(bom::BomArgs),
 
+    /// Compare a design between two git revisions
+    Diff(diff::DiffArgs),
+
     /// Display workspace and board information
     Info(info::InfoArgs),
 
@@ -108,6 +118,18 @@ enum Commands {
     /// Vendor external dependencies
     Vendor(vendor::VendorArgs),
 
+    /// Inspect the resolved dependency graph
+    Deps(deps::DepsArgs),
+
+    /// Generate a software bill of materials
+    Sbom(sbom::SbomArgs),
+
+    /// Show the power tree and rail current budget
+    Power(power::PowerArgs),
+
+    /// Import designs from other EDA tools
+    Import(import::ImportArgs),
+
     /// Manage forked dependencies for local development
     Fork(fork::ForkArgs),
 
@@ -178,6 +200,7 @@ fn run() -> anyhow::Result<()> {
         Commands::Update(args) => update::execute(args),
         Commands::SelfUpdate(args) => self_update::execute(args),
         Commands::Bom(args) => bom::execute(args),
+        Commands::Diff(args) => diff::execute(args),
         Commands::Info(args) => info::execute(args),
         Commands::Layout(args) => layout::execute(args),
         Commands::Fmt(args) => fmt::execute(args),
@@ -187,6 +210,10 @@ fn run() -> anyhow::Result<()> {
         Commands::Release(args) => release::execute(args),
         Commands::Tag(args) => tag::execute(args),
         Commands::Vendor(args) => vendor::execute(args),
+        Commands::Deps(args) => deps::execute(args),
+        Commands::Sbom(args) => sbom::execute(args),
+        Commands::Power(args) => power::execute(args),
+        Commands::Import(args) => import::execute(args),
         Commands::Fork(args) => fork::execute(args),
         #[cfg(feature = "api")]
         Commands::Scan(args) => api::execute_scan(args),
@@ -158,7 +158,7 @@ fn run_layout(args: Option<Value>, ctx: &McpContext) -> Result<CallToolResult> {
         let schematic = match build(
             zen_path,
             false, // offline
-            create_diagnostics_passes(&[], &[]),
+            create_diagnostics_passes(&[], &[], &[]),
             false, // deny_warnings
             &mut has_errors,
             &mut has_warnings,
@@ -171,7 +171,7 @@ fn run_layout(args: Option<Value>, ctx: &McpContext) -> Result<CallToolResult> {
             }
         };
 
-        match process_layout(&schematic, zen_path, sync_board_config, false, false) {
+        match process_layout(&schematic, zen_path, sync_board_config, false, false, None) {
             Ok(layout_result) => {
                 ctx.log(
                     "info",
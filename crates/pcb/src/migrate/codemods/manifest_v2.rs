@@ -220,6 +220,7 @@ fn convert_pcb_toml_to_v2(
             default_board,
             vendor: vec!["github.com/diodeinc/registry/**".to_string()],
             exclude: Vec::new(),
+            index: None,
         });
     } else if let Some(mut ws) = config.workspace.take() {
         // Member package - set pcb-version
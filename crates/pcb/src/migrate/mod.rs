@@ -4,7 +4,7 @@ use pcb_ui::prelude::*;
 use pcb_zen::git;
 use pcb_zen_core::{config::find_workspace_root, DefaultFileProvider};
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
 pub mod codemods;
@@ -68,14 +68,7 @@ pub fn execute(args: MigrateArgs) -> Result<()> {
 
         // Step 5: Run all codemods on .zen files
         eprintln!("\nStep 5: Running codemods on .zen files");
-        let codemods: Vec<Box<dyn Codemod>> = vec![
-            Box::new(RemoveDirectoryLoads),
-            Box::new(WorkspacePaths),
-            Box::new(EscapePaths),
-            Box::new(AliasExpansion),
-            Box::new(PathCorrection),
-        ];
-        run_codemods(&ctx, &zen_files, &codemods)?;
+        run_codemods(&ctx, &zen_files, &zen_codemods())?;
     }
 
     eprintln!("\n✓ Migration complete");
@@ -85,6 +78,38 @@ pub fn execute(args: MigrateArgs) -> Result<()> {
     Ok(())
 }
 
+/// The codemods run on every .zen file, in order
+fn zen_codemods() -> Vec<Box<dyn Codemod>> {
+    vec![
+        Box::new(RemoveDirectoryLoads),
+        Box::new(WorkspacePaths),
+        Box::new(EscapePaths),
+        Box::new(AliasExpansion),
+        Box::new(PathCorrection),
+    ]
+}
+
+/// Run the .zen codemods on `content` of the file at `path`, returning the migrated
+/// source if any codemod changed it. Used by the language server, which works on
+/// unsaved contents and leaves `pcb.toml` files alone.
+pub fn migrate_source(path: &Path, content: &str) -> Result<Option<String>> {
+    let file_provider = DefaultFileProvider::new();
+    let workspace_root = find_workspace_root(&file_provider, path)?;
+    let ctx = MigrateContext {
+        repository: git::detect_repository_url(&workspace_root)?,
+        repo_subpath: git::get_repo_subpath(&workspace_root)?,
+        workspace_root,
+    };
+
+    let mut migrated = content.to_string();
+    for codemod in zen_codemods() {
+        if let Some(updated) = codemod.apply(&ctx, path, &migrated)? {
+            migrated = updated;
+        }
+    }
+    Ok((migrated != content).then_some(migrated))
+}
+
 /// Run codemods on a list of .zen files
 fn run_codemods(
     ctx: &MigrateContext,
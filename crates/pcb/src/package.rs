@@ -1,6 +1,7 @@
-use anyhow::Result;
+use anyhow::{Context, Result};
 use clap::Args;
-use std::path::PathBuf;
+use pcb_zen::package_index::IndexFile;
+use std::path::{Path, PathBuf};
 
 #[derive(Args)]
 pub struct PackageArgs {
@@ -14,6 +15,18 @@ pub struct PackageArgs {
     /// Enable verbose output (shows file list and individual hashes)
     #[arg(short = 'v', long = "verbose")]
     verbose: bool,
+
+    /// Add the package to this package index (JSON), with its tarball stored next to it
+    #[arg(long, requires_all = ["module", "version"])]
+    index: Option<PathBuf>,
+
+    /// Package URL to list the package under in the index
+    #[arg(long)]
+    module: Option<String>,
+
+    /// Version to list the package under in the index
+    #[arg(long)]
+    version: Option<String>,
 }
 
 pub fn execute(args: PackageArgs) -> Result<()> {
@@ -49,6 +62,12 @@ pub fn execute(args: PackageArgs) -> Result<()> {
         println!("Tar size: {} bytes", tar_data.len());
     }
 
+    if let (Some(index_path), Some(module), Some(version)) =
+        (&args.index, &args.module, &args.version)
+    {
+        add_to_index(index_path, &path, module, version)?;
+    }
+
     // Compute and print content hash
     let content_hash = pcb_zen::canonical::compute_content_hash_from_dir(&path)?;
     println!("Content hash: {}", content_hash);
@@ -65,3 +84,23 @@ pub fn execute(args: PackageArgs) -> Result<()> {
 
     Ok(())
 }
+
+/// Store the canonical tarball of `package_dir` next to the index at `index_path`, and list it
+/// there as `module`@`version`
+fn add_to_index(index_path: &Path, package_dir: &Path, module: &str, version: &str) -> Result<()> {
+    let version = semver::Version::parse(version.trim_start_matches('v'))
+        .with_context(|| format!("Invalid version: {version}"))?;
+    let mut index: IndexFile = if index_path.exists() {
+        serde_json::from_str(&std::fs::read_to_string(index_path)?)
+            .with_context(|| format!("Invalid package index {}", index_path.display()))?
+    } else {
+        IndexFile::default()
+    };
+
+    let url = format!("{module}/{version}.tar");
+    let index_dir = index_path.parent().unwrap_or(Path::new(""));
+    index.add_package(module, &version, package_dir, &index_dir.join(&url), &url)?;
+    std::fs::write(index_path, serde_json::to_string_pretty(&index)? + "\n")?;
+    println!("Added {}@{} to {}", module, version, index_path.display());
+    Ok(())
+}
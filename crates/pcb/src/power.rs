@@ -0,0 +1,126 @@
+//! `pcb power`: power tree of a design and the current budget of each rail
+
+use anyhow::{bail, Context, Result};
+use clap::Args;
+use colored::Colorize;
+use pcb_ui::prelude::*;
+use pcb_zen_core::power::{self, format_current, PowerTree, Rail};
+use std::path::PathBuf;
+
+use crate::build::create_diagnostics_passes;
+use crate::info::OutputFormat;
+
+#[derive(Args, Debug)]
+#[command(about = "Show the power tree of a design and check the current budget of each rail")]
+pub struct PowerArgs {
+    /// .zen file to analyze
+    #[arg(value_name = "FILE", value_hint = clap::ValueHint::FilePath)]
+    pub file: PathBuf,
+
+    /// Output format
+    #[arg(short = 'f', long, value_enum, default_value = "human")]
+    pub format: OutputFormat,
+
+    /// Disable network access (offline mode) - only use vendored dependencies
+    #[arg(long = "offline")]
+    pub offline: bool,
+
+    /// Require that pcb.toml and pcb.sum are up-to-date. Fails if auto-deps would
+    /// add dependencies or if the lockfile would be modified. Recommended for CI.
+    #[arg(long)]
+    pub locked: bool,
+}
+
+pub fn execute(args: PowerArgs) -> Result<()> {
+    let (_workspace_info, resolution_result) =
+        crate::resolve::resolve_v2_if_needed(args.file.parent(), args.offline, args.locked)?;
+
+    let file_name = args.file.file_name().unwrap().to_string_lossy();
+    let spinner = Spinner::builder(format!("{file_name}: Building")).start();
+
+    let eval_result = pcb_zen::eval(
+        &args.file,
+        pcb_zen::EvalConfig::with_resolution(resolution_result, args.offline),
+    );
+    let eval_output = eval_result.output_result().map_err(|mut diagnostics| {
+        diagnostics.apply_passes(&create_diagnostics_passes(&[], &[], &[]));
+        anyhow::anyhow!("Failed to build {} - cannot analyze power", file_name)
+    })?;
+    let schematic = eval_output
+        .to_schematic()
+        .context("Failed to convert to schematic")?;
+
+    spinner.set_message(format!("{file_name}: Analyzing power tree"));
+    let tree = power::analyze(&schematic).context("Failed to build the circuit graph")?;
+    spinner.finish();
+
+    match args.format {
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&tree)?),
+        OutputFormat::Human => print_tree(&tree),
+    }
+
+    let mut diagnostics = tree.diagnostics(&args.file);
+    let has_errors = diagnostics.has_errors();
+    diagnostics.apply_passes(&create_diagnostics_passes(&[], &[], &[]));
+    if has_errors {
+        bail!("{file_name}: power budget exceeded");
+    }
+    Ok(())
+}
+
+fn print_tree(tree: &PowerTree) {
+    if tree.rails.is_empty() && tree.orphans.is_empty() {
+        println!("No regulators or power nets found");
+        return;
+    }
+    for rail in &tree.rails {
+        print_rail(rail, 0);
+    }
+    if !tree.orphans.is_empty() {
+        println!();
+        println!("{}", "Unregulated power nets".bold());
+        for rail in &tree.orphans {
+            print_rail(rail, 1);
+        }
+    }
+}
+
+fn print_rail(rail: &Rail, depth: usize) {
+    let indent = "  ".repeat(depth);
+    let mut line = format!("{indent}{}", rail.net.bold());
+    if let Some(voltage) = &rail.voltage {
+        line.push_str(&format!(" {voltage}"));
+    }
+    let budget = match rail.capacity {
+        Some(capacity) => {
+            let budget = format!(
+                "{} / {}",
+                format_current(rail.current),
+                format_current(capacity)
+            );
+            if rail.current > capacity {
+                budget.red().to_string()
+            } else {
+                budget.green().to_string()
+            }
+        }
+        None => format_current(rail.current),
+    };
+    line.push_str(&format!("  {budget}"));
+    if !rail.regulators.is_empty() {
+        let via = format!("  via {}", rail.regulators.join(", "));
+        line.push_str(&via.dimmed().to_string());
+    }
+    println!("{line}");
+
+    for load in &rail.loads {
+        println!(
+            "{indent}  {} {}",
+            load.component,
+            format_current(load.current).dimmed()
+        );
+    }
+    for child in &rail.children {
+        print_rail(child, depth + 1);
+    }
+}
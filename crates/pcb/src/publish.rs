@@ -8,6 +8,7 @@ use anyhow::{bail, Result};
 use clap::{Args, ValueEnum};
 use colored::Colorize;
 use inquire::{Confirm, Select};
+use pcb_zen::public_api::{self, ChangeLevel};
 use pcb_zen::workspace::{get_workspace_info, MemberPackage, WorkspaceInfo, WorkspaceInfoExt};
 use pcb_zen::{canonical, git, tags};
 use pcb_zen_core::config::{DependencySpec, PcbToml};
@@ -21,9 +22,10 @@ use std::env;
 use std::path::Path;
 
 use crate::file_walker::collect_zen_files;
+use crate::semver_check;
 
 /// Version bump type for publishing
-#[derive(Clone, Copy, Debug, ValueEnum, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, ValueEnum, PartialEq, Eq, PartialOrd, Ord)]
 pub enum BumpType {
     /// Bug fixes only (x.y.Z)
     Patch,
@@ -56,6 +58,11 @@ pub struct PublishArgs {
     #[arg(long, value_enum)]
     pub bump: Option<BumpType>,
 
+    /// Diff each package's public API against its latest published version and require
+    /// a bump that covers the changes
+    #[arg(long)]
+    pub check_semver: bool,
+
     /// Optional path to start discovery from (defaults to current directory)
     pub path: Option<String>,
 }
@@ -257,8 +264,15 @@ pub fn execute(args: PublishArgs) -> Result<()> {
         return Ok(());
     }
 
+    let required_bumps = if args.check_semver {
+        check_semver(&workspace, &waves)?
+    } else {
+        BTreeMap::new()
+    };
+
     // Collect all bump info and show summary upfront
-    let bump_map = collect_all_bumps(&workspace, &waves, &dirty_urls, args.bump)?;
+    let bump_map = collect_all_bumps(&workspace, &waves, &dirty_urls, args.bump, &required_bumps)?;
+    enforce_required_bumps(&workspace, &bump_map, &required_bumps)?;
 
     // Show summary and confirm
     let all_tags_list = git::list_all_tags_vec(&workspace.root);
@@ -502,7 +516,7 @@ fn build_workspace(workspace: &WorkspaceInfo, suppress: &[String]) -> Result<()>
         if let Some(schematic) = crate::build::build(
             zen_path,
             false,
-            crate::build::create_diagnostics_passes(suppress, &[]),
+            crate::build::create_diagnostics_passes(suppress, &[], &[]),
             false,
             &mut has_errors,
             &mut has_warnings,
@@ -765,12 +779,98 @@ fn print_dependency_tree(
     }
 }
 
+/// Bump each published package needs for the changes to its public API since its
+/// latest tag. Unpublished packages are left out.
+fn check_semver(
+    workspace: &WorkspaceInfo,
+    waves: &[Vec<String>],
+) -> Result<BTreeMap<String, BumpType>> {
+    println!("{}", "Checking API compatibility...".cyan().bold());
+    let all_tags = git::list_all_tags_vec(&workspace.root);
+    let ws_path = workspace.path();
+
+    let mut required = BTreeMap::new();
+    for url in waves.iter().flatten() {
+        let pkg = &workspace.packages[url];
+        let tag_prefix = tags::compute_tag_prefix(Some(&pkg.rel_path), ws_path);
+        let Some(tag) = tags::find_latest_tag(&all_tags, &tag_prefix) else {
+            continue;
+        };
+
+        let changes = semver_check::check_package(workspace, url, &tag)?;
+        let level = public_api::required_level(&changes);
+        let bump = match level {
+            ChangeLevel::Patch => BumpType::Patch,
+            ChangeLevel::Additive => BumpType::Minor,
+            ChangeLevel::Breaking => BumpType::Major,
+        };
+
+        println!(
+            "  {} {}",
+            pkg.rel_path.display(),
+            format!("({level} since {tag})").dimmed()
+        );
+        for change in &changes {
+            let marker = match change.level {
+                ChangeLevel::Breaking => "!".red(),
+                ChangeLevel::Additive => "+".green(),
+                ChangeLevel::Patch => "~".normal(),
+            };
+            println!("    {} {}: {}", marker, change.module, change.message);
+        }
+        required.insert(url.clone(), bump);
+    }
+    println!();
+    Ok(required)
+}
+
+/// Fail if a chosen bump doesn't produce a version at least as high as the required one.
+fn enforce_required_bumps(
+    workspace: &WorkspaceInfo,
+    bump_map: &BTreeMap<String, BumpType>,
+    required: &BTreeMap<String, BumpType>,
+) -> Result<()> {
+    let all_tags = git::list_all_tags_vec(&workspace.root);
+    let ws_path = workspace.path();
+
+    let mut insufficient = 0;
+    for (url, &needed) in required {
+        let (Some(pkg), Some(&chosen)) = (workspace.packages.get(url), bump_map.get(url)) else {
+            continue;
+        };
+        let tag_prefix = tags::compute_tag_prefix(Some(&pkg.rel_path), ws_path);
+        let current = tags::find_latest_version(&all_tags, &tag_prefix);
+        let next = compute_next_version(current.as_ref(), chosen);
+        let minimum = compute_next_version(current.as_ref(), needed);
+        if next < minimum {
+            eprintln!(
+                "{} {}: API changes need at least {}, got {}",
+                "✗".red(),
+                pkg.rel_path.display(),
+                minimum,
+                next
+            );
+            insufficient += 1;
+        }
+    }
+
+    if insufficient > 0 {
+        bail!(
+            "{} package(s) need a larger version bump for their API changes",
+            insufficient
+        );
+    }
+    Ok(())
+}
+
 /// Collect all bump types upfront, displaying packages and prompting for choices.
+/// Prompts start at the bump `suggested` for each package, if any.
 fn collect_all_bumps(
     workspace: &WorkspaceInfo,
     waves: &[Vec<String>],
     dirty_urls: &HashSet<String>,
     cli_bump: Option<BumpType>,
+    suggested: &BTreeMap<String, BumpType>,
 ) -> Result<BTreeMap<String, BumpType>> {
     let all_tags = git::list_all_tags_vec(&workspace.root);
     let ws_path = workspace.path();
@@ -836,7 +936,11 @@ fn collect_all_bumps(
                         BumpType::Minor
                     } else {
                         let display_name = pkg.rel_path.display().to_string();
-                        prompt_single_bump(&display_name, current.as_ref())?
+                        prompt_single_bump(
+                            &display_name,
+                            current.as_ref(),
+                            suggested.get(url).copied(),
+                        )?
                     };
 
                     map.insert(url.clone(), bump);
@@ -845,7 +949,7 @@ fn collect_all_bumps(
         }
         Ok(map)
     } else {
-        let bump = prompt_bump_type()?;
+        let bump = prompt_bump_type(suggested.values().max().copied())?;
         Ok(waves
             .iter()
             .flat_map(|w| w.iter())
@@ -884,7 +988,7 @@ fn print_publish_summary(
     }
 }
 
-fn prompt_bump_type() -> Result<BumpType> {
+fn prompt_bump_type(suggested: Option<BumpType>) -> Result<BumpType> {
     let options = [
         ("Patch (x.y.Z) - bug fixes", BumpType::Patch),
         ("Minor (x.Y.0) - new features", BumpType::Minor),
@@ -892,7 +996,9 @@ fn prompt_bump_type() -> Result<BumpType> {
     ];
 
     let labels: Vec<_> = options.iter().map(|(l, _)| *l).collect();
+    let cursor = suggested.map_or(0, |s| s as usize);
     let selected = Select::new("Select version bump:", labels)
+        .with_starting_cursor(cursor)
         .prompt()
         .map_err(|e| anyhow::anyhow!("Prompt cancelled: {}", e))?;
 
@@ -903,7 +1009,11 @@ fn prompt_bump_type() -> Result<BumpType> {
         .unwrap_or(BumpType::Minor))
 }
 
-fn prompt_single_bump(name: &str, current: Option<&Version>) -> Result<BumpType> {
+fn prompt_single_bump(
+    name: &str,
+    current: Option<&Version>,
+    suggested: Option<BumpType>,
+) -> Result<BumpType> {
     let ver = current
         .map(|v| v.to_string())
         .unwrap_or_else(|| "unpublished".to_string());
@@ -941,8 +1051,19 @@ fn prompt_single_bump(name: &str, current: Option<&Version>) -> Result<BumpType>
             .collect()
     };
 
+    // Start at the first option that covers the suggested bump
+    let cursor = suggested
+        .and_then(|s| {
+            let minimum = compute_next_version(current, s);
+            options
+                .iter()
+                .position(|(_, b)| compute_next_version(current, *b) >= minimum)
+        })
+        .unwrap_or(0);
+
     let labels: Vec<_> = options.iter().map(|(l, _)| l.as_str()).collect();
     let selected = Select::new(&format!("{} ({})", name, ver), labels)
+        .with_starting_cursor(cursor)
         .prompt()
         .map_err(|e| anyhow::anyhow!("Prompt cancelled: {}", e))?;
 
@@ -13,6 +13,7 @@ use pcb_zen_core::{EvalOutput, WithDiagnostics};
 
 use pcb_zen::WorkspaceInfo;
 
+use std::collections::HashMap;
 use std::fs;
 use std::io::{BufWriter, IsTerminal, Write};
 use std::time::Instant;
@@ -57,6 +58,7 @@ impl std::fmt::Display for ReleaseOutputFormat {
 pub enum ArtifactType {
     Drc,
     Bom,
+    Schematic,
     Gerbers,
     Cpl,
     Assembly,
@@ -74,6 +76,7 @@ impl ArtifactType {
         match self {
             ArtifactType::Drc => "Running KiCad DRC checks",
             ArtifactType::Bom => "Generating design BOM",
+            ArtifactType::Schematic => "Generating KiCad schematic",
             ArtifactType::Gerbers => "Generating gerber files",
             ArtifactType::Cpl => "Generating pick-and-place file",
             ArtifactType::Assembly => "Generating assembly drawings",
@@ -91,6 +94,7 @@ impl ArtifactType {
         match self {
             ArtifactType::Drc => run_kicad_drc,
             ArtifactType::Bom => generate_design_bom,
+            ArtifactType::Schematic => generate_kicad_schematic,
             ArtifactType::Gerbers => generate_gerbers,
             ArtifactType::Cpl => generate_cpl,
             ArtifactType::Assembly => generate_assembly_drawings,
@@ -151,6 +155,10 @@ pub struct ReleaseArgs {
     /// Supports hierarchical matching (e.g., 'layout.drc' matches 'layout.drc.clearance')
     #[arg(short = 'S', long = "suppress", value_name = "KIND")]
     pub suppress: Vec<String>,
+
+    /// Release the named assembly variant declared with variant()
+    #[arg(long, value_name = "NAME")]
+    pub variant: Option<String>,
 }
 
 /// All information gathered during the release preparation phase
@@ -171,8 +179,10 @@ pub struct ReleaseInfo {
     pub staging_dir: PathBuf,
     /// Path to the layout directory containing KiCad files
     pub layout_path: PathBuf,
-    /// Evaluated schematic from the zen file
+    /// Evaluated schematic from the zen file, with the variant applied
     pub schematic: pcb_sch::Schematic,
+    /// Assembly variant being released
+    pub variant: Option<String>,
     /// Type of release being created
     pub kind: ReleaseKind,
     /// Directory where the final .zip file will be placed
@@ -223,6 +233,7 @@ type TaskFn = fn(&ReleaseInfo, &Spinner) -> Result<()>;
 
 const BASE_TASKS: &[(&str, TaskFn)] = &[
     ("Copying source files and dependencies", copy_sources),
+    ("Generating SBOM", write_sbom),
     ("Validating build from staged sources", validate_build),
     ("Generating board config", generate_board_config),
     ("Copying documentation", copy_docs),
@@ -233,6 +244,7 @@ const BASE_TASKS: &[(&str, TaskFn)] = &[
 const MANUFACTURING_ARTIFACTS: &[ArtifactType] = &[
     ArtifactType::Drc, // Run DRC checks first, before generating any manufacturing files
     ArtifactType::Bom,
+    ArtifactType::Schematic,
     ArtifactType::Gerbers,
     ArtifactType::Cpl,
     ArtifactType::Assembly,
@@ -375,7 +387,7 @@ pub fn execute(args: ReleaseArgs) -> Result<()> {
         // Render diagnostics and fail early if there are errors
         if eval_result.diagnostics.has_errors() || eval_result.output.is_none() {
             let mut diagnostics = eval_result.diagnostics.clone();
-            let passes = crate::build::create_diagnostics_passes(&[], &[]);
+            let passes = crate::build::create_diagnostics_passes(&[], &[], &[]);
             diagnostics.apply_passes(&passes);
             anyhow::bail!("Evaluation failed");
         }
@@ -455,22 +467,29 @@ fn build_release_info(
     // Get version and git hash from git
     let (version, git_hash) = git_version_and_hash(&config.root, &tag_prefix)?;
 
+    // Variant releases share the version but get their own staging directory and archive
+    let variant_suffix = args
+        .variant
+        .as_ref()
+        .map(|variant| format!("-{variant}"))
+        .unwrap_or_default();
+
     // Create release staging directory in workspace root with flat structure:
-    // Structure: {workspace_root}/.pcb/releases/{board_name}-{version}
+    // Structure: {workspace_root}/.pcb/releases/{board_name}-{version}[-{variant}]
     // Example: /workspace/.pcb/releases/test_board-f20ac95-dirty
     let staging_dir = config
         .root
         .join(".pcb/releases")
-        .join(format!("{}-{}", board_name, version));
+        .join(format!("{}-{}{}", board_name, version, variant_suffix));
 
     // Determine output directory and name
     let default_output_dir = config.root.join(".pcb/releases");
     let output_dir = args.output_dir.clone().unwrap_or(default_output_dir);
     let output_name = args.output_name.clone().unwrap_or_else(|| {
         if args.source_only {
-            format!("{}-{}.source.zip", board_name, version)
+            format!("{}-{}{}.source.zip", board_name, version, variant_suffix)
         } else {
-            format!("{}-{}.zip", board_name, version)
+            format!("{}-{}{}.zip", board_name, version, variant_suffix)
         }
     });
 
@@ -491,7 +510,10 @@ fn build_release_info(
         .context("Layout path must be within workspace root")?
         .to_path_buf();
 
-    let schematic = eval_output.to_schematic()?;
+    let mut schematic = eval_output.to_schematic()?;
+    if let Some(variant) = &args.variant {
+        schematic.apply_variant(variant)?;
+    }
     let kind = if args.source_only {
         ReleaseKind::SourceOnly
     } else {
@@ -508,6 +530,7 @@ fn build_release_info(
         staging_dir,
         layout_path,
         schematic,
+        variant: args.variant.clone(),
         kind,
         output_dir,
         output_name,
@@ -538,6 +561,9 @@ fn display_release_info(info: &ReleaseInfo, format: ReleaseOutputFormat) {
             // Add release information
             table.add_row(vec!["Release Type", release_type]);
             table.add_row(vec!["Version", &info.version]);
+            if let Some(variant) = &info.variant {
+                table.add_row(vec!["Variant", variant]);
+            }
             table.add_row(vec![
                 "Git Hash",
                 &info.git_hash[..8.min(info.git_hash.len())],
@@ -648,6 +674,10 @@ fn create_metadata_json(info: &ReleaseInfo) -> serde_json::Value {
         release_obj["description"] = serde_json::json!(desc);
     }
 
+    if let Some(variant) = &info.variant {
+        release_obj["variant"] = serde_json::json!(variant);
+    }
+
     // Get git info
     let workspace_root = info.workspace_root();
     let branch = git::rev_parse_abbrev_ref_head(workspace_root);
@@ -818,6 +848,21 @@ fn copy_sources_v2(info: &ReleaseInfo, closure: &PackageClosure) -> Result<()> {
     Ok(())
 }
 
+/// Write sbom.cdx.json for the V2 package closure and enforce the workspace license policy
+fn write_sbom(info: &ReleaseInfo, _spinner: &Spinner) -> Result<()> {
+    let Some(closure) = &info.v2_closure else {
+        debug!("No V2 package closure, skipping SBOM");
+        return Ok(());
+    };
+    crate::sbom::write_release_sbom(
+        &info.config,
+        closure,
+        &info.board_display_name(),
+        &info.version,
+        &info.staging_dir,
+    )
+}
+
 /// Generate board config JSON file
 fn generate_board_config(info: &ReleaseInfo, _spinner: &Spinner) -> Result<()> {
     // Extract board config from the schematic
@@ -1035,7 +1080,7 @@ fn validate_build(info: &ReleaseInfo, spinner: &Spinner) -> Result<()> {
         let mut has_warnings = false;
 
         // Export diagnostics to JSON for release artifacts
-        let mut passes = crate::build::create_diagnostics_passes(&[], &[]);
+        let mut passes = crate::build::create_diagnostics_passes(&[], &[], &[]);
         passes.push(Box::new(pcb_zen_core::JsonExportPass::new(
             info.staging_dir.join("diagnostics.json"),
             zen_file_rel.display().to_string(),
@@ -1117,6 +1162,22 @@ fn generate_design_bom(info: &ReleaseInfo, _spinner: &Spinner) -> Result<()> {
     Ok(())
 }
 
+/// Generate hierarchical KiCad schematic sheets alongside the staged layout
+fn generate_kicad_schematic(info: &ReleaseInfo, _spinner: &Spinner) -> Result<()> {
+    let staged_layout_dir = info.staged_layout_dir();
+    fs::create_dir_all(&staged_layout_dir)?;
+    let root_sheet = staged_layout_dir.join("layout.kicad_sch");
+    let sheets =
+        pcb_sch::kicad_schematic::write_kicad_schematic_sheets(&info.schematic, &root_sheet)
+            .with_context(|| format!("Failed to write {}", root_sheet.display()))?;
+    debug!(
+        "Wrote {} schematic sheets to {}",
+        sheets.len(),
+        staged_layout_dir.display()
+    );
+    Ok(())
+}
+
 /// Write release metadata to JSON file
 fn write_metadata(info: &ReleaseInfo, _spinner: &Spinner) -> Result<()> {
     let metadata = create_metadata_json(info);
@@ -1292,6 +1353,11 @@ fn generate_cpl(info: &ReleaseInfo, _spinner: &Spinner) -> Result<()> {
     // Fix CPL CSV header to match expected format
     fix_cpl_header(&manufacturing_dir.join("cpl.csv"))?;
 
+    // The layout is shared between variants, so apply the variant's population to the export
+    if let Some(variant) = &info.variant {
+        apply_variant_to_cpl(&manufacturing_dir.join("cpl.csv"), &info.schematic, variant)?;
+    }
+
     Ok(())
 }
 
@@ -1370,15 +1436,79 @@ fn fix_cpl_header(cpl_path: &Path) -> Result<()> {
     let content = fs::read_to_string(cpl_path)?;
     let lines: Vec<&str> = content.lines().collect();
     if lines.len() > 1 {
-        let fixed_content = format!(
+        let mut fixed_content = format!(
             "Designator,Val,Package,Mid X,Mid Y,Rotation,Layer\n{}",
             lines[1..].join("\n")
         );
+        if content.ends_with('\n') {
+            fixed_content.push('\n');
+        }
         fs::write(cpl_path, fixed_content)?;
     }
     Ok(())
 }
 
+/// Drop the rows of components a variant does not fit and update the values it changes
+fn apply_variant_to_cpl(
+    cpl_path: &Path,
+    schematic: &pcb_sch::Schematic,
+    variant: &str,
+) -> Result<()> {
+    let field = format!("{}{variant}", pcb_sch::variants::VARIANT_FIELD_PREFIX);
+    let changed: HashMap<&str, &pcb_sch::Instance> = schematic
+        .instances
+        .values()
+        .filter(|instance| instance.attributes.contains_key(&field))
+        .filter_map(|instance| Some((instance.reference_designator.as_deref()?, instance)))
+        .collect();
+
+    let content = fs::read_to_string(cpl_path)?;
+    let mut lines = content.lines();
+    let mut output: Vec<String> = lines.next().map(str::to_string).into_iter().collect();
+    for line in lines {
+        let mut fields = split_csv_line(line);
+        let designator = fields
+            .first()
+            .map(|f| f.trim_matches('"'))
+            .unwrap_or_default();
+        let Some(instance) = changed.get(designator) else {
+            output.push(line.to_string());
+            continue;
+        };
+        if instance.dnp() {
+            continue;
+        }
+        if let (Some(val), Some(value)) = (fields.get_mut(1), instance.value()) {
+            *val = format!("\"{}\"", value.replace('"', "\"\""));
+        }
+        output.push(fields.join(","));
+    }
+    let mut output = output.join("\n");
+    if content.ends_with('\n') {
+        output.push('\n');
+    }
+    fs::write(cpl_path, output)?;
+    Ok(())
+}
+
+/// Split a CSV line into its raw fields, keeping quotes and commas inside quoted fields
+fn split_csv_line(line: &str) -> Vec<String> {
+    let mut fields = vec![String::new()];
+    let mut quoted = false;
+    for c in line.chars() {
+        match c {
+            '"' => quoted = !quoted,
+            ',' if !quoted => {
+                fields.push(String::new());
+                continue;
+            }
+            _ => {}
+        }
+        fields.last_mut().unwrap().push(c);
+    }
+    fields
+}
+
 /// Generate ODB++ files
 fn generate_odb(info: &ReleaseInfo, _spinner: &Spinner) -> Result<()> {
     let manufacturing_dir = info.staging_dir.join("manufacturing");
@@ -1630,7 +1760,8 @@ fn run_kicad_drc(info: &ReleaseInfo, spinner: &Spinner) -> Result<()> {
 
     // Check for layout diagnostics (e.g., FPID mismatches) without modifying the board
     let sync_diagnostics =
-        match pcb_layout::process_layout(&info.schematic, &info.zen_path, false, false, true) {
+        match pcb_layout::process_layout(&info.schematic, &info.zen_path, false, false, true, None)
+        {
             Ok(result) => result.sync_diagnostics,
             Err(pcb_layout::LayoutError::NoLayoutPath) => vec![],
             Err(pcb_layout::LayoutError::NoLayoutFile(_)) => vec![],
@@ -1639,7 +1770,7 @@ fn run_kicad_drc(info: &ReleaseInfo, spinner: &Spinner) -> Result<()> {
 
     // Run DRC checks and print results
     let (had_errors, warnings) = spinner.suspend(|| {
-        crate::drc::run_and_print_drc(&kicad_pcb_path, &info.suppress, &sync_diagnostics)
+        crate::drc::run_and_print_drc(&kicad_pcb_path, &info.suppress, &sync_diagnostics, None)
     })?;
 
     // Handle errors - always fail if there are errors
@@ -1670,3 +1801,84 @@ fn run_kicad_drc(info: &ReleaseInfo, spinner: &Spinner) -> Result<()> {
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pcb_sch::{AttributeValue, Instance, InstanceRef, ModuleRef, Schematic};
+
+    /// Schematic with components changed by the `lite` variant: R1 gets a new value, C1 is not fitted
+    fn variant_schematic() -> Schematic {
+        let module = ModuleRef::new("board.zen", "Board");
+        let field = format!("{}lite", pcb_sch::variants::VARIANT_FIELD_PREFIX);
+        let mut schematic = Schematic::new();
+        schematic.add_instance(
+            InstanceRef::new(module.clone(), vec!["R1".into()]),
+            Instance::component(module.clone())
+                .with_reference_designator("R1")
+                .with_attribute("value", "4.7kOhm".to_string())
+                .with_attribute("mpn", "RC0402FR-074K7L".to_string())
+                .with_attribute(field.clone(), "Value 4.7kOhm".to_string()),
+        );
+        schematic.add_instance(
+            InstanceRef::new(module.clone(), vec!["C1".into()]),
+            Instance::component(module.clone())
+                .with_reference_designator("C1")
+                .with_attribute("value", "100nF".to_string())
+                .with_attribute("dnp", AttributeValue::Boolean(true))
+                .with_attribute(field, "DNP".to_string()),
+        );
+        schematic.add_instance(
+            InstanceRef::new(module.clone(), vec!["R2".into()]),
+            Instance::component(module)
+                .with_reference_designator("R2")
+                .with_attribute("value", "10kOhm".to_string()),
+        );
+        schematic
+    }
+
+    #[test]
+    fn test_split_csv_line_keeps_quoted_commas() {
+        assert_eq!(
+            split_csv_line(r#""R1","1k, 1%",0402,1.5,-2"#),
+            vec![r#""R1""#, r#""1k, 1%""#, "0402", "1.5", "-2"]
+        );
+        assert_eq!(split_csv_line("a,,b"), vec!["a", "", "b"]);
+    }
+
+    #[test]
+    fn test_apply_variant_to_cpl() {
+        let dir = tempfile::tempdir().unwrap();
+        let cpl_path = dir.path().join("cpl.csv");
+        fs::write(
+            &cpl_path,
+            "Designator,Val,Package,Mid X,Mid Y,Rotation,Layer\n\
+             \"C1\",\"100nF\",\"C_0402\",1,2,0,top\n\
+             \"R1\",\"10k, 1%\",\"R_0402\",3,4,90,top\n\
+             \"R2\",\"10kOhm\",\"R_0402\",5,6,0,bottom\n",
+        )
+        .unwrap();
+
+        apply_variant_to_cpl(&cpl_path, &variant_schematic(), "lite").unwrap();
+
+        assert_eq!(
+            fs::read_to_string(&cpl_path).unwrap(),
+            "Designator,Val,Package,Mid X,Mid Y,Rotation,Layer\n\
+             \"R1\",\"4.7kOhm\",\"R_0402\",3,4,90,top\n\
+             \"R2\",\"10kOhm\",\"R_0402\",5,6,0,bottom\n"
+        );
+    }
+
+    #[test]
+    fn test_apply_unknown_variant_to_cpl() {
+        let dir = tempfile::tempdir().unwrap();
+        let cpl_path = dir.path().join("cpl.csv");
+        let content = "Designator,Val,Package,Mid X,Mid Y,Rotation,Layer\n\
+                       \"C1\",\"100nF\",\"C_0402\",1,2,0,top";
+        fs::write(&cpl_path, content).unwrap();
+
+        apply_variant_to_cpl(&cpl_path, &variant_schematic(), "full").unwrap();
+
+        assert_eq!(fs::read_to_string(&cpl_path).unwrap(), content);
+    }
+}
@@ -0,0 +1,75 @@
+//! `--format sarif|junit` diagnostic reports for `pcb build`, `pcb test` and `pcb layout --check`
+
+use anyhow::Result;
+use clap::ValueEnum;
+use pcb_zen::report::{self, DiagnosticsCollector, JunitSuite};
+use pcb_zen_core::DiagnosticsPass;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// Machine-readable report of the diagnostics of a run, printed to stdout
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ReportFormat {
+    /// SARIF 2.1.0 log
+    Sarif,
+    /// JUnit XML
+    Junit,
+}
+
+/// Where a command prints its human-readable lines: stdout, or stderr when a report owns stdout
+pub fn status_output(format: Option<ReportFormat>) -> Box<dyn Write> {
+    match format {
+        Some(_) => Box::new(std::io::stderr()),
+        None => Box::new(std::io::stdout()),
+    }
+}
+
+/// Record the diagnostics of `source` after `passes` run, when a report was requested
+pub fn collect(
+    passes: &mut Vec<Box<dyn DiagnosticsPass>>,
+    collector: Option<&DiagnosticsCollector>,
+    source: &Path,
+) {
+    if let Some(collector) = collector {
+        passes.push(Box::new(collector.pass(source)));
+    }
+}
+
+/// `source` relative to `root`, for naming JUnit suites
+pub fn display_path(source: &Path, root: &Path) -> String {
+    source
+        .strip_prefix(root)
+        .unwrap_or(source)
+        .display()
+        .to_string()
+}
+
+/// One JUnit suite per source of `sources`
+fn diagnostics_suites(
+    sources: &[(PathBuf, Vec<pcb_zen::Diagnostic>)],
+    root: &Path,
+) -> Vec<JunitSuite> {
+    sources
+        .iter()
+        .map(|(source, diagnostics)| {
+            report::diagnostics_suite(&display_path(source, root), diagnostics, root)
+        })
+        .collect()
+}
+
+/// Print the collected diagnostics as a `format` report, with paths relative to `root`
+pub fn print_report(
+    format: ReportFormat,
+    collector: &DiagnosticsCollector,
+    root: &Path,
+) -> Result<()> {
+    let sources = collector.sources();
+    match format {
+        ReportFormat::Sarif => {
+            let log = report::sarif(&sources, root, env!("CARGO_PKG_VERSION"));
+            println!("{}", serde_json::to_string_pretty(&log)?);
+        }
+        ReportFormat::Junit => print!("{}", report::junit_xml(&diagnostics_suites(&sources, root))),
+    }
+    Ok(())
+}
@@ -0,0 +1,191 @@
+//! `pcb sbom`: bill of materials of the dependency closure, with a license policy check
+
+use anyhow::{bail, Context, Result};
+use clap::{Args, ValueEnum};
+use colored::Colorize;
+use pcb_zen::sbom::{self, LicenseViolation, Sbom};
+use pcb_zen::workspace::{PackageClosure, WorkspaceInfoExt};
+use pcb_zen::{ResolutionResult, WorkspaceInfo};
+use std::path::{Path, PathBuf};
+
+#[derive(ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SbomFormat {
+    /// CycloneDX 1.5 JSON
+    #[default]
+    Cyclonedx,
+    /// SPDX 2.3 JSON
+    Spdx,
+}
+
+#[derive(Args, Debug)]
+#[command(about = "Generate a software bill of materials for the dependency closure")]
+pub struct SbomArgs {
+    /// Package directory or .zen file to describe (defaults to the whole workspace)
+    pub path: Option<PathBuf>,
+
+    /// Document format
+    #[arg(short = 'f', long, value_enum, default_value_t)]
+    pub format: SbomFormat,
+
+    /// Write the document to a file instead of stdout
+    #[arg(short = 'o', long)]
+    pub output: Option<PathBuf>,
+
+    /// Fail if a license in the closure is rejected by the `[licenses]` policy
+    #[arg(long)]
+    pub check: bool,
+
+    /// Resolve without network access, from pcb.sum and the cache
+    #[arg(long)]
+    pub offline: bool,
+}
+
+pub fn execute(args: SbomArgs) -> Result<()> {
+    let start_dir = args.path.as_deref().map(|p| {
+        if p.is_file() {
+            p.parent().unwrap_or(p)
+        } else {
+            p
+        }
+    });
+    let (workspace, resolution) =
+        crate::resolve::resolve_v2_if_needed(start_dir, args.offline, false)?;
+    let Some(resolution) = resolution else {
+        bail!("SBOMs are only available for V2 workspaces");
+    };
+
+    let target = match &args.path {
+        Some(path) => path.canonicalize()?,
+        None => workspace.root.clone(),
+    };
+    let sbom = if target == workspace.root {
+        let name = workspace_name(&workspace);
+        let closure = workspace_closure(&workspace, &resolution);
+        Sbom::from_closure(
+            &name,
+            None,
+            &workspace,
+            &closure,
+            workspace.license_policy(),
+        )
+    } else {
+        let url = workspace
+            .package_url_for_zen(&target)
+            .with_context(|| format!("{} is not in a workspace package", target.display()))?;
+        let version = workspace.packages[&url].version.clone();
+        let closure = workspace.package_closure(&url, &resolution);
+        Sbom::from_closure(
+            &url,
+            version.as_deref(),
+            &workspace,
+            &closure,
+            workspace.license_policy(),
+        )
+    };
+
+    let document = render(&sbom, args.format)?;
+    match &args.output {
+        Some(path) => {
+            std::fs::write(path, document)
+                .with_context(|| format!("Failed to write {}", path.display()))?;
+            eprintln!(
+                "{} Wrote {} components to {}",
+                "✓".green(),
+                sbom.components.len(),
+                path.display()
+            );
+        }
+        None => println!("{document}"),
+    }
+
+    if args.check {
+        check(&workspace, &sbom)?;
+    }
+    Ok(())
+}
+
+/// `sbom` as a `format` JSON document
+pub fn render(sbom: &Sbom, format: SbomFormat) -> Result<String> {
+    let serial = uuid::Uuid::new_v4().to_string();
+    let timestamp = chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, true);
+    let tool_version = env!("CARGO_PKG_VERSION");
+    let document = match format {
+        SbomFormat::Cyclonedx => sbom::cyclonedx(sbom, tool_version, &serial, &timestamp),
+        SbomFormat::Spdx => sbom::spdx(sbom, tool_version, &serial, &timestamp),
+    };
+    Ok(serde_json::to_string_pretty(&document)?)
+}
+
+/// Fail if the workspace's license policy rejects a component of `sbom`
+pub fn check(workspace: &WorkspaceInfo, sbom: &Sbom) -> Result<()> {
+    let Some(policy) = workspace.license_policy() else {
+        bail!(
+            "No [licenses] policy in {}",
+            workspace.root.join("pcb.toml").display()
+        );
+    };
+    let violations = sbom::check_licenses(sbom, policy);
+    if violations.is_empty() {
+        return Ok(());
+    }
+    print_violations(&violations);
+    bail!("{} package(s) violate the license policy", violations.len())
+}
+
+fn print_violations(violations: &[LicenseViolation]) {
+    for violation in violations {
+        eprintln!(
+            "{} {}: {} ({})",
+            "✗".red(),
+            violation.component.bold(),
+            violation.license.as_deref().unwrap_or("no license"),
+            violation.reason
+        );
+    }
+}
+
+fn workspace_name(workspace: &WorkspaceInfo) -> String {
+    workspace
+        .repository()
+        .map(str::to_string)
+        .or_else(|| {
+            workspace
+                .root
+                .file_name()
+                .map(|n| n.to_string_lossy().into_owned())
+        })
+        .unwrap_or_else(|| "workspace".to_string())
+}
+
+/// Union of the closures of all workspace members
+fn workspace_closure(workspace: &WorkspaceInfo, resolution: &ResolutionResult) -> PackageClosure {
+    let mut closure = PackageClosure::default();
+    for url in workspace.packages.keys() {
+        let member = workspace.package_closure(url, resolution);
+        closure.local_packages.extend(member.local_packages);
+        closure.remote_packages.extend(member.remote_packages);
+        closure.assets.extend(member.assets);
+    }
+    closure
+}
+
+/// SBOM of a release's package closure, written to `staging_dir` and checked against the
+/// workspace's license policy, if it has one
+pub fn write_release_sbom(
+    workspace: &WorkspaceInfo,
+    closure: &PackageClosure,
+    name: &str,
+    version: &str,
+    staging_dir: &Path,
+) -> Result<()> {
+    let policy = workspace.license_policy();
+    let sbom = Sbom::from_closure(name, Some(version), workspace, closure, policy);
+    std::fs::write(
+        staging_dir.join("sbom.cdx.json"),
+        render(&sbom, SbomFormat::Cyclonedx)?,
+    )?;
+    if policy.is_some() {
+        check(workspace, &sbom)?;
+    }
+    Ok(())
+}
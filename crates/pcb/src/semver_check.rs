@@ -0,0 +1,96 @@
+//! API compatibility check for `pcb publish --check-semver`
+//!
+//! Evaluates every module of a package at its latest tag and in the working tree, and
+//! diffs their public APIs to tell which version bump the changes need.
+
+use anyhow::{bail, Context, Result};
+use path_slash::PathExt;
+use pcb_zen::public_api::{self, ApiChange, ModuleApi};
+use pcb_zen::workspace::{get_workspace_info, WorkspaceInfo};
+use pcb_zen::{git, EvalConfig};
+use pcb_zen_core::DefaultFileProvider;
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use crate::file_walker::collect_zen_files;
+
+/// API changes of the package `url` since the tag `tag`
+pub fn check_package(workspace: &WorkspaceInfo, url: &str, tag: &str) -> Result<Vec<ApiChange>> {
+    let new = package_apis(workspace, url)?;
+    let old = tagged_package_apis(workspace, url, tag)
+        .with_context(|| format!("Failed to evaluate {tag}"))?;
+    Ok(public_api::diff(&old, &new))
+}
+
+/// APIs of the package `url` at `tag`, evaluated in a temporary worktree
+fn tagged_package_apis(
+    workspace: &WorkspaceInfo,
+    url: &str,
+    tag: &str,
+) -> Result<BTreeMap<String, ModuleApi>> {
+    let repo_root = git::get_repo_root(&workspace.root)?;
+    let subpath = workspace
+        .root
+        .strip_prefix(&repo_root)
+        .unwrap_or(Path::new(""));
+
+    let tmp = tempfile::tempdir()?;
+    let worktree = tmp.path().join("tree");
+    git::create_worktree(&repo_root, &worktree, tag)?;
+
+    let result = get_workspace_info(&DefaultFileProvider::new(), &worktree.join(subpath)).and_then(
+        |old_workspace| {
+            if !old_workspace.packages.contains_key(url) {
+                bail!("Package {url} not found");
+            }
+            package_apis(&old_workspace, url)
+        },
+    );
+
+    let _ = git::remove_worktree(&repo_root, &worktree);
+    let _ = git::prune_worktrees(&repo_root);
+    result
+}
+
+/// APIs of the modules of the package `url`, keyed by path relative to the package.
+/// Files of packages nested inside it are left out.
+fn package_apis(workspace: &WorkspaceInfo, url: &str) -> Result<BTreeMap<String, ModuleApi>> {
+    let pkg_dir = workspace.packages[url].dir(&workspace.root);
+    let nested: Vec<_> = workspace
+        .packages
+        .values()
+        .map(|p| p.dir(&workspace.root))
+        .filter(|dir| *dir != pkg_dir && dir.starts_with(&pkg_dir))
+        .collect();
+
+    let resolution = if workspace.is_v2() {
+        let mut ws = workspace.clone();
+        Some(pcb_zen::resolve_dependencies(&mut ws, false, false)?)
+    } else {
+        None
+    };
+
+    let mut apis = BTreeMap::new();
+    for zen_path in collect_zen_files(std::slice::from_ref(&pkg_dir), false)? {
+        if nested.iter().any(|dir| zen_path.starts_with(dir)) {
+            continue;
+        }
+        let rel_path = zen_path
+            .strip_prefix(&pkg_dir)
+            .unwrap_or(&zen_path)
+            .to_slash_lossy()
+            .into_owned();
+
+        let eval_result = pcb_zen::eval(
+            &zen_path,
+            EvalConfig::with_resolution(resolution.clone(), false),
+        );
+        match eval_result.output {
+            Some(output) if !eval_result.diagnostics.has_errors() => {
+                apis.insert(rel_path, ModuleApi::from_output(&output));
+            }
+            _ => bail!("Failed to evaluate {rel_path}"),
+        }
+    }
+    Ok(apis)
+}
@@ -1,9 +1,17 @@
 use anyhow::Result;
 use clap::Args;
-use pcb_sim::gen_sim;
+use itertools::Itertools;
+use pcb_sch::physical::{PhysicalRange, PhysicalUnitDims};
+use pcb_sch::tolerance::Variation;
+use pcb_sch::{PhysicalUnit, Schematic};
+use pcb_sim::raw::Analysis;
+use pcb_sim::{gen_sim, gen_sim_with_values, ngspice, variation};
+use pcb_ui::prelude::*;
+use rust_decimal::prelude::ToPrimitive;
 use std::fs::File;
 use std::io::{Read, Write};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
 
 use crate::build::{build as build_zen, create_diagnostics_passes};
 
@@ -31,6 +39,35 @@ pub struct SimArgs {
         default_value = "sim.cir",
     )]
     pub output: PathBuf,
+
+    // Run the generated netlist through ngspice and print a summary of the results
+    #[arg(long = "run")]
+    pub run: bool,
+
+    // Simulate every combination of toleranced passives at their minimum and maximum
+    #[arg(long = "corners", conflicts_with = "monte_carlo")]
+    pub corners: bool,
+
+    // Simulate N random draws of the toleranced passives
+    #[arg(long = "monte-carlo", value_name = "N")]
+    pub monte_carlo: Option<usize>,
+
+    // Seed for --monte-carlo draws
+    #[arg(long = "seed", value_name = "SEED", default_value_t = 0)]
+    pub seed: u64,
+
+    // Pass criterion for corner and Monte Carlo yield, e.g. "VOUT=3.2V to 3.4V"
+    #[arg(long = "spec", value_name = "NODE=RANGE")]
+    pub spec: Vec<String>,
+
+    // ngspice executable used with --run, --corners and --monte-carlo
+    #[arg(
+        long = "ngspice",
+        value_name = "PATH",
+        value_hint = clap::ValueHint::ExecutablePath,
+        default_value = ngspice::DEFAULT_NGSPICE,
+    )]
+    pub ngspice: PathBuf,
 }
 
 fn get_output_writer(path: &str) -> Result<Box<dyn Write>> {
@@ -49,7 +86,7 @@ pub fn execute(args: SimArgs) -> Result<()> {
     // Reuse the shared build flow from build.rs
     let mut has_errors = false;
     let mut has_warnings = false;
-    let passes = create_diagnostics_passes(&[], &[]);
+    let passes = create_diagnostics_passes(&[], &[], &[]);
     let Some(schematic) = build_zen(
         &zen_path,
         false,
@@ -66,13 +103,210 @@ pub fn execute(args: SimArgs) -> Result<()> {
         }
     };
 
-    gen_sim(&schematic, &mut out)?;
+    let mut body = Vec::new();
+    let mut sim_diagnostics = gen_sim(&schematic, &mut body)?;
+    sim_diagnostics.apply_passes(&create_diagnostics_passes(&[], &[], &[]));
+    out.write_all(&body)?;
 
+    let mut setup = String::new();
     if let Some(setup_path) = args.setup {
-        let mut setup = String::new();
         File::open(setup_path)?.read_to_string(&mut setup).unwrap();
         writeln!(out, "{setup}").unwrap();
     }
 
+    let variation = match (args.corners, args.monte_carlo) {
+        (true, _) => Some(Variation::Corners),
+        (false, Some(runs)) => Some(Variation::MonteCarlo {
+            runs,
+            seed: args.seed,
+        }),
+        (false, None) => None,
+    };
+    if let Some(variation) = variation {
+        let specs = args
+            .spec
+            .iter()
+            .map(|spec| parse_spec(spec))
+            .collect::<Result<Vec<_>>>()?;
+        return run_variations(&schematic, &setup, &args.ngspice, variation, &specs);
+    }
+
+    if args.run {
+        let netlist = ngspice::with_setup(&String::from_utf8_lossy(&body), &setup);
+        let plots = ngspice::run(&netlist, &args.ngspice)?;
+        print_summary(&plots);
+    }
+
     Ok(())
 }
+
+/// Print the final, minimum and maximum value of every simulated vector
+fn print_summary(plots: &[pcb_sim::raw::Plot]) {
+    for plot in plots {
+        eprintln!("{}", plot.name.as_str().bold());
+        for vector in plot.vectors.iter().skip(1) {
+            let Some(waveform) = plot.waveform(vector) else {
+                continue;
+            };
+            let (Some(last), Some(min), Some(max)) =
+                (waveform.final_value(), waveform.min(), waveform.max())
+            else {
+                continue;
+            };
+            eprintln!(
+                "  {:<24} final {last:>12.6e}  min {min:>12.6e}  max {max:>12.6e}",
+                waveform.name
+            );
+        }
+    }
+}
+
+/// Parse a `NODE=RANGE` pass criterion
+fn parse_spec(spec: &str) -> Result<(String, PhysicalRange)> {
+    let Some((node, range)) = spec.split_once('=') else {
+        anyhow::bail!("Invalid --spec '{spec}': expected NODE=RANGE, e.g. \"VOUT=3.2V to 3.4V\"");
+    };
+    let range = PhysicalRange::from_str(range.trim())
+        .map_err(|e| anyhow::anyhow!("Invalid range in --spec '{spec}': {e}"))?;
+    // Specs are checked against node voltages
+    let volts: PhysicalUnitDims = PhysicalUnit::Volts.into();
+    if range.unit() != volts {
+        anyhow::bail!(
+            "Invalid range in --spec '{spec}': expected a voltage, got {}",
+            range.unit().quantity()
+        );
+    }
+    Ok((node.trim().to_string(), range))
+}
+
+/// Simulate every corner or Monte Carlo run and report each spec's yield and the worst-case
+/// final node voltages
+fn run_variations(
+    schematic: &Schematic,
+    setup: &str,
+    ngspice_path: &Path,
+    variation: Variation,
+    specs: &[(String, PhysicalRange)],
+) -> Result<()> {
+    let passives = variation::toleranced_passives(schematic);
+    if passives.is_empty() {
+        anyhow::bail!("No toleranced resistors, capacitors or inductors to vary");
+    }
+    let runs = variation::runs(&passives, variation)?;
+    if runs.is_empty() {
+        anyhow::bail!("--monte-carlo needs at least one run");
+    }
+
+    let progress = ProgressBar::builder(runs.len() as u64)
+        .message("Simulating")
+        .start();
+    let mut results = Vec::with_capacity(runs.len());
+    for values in &runs {
+        let mut body = Vec::new();
+        gen_sim_with_values(schematic, &mut body, values)?;
+        let netlist = ngspice::with_setup(&String::from_utf8_lossy(&body), setup);
+        match ngspice::run(&netlist, ngspice_path) {
+            Ok(plots) => results.push(plots),
+            Err(e) => {
+                progress.error("Simulation failed");
+                return Err(e);
+            }
+        }
+        progress.inc(1);
+    }
+    progress.finish();
+
+    let names = passives
+        .iter()
+        .map(|(refdes, _)| refdes.as_str())
+        .join(", ");
+    eprintln!("{} runs varying {names}", runs.len().to_string().bold());
+
+    // Without specs, report every node of the first run
+    let nodes: Vec<(String, Option<&PhysicalRange>)> = if specs.is_empty() {
+        results[0]
+            .iter()
+            .find(|p| p.analysis == Analysis::Transient)
+            .or(results[0].first())
+            .map(|plot| {
+                plot.vectors
+                    .iter()
+                    .skip(1)
+                    .filter(|v| v.kind == "voltage")
+                    .map(|v| (v.name.clone(), None))
+                    .collect()
+            })
+            .unwrap_or_default()
+    } else {
+        specs
+            .iter()
+            .map(|(node, range)| (node.clone(), Some(range)))
+            .collect()
+    };
+
+    let mut failed = false;
+    for (node, range) in nodes {
+        let finals: Vec<f64> = results
+            .iter()
+            .filter_map(|plots| variation::node_voltage(plots, &node)?.final_value())
+            .collect();
+        if finals.len() != results.len() {
+            anyhow::bail!("Node '{node}' is missing from the simulation results");
+        }
+        let by_value = |a: &usize, b: &usize| finals[*a].total_cmp(&finals[*b]);
+        let worst_low = (0..finals.len()).min_by(by_value).unwrap_or_default();
+        let worst_high = (0..finals.len()).max_by(by_value).unwrap_or_default();
+        let mut line = format!(
+            "  {node:<24} final {:.6}–{:.6} V",
+            finals[worst_low], finals[worst_high]
+        );
+        if let Some(range) = range {
+            let (min, max) = range.bounds();
+            let (min, max) = (
+                min.to_f64().unwrap_or(f64::MIN),
+                max.to_f64().unwrap_or(f64::MAX),
+            );
+            let passed = finals.iter().filter(|v| (min..=max).contains(*v)).count();
+            let yield_pct = 100.0 * passed as f64 / finals.len() as f64;
+            failed |= passed < finals.len();
+            let status = if passed == finals.len() {
+                format!("yield {yield_pct:.1}%").green()
+            } else {
+                format!("yield {yield_pct:.1}%").red()
+            };
+            line = format!("{line}  spec {range}  {status} ({passed}/{})", finals.len());
+        }
+        eprintln!("{line}");
+        for (label, run) in [("min", worst_low), ("max", worst_high)] {
+            let values = runs[run]
+                .iter()
+                .sorted_by(|a, b| a.0.cmp(b.0))
+                .map(|(refdes, value)| format!("{refdes}={}", value.normalize()))
+                .join(" ");
+            eprintln!("    {label} at run {}: {values}", run + 1);
+        }
+    }
+
+    if failed {
+        anyhow::bail!("Simulated yield is below 100%");
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn spec_ranges_must_be_voltages() {
+        let (node, range) = parse_spec("VOUT=3.2V to 3.4V").unwrap();
+        assert_eq!(node, "VOUT");
+        assert_eq!(range.unit(), PhysicalUnitDims::from(PhysicalUnit::Volts));
+
+        let err = parse_spec("VOUT=3.2A to 3.4A").unwrap_err();
+        assert!(
+            err.to_string().contains("expected a voltage, got Current"),
+            "{err}"
+        );
+    }
+}
@@ -3,12 +3,15 @@ use clap::{Args, ValueEnum};
 use comfy_table::{presets::UTF8_FULL_CONDENSED, Cell, Color, Table};
 use log::debug;
 use pcb_ui::prelude::*;
+use pcb_zen::report::DiagnosticsCollector;
+use pcb_zen_core::lang::error::BenchTestResult;
 use pcb_zen_core::ModulePath;
 use serde::Serialize;
 use std::path::{Path, PathBuf};
 
 use crate::build::create_diagnostics_passes;
 use crate::file_walker;
+use crate::report::{self, ReportFormat};
 
 #[derive(Args, Debug, Default, Clone)]
 #[command(about = "Run tests in .zen files")]
@@ -41,6 +44,10 @@ pub struct TestArgs {
     /// Output format for test results
     #[arg(short = 'f', long = "format", value_enum, default_value_t = OutputFormat::Table)]
     pub format: OutputFormat,
+
+    /// ngspice executable used for TestBenches that set `simulation`
+    #[arg(long = "ngspice", value_name = "PATH", value_hint = clap::ValueHint::ExecutablePath)]
+    pub ngspice: Option<PathBuf>,
 }
 
 #[derive(ValueEnum, Clone, Debug, Default)]
@@ -49,6 +56,10 @@ pub enum OutputFormat {
     Json,
     #[default]
     Table,
+    /// SARIF 2.1.0 log of the failed checks and other diagnostics
+    Sarif,
+    /// JUnit XML with a test suite per TestBench
+    Junit,
 }
 
 #[derive(Serialize, Clone)]
@@ -80,6 +91,7 @@ pub fn test(
     offline: bool,
     passes: Vec<Box<dyn pcb_zen_core::DiagnosticsPass>>,
     resolution_result: Option<pcb_zen::ResolutionResult>,
+    ngspice: &Path,
 ) -> (Vec<pcb_zen_core::lang::error::BenchTestResult>, bool) {
     let file_name = zen_path.file_name().unwrap().to_string_lossy();
 
@@ -105,9 +117,21 @@ pub fn test(
                 testbenches.len()
             );
 
+            // Simulated TestBenches need the netlist, so convert the design once up front
+            let schematic = if testbenches.iter().any(|tb| tb.simulation().is_some()) {
+                let result = eval_output.to_schematic_with_diagnostics();
+                diagnostics
+                    .diagnostics
+                    .extend(result.diagnostics.diagnostics);
+                result.output
+            } else {
+                None
+            };
+
             // Execute checks for each TestBench
             for testbench in testbenches {
-                let check_diagnostics = execute_testbench_checks(testbench, &eval_output);
+                let check_diagnostics =
+                    execute_testbench_checks(testbench, &eval_output, schematic.as_ref(), ngspice);
                 diagnostics.diagnostics.extend(check_diagnostics);
             }
         }
@@ -133,13 +157,35 @@ pub fn test(
     (test_results, had_errors)
 }
 
+/// Simulate one TestBench case with ngspice, adding netlist warnings to `diagnostics`
+fn simulate_case(
+    schematic: Option<&pcb_sch::Schematic>,
+    case_final_name: &str,
+    setup: &str,
+    ngspice: &Path,
+    diagnostics: &mut Vec<pcb_zen_core::Diagnostic>,
+) -> Result<Vec<pcb_sim::raw::Plot>> {
+    let Some(schematic) = schematic else {
+        anyhow::bail!("the design could not be converted to a netlist");
+    };
+    let mut body = Vec::new();
+    let netlist_diagnostics = pcb_sim::gen_sim_instance(schematic, case_final_name, &mut body)?;
+    diagnostics.extend(netlist_diagnostics.diagnostics);
+    let netlist = pcb_sim::ngspice::with_setup(&String::from_utf8_lossy(&body), setup);
+    debug!("Simulating {case_final_name}:\n{netlist}");
+    pcb_sim::ngspice::run(&netlist, ngspice)
+}
+
 /// Execute all deferred checks for a TestBench
 fn execute_testbench_checks(
     testbench: &pcb_zen_core::lang::test_bench::FrozenTestBenchValue,
     eval_output: &pcb_zen_core::lang::eval::EvalOutput,
+    schematic: Option<&pcb_sch::Schematic>,
+    ngspice: &Path,
 ) -> Vec<pcb_zen_core::Diagnostic> {
+    use pcb_sim::bench::SimulationValue;
     use pcb_zen_core::lang::eval::EvalContext;
-    use pcb_zen_core::lang::test_bench::execute_deferred_check;
+    use pcb_zen_core::lang::test_bench::{execute_deferred_check, failed_check_diagnostic};
     use starlark::environment::Module;
     use starlark::eval::Evaluator;
     use starlark::values::{dict::AllocDict, Heap, ValueLike};
@@ -187,13 +233,49 @@ fn execute_testbench_checks(
                 call_span: testbench.call_span(),
             };
 
+            // Simulate the case once and share the results between its checks
+            let sim = match testbench.simulation() {
+                None => None,
+                Some(setup) => {
+                    match simulate_case(
+                        schematic,
+                        &deferred_case.case_final_name,
+                        setup,
+                        ngspice,
+                        &mut all_diagnostics,
+                    ) {
+                        Ok(plots) => Some(heap.alloc(SimulationValue::new(plots))),
+                        Err(err) => {
+                            let error = pcb_zen_core::Diagnostic::from(
+                                err.context("TestBench simulation failed"),
+                            );
+                            for check in &deferred_case.checks {
+                                total_checks += 1;
+                                all_diagnostics.push(failed_check_diagnostic(
+                                    check,
+                                    error.clone(),
+                                    &ctx,
+                                ));
+                            }
+                            continue;
+                        }
+                    }
+                }
+            };
+
             for check in &deferred_case.checks {
                 total_checks += 1;
                 // module_value is FrozenModuleValue (ModuleValueGen<FrozenValue>)
                 // Allocate it to heap to get a Value
                 let module_as_value = heap.alloc_complex(module_value.clone());
-                let (passed, mut diagnostics) =
-                    execute_deferred_check(&mut eval, check, module_as_value, inputs_dict, &ctx);
+                let (passed, mut diagnostics) = execute_deferred_check(
+                    &mut eval,
+                    check,
+                    module_as_value,
+                    inputs_dict,
+                    sim,
+                    &ctx,
+                );
 
                 if passed {
                     passed_checks += 1;
@@ -239,14 +321,24 @@ pub fn execute(args: TestArgs) -> Result<()> {
 
     let mut all_test_results: Vec<pcb_zen_core::lang::error::BenchTestResult> = Vec::new();
     let mut has_errors = false;
+    let ngspice = args
+        .ngspice
+        .as_deref()
+        .unwrap_or(Path::new(pcb_sim::ngspice::DEFAULT_NGSPICE));
+
+    let collector = matches!(args.format, OutputFormat::Sarif | OutputFormat::Junit)
+        .then(DiagnosticsCollector::new);
 
     // Process each .zen file
     for zen_path in zen_paths {
+        let mut passes = create_diagnostics_passes(&args.suppress, &[], &args.deny);
+        report::collect(&mut passes, collector.as_ref(), &zen_path);
         let (results, had_errors_file) = test(
             &zen_path,
             args.offline,
-            create_diagnostics_passes(&args.suppress, &[]),
+            passes,
             resolution_result.clone(),
+            ngspice,
         );
         all_test_results.extend(results);
         if had_errors_file {
@@ -271,6 +363,12 @@ pub fn execute(args: TestArgs) -> Result<()> {
         OutputFormat::Tap => output_tap(&all_results),
         OutputFormat::Json => output_json(&all_results)?,
         OutputFormat::Table => output_table(&all_results),
+        OutputFormat::Sarif => report::print_report(
+            ReportFormat::Sarif,
+            collector.as_ref().unwrap(),
+            &workspace_info.root,
+        )?,
+        OutputFormat::Junit => output_junit(collector.as_ref().unwrap(), &workspace_info.root),
     }
 
     // Exit with error if there were failures
@@ -392,3 +490,30 @@ fn output_json(results: &[TestResult]) -> Result<()> {
     println!("{}", serde_json::to_string_pretty(&output)?);
     Ok(())
 }
+
+fn output_junit(collector: &DiagnosticsCollector, root: &Path) {
+    let sources = collector.sources();
+    let all: Vec<_> = sources
+        .iter()
+        .flat_map(|(_, d)| d.iter().cloned())
+        .collect();
+    let mut suites = pcb_zen::report::test_bench_suites(&all, root);
+
+    // Errors outside of checks, such as a file that fails to evaluate
+    for (source, diagnostics) in &sources {
+        let errors: Vec<_> = diagnostics
+            .iter()
+            .filter(|d| d.is_error() && !d.is_error_type::<BenchTestResult>())
+            .cloned()
+            .collect();
+        if !errors.is_empty() {
+            suites.push(pcb_zen::report::diagnostics_suite(
+                &report::display_path(source, root),
+                &errors,
+                root,
+            ));
+        }
+    }
+
+    print!("{}", pcb_zen::report::junit_xml(&suites));
+}
@@ -8,6 +8,7 @@ use clap::Args;
 use colored::Colorize;
 use inquire::MultiSelect;
 use pcb_zen::cache_index::CacheIndex;
+use pcb_zen::package_index::PackageIndex;
 use pcb_zen::workspace::get_workspace_info;
 use pcb_zen::{git, tags, WorkspaceInfo};
 use pcb_zen_core::config::{DependencySpec, PcbToml};
@@ -208,6 +209,7 @@ fn find_version_updates(
     filter: &[String],
 ) -> Result<Vec<PendingUpdate>> {
     let workspace_members: HashSet<&String> = workspace.packages.keys().collect();
+    let package_index = PackageIndex::for_workspace(workspace, false)?;
     let mut version_cache: BTreeMap<String, BTreeMap<String, Vec<Version>>> = BTreeMap::new();
     let mut pending = Vec::new();
 
@@ -238,7 +240,10 @@ fn find_version_updates(
             let (repo_url, subpath) = git::split_repo_and_subpath(url);
             let repo_versions = version_cache
                 .entry(repo_url.to_string())
-                .or_insert_with(|| tags::get_all_versions_for_repo(repo_url).unwrap_or_default());
+                .or_insert_with(|| {
+                    tags::get_versions_for_repo(repo_url, package_index.as_ref())
+                        .unwrap_or_default()
+                });
 
             let pkg_key = if subpath.is_empty() { "" } else { subpath };
             let Some(available) = repo_versions.get(pkg_key) else {
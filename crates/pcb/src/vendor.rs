@@ -241,7 +241,7 @@ fn gather_vendor_info(
     let passes = if ignore_errors {
         None
     } else {
-        Some(create_diagnostics_passes(&[], &[]))
+        Some(create_diagnostics_passes(&[], &[], &[]))
     };
     for zen_file in &zen_files {
         // Don't use the vendor path for eval, we're just gathering dependencies